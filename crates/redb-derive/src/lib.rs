@@ -68,6 +68,7 @@ fn generate_value_impl(input: &DeriveInput) -> syn::Result<proc_macro2::TokenStr
     let self_type = generate_self_type(name, generics)?;
 
     let type_name_impl = generate_type_name(name, &data_struct.fields);
+    let layout_hash_impl = generate_layout_hash(&data_struct.fields);
     let as_bytes_impl = generate_as_bytes(&data_struct.fields);
     let from_bytes_impl = generate_from_bytes(name, &data_struct.fields);
     let fixed_width_impl = generate_fixed_width(&data_struct.fields);
@@ -100,7 +101,9 @@ fn generate_value_impl(input: &DeriveInput) -> syn::Result<proc_macro2::TokenStr
             }
 
             fn type_name() -> redb::TypeName {
-                #type_name_impl
+                let name: redb::TypeName = #type_name_impl;
+                let layout_hash: u64 = #layout_hash_impl;
+                redb::TypeName::new(&format!("{} [layout:{:016x}]", name.name(), layout_hash))
             }
         }
     })
@@ -201,6 +204,53 @@ fn generate_type_name(struct_name: &Ident, fields: &Fields) -> proc_macro2::Toke
     }
 }
 
+// Version of the encoding produced by generate_as_bytes() and generate_from_bytes(). Must be
+// bumped whenever the serialized layout of derived types changes
+const LAYOUT_VERSION: u32 = 1;
+
+// Computes a stable FNV-1a hash over the encoding version and, for every field, its position,
+// name, type name, and fixed width. This catches layout changes that leave the textual type
+// name unchanged
+fn generate_layout_hash(fields: &Fields) -> proc_macro2::TokenStream {
+    let field_types = get_field_types(fields);
+    let (kind, field_labels): (&str, Vec<String>) = match fields {
+        Fields::Named(fields_named) => (
+            "named",
+            fields_named
+                .named
+                .iter()
+                .map(|field| field.ident.as_ref().unwrap().to_string())
+                .collect(),
+        ),
+        Fields::Unnamed(_) => (
+            "unnamed",
+            (0..field_types.len()).map(|i| i.to_string()).collect(),
+        ),
+        Fields::Unit => ("unit", vec![]),
+    };
+    let header = format!("redb-derive layout v{LAYOUT_VERSION} {kind}");
+
+    quote! {
+        {
+            let mut layout = String::from(#header);
+            #(
+                layout.push_str(&format!(
+                    ";{}:{}:{:?}",
+                    #field_labels,
+                    <#field_types>::type_name().name(),
+                    <#field_types>::fixed_width()
+                ));
+            )*
+            let mut hash = 0xcbf2_9ce4_8422_2325u64;
+            for byte in layout.as_bytes() {
+                hash ^= u64::from(*byte);
+                hash = hash.wrapping_mul(0x0100_0000_01b3);
+            }
+            hash
+        }
+    }
+}
+
 fn get_field_types(fields: &Fields) -> Vec<syn::Type> {
     match fields {
         Fields::Named(fields_named) => fields_named
@@ -1,4 +1,4 @@
-use redb::{Database, Key, ReadableDatabase, TableDefinition, Value};
+use redb::{Database, Key, ReadableDatabase, TableDefinition, TableError, TypeName, Value};
 use redb_derive::{Key, Value};
 use std::fmt::Debug;
 use tempfile::NamedTempFile;
@@ -71,7 +71,9 @@ fn test_value_helper<V: Value + 'static>(
     for<'x> <V as Value>::SelfType<'x>: PartialEq,
 {
     let type_name = V::type_name();
-    assert_eq!(type_name.name(), expected_type_name);
+    let (name, layout) = type_name.name().rsplit_once(' ').unwrap();
+    assert_eq!(name, expected_type_name);
+    assert!(layout.starts_with("[layout:") && layout.ends_with(']'));
 
     let file = create_tempfile();
     let db = Database::create(file.path()).unwrap();
@@ -225,3 +227,116 @@ fn test_complex_struct() {
     test_key_helper::<ComplexStruct>(&original);
     test_value_helper::<ComplexStruct>(original, expected_name);
 }
+
+// Two types with identical names but different encodings
+mod fixed_inner {
+    #[derive(Debug)]
+    pub struct Inner(pub u32);
+
+    impl redb::Value for Inner {
+        type SelfType<'a> = Inner;
+        type AsBytes<'a> = [u8; 4];
+
+        fn fixed_width() -> Option<usize> {
+            Some(4)
+        }
+
+        fn from_bytes<'a>(data: &'a [u8]) -> Inner
+        where
+            Self: 'a,
+        {
+            Inner(u32::from_le_bytes(data.try_into().unwrap()))
+        }
+
+        fn as_bytes<'a, 'b: 'a>(value: &'a Inner) -> [u8; 4]
+        where
+            Self: 'b,
+        {
+            value.0.to_le_bytes()
+        }
+
+        fn type_name() -> redb::TypeName {
+            redb::TypeName::new("Inner")
+        }
+    }
+
+    #[derive(redb_derive::Value, Debug)]
+    pub struct Outer {
+        pub inner: Inner,
+    }
+}
+
+mod variable_inner {
+    #[derive(Debug)]
+    pub struct Inner(pub u32);
+
+    impl redb::Value for Inner {
+        type SelfType<'a> = Inner;
+        type AsBytes<'a> = [u8; 4];
+
+        fn fixed_width() -> Option<usize> {
+            None
+        }
+
+        fn from_bytes<'a>(data: &'a [u8]) -> Inner
+        where
+            Self: 'a,
+        {
+            Inner(u32::from_le_bytes(data.try_into().unwrap()))
+        }
+
+        fn as_bytes<'a, 'b: 'a>(value: &'a Inner) -> [u8; 4]
+        where
+            Self: 'b,
+        {
+            value.0.to_le_bytes()
+        }
+
+        fn type_name() -> redb::TypeName {
+            redb::TypeName::new("Inner")
+        }
+    }
+
+    #[derive(redb_derive::Value, Debug)]
+    pub struct Outer {
+        pub inner: Inner,
+    }
+}
+
+#[test]
+fn test_layout_hash() {
+    let fixed: TypeName = fixed_inner::Outer::type_name();
+    let variable: TypeName = variable_inner::Outer::type_name();
+    assert_ne!(fixed, variable);
+    let (fixed_name, _) = fixed.name().rsplit_once(' ').unwrap();
+    let (variable_name, _) = variable.name().rsplit_once(' ').unwrap();
+    assert_eq!(fixed_name, variable_name);
+
+    // The hash must be stable across calls
+    assert_eq!(fixed, fixed_inner::Outer::type_name());
+
+    let file = create_tempfile();
+    let db = Database::create(file.path()).unwrap();
+    let fixed_def: TableDefinition<u32, fixed_inner::Outer> = TableDefinition::new("test");
+    let variable_def: TableDefinition<u32, variable_inner::Outer> = TableDefinition::new("test");
+
+    let write_txn = db.begin_write().unwrap();
+    {
+        let mut table = write_txn.open_table(fixed_def).unwrap();
+        table
+            .insert(
+                1,
+                fixed_inner::Outer {
+                    inner: fixed_inner::Inner(5),
+                },
+            )
+            .unwrap();
+    }
+    write_txn.commit().unwrap();
+
+    let read_txn = db.begin_read().unwrap();
+    assert!(matches!(
+        read_txn.open_table(variable_def),
+        Err(TableError::TableTypeMismatch { .. })
+    ));
+}
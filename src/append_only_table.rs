@@ -0,0 +1,111 @@
+use crate::{
+    AppendOnlyError, Key, ReadableTable, Table, TableDefinition, TableError, TableHandle, Value,
+    WriteTransaction,
+};
+use std::borrow::Borrow;
+use std::cmp::Ordering;
+use std::fmt::{Debug, Formatter};
+
+/// A table to which entries can be added, but in which existing entries cannot be modified or
+/// removed
+///
+/// This is intended for audit logs, and other tables which are written once. Inserting a key which
+/// is already present fails with [`AppendOnlyError::KeyExists`], and no method to remove entries is
+/// provided. The table can be read through [`AppendOnlyTable::table`].
+///
+/// Appending keys in increasing order is fast: a key after the last key in the table is inserted
+/// without first checking whether it exists, usually directly into the last leaf page without
+/// searching the tree, and full pages are left as they are rather than split in half, so the table
+/// is densely packed.
+///
+/// Entries are only protected from writes made through an `AppendOnlyTable`. Nothing is recorded
+/// in the database file, so [`WriteTransaction::open_table`] returns a regular [`crate::Table`] for
+/// the same name, through which entries can be overwritten or removed.
+///
+/// # Examples
+///
+/// ```rust
+/// use redb::*;
+/// # use tempfile::NamedTempFile;
+/// const LOG: TableDefinition<u64, &str> = TableDefinition::new("audit_log");
+///
+/// # fn main() -> Result<(), Error> {
+/// # #[cfg(not(target_os = "wasi"))]
+/// # let tmpfile = NamedTempFile::new().unwrap();
+/// # #[cfg(target_os = "wasi")]
+/// # let tmpfile = NamedTempFile::new_in("/tmp").unwrap();
+/// # let filename = tmpfile.path();
+/// let db = Database::create(filename)?;
+///
+/// let write_txn = db.begin_write()?;
+/// {
+///     let mut log = AppendOnlyTable::open(&write_txn, LOG)?;
+///     log.insert(0, "created")?;
+///     log.insert(1, "updated")?;
+///     assert!(matches!(
+///         log.insert(0, "tampered"),
+///         Err(AppendOnlyError::KeyExists)
+///     ));
+/// }
+/// write_txn.commit()?;
+/// # Ok(())
+/// # }
+/// ```
+pub struct AppendOnlyTable<'txn, K: Key + 'static, V: Value + 'static> {
+    table: Table<'txn, K, V>,
+    // Serialized last key in the table. Keys after it can't already be present, so they don't need
+    // to be looked up
+    last_key: Option<Vec<u8>>,
+}
+
+impl<'txn, K: Key + 'static, V: Value + 'static> AppendOnlyTable<'txn, K, V> {
+    /// Open the given table, which is created if it does not exist
+    pub fn open(
+        transaction: &'txn WriteTransaction,
+        definition: TableDefinition<K, V>,
+    ) -> Result<Self, TableError> {
+        let table = transaction.open_table(definition)?;
+        let last_key = table
+            .last()?
+            .map(|(key, _)| K::as_bytes(&key.value()).as_ref().to_vec());
+        Ok(Self { table, last_key })
+    }
+
+    /// Insert mapping from the given key to the given value
+    ///
+    /// Returns [`AppendOnlyError::KeyExists`] if the key is already present
+    pub fn insert<'k, 'v>(
+        &mut self,
+        key: impl Borrow<K::SelfType<'k>>,
+        value: impl Borrow<V::SelfType<'v>>,
+    ) -> Result<(), AppendOnlyError> {
+        let key_bytes = K::as_bytes(key.borrow());
+        let appended = self
+            .last_key
+            .as_ref()
+            .is_none_or(|last| K::compare(key_bytes.as_ref(), last) == Ordering::Greater);
+        if appended {
+            self.table.insert_append(key.borrow(), value.borrow())?;
+            self.last_key = Some(key_bytes.as_ref().to_vec());
+        } else {
+            if self.table.get(key.borrow())?.is_some() {
+                return Err(AppendOnlyError::KeyExists);
+            }
+            self.table.insert(key.borrow(), value.borrow())?;
+        }
+        Ok(())
+    }
+
+    /// The underlying table
+    pub fn table(&self) -> &Table<'txn, K, V> {
+        &self.table
+    }
+}
+
+impl<K: Key + 'static, V: Value + 'static> Debug for AppendOnlyTable<'_, K, V> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("AppendOnlyTable")
+            .field("table", &self.table.name())
+            .finish_non_exhaustive()
+    }
+}
@@ -0,0 +1,330 @@
+use crate::{
+    CommitError, Database, ReadTransaction, ReadableDatabase, StorageError, TransactionError,
+    WriteTransaction,
+};
+use std::any::Any;
+use std::fmt::{Debug, Formatter};
+use std::future::Future;
+use std::panic::{self, AssertUnwindSafe};
+use std::pin::Pin;
+use std::sync::mpsc::{self, Sender};
+use std::sync::{Arc, Mutex, PoisonError};
+use std::task::{Context, Poll, Waker};
+use std::thread;
+
+const DEFAULT_WORKERS: usize = 4;
+
+type Job = Box<dyn FnOnce() + Send>;
+
+// A fixed set of threads which run blocking work submitted by async callers. The threads exit once
+// every handle to the pool has been dropped
+struct BlockingPool {
+    sender: Mutex<Sender<Job>>,
+}
+
+impl BlockingPool {
+    fn new(workers: usize) -> Self {
+        let (sender, receiver) = mpsc::channel::<Job>();
+        let receiver = Arc::new(Mutex::new(receiver));
+        for i in 0..workers.max(1) {
+            let receiver = receiver.clone();
+            thread::Builder::new()
+                .name(format!("redb-async-{i}"))
+                .spawn(move || {
+                    loop {
+                        let job = receiver.lock().unwrap().recv();
+                        let Ok(job) = job else {
+                            break;
+                        };
+                        job();
+                    }
+                })
+                .expect("failed to spawn redb worker thread");
+        }
+        Self {
+            sender: Mutex::new(sender),
+        }
+    }
+
+    fn spawn<T: Send + 'static>(
+        &self,
+        f: impl FnOnce() -> T + Send + 'static,
+    ) -> BlockingFuture<T> {
+        let (future, job) = BlockingFuture::new(f);
+        // The workers only exit once the sender has been dropped, so this cannot fail
+        self.sender.lock().unwrap().send(job).unwrap();
+        future
+    }
+}
+
+type Outcome<T> = Result<T, Box<dyn Any + Send>>;
+
+struct BlockingState<T> {
+    outcome: Option<Outcome<T>>,
+    waker: Option<Waker>,
+}
+
+/// Future which resolves to the result of blocking work run by an [`AsyncDatabase`]
+///
+/// If the work panics, the panic is resumed in the task which polls this future
+pub struct BlockingFuture<T> {
+    state: Arc<Mutex<BlockingState<T>>>,
+}
+
+impl<T: Send + 'static> BlockingFuture<T> {
+    // Returns the future, and the job which runs `f` and resolves it
+    fn new(f: impl FnOnce() -> T + Send + 'static) -> (Self, Job) {
+        let state = Arc::new(Mutex::new(BlockingState {
+            outcome: None,
+            waker: None,
+        }));
+        let job_state = state.clone();
+        let job = Box::new(move || {
+            let outcome = panic::catch_unwind(AssertUnwindSafe(f));
+            let waker = {
+                let mut state = job_state.lock().unwrap();
+                state.outcome = Some(outcome);
+                state.waker.take()
+            };
+            if let Some(waker) = waker {
+                waker.wake();
+            }
+        });
+        (Self { state }, job)
+    }
+
+    // Runs `f` on a new thread, for work which may block until other work submitted to the pool
+    // has completed
+    fn spawn_thread(f: impl FnOnce() -> T + Send + 'static) -> Self {
+        let (future, job) = Self::new(f);
+        thread::Builder::new()
+            .name("redb-async".to_string())
+            .spawn(job)
+            .expect("failed to spawn redb thread");
+        future
+    }
+}
+
+impl<T> Future for BlockingFuture<T> {
+    type Output = T;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<T> {
+        let mut state = self.state.lock().unwrap();
+        match state.outcome.take() {
+            Some(Ok(value)) => Poll::Ready(value),
+            Some(Err(payload)) => {
+                drop(state);
+                panic::resume_unwind(payload)
+            }
+            None => {
+                state.waker = Some(cx.waker().clone());
+                Poll::Pending
+            }
+        }
+    }
+}
+
+impl<T> Debug for BlockingFuture<T> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("BlockingFuture").finish_non_exhaustive()
+    }
+}
+
+/// A [`Database`] which can be used from async code
+///
+/// Every operation which may block, such as beginning or committing a transaction, or reading and
+/// writing tables, runs on a pool of worker threads owned by this database, and returns a
+/// [`BlockingFuture`] which resolves once the operation has completed. The futures do not depend on
+/// any particular async runtime.
+///
+/// Tables are accessed by passing a closure to [`AsyncWriteTransaction::run`] or
+/// [`AsyncReadTransaction::run`], which is called with the transaction on a worker thread.
+///
+/// # Examples
+///
+/// ```rust,ignore
+/// const TABLE: TableDefinition<u64, u64> = TableDefinition::new("my_data");
+///
+/// let db = AsyncDatabase::new(Database::create(filename)?);
+/// let write_txn = db.begin_write().await?;
+/// write_txn
+///     .run(|txn| txn.open_table(TABLE)?.insert(0, 1).map(|_| ()).map_err(Error::from))
+///     .await??;
+/// write_txn.commit().await?;
+///
+/// let read_txn = db.begin_read().await?;
+/// let value = read_txn
+///     .run(|txn| Ok::<_, Error>(txn.open_table(TABLE)?.get(0)?.map(|v| v.value())))
+///     .await?;
+/// assert_eq!(value, Some(1));
+/// ```
+#[derive(Clone)]
+pub struct AsyncDatabase {
+    database: Arc<Database>,
+    pool: Arc<BlockingPool>,
+}
+
+impl AsyncDatabase {
+    /// Wrap the given database, with the default number of worker threads
+    pub fn new(database: Database) -> Self {
+        Self::with_workers(database, DEFAULT_WORKERS)
+    }
+
+    /// Wrap the given database, with `workers` worker threads
+    pub fn with_workers(database: Database, workers: usize) -> Self {
+        Self {
+            database: Arc::new(database),
+            pool: Arc::new(BlockingPool::new(workers)),
+        }
+    }
+
+    /// The underlying database
+    pub fn database(&self) -> &Database {
+        &self.database
+    }
+
+    /// Begins a write transaction, as if by [`Database::begin_write`]
+    ///
+    /// This waits for any other write transaction to complete, so it runs on its own thread,
+    /// rather than occupying a worker thread
+    pub fn begin_write(&self) -> BlockingFuture<Result<AsyncWriteTransaction, TransactionError>> {
+        let database = self.database.clone();
+        let pool = self.pool.clone();
+        BlockingFuture::spawn_thread(move || {
+            let transaction = database.begin_write()?;
+            Ok(AsyncWriteTransaction {
+                transaction: Arc::new(Mutex::new(Some(transaction))),
+                pool,
+                completed: false,
+            })
+        })
+    }
+
+    /// Begins a read transaction, as if by [`ReadableDatabase::begin_read`]
+    pub fn begin_read(&self) -> BlockingFuture<Result<AsyncReadTransaction, TransactionError>> {
+        let database = self.database.clone();
+        let pool = self.pool.clone();
+        self.pool.spawn(move || {
+            let transaction = database.begin_read()?;
+            Ok(AsyncReadTransaction {
+                transaction: Arc::new(transaction),
+                pool,
+            })
+        })
+    }
+
+    /// Run `f` with the database on a worker thread
+    pub fn run<R: Send + 'static>(
+        &self,
+        f: impl FnOnce(&Database) -> R + Send + 'static,
+    ) -> BlockingFuture<R> {
+        let database = self.database.clone();
+        self.pool.spawn(move || f(&database))
+    }
+}
+
+impl Debug for AsyncDatabase {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("AsyncDatabase").finish_non_exhaustive()
+    }
+}
+
+/// A [`WriteTransaction`] which can be used from async code
+///
+/// If it is dropped without being committed, the transaction is aborted
+pub struct AsyncWriteTransaction {
+    // Only None once the transaction has been committed or aborted
+    transaction: Arc<Mutex<Option<WriteTransaction>>>,
+    pool: Arc<BlockingPool>,
+    // Set once commit or abort has been submitted, so that Drop leaves the transaction to them
+    completed: bool,
+}
+
+impl AsyncWriteTransaction {
+    /// Run `f` with the transaction on a worker thread
+    ///
+    /// Calls made while a previous call is still running wait for it to complete. Returns
+    /// [`TransactionError::TransactionCompleted`] if the transaction is committed or aborted before
+    /// `f` runs
+    pub fn run<R: Send + 'static>(
+        &self,
+        f: impl FnOnce(&WriteTransaction) -> R + Send + 'static,
+    ) -> BlockingFuture<Result<R, TransactionError>> {
+        let transaction = self.transaction.clone();
+        self.pool.spawn(move || {
+            let transaction = transaction.lock().unwrap_or_else(PoisonError::into_inner);
+            transaction
+                .as_ref()
+                .map(f)
+                .ok_or(TransactionError::TransactionCompleted)
+        })
+    }
+
+    /// Commit the transaction, as if by [`WriteTransaction::commit`]
+    pub fn commit(mut self) -> BlockingFuture<Result<(), CommitError>> {
+        self.completed = true;
+        let transaction = self.transaction.clone();
+        // The transaction is taken on the worker, since a call to run() may be holding it
+        self.pool.spawn(move || match take(&transaction) {
+            Some(transaction) => transaction.commit(),
+            None => Err(CommitError::TransactionPoisoned),
+        })
+    }
+
+    /// Abort the transaction, as if by [`WriteTransaction::abort`]
+    pub fn abort(mut self) -> BlockingFuture<Result<(), StorageError>> {
+        self.completed = true;
+        let transaction = self.transaction.clone();
+        self.pool
+            .spawn(move || take(&transaction).map_or(Ok(()), WriteTransaction::abort))
+    }
+}
+
+fn take(transaction: &Mutex<Option<WriteTransaction>>) -> Option<WriteTransaction> {
+    transaction
+        .lock()
+        .unwrap_or_else(PoisonError::into_inner)
+        .take()
+}
+
+impl Drop for AsyncWriteTransaction {
+    fn drop(&mut self) {
+        // Abort on a worker thread, since aborting may block
+        if !self.completed {
+            let transaction = self.transaction.clone();
+            drop(self.pool.spawn(move || drop(take(&transaction))));
+        }
+    }
+}
+
+impl Debug for AsyncWriteTransaction {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("AsyncWriteTransaction")
+            .finish_non_exhaustive()
+    }
+}
+
+/// A [`ReadTransaction`] which can be used from async code
+#[derive(Clone)]
+pub struct AsyncReadTransaction {
+    transaction: Arc<ReadTransaction>,
+    pool: Arc<BlockingPool>,
+}
+
+impl AsyncReadTransaction {
+    /// Run `f` with the transaction on a worker thread
+    pub fn run<R: Send + 'static>(
+        &self,
+        f: impl FnOnce(&ReadTransaction) -> R + Send + 'static,
+    ) -> BlockingFuture<R> {
+        let transaction = self.transaction.clone();
+        self.pool.spawn(move || f(&transaction))
+    }
+}
+
+impl Debug for AsyncReadTransaction {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("AsyncReadTransaction")
+            .finish_non_exhaustive()
+    }
+}
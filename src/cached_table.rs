@@ -0,0 +1,202 @@
+use crate::table::TableVersion;
+use crate::{AccessGuard, Key, ReadOnlyTable, Result, Table, TableDefinition, TableHandle, Value};
+use std::borrow::Borrow;
+use std::collections::HashMap;
+use std::fmt::{Debug, Formatter};
+use std::sync::{Arc, Mutex};
+
+struct CacheState {
+    // Version of the table that the entries were read from. Any committed change to the table
+    // changes it
+    version: Option<TableVersion>,
+    // Serialized key -> serialized value, or None if the key is not in the table
+    entries: HashMap<Vec<u8>, Option<Arc<[u8]>>>,
+}
+
+/// An in-memory cache of the entries in a table
+///
+/// Entries are cached as they are read through [`CachedTable::get`], and remain valid until a
+/// transaction which modifies the table commits. At that point every cached entry is discarded,
+/// the next time the cache is read through a table from a newer transaction. A cache can be shared
+/// by any number of threads, and read through tables from different read transactions, although
+/// alternating between transactions which see different versions of the table discards the cached
+/// entries each time.
+///
+/// Writes made through [`CachedTable::insert`] and [`CachedTable::remove`] are applied to the
+/// table, and the affected key is removed from the cache.
+///
+/// # Examples
+///
+/// ```rust
+/// use redb::*;
+/// # use tempfile::NamedTempFile;
+/// const TABLE: TableDefinition<&str, u64> = TableDefinition::new("my_data");
+///
+/// # fn main() -> Result<(), Error> {
+/// # #[cfg(not(target_os = "wasi"))]
+/// # let tmpfile = NamedTempFile::new().unwrap();
+/// # #[cfg(target_os = "wasi")]
+/// # let tmpfile = NamedTempFile::new_in("/tmp").unwrap();
+/// # let filename = tmpfile.path();
+/// let db = Database::create(filename)?;
+/// let cache = CachedTable::new(TABLE, 1024);
+///
+/// let write_txn = db.begin_write()?;
+/// {
+///     let mut table = write_txn.open_table(TABLE)?;
+///     cache.insert(&mut table, "a", 1)?;
+/// }
+/// write_txn.commit()?;
+///
+/// let read_txn = db.begin_read()?;
+/// let table = read_txn.open_table(TABLE)?;
+/// assert_eq!(cache.get(&table, "a")?.unwrap().value(), 1);
+/// // Served from the cache
+/// assert_eq!(cache.get(&table, "a")?.unwrap().value(), 1);
+/// # Ok(())
+/// # }
+/// ```
+pub struct CachedTable<K: Key + 'static, V: Value + 'static> {
+    definition: TableDefinition<'static, K, V>,
+    capacity: usize,
+    state: Mutex<CacheState>,
+}
+
+impl<K: Key + 'static, V: Value + 'static> CachedTable<K, V> {
+    /// Create an empty cache for the given table, which holds at most `capacity` entries
+    pub fn new(definition: TableDefinition<'static, K, V>, capacity: usize) -> Self {
+        Self {
+            definition,
+            capacity,
+            state: Mutex::new(CacheState {
+                version: None,
+                entries: HashMap::new(),
+            }),
+        }
+    }
+
+    /// The definition of the cached table
+    pub fn definition(&self) -> TableDefinition<'static, K, V> {
+        self.definition
+    }
+
+    /// Number of entries in the cache
+    pub fn len(&self) -> usize {
+        self.state.lock().unwrap().entries.len()
+    }
+
+    /// Returns `true` if the cache contains no entries
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Discard every cached entry
+    pub fn clear(&self) {
+        let mut state = self.state.lock().unwrap();
+        state.entries.clear();
+    }
+
+    /// Returns the value corresponding to the given key, from the cache if possible
+    ///
+    /// # Panics
+    ///
+    /// Panics if `table` is not the table this cache was created for
+    pub fn get<'a>(
+        &self,
+        table: &ReadOnlyTable<K, V>,
+        key: impl Borrow<K::SelfType<'a>>,
+    ) -> Result<Option<AccessGuard<'static, V>>> {
+        assert_eq!(table.name(), self.definition.name());
+        let key_bytes = K::as_bytes(key.borrow()).as_ref().to_vec();
+        // Tables whose version can't be identified are read without the cache
+        let version = table.version();
+        if version.is_some() {
+            let mut state = self.state.lock().unwrap();
+            if state.version == version {
+                if let Some(cached) = state.entries.get(&key_bytes) {
+                    return Ok(cached.clone().map(Self::guard));
+                }
+            } else {
+                state.version = version;
+                state.entries.clear();
+            }
+        }
+
+        let value: Option<Arc<[u8]>> = table.get(key)?.map(|guard| {
+            // Copy the value out, rather than holding a reference to the whole page
+            let (page, range) = guard.arc_view();
+            Arc::from(&page[range])
+        });
+        let mut state = self.state.lock().unwrap();
+        // Another thread may have read a different version of the table in the meantime
+        if version.is_some() && state.version == version && self.capacity > 0 {
+            if state.entries.len() >= self.capacity
+                && let Some(evicted) = state.entries.keys().next().cloned()
+            {
+                state.entries.remove(&evicted);
+            }
+            state.entries.insert(key_bytes, value.clone());
+        }
+
+        Ok(value.map(Self::guard))
+    }
+
+    /// Insert mapping from the given key to the given value, in `table`
+    ///
+    /// Returns the old value, if the key was present in the table
+    ///
+    /// # Panics
+    ///
+    /// Panics if `table` is not the table this cache was created for
+    pub fn insert<'t, 'k, 'v>(
+        &self,
+        table: &'t mut Table<'_, K, V>,
+        key: impl Borrow<K::SelfType<'k>>,
+        value: impl Borrow<V::SelfType<'v>>,
+    ) -> Result<Option<AccessGuard<'t, V>>> {
+        assert_eq!(table.name(), self.definition.name());
+        self.invalidate(key.borrow());
+        table.insert(key, value)
+    }
+
+    /// Removes the given key from `table`
+    ///
+    /// Returns the old value, if the key was present in the table
+    ///
+    /// # Panics
+    ///
+    /// Panics if `table` is not the table this cache was created for
+    pub fn remove<'t, 'a>(
+        &self,
+        table: &'t mut Table<'_, K, V>,
+        key: impl Borrow<K::SelfType<'a>>,
+    ) -> Result<Option<AccessGuard<'t, V>>> {
+        assert_eq!(table.name(), self.definition.name());
+        self.invalidate(key.borrow());
+        table.remove(key)
+    }
+
+    fn invalidate(&self, key: &K::SelfType<'_>) {
+        let key_bytes = K::as_bytes(key);
+        self.state
+            .lock()
+            .unwrap()
+            .entries
+            .remove(key_bytes.as_ref());
+    }
+
+    fn guard<'a>(value: Arc<[u8]>) -> AccessGuard<'a, V> {
+        let len = value.len();
+        AccessGuard::with_arc_page(value, 0..len)
+    }
+}
+
+impl<K: Key + 'static, V: Value + 'static> Debug for CachedTable<K, V> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("CachedTable")
+            .field("table", &self.definition.name())
+            .field("capacity", &self.capacity)
+            .field("len", &self.len())
+            .finish_non_exhaustive()
+    }
+}
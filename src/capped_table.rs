@@ -0,0 +1,156 @@
+use crate::{
+    Key, ReadableTableMetadata, Result, Table, TableDefinition, TableError, TableHandle, Value,
+    WriteTransaction,
+};
+use std::borrow::Borrow;
+use std::fmt::{Debug, Formatter};
+
+/// Limit on the size of a [`CappedTable`]
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum Cap {
+    /// At most this many entries
+    Entries(u64),
+    /// At most this many bytes of keys and values, as reported by
+    /// [`TableStats::stored_bytes`](crate::TableStats::stored_bytes)
+    Bytes(u64),
+}
+
+/// A table which is kept below a maximum size by removing its smallest keys
+///
+/// This is intended for logs, metrics, and other tables which retain only their most recent
+/// entries, and whose keys increase with age, such as a timestamp or sequence number. Whenever an
+/// insert takes the table over its [`Cap`], entries are removed from the start of the table, in the
+/// same transaction, until it is within the cap again.
+///
+/// A table opened with [`Cap::Bytes`] is scanned when it is opened, to measure its size.
+///
+/// Only inserts made through a `CappedTable` trim the table. Entries inserted after opening it with
+/// [`WriteTransaction::open_table`] are kept until the next insert through a `CappedTable`, which
+/// removes the oldest entries until the table is back within its cap.
+///
+/// # Examples
+///
+/// ```rust
+/// use redb::*;
+/// # use tempfile::NamedTempFile;
+/// const EVENTS: TableDefinition<u64, &str> = TableDefinition::new("events");
+///
+/// # fn main() -> Result<(), Error> {
+/// # #[cfg(not(target_os = "wasi"))]
+/// # let tmpfile = NamedTempFile::new().unwrap();
+/// # #[cfg(target_os = "wasi")]
+/// # let tmpfile = NamedTempFile::new_in("/tmp").unwrap();
+/// # let filename = tmpfile.path();
+/// let db = Database::create(filename)?;
+///
+/// let write_txn = db.begin_write()?;
+/// {
+///     let mut events = CappedTable::open(&write_txn, EVENTS, Cap::Entries(2))?;
+///     events.insert(0, "started")?;
+///     events.insert(1, "running")?;
+///     assert_eq!(events.insert(2, "stopped")?, 1);
+///     assert_eq!(events.table().first()?.unwrap().0.value(), 1);
+/// }
+/// write_txn.commit()?;
+/// # Ok(())
+/// # }
+/// ```
+pub struct CappedTable<'txn, K: Key + 'static, V: Value + 'static> {
+    table: Table<'txn, K, V>,
+    cap: Cap,
+    // Bytes of keys and values in the table. Only tracked for Cap::Bytes
+    stored_bytes: u64,
+}
+
+impl<'txn, K: Key + 'static, V: Value + 'static> CappedTable<'txn, K, V> {
+    /// Open the given table, which is created if it does not exist
+    ///
+    /// If the table is over the cap, its smallest keys are removed
+    pub fn open(
+        transaction: &'txn WriteTransaction,
+        definition: TableDefinition<K, V>,
+        cap: Cap,
+    ) -> Result<Self, TableError> {
+        let table = transaction.open_table(definition)?;
+        let stored_bytes = match cap {
+            Cap::Entries(_) => 0,
+            Cap::Bytes(_) => table.stats()?.stored_bytes(),
+        };
+        let mut result = Self {
+            table,
+            cap,
+            stored_bytes,
+        };
+        result.evict()?;
+        Ok(result)
+    }
+
+    /// Insert mapping from the given key to the given value, and then remove the smallest keys
+    /// until the table is within its cap
+    ///
+    /// Returns the number of entries removed. If the key is smaller than every other key in the
+    /// table, the new entry may be one of them
+    pub fn insert<'k, 'v>(
+        &mut self,
+        key: impl Borrow<K::SelfType<'k>>,
+        value: impl Borrow<V::SelfType<'v>>,
+    ) -> Result<u64> {
+        if !matches!(self.cap, Cap::Bytes(_)) {
+            self.table.insert(key, value)?;
+            return self.evict();
+        }
+        let key_len = K::as_bytes(key.borrow()).as_ref().len() as u64;
+        let value_len = V::as_bytes(value.borrow()).as_ref().len() as u64;
+        if let Some(old) = self.table.insert(key, value)? {
+            let old_len = V::as_bytes(&old.value()).as_ref().len() as u64;
+            self.stored_bytes = self.stored_bytes - old_len + value_len;
+        } else {
+            self.stored_bytes += key_len + value_len;
+        }
+        self.evict()
+    }
+
+    /// The cap on the size of the table
+    pub fn cap(&self) -> Cap {
+        self.cap
+    }
+
+    /// The underlying table
+    pub fn table(&self) -> &Table<'txn, K, V> {
+        &self.table
+    }
+
+    fn over_cap(&self) -> Result<bool> {
+        Ok(match self.cap {
+            Cap::Entries(max) => self.table.len()? > max,
+            Cap::Bytes(max) => self.stored_bytes > max,
+        })
+    }
+
+    // Removes the smallest keys until the table is within the cap
+    fn evict(&mut self) -> Result<u64> {
+        let mut evicted = 0;
+        while self.over_cap()? {
+            let Some((key, value)) = self.table.pop_first()? else {
+                break;
+            };
+            if matches!(self.cap, Cap::Bytes(_)) {
+                let len = K::as_bytes(&key.value()).as_ref().len()
+                    + V::as_bytes(&value.value()).as_ref().len();
+                self.stored_bytes -= len as u64;
+            }
+            evicted += 1;
+        }
+        Ok(evicted)
+    }
+}
+
+impl<K: Key + 'static, V: Value + 'static> Debug for CappedTable<'_, K, V> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("CappedTable")
+            .field("table", &self.table.name())
+            .field("cap", &self.cap)
+            .finish_non_exhaustive()
+    }
+}
@@ -0,0 +1,138 @@
+use crate::StorageError;
+use std::mem::size_of;
+
+// Format:
+// 4 bytes: table name length
+// n bytes: table name
+// 4 bytes: key length
+// n bytes: key
+// 1 byte: flags (bit 0 = old value present, bit 1 = new value present)
+// If the old value is present:
+// 4 bytes: old value length
+// n bytes: old value
+// If the new value is present:
+// 4 bytes: new value length
+// n bytes: new value
+const OLD_VALUE: u8 = 1;
+const NEW_VALUE: u8 = 2;
+
+/// A change to an entry in a table, as recorded by [`crate::Builder::set_change_capture`]
+///
+/// Keys and values are in their serialized form, and can be decoded with
+/// [`crate::Value::from_bytes`] of the table's key and value types.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ChangeRecord {
+    transaction_id: u64,
+    table_name: String,
+    key: Vec<u8>,
+    old_value: Option<Vec<u8>>,
+    new_value: Option<Vec<u8>>,
+}
+
+impl ChangeRecord {
+    pub(crate) fn new(
+        transaction_id: u64,
+        table_name: &str,
+        key: Vec<u8>,
+        old_value: Option<Vec<u8>>,
+        new_value: Option<Vec<u8>>,
+    ) -> Self {
+        Self {
+            transaction_id,
+            table_name: table_name.to_string(),
+            key,
+            old_value,
+            new_value,
+        }
+    }
+
+    /// Id of the transaction which made the change
+    pub fn transaction_id(&self) -> u64 {
+        self.transaction_id
+    }
+
+    /// Name of the table
+    pub fn table_name(&self) -> &str {
+        &self.table_name
+    }
+
+    /// The key which changed
+    pub fn key(&self) -> &[u8] {
+        &self.key
+    }
+
+    /// The value before the change, or `None` if the key was inserted
+    pub fn old_value(&self) -> Option<&[u8]> {
+        self.old_value.as_deref()
+    }
+
+    /// The value after the change, or `None` if the key was removed
+    pub fn new_value(&self) -> Option<&[u8]> {
+        self.new_value.as_deref()
+    }
+
+    pub(crate) fn to_bytes(&self) -> Vec<u8> {
+        let mut result = vec![];
+        push_bytes(&mut result, self.table_name.as_bytes());
+        push_bytes(&mut result, &self.key);
+        let mut flags = 0;
+        if self.old_value.is_some() {
+            flags |= OLD_VALUE;
+        }
+        if self.new_value.is_some() {
+            flags |= NEW_VALUE;
+        }
+        result.push(flags);
+        if let Some(value) = &self.old_value {
+            push_bytes(&mut result, value);
+        }
+        if let Some(value) = &self.new_value {
+            push_bytes(&mut result, value);
+        }
+        result
+    }
+
+    pub(crate) fn from_bytes(transaction_id: u64, data: &[u8]) -> Result<Self, StorageError> {
+        let corrupted = || StorageError::Corrupted("Change log entry is corrupted".to_string());
+        let mut offset = 0;
+        let table_name = read_bytes(data, &mut offset).ok_or_else(corrupted)?;
+        let table_name = String::from_utf8(table_name).map_err(|_| corrupted())?;
+        let key = read_bytes(data, &mut offset).ok_or_else(corrupted)?;
+        let flags = *data.get(offset).ok_or_else(corrupted)?;
+        offset += 1;
+        if flags & !(OLD_VALUE | NEW_VALUE) != 0 {
+            return Err(corrupted());
+        }
+        let old_value = if flags & OLD_VALUE != 0 {
+            Some(read_bytes(data, &mut offset).ok_or_else(corrupted)?)
+        } else {
+            None
+        };
+        let new_value = if flags & NEW_VALUE != 0 {
+            Some(read_bytes(data, &mut offset).ok_or_else(corrupted)?)
+        } else {
+            None
+        };
+        Ok(Self {
+            transaction_id,
+            table_name,
+            key,
+            old_value,
+            new_value,
+        })
+    }
+}
+
+fn push_bytes(output: &mut Vec<u8>, value: &[u8]) {
+    output.extend_from_slice(&u32::try_from(value.len()).unwrap().to_le_bytes());
+    output.extend_from_slice(value);
+}
+
+fn read_bytes(data: &[u8], offset: &mut usize) -> Option<Vec<u8>> {
+    let len_bytes = data.get(*offset..(*offset + size_of::<u32>()))?;
+    let len = u32::from_le_bytes(len_bytes.try_into().unwrap()) as usize;
+    *offset += size_of::<u32>();
+    let value = data.get(*offset..(*offset + len))?;
+    *offset += len;
+    Some(value.to_vec())
+}
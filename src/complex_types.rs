@@ -1,4 +1,7 @@
-use crate::types::{TypeName, Value};
+use crate::DecodeError;
+use crate::types::{TryValue, TypeName, Value};
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
+use std::hash::{BuildHasher, Hash};
 
 // Encode len as a varint and store it at the end of output
 pub(super) fn encode_varint_len(len: usize, output: &mut Vec<u8>) {
@@ -31,6 +34,105 @@ pub(super) fn decode_varint_len(data: &[u8]) -> (usize, usize) {
     }
 }
 
+// Same as decode_varint_len(), but returns an error if data is too short
+pub(super) fn try_decode_varint_len(data: &[u8]) -> Result<(usize, usize), DecodeError> {
+    let required = match data.first() {
+        Some(0..=253) => 1,
+        Some(254) => 3,
+        Some(255) => 5,
+        None => 0,
+    };
+    if required == 0 || data.len() < required {
+        return Err(DecodeError::new("length is truncated"));
+    }
+    Ok(decode_varint_len(data))
+}
+
+// Store an element at the end of output, prefixed with its length if it is not fixed width
+fn encode_element<T: Value>(element: &T::SelfType<'_>, output: &mut Vec<u8>) {
+    let serialized = T::as_bytes(element);
+    if T::fixed_width().is_none() {
+        encode_varint_len(serialized.as_ref().len(), output);
+    }
+    output.extend_from_slice(serialized.as_ref());
+}
+
+// Decode an element starting at offset, and advance offset past it
+fn decode_element<'a, T: Value + 'a>(data: &'a [u8], offset: &mut usize) -> T::SelfType<'a> {
+    let element_len = if let Some(len) = T::fixed_width() {
+        len
+    } else {
+        let (len, consumed) = decode_varint_len(&data[*offset..]);
+        *offset += consumed;
+        len
+    };
+    let result = T::from_bytes(&data[*offset..(*offset + element_len)]);
+    *offset += element_len;
+    result
+}
+
+// Same as decode_element(), but returns an error if the element is invalid or out of bounds
+fn try_decode_element<'a, T: TryValue + 'a>(
+    data: &'a [u8],
+    offset: &mut usize,
+) -> Result<T::SelfType<'a>, DecodeError> {
+    let element_len = if let Some(len) = T::fixed_width() {
+        len
+    } else {
+        let (len, consumed) = try_decode_varint_len(&data[*offset..])?;
+        *offset += consumed;
+        len
+    };
+    let Some(element) = data.get(*offset..(*offset).saturating_add(element_len)) else {
+        return Err(DecodeError::new("element is out of bounds"));
+    };
+    let result = T::try_from_bytes(element)?;
+    *offset += element_len;
+    Ok(result)
+}
+
+// Encode a sequence of elements in the format used by Vec: the number of elements as a varint,
+// followed by the elements
+pub(crate) fn encode_sequence<T: Value>(elements: &[T::SelfType<'_>]) -> Vec<u8> {
+    let mut result = if let Some(width) = T::fixed_width() {
+        Vec::with_capacity(elements.len() * width + 5)
+    } else {
+        Vec::with_capacity(elements.len() * 2 + 5)
+    };
+    encode_varint_len(elements.len(), &mut result);
+
+    for element in elements {
+        encode_element::<T>(element, &mut result);
+    }
+    result
+}
+
+// Decode a sequence of elements encoded by encode_sequence()
+pub(crate) fn decode_sequence<'a, T: Value + 'a, C: FromIterator<T::SelfType<'a>>>(
+    data: &'a [u8],
+) -> C {
+    let (elements, mut offset) = decode_varint_len(data);
+    let result = (0..elements)
+        .map(|_| decode_element::<T>(data, &mut offset))
+        .collect();
+    assert_eq!(offset, data.len());
+    result
+}
+
+// Same as decode_sequence(), but returns an error if the sequence is invalid
+pub(crate) fn try_decode_sequence<'a, T: TryValue + 'a, C: FromIterator<T::SelfType<'a>>>(
+    data: &'a [u8],
+) -> Result<C, DecodeError> {
+    let (elements, mut offset) = try_decode_varint_len(data)?;
+    let result = (0..elements)
+        .map(|_| try_decode_element::<T>(data, &mut offset))
+        .collect::<Result<C, DecodeError>>()?;
+    if offset != data.len() {
+        return Err(DecodeError::new("sequence has trailing data"));
+    }
+    Ok(result)
+}
+
 impl<T: Value> Value for Vec<T> {
     type SelfType<'a>
         = Vec<T::SelfType<'a>>
@@ -49,45 +151,194 @@ impl<T: Value> Value for Vec<T> {
     where
         Self: 'a,
     {
-        let (elements, mut offset) = decode_varint_len(data);
-        let mut result = Vec::with_capacity(elements);
-        for _ in 0..elements {
-            let element_len = if let Some(len) = T::fixed_width() {
-                len
-            } else {
-                let (len, consumed) = decode_varint_len(&data[offset..]);
-                offset += consumed;
-                len
-            };
-            result.push(T::from_bytes(&data[offset..(offset + element_len)]));
-            offset += element_len;
-        }
-        assert_eq!(offset, data.len());
-        result
+        decode_sequence::<T, _>(data)
     }
 
     fn as_bytes<'a, 'b: 'a>(value: &'a Vec<T::SelfType<'b>>) -> Vec<u8>
     where
         Self: 'b,
     {
-        let mut result = if let Some(width) = T::fixed_width() {
-            Vec::with_capacity(value.len() * width + 5)
-        } else {
-            Vec::with_capacity(value.len() * 2 + 5)
-        };
-        encode_varint_len(value.len(), &mut result);
-
-        for element in value {
-            let serialized = T::as_bytes(element);
-            if T::fixed_width().is_none() {
-                encode_varint_len(serialized.as_ref().len(), &mut result);
-            }
-            result.extend_from_slice(serialized.as_ref());
-        }
-        result
+        encode_sequence::<T>(value)
     }
 
     fn type_name() -> TypeName {
         TypeName::internal(&format!("Vec<{}>", T::type_name().name()))
     }
 }
+
+impl<T: TryValue> TryValue for Vec<T> {
+    fn try_from_bytes<'a>(data: &'a [u8]) -> Result<Vec<T::SelfType<'a>>, DecodeError>
+    where
+        Self: 'a,
+    {
+        try_decode_sequence::<T, _>(data)
+    }
+}
+
+// Store the encoded entries at the end of output, sorted by their bytes, so that equal collections
+// are always stored the same way, whatever order they iterate in
+fn extend_sorted(mut entries: Vec<Vec<u8>>, output: &mut Vec<u8>) {
+    entries.sort_unstable();
+    for entry in entries {
+        output.extend_from_slice(&entry);
+    }
+}
+
+// Sets are stored in the same format as Vec, and maps are stored as a Vec of (key, value) pairs,
+// without a length prefix on each pair. The entries of collections without a defined order are
+// sorted by their encoding
+macro_rules! set_impl {
+    ($set:ident<T $(, $hasher:ident)?>, $new:expr, sorted = $sorted:literal, $($bound:tt)+) => {
+        impl<T: Value $(, $hasher: BuildHasher + Default + 'static)?> Value
+            for $set<T $(, $hasher)?>
+        where
+            for<'a> T::SelfType<'a>: $($bound)+,
+        {
+            type SelfType<'a>
+                = $set<T::SelfType<'a> $(, $hasher)?>
+            where
+                Self: 'a;
+            type AsBytes<'a>
+                = Vec<u8>
+            where
+                Self: 'a;
+
+            fn fixed_width() -> Option<usize> {
+                None
+            }
+
+            fn from_bytes<'a>(data: &'a [u8]) -> Self::SelfType<'a>
+            where
+                Self: 'a,
+            {
+                let (elements, mut offset) = decode_varint_len(data);
+                let mut result = $new;
+                for _ in 0..elements {
+                    result.insert(decode_element::<T>(data, &mut offset));
+                }
+                assert_eq!(offset, data.len());
+                result
+            }
+
+            fn as_bytes<'a, 'b: 'a>(value: &'a Self::SelfType<'b>) -> Vec<u8>
+            where
+                Self: 'b,
+            {
+                let mut result = Vec::with_capacity(value.len() * 2 + 5);
+                encode_varint_len(value.len(), &mut result);
+                if $sorted {
+                    let entries = value
+                        .iter()
+                        .map(|element| {
+                            let mut entry = vec![];
+                            encode_element::<T>(element, &mut entry);
+                            entry
+                        })
+                        .collect();
+                    extend_sorted(entries, &mut result);
+                } else {
+                    for element in value {
+                        encode_element::<T>(element, &mut result);
+                    }
+                }
+                result
+            }
+
+            fn type_name() -> TypeName {
+                TypeName::internal(&format!(
+                    "{}<{}>",
+                    stringify!($set),
+                    T::type_name().name()
+                ))
+            }
+        }
+    };
+}
+
+macro_rules! map_impl {
+    ($map:ident<K, V $(, $hasher:ident)?>, $new:expr, sorted = $sorted:literal, $($bound:tt)+) => {
+        impl<K: Value, V: Value $(, $hasher: BuildHasher + Default + 'static)?> Value
+            for $map<K, V $(, $hasher)?>
+        where
+            for<'a> K::SelfType<'a>: $($bound)+,
+        {
+            type SelfType<'a>
+                = $map<K::SelfType<'a>, V::SelfType<'a> $(, $hasher)?>
+            where
+                Self: 'a;
+            type AsBytes<'a>
+                = Vec<u8>
+            where
+                Self: 'a;
+
+            fn fixed_width() -> Option<usize> {
+                None
+            }
+
+            fn from_bytes<'a>(data: &'a [u8]) -> Self::SelfType<'a>
+            where
+                Self: 'a,
+            {
+                let (elements, mut offset) = decode_varint_len(data);
+                let mut result = $new;
+                for _ in 0..elements {
+                    let key = decode_element::<K>(data, &mut offset);
+                    let value = decode_element::<V>(data, &mut offset);
+                    result.insert(key, value);
+                }
+                assert_eq!(offset, data.len());
+                result
+            }
+
+            fn as_bytes<'a, 'b: 'a>(value: &'a Self::SelfType<'b>) -> Vec<u8>
+            where
+                Self: 'b,
+            {
+                let mut result = Vec::with_capacity(value.len() * 4 + 5);
+                encode_varint_len(value.len(), &mut result);
+                if $sorted {
+                    let entries = value
+                        .iter()
+                        .map(|(key, value)| {
+                            let mut entry = vec![];
+                            encode_element::<K>(key, &mut entry);
+                            encode_element::<V>(value, &mut entry);
+                            entry
+                        })
+                        .collect();
+                    extend_sorted(entries, &mut result);
+                } else {
+                    for (key, value) in value {
+                        encode_element::<K>(key, &mut result);
+                        encode_element::<V>(value, &mut result);
+                    }
+                }
+                result
+            }
+
+            fn type_name() -> TypeName {
+                TypeName::internal(&format!(
+                    "{}<{},{}>",
+                    stringify!($map),
+                    K::type_name().name(),
+                    V::type_name().name()
+                ))
+            }
+        }
+    };
+}
+
+set_impl!(BTreeSet<T>, BTreeSet::new(), sorted = false, Ord);
+set_impl!(
+    HashSet<T, S>,
+    HashSet::with_hasher(S::default()),
+    sorted = true,
+    Hash + Eq
+);
+map_impl!(BTreeMap<K, V>, BTreeMap::new(), sorted = false, Ord);
+map_impl!(
+    HashMap<K, V, S>,
+    HashMap::with_hasher(S::default()),
+    sorted = true,
+    Hash + Eq
+);
@@ -0,0 +1,194 @@
+use crate::{Database, Durability, PrepareError, TransactionError, WriteTransaction};
+use std::sync::Arc;
+
+/// Commits write transactions on several databases atomically
+///
+/// Either all of the transactions passed to [`CommitCoordinator::commit`] are committed, or none
+/// of them are, even if the process crashes part way through. The first database is the
+/// coordinator: the other databases prepare their transactions with
+/// [`WriteTransaction::prepare`], and the commit of the coordinator's transaction decides whether
+/// they are committed. Prepared transactions left behind by a crash are resolved by
+/// [`CommitCoordinator::recover`], which is called by [`CommitCoordinator::new`].
+///
+/// A database should only take part in the commits of a single coordinator database, and should
+/// not be written to outside of the coordinator while a commit may have been left unresolved.
+pub struct CommitCoordinator<'a> {
+    databases: Vec<&'a Database>,
+}
+
+impl<'a> CommitCoordinator<'a> {
+    /// Creates a coordinator for `databases`, and resolves any commits left unfinished by a
+    /// previous coordinator. The first database records the outcome of each commit
+    ///
+    /// Returns [`PrepareError::InvalidParticipants`] if `databases` is empty, or contains the same
+    /// database more than once
+    pub fn new(databases: &[&'a Database]) -> Result<Self, PrepareError> {
+        if databases.is_empty() {
+            return Err(PrepareError::InvalidParticipants(
+                "No databases to coordinate".to_string(),
+            ));
+        }
+        for (i, database) in databases.iter().enumerate() {
+            if databases[..i]
+                .iter()
+                .any(|other| Arc::ptr_eq(&other.get_memory(), &database.get_memory()))
+            {
+                return Err(PrepareError::InvalidParticipants(format!(
+                    "Database {i} is listed more than once"
+                )));
+            }
+        }
+        let coordinator = Self {
+            databases: databases.to_vec(),
+        };
+        coordinator.recover()?;
+        Ok(coordinator)
+    }
+
+    /// Begins a write transaction on each database, in the order they were passed to
+    /// [`CommitCoordinator::new`]
+    pub fn begin_write(&self) -> Result<Vec<WriteTransaction>, TransactionError> {
+        self.databases
+            .iter()
+            .map(|database| database.begin_write())
+            .collect()
+    }
+
+    /// Commits the transactions, which must be the ones returned by
+    /// [`CommitCoordinator::begin_write`], in the same order
+    ///
+    /// If an error is returned, the transactions on the other databases may be left prepared.
+    /// [`CommitCoordinator::recover`] commits them if the coordinator's transaction was committed,
+    /// and rolls them back otherwise.
+    ///
+    /// Returns [`PrepareError::InvalidParticipants`], and aborts the transactions, if they are not
+    /// one per database in the same order
+    pub fn commit(&self, transactions: Vec<WriteTransaction>) -> Result<(), PrepareError> {
+        if transactions.len() != self.databases.len() {
+            return Err(PrepareError::InvalidParticipants(format!(
+                "Expected {} transactions, one per database, but got {}",
+                self.databases.len(),
+                transactions.len()
+            )));
+        }
+        for (i, (transaction, database)) in transactions.iter().zip(&self.databases).enumerate() {
+            if !transaction.belongs_to(database) {
+                return Err(PrepareError::InvalidParticipants(format!(
+                    "Transaction {i} does not belong to database {i}"
+                )));
+            }
+        }
+        let mut transactions = transactions.into_iter();
+        let mut coordinator = transactions.next().unwrap();
+        let commit_id = coordinator.id();
+
+        // 1) prepare the participants
+        let mut prepared = vec![];
+        for (transaction, database) in transactions.zip(&self.databases[1..]) {
+            match transaction.prepare_coordinated(commit_id) {
+                Ok(id) => prepared.push((*database, id)),
+                Err(err) => {
+                    coordinator.abort()?;
+                    Self::rollback(&prepared)?;
+                    return Err(err);
+                }
+            }
+        }
+
+        // 2) apply each prepared transaction, while holding the participant's write lock so that
+        // it can't be modified until the outcome is decided
+        let mut staged = vec![];
+        for (database, id) in &prepared {
+            let result = database
+                .begin_write()
+                .map_err(|err| PrepareError::Storage(err.into_storage_error()))
+                .and_then(|mut transaction| {
+                    transaction.commit_prepared(*id)?;
+                    Ok(transaction)
+                });
+            match result {
+                Ok(transaction) => staged.push(transaction),
+                Err(err) => {
+                    drop(staged);
+                    coordinator.abort()?;
+                    Self::rollback(&prepared)?;
+                    return Err(err);
+                }
+            }
+        }
+
+        // 3) decide. Once the coordinator's transaction is durable, the participants must commit
+        coordinator.record_coordinated_commit(commit_id)?;
+        coordinator.set_durability(Durability::Immediate).unwrap();
+        coordinator.commit()?;
+
+        // 4) commit the participants
+        for transaction in staged {
+            transaction.commit()?;
+        }
+
+        // The decision is no longer needed. If this commit is lost, recover() removes it
+        let mut transaction = self.databases[0]
+            .begin_write()
+            .map_err(|err| PrepareError::Storage(err.into_storage_error()))?;
+        transaction.set_durability(Durability::None).unwrap();
+        transaction.remove_coordinated_commits(&[commit_id])?;
+        transaction.commit()?;
+
+        Ok(())
+    }
+
+    /// Resolves the commits which were interrupted, for example by a crash
+    ///
+    /// Prepared transactions whose commit was decided by the coordinator are committed, and the
+    /// others are rolled back. This must not be called while [`CommitCoordinator::commit`] is
+    /// running on another coordinator for the same databases.
+    ///
+    /// Returns [`PrepareError::Conflict`] if a decided transaction can't be committed, because its
+    /// database was modified after it was prepared. The prepared transaction and the coordinator's
+    /// decision are both kept, so that the conflict can be resolved by hand.
+    pub fn recover(&self) -> Result<(), PrepareError> {
+        // Holding the coordinator's write lock excludes concurrent commits from this process
+        let coordinator = self.databases[0]
+            .begin_write()
+            .map_err(|err| PrepareError::Storage(err.into_storage_error()))?;
+        let decided = coordinator.list_coordinated_commits()?;
+        for database in &self.databases[1..] {
+            let transaction = database
+                .begin_write()
+                .map_err(|err| PrepareError::Storage(err.into_storage_error()))?;
+            let prepared = transaction.list_coordinated_prepared()?;
+            drop(transaction);
+            for (id, commit_id) in prepared {
+                let mut transaction = database
+                    .begin_write()
+                    .map_err(|err| PrepareError::Storage(err.into_storage_error()))?;
+                if decided.contains(&commit_id) {
+                    transaction.commit_prepared(id)?;
+                } else {
+                    transaction.rollback_prepared(id)?;
+                }
+                transaction.commit()?;
+            }
+        }
+        if decided.is_empty() {
+            coordinator.abort()?;
+        } else {
+            coordinator.remove_coordinated_commits(&decided)?;
+            coordinator.commit()?;
+        }
+
+        Ok(())
+    }
+
+    fn rollback(prepared: &[(&Database, u64)]) -> Result<(), PrepareError> {
+        for (database, id) in prepared {
+            let mut transaction = database
+                .begin_write()
+                .map_err(|err| PrepareError::Storage(err.into_storage_error()))?;
+            transaction.rollback_prepared(*id)?;
+            transaction.commit()?;
+        }
+        Ok(())
+    }
+}
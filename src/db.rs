@@ -1,32 +1,43 @@
-use crate::transaction_tracker::{TransactionId, TransactionTracker};
+use crate::transaction_tracker::{ActiveTransaction, ReadLease, TransactionId, TransactionTracker};
 use crate::tree_store::{
     AllocationPolicy, BtreeHeader, InternalTableDefinition, PAGE_SIZE, PageHint, PageNumber,
-    PageResolver, ReadOnlyBackend, ShrinkPolicy, TableTree, TableType, TransactionalMemory,
+    PageResolver, ReadOnlyBackend, ReaderTable, ShrinkPolicy, TableTree, TableType,
+    TransactionalMemory, apply_backup, write_backup,
 };
 use crate::types::{Key, Value};
+use crate::watch::WatchRegistry;
 use crate::{
-    CompactionError, DatabaseError, Error, ReadOnlyTable, SavepointError, StorageError, TableError,
+    BackupError, CommitError, CompactionError, DatabaseError, Durability, ExportError,
+    ReadOnlyTable, Savepoint, SavepointError, SchemaChange, StorageError, TableError,
 };
-use crate::{ReadTransaction, Result, WriteTransaction};
+use crate::{ChangeRecord, ReadTransaction, Result, Watch, WritePriority, WriteTransaction};
 use std::fmt::{Debug, Display, Formatter};
 
+use std::borrow::Borrow;
+use std::collections::{HashMap, HashSet};
 use std::fs::{File, OpenOptions};
+use std::io::{BufReader, BufWriter, Read, Write};
 use std::marker::PhantomData;
+use std::ops::RangeBounds;
 use std::path::Path;
-use std::sync::Arc;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
 use std::{io, thread};
 
 use crate::error::TransactionError;
+use crate::group_commit::GroupCommit;
 use crate::sealed::Sealed;
 use crate::transactions::{
-    ALLOCATOR_STATE_TABLE_NAME, AllocatorStateKey, AllocatorStateTree, DATA_ALLOCATED_TABLE,
-    DATA_FREED_TABLE, PageList, SYSTEM_FREED_TABLE, SystemTableDefinition,
-    TransactionIdWithPagination,
+    ALLOCATOR_STATE_TABLE_NAME, AllocatorStateKey, AllocatorStateTree, CommitHook,
+    CompletedTransaction, DATA_ALLOCATED_TABLE, DATA_FREED_TABLE, PREPARED_ALLOCATED_TABLE,
+    PageList, SYSTEM_FREED_TABLE, SystemTableDefinition, TransactionIdWithPagination,
 };
 use crate::tree_store::file_backend::FileBackend;
 #[cfg(feature = "logging")]
 use log::{debug, info, warn};
 
+type LongReadHook = dyn Fn(&ActiveTransaction) + Send + Sync;
+
 #[allow(clippy::len_without_is_empty)]
 /// Implements persistent storage for a database.
 pub trait StorageBackend: 'static + Debug + Send + Sync {
@@ -56,6 +67,17 @@ pub trait StorageBackend: 'static + Debug + Send + Sync {
     fn close(&self) -> std::result::Result<(), io::Error> {
         Ok(())
     }
+
+    /// Hints that the specified range of the storage no longer holds any data, so the space that
+    /// it occupies may be released. The length of the storage must not change.
+    ///
+    /// Subsequent reads of the range may return either zeros or the previous contents. Used by
+    /// [`Builder::set_page_compression`] to release the space saved by compressing a page. The
+    /// default implementation does nothing
+    fn discard(&self, offset: u64, len: u64) -> std::result::Result<(), io::Error> {
+        let _ = (offset, len);
+        Ok(())
+    }
 }
 
 pub trait TableHandle: Sealed {
@@ -174,10 +196,24 @@ impl<K: Key + 'static, V: Value + 'static> Display for TableDefinition<'_, K, V>
 /// that is stored or retreived from the table
 pub struct MultimapTableDefinition<'a, K: Key + 'static, V: Key + 'static> {
     name: &'a str,
+    max_values_per_key: Option<(u64, ValueLimitPolicy)>,
     _key_type: PhantomData<K>,
     _value_type: PhantomData<V>,
 }
 
+/// What happens when a value is inserted under a key of a multimap table which already has the
+/// maximum number of values allowed by [`MultimapTableDefinition::with_max_values_per_key`]
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum ValueLimitPolicy {
+    /// The insert fails with [`crate::ValueLimitError::ValueLimitExceeded`]
+    Reject,
+    /// The smallest value of the key is removed
+    EvictSmallest,
+    /// The largest value of the key is removed
+    EvictLargest,
+}
+
 impl<'a, K: Key + 'static, V: Key + 'static> MultimapTableDefinition<'a, K, V> {
     /// Construct a new multimap table with given `name`
     ///
@@ -190,10 +226,31 @@ impl<'a, K: Key + 'static, V: Key + 'static> MultimapTableDefinition<'a, K, V> {
         assert!(!name.is_empty());
         Self {
             name,
+            max_values_per_key: None,
             _key_type: PhantomData,
             _value_type: PhantomData,
         }
     }
+
+    /// Limit the number of values stored under each key to `max`
+    ///
+    /// Inserting a new value under a key which already has `max` values is handled according to
+    /// `policy`. When a value is evicted, it may be the value which was just inserted. The limit is
+    /// not stored in the database, and only applies to tables opened with this definition.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `max` is zero
+    #[must_use]
+    pub const fn with_max_values_per_key(mut self, max: u64, policy: ValueLimitPolicy) -> Self {
+        assert!(max > 0);
+        self.max_values_per_key = Some((max, policy));
+        self
+    }
+
+    pub(crate) fn max_values_per_key(&self) -> Option<(u64, ValueLimitPolicy)> {
+        self.max_values_per_key
+    }
 }
 
 impl<K: Key + 'static, V: Key + 'static> MultimapTableHandle for MultimapTableDefinition<'_, K, V> {
@@ -271,10 +328,156 @@ impl CacheStats {
     }
 }
 
+/// Result of [`verify_file`]
+#[derive(Debug)]
+pub struct VerifyReport {
+    pub(crate) recovery_required: bool,
+    // Primary, then secondary
+    pub(crate) slots_valid: [bool; 2],
+    pub(crate) rolled_back: bool,
+    pub(crate) checksums_valid: bool,
+    pub(crate) allocator_state_valid: Option<bool>,
+}
+
+impl VerifyReport {
+    /// Returns `true` if no corruption was found. The file may still require recovery, which will
+    /// be performed the next time it is opened as a [`Database`]
+    pub fn is_valid(&self) -> bool {
+        self.checksums_valid && self.allocator_state_valid != Some(false)
+    }
+
+    /// Whether the file was not shutdown cleanly, and will be repaired when it is next opened
+    pub fn recovery_required(&self) -> bool {
+        self.recovery_required
+    }
+
+    /// Whether the checksum of the primary commit slot in the header is valid
+    pub fn primary_slot_valid(&self) -> bool {
+        self.slots_valid[0]
+    }
+
+    /// Whether the checksum of the secondary commit slot in the header is valid
+    pub fn secondary_slot_valid(&self) -> bool {
+        self.slots_valid[1]
+    }
+
+    /// Whether the most recent commit is incomplete, so that opening the file will roll it back to
+    /// the previous commit
+    pub fn rolled_back(&self) -> bool {
+        self.rolled_back
+    }
+
+    /// Whether the checksums of all pages reachable from the commit that will be used when the
+    /// file is opened are valid
+    pub fn checksums_valid(&self) -> bool {
+        self.checksums_valid
+    }
+
+    /// Whether every page in use is marked as allocated in the stored allocator state
+    ///
+    /// Returns `None` if the file does not contain a valid allocator state (for example, because
+    /// it was not shutdown cleanly), or if the checksums were not valid
+    pub fn allocator_state_valid(&self) -> Option<bool> {
+        self.allocator_state_valid
+    }
+}
+
+/// Checks the integrity of the database file at `path`, without modifying it
+///
+/// The header, the checksums of every page in use, and the stored allocator state are checked. The
+/// file is opened read-only and is not locked, so this may be used on read-only media, or on a
+/// backup copy while the original database is still open. No repair is performed: if the file
+/// requires recovery, this is reported in the returned [`VerifyReport`].
+///
+/// Returns an error if the file cannot be read, is not a redb database, or is so badly corrupted
+/// that no commit can be recovered from it.
+pub fn verify_file(path: impl AsRef<Path>) -> Result<VerifyReport, DatabaseError> {
+    let file = OpenOptions::new().read(true).open(path)?;
+    let backend = ReadOnlyBackend::new(Box::new(FileBackend::new_unlocked(file)));
+    let (mem, header) =
+        TransactionalMemory::new_for_verification(Box::new(backend), VERIFY_CACHE_SIZE)?;
+    let mem = Arc::new(mem);
+
+    let mut report = VerifyReport {
+        recovery_required: header.recovery_required,
+        slots_valid: [!header.primary_corrupted, !header.secondary_corrupted],
+        rolled_back: false,
+        checksums_valid: false,
+        allocator_state_valid: None,
+    };
+
+    report.checksums_valid = verify_checksums(&mem)?;
+    // Mirror the repair process: without 2-phase commit, a corrupted primary is rolled back
+    if !report.checksums_valid && !mem.used_two_phase_commit() {
+        report.rolled_back = true;
+        mem.repair_primary_corrupted();
+        mem.clear_read_cache();
+        report.checksums_valid = verify_checksums(&mem)?;
+    }
+    if !report.checksums_valid {
+        return Ok(report);
+    }
+
+    if let Some(tree) = Database::get_allocator_state_table(&mem)? {
+        mem.load_allocator_state(&tree)?;
+        report.allocator_state_valid = Some(verify_allocator_state(&mem)?);
+    }
+
+    Ok(report)
+}
+
+// Cache used while verifying a file. Every page is read only once, so it does not need to be large
+const VERIFY_CACHE_SIZE: usize = 16 * 1024 * 1024;
+
+fn verify_checksums(mem: &Arc<TransactionalMemory>) -> Result<bool> {
+    match Database::verify_primary_checksums(mem.clone()) {
+        Ok(valid) => Ok(valid),
+        Err(StorageError::Corrupted(_)) => Ok(false),
+        Err(err) => Err(err),
+    }
+}
+
+// Check that every page reachable from the primary is marked as allocated
+fn verify_allocator_state(mem: &Arc<TransactionalMemory>) -> Result<bool> {
+    let mut valid = true;
+    for root in [mem.get_data_root(), mem.get_system_root()] {
+        let tree = TableTree::new(
+            root,
+            PageHint::None,
+            Arc::new(TransactionGuard::untracked()),
+            PageResolver::new(mem.clone()),
+        )?;
+        tree.visit_all_pages(|path| {
+            valid &= mem.is_page_allocated(path.page_number());
+            Ok(())
+        })?;
+    }
+    let system_root = mem.get_system_root();
+    Database::visit_freed_tree(system_root, DATA_FREED_TABLE, mem.clone(), |page| {
+        valid &= mem.is_page_allocated(page);
+        Ok(())
+    })?;
+    Database::visit_freed_tree(system_root, SYSTEM_FREED_TABLE, mem.clone(), |page| {
+        valid &= mem.is_page_allocated(page);
+        Ok(())
+    })?;
+    // Pages of prepared transactions are not reachable from either root
+    Database::visit_freed_tree(system_root, PREPARED_ALLOCATED_TABLE, mem.clone(), |page| {
+        valid &= mem.is_page_allocated(page);
+        Ok(())
+    })?;
+
+    Ok(valid)
+}
+
 pub(crate) enum TransactionGuard {
     Read {
         tracker: Arc<TransactionTracker>,
         transaction_id: TransactionId,
+        handle: u64,
+        lease: Arc<ReadLease>,
+        // Set for reads of a multi-process database, whose reader slot is updated when they end
+        multi_process: Option<Arc<TransactionalMemory>>,
     },
     Write {
         tracker: Arc<TransactionTracker>,
@@ -290,10 +493,27 @@ impl TransactionGuard {
         transaction_id: TransactionId,
         tracker: Arc<TransactionTracker>,
     ) -> Self {
+        let (handle, lease) = tracker.begin_read_transaction(transaction_id);
         Self::Read {
             tracker,
             transaction_id,
+            handle,
+            lease,
+            multi_process: None,
+        }
+    }
+
+    // Same as new_read(), but republishes the oldest read of this process when the read ends
+    fn new_multi_process_read(
+        transaction_id: TransactionId,
+        tracker: Arc<TransactionTracker>,
+        mem: Arc<TransactionalMemory>,
+    ) -> Self {
+        let mut guard = Self::new_read(transaction_id, tracker);
+        if let Self::Read { multi_process, .. } = &mut guard {
+            *multi_process = Some(mem);
         }
+        guard
     }
 
     pub(crate) fn allocate_read(
@@ -328,6 +548,36 @@ impl TransactionGuard {
             }
         }
     }
+
+    // Same as id(), but returns None for an untracked guard
+    pub(crate) fn tracked_id(&self) -> Option<TransactionId> {
+        match self {
+            Self::Read { transaction_id, .. } | Self::Write { transaction_id, .. } => {
+                Some(*transaction_id)
+            }
+            Self::Untracked => None,
+        }
+    }
+
+    pub(crate) fn lease(&self) -> Option<Arc<ReadLease>> {
+        match self {
+            Self::Read { lease, .. } => Some(lease.clone()),
+            Self::Write { .. } | Self::Untracked => None,
+        }
+    }
+
+    pub(crate) fn set_label(&self, label: String) {
+        match self {
+            Self::Read {
+                tracker, handle, ..
+            } => tracker.set_read_label(*handle, label),
+            Self::Write {
+                tracker,
+                transaction_id,
+            } => tracker.set_write_label(*transaction_id, label),
+            Self::Untracked => {}
+        }
+    }
 }
 
 impl Drop for TransactionGuard {
@@ -335,8 +585,16 @@ impl Drop for TransactionGuard {
         match self {
             Self::Read {
                 tracker,
-                transaction_id,
-            } => tracker.deallocate_read_transaction(*transaction_id),
+                handle,
+                multi_process,
+                ..
+            } => {
+                tracker.end_read_transaction(*handle);
+                if let Some(mem) = multi_process {
+                    // Errors are ignored, since the slot only keeps pages from being reused
+                    let _ = mem.publish_oldest_reader(|| tracker.oldest_live_read_transaction());
+                }
+            }
             Self::Write {
                 tracker,
                 transaction_id,
@@ -405,10 +663,15 @@ pub trait ReadableDatabase {
 pub struct ReadOnlyDatabase {
     mem: Arc<TransactionalMemory>,
     transaction_tracker: Arc<TransactionTracker>,
+    // Serializes loading the latest commit from the file, in multi-process mode
+    refresh_lock: Mutex<()>,
 }
 
 impl ReadableDatabase for ReadOnlyDatabase {
     fn begin_read(&self) -> Result<ReadTransaction, TransactionError> {
+        if self.mem.is_multi_process() {
+            return self.begin_read_multi_process();
+        }
         let id = self
             .transaction_tracker
             .register_read_transaction(&self.mem)?;
@@ -431,17 +694,71 @@ impl ReadOnlyDatabase {
         Builder::new().open_read_only(path)
     }
 
+    // Begins a read of the latest commit made by the writing process
+    fn begin_read_multi_process(&self) -> Result<ReadTransaction, TransactionError> {
+        let _refresh = self.refresh_lock.lock().unwrap();
+        loop {
+            self.mem.reload_header()?;
+            let id = self
+                .transaction_tracker
+                .register_read_transaction(&self.mem)?;
+            // The writer may have read the slot before it was updated, so the commit must be
+            // checked again after publishing this one
+            self.mem.publish_oldest_reader(|| {
+                self.transaction_tracker.oldest_live_read_transaction()
+            })?;
+            if self.mem.durable_transaction_id_in_file()? == id {
+                #[cfg(feature = "logging")]
+                debug!("Beginning read transaction id={id:?}");
+                let guard = TransactionGuard::new_multi_process_read(
+                    id,
+                    self.transaction_tracker.clone(),
+                    self.mem.clone(),
+                );
+                return ReadTransaction::new(self.mem.clone(), guard);
+            }
+            self.transaction_tracker.deallocate_read_transaction(id);
+        }
+    }
+
+    fn new_multi_process(
+        file: Box<dyn StorageBackend>,
+        page_size: usize,
+        cache_size: usize,
+        corruption_policy: CorruptionPolicy,
+        reader_table: ReaderTable,
+    ) -> Result<Self, DatabaseError> {
+        #[cfg(feature = "logging")]
+        info!("Opening database in multi-process read-only {:?}", &file);
+        let mut mem = TransactionalMemory::new_multi_process_reader(
+            Box::new(ReadOnlyBackend::new(file)),
+            page_size,
+            cache_size,
+            reader_table,
+        )?;
+        mem.set_corruption_policy(corruption_policy);
+        // Pages are never allocated, so the allocator state is not needed
+        let next_transaction_id = mem.get_last_committed_transaction_id()?.next();
+
+        Ok(Self {
+            mem: Arc::new(mem),
+            transaction_tracker: Arc::new(TransactionTracker::new(next_transaction_id)),
+            refresh_lock: Mutex::new(()),
+        })
+    }
+
     fn new(
         file: Box<dyn StorageBackend>,
         page_size: usize,
         region_size: Option<u64>,
         cache_size: usize,
+        corruption_policy: CorruptionPolicy,
     ) -> Result<Self, DatabaseError> {
         #[cfg(feature = "logging")]
         let file_path = format!("{:?}", &file);
         #[cfg(feature = "logging")]
         info!("Opening database in read-only {:?}", &file_path);
-        let mem = TransactionalMemory::new(
+        let mut mem = TransactionalMemory::new(
             Box::new(ReadOnlyBackend::new(file)),
             false,
             page_size,
@@ -449,6 +766,7 @@ impl ReadOnlyDatabase {
             cache_size,
             true,
         )?;
+        mem.set_corruption_policy(corruption_policy);
         let mem = Arc::new(mem);
         // If the last transaction used 2-phase commit and updated the allocator state table, then
         // we can just load the allocator state from there. Otherwise, we need a full repair
@@ -457,16 +775,19 @@ impl ReadOnlyDatabase {
         } else {
             #[cfg(feature = "logging")]
             warn!(
+                target: crate::log_targets::REPAIR,
+                event = "repair_required";
                 "Database {:?} not shutdown cleanly. Repair required",
                 &file_path
             );
-            return Err(DatabaseError::RepairAborted);
+            return Err(DatabaseError::RecoveryRequired);
         }
 
         let next_transaction_id = mem.get_last_committed_transaction_id()?.next();
         let db = Self {
             mem,
             transaction_tracker: Arc::new(TransactionTracker::new(next_transaction_id)),
+            refresh_lock: Mutex::new(()),
         };
 
         Ok(db)
@@ -509,6 +830,18 @@ impl ReadOnlyDatabase {
 pub struct Database {
     mem: Arc<TransactionalMemory>,
     transaction_tracker: Arc<TransactionTracker>,
+    auto_checkpoint_size: Option<u64>,
+    change_capture: bool,
+    schema_history: bool,
+    read_transaction_max_age: Option<Duration>,
+    max_persistent_savepoints: Option<usize>,
+    persistent_savepoint_max_age: Option<Duration>,
+    group_commit: Option<Arc<GroupCommit>>,
+    commit_hook: Mutex<Option<Arc<CommitHook>>>,
+    long_read_hook: Mutex<Option<(Duration, Arc<LongReadHook>)>>,
+    watch_registry: Arc<WatchRegistry>,
+    // Set once the file has been closed, so that it isn't written again when this is dropped
+    closed: bool,
 }
 
 impl ReadableDatabase for Database {
@@ -538,6 +871,17 @@ impl Database {
         Self::builder().open(path)
     }
 
+    /// Opens an existing redb database in read-only mode.
+    ///
+    /// Nothing is ever written to the file, so it may be on read-only media. If the file was not
+    /// shut down cleanly [`DatabaseError::RecoveryRequired`] is returned, since it cannot be
+    /// repaired without writing to it.
+    ///
+    /// This is the same as [`ReadOnlyDatabase::open`]
+    pub fn open_read_only(path: impl AsRef<Path>) -> Result<ReadOnlyDatabase, DatabaseError> {
+        ReadOnlyDatabase::open(path)
+    }
+
     pub(crate) fn get_memory(&self) -> Arc<TransactionalMemory> {
         self.mem.clone()
     }
@@ -607,15 +951,111 @@ impl Database {
         }
 
         self.mem.begin_writable()?;
+        self.mem.reset_access_tracking();
 
         Ok(was_clean)
     }
 
+    /// Verifies the checksum of every page in every table, and quarantines the regions of the file
+    /// which contain a corrupted page
+    ///
+    /// Reading any page in a quarantined region fails immediately with [`StorageError::Corrupted`],
+    /// which reports the page and its region, and no new pages are allocated in it. Tables with no
+    /// pages in a quarantined region remain fully usable. Unlike [`Database::check_integrity`],
+    /// this does not attempt a repair and does not require exclusive access to the database.
+    ///
+    /// Newly quarantined regions are recorded in the database with a write transaction, and are
+    /// quarantined again when it is reopened, so that the corrupted pages are not read.
+    ///
+    /// Returns the regions which were newly quarantined
+    pub fn quarantine_corrupted_regions(&self) -> Result<Vec<u32>, TransactionError> {
+        let txn = self.begin_read()?;
+        let corrupted = txn
+            .find_corrupted_pages()
+            .map_err(TransactionError::Storage)?;
+        drop(txn);
+        let mut regions = vec![];
+        for page in corrupted {
+            if self.mem.quarantine_region(page.region) {
+                regions.push(page.region);
+            }
+        }
+        regions.sort_unstable();
+        if !regions.is_empty() {
+            let txn = self.begin_write()?;
+            txn.record_quarantined_regions(&regions)?;
+            txn.commit()
+                .map_err(|e| TransactionError::Storage(e.into_storage_error()))?;
+        }
+        Ok(regions)
+    }
+
+    /// Returns the regions which are currently quarantined
+    ///
+    /// See [`Database::quarantine_corrupted_regions`]
+    pub fn quarantined_regions(&self) -> Vec<u32> {
+        self.mem.quarantined_regions()
+    }
+
+    /// Lifts the quarantine of every region, for example after the file has been repaired
+    pub fn clear_quarantine(&self) -> Result<(), TransactionError> {
+        let txn = self.begin_write()?;
+        txn.clear_quarantined_regions()?;
+        txn.commit()
+            .map_err(|e| TransactionError::Storage(e.into_storage_error()))?;
+        self.mem.clear_quarantine();
+        Ok(())
+    }
+
+    /// Makes all data written by commits with [`Durability::None`] durable
+    ///
+    /// This performs an empty commit with [`Durability::Immediate`]. Afterwards, no committed data
+    /// can be lost in a crash, and the memory and file space held by the non-durable commits can be
+    /// reclaimed. See [`Builder::set_auto_checkpoint_size`] to checkpoint automatically.
+    ///
+    /// If a write is in progress, this function will block until it completes.
+    pub fn checkpoint(&self) -> Result<(), CommitError> {
+        let mut txn = self.begin_write().map_err(|e| e.into_storage_error())?;
+        txn.set_durability(Durability::Immediate)
+            .expect("Durability can always be increased");
+        txn.commit()
+    }
+
+    /// Returns every table creation, deletion, and rename that has been committed, oldest first
+    ///
+    /// Changes are only recorded if [`Builder::set_schema_history`] is enabled. They are recorded by
+    /// the transaction which makes them, so changes made by an aborted transaction are not
+    /// included. Tables recreated by restoring a savepoint are not recorded.
+    pub fn schema_history(&self) -> Result<Vec<SchemaChange>, TransactionError> {
+        let txn = self.begin_read()?;
+        txn.schema_history().map_err(TransactionError::Storage)
+    }
+
+    /// Returns the changes recorded by the transactions whose ids are in `transactions`, oldest
+    /// first
+    ///
+    /// Changes are only recorded if [`Builder::set_change_capture`] is enabled. Within a transaction,
+    /// changes are ordered by table name.
+    pub fn change_log(
+        &self,
+        transactions: impl RangeBounds<u64>,
+    ) -> Result<Vec<ChangeRecord>, TransactionError> {
+        let txn = self.begin_read()?;
+        txn.change_log(transactions)
+            .map_err(TransactionError::Storage)
+    }
+
     /// Compacts the database file
     ///
+    /// If access tracking is enabled with [`Builder::set_access_tracking`], pages that have been
+    /// read since the database was opened, or since the last compaction, are clustered near the
+    /// start of the file, and the remaining cold pages are placed after them.
+    ///
     /// Returns `true` if compaction was performed, and `false` if no futher compaction was possible
     pub fn compact(&mut self) -> Result<bool, CompactionError> {
-        if self.transaction_tracker.any_user_read_reference_exists() {
+        if self.transaction_tracker.any_user_read_reference_exists()
+            || self.mem.oldest_external_reader()?.is_some()
+        {
             return Err(CompactionError::TransactionInProgress);
         }
         // Commit to free up any pending free pages
@@ -629,13 +1069,35 @@ impl Database {
         if self.transaction_tracker.any_savepoint_exists() {
             return Err(CompactionError::EphemeralSavepointExists);
         }
+        // Compaction rewrites the tree that a prepared transaction was based on
+        if txn.list_prepared_transactions()?.next().is_some() {
+            return Err(CompactionError::TransactionInProgress);
+        }
         txn.abort()?;
+
+        #[cfg(feature = "logging")]
+        info!(
+            target: crate::log_targets::COMPACTION,
+            event = "compaction_start";
+            "Compacting database"
+        );
+        // Compaction reads every page, which must not count as an access
+        self.mem.pause_access_tracking();
+        let result = self.compact_and_cluster();
+        self.mem.reset_access_tracking();
+
+        result
+    }
+
+    fn compact_and_cluster(&mut self) -> Result<bool, CompactionError> {
         self.drain_pending_free_pages(ShrinkPolicy::Maximum)?;
 
         let mut compacted = false;
+        let mut batches = 0u64;
         // Iteratively compact until no progress is made
         loop {
             let mut progress = false;
+            batches += 1;
 
             let mut txn = self.begin_write().map_err(|e| e.into_storage_error())?;
             if txn.compact_pages()? {
@@ -648,6 +1110,14 @@ impl Database {
             // Drain pages freed by compact_pages(), including system pages queued by any
             // post-commit cleanup root updates.
             self.drain_pending_free_pages(ShrinkPolicy::Maximum)?;
+            #[cfg(feature = "logging")]
+            debug!(
+                target: crate::log_targets::COMPACTION,
+                event = "compaction_batch",
+                batch = batches,
+                progress = progress;
+                "Compaction batch finished"
+            );
 
             if !progress {
                 break;
@@ -656,9 +1126,343 @@ impl Database {
             compacted = true;
         }
 
+        if self.cluster_hot_pages()? {
+            compacted = true;
+        }
+        #[cfg(feature = "logging")]
+        info!(
+            target: crate::log_targets::COMPACTION,
+            event = "compaction_end",
+            batches = batches,
+            compacted = compacted;
+            "Compaction finished"
+        );
+        #[cfg(not(feature = "logging"))]
+        let _ = batches;
+
         Ok(compacted)
     }
 
+    // Moves recently accessed pages to the start of the file, which must already be compacted.
+    // The file grows by at most the size of the hot pages while this runs.
+    //
+    // Returns true if any pages were moved
+    fn cluster_hot_pages(&self) -> Result<bool> {
+        if !self.mem.access_tracking_enabled() {
+            return Ok(false);
+        }
+        let txn = self.begin_write().map_err(|e| e.into_storage_error())?;
+        let pages: Vec<PageNumber> = txn
+            .page_paths()?
+            .iter()
+            .map(|path| path.page_number())
+            .collect();
+        txn.abort()?;
+
+        let mut hot: Vec<PageNumber> = pages
+            .iter()
+            .copied()
+            .filter(|page| self.mem.page_recency(*page) > 0)
+            .collect();
+        if hot.is_empty() || hot.len() == pages.len() {
+            return Ok(false);
+        }
+        // Most recently accessed first. The sort is stable, so ties remain in tree order
+        hot.sort_by_key(|page| std::cmp::Reverse(self.mem.page_recency(*page)));
+        let hot_set: HashSet<PageNumber> = hot.iter().copied().collect();
+
+        // The hot pages should occupy the lowest addresses, up to their total size
+        let hot_size: u64 = hot.iter().map(|page| 1u64 << page.page_order).sum();
+        let mut by_address = pages;
+        by_address.sort();
+        let mut prefix_size = 0;
+        let mut prefix = HashSet::new();
+        let mut cold_in_prefix = vec![];
+        for page in &by_address {
+            if prefix_size >= hot_size {
+                break;
+            }
+            prefix_size += 1u64 << page.page_order;
+            prefix.insert(*page);
+            if !hot_set.contains(page) {
+                cold_in_prefix.push(*page);
+            }
+        }
+        if cold_in_prefix.is_empty() {
+            return Ok(false);
+        }
+        let mut hot_outside: Vec<PageNumber> = hot
+            .into_iter()
+            .filter(|page| !prefix.contains(page))
+            .collect();
+
+        // Move the displaced hot pages out of the way, then the cold pages out of the prefix into
+        // the space that was just freed, and finally the hot pages into the prefix
+        #[cfg(feature = "logging")]
+        debug!(
+            target: crate::log_targets::COMPACTION,
+            event = "compaction_cluster",
+            hot_pages = hot_set.len(),
+            moved_pages = hot_outside.len() + cold_in_prefix.len();
+            "Clustering recently accessed pages"
+        );
+        let relocations = self.relocate_and_drain(&hot_outside, ShrinkPolicy::Never)?;
+        remap_pages(&mut hot_outside, &relocations);
+        remap_pages(&mut cold_in_prefix, &relocations);
+        let relocations = self.relocate_and_drain(&cold_in_prefix, ShrinkPolicy::Never)?;
+        remap_pages(&mut hot_outside, &relocations);
+        self.relocate_and_drain(&hot_outside, ShrinkPolicy::Maximum)?;
+
+        Ok(true)
+    }
+
+    fn relocate_and_drain(
+        &self,
+        pages: &[PageNumber],
+        shrink_policy: ShrinkPolicy,
+    ) -> Result<HashMap<PageNumber, PageNumber>> {
+        let mut txn = self.begin_write().map_err(|e| e.into_storage_error())?;
+        let relocations = txn.relocate_pages(pages)?;
+        txn.commit().map_err(|e| e.into_storage_error())?;
+        self.drain_pending_free_pages(shrink_policy)?;
+
+        Ok(relocations)
+    }
+
+    /// Exports the state of the database at `savepoint` to a new snapshot file at `path`
+    ///
+    /// The pages reachable from the savepoint are copied into the new file, which is then
+    /// compacted and sealed (see [`Database::seal`]), so that it can be opened with
+    /// [`ReadOnlyDatabase`]. Other transactions are not blocked while the snapshot is written.
+    ///
+    /// Returns an error if a file already exists at `path`
+    pub fn export_savepoint(
+        &self,
+        savepoint: &Savepoint,
+        path: impl AsRef<Path>,
+    ) -> Result<(), ExportError> {
+        if !self.is_valid_savepoint(savepoint) {
+            return Err(ExportError::InvalidSavepoint);
+        }
+
+        let path = path.as_ref();
+        let file = OpenOptions::new()
+            .read(true)
+            .write(true)
+            .create_new(true)
+            .open(path)
+            .map_err(DatabaseError::from)?;
+        let result = self.export_savepoint_to(savepoint, file, path);
+        if result.is_err() {
+            // Don't leave behind a partial snapshot
+            let _ = std::fs::remove_file(path);
+        }
+        result
+    }
+
+    fn export_savepoint_to(
+        &self,
+        savepoint: &Savepoint,
+        file: File,
+        path: &Path,
+    ) -> Result<(), ExportError> {
+        // Pages are copied as-is, so the snapshot must use the same page size
+        let mut builder = Self::builder();
+        builder.page_size = self.mem.get_page_size();
+        let snapshot = builder.create_file(file)?;
+        let txn = snapshot.begin_write().map_err(|e| e.into_storage_error())?;
+        txn.import_tables(
+            savepoint.get_user_root(),
+            &PageResolver::new(self.mem.clone()),
+        )?;
+        txn.commit().map_err(|e| e.into_storage_error())?;
+        drop(snapshot);
+
+        Self::seal(path)?;
+        Ok(())
+    }
+
+    /// Writes a backup of the state of the database at `savepoint` to `writer`
+    ///
+    /// The backup contains every page of the database's tables, and can be restored with
+    /// [`Database::apply_backup`]. Other transactions are not blocked while the backup is written.
+    pub fn backup(&self, savepoint: &Savepoint, writer: impl Write) -> Result<(), BackupError> {
+        self.backup_inner(None, savepoint, writer)
+    }
+
+    /// Writes an incremental backup of the state of the database at `savepoint` to `writer`
+    ///
+    /// Only the pages which have changed since `since` are included, so the backup can only be
+    /// applied to a file which a backup of `since` was the last one applied to. Keeping `since`
+    /// alive until the next backup has been taken allows a chain of incremental backups to be
+    /// taken. `since` must be older than `savepoint`.
+    ///
+    /// Waits for any in-progress write transaction to complete, but otherwise other transactions
+    /// are not blocked while the backup is written.
+    pub fn backup_incremental(
+        &self,
+        since: &Savepoint,
+        savepoint: &Savepoint,
+        writer: impl Write,
+    ) -> Result<(), BackupError> {
+        self.backup_inner(Some(since), savepoint, writer)
+    }
+
+    fn backup_inner(
+        &self,
+        since: Option<&Savepoint>,
+        savepoint: &Savepoint,
+        writer: impl Write,
+    ) -> Result<(), BackupError> {
+        if !self.is_valid_savepoint(savepoint) {
+            return Err(BackupError::InvalidSavepoint);
+        }
+        // Pages reachable from `savepoint` which were not allocated after `since` are unchanged
+        // since `since`, because `since` prevents them from being freed and reused
+        let changed_pages = if let Some(since) = since {
+            if !self.is_valid_savepoint(since)
+                || since.get_transaction_id() >= savepoint.get_transaction_id()
+            {
+                return Err(BackupError::InvalidSavepoint);
+            }
+            let txn = self.begin_write().map_err(|e| e.into_storage_error())?;
+            let pages = txn.data_pages_allocated_after(since.get_transaction_id())?;
+            txn.abort()?;
+            Some(pages)
+        } else {
+            None
+        };
+
+        let mut pages = vec![];
+        let tables = TableTree::new(
+            savepoint.get_user_root(),
+            PageHint::None,
+            Arc::new(TransactionGuard::untracked()),
+            PageResolver::new(self.mem.clone()),
+        )?;
+        tables.visit_pages_while(|path| {
+            let page = path.page_number();
+            if changed_pages
+                .as_ref()
+                .is_none_or(|changed| changed.contains(&page))
+            {
+                pages.push(page);
+                Ok(true)
+            } else {
+                Ok(false)
+            }
+        })?;
+
+        write_backup(
+            &self.mem,
+            since.map(|x| x.get_transaction_id()),
+            savepoint.get_transaction_id(),
+            savepoint.get_user_root(),
+            &pages,
+            &mut BufWriter::new(writer),
+        )?;
+
+        Ok(())
+    }
+
+    /// Applies a backup written by [`Database::backup`] or [`Database::backup_incremental`] to the
+    /// file at `path`
+    ///
+    /// A full backup must be applied to a file which is empty or does not exist. An incremental
+    /// backup must be applied to a file that a backup of the savepoint it was taken relative to was
+    /// the last one applied to, and which has not been opened as a [`Database`] since. Otherwise,
+    /// [`BackupError::IncompatibleBackup`] is returned. Afterwards, the file can be opened as a
+    /// [`Database`] or [`ReadOnlyDatabase`]. If this is interrupted, the file is left in its
+    /// previous state.
+    pub fn apply_backup(path: impl AsRef<Path>, reader: impl Read) -> Result<(), BackupError> {
+        let file = OpenOptions::new()
+            .read(true)
+            .write(true)
+            .create(true)
+            .truncate(false)
+            .open(path)
+            .map_err(DatabaseError::from)?;
+        let backend = FileBackend::new(file)?;
+        apply_backup(&backend, &mut BufReader::new(reader))
+    }
+
+    // Returns false for a Savepoint that is from a different Database, or has been invalidated
+    fn is_valid_savepoint(&self, savepoint: &Savepoint) -> bool {
+        std::ptr::from_ref(self.transaction_tracker.as_ref()) == savepoint.db_address()
+            && self
+                .transaction_tracker
+                .is_valid_savepoint(savepoint.get_id())
+    }
+
+    /// Finalizes the database file at `path` as an immutable, read-optimized artifact
+    ///
+    /// All persistent savepoints are deleted, the file is compacted, and the pages of each table
+    /// are rewritten in key order so that range scans read the file sequentially. Afterwards the
+    /// file is marked as sealed: it can still be opened with [`ReadOnlyDatabase`], but opening it
+    /// as a [`Database`] returns [`DatabaseError::DatabaseSealed`].
+    ///
+    /// Returns [`DatabaseError::TransactionInProgress`], without modifying the file, if it has a
+    /// prepared transaction. It must be committed or rolled back first.
+    pub fn seal(path: impl AsRef<Path>) -> Result<(), DatabaseError> {
+        let mut db = Self::open(path)?;
+
+        let txn = db.begin_write().map_err(|e| e.into_storage_error())?;
+        // Compaction would rewrite the tree that a prepared transaction was based on
+        if txn.list_prepared_transactions()?.next().is_some() {
+            txn.abort()?;
+            return Err(DatabaseError::TransactionInProgress);
+        }
+        let savepoints: Vec<u64> = txn.list_persistent_savepoints()?.collect();
+        for id in savepoints {
+            txn.delete_persistent_savepoint(id).map_err(|e| match e {
+                SavepointError::Storage(storage) => storage,
+                SavepointError::Table(table) => {
+                    table.into_storage_error_or_corrupted("Failed to open savepoint table")
+                }
+                // The savepoint was just listed, and the transaction's durability is Immediate
+                err @ (SavepointError::InvalidSavepoint
+                | SavepointError::ImmediateDurabilityRequired) => StorageError::Corrupted(format!(
+                    "Failed to delete persistent savepoint {id}: {err}"
+                )),
+            })?;
+        }
+        txn.commit().map_err(|e| e.into_storage_error())?;
+
+        db.compact().map_err(|e| match e {
+            CompactionError::Storage(storage) => storage.into(),
+            CompactionError::TransactionInProgress => DatabaseError::TransactionInProgress,
+            // All persistent savepoints were deleted above, and no ephemeral ones can have been
+            // created, since the database is not shared
+            err @ (CompactionError::PersistentSavepointExists
+            | CompactionError::EphemeralSavepointExists) => {
+                StorageError::Corrupted(format!("Failed to compact the database: {err}")).into()
+            }
+        })?;
+
+        // The first pass moves every page past the end of the compacted data, and the second
+        // moves them back, in order, into the space that the first pass freed
+        for _ in 0..2 {
+            let mut txn = db.begin_write().map_err(|e| e.into_storage_error())?;
+            let pages: Vec<PageNumber> = txn
+                .page_paths()?
+                .iter()
+                .map(|path| path.page_number())
+                .collect();
+            txn.relocate_pages(&pages)?;
+            txn.commit().map_err(|e| e.into_storage_error())?;
+            db.drain_pending_free_pages(ShrinkPolicy::Maximum)?;
+        }
+
+        // Close the file here, rather than when the database is dropped, so that an error writing
+        // the sealed header is returned
+        db.mem.seal();
+        db.ensure_allocator_state_table_and_trim()?;
+        db.closed = true;
+        db.mem.close()?;
+
+        Ok(())
+    }
+
     fn drain_pending_free_pages(&self, shrink_policy: ShrinkPolicy) -> Result {
         // Preserve compact()'s empty durable commit, which also publishes pending
         // non-durable roots before checking for pending frees.
@@ -815,7 +1619,11 @@ impl Database {
             mem.mark_debug_allocated_page(page);
             Ok(())
         })?;
-
+        Self::visit_freed_tree(system_root, PREPARED_ALLOCATED_TABLE, mem.clone(), |page| {
+            mem.mark_debug_allocated_page(page);
+            Ok(())
+        })?;
+
         Ok(())
     }
 
@@ -837,6 +1645,12 @@ impl Database {
                 return Err(DatabaseError::RepairAborted);
             }
 
+            #[cfg(feature = "logging")]
+            warn!(
+                target: crate::log_targets::REPAIR,
+                event = "repair_rollback";
+                "Last commit is incomplete. Rolling back to the previous commit"
+            );
             mem.repair_primary_corrupted();
             // We need to invalidate the userspace cache, because walking the tree in verify_primary_checksums() may
             // have poisoned it with pages that just got rolled back by repair_primary_corrupted(), since
@@ -856,6 +1670,7 @@ impl Database {
         }
 
         mem.begin_repair()?;
+        let mut pages_scanned = 0u64;
 
         let data_root = mem.get_data_root();
         {
@@ -868,6 +1683,7 @@ impl Database {
             )?;
             tables.visit_all_pages(|path| {
                 mem.mark_page_allocated(path.page_number());
+                pages_scanned += 1;
                 Ok(())
             })?;
         }
@@ -890,16 +1706,25 @@ impl Database {
             )?;
             system_tables.visit_all_pages(|path| {
                 mem.mark_page_allocated(path.page_number());
+                pages_scanned += 1;
                 Ok(())
             })?;
         }
 
         Self::visit_freed_tree(system_root, DATA_FREED_TABLE, mem.clone(), |page| {
             mem.mark_page_allocated(page);
+            pages_scanned += 1;
             Ok(())
         })?;
         Self::visit_freed_tree(system_root, SYSTEM_FREED_TABLE, mem.clone(), |page| {
             mem.mark_page_allocated(page);
+            pages_scanned += 1;
+            Ok(())
+        })?;
+        // Pages of prepared transactions are not reachable from either root
+        Self::visit_freed_tree(system_root, PREPARED_ALLOCATED_TABLE, mem.clone(), |page| {
+            mem.mark_page_allocated(page);
+            pages_scanned += 1;
             Ok(())
         })?;
         #[cfg(debug_assertions)]
@@ -908,6 +1733,15 @@ impl Database {
         }
 
         mem.end_repair()?;
+        #[cfg(feature = "logging")]
+        info!(
+            target: crate::log_targets::REPAIR,
+            event = "repair_end",
+            pages_scanned = pages_scanned;
+            "Repair finished"
+        );
+        #[cfg(not(feature = "logging"))]
+        let _ = pages_scanned;
 
         // We need to invalidate the userspace cache, because we're about to implicitly free the freed table
         // by storing an empty root during the below commit()
@@ -916,19 +1750,31 @@ impl Database {
         Ok([data_root, system_root])
     }
 
+    #[allow(clippy::too_many_arguments, clippy::fn_params_excessive_bools)]
     fn new(
         file: Box<dyn StorageBackend>,
         allow_initialize: bool,
         page_size: usize,
         region_size: Option<u64>,
         cache_size: usize,
+        auto_checkpoint_size: Option<u64>,
+        change_capture: bool,
+        schema_history: bool,
+        read_transaction_max_age: Option<Duration>,
+        max_persistent_savepoints: Option<usize>,
+        persistent_savepoint_max_age: Option<Duration>,
+        group_commit_window: Option<Duration>,
+        corruption_policy: CorruptionPolicy,
+        page_compression: PageCompression,
+        access_tracking: bool,
+        reader_table: Option<ReaderTable>,
         repair_callback: &(dyn Fn(&mut RepairSession) + 'static),
     ) -> Result<Self, DatabaseError> {
         #[cfg(feature = "logging")]
         let file_path = format!("{:?}", &file);
         #[cfg(feature = "logging")]
         info!("Opening database {:?}", &file_path);
-        let mem = TransactionalMemory::new(
+        let mut mem = TransactionalMemory::new(
             file,
             allow_initialize,
             page_size,
@@ -936,6 +1782,12 @@ impl Database {
             cache_size,
             false,
         )?;
+        mem.set_corruption_policy(corruption_policy);
+        mem.set_page_compression(page_compression);
+        mem.set_access_tracking(access_tracking);
+        if let Some(reader_table) = reader_table {
+            mem.set_reader_table(reader_table);
+        }
         let mut mem = Arc::new(mem);
         // If the last transaction used 2-phase commit and updated the allocator state table, then
         // we can just load the allocator state from there. Otherwise, we need a full repair
@@ -947,12 +1799,24 @@ impl Database {
             Self::mark_allocated_page_for_debug(&mut mem)?;
         } else {
             #[cfg(feature = "logging")]
-            warn!("Database {:?} not shutdown cleanly. Repairing", &file_path);
+            warn!(
+                target: crate::log_targets::REPAIR,
+                event = "repair_start";
+                "Database {:?} not shutdown cleanly. Repairing",
+                &file_path
+            );
             let mut handle = RepairSession::new(0.0);
             repair_callback(&mut handle);
             if handle.aborted() {
                 return Err(DatabaseError::RepairAborted);
             }
+            // Repair only preserves the pages of the last commit, so it must not run while another
+            // process is reading an older one
+            if let Some(oldest) = mem.oldest_external_reader()?
+                && oldest < mem.get_last_committed_transaction_id()?
+            {
+                return Err(DatabaseError::RepairAborted);
+            }
             let [data_root, system_root] = Self::do_repair(&mut mem, repair_callback)?;
             let next_transaction_id = mem.get_last_committed_transaction_id()?.next();
             mem.commit(
@@ -966,10 +1830,25 @@ impl Database {
 
         mem.begin_writable()?;
         let next_transaction_id = mem.get_last_committed_transaction_id()?.next();
+        let last_durable_transaction_id = mem.get_last_durable_transaction_id()?;
+        // Repair and debug checks may have read every page
+        mem.reset_access_tracking();
 
         let db = Database {
             mem,
             transaction_tracker: Arc::new(TransactionTracker::new(next_transaction_id)),
+            auto_checkpoint_size,
+            change_capture,
+            schema_history,
+            read_transaction_max_age,
+            max_persistent_savepoints,
+            persistent_savepoint_max_age,
+            group_commit: group_commit_window
+                .map(|window| Arc::new(GroupCommit::new(window, last_durable_transaction_id))),
+            commit_hook: Mutex::new(None),
+            long_read_hook: Mutex::new(None),
+            watch_registry: Arc::default(),
+            closed: false,
         };
 
         // Restore the tracker state for any persistent savepoints
@@ -983,7 +1862,8 @@ impl Database {
                 Ok(savepoint) => savepoint,
                 Err(err) => match err {
                     SavepointError::InvalidSavepoint
-                    | SavepointError::ImmediateDurabilityRequired => unreachable!(),
+                    | SavepointError::ImmediateDurabilityRequired
+                    | SavepointError::Table(_) => unreachable!(),
                     SavepointError::Storage(storage) => {
                         return Err(storage.into());
                     }
@@ -992,6 +1872,9 @@ impl Database {
             db.transaction_tracker
                 .register_persistent_savepoint(&savepoint);
         }
+        for region in txn.list_quarantined_regions()? {
+            db.mem.quarantine_region(region);
+        }
         txn.abort()?;
 
         Ok(db)
@@ -1044,13 +1927,158 @@ impl Database {
         Builder::new()
     }
 
+    /// Lists the read and write transactions which have begun and not yet been dropped, ordered from
+    /// the oldest snapshot to the newest
+    ///
+    /// Pages freed after the snapshot of the oldest read transaction cannot be reused until it is
+    /// dropped, so a long lived read transaction can cause the database file to grow
+    pub fn active_transactions(&self) -> Result<Vec<ActiveTransaction>> {
+        let last_committed = self.mem.get_last_committed_transaction_id()?;
+        Ok(self.transaction_tracker.active_transactions(last_committed))
+    }
+
     /// Begins a write transaction
     ///
     /// Returns a [`WriteTransaction`] which may be used to read/write to the database. Only a single
     /// write may be in progress at a time. If a write is in progress, this function will block
     /// until it completes.
     pub fn begin_write(&self) -> Result<WriteTransaction, TransactionError> {
-        self.begin_write_with_allocation_policy(AllocationPolicy::Default)
+        self.begin_write_with_allocation_policy(AllocationPolicy::Default, WritePriority::Normal)
+    }
+
+    /// Begins a write transaction with the given priority
+    ///
+    /// Only one write transaction may be in progress at a time, so this blocks until the current
+    /// writer completes. If several threads are waiting, those with [`WritePriority::High`] begin
+    /// before those with [`WritePriority::Normal`]. A transaction which is already in progress is
+    /// never interrupted.
+    pub fn begin_write_with_priority(
+        &self,
+        priority: WritePriority,
+    ) -> Result<WriteTransaction, TransactionError> {
+        self.begin_write_with_allocation_policy(AllocationPolicy::Default, priority)
+    }
+
+    /// Set a hook to be invoked each time a write transaction on this database completes
+    ///
+    /// The hook is invoked after a transaction has been committed, or aborted, and is passed its id
+    /// and the names of the tables it modified, so that caches or subscribers can be notified. It is
+    /// not invoked if a commit fails with an error other than
+    /// [`CommitError::TransactionPoisoned`]. This replaces any previously set hook, and only applies
+    /// to transactions begun after it is set.
+    ///
+    /// The hook runs on the thread which completes the transaction, before the write lock is
+    /// released, so it must not begin another write transaction on this database.
+    pub fn set_commit_hook(&self, hook: impl Fn(&CompletedTransaction) + Send + Sync + 'static) {
+        *self.commit_hook.lock().unwrap() = Some(Arc::new(hook));
+    }
+
+    /// Remove the hook set with [`Self::set_commit_hook`]
+    pub fn clear_commit_hook(&self) {
+        *self.commit_hook.lock().unwrap() = None;
+    }
+
+    /// Set a hook to be invoked for each read transaction which is still open `threshold` after it
+    /// began
+    ///
+    /// Read transactions are checked each time a write transaction begins, since that is when a
+    /// long lived read transaction prevents freed pages from being reused, and each one is
+    /// reported at most once. Labels set with [`ReadTransaction::set_label`] are included, to help
+    /// find the code which leaked it. When the `logging` feature is enabled, each one is also
+    /// logged as a warning. This replaces any previously set hook.
+    ///
+    /// The hook runs on the thread which is beginning the write transaction, before it waits for
+    /// the write lock.
+    pub fn set_long_read_hook(
+        &self,
+        threshold: Duration,
+        hook: impl Fn(&ActiveTransaction) + Send + Sync + 'static,
+    ) {
+        *self.long_read_hook.lock().unwrap() = Some((threshold, Arc::new(hook)));
+    }
+
+    /// Remove the hook set with [`Self::set_long_read_hook`]
+    pub fn clear_long_read_hook(&self) {
+        *self.long_read_hook.lock().unwrap() = None;
+    }
+
+    fn report_long_reads(&self) -> Result {
+        let Some((threshold, hook)) = self.long_read_hook.lock().unwrap().clone() else {
+            return Ok(());
+        };
+        let last_committed = self.mem.get_last_committed_transaction_id()?;
+        for read in self
+            .transaction_tracker
+            .take_long_reads(threshold, last_committed)
+        {
+            #[cfg(feature = "logging")]
+            warn!(
+                target: crate::log_targets::TRANSACTIONS,
+                event = "long_read",
+                transaction_id = read.transaction_id(),
+                elapsed_ms = u64::try_from(read.elapsed().as_millis()).unwrap_or(u64::MAX),
+                label = read.label().unwrap_or("");
+                "Read transaction {:?} has been open for {:?}",
+                read.label().unwrap_or("<unlabeled>"),
+                read.elapsed()
+            );
+            hook(&read);
+        }
+        Ok(())
+    }
+
+    /// Watch a range of keys in a table for changes
+    ///
+    /// Returns a [`Watch`] which receives an event for each key in `range` that is inserted, has its
+    /// value replaced, or is removed, once the transaction which changed it has committed. The
+    /// table does not need to exist yet.
+    ///
+    /// Each commit which modifies the table compares the entries in the watched range before and
+    /// after the commit, so watching a large range of a large table slows down commits.
+    pub fn watch<'a, K: Key + 'static, V: Value + 'static, KR>(
+        &self,
+        definition: TableDefinition<K, V>,
+        range: impl RangeBounds<KR> + 'a,
+    ) -> Watch<K, V>
+    where
+        KR: Borrow<K::SelfType<'a>> + 'a,
+    {
+        self.watch_registry.watch(definition, range)
+    }
+
+    /// Begins a write transaction on each of the given databases
+    ///
+    /// The transactions are returned in the same order as `databases`, but are always begun in a
+    /// canonical order which is the same for every call. Threads which need to write to several
+    /// databases at once should use this function, rather than calling [`Self::begin_write`] on
+    /// each of them, to avoid deadlocking with another thread that begins the same writes in a
+    /// different order.
+    ///
+    /// If an error occurs, any transactions that were already begun are aborted.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the same database is given more than once
+    pub fn begin_write_all(
+        databases: &[&Database],
+    ) -> Result<Vec<WriteTransaction>, TransactionError> {
+        let mut order: Vec<usize> = (0..databases.len()).collect();
+        // The address of the shared state is unique to each database, for as long as it is open
+        order.sort_by_key(|i| Arc::as_ptr(&databases[*i].mem) as usize);
+        for pair in order.windows(2) {
+            assert!(
+                !Arc::ptr_eq(&databases[pair[0]].mem, &databases[pair[1]].mem),
+                "The same database was given more than once"
+            );
+        }
+
+        let mut transactions: Vec<Option<WriteTransaction>> =
+            databases.iter().map(|_| None).collect();
+        for i in order {
+            transactions[i] = Some(databases[i].begin_write()?);
+        }
+
+        Ok(transactions.into_iter().map(Option::unwrap).collect())
     }
 
     // The allocation policy is fixed for the lifetime of the transaction; every page allocation
@@ -1058,23 +2086,50 @@ impl Database {
     pub(crate) fn begin_write_with_allocation_policy(
         &self,
         allocation_policy: AllocationPolicy,
+        priority: WritePriority,
     ) -> Result<WriteTransaction, TransactionError> {
         // Fail early if there has been an I/O error -- nothing can be committed in that case
         self.mem.check_io_errors()?;
+        self.report_long_reads()?;
+        if let Some(max_age) = self.read_transaction_max_age {
+            let expired = self.transaction_tracker.expire_reads(max_age);
+            #[cfg(feature = "logging")]
+            if expired > 0 {
+                warn!(
+                    target: crate::log_targets::TRANSACTIONS,
+                    event = "reads_expired",
+                    count = expired;
+                    "Expired {expired} read transactions older than {max_age:?}"
+                );
+            }
+            #[cfg(not(feature = "logging"))]
+            let _ = expired;
+        }
         let guard = TransactionGuard::new_write(
-            self.transaction_tracker.start_write_transaction(),
+            self.transaction_tracker.start_write_transaction(priority),
             self.transaction_tracker.clone(),
         );
-        WriteTransaction::new(
+        let mut txn = WriteTransaction::new(
             guard,
             self.transaction_tracker.clone(),
             self.mem.clone(),
             allocation_policy,
-        )
-        .map_err(|e| e.into())
+        )?;
+        txn.set_auto_checkpoint_size(self.auto_checkpoint_size);
+        txn.set_change_capture(self.change_capture);
+        txn.set_schema_history(self.schema_history);
+        txn.set_savepoint_retention(
+            self.max_persistent_savepoints,
+            self.persistent_savepoint_max_age,
+        );
+        txn.set_database_hook(self.commit_hook.lock().unwrap().clone());
+        txn.set_watch_registry(self.watch_registry.clone());
+        txn.set_group_commit(self.group_commit.clone());
+
+        Ok(txn)
     }
 
-    fn ensure_allocator_state_table_and_trim(&self) -> Result<(), Error> {
+    fn ensure_allocator_state_table_and_trim(&self) -> Result {
         // Make a new quick-repair commit to update the allocator state table
         #[cfg(feature = "logging")]
         debug!("Writing allocator state table");
@@ -1082,11 +2137,13 @@ impl Database {
         // commit's writes at high page indices (see AllocationPolicy::Lowest)
         // and try_shrink can't reclaim the growth. See
         // https://github.com/cberner/redb/issues/1165
-        let mut tx = self.begin_write_with_allocation_policy(AllocationPolicy::Lowest)?;
+        let mut tx = self
+            .begin_write_with_allocation_policy(AllocationPolicy::Lowest, WritePriority::Normal)
+            .map_err(|e| e.into_storage_error())?;
         tx.set_quick_repair(true);
         tx.disable_post_commit_free();
         tx.set_shrink_policy(ShrinkPolicy::Maximum);
-        tx.commit()?;
+        tx.commit().map_err(|e| e.into_storage_error())?;
 
         Ok(())
     }
@@ -1094,18 +2151,55 @@ impl Database {
 
 impl Drop for Database {
     fn drop(&mut self) {
+        if self.closed {
+            return;
+        }
         if !thread::panicking() && self.ensure_allocator_state_table_and_trim().is_err() {
             #[cfg(feature = "logging")]
-            warn!("Failed to write allocator state table. Repair may be required at restart.");
+            warn!(
+                target: crate::log_targets::DURABILITY,
+                event = "allocator_state_not_saved";
+                "Failed to write allocator state table. Repair may be required at restart."
+            );
         }
 
         if self.mem.close().is_err() {
             #[cfg(feature = "logging")]
-            warn!("Failed to flush database file. Repair may be required at restart.");
+            warn!(
+                target: crate::log_targets::DURABILITY,
+                event = "flush_failed";
+                "Failed to flush database file. Repair may be required at restart."
+            );
         }
     }
 }
 
+/// What to do when corrupted data is encountered in the middle of an operation
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum CorruptionPolicy {
+    /// Panic. This is the default
+    #[default]
+    Panic,
+    /// Fail the operation with [`StorageError::Corrupted`], which includes the location of the
+    /// corrupted data. Other tables, which do not contain the corrupted data, can still be used.
+    /// If the operation was modifying a table, its write transaction is poisoned, and can only be
+    /// aborted
+    ReturnError,
+}
+
+/// Compression applied to pages when they are written to the database file
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum PageCompression {
+    /// Pages are stored uncompressed. This is the default
+    #[default]
+    None,
+    /// Pages are compressed with LZ4. Requires the `lz4` feature
+    #[cfg(feature = "lz4")]
+    Lz4,
+}
+
 pub struct RepairSession {
     progress: f64,
     aborted: bool,
@@ -1135,10 +2229,22 @@ impl RepairSession {
 }
 
 /// Configuration builder of a redb [Database].
+#[allow(clippy::struct_excessive_bools)]
 pub struct Builder {
     page_size: usize,
     region_size: Option<u64>,
     cache_size: usize,
+    auto_checkpoint_size: Option<u64>,
+    change_capture: bool,
+    schema_history: bool,
+    read_transaction_max_age: Option<Duration>,
+    max_persistent_savepoints: Option<usize>,
+    persistent_savepoint_max_age: Option<Duration>,
+    group_commit_window: Option<Duration>,
+    corruption_policy: CorruptionPolicy,
+    page_compression: PageCompression,
+    access_tracking: bool,
+    multi_process: bool,
     repair_callback: Box<dyn Fn(&mut RepairSession)>,
 }
 
@@ -1157,6 +2263,17 @@ impl Builder {
             page_size: PAGE_SIZE,
             region_size: None,
             cache_size: 1024 * 1024 * 1024,
+            auto_checkpoint_size: None,
+            change_capture: false,
+            schema_history: false,
+            read_transaction_max_age: None,
+            max_persistent_savepoints: None,
+            persistent_savepoint_max_age: None,
+            group_commit_window: None,
+            corruption_policy: CorruptionPolicy::Panic,
+            page_compression: PageCompression::None,
+            access_tracking: false,
+            multi_process: false,
             repair_callback: Box::new(|_| {}),
         }
     }
@@ -1190,12 +2307,203 @@ impl Builder {
         self
     }
 
+    /// Set what to do when corrupted data is encountered in the middle of an operation, such as a
+    /// page of an unknown type
+    ///
+    /// ## Defaults
+    ///
+    /// Defaults to [`CorruptionPolicy::Panic`]
+    pub fn set_corruption_policy(&mut self, policy: CorruptionPolicy) -> &mut Self {
+        self.corruption_policy = policy;
+        self
+    }
+
+    /// Set the compression applied to pages when they are written to the database file
+    ///
+    /// Each page is compressed in place, and the space that is no longer used is released with
+    /// [`StorageBackend::discard`]. The file system releases space in whole blocks, so only pages
+    /// larger than a block, such as those which hold large values, take less space. Compressed
+    /// pages are always decompressed when they are read, regardless of this setting.
+    ///
+    /// Once compression has been enabled, the file is marked as possibly containing compressed
+    /// pages. Opening it with a build that does not support compression returns
+    /// [`DatabaseError::PageCompressionUnsupported`].
+    ///
+    /// ## Defaults
+    ///
+    /// Defaults to [`PageCompression::None`]
+    pub fn set_page_compression(&mut self, compression: PageCompression) -> &mut Self {
+        self.page_compression = compression;
+        self
+    }
+
+    /// Track which pages are read, so that [`Database::compact`] can place recently read pages
+    /// near the start of the file
+    ///
+    /// Tracking adds a small cost to every page read, and uses 64KiB of memory.
+    ///
+    /// ## Defaults
+    ///
+    /// Defaults to `false`
+    pub fn set_access_tracking(&mut self, enabled: bool) -> &mut Self {
+        self.access_tracking = enabled;
+        self
+    }
+
     /// Set the amount of memory (in bytes) used for caching data
     pub fn set_cache_size(&mut self, bytes: usize) -> &mut Self {
         self.cache_size = bytes;
         self
     }
 
+    /// Automatically checkpoint once the data written by commits with [`Durability::None`] reaches
+    /// `bytes`
+    ///
+    /// When the limit is reached, the next commit is made with [`Durability::Immediate`], even if
+    /// [`Durability::None`] was requested. This bounds the amount of data that can be lost in a
+    /// crash, and the memory and file space held by non-durable commits. See
+    /// [`Database::checkpoint`] to trigger a checkpoint explicitly.
+    ///
+    /// ## Defaults
+    ///
+    /// Disabled by default
+    pub fn set_auto_checkpoint_size(&mut self, bytes: u64) -> &mut Self {
+        self.auto_checkpoint_size = Some(bytes);
+        self
+    }
+
+    /// Record every change to a normal table in a change log, which can be read with
+    /// [`Database::change_log`]
+    ///
+    /// Each commit appends a [`crate::ChangeRecord`] for every key it inserted, replaced, or
+    /// removed, including those of tables that it deleted or renamed. Changes to multimap tables
+    /// are not recorded. The log grows until it is truncated with
+    /// [`WriteTransaction::truncate_change_log`].
+    ///
+    /// Finding the changes only requires reading the pages that the commit wrote, and the pages
+    /// they replaced, so the cost is proportional to the size of the changes rather than of the
+    /// tables. Deleting a table reads all of its entries.
+    ///
+    /// ## Defaults
+    ///
+    /// Disabled by default
+    pub fn set_change_capture(&mut self, enabled: bool) -> &mut Self {
+        self.change_capture = enabled;
+        self
+    }
+
+    /// Record every table creation, deletion, and rename, so that they can be read with
+    /// [`Database::schema_history`]
+    ///
+    /// Each change adds a small entry to a system table. The history is never pruned, so it grows
+    /// with the number of changes made while this is enabled.
+    ///
+    /// ## Defaults
+    ///
+    /// Disabled by default
+    pub fn set_schema_history(&mut self, enabled: bool) -> &mut Self {
+        self.schema_history = enabled;
+        self
+    }
+
+    /// Expire read transactions which have been open for longer than `max_age`
+    ///
+    /// Read transactions are checked each time a write transaction begins. Once a read
+    /// transaction has expired, its snapshot is released so that the pages it was preventing from
+    /// being reused can be reclaimed, and any further read through it, or through a table or
+    /// iterator opened from it, fails with [`StorageError::ReadTransactionExpired`]. Values which
+    /// were already read remain valid. This protects against leaked read transactions preventing
+    /// space from being reclaimed forever.
+    ///
+    /// ## Defaults
+    ///
+    /// Disabled by default
+    pub fn set_read_transaction_max_age(&mut self, max_age: Duration) -> &mut Self {
+        self.read_transaction_max_age = Some(max_age);
+        self
+    }
+
+    /// Keep at most `max` persistent savepoints
+    ///
+    /// Each durable commit deletes the oldest persistent savepoints, until no more than `max`
+    /// remain, so that the pages they were preventing from being reused can be reclaimed.
+    ///
+    /// ## Defaults
+    ///
+    /// Disabled by default
+    pub fn set_max_persistent_savepoints(&mut self, max: usize) -> &mut Self {
+        self.max_persistent_savepoints = Some(max);
+        self
+    }
+
+    /// Delete persistent savepoints once they are older than `max_age`
+    ///
+    /// Persistent savepoints are checked each time a transaction is committed with
+    /// [`Durability::Immediate`], and those that were created more than `max_age` ago are deleted,
+    /// so that the pages they were preventing from being reused can be reclaimed. Savepoints
+    /// created by a version of redb which did not record their creation time are never deleted
+    /// because of their age.
+    ///
+    /// ## Defaults
+    ///
+    /// Disabled by default
+    pub fn set_persistent_savepoint_max_age(&mut self, max_age: Duration) -> &mut Self {
+        self.persistent_savepoint_max_age = Some(max_age);
+        self
+    }
+
+    /// Share the file sync of commits made with [`Durability::Immediate`] by writers that are
+    /// queued behind each other
+    ///
+    /// When a transaction commits while other threads are waiting to begin a write transaction,
+    /// its commit is made visible without syncing the file, and the write lock is released to the
+    /// next writer. [`WriteTransaction::commit`] then waits until a later commit in the queue, made
+    /// by a writer with no one waiting behind it, syncs the file. Each commit is still durable once
+    /// `commit()` returns. If no later commit syncs the file within `window`, the waiting thread
+    /// makes its commit durable itself. This greatly increases the throughput of many threads
+    /// making small transactions.
+    ///
+    /// ## Defaults
+    ///
+    /// Disabled by default
+    pub fn set_group_commit_window(&mut self, window: Duration) -> &mut Self {
+        self.group_commit_window = Some(window);
+        self
+    }
+
+    /// Allow the database file to be opened by multiple processes at once: one which has it open
+    /// for writing, with [`Self::create`] or [`Self::open`], and any number which have it open for
+    /// reading, with [`Self::open_read_only`]. All of them must enable this setting.
+    ///
+    /// Each [`ReadOnlyDatabase`] claims one of the reader slots stored in the file, and records the
+    /// oldest transaction that it may read, so that the writer does not reuse its pages. Every
+    /// call to [`ReadableDatabase::begin_read`] reads the latest durable commit. All commits use
+    /// 2-phase commit, so that a reader never observes a partially written commit.
+    ///
+    /// The file is locked with open file description locks, so this is only supported on Linux
+    /// and Android, and not with [`Self::create_with_backend`].
+    ///
+    /// ## Defaults
+    ///
+    /// Disabled by default
+    pub fn set_multi_process(&mut self, enabled: bool) -> &mut Self {
+        self.multi_process = enabled;
+        self
+    }
+
+    // Opens the backend for `file`, and if multi-process mode is enabled, registers as the writer
+    fn writable_file_backend(
+        &self,
+        file: File,
+    ) -> Result<(FileBackend, Option<ReaderTable>), DatabaseError> {
+        if self.multi_process {
+            let reader_table = ReaderTable::new_writer(file.try_clone()?, self.page_size)?;
+            Ok((FileBackend::new_internal(file, true)?, Some(reader_table)))
+        } else {
+            Ok((FileBackend::new(file)?, None))
+        }
+    }
+
     #[cfg(any(test, fuzzing))]
     pub fn set_region_size(&mut self, size: u64) -> &mut Self {
         assert!(size.is_power_of_two());
@@ -1214,13 +2522,25 @@ impl Builder {
             .create(true)
             .truncate(false)
             .open(path)?;
+        let (backend, reader_table) = self.writable_file_backend(file)?;
 
         Database::new(
-            Box::new(FileBackend::new(file)?),
+            Box::new(backend),
             true,
             self.page_size,
             self.region_size,
             self.cache_size,
+            self.auto_checkpoint_size,
+            self.change_capture,
+            self.schema_history,
+            self.read_transaction_max_age,
+            self.max_persistent_savepoints,
+            self.persistent_savepoint_max_age,
+            self.group_commit_window,
+            self.corruption_policy,
+            self.page_compression,
+            self.access_tracking,
+            reader_table,
             &self.repair_callback,
         )
     }
@@ -1228,13 +2548,25 @@ impl Builder {
     /// Opens an existing redb database.
     pub fn open(&self, path: impl AsRef<Path>) -> Result<Database, DatabaseError> {
         let file = OpenOptions::new().read(true).write(true).open(path)?;
+        let (backend, reader_table) = self.writable_file_backend(file)?;
 
         Database::new(
-            Box::new(FileBackend::new(file)?),
+            Box::new(backend),
             false,
             self.page_size,
             None,
             self.cache_size,
+            self.auto_checkpoint_size,
+            self.change_capture,
+            self.schema_history,
+            self.read_transaction_max_age,
+            self.max_persistent_savepoints,
+            self.persistent_savepoint_max_age,
+            self.group_commit_window,
+            self.corruption_policy,
+            self.page_compression,
+            self.access_tracking,
+            reader_table,
             &self.repair_callback,
         )
     }
@@ -1244,10 +2576,25 @@ impl Builder {
     /// If the file has been opened for writing (i.e. as a [`Database`]) [`DatabaseError::DatabaseAlreadyOpen`]
     /// will be returned on platforms which support file locks (macOS, Windows, Linux). On other platforms,
     /// the caller MUST avoid calling this method when the database is open for writing.
+    ///
+    /// Nothing is written to the file. If it requires repair, because it was not shut down cleanly,
+    /// [`DatabaseError::RecoveryRequired`] is returned.
     pub fn open_read_only(
         &self,
         path: impl AsRef<Path>,
     ) -> Result<ReadOnlyDatabase, DatabaseError> {
+        if self.multi_process {
+            // The reader table is stored in the file, so it must be writable
+            let file = OpenOptions::new().read(true).write(true).open(path)?;
+            let reader_table = ReaderTable::new_reader(file.try_clone()?, self.page_size)?;
+            return ReadOnlyDatabase::new_multi_process(
+                Box::new(FileBackend::new_internal(file, true)?),
+                self.page_size,
+                self.cache_size,
+                self.corruption_policy,
+                reader_table,
+            );
+        }
         let file = OpenOptions::new().read(true).open(path)?;
 
         ReadOnlyDatabase::new(
@@ -1255,6 +2602,7 @@ impl Builder {
             self.page_size,
             None,
             self.cache_size,
+            self.corruption_policy,
         )
     }
 
@@ -1262,12 +2610,24 @@ impl Builder {
     ///
     /// The file must be empty or contain a valid database.
     pub fn create_file(&self, file: File) -> Result<Database, DatabaseError> {
+        let (backend, reader_table) = self.writable_file_backend(file)?;
         Database::new(
-            Box::new(FileBackend::new(file)?),
+            Box::new(backend),
             true,
             self.page_size,
             self.region_size,
             self.cache_size,
+            self.auto_checkpoint_size,
+            self.change_capture,
+            self.schema_history,
+            self.read_transaction_max_age,
+            self.max_persistent_savepoints,
+            self.persistent_savepoint_max_age,
+            self.group_commit_window,
+            self.corruption_policy,
+            self.page_compression,
+            self.access_tracking,
+            reader_table,
             &self.repair_callback,
         )
     }
@@ -1283,11 +2643,30 @@ impl Builder {
             self.page_size,
             self.region_size,
             self.cache_size,
+            self.auto_checkpoint_size,
+            self.change_capture,
+            self.schema_history,
+            self.read_transaction_max_age,
+            self.max_persistent_savepoints,
+            self.persistent_savepoint_max_age,
+            self.group_commit_window,
+            self.corruption_policy,
+            self.page_compression,
+            self.access_tracking,
+            None,
             &self.repair_callback,
         )
     }
 }
 
+fn remap_pages(pages: &mut [PageNumber], relocations: &HashMap<PageNumber, PageNumber>) {
+    for page in pages {
+        if let Some(new_page) = relocations.get(page) {
+            *page = *new_page;
+        }
+    }
+}
+
 impl std::fmt::Debug for Database {
     fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
         f.debug_struct("Database").finish()
@@ -1297,12 +2676,15 @@ impl std::fmt::Debug for Database {
 #[cfg(test)]
 mod test {
     use crate::backends::FileBackend;
+    use crate::db::TransactionGuard;
+    use crate::tree_store::{PageHint, PageResolver, TableTree, TableType};
     use crate::{
-        CommitError, Database, DatabaseError, Durability, ReadableTable, StorageBackend,
-        StorageError, TableDefinition, TransactionError,
+        CommitError, Database, DatabaseError, Durability, ReadableDatabase, ReadableTable,
+        ReadableTableMetadata, StorageBackend, StorageError, TableDefinition, TableError,
+        TransactionError,
     };
     use std::fs::File;
-    use std::io::{ErrorKind, Read, Seek, SeekFrom};
+    use std::io::{ErrorKind, Read, Seek, SeekFrom, Write};
     use std::sync::Arc;
     use std::sync::atomic::{AtomicU64, Ordering};
 
@@ -1373,7 +2755,8 @@ mod test {
         let tmpfile = crate::create_tempfile();
         let (file, path) = tmpfile.into_parts();
 
-        let backend = FailingBackend::new(FileBackend::new(file).unwrap(), 20);
+        let backend = FailingBackend::new(FileBackend::new(file).unwrap(), u64::MAX);
+        let countdown = backend.countdown.clone();
         let db = Database::builder()
             .set_cache_size(12686)
             .set_page_size(8 * 1024)
@@ -1387,6 +2770,10 @@ mod test {
         let _savepoint = tx.ephemeral_savepoint().unwrap();
         let _persistent_savepoint = tx.persistent_savepoint().unwrap();
         tx.commit().unwrap();
+        // The crash happens on the fifth write of this commit. The countdown is set here, rather
+        // than when the backend is created, so that it doesn't depend on how many writes the
+        // earlier commits make
+        countdown.store(4, Ordering::SeqCst);
         let tx = db.begin_write().unwrap();
         {
             let mut table = tx.open_table(table_def).unwrap();
@@ -1726,4 +3113,228 @@ mod test {
             err => panic!("Unexpected error for empty file: {err}"),
         }
     }
+
+    // Average file position of the table's pages, in units of order-0 pages
+    fn average_page_position(db: &Database, name: &str) -> u64 {
+        db.mem.pause_access_tracking();
+        let tables = TableTree::new(
+            db.mem.get_data_root(),
+            PageHint::None,
+            Arc::new(TransactionGuard::untracked()),
+            PageResolver::new(db.mem.clone()),
+        )
+        .unwrap();
+        let definition = tables
+            .get_table_untyped(name, TableType::Normal)
+            .unwrap()
+            .unwrap();
+        let mut total = 0;
+        let mut count = 0;
+        definition
+            .visit_all_pages(PageResolver::new(db.mem.clone()), PageHint::None, |path| {
+                let page = path.page_number();
+                total += (u64::from(page.region) << 20)
+                    + (u64::from(page.page_index) << page.page_order);
+                count += 1;
+                Ok(())
+            })
+            .unwrap();
+        total / count
+    }
+
+    #[test]
+    fn checkpoint() {
+        let tmpfile = crate::create_tempfile();
+        let table_def: TableDefinition<u64, &[u8]> = TableDefinition::new("x");
+
+        let db = Database::create(tmpfile.path()).unwrap();
+        for i in 0..10 {
+            let mut txn = db.begin_write().unwrap();
+            txn.set_durability(Durability::None).unwrap();
+            {
+                let mut table = txn.open_table(table_def).unwrap();
+                table.insert(&i, [0u8; 1000].as_slice()).unwrap();
+            }
+            txn.commit().unwrap();
+        }
+        let unpersisted = db.mem.unpersisted_bytes();
+        assert!(unpersisted >= 10 * 1000);
+
+        db.checkpoint().unwrap();
+        // Only pages written by the non-durable cleanup that follows a durable commit remain
+        assert!(db.mem.unpersisted_bytes() < unpersisted / 2);
+        let txn = db.begin_read().unwrap();
+        let table = txn.open_table(table_def).unwrap();
+        assert_eq!(table.len().unwrap(), 10);
+    }
+
+    #[test]
+    fn auto_checkpoint() {
+        let tmpfile = crate::create_tempfile();
+        let table_def: TableDefinition<u64, &[u8]> = TableDefinition::new("x");
+        let limit = 64 * 1024;
+
+        let db = Database::builder()
+            .set_auto_checkpoint_size(limit)
+            .create(tmpfile.path())
+            .unwrap();
+        let mut max_unpersisted = 0;
+        for i in 0..100 {
+            let mut txn = db.begin_write().unwrap();
+            txn.set_durability(Durability::None).unwrap();
+            {
+                let mut table = txn.open_table(table_def).unwrap();
+                table.insert(&i, [0u8; 4000].as_slice()).unwrap();
+            }
+            txn.commit().unwrap();
+            let unpersisted = db.mem.unpersisted_bytes();
+            // The limit may be exceeded by at most the commit which reached it
+            assert!(unpersisted < 2 * limit, "{unpersisted}");
+            max_unpersisted = max_unpersisted.max(unpersisted);
+        }
+        assert!(max_unpersisted >= limit);
+    }
+
+    #[test]
+    fn compact_clusters_hot_pages() {
+        let tmpfile = crate::create_tempfile();
+        let cold: TableDefinition<u64, &[u8]> = TableDefinition::new("cold");
+        let hot: TableDefinition<u64, &[u8]> = TableDefinition::new("hot");
+
+        let db = Database::builder()
+            .set_access_tracking(true)
+            .create(tmpfile.path())
+            .unwrap();
+        for definition in [cold, hot] {
+            let txn = db.begin_write().unwrap();
+            {
+                let mut table = txn.open_table(definition).unwrap();
+                for i in 0..2000 {
+                    table.insert(&i, [0u8; 500].as_slice()).unwrap();
+                }
+            }
+            txn.commit().unwrap();
+        }
+        drop(db);
+
+        let mut db = Database::builder()
+            .set_access_tracking(true)
+            .open(tmpfile.path())
+            .unwrap();
+        let txn = db.begin_read().unwrap();
+        {
+            let table = txn.open_table(hot).unwrap();
+            for entry in table.iter().unwrap() {
+                entry.unwrap();
+            }
+        }
+        drop(txn);
+        assert!(average_page_position(&db, "hot") > average_page_position(&db, "cold"));
+
+        assert!(db.compact().unwrap());
+        assert!(average_page_position(&db, "hot") < average_page_position(&db, "cold"));
+
+        let txn = db.begin_read().unwrap();
+        let table = txn.open_table(hot).unwrap();
+        assert_eq!(table.len().unwrap(), 2000);
+        let table = txn.open_table(cold).unwrap();
+        assert_eq!(table.len().unwrap(), 2000);
+    }
+
+    #[test]
+    fn quarantine_corrupted_regions() {
+        let tmpfile = crate::create_tempfile();
+        let healthy: TableDefinition<u64, u64> = TableDefinition::new("healthy");
+        let filler: TableDefinition<u64, &[u8]> = TableDefinition::new("filler");
+        let corrupted: TableDefinition<u64, &[u8]> = TableDefinition::new("corrupted");
+
+        // Disable the cache, so that pages are read from the file after they're corrupted
+        let db = Database::builder()
+            .set_cache_size(0)
+            .set_region_size(8 * 4096)
+            .create(tmpfile.path())
+            .unwrap();
+        let txn = db.begin_write().unwrap();
+        {
+            let mut table = txn.open_table(healthy).unwrap();
+            table.insert(&0, &1).unwrap();
+            // Spread the tables over several regions, and surround the corrupted table with filler
+            // so that it doesn't share a region with the pages written at commit
+            let mut filler_table = txn.open_table(filler).unwrap();
+            for i in 0..40 {
+                filler_table.insert(&i, [0u8; 3000].as_slice()).unwrap();
+            }
+            let mut table = txn.open_table(corrupted).unwrap();
+            table.insert(&0, [0xAB; 600].as_slice()).unwrap();
+            for i in 40..80 {
+                filler_table.insert(&i, [0u8; 3000].as_slice()).unwrap();
+            }
+        }
+        txn.commit().unwrap();
+        assert!(db.quarantine_corrupted_regions().unwrap().is_empty());
+
+        // Corrupt the value, which leaves a valid page with an invalid checksum
+        let mut file = File::options()
+            .read(true)
+            .write(true)
+            .open(tmpfile.path())
+            .unwrap();
+        let mut data = vec![];
+        file.read_to_end(&mut data).unwrap();
+        let offset = data
+            .windows(600)
+            .position(|window| window.iter().all(|x| *x == 0xAB))
+            .unwrap();
+        file.seek(SeekFrom::Start(offset.try_into().unwrap()))
+            .unwrap();
+        file.write_all(&[0]).unwrap();
+        file.sync_all().unwrap();
+
+        let regions = db.quarantine_corrupted_regions().unwrap();
+        assert_eq!(regions.len(), 1);
+        assert_eq!(db.quarantined_regions(), regions);
+        // Already quarantined
+        assert!(db.quarantine_corrupted_regions().unwrap().is_empty());
+
+        let txn = db.begin_read().unwrap();
+        let Err(TableError::Storage(StorageError::Corrupted(message))) = txn.open_table(corrupted)
+        else {
+            panic!();
+        };
+        assert!(message.contains("quarantined"));
+        let table = txn.open_table(healthy).unwrap();
+        assert_eq!(table.get(&0).unwrap().unwrap().value(), 1);
+        drop(table);
+        drop(txn);
+
+        // Writes which don't touch the quarantined region succeed, and don't allocate in it
+        let txn = db.begin_write().unwrap();
+        {
+            let mut table = txn.open_table(healthy).unwrap();
+            for i in 1..100 {
+                table.insert(&i, &i).unwrap();
+            }
+        }
+        txn.commit().unwrap();
+        let txn = db.begin_read().unwrap();
+        let table = txn.open_table(healthy).unwrap();
+        assert_eq!(table.len().unwrap(), 100);
+        drop(table);
+        drop(txn);
+
+        // The quarantine is restored when the database is reopened
+        drop(db);
+        let db = Database::builder()
+            .set_cache_size(0)
+            .open(tmpfile.path())
+            .unwrap();
+        assert_eq!(db.quarantined_regions(), regions);
+        let txn = db.begin_read().unwrap();
+        assert!(txn.open_table(corrupted).is_err());
+        drop(txn);
+
+        db.clear_quarantine().unwrap();
+        assert!(db.quarantined_regions().is_empty());
+        assert_eq!(db.quarantine_corrupted_regions().unwrap(), regions);
+    }
 }
@@ -16,6 +16,11 @@ pub enum StorageError {
     PreviousIo,
     DatabaseClosed,
     LockPoisoned(&'static panic::Location<'static>),
+    /// A key or value read from the database could not be decoded
+    Decode(DecodeError),
+    /// The read transaction was open for longer than the limit set with
+    /// [`crate::Builder::set_read_transaction_max_age`], and its snapshot has been released
+    ReadTransactionExpired,
 }
 
 impl<T> From<PoisonError<T>> for StorageError {
@@ -30,6 +35,12 @@ impl From<io::Error> for StorageError {
     }
 }
 
+impl From<DecodeError> for StorageError {
+    fn from(err: DecodeError) -> StorageError {
+        StorageError::Decode(err)
+    }
+}
+
 impl From<StorageError> for Error {
     fn from(err: StorageError) -> Error {
         match err {
@@ -39,6 +50,8 @@ impl From<StorageError> for Error {
             StorageError::PreviousIo => Error::PreviousIo,
             StorageError::DatabaseClosed => Error::DatabaseClosed,
             StorageError::LockPoisoned(location) => Error::LockPoisoned(location),
+            StorageError::Decode(err) => Error::Decode(err),
+            StorageError::ReadTransactionExpired => Error::ReadTransactionExpired,
         }
     }
 }
@@ -71,12 +84,47 @@ impl Display for StorageError {
             StorageError::LockPoisoned(location) => {
                 write!(f, "Poisoned internal lock: {location}")
             }
+            StorageError::Decode(err) => {
+                write!(f, "{err}")
+            }
+            StorageError::ReadTransactionExpired => {
+                write!(f, "Read transaction expired")
+            }
         }
     }
 }
 
 impl std::error::Error for StorageError {}
 
+/// Error returned by [`TryValue::try_from_bytes`](crate::TryValue::try_from_bytes) when the data is
+/// not a valid serialization of the type
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DecodeError {
+    message: String,
+}
+
+impl DecodeError {
+    /// Create an error with a message describing why the data is invalid
+    pub fn new(message: impl Into<String>) -> Self {
+        Self {
+            message: message.into(),
+        }
+    }
+
+    /// Describes why the data is invalid
+    pub fn message(&self) -> &str {
+        &self.message
+    }
+}
+
+impl Display for DecodeError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Failed to decode data: {}", self.message)
+    }
+}
+
+impl std::error::Error for DecodeError {}
+
 /// Errors related to opening tables
 #[derive(Debug)]
 #[non_exhaustive]
@@ -213,6 +261,15 @@ pub enum DatabaseError {
     UpgradeRequired(u8),
     /// A transaction is still in-progress
     TransactionInProgress,
+    /// The database file has been sealed by [`crate::Database::seal`] and can only be opened
+    /// with [`crate::ReadOnlyDatabase`]
+    DatabaseSealed,
+    /// The database file was not shut down cleanly, and cannot be opened read-only until it has
+    /// been repaired by opening it with [`crate::Database`]
+    RecoveryRequired,
+    /// The database file contains compressed pages, and this build does not support page
+    /// compression. See [`crate::Builder::set_page_compression`]
+    PageCompressionUnsupported,
     /// Error from underlying storage
     Storage(StorageError),
 }
@@ -224,6 +281,9 @@ impl From<DatabaseError> for Error {
             DatabaseError::RepairAborted => Error::RepairAborted,
             DatabaseError::UpgradeRequired(x) => Error::UpgradeRequired(x),
             DatabaseError::TransactionInProgress => Error::TransactionInProgress,
+            DatabaseError::DatabaseSealed => Error::DatabaseSealed,
+            DatabaseError::RecoveryRequired => Error::RecoveryRequired,
+            DatabaseError::PageCompressionUnsupported => Error::PageCompressionUnsupported,
             DatabaseError::Storage(storage) => storage.into(),
         }
     }
@@ -262,6 +322,21 @@ impl Display for DatabaseError {
                     "A transaction is still in progress. Operation cannot be performed."
                 )
             }
+            DatabaseError::DatabaseSealed => {
+                write!(f, "Database is sealed and cannot be opened for writing.")
+            }
+            DatabaseError::RecoveryRequired => {
+                write!(
+                    f,
+                    "Database was not shutdown cleanly and must be repaired before it can be opened read-only."
+                )
+            }
+            DatabaseError::PageCompressionUnsupported => {
+                write!(
+                    f,
+                    "Database contains compressed pages, but page compression is not supported by this build."
+                )
+            }
             DatabaseError::Storage(storage) => storage.fmt(f),
         }
     }
@@ -284,6 +359,8 @@ pub enum SavepointError {
     /// creating or deleting a persistent savepoint, or restoring an older savepoint while
     /// newer persistent savepoints exist that would need to be deleted.
     ImmediateDurabilityRequired,
+    /// Error from opening the table being restored
+    Table(TableError),
     /// Error from underlying storage
     Storage(StorageError),
 }
@@ -293,11 +370,18 @@ impl From<SavepointError> for Error {
         match err {
             SavepointError::InvalidSavepoint => Error::InvalidSavepoint,
             SavepointError::ImmediateDurabilityRequired => Error::ImmediateDurabilityRequired,
+            SavepointError::Table(table) => table.into(),
             SavepointError::Storage(storage) => storage.into(),
         }
     }
 }
 
+impl From<TableError> for SavepointError {
+    fn from(err: TableError) -> SavepointError {
+        SavepointError::Table(err)
+    }
+}
+
 impl From<StorageError> for SavepointError {
     fn from(err: StorageError) -> SavepointError {
         SavepointError::Storage(err)
@@ -316,6 +400,7 @@ impl Display for SavepointError {
                     "Operation requires Durability::Immediate for the current transaction."
                 )
             }
+            SavepointError::Table(table) => table.fmt(f),
             SavepointError::Storage(storage) => storage.fmt(f),
         }
     }
@@ -382,6 +467,118 @@ impl Display for CompactionError {
 
 impl std::error::Error for CompactionError {}
 
+/// Errors related to exporting a savepoint
+#[derive(Debug)]
+#[non_exhaustive]
+pub enum ExportError {
+    /// The savepoint is invalid, or is from a different database
+    InvalidSavepoint,
+    /// Error from creating the snapshot file
+    Database(DatabaseError),
+    /// Error from underlying storage
+    Storage(StorageError),
+}
+
+impl From<ExportError> for Error {
+    fn from(err: ExportError) -> Error {
+        match err {
+            ExportError::InvalidSavepoint => Error::InvalidSavepoint,
+            ExportError::Database(database) => database.into(),
+            ExportError::Storage(storage) => storage.into(),
+        }
+    }
+}
+
+impl From<DatabaseError> for ExportError {
+    fn from(err: DatabaseError) -> ExportError {
+        match err {
+            DatabaseError::Storage(storage) => ExportError::Storage(storage),
+            err => ExportError::Database(err),
+        }
+    }
+}
+
+impl From<StorageError> for ExportError {
+    fn from(err: StorageError) -> ExportError {
+        ExportError::Storage(err)
+    }
+}
+
+impl Display for ExportError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ExportError::InvalidSavepoint => {
+                write!(f, "Savepoint is invalid or cannot be exported.")
+            }
+            ExportError::Database(database) => database.fmt(f),
+            ExportError::Storage(storage) => storage.fmt(f),
+        }
+    }
+}
+
+impl std::error::Error for ExportError {}
+
+/// Errors related to backing up a database, or applying a backup
+#[derive(Debug)]
+#[non_exhaustive]
+pub enum BackupError {
+    /// The savepoint is invalid, or is from a different database
+    InvalidSavepoint,
+    /// The backup is incremental, and the file it was applied to is not in the state that the
+    /// backup was taken relative to
+    IncompatibleBackup,
+    /// Error from opening the file that the backup was applied to
+    Database(DatabaseError),
+    /// Error from underlying storage
+    Storage(StorageError),
+}
+
+impl From<BackupError> for Error {
+    fn from(err: BackupError) -> Error {
+        match err {
+            BackupError::InvalidSavepoint => Error::InvalidSavepoint,
+            BackupError::IncompatibleBackup => Error::IncompatibleBackup,
+            BackupError::Database(database) => database.into(),
+            BackupError::Storage(storage) => storage.into(),
+        }
+    }
+}
+
+impl From<DatabaseError> for BackupError {
+    fn from(err: DatabaseError) -> BackupError {
+        match err {
+            DatabaseError::Storage(storage) => BackupError::Storage(storage),
+            err => BackupError::Database(err),
+        }
+    }
+}
+
+impl From<StorageError> for BackupError {
+    fn from(err: StorageError) -> BackupError {
+        BackupError::Storage(err)
+    }
+}
+
+impl Display for BackupError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            BackupError::InvalidSavepoint => {
+                write!(f, "Savepoint is invalid or cannot be backed up.")
+            }
+            BackupError::IncompatibleBackup => {
+                write!(
+                    f,
+                    "Backup was not taken relative to the state of the file it was applied to"
+                )
+            }
+            BackupError::Database(database) => database.fmt(f),
+            BackupError::Storage(storage) => storage.fmt(f),
+        }
+    }
+}
+
+impl std::error::Error for BackupError {}
+
 /// Errors related to transactions
 #[derive(Debug)]
 #[non_exhaustive]
@@ -421,6 +618,8 @@ pub enum TransactionError {
     Storage(StorageError),
     /// The transaction is still referenced by a table or other object
     ReadTransactionStillInUse(Box<ReadTransaction>),
+    /// The write transaction has already been committed or aborted
+    TransactionCompleted,
 }
 
 impl TransactionError {
@@ -439,6 +638,7 @@ impl From<TransactionError> for Error {
             TransactionError::ReadTransactionStillInUse(txn) => {
                 Error::ReadTransactionStillInUse(txn)
             }
+            TransactionError::TransactionCompleted => Error::TransactionCompleted,
         }
     }
 }
@@ -456,6 +656,9 @@ impl Display for TransactionError {
             TransactionError::ReadTransactionStillInUse(_) => {
                 write!(f, "Transaction still in use")
             }
+            TransactionError::TransactionCompleted => {
+                write!(f, "Transaction has already been committed or aborted")
+            }
         }
     }
 }
@@ -468,15 +671,19 @@ impl std::error::Error for TransactionError {}
 pub enum CommitError {
     /// Error from underlying storage
     Storage(StorageError),
-    /// The transaction was poisoned by a panic and can no longer be committed
+    /// The transaction was poisoned by a panic, or by an error part way through modifying a
+    /// table, and can no longer be committed
     TransactionPoisoned,
+    /// A validator registered with [`crate::WriteTransaction::validate`] failed, and the
+    /// transaction was aborted
+    ValidationFailed(ValidationError),
 }
 
 impl CommitError {
     pub(crate) fn into_storage_error(self) -> StorageError {
         match self {
             CommitError::Storage(storage) => storage,
-            CommitError::TransactionPoisoned => unreachable!(),
+            CommitError::TransactionPoisoned | CommitError::ValidationFailed(_) => unreachable!(),
         }
     }
 }
@@ -486,6 +693,7 @@ impl From<CommitError> for Error {
         match err {
             CommitError::Storage(storage) => storage.into(),
             CommitError::TransactionPoisoned => Error::TransactionPoisoned,
+            CommitError::ValidationFailed(err) => err.into(),
         }
     }
 }
@@ -501,14 +709,427 @@ impl Display for CommitError {
         match self {
             CommitError::Storage(storage) => storage.fmt(f),
             CommitError::TransactionPoisoned => {
-                write!(f, "Transaction was poisoned by a panic")
+                write!(f, "Transaction was poisoned by a panic or a failed write")
             }
+            CommitError::ValidationFailed(err) => err.fmt(f),
         }
     }
 }
 
 impl std::error::Error for CommitError {}
 
+/// Errors returned by the validators registered with [`crate::WriteTransaction::validate`]
+#[derive(Debug)]
+#[non_exhaustive]
+pub enum ValidationError {
+    /// The validator rejected the transaction, for the given reason
+    Rejected(String),
+    /// Error from opening a table
+    Table(TableError),
+    /// Error from underlying storage
+    Storage(StorageError),
+}
+
+impl From<ValidationError> for Error {
+    fn from(err: ValidationError) -> Error {
+        match err {
+            ValidationError::Rejected(reason) => Error::ValidationFailed(reason),
+            ValidationError::Table(table) => table.into(),
+            ValidationError::Storage(storage) => storage.into(),
+        }
+    }
+}
+
+impl From<TableError> for ValidationError {
+    fn from(err: TableError) -> ValidationError {
+        ValidationError::Table(err)
+    }
+}
+
+impl From<StorageError> for ValidationError {
+    fn from(err: StorageError) -> ValidationError {
+        ValidationError::Storage(err)
+    }
+}
+
+impl Display for ValidationError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ValidationError::Rejected(reason) => {
+                write!(f, "Transaction failed validation: {reason}")
+            }
+            ValidationError::Table(table) => table.fmt(f),
+            ValidationError::Storage(storage) => storage.fmt(f),
+        }
+    }
+}
+
+impl std::error::Error for ValidationError {}
+
+/// Errors from [`crate::Table::append`]
+#[derive(Debug)]
+#[non_exhaustive]
+pub enum AppendError {
+    /// The key is not greater than the last key in the table
+    KeyOutOfOrder,
+    /// Error from underlying storage
+    Storage(StorageError),
+}
+
+impl From<AppendError> for Error {
+    fn from(err: AppendError) -> Error {
+        match err {
+            AppendError::KeyOutOfOrder => Error::KeyOutOfOrder,
+            AppendError::Storage(storage) => storage.into(),
+        }
+    }
+}
+
+impl From<StorageError> for AppendError {
+    fn from(err: StorageError) -> AppendError {
+        AppendError::Storage(err)
+    }
+}
+
+impl Display for AppendError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            AppendError::KeyOutOfOrder => {
+                write!(f, "Key is not greater than the last key in the table")
+            }
+            AppendError::Storage(storage) => storage.fmt(f),
+        }
+    }
+}
+
+impl std::error::Error for AppendError {}
+
+/// Errors related to two-phase commits, with [`crate::WriteTransaction::prepare`]
+#[derive(Debug)]
+#[non_exhaustive]
+pub enum PrepareError {
+    /// No prepared transaction has the given id
+    NotFound(u64),
+    /// The database has been modified since the transaction with the given id was prepared, so it
+    /// can only be rolled back
+    Conflict(u64),
+    /// A persistent savepoint exists
+    PersistentSavepointExists,
+    /// A ephemeral savepoint exists
+    EphemeralSavepointExists,
+    /// The transaction was poisoned by a panic, or by an error part way through modifying a
+    /// table, and can no longer be prepared
+    TransactionPoisoned,
+    /// A validator registered with [`crate::WriteTransaction::validate`] failed, and the
+    /// transaction was aborted
+    ValidationFailed(ValidationError),
+    /// The databases passed to [`crate::CommitCoordinator::new`], or the transactions passed to
+    /// [`crate::CommitCoordinator::commit`], are invalid for the given reason
+    InvalidParticipants(String),
+    /// Error from underlying storage
+    Storage(StorageError),
+}
+
+impl From<PrepareError> for Error {
+    fn from(err: PrepareError) -> Error {
+        match err {
+            PrepareError::NotFound(id) => Error::PreparedTransactionNotFound(id),
+            PrepareError::Conflict(id) => Error::PreparedTransactionConflict(id),
+            PrepareError::PersistentSavepointExists => Error::PersistentSavepointExists,
+            PrepareError::EphemeralSavepointExists => Error::EphemeralSavepointExists,
+            PrepareError::TransactionPoisoned => Error::TransactionPoisoned,
+            PrepareError::ValidationFailed(err) => err.into(),
+            PrepareError::InvalidParticipants(reason) => Error::InvalidParticipants(reason),
+            PrepareError::Storage(storage) => storage.into(),
+        }
+    }
+}
+
+impl From<CommitError> for PrepareError {
+    fn from(err: CommitError) -> PrepareError {
+        match err {
+            CommitError::Storage(storage) => PrepareError::Storage(storage),
+            CommitError::TransactionPoisoned => PrepareError::TransactionPoisoned,
+            CommitError::ValidationFailed(err) => PrepareError::ValidationFailed(err),
+        }
+    }
+}
+
+impl From<StorageError> for PrepareError {
+    fn from(err: StorageError) -> PrepareError {
+        PrepareError::Storage(err)
+    }
+}
+
+impl Display for PrepareError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            PrepareError::NotFound(id) => {
+                write!(f, "Prepared transaction {id} not found")
+            }
+            PrepareError::Conflict(id) => {
+                write!(
+                    f,
+                    "Database was modified after transaction {id} was prepared"
+                )
+            }
+            PrepareError::PersistentSavepointExists => {
+                write!(
+                    f,
+                    "Persistent savepoint exists. Operation cannot be performed."
+                )
+            }
+            PrepareError::EphemeralSavepointExists => {
+                write!(
+                    f,
+                    "Ephemeral savepoint exists. Operation cannot be performed."
+                )
+            }
+            PrepareError::TransactionPoisoned => {
+                write!(f, "Transaction was poisoned by a panic or a failed write")
+            }
+            PrepareError::ValidationFailed(err) => err.fmt(f),
+            PrepareError::InvalidParticipants(reason) => {
+                write!(f, "Invalid participants: {reason}")
+            }
+            PrepareError::Storage(storage) => storage.fmt(f),
+        }
+    }
+}
+
+impl std::error::Error for PrepareError {}
+
+/// Errors from writing to a [`crate::QuotaTable`]
+#[derive(Debug)]
+#[non_exhaustive]
+pub enum QuotaError {
+    /// The write would take the table over its quota
+    QuotaExceeded,
+    /// Error from underlying storage
+    Storage(StorageError),
+}
+
+impl From<QuotaError> for Error {
+    fn from(err: QuotaError) -> Error {
+        match err {
+            QuotaError::QuotaExceeded => Error::QuotaExceeded,
+            QuotaError::Storage(storage) => storage.into(),
+        }
+    }
+}
+
+impl From<StorageError> for QuotaError {
+    fn from(err: StorageError) -> QuotaError {
+        QuotaError::Storage(err)
+    }
+}
+
+impl Display for QuotaError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            QuotaError::QuotaExceeded => {
+                write!(f, "Table quota exceeded")
+            }
+            QuotaError::Storage(storage) => storage.fmt(f),
+        }
+    }
+}
+
+impl std::error::Error for QuotaError {}
+
+/// Errors related to writing to an [`crate::AppendOnlyTable`]
+#[derive(Debug)]
+#[non_exhaustive]
+pub enum AppendOnlyError {
+    /// The key is already present in the table, and existing keys cannot be modified
+    KeyExists,
+    /// Error from underlying storage
+    Storage(StorageError),
+}
+
+impl From<AppendOnlyError> for Error {
+    fn from(err: AppendOnlyError) -> Error {
+        match err {
+            AppendOnlyError::KeyExists => Error::KeyExists,
+            AppendOnlyError::Storage(storage) => storage.into(),
+        }
+    }
+}
+
+impl From<StorageError> for AppendOnlyError {
+    fn from(err: StorageError) -> AppendOnlyError {
+        AppendOnlyError::Storage(err)
+    }
+}
+
+impl Display for AppendOnlyError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            AppendOnlyError::KeyExists => {
+                write!(f, "Key already exists in append-only table")
+            }
+            AppendOnlyError::Storage(storage) => storage.fmt(f),
+        }
+    }
+}
+
+impl std::error::Error for AppendOnlyError {}
+
+/// Errors from [`crate::WriteTransaction::next_sequence`] and
+/// [`crate::WriteTransaction::reserve_sequence`]
+#[derive(Debug)]
+#[non_exhaustive]
+pub enum SequenceError {
+    /// The sequence would pass `u64::MAX`
+    SequenceExhausted,
+    /// Error from underlying storage
+    Storage(StorageError),
+}
+
+impl From<SequenceError> for Error {
+    fn from(err: SequenceError) -> Error {
+        match err {
+            SequenceError::SequenceExhausted => Error::SequenceExhausted,
+            SequenceError::Storage(storage) => storage.into(),
+        }
+    }
+}
+
+impl From<StorageError> for SequenceError {
+    fn from(err: StorageError) -> SequenceError {
+        SequenceError::Storage(err)
+    }
+}
+
+impl Display for SequenceError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            SequenceError::SequenceExhausted => {
+                write!(f, "Sequence would pass u64::MAX")
+            }
+            SequenceError::Storage(storage) => storage.fmt(f),
+        }
+    }
+}
+
+impl std::error::Error for SequenceError {}
+
+/// Errors from pushing onto a [`crate::QueueTable`]
+#[derive(Debug)]
+#[non_exhaustive]
+pub enum QueueError {
+    /// No positions are left at the end of the queue being pushed to
+    QueueFull,
+    /// Error from underlying storage
+    Storage(StorageError),
+}
+
+impl From<QueueError> for Error {
+    fn from(err: QueueError) -> Error {
+        match err {
+            QueueError::QueueFull => Error::QueueFull,
+            QueueError::Storage(storage) => storage.into(),
+        }
+    }
+}
+
+impl From<StorageError> for QueueError {
+    fn from(err: StorageError) -> QueueError {
+        QueueError::Storage(err)
+    }
+}
+
+impl Display for QueueError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            QueueError::QueueFull => {
+                write!(f, "Queue has no free positions")
+            }
+            QueueError::Storage(storage) => storage.fmt(f),
+        }
+    }
+}
+
+impl std::error::Error for QueueError {}
+
+/// Errors from [`crate::Table::merge`]
+#[derive(Debug)]
+#[non_exhaustive]
+pub enum MergeError {
+    /// No operator has been set with [`crate::Table::set_merge_operator`] on this `Table`
+    NoMergeOperator,
+    /// Error from underlying storage
+    Storage(StorageError),
+}
+
+impl From<MergeError> for Error {
+    fn from(err: MergeError) -> Error {
+        match err {
+            MergeError::NoMergeOperator => Error::NoMergeOperator,
+            MergeError::Storage(storage) => storage.into(),
+        }
+    }
+}
+
+impl From<StorageError> for MergeError {
+    fn from(err: StorageError) -> MergeError {
+        MergeError::Storage(err)
+    }
+}
+
+impl Display for MergeError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            MergeError::NoMergeOperator => {
+                write!(f, "No merge operator has been set for this table")
+            }
+            MergeError::Storage(storage) => storage.fmt(f),
+        }
+    }
+}
+
+impl std::error::Error for MergeError {}
+
+/// Errors from inserting into a multimap table
+#[derive(Debug)]
+#[non_exhaustive]
+pub enum ValueLimitError {
+    /// The key already has the maximum number of values allowed by
+    /// [`crate::MultimapTableDefinition::with_max_values_per_key`], and the table's
+    /// [`crate::ValueLimitPolicy`] is `Reject`
+    ValueLimitExceeded(u64),
+    /// Error from underlying storage
+    Storage(StorageError),
+}
+
+impl From<ValueLimitError> for Error {
+    fn from(err: ValueLimitError) -> Error {
+        match err {
+            ValueLimitError::ValueLimitExceeded(max) => Error::ValueLimitExceeded(max),
+            ValueLimitError::Storage(storage) => storage.into(),
+        }
+    }
+}
+
+impl From<StorageError> for ValueLimitError {
+    fn from(err: StorageError) -> ValueLimitError {
+        ValueLimitError::Storage(err)
+    }
+}
+
+impl Display for ValueLimitError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ValueLimitError::ValueLimitExceeded(max) => {
+                write!(f, "The key already has the maximum of {max} values")
+            }
+            ValueLimitError::Storage(storage) => storage.fmt(f),
+        }
+    }
+}
+
+impl std::error::Error for ValueLimitError {}
+
 /// Superset of all other errors that can occur. Convenience enum so that users can convert all errors into a single type
 #[derive(Debug)]
 #[non_exhaustive]
@@ -532,8 +1153,19 @@ pub enum Error {
     EphemeralSavepointExists,
     /// A transaction is still in-progress
     TransactionInProgress,
-    /// The transaction was poisoned by a panic and can no longer be committed
+    /// The transaction was poisoned by a panic, or by an error part way through modifying a
+    /// table, and can no longer be committed
     TransactionPoisoned,
+    /// The write transaction has already been committed or aborted
+    TransactionCompleted,
+    /// The database file has been sealed and can only be opened with [`crate::ReadOnlyDatabase`]
+    DatabaseSealed,
+    /// The database file was not shut down cleanly, and cannot be opened read-only until it has
+    /// been repaired by opening it with [`crate::Database`]
+    RecoveryRequired,
+    /// The database file contains compressed pages, and this build does not support page
+    /// compression
+    PageCompressionUnsupported,
     /// The Database is corrupted
     Corrupted(String),
     /// The database file is in an old file format and must be manually upgraded
@@ -569,6 +1201,40 @@ pub enum Error {
     LockPoisoned(&'static panic::Location<'static>),
     /// The transaction is still referenced by a table or other object
     ReadTransactionStillInUse(Box<ReadTransaction>),
+    /// The key is already present in an append-only table
+    KeyExists,
+    /// The key passed to [`crate::Table::append`] is not greater than the last key in the table
+    KeyOutOfOrder,
+    /// A write to a [`crate::QuotaTable`] would take it over its quota
+    QuotaExceeded,
+    /// A sequence would pass `u64::MAX`
+    SequenceExhausted,
+    /// No positions are left at the end of a [`crate::QueueTable`] being pushed to
+    QueueFull,
+    /// [`crate::Table::merge`] was called before an operator was set with
+    /// [`crate::Table::set_merge_operator`]
+    NoMergeOperator,
+    /// A key or value read from the database could not be decoded
+    Decode(DecodeError),
+    /// The key of a multimap table already has the maximum number of values allowed by
+    /// [`crate::MultimapTableDefinition::with_max_values_per_key`]
+    ValueLimitExceeded(u64),
+    /// No prepared transaction has the given id
+    PreparedTransactionNotFound(u64),
+    /// The database has been modified since the transaction with the given id was prepared
+    PreparedTransactionConflict(u64),
+    /// The databases or transactions passed to a [`crate::CommitCoordinator`] are invalid for the
+    /// given reason
+    InvalidParticipants(String),
+    /// The read transaction was open for longer than the limit set with
+    /// [`crate::Builder::set_read_transaction_max_age`], and its snapshot has been released
+    ReadTransactionExpired,
+    /// A validator registered with [`crate::WriteTransaction::validate`] rejected the transaction,
+    /// for the given reason
+    ValidationFailed(String),
+    /// An incremental backup was applied to a file that is not in the state that the backup was
+    /// taken relative to
+    IncompatibleBackup,
 }
 
 impl<T> From<PoisonError<T>> for Error {
@@ -684,7 +1350,25 @@ impl Display for Error {
                 )
             }
             Error::TransactionPoisoned => {
-                write!(f, "Transaction was poisoned by a panic")
+                write!(f, "Transaction was poisoned by a panic or a failed write")
+            }
+            Error::TransactionCompleted => {
+                write!(f, "Transaction has already been committed or aborted")
+            }
+            Error::DatabaseSealed => {
+                write!(f, "Database is sealed and cannot be opened for writing.")
+            }
+            Error::RecoveryRequired => {
+                write!(
+                    f,
+                    "Database was not shutdown cleanly and must be repaired before it can be opened read-only."
+                )
+            }
+            Error::PageCompressionUnsupported => {
+                write!(
+                    f,
+                    "Database contains compressed pages, but page compression is not supported by this build."
+                )
             }
             Error::InvalidSavepoint => {
                 write!(f, "Savepoint is invalid or cannot be created.")
@@ -698,6 +1382,54 @@ impl Display for Error {
             Error::ReadTransactionStillInUse(_) => {
                 write!(f, "Transaction still in use")
             }
+            Error::KeyExists => {
+                write!(f, "Key already exists in append-only table")
+            }
+            Error::KeyOutOfOrder => {
+                write!(f, "Key is not greater than the last key in the table")
+            }
+            Error::QuotaExceeded => {
+                write!(f, "Table quota exceeded")
+            }
+            Error::SequenceExhausted => {
+                write!(f, "Sequence would pass u64::MAX")
+            }
+            Error::QueueFull => {
+                write!(f, "Queue has no free positions")
+            }
+            Error::NoMergeOperator => {
+                write!(f, "No merge operator has been set for this table")
+            }
+            Error::Decode(err) => {
+                write!(f, "{err}")
+            }
+            Error::ValueLimitExceeded(max) => {
+                write!(f, "The key already has the maximum of {max} values")
+            }
+            Error::PreparedTransactionNotFound(id) => {
+                write!(f, "Prepared transaction {id} not found")
+            }
+            Error::PreparedTransactionConflict(id) => {
+                write!(
+                    f,
+                    "Database was modified after transaction {id} was prepared"
+                )
+            }
+            Error::InvalidParticipants(reason) => {
+                write!(f, "Invalid participants: {reason}")
+            }
+            Error::ReadTransactionExpired => {
+                write!(f, "Read transaction expired")
+            }
+            Error::ValidationFailed(reason) => {
+                write!(f, "Transaction failed validation: {reason}")
+            }
+            Error::IncompatibleBackup => {
+                write!(
+                    f,
+                    "Backup was not taken relative to the state of the file it was applied to"
+                )
+            }
         }
     }
 }
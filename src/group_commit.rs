@@ -0,0 +1,55 @@
+use crate::transaction_tracker::TransactionId;
+use std::sync::{Condvar, Mutex};
+use std::time::{Duration, Instant};
+
+// Shared by the write transactions of a database with group commit enabled. A commit with
+// `Durability::Immediate` made while other writers are waiting is committed without syncing the
+// file, and its thread waits for a later durable commit, so that one sync covers the whole group.
+pub(crate) struct GroupCommit {
+    window: Duration,
+    // Id of the latest transaction which is known to be durable
+    durable: Mutex<TransactionId>,
+    durable_changed: Condvar,
+}
+
+impl GroupCommit {
+    pub(crate) fn new(window: Duration, durable: TransactionId) -> Self {
+        Self {
+            window,
+            durable: Mutex::new(durable),
+            durable_changed: Condvar::new(),
+        }
+    }
+
+    // Called after every durable commit, since it also makes all earlier commits durable
+    pub(crate) fn notify_durable(&self, id: TransactionId) {
+        let mut durable = self.durable.lock().unwrap();
+        if id > *durable {
+            *durable = id;
+        }
+        self.durable_changed.notify_all();
+    }
+
+    pub(crate) fn is_durable(&self, id: TransactionId) -> bool {
+        *self.durable.lock().unwrap() >= id
+    }
+
+    // Waits for up to the group commit window for `id` to become durable. Returns false if it did
+    // not, in which case the caller must make it durable itself
+    pub(crate) fn wait_durable(&self, id: TransactionId) -> bool {
+        let deadline = Instant::now() + self.window;
+        let mut durable = self.durable.lock().unwrap();
+        while *durable < id {
+            let now = Instant::now();
+            if now >= deadline {
+                return false;
+            }
+            durable = self
+                .durable_changed
+                .wait_timeout(durable, deadline - now)
+                .unwrap()
+                .0;
+        }
+        true
+    }
+}
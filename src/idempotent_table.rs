@@ -0,0 +1,121 @@
+use crate::{
+    Key, ReadableTable, Result, Table, TableDefinition, TableError, TableHandle, Value,
+    WriteTransaction,
+};
+use std::borrow::Borrow;
+use std::fmt::{Debug, Formatter};
+
+/// A table whose writes are each tagged with a client-supplied idempotency token
+///
+/// The token of every write is recorded in a side table, in the same transaction as the write, so
+/// a write is applied if and only if its token is recorded. A write whose token has already been
+/// recorded is skipped, which allows an upstream system with at-least-once delivery to safely
+/// retry writes without applying them twice.
+///
+/// Tokens are kept until they are removed from the side table, which can be read and modified
+/// like any other table through its definition.
+///
+/// # Examples
+///
+/// ```rust
+/// use redb::*;
+/// # use tempfile::NamedTempFile;
+/// const TABLE: TableDefinition<&str, u64> = TableDefinition::new("my_data");
+/// const TOKENS: TableDefinition<&[u8], ()> = TableDefinition::new("my_data_tokens");
+///
+/// # fn main() -> Result<(), Error> {
+/// # #[cfg(not(target_os = "wasi"))]
+/// # let tmpfile = NamedTempFile::new().unwrap();
+/// # #[cfg(target_os = "wasi")]
+/// # let tmpfile = NamedTempFile::new_in("/tmp").unwrap();
+/// # let filename = tmpfile.path();
+/// let db = Database::create(filename)?;
+///
+/// for _ in 0..2 {
+///     let write_txn = db.begin_write()?;
+///     {
+///         let mut table = IdempotentTable::open(&write_txn, TABLE, TOKENS)?;
+///         if table.insert(b"request-1", "a", 1)? {
+///             // First delivery of request-1
+///         }
+///     }
+///     write_txn.commit()?;
+/// }
+///
+/// let read_txn = db.begin_read()?;
+/// let tokens = read_txn.open_table(TOKENS)?;
+/// assert!(tokens.get(b"request-1".as_slice())?.is_some());
+/// # Ok(())
+/// # }
+/// ```
+pub struct IdempotentTable<'txn, K: Key + 'static, V: Value + 'static> {
+    table: Table<'txn, K, V>,
+    tokens: Table<'txn, &'static [u8], ()>,
+}
+
+impl<'txn, K: Key + 'static, V: Value + 'static> IdempotentTable<'txn, K, V> {
+    /// Open the table, and the side table in which its tokens are recorded
+    ///
+    /// Both tables are created if they do not exist
+    pub fn open(
+        transaction: &'txn WriteTransaction,
+        definition: TableDefinition<K, V>,
+        tokens: TableDefinition<&'static [u8], ()>,
+    ) -> Result<Self, TableError> {
+        Ok(Self {
+            table: transaction.open_table(definition)?,
+            tokens: transaction.open_table(tokens)?,
+        })
+    }
+
+    /// Returns `true` if a write with the given token has been applied
+    pub fn was_applied(&self, token: &[u8]) -> Result<bool> {
+        Ok(self.tokens.get(token)?.is_some())
+    }
+
+    /// Insert mapping from the given key to the given value, unless a write with the given token
+    /// has already been applied
+    ///
+    /// Returns `true` if the insert was applied, and `false` if it was skipped
+    pub fn insert<'k, 'v>(
+        &mut self,
+        token: &[u8],
+        key: impl Borrow<K::SelfType<'k>>,
+        value: impl Borrow<V::SelfType<'v>>,
+    ) -> Result<bool> {
+        if self.was_applied(token)? {
+            return Ok(false);
+        }
+        self.table.insert(key, value)?;
+        self.tokens.insert(token, ())?;
+        Ok(true)
+    }
+
+    /// Removes the given key, unless a write with the given token has already been applied
+    ///
+    /// Returns `true` if the removal was applied, and `false` if it was skipped
+    pub fn remove<'a>(&mut self, token: &[u8], key: impl Borrow<K::SelfType<'a>>) -> Result<bool> {
+        if self.was_applied(token)? {
+            return Ok(false);
+        }
+        self.table.remove(key)?;
+        self.tokens.insert(token, ())?;
+        Ok(true)
+    }
+
+    /// The underlying table
+    ///
+    /// Writes made directly to it are not tagged with a token
+    pub fn table(&mut self) -> &mut Table<'txn, K, V> {
+        &mut self.table
+    }
+}
+
+impl<K: Key + 'static, V: Value + 'static> Debug for IdempotentTable<'_, K, V> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("IdempotentTable")
+            .field("table", &self.table.name())
+            .field("tokens", &self.tokens.name())
+            .finish()
+    }
+}
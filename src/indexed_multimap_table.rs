@@ -0,0 +1,175 @@
+use crate::{
+    Key, MultimapTable, MultimapTableDefinition, MultimapTableHandle, MultimapValue,
+    ReadableMultimapTable, ReadableTableMetadata, Result, TableError, ValueLimitError,
+    WriteTransaction,
+};
+use std::borrow::Borrow;
+use std::fmt::{Debug, Formatter};
+
+/// A multimap table which maintains an inverted index of itself
+///
+/// Every key-value pair inserted into or removed from the table is also inserted into or removed
+/// from a second multimap table, which maps each value to the keys it is stored under, in the same
+/// transaction. This keeps the two tables in sync, instead of updating the index by hand.
+///
+/// When opened, the index is rebuilt from the table if the two do not contain the same number of
+/// pairs, for example because the index has just been created for an existing table.
+///
+/// The index is only maintained for writes made through this type. Both tables are ordinary
+/// multimap tables in the database file, and can still be modified by opening them with
+/// [`WriteTransaction::open_multimap_table`].
+///
+/// Any limit on the number of values per key in the index's definition is ignored when the index
+/// is written, so that it always contains every pair in the table.
+///
+/// # Examples
+///
+/// ```rust
+/// use redb::*;
+/// # use tempfile::NamedTempFile;
+/// const TAGS: MultimapTableDefinition<&str, &str> = MultimapTableDefinition::new("tags");
+/// const TAGGED: MultimapTableDefinition<&str, &str> = MultimapTableDefinition::new("tagged");
+///
+/// # fn main() -> Result<(), Error> {
+/// # #[cfg(not(target_os = "wasi"))]
+/// # let tmpfile = NamedTempFile::new().unwrap();
+/// # #[cfg(target_os = "wasi")]
+/// # let tmpfile = NamedTempFile::new_in("/tmp").unwrap();
+/// # let filename = tmpfile.path();
+/// let db = Database::create(filename)?;
+///
+/// let write_txn = db.begin_write()?;
+/// {
+///     let mut tags = IndexedMultimapTable::open(&write_txn, TAGS, TAGGED)?;
+///     tags.insert("post1", "rust")?;
+///     tags.insert("post2", "rust")?;
+///     tags.remove("post1", "rust")?;
+///     let posts: Vec<String> = tags
+///         .get_keys("rust")?
+///         .map(|key| key.map(|key| key.value().to_string()))
+///         .collect::<Result<_, _>>()?;
+///     assert_eq!(posts, vec!["post2"]);
+/// }
+/// write_txn.commit()?;
+/// # Ok(())
+/// # }
+/// ```
+pub struct IndexedMultimapTable<'txn, K: Key + 'static, V: Key + 'static> {
+    table: MultimapTable<'txn, K, V>,
+    index: MultimapTable<'txn, V, K>,
+}
+
+impl<'txn, K: Key + 'static, V: Key + 'static> IndexedMultimapTable<'txn, K, V> {
+    /// Open the given table and its index, which are created if they do not exist
+    ///
+    /// If the index does not contain the same number of pairs as the table, it is rebuilt
+    pub fn open(
+        transaction: &'txn WriteTransaction,
+        definition: MultimapTableDefinition<K, V>,
+        index_definition: MultimapTableDefinition<V, K>,
+    ) -> Result<Self, TableError> {
+        let mut result = Self {
+            table: transaction.open_multimap_table(definition)?,
+            index: transaction.open_multimap_table(index_definition)?,
+        };
+        if result.table.len()? != result.index.len()? {
+            result.rebuild_index()?;
+        }
+        Ok(result)
+    }
+
+    /// Replace the contents of the index with the inverse of the table
+    pub fn rebuild_index(&mut self) -> Result {
+        self.index.retain(|_, _| false)?;
+        for entry in self.table.iter()? {
+            let (key, values) = entry?;
+            for value in values {
+                self.index.insert_unlimited(value?.value(), key.value())?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Add the given value to the mapping of the key, and the key to the mapping of the value in
+    /// the index
+    ///
+    /// If the table limits the number of values per key, any value evicted from the key is also
+    /// removed from the index
+    ///
+    /// Returns `true` if the key-value pair was present
+    pub fn insert<'k, 'v>(
+        &mut self,
+        key: impl Borrow<K::SelfType<'k>>,
+        value: impl Borrow<V::SelfType<'v>>,
+    ) -> Result<bool, ValueLimitError> {
+        let (existed, evicted) = self.table.insert_and_evict(key.borrow(), value.borrow())?;
+        if !existed {
+            self.index.insert_unlimited(value, key.borrow())?;
+        }
+        for value in evicted {
+            self.index.remove(V::from_bytes(&value), key.borrow())?;
+        }
+        Ok(existed)
+    }
+
+    /// Removes the given key-value pair from the table and the index
+    ///
+    /// Returns `true` if the key-value pair was present
+    pub fn remove<'k, 'v>(
+        &mut self,
+        key: impl Borrow<K::SelfType<'k>>,
+        value: impl Borrow<V::SelfType<'v>>,
+    ) -> Result<bool> {
+        let existed = self.table.remove(key.borrow(), value.borrow())?;
+        if existed {
+            self.index.remove(value, key)?;
+        }
+        Ok(existed)
+    }
+
+    /// Removes all values for the given key, and the key from the mapping of each of those values
+    /// in the index
+    ///
+    /// Returns the number of values removed
+    pub fn remove_all<'k>(&mut self, key: impl Borrow<K::SelfType<'k>>) -> Result<u64> {
+        let mut removed = 0;
+        for value in self.table.remove_all(key.borrow())? {
+            self.index.remove(value?.value(), key.borrow())?;
+            removed += 1;
+        }
+        Ok(removed)
+    }
+
+    /// Returns an iterator over all values for the given key. Values are in ascending order.
+    pub fn get<'k>(&self, key: impl Borrow<K::SelfType<'k>>) -> Result<MultimapValue<'_, V>> {
+        self.table.get(key)
+    }
+
+    /// Returns an iterator over all keys which the given value is stored under. Keys are in
+    /// ascending order.
+    pub fn get_keys<'v>(
+        &self,
+        value: impl Borrow<V::SelfType<'v>>,
+    ) -> Result<MultimapValue<'_, K>> {
+        self.index.get(value)
+    }
+
+    /// The underlying table
+    pub fn table(&self) -> &MultimapTable<'txn, K, V> {
+        &self.table
+    }
+
+    /// The index, which maps each value to the keys it is stored under
+    pub fn index(&self) -> &MultimapTable<'txn, V, K> {
+        &self.index
+    }
+}
+
+impl<K: Key + 'static, V: Key + 'static> Debug for IndexedMultimapTable<'_, K, V> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("IndexedMultimapTable")
+            .field("table", &self.table.name())
+            .field("index", &self.index.name())
+            .finish_non_exhaustive()
+    }
+}
@@ -0,0 +1,326 @@
+//! Helpers for building composite keys for tables keyed by `&[u8]`
+//!
+//! A key is built by appending its parts, in order, to a [`KeyEncoder`]. Each part is encoded so
+//! that comparing two encoded keys byte-wise, as `&[u8]` keys are compared, gives the same result
+//! as comparing their parts one by one. The encoding of a part never depends on the parts which
+//! follow it, so a key built from the first few parts is a prefix of every key which starts with
+//! those parts, and [`prefix_range_end`] can be used to scan all of them.
+//!
+//! * Integers are stored big-endian, with the sign bit flipped for signed types.
+//! * Byte strings and strings are escaped, so they may contain any byte, and are followed by a
+//!   terminator. A string therefore sorts before any longer string which it is a prefix of.
+//!
+//! Keys are parsed with a [`KeyDecoder`], by reading the parts back in the same order, and with the
+//! same types, as they were appended.
+//!
+//! # Examples
+//!
+//! ```rust
+//! use redb::key_encoding::{KeyDecoder, KeyEncoder, prefix_range_end};
+//! use redb::*;
+//! # use tempfile::NamedTempFile;
+//! const TABLE: TableDefinition<&[u8], u64> = TableDefinition::new("events");
+//!
+//! # fn main() -> Result<(), Error> {
+//! # #[cfg(not(target_os = "wasi"))]
+//! # let tmpfile = NamedTempFile::new().unwrap();
+//! # #[cfg(target_os = "wasi")]
+//! # let tmpfile = NamedTempFile::new_in("/tmp").unwrap();
+//! # let filename = tmpfile.path();
+//! let db = Database::create(filename)?;
+//! let write_txn = db.begin_write()?;
+//! {
+//!     let mut table = write_txn.open_table(TABLE)?;
+//!     for (user, time) in [("alice", -5), ("bob", 3), ("alice", 10)] {
+//!         let key = KeyEncoder::new().push_str(user).push_i64(time).finish();
+//!         table.insert(key.as_slice(), 0)?;
+//!     }
+//! }
+//! write_txn.commit()?;
+//!
+//! let read_txn = db.begin_read()?;
+//! let table = read_txn.open_table(TABLE)?;
+//! let start = KeyEncoder::new().push_str("alice").finish();
+//! let end = prefix_range_end(&start).unwrap();
+//! let mut times = vec![];
+//! for entry in table.range(start.as_slice()..end.as_slice())? {
+//!     let (key, _) = entry?;
+//!     let mut decoder = KeyDecoder::new(key.value());
+//!     assert_eq!(decoder.read_str().unwrap(), "alice");
+//!     times.push(decoder.read_i64().unwrap());
+//! }
+//! assert_eq!(times, vec![-5, 10]);
+//! # Ok(())
+//! # }
+//! ```
+
+use std::fmt::{Display, Formatter};
+
+// Byte strings are terminated by ESCAPE, TERMINATOR, and an ESCAPE byte in the string is replaced
+// by ESCAPE, ESCAPED. TERMINATOR < ESCAPED, so a string sorts before any longer string that it is a
+// prefix of
+const ESCAPE: u8 = 0x00;
+const TERMINATOR: u8 = 0x01;
+const ESCAPED: u8 = 0xFF;
+
+/// Builds a composite key from its parts
+///
+/// See the [module documentation](self) for details of the encoding
+#[derive(Clone, Debug, Default)]
+pub struct KeyEncoder {
+    data: Vec<u8>,
+}
+
+macro_rules! encode_int {
+    ($name:ident, $int:ty, $sign_flip:expr) => {
+        #[doc = concat!("Append a `", stringify!($int), "`")]
+        // Big-endian for the reason given in types/float.rs
+        #[allow(clippy::big_endian_bytes)]
+        #[must_use]
+        pub fn $name(mut self, value: $int) -> Self {
+            let mut bytes = value.to_be_bytes();
+            bytes[0] ^= $sign_flip;
+            self.data.extend_from_slice(&bytes);
+            self
+        }
+    };
+}
+
+impl KeyEncoder {
+    /// Create an encoder for an empty key
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    encode_int!(push_u8, u8, 0);
+    encode_int!(push_u16, u16, 0);
+    encode_int!(push_u32, u32, 0);
+    encode_int!(push_u64, u64, 0);
+    encode_int!(push_u128, u128, 0);
+    encode_int!(push_i8, i8, 0x80);
+    encode_int!(push_i16, i16, 0x80);
+    encode_int!(push_i32, i32, 0x80);
+    encode_int!(push_i64, i64, 0x80);
+    encode_int!(push_i128, i128, 0x80);
+
+    /// Append a byte string, which may contain any bytes
+    #[must_use]
+    pub fn push_bytes(mut self, value: &[u8]) -> Self {
+        for &byte in value {
+            self.data.push(byte);
+            if byte == ESCAPE {
+                self.data.push(ESCAPED);
+            }
+        }
+        self.data.push(ESCAPE);
+        self.data.push(TERMINATOR);
+        self
+    }
+
+    /// Append a string
+    #[must_use]
+    pub fn push_str(self, value: &str) -> Self {
+        self.push_bytes(value.as_bytes())
+    }
+
+    /// The key built so far
+    pub fn as_slice(&self) -> &[u8] {
+        &self.data
+    }
+
+    /// Returns the encoded key
+    pub fn finish(self) -> Vec<u8> {
+        self.data
+    }
+}
+
+/// Error parsing a composite key
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum KeyDecodeError {
+    /// The key ended before the part being read
+    UnexpectedEnd,
+    /// A byte string contained an invalid escape sequence
+    InvalidEscape,
+    /// A string was not valid UTF-8
+    InvalidUtf8,
+}
+
+impl Display for KeyDecodeError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            KeyDecodeError::UnexpectedEnd => write!(f, "Key ended unexpectedly"),
+            KeyDecodeError::InvalidEscape => write!(f, "Invalid escape sequence in key"),
+            KeyDecodeError::InvalidUtf8 => write!(f, "Invalid UTF-8 in key"),
+        }
+    }
+}
+
+impl std::error::Error for KeyDecodeError {}
+
+/// Parses a composite key built by a [`KeyEncoder`]
+///
+/// Parts must be read in the same order, and with the same types, as they were appended
+#[derive(Clone, Debug)]
+pub struct KeyDecoder<'a> {
+    data: &'a [u8],
+}
+
+macro_rules! decode_int {
+    ($name:ident, $int:ty, $sign_flip:expr) => {
+        #[doc = concat!("Read a `", stringify!($int), "`")]
+        #[allow(clippy::big_endian_bytes)]
+        pub fn $name(&mut self) -> Result<$int, KeyDecodeError> {
+            let Some((bytes, remaining)) = self.data.split_first_chunk::<{ size_of::<$int>() }>()
+            else {
+                return Err(KeyDecodeError::UnexpectedEnd);
+            };
+            let mut bytes = *bytes;
+            bytes[0] ^= $sign_flip;
+            self.data = remaining;
+            Ok(<$int>::from_be_bytes(bytes))
+        }
+    };
+}
+
+impl<'a> KeyDecoder<'a> {
+    /// Create a decoder which reads the parts of the given key
+    pub fn new(data: &'a [u8]) -> Self {
+        Self { data }
+    }
+
+    decode_int!(read_u8, u8, 0);
+    decode_int!(read_u16, u16, 0);
+    decode_int!(read_u32, u32, 0);
+    decode_int!(read_u64, u64, 0);
+    decode_int!(read_u128, u128, 0);
+    decode_int!(read_i8, i8, 0x80);
+    decode_int!(read_i16, i16, 0x80);
+    decode_int!(read_i32, i32, 0x80);
+    decode_int!(read_i64, i64, 0x80);
+    decode_int!(read_i128, i128, 0x80);
+
+    /// Read a byte string
+    pub fn read_bytes(&mut self) -> Result<Vec<u8>, KeyDecodeError> {
+        let mut result = vec![];
+        let mut i = 0;
+        loop {
+            let Some(&byte) = self.data.get(i) else {
+                return Err(KeyDecodeError::UnexpectedEnd);
+            };
+            if byte == ESCAPE {
+                match self.data.get(i + 1) {
+                    Some(&TERMINATOR) => {
+                        self.data = &self.data[(i + 2)..];
+                        return Ok(result);
+                    }
+                    Some(&ESCAPED) => {
+                        result.push(ESCAPE);
+                        i += 2;
+                    }
+                    Some(_) => return Err(KeyDecodeError::InvalidEscape),
+                    None => return Err(KeyDecodeError::UnexpectedEnd),
+                }
+            } else {
+                result.push(byte);
+                i += 1;
+            }
+        }
+    }
+
+    /// Read a string
+    pub fn read_str(&mut self) -> Result<String, KeyDecodeError> {
+        String::from_utf8(self.read_bytes()?).map_err(|_| KeyDecodeError::InvalidUtf8)
+    }
+
+    /// The parts of the key which have not been read yet
+    pub fn remaining(&self) -> &'a [u8] {
+        self.data
+    }
+
+    /// Returns `true` if every part of the key has been read
+    pub fn is_empty(&self) -> bool {
+        self.data.is_empty()
+    }
+}
+
+/// Returns the smallest key which is greater than every key that starts with `prefix`
+///
+/// `prefix..end` is therefore the range of keys which start with `prefix`. Returns `None` if
+/// there is no such key, because `prefix` is empty or consists only of `0xFF` bytes, in which case
+/// the range is `prefix..`
+pub fn prefix_range_end(prefix: &[u8]) -> Option<Vec<u8>> {
+    let last = prefix.iter().rposition(|&byte| byte != 0xFF)?;
+    let mut end = prefix[..=last].to_vec();
+    end[last] += 1;
+    Some(end)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::key_encoding::{KeyDecodeError, KeyDecoder, KeyEncoder, prefix_range_end};
+
+    #[test]
+    fn test_ordering() {
+        let strings: &[&[u8]] = &[
+            b"",
+            b"\x00",
+            b"\x00\x00",
+            b"\x00\x01",
+            b"\x00\xff",
+            b"\x01",
+            b"a",
+        ];
+        let ints = [i64::MIN, -1, 0, 1, i64::MAX];
+        let mut expected = vec![];
+        for s in strings {
+            for i in ints {
+                expected.push(KeyEncoder::new().push_bytes(s).push_i64(i).finish());
+            }
+        }
+        let mut sorted = expected.clone();
+        sorted.sort();
+        assert_eq!(sorted, expected);
+
+        for (key, (s, i)) in expected
+            .iter()
+            .zip(strings.iter().flat_map(|s| ints.map(|i| (*s, i))))
+        {
+            let mut decoder = KeyDecoder::new(key);
+            assert_eq!(decoder.read_bytes().unwrap(), s);
+            assert_eq!(decoder.read_i64().unwrap(), i);
+            assert!(decoder.is_empty());
+        }
+    }
+
+    #[test]
+    fn test_decode_errors() {
+        let key = KeyEncoder::new().push_str("ab").push_u16(7).finish();
+        let mut decoder = KeyDecoder::new(&key);
+        assert_eq!(decoder.read_str().unwrap(), "ab");
+        assert_eq!(decoder.read_u32(), Err(KeyDecodeError::UnexpectedEnd));
+        assert_eq!(decoder.read_u16().unwrap(), 7);
+
+        assert_eq!(
+            KeyDecoder::new(b"a\x00\x02").read_bytes(),
+            Err(KeyDecodeError::InvalidEscape)
+        );
+        assert_eq!(
+            KeyDecoder::new(b"a\x00").read_bytes(),
+            Err(KeyDecodeError::UnexpectedEnd)
+        );
+        assert_eq!(
+            KeyDecoder::new(b"\xff\x00\x01").read_str(),
+            Err(KeyDecodeError::InvalidUtf8)
+        );
+    }
+
+    #[test]
+    fn test_prefix_range_end() {
+        assert_eq!(prefix_range_end(b""), None);
+        assert_eq!(prefix_range_end(b"\xff\xff"), None);
+        assert_eq!(prefix_range_end(b"a"), Some(b"b".to_vec()));
+        assert_eq!(prefix_range_end(b"a\xff\xff"), Some(b"b".to_vec()));
+        assert_eq!(prefix_range_end(b"a\x00\x01"), Some(b"a\x00\x02".to_vec()));
+    }
+}
@@ -60,41 +60,90 @@
 //! [lmdb]: https://www.lmdb.tech/doc/
 //! [design]: https://github.com/cberner/redb/blob/master/docs/design.md
 
+pub use append_only_table::AppendOnlyTable;
+#[cfg(feature = "async")]
+pub use async_db::{AsyncDatabase, AsyncReadTransaction, AsyncWriteTransaction, BlockingFuture};
+pub use cached_table::CachedTable;
+pub use capped_table::{Cap, CappedTable};
+pub use change_log::ChangeRecord;
+pub use coordinator::CommitCoordinator;
 pub use db::{
-    Builder, CacheStats, Database, MultimapTableDefinition, MultimapTableHandle, ReadOnlyDatabase,
-    ReadableDatabase, RepairSession, StorageBackend, TableDefinition, TableHandle,
-    UntypedMultimapTableHandle, UntypedTableHandle,
+    Builder, CacheStats, CorruptionPolicy, Database, MultimapTableDefinition, MultimapTableHandle,
+    PageCompression, ReadOnlyDatabase, ReadableDatabase, RepairSession, StorageBackend,
+    TableDefinition, TableHandle, UntypedMultimapTableHandle, UntypedTableHandle, ValueLimitPolicy,
+    VerifyReport, verify_file,
 };
 pub use error::{
-    CommitError, CompactionError, DatabaseError, Error, SavepointError, SetDurabilityError,
-    StorageError, TableError, TransactionError,
+    AppendError, AppendOnlyError, BackupError, CommitError, CompactionError, DatabaseError,
+    DecodeError, Error, ExportError, MergeError, PrepareError, QueueError, QuotaError,
+    SavepointError, SequenceError, SetDurabilityError, StorageError, TableError, TransactionError,
+    ValidationError, ValueLimitError,
 };
+pub use idempotent_table::IdempotentTable;
+pub use indexed_multimap_table::IndexedMultimapTable;
 pub use multimap_table::{
-    MultimapRange, MultimapTable, MultimapValue, ReadOnlyMultimapTable,
-    ReadOnlyUntypedMultimapTable, ReadableMultimapTable,
+    MultimapExtractIf, MultimapKeys, MultimapRange, MultimapTable, MultimapTableStats,
+    MultimapValue, ReadOnlyMultimapTable, ReadOnlyUntypedMultimapTable, ReadableMultimapTable,
 };
+pub use queue_table::{QueueTable, QueueTableDefinition, ReadOnlyQueueTable};
+pub use quota_table::{Quota, QuotaTable};
+pub use schema_history::{SchemaChange, SchemaChangeKind};
+pub use set_table::{ReadOnlySetTable, SetRange, SetTable, SetTableDefinition};
 pub use table::{
-    Entry, ExtractIf, OccupiedEntry, Range, ReadOnlyTable, ReadOnlyUntypedTable, ReadableTable,
-    ReadableTableMetadata, Table, TableStats, VacantEntry,
+    Cursor, Entry, ExtractIf, ExtractWhile, Keys, MergeOperator, OccupiedEntry, Range,
+    RangeEstimate, ReadOnlyTable, ReadOnlyUntypedTable, ReadableTable, ReadableTableMetadata,
+    Table, TableOperationStats, TableStats, TryRange, UntypedRange, VacantEntry, Values,
 };
-pub use transactions::{DatabaseStats, Durability, ReadTransaction, WriteTransaction};
-pub use tree_store::{AccessGuard, AccessGuardMut, AccessGuardMutInPlace, Savepoint};
-pub use types::{Key, MutInPlaceValue, TypeName, Value};
+pub use transaction_tracker::{ActiveTransaction, TransactionKind};
+#[cfg(feature = "async")]
+pub use transactions::CommitFuture;
+pub use transactions::{
+    CompletedTransaction, DatabaseStats, Durability, ReadTransaction, ReadView, TransactionStats,
+    WritePriority, WriteTransaction,
+};
+pub use tree_store::{
+    AccessGuard, AccessGuardMut, AccessGuardMutInPlace, RangeMut, RangeMutEntry, Savepoint,
+    SavepointInfo,
+};
+pub use types::{
+    EnumRepr, F32, F64, Flags, I16BE, I32BE, I64BE, I128BE, Key, MutInPlaceValue, NumericMut,
+    NumericSliceMut, PrefixKey, TryValue, TypeName, U16BE, U32BE, U64BE, U128BE, Value,
+};
+pub use watch::{Watch, WatchEvent};
+pub use write_batch::WriteBatch;
 
 pub type Result<T = (), E = StorageError> = std::result::Result<T, E>;
 
+mod append_only_table;
+#[cfg(feature = "async")]
+mod async_db;
 pub mod backends;
+mod cached_table;
+mod capped_table;
+mod change_log;
 mod complex_types;
+mod coordinator;
 mod db;
 mod error;
+mod group_commit;
+mod idempotent_table;
+mod indexed_multimap_table;
+pub mod key_encoding;
+pub mod log_targets;
 mod multimap_table;
+mod queue_table;
+mod quota_table;
+mod schema_history;
 mod sealed;
+mod set_table;
 mod table;
 mod transaction_tracker;
 mod transactions;
 mod tree_store;
 mod tuple_types;
 mod types;
+mod watch;
+mod write_batch;
 
 #[cfg(test)]
 fn create_tempfile() -> tempfile::NamedTempFile {
@@ -0,0 +1,142 @@
+//! Targets of the maintenance events logged when the `logging` feature is enabled
+//!
+//! Each event is logged with one of the targets below, and has an `event` field, which names it,
+//! followed by the fields listed here. The targets, event names, and fields are stable, so alerts
+//! can match on them instead of on the message text, which may change. Fields are attached as
+//! [`log` key-values](https://docs.rs/log/latest/log/kv/index.html).
+//!
+//! # [`REPAIR`]
+//!
+//! | `event` | Level | Fields |
+//! |---|---|---|
+//! | `repair_start` | warn | none. The database was not shut down cleanly |
+//! | `repair_rollback` | warn | none. The last commit was incomplete, and is being rolled back |
+//! | `repair_end` | info | `pages_scanned`: number of pages found in use |
+//! | `repair_required` | warn | none. A read-only database needs a repair, so it can't be opened |
+//!
+//! # [`COMPACTION`]
+//!
+//! | `event` | Level | Fields |
+//! |---|---|---|
+//! | `compaction_start` | info | none |
+//! | `compaction_batch` | debug | `batch`: starting from 1, `progress`: whether any pages moved |
+//! | `compaction_cluster` | debug | `hot_pages`, `moved_pages`: pages moved to the start of the file |
+//! | `compaction_end` | info | `batches`, `compacted`: whether the file changed |
+//!
+//! # [`DURABILITY`]
+//!
+//! | `event` | Level | Fields |
+//! |---|---|---|
+//! | `auto_checkpoint` | info | `transaction_id`, `unpersisted_bytes`: a non-durable commit was made durable |
+//! | `allocator_state_not_saved` | warn | none. A full repair will be needed when the database is next opened |
+//! | `flush_failed` | warn | none. A repair may be needed when the database is next opened |
+//!
+//! # [`TRANSACTIONS`]
+//!
+//! | `event` | Level | Fields |
+//! |---|---|---|
+//! | `long_read` | warn | `transaction_id`, `elapsed_ms`, `label`: empty if no label was set |
+//! | `reads_expired` | warn | `count`: read transactions expired by [`Builder::set_read_transaction_max_age()`](crate::Builder::set_read_transaction_max_age) |
+
+/// Opening a database which was not shut down cleanly
+pub const REPAIR: &str = "redb::repair";
+
+/// [`Database::compact()`](crate::Database::compact)
+pub const COMPACTION: &str = "redb::compaction";
+
+/// Changes to how durably data is written, and failures to persist it
+pub const DURABILITY: &str = "redb::durability";
+
+/// Read transactions reported by [`Database::set_long_read_hook()`](crate::Database::set_long_read_hook),
+/// or expired
+pub const TRANSACTIONS: &str = "redb::transactions";
+
+#[cfg(all(test, feature = "logging"))]
+mod tests {
+    use crate::{Database, TableDefinition};
+    use log::kv::{Key, VisitSource};
+    use log::{Level, LevelFilter, Log, Metadata, Record};
+    use std::sync::Mutex;
+    use std::thread::{self, ThreadId};
+    use tempfile::NamedTempFile;
+
+    // (thread, target, fields) of each maintenance event. Other tests may be logging concurrently
+    type Event = (ThreadId, String, Vec<(String, String)>);
+    static EVENTS: Mutex<Vec<Event>> = Mutex::new(vec![]);
+
+    struct Capture;
+
+    impl Log for Capture {
+        fn enabled(&self, metadata: &Metadata) -> bool {
+            metadata.level() <= Level::Debug
+        }
+
+        fn log(&self, record: &Record) {
+            struct Fields(Vec<(String, String)>);
+            impl<'kvs> VisitSource<'kvs> for Fields {
+                fn visit_pair(
+                    &mut self,
+                    key: Key<'kvs>,
+                    value: log::kv::Value<'kvs>,
+                ) -> Result<(), log::kv::Error> {
+                    self.0.push((key.to_string(), value.to_string()));
+                    Ok(())
+                }
+            }
+
+            if record.target().starts_with("redb::") {
+                let mut fields = Fields(vec![]);
+                record.key_values().visit(&mut fields).unwrap();
+                EVENTS.lock().unwrap().push((
+                    thread::current().id(),
+                    record.target().to_string(),
+                    fields.0,
+                ));
+            }
+        }
+
+        fn flush(&self) {}
+    }
+
+    #[test]
+    fn compaction_events() {
+        log::set_logger(&Capture).unwrap();
+        log::set_max_level(LevelFilter::Debug);
+
+        let definition: TableDefinition<u64, &[u8]> = TableDefinition::new("table");
+        let mut db = Database::create(NamedTempFile::new().unwrap()).unwrap();
+        let txn = db.begin_write().unwrap();
+        {
+            let mut table = txn.open_table(definition).unwrap();
+            for i in 0..100 {
+                table.insert(i, [0; 1024].as_slice()).unwrap();
+            }
+        }
+        txn.commit().unwrap();
+        let txn = db.begin_write().unwrap();
+        {
+            let mut table = txn.open_table(definition).unwrap();
+            table.retain(|key, _| key % 2 == 0).unwrap();
+        }
+        txn.commit().unwrap();
+        db.compact().unwrap();
+
+        let events = EVENTS.lock().unwrap();
+        let compaction: Vec<&Vec<(String, String)>> = events
+            .iter()
+            .filter(|(thread, target, _)| {
+                *thread == thread::current().id() && target == super::COMPACTION
+            })
+            .map(|(_, _, fields)| fields)
+            .collect();
+        let names: Vec<&str> = compaction
+            .iter()
+            .map(|fields| fields[0].1.as_str())
+            .collect();
+        assert_eq!(names.first(), Some(&"compaction_start"));
+        assert_eq!(names.last(), Some(&"compaction_end"));
+        assert!(names.contains(&"compaction_batch"));
+        let end = compaction.last().unwrap();
+        assert_eq!(end[2], ("compacted".to_string(), "true".to_string()));
+    }
+}
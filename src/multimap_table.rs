@@ -1,21 +1,53 @@
 use crate::db::TransactionGuard;
 use crate::multimap_table::DynamicCollectionType::{Inline, SubtreeV2};
 use crate::sealed::Sealed;
-use crate::table::{ReadableTableMetadata, TableStats};
+use crate::table::{ReadableTableMetadata, TableOperationStats, TableStats};
 use crate::tree_store::{
     AllPageNumbersBtreeIter, BRANCH, Btree, BtreeHeader, BtreeMut, BtreeRangeIter,
     DynamicCollection, DynamicCollectionType, LEAF, LeafAccessor, MAX_PAIR_LENGTH,
     MAX_VALUE_LENGTH, Page, PageAllocator, PageHint, PageNumber, PageResolver, PageTrackerPolicy,
-    RawBtree, RawLeafBuilder, multimap_btree_stats,
+    RawBtree, RawLeafBuilder, btree_stats, multimap_btree_stats,
 };
 use crate::types::{Key, Value};
-use crate::{AccessGuard, MultimapTableHandle, Result, StorageError, WriteTransaction};
+use crate::{
+    AccessGuard, MultimapTableHandle, Result, StorageError, ValueLimitError, ValueLimitPolicy,
+    WriteTransaction,
+};
 use std::borrow::Borrow;
+use std::collections::VecDeque;
 use std::marker::PhantomData;
 use std::mem;
-use std::ops::{Range, RangeBounds, RangeFull};
+use std::ops::{Bound, Range, RangeBounds, RangeFull};
 use std::sync::{Arc, Mutex};
 
+// Returns the positions of the first entry within `range`, and one past the last entry within it
+fn inline_positions<'r, V: Key + 'static, T: RangeBounds<KR>, KR: Borrow<V::SelfType<'r>>>(
+    accessor: &LeafAccessor<'_>,
+    range: &T,
+) -> (usize, usize) {
+    let start = match range.start_bound() {
+        Bound::Included(start) => {
+            accessor
+                .position::<V>(V::as_bytes(start.borrow()).as_ref())
+                .0
+        }
+        Bound::Excluded(start) => {
+            let (position, found) = accessor.position::<V>(V::as_bytes(start.borrow()).as_ref());
+            if found { position + 1 } else { position }
+        }
+        Bound::Unbounded => 0,
+    };
+    let end = match range.end_bound() {
+        Bound::Included(end) => {
+            let (position, found) = accessor.position::<V>(V::as_bytes(end.borrow()).as_ref());
+            if found { position + 1 } else { position }
+        }
+        Bound::Excluded(end) => accessor.position::<V>(V::as_bytes(end.borrow()).as_ref()).0,
+        Bound::Unbounded => accessor.num_pairs(),
+    };
+    (start, end.max(start))
+}
+
 pub(crate) struct LeafKeyIter<'a, V: Key + 'static> {
     // Kept alive so any Drop side-effects on `data` (e.g. `remove_on_drop`) still run.
     _inline_collection: AccessGuard<'a, &'static DynamicCollection<V>>,
@@ -54,17 +86,30 @@ impl<'a, V: Key> LeafKeyIter<'a, V> {
         }
     }
 
+    fn new_range<'r, T: RangeBounds<KR>, KR: Borrow<V::SelfType<'r>>>(
+        data: AccessGuard<'a, &'static DynamicCollection<V>>,
+        fixed_key_size: Option<usize>,
+        fixed_value_size: Option<usize>,
+        range: &T,
+    ) -> Self {
+        let mut iter = Self::new(data, fixed_key_size, fixed_value_size);
+        let accessor = LeafAccessor::new(iter.inline_bytes(), fixed_key_size, fixed_value_size);
+        let (start, end) = inline_positions::<V, _, _>(&accessor, range);
+        iter.start_entry = isize::try_from(start).unwrap();
+        iter.end_entry = isize::try_from(end).unwrap() - 1;
+        iter
+    }
+
     fn inline_bytes(&self) -> &[u8] {
         &self.page_data[self.inline_range.clone()]
     }
 
     fn num_values(&self) -> u64 {
-        let accessor = LeafAccessor::new(
-            self.inline_bytes(),
-            self.fixed_key_size,
-            self.fixed_value_size,
-        );
-        accessor.num_pairs() as u64
+        if self.end_entry < self.start_entry {
+            0
+        } else {
+            (self.end_entry - self.start_entry + 1).try_into().unwrap()
+        }
     }
 
     fn key_at(&self, n: usize) -> Option<AccessGuard<'static, V>> {
@@ -192,6 +237,35 @@ impl<'a, V: Key + 'static> MultimapValue<'a, V> {
         })
     }
 
+    fn from_collection_range<'r, T: RangeBounds<KR>, KR: Borrow<V::SelfType<'r>>>(
+        collection: AccessGuard<'a, &'static DynamicCollection<V>>,
+        range: &T,
+        guard: Arc<TransactionGuard>,
+        mem: PageResolver,
+    ) -> Result<Self> {
+        Ok(match collection.value().collection_type() {
+            Inline => {
+                let leaf_iter = LeafKeyIter::new_range(
+                    collection,
+                    V::fixed_width(),
+                    <() as Value>::fixed_width(),
+                    range,
+                );
+                Self::new_inline(leaf_iter, guard)
+            }
+            SubtreeV2 => {
+                // The number of values within the range is not tracked, so use the size of the
+                // whole collection as an upper bound
+                let root = collection.value().as_subtree().root;
+                Self::new_subtree(
+                    BtreeRangeIter::new(range, Some(root), mem, PageHint::None)?,
+                    collection.value().get_num_values(),
+                    guard,
+                )
+            }
+        })
+    }
+
     fn from_collection_free_on_drop(
         collection: AccessGuard<'a, &'static DynamicCollection<V>>,
         pages: Vec<PageNumber>,
@@ -231,6 +305,9 @@ impl<'a, V: Key + 'static> MultimapValue<'a, V> {
     /// Returns the number of times this iterator will return `Some(Ok(_))`
     ///
     /// Note that `Some` may be returned from `next()` more than `len()` times if `Some(Err(_))` is returned
+    ///
+    /// For an iterator returned by `get_range()`, this is an upper bound if the key has too many
+    /// values to be stored inline
     pub fn len(&self) -> u64 {
         self.remaining
     }
@@ -265,7 +342,7 @@ impl<'a, V: Key + 'static> Iterator for MultimapValue<'a, V> {
             },
             ValueIterState::InlineLeaf(iter) => iter.next_key()?,
         };
-        self.remaining -= 1;
+        self.remaining = self.remaining.saturating_sub(1);
         Some(Ok(guard))
     }
 }
@@ -289,7 +366,7 @@ impl<V: Key + 'static> DoubleEndedIterator for MultimapValue<'_, V> {
             },
             ValueIterState::InlineLeaf(iter) => iter.next_key_back()?,
         };
-        self.remaining -= 1;
+        self.remaining = self.remaining.saturating_sub(1);
         Some(Ok(guard))
     }
 }
@@ -315,6 +392,236 @@ impl<V: Key + 'static> Drop for MultimapValue<'_, V> {
     }
 }
 
+/// Informational stats about the keys and value sets of a multimap table
+#[derive(Debug, Clone, Copy, Default)]
+pub struct MultimapTableStats {
+    keys: u64,
+    values: u64,
+    max_values_per_key: u64,
+    inline_keys: u64,
+    inline_values: u64,
+    inline_stored_bytes: u64,
+    subtree_keys: u64,
+    subtree_values: u64,
+    subtree_stored_bytes: u64,
+    subtree_pages: u64,
+}
+
+impl MultimapTableStats {
+    // Visits the value collection of every key in `iter`. Value sets stored in their own subtree
+    // are traversed to count their pages and bytes
+    fn collect<K: Key + 'static, V: Key + 'static>(
+        iter: BtreeRangeIter<K, &'static DynamicCollection<V>>,
+        mem: &PageResolver,
+    ) -> Result<Self> {
+        let mut stats = Self::default();
+        for entry in iter {
+            let entry = entry?;
+            let collection = entry.value();
+            let num_values = collection.get_num_values();
+            stats.keys += 1;
+            stats.values += num_values;
+            stats.max_values_per_key = stats.max_values_per_key.max(num_values);
+            match collection.collection_type() {
+                Inline => {
+                    let accessor = LeafAccessor::new(
+                        collection.as_inline(),
+                        V::fixed_width(),
+                        <() as Value>::fixed_width(),
+                    );
+                    stats.inline_keys += 1;
+                    stats.inline_values += num_values;
+                    stats.inline_stored_bytes +=
+                        accessor.length_of_pairs(0, accessor.num_pairs()) as u64;
+                }
+                SubtreeV2 => {
+                    let tree_stats = btree_stats(
+                        Some(collection.as_subtree().root),
+                        mem,
+                        V::fixed_width(),
+                        <() as Value>::fixed_width(),
+                        PageHint::None,
+                    )?;
+                    stats.subtree_keys += 1;
+                    stats.subtree_values += num_values;
+                    stats.subtree_stored_bytes += tree_stats.stored_leaf_bytes;
+                    stats.subtree_pages += tree_stats.leaf_pages + tree_stats.branch_pages;
+                }
+            }
+        }
+        Ok(stats)
+    }
+
+    /// Number of distinct keys
+    pub fn keys(&self) -> u64 {
+        self.keys
+    }
+
+    /// Total number of values, across all keys
+    pub fn values(&self) -> u64 {
+        self.values
+    }
+
+    /// Average number of values stored under a key, or `0.0` if the table is empty
+    #[allow(clippy::cast_precision_loss)]
+    pub fn average_values_per_key(&self) -> f64 {
+        if self.keys == 0 {
+            0.0
+        } else {
+            self.values as f64 / self.keys as f64
+        }
+    }
+
+    /// Largest number of values stored under a single key
+    pub fn max_values_per_key(&self) -> u64 {
+        self.max_values_per_key
+    }
+
+    /// Number of keys whose values are stored inline, next to the key
+    pub fn inline_keys(&self) -> u64 {
+        self.inline_keys
+    }
+
+    /// Number of values stored inline
+    pub fn inline_values(&self) -> u64 {
+        self.inline_values
+    }
+
+    /// Number of bytes consumed by values stored inline
+    pub fn inline_stored_bytes(&self) -> u64 {
+        self.inline_stored_bytes
+    }
+
+    /// Number of keys whose values are stored in a separate btree
+    pub fn subtree_keys(&self) -> u64 {
+        self.subtree_keys
+    }
+
+    /// Number of values stored in separate btrees
+    pub fn subtree_values(&self) -> u64 {
+        self.subtree_values
+    }
+
+    /// Number of bytes consumed by values stored in separate btrees.
+    /// Does not include indexing overhead
+    pub fn subtree_stored_bytes(&self) -> u64 {
+        self.subtree_stored_bytes
+    }
+
+    /// Number of leaf and branch pages used by separate btrees
+    pub fn subtree_pages(&self) -> u64 {
+        self.subtree_pages
+    }
+}
+
+/// Iterator returned by [`MultimapTable::extract_if()`] and [`MultimapTable::extract_from_if()`]
+///
+/// Keys are visited in order. When a key is reached, `predicate` is applied to all of its values,
+/// and the matching values are then removed one at a time as they are read from the iterator
+pub struct MultimapExtractIf<
+    'a,
+    'txn,
+    K: Key + 'static,
+    V: Key + 'static,
+    F: for<'f> FnMut(K::SelfType<'f>, V::SelfType<'f>) -> bool,
+> {
+    table: &'a mut MultimapTable<'txn, K, V>,
+    predicate: F,
+    // Bounds of the keys that have not yet been visited
+    next_start: Bound<Vec<u8>>,
+    end: Bound<Vec<u8>>,
+    current_key: Vec<u8>,
+    // Values of `current_key` which matched `predicate` and have not been returned yet
+    pending: VecDeque<Vec<u8>>,
+    done: bool,
+}
+
+impl<K: Key + 'static, V: Key + 'static, F: for<'f> FnMut(K::SelfType<'f>, V::SelfType<'f>) -> bool>
+    MultimapExtractIf<'_, '_, K, V, F>
+{
+    // Moves to the next key in range which has at least one matching value. Returns false if there
+    // are no more keys
+    fn advance_key(&mut self) -> Result<bool> {
+        while !self.done {
+            let key = {
+                let start = self.next_start.as_ref().map(|x| K::from_bytes(x));
+                let end = self.end.as_ref().map(|x| K::from_bytes(x));
+                let mut iter = self.table.tree.range(&(start, end))?;
+                let Some(entry) = iter.next() else {
+                    self.done = true;
+                    return Ok(false);
+                };
+                entry?.key_data()
+            };
+            for value in self.table.get(K::from_bytes(&key))? {
+                let value = value?;
+                if (self.predicate)(K::from_bytes(&key), value.value()) {
+                    self.pending
+                        .push_back(V::as_bytes(&value.value()).as_ref().to_vec());
+                }
+            }
+            self.next_start = Bound::Excluded(key.clone());
+            self.current_key = key;
+            if !self.pending.is_empty() {
+                return Ok(true);
+            }
+        }
+        Ok(false)
+    }
+}
+
+impl<K: Key + 'static, V: Key + 'static, F: for<'f> FnMut(K::SelfType<'f>, V::SelfType<'f>) -> bool>
+    Iterator for MultimapExtractIf<'_, '_, K, V, F>
+{
+    type Item = Result<(AccessGuard<'static, K>, AccessGuard<'static, V>)>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.pending.is_empty() {
+            match self.advance_key() {
+                Ok(true) => {}
+                Ok(false) => return None,
+                Err(err) => return Some(Err(err)),
+            }
+        }
+        let value = self.pending.pop_front().unwrap();
+        if let Err(err) = self
+            .table
+            .remove(K::from_bytes(&self.current_key), V::from_bytes(&value))
+        {
+            return Some(Err(err));
+        }
+        Some(Ok((
+            AccessGuard::with_owned_value(self.current_key.clone()),
+            AccessGuard::with_owned_value(value),
+        )))
+    }
+}
+
+/// Iterator returned by [`ReadableMultimapTable::keys()`]
+pub struct MultimapKeys<'a, K: Key + 'static, V: Key + 'static> {
+    inner: MultimapRange<'a, K, V>,
+}
+
+impl<'a, K: Key + 'static, V: Key + 'static> Iterator for MultimapKeys<'a, K, V> {
+    type Item = Result<AccessGuard<'a, K>>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.inner
+            .inner
+            .next_key()
+            .map(|x| x.map(|(page, key_range)| AccessGuard::with_page(page, key_range)))
+    }
+}
+
+impl<K: Key + 'static, V: Key + 'static> DoubleEndedIterator for MultimapKeys<'_, K, V> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        self.inner
+            .inner
+            .next_back_key()
+            .map(|x| x.map(|(page, key_range)| AccessGuard::with_page(page, key_range)))
+    }
+}
+
 pub struct MultimapRange<'a, K: Key + 'static, V: Key + 'static> {
     inner: BtreeRangeIter<K, &'static DynamicCollection<V>>,
     mem: PageResolver,
@@ -391,6 +698,7 @@ impl<K: Key + 'static, V: Key + 'static> DoubleEndedIterator for MultimapRange<'
 pub struct MultimapTable<'txn, K: Key + 'static, V: Key + 'static> {
     name: String,
     num_values: u64,
+    max_values_per_key: Option<(u64, ValueLimitPolicy)>,
     transaction: &'txn WriteTransaction,
     freed_pages: Arc<Mutex<Vec<PageNumber>>>,
     allocated_pages: Arc<Mutex<PageTrackerPolicy>>,
@@ -411,6 +719,7 @@ impl<'txn, K: Key + 'static, V: Key + 'static> MultimapTable<'txn, K, V> {
         name: &str,
         table_root: Option<BtreeHeader>,
         num_values: u64,
+        max_values_per_key: Option<(u64, ValueLimitPolicy)>,
         freed_pages: Arc<Mutex<Vec<PageNumber>>>,
         allocated_pages: Arc<Mutex<PageTrackerPolicy>>,
         page_allocator: PageAllocator,
@@ -419,6 +728,7 @@ impl<'txn, K: Key + 'static, V: Key + 'static> MultimapTable<'txn, K, V> {
         MultimapTable {
             name: name.to_string(),
             num_values,
+            max_values_per_key,
             transaction,
             freed_pages: freed_pages.clone(),
             allocated_pages: allocated_pages.clone(),
@@ -441,11 +751,61 @@ impl<'txn, K: Key + 'static, V: Key + 'static> MultimapTable<'txn, K, V> {
 
     /// Add the given value to the mapping of the key
     ///
+    /// If the table was opened with a definition that limits the number of values per key, and the
+    /// key already has that many values, the value is handled according to its
+    /// [`ValueLimitPolicy`]
+    ///
     /// Returns `true` if the key-value pair was present
     pub fn insert<'k, 'v>(
         &mut self,
         key: impl Borrow<K::SelfType<'k>>,
         value: impl Borrow<V::SelfType<'v>>,
+    ) -> Result<bool, ValueLimitError> {
+        Ok(self.insert_and_evict(key, value)?.0)
+    }
+
+    // Same as insert(), but also returns the values which were evicted from the key
+    pub(crate) fn insert_and_evict<'k, 'v>(
+        &mut self,
+        key: impl Borrow<K::SelfType<'k>>,
+        value: impl Borrow<V::SelfType<'v>>,
+    ) -> Result<(bool, Vec<Vec<u8>>), ValueLimitError> {
+        let Some((max, policy)) = self.max_values_per_key else {
+            return Ok((self.insert_unlimited(key, value)?, vec![]));
+        };
+        if policy == ValueLimitPolicy::Reject
+            && self.value_len(key.borrow())? >= max
+            && self
+                .get_range::<&V::SelfType<'v>>(key.borrow(), value.borrow()..=value.borrow())?
+                .next()
+                .is_none()
+        {
+            return Err(ValueLimitError::ValueLimitExceeded(max));
+        }
+        let existed = self.insert_unlimited(key.borrow(), value)?;
+        let mut evicted = vec![];
+        if !existed {
+            while self.value_len(key.borrow())? > max {
+                let mut values = self.get(key.borrow())?;
+                let value = if policy == ValueLimitPolicy::EvictLargest {
+                    values.next_back()
+                } else {
+                    values.next()
+                };
+                let value = V::as_bytes(&value.unwrap()?.value()).as_ref().to_vec();
+                drop(values);
+                self.remove(key.borrow(), V::from_bytes(&value))?;
+                evicted.push(value);
+            }
+        }
+        Ok((existed, evicted))
+    }
+
+    // Same as insert(), but ignores any limit on the number of values per key
+    pub(crate) fn insert_unlimited<'k, 'v>(
+        &mut self,
+        key: impl Borrow<K::SelfType<'k>>,
+        value: impl Borrow<V::SelfType<'v>>,
     ) -> Result<bool> {
         let value_bytes = V::as_bytes(value.borrow());
         let value_bytes_ref = value_bytes.as_ref();
@@ -609,6 +969,150 @@ impl<'txn, K: Key + 'static, V: Key + 'static> MultimapTable<'txn, K, V> {
         Ok(existed)
     }
 
+    /// Add all the given values to the mapping of the key
+    ///
+    /// The values are sorted and then merged into the key's existing values in a single pass: a
+    /// key whose values are stored inline is rebuilt once, rather than once per value, and values
+    /// after the last value already stored are appended to the end of the key's subtree without
+    /// descending from its root. If the table limits the number of values per key, the values are
+    /// instead inserted one at a time, in order, as if by [`Self::insert`].
+    ///
+    /// Returns the number of values which were not already present
+    pub fn insert_many<'k, 'v, I, VB>(
+        &mut self,
+        key: impl Borrow<K::SelfType<'k>>,
+        values: I,
+    ) -> Result<u64, ValueLimitError>
+    where
+        I: IntoIterator<Item = VB>,
+        VB: Borrow<V::SelfType<'v>>,
+    {
+        let key_len = K::as_bytes(key.borrow()).as_ref().len();
+        if key_len > MAX_VALUE_LENGTH {
+            return Err(StorageError::ValueTooLarge(key_len).into());
+        }
+        let mut new_values = vec![];
+        for value in values {
+            let value_bytes = V::as_bytes(value.borrow()).as_ref().to_vec();
+            if value_bytes.len() > MAX_VALUE_LENGTH {
+                return Err(StorageError::ValueTooLarge(value_bytes.len()).into());
+            }
+            if value_bytes.len() + key_len > MAX_PAIR_LENGTH {
+                return Err(StorageError::ValueTooLarge(value_bytes.len() + key_len).into());
+            }
+            new_values.push(value_bytes);
+        }
+        new_values.sort_by(|a, b| V::compare(a, b));
+        new_values.dedup_by(|a, b| V::compare(a, b).is_eq());
+        if new_values.is_empty() {
+            return Ok(0);
+        }
+        if self.max_values_per_key.is_some() {
+            let mut inserted = 0;
+            for value in &new_values {
+                if !self.insert(key.borrow(), V::from_bytes(value))? {
+                    inserted += 1;
+                }
+            }
+            return Ok(inserted);
+        }
+
+        let guard = self.tree.get(key.borrow())?;
+        let subtree_root = guard
+            .as_ref()
+            .filter(|guard| matches!(guard.value().collection_type(), SubtreeV2))
+            .map(|guard| guard.value().as_subtree());
+        let inserted = if let Some(subtree_root) = subtree_root {
+            drop(guard);
+            let mut subtree: BtreeMut<V, ()> = BtreeMut::new(
+                Some(subtree_root),
+                self.transaction.transaction_guard(),
+                self.page_allocator.clone(),
+                self.freed_pages.clone(),
+                self.allocated_pages.clone(),
+            );
+            let mut inserted = 0;
+            for value in &new_values {
+                if subtree.insert_append(&V::from_bytes(value), &())?.is_none() {
+                    inserted += 1;
+                }
+            }
+            let subtree_data =
+                DynamicCollection::<V>::make_subtree_data(subtree.get_root().unwrap());
+            self.tree
+                .insert(key.borrow(), &DynamicCollection::new(&subtree_data))?;
+            inserted
+        } else {
+            let accessor = guard.as_ref().map(|guard| {
+                LeafAccessor::new(
+                    guard.value().as_inline(),
+                    V::fixed_width(),
+                    <() as Value>::fixed_width(),
+                )
+            });
+            let existing = accessor.as_ref().map_or(0, LeafAccessor::num_pairs);
+            // Merge the existing values with the new ones
+            let mut merged: Vec<&[u8]> = Vec::with_capacity(existing + new_values.len());
+            let mut new_iter = new_values.iter().peekable();
+            for i in 0..existing {
+                let current = accessor.as_ref().unwrap().entry(i).unwrap().key();
+                while let Some(value) = new_iter.next_if(|x| V::compare(x, current).is_lt()) {
+                    merged.push(value);
+                }
+                new_iter.next_if(|x| V::compare(x, current).is_eq());
+                merged.push(current);
+            }
+            merged.extend(new_iter.map(Vec::as_slice));
+            let inserted = (merged.len() - existing) as u64;
+
+            let merged_bytes: usize = merged.iter().map(|x| x.len()).sum();
+            let required_inline_bytes = RawLeafBuilder::required_bytes(
+                merged.len(),
+                merged_bytes,
+                V::fixed_width(),
+                <() as Value>::fixed_width(),
+            );
+            if required_inline_bytes < self.page_allocator.get_page_size() / 2 {
+                let mut data = vec![0; required_inline_bytes];
+                let mut builder = RawLeafBuilder::new(
+                    &mut data,
+                    merged.len(),
+                    V::fixed_width(),
+                    <() as Value>::fixed_width(),
+                    merged_bytes,
+                );
+                for value in &merged {
+                    builder.append(value, <() as Value>::as_bytes(&()).as_ref());
+                }
+                drop(builder);
+                drop(guard);
+                let inline_data = DynamicCollection::<V>::make_inline_data(&data);
+                self.tree
+                    .insert(key.borrow(), &DynamicCollection::new(&inline_data))?;
+            } else {
+                let mut subtree: BtreeMut<V, ()> = BtreeMut::new(
+                    None,
+                    self.transaction.transaction_guard(),
+                    self.page_allocator.clone(),
+                    self.freed_pages.clone(),
+                    self.allocated_pages.clone(),
+                );
+                for value in &merged {
+                    subtree.insert_append(&V::from_bytes(value), &())?;
+                }
+                drop(guard);
+                let subtree_data =
+                    DynamicCollection::<V>::make_subtree_data(subtree.get_root().unwrap());
+                self.tree
+                    .insert(key.borrow(), &DynamicCollection::new(&subtree_data))?;
+            }
+            inserted
+        };
+        self.num_values += inserted;
+
+        Ok(inserted)
+    }
+
     /// Removes the given key-value pair
     ///
     /// Returns `true` if the key-value pair was present
@@ -683,57 +1187,7 @@ impl<'txn, K: Key + 'static, V: Key + 'static> MultimapTable<'txn, K, V> {
                 drop(guard);
                 let existed = subtree.remove(value.borrow())?.is_some();
 
-                if let Some(BtreeHeader {
-                    root: new_root,
-                    checksum: new_checksum,
-                    length: new_length,
-                }) = subtree.get_root()
-                {
-                    let page = self.page_allocator.get_page(new_root, PageHint::None)?;
-                    match page.memory()[0] {
-                        LEAF => {
-                            let accessor = LeafAccessor::new(
-                                page.memory(),
-                                V::fixed_width(),
-                                <() as Value>::fixed_width(),
-                            );
-                            let len = accessor.total_length();
-                            if len < self.page_allocator.get_page_size() / 2 {
-                                let inline_data =
-                                    DynamicCollection::<V>::make_inline_data(&page.memory()[..len]);
-                                self.tree
-                                    .insert(key.borrow(), &DynamicCollection::new(&inline_data))?;
-                                drop(page);
-                                let mut allocated_pages = self.allocated_pages.lock().unwrap();
-                                if !self
-                                    .page_allocator
-                                    .free_if_uncommitted(new_root, &mut allocated_pages)
-                                {
-                                    (*self.freed_pages).lock().unwrap().push(new_root);
-                                }
-                            } else {
-                                let subtree_data =
-                                    DynamicCollection::<V>::make_subtree_data(BtreeHeader::new(
-                                        new_root,
-                                        new_checksum,
-                                        accessor.num_pairs() as u64,
-                                    ));
-                                self.tree
-                                    .insert(key.borrow(), &DynamicCollection::new(&subtree_data))?;
-                            }
-                        }
-                        BRANCH => {
-                            let subtree_data = DynamicCollection::<V>::make_subtree_data(
-                                BtreeHeader::new(new_root, new_checksum, new_length),
-                            );
-                            self.tree
-                                .insert(key.borrow(), &DynamicCollection::new(&subtree_data))?;
-                        }
-                        _ => unreachable!(),
-                    }
-                } else {
-                    self.tree.remove(key.borrow())?;
-                }
+                self.store_subtree(key.borrow(), subtree.get_root())?;
 
                 existed
             }
@@ -746,6 +1200,205 @@ impl<'txn, K: Key + 'static, V: Key + 'static> MultimapTable<'txn, K, V> {
         Ok(existed)
     }
 
+    /// Removes the values for the given key that are within `value_range`
+    ///
+    /// The values are removed in a single pass over the key's value collection, rather than with
+    /// one lookup per value, but every page within the range is still read. Returns the number of
+    /// values removed
+    pub fn remove_range<'k, 'v, KR>(
+        &mut self,
+        key: impl Borrow<K::SelfType<'k>>,
+        value_range: impl RangeBounds<KR> + 'v,
+    ) -> Result<u64>
+    where
+        KR: Borrow<V::SelfType<'v>> + 'v,
+    {
+        let Some(guard) = self.tree.get(key.borrow())? else {
+            return Ok(0);
+        };
+        let v = guard.value();
+        let removed = match v.collection_type() {
+            Inline => {
+                let accessor = LeafAccessor::new(
+                    v.as_inline(),
+                    V::fixed_width(),
+                    <() as Value>::fixed_width(),
+                );
+                let (start, end) = inline_positions::<V, _, _>(&accessor, &value_range);
+                let old_num_pairs = accessor.num_pairs();
+                let new_num_pairs = old_num_pairs - (end - start);
+                if start == end {
+                    // Nothing to remove
+                } else if new_num_pairs == 0 {
+                    drop(guard);
+                    self.tree.remove(key.borrow())?;
+                } else {
+                    let new_pairs_len = accessor.length_of_pairs(0, old_num_pairs)
+                        - accessor.length_of_pairs(start, end);
+                    let new_key_len = accessor.length_of_keys(0, old_num_pairs)
+                        - accessor.length_of_keys(start, end);
+                    let required = RawLeafBuilder::required_bytes(
+                        new_num_pairs,
+                        new_pairs_len,
+                        V::fixed_width(),
+                        <() as Value>::fixed_width(),
+                    );
+                    let mut new_data = vec![0; required];
+                    let mut builder = RawLeafBuilder::new(
+                        &mut new_data,
+                        new_num_pairs,
+                        V::fixed_width(),
+                        <() as Value>::fixed_width(),
+                        new_key_len,
+                    );
+                    for i in (0..start).chain(end..old_num_pairs) {
+                        let entry = accessor.entry(i).unwrap();
+                        builder.append(entry.key(), entry.value());
+                    }
+                    drop(builder);
+                    drop(guard);
+
+                    let inline_data = DynamicCollection::<V>::make_inline_data(&new_data);
+                    self.tree
+                        .insert(key.borrow(), &DynamicCollection::new(&inline_data))?;
+                }
+                (end - start) as u64
+            }
+            SubtreeV2 => {
+                let old_num_values = v.get_num_values();
+                let mut subtree: BtreeMut<V, ()> = BtreeMut::new(
+                    Some(v.as_subtree()),
+                    self.transaction.transaction_guard(),
+                    self.page_allocator.clone(),
+                    self.freed_pages.clone(),
+                    self.allocated_pages.clone(),
+                );
+                drop(guard);
+                subtree.retain_in(|_, ()| false, value_range)?;
+                let new_root = subtree.get_root();
+                let removed = old_num_values - new_root.map_or(0, |header| header.length);
+                self.store_subtree(key.borrow(), new_root)?;
+                removed
+            }
+        };
+        self.num_values -= removed;
+
+        Ok(removed)
+    }
+
+    // Stores the value collection of `key` after its subtree has been modified, moving the values
+    // back inline if they now fit within half a page
+    fn store_subtree(&mut self, key: &K::SelfType<'_>, root: Option<BtreeHeader>) -> Result {
+        if let Some(BtreeHeader {
+            root: new_root,
+            checksum: new_checksum,
+            length: new_length,
+        }) = root
+        {
+            let page = self.page_allocator.get_page(new_root, PageHint::None)?;
+            match page.memory()[0] {
+                LEAF => {
+                    let accessor = LeafAccessor::new(
+                        page.memory(),
+                        V::fixed_width(),
+                        <() as Value>::fixed_width(),
+                    );
+                    let len = accessor.total_length();
+                    if len < self.page_allocator.get_page_size() / 2 {
+                        let inline_data =
+                            DynamicCollection::<V>::make_inline_data(&page.memory()[..len]);
+                        self.tree
+                            .insert(key, &DynamicCollection::new(&inline_data))?;
+                        drop(page);
+                        let mut allocated_pages = self.allocated_pages.lock().unwrap();
+                        if !self
+                            .page_allocator
+                            .free_if_uncommitted(new_root, &mut allocated_pages)
+                        {
+                            (*self.freed_pages).lock().unwrap().push(new_root);
+                        }
+                    } else {
+                        let subtree_data = DynamicCollection::<V>::make_subtree_data(
+                            BtreeHeader::new(new_root, new_checksum, accessor.num_pairs() as u64),
+                        );
+                        self.tree
+                            .insert(key, &DynamicCollection::new(&subtree_data))?;
+                    }
+                }
+                BRANCH => {
+                    let subtree_data = DynamicCollection::<V>::make_subtree_data(BtreeHeader::new(
+                        new_root,
+                        new_checksum,
+                        new_length,
+                    ));
+                    self.tree
+                        .insert(key, &DynamicCollection::new(&subtree_data))?;
+                }
+                _ => unreachable!(),
+            }
+        } else {
+            self.tree.remove(key)?;
+        }
+
+        Ok(())
+    }
+
+    /// Returns an iterator over the values for the given key that are within `value_range`.
+    /// Values are in ascending order.
+    ///
+    /// Only the part of the key's value collection within the range is read
+    pub fn get_range<'a, 'v, KR>(
+        &self,
+        key: impl Borrow<K::SelfType<'a>>,
+        value_range: impl RangeBounds<KR>,
+    ) -> Result<MultimapValue<'_, V>>
+    where
+        KR: Borrow<V::SelfType<'v>>,
+    {
+        let guard = self.transaction.transaction_guard();
+        let iter = if let Some(collection) = self.tree.get(key.borrow())? {
+            MultimapValue::from_collection_range(
+                collection,
+                &value_range,
+                guard,
+                self.page_allocator.resolver(),
+            )?
+        } else {
+            MultimapValue::new_subtree(
+                BtreeRangeIter::new::<RangeFull, &V::SelfType<'_>>(
+                    &(..),
+                    None,
+                    self.page_allocator.resolver(),
+                    PageHint::None,
+                )?,
+                0,
+                guard,
+            )
+        };
+
+        Ok(iter)
+    }
+
+    /// Retrieves stats about the keys of the table and how their values are stored
+    ///
+    /// This visits every key, and every page of the value sets which are stored in their own btree
+    pub fn multimap_stats(&self) -> Result<MultimapTableStats> {
+        MultimapTableStats::collect(
+            self.tree.range::<RangeFull, K::SelfType<'_>>(&(..))?,
+            &self.page_allocator.resolver(),
+        )
+    }
+
+    /// Returns the number of values stored under the given key
+    ///
+    /// The count is read from the key's value collection, so the values are not iterated
+    pub fn value_len<'a>(&self, key: impl Borrow<K::SelfType<'a>>) -> Result<u64> {
+        Ok(self
+            .tree
+            .get(key.borrow())?
+            .map_or(0, |collection| collection.value().get_num_values()))
+    }
+
     /// Removes all values for the given key
     ///
     /// Returns an iterator over the removed values. Values are in ascending order.
@@ -797,6 +1450,66 @@ impl<'txn, K: Key + 'static, V: Key + 'static> MultimapTable<'txn, K, V> {
 
         Ok(iter)
     }
+    /// Applies `predicate` to all key-value pairs. All pairs for which `predicate` evaluates to
+    /// `true` are returned in an iterator, and those which are read from the iterator are removed
+    ///
+    /// Note: values not read from the iterator will not be removed
+    pub fn extract_if<F: for<'f> FnMut(K::SelfType<'f>, V::SelfType<'f>) -> bool>(
+        &mut self,
+        predicate: F,
+    ) -> Result<MultimapExtractIf<'_, 'txn, K, V, F>> {
+        self.extract_from_if::<K::SelfType<'_>, F>(.., predicate)
+    }
+
+    /// Applies `predicate` to all key-value pairs whose key is in the specified range. All pairs
+    /// for which `predicate` evaluates to `true` are returned in an iterator, and those which are
+    /// read from the iterator are removed
+    ///
+    /// Note: values not read from the iterator will not be removed
+    pub fn extract_from_if<'a, KR, F: for<'f> FnMut(K::SelfType<'f>, V::SelfType<'f>) -> bool>(
+        &mut self,
+        range: impl RangeBounds<KR> + 'a,
+        predicate: F,
+    ) -> Result<MultimapExtractIf<'_, 'txn, K, V, F>>
+    where
+        KR: Borrow<K::SelfType<'a>> + 'a,
+    {
+        let to_bytes = |bound: Bound<&KR>| bound.map(|x| K::as_bytes(x.borrow()).as_ref().to_vec());
+        Ok(MultimapExtractIf {
+            next_start: to_bytes(range.start_bound()),
+            end: to_bytes(range.end_bound()),
+            table: self,
+            predicate,
+            current_key: vec![],
+            pending: VecDeque::new(),
+            done: false,
+        })
+    }
+
+    /// Applies `predicate` to all key-value pairs. All pairs for which `predicate` evaluates to
+    /// `false` are removed.
+    pub fn retain<F: for<'f> FnMut(K::SelfType<'f>, V::SelfType<'f>) -> bool>(
+        &mut self,
+        predicate: F,
+    ) -> Result {
+        self.retain_in::<K::SelfType<'_>, F>(.., predicate)
+    }
+
+    /// Applies `predicate` to all key-value pairs whose key is in the specified range. All pairs
+    /// for which `predicate` evaluates to `false` are removed.
+    pub fn retain_in<'a, KR, F: for<'f> FnMut(K::SelfType<'f>, V::SelfType<'f>) -> bool>(
+        &mut self,
+        range: impl RangeBounds<KR> + 'a,
+        mut predicate: F,
+    ) -> Result
+    where
+        KR: Borrow<K::SelfType<'a>> + 'a,
+    {
+        for entry in self.extract_from_if(range, |k, v| !predicate(k, v))? {
+            entry?;
+        }
+        Ok(())
+    }
 }
 
 impl<K: Key + 'static, V: Key + 'static> ReadableTableMetadata for MultimapTable<'_, K, V> {
@@ -816,6 +1529,8 @@ impl<K: Key + 'static, V: Key + 'static> ReadableTableMetadata for MultimapTable
             stored_leaf_bytes: tree_stats.stored_leaf_bytes,
             metadata_bytes: tree_stats.metadata_bytes,
             fragmented_bytes: tree_stats.fragmented_bytes,
+            leaf_fill_histogram: tree_stats.leaf_fill_histogram,
+            largest_leaf_pages: tree_stats.largest_leaf_pages,
         })
     }
 
@@ -863,8 +1578,12 @@ impl<K: Key + 'static, V: Key + 'static> Sealed for MultimapTable<'_, K, V> {}
 
 impl<K: Key + 'static, V: Key + 'static> Drop for MultimapTable<'_, K, V> {
     fn drop(&mut self) {
-        self.transaction
-            .close_table(&self.name, &self.tree, self.num_values);
+        self.transaction.close_table(
+            &self.name,
+            &self.tree,
+            self.num_values,
+            TableOperationStats::default(),
+        );
     }
 }
 
@@ -882,6 +1601,19 @@ pub trait ReadableMultimapTable<K: Key + 'static, V: Key + 'static>: ReadableTab
     fn iter(&self) -> Result<MultimapRange<'_, K, V>> {
         self.range::<K::SelfType<'_>>(..)
     }
+
+    /// Returns a double-ended iterator over the distinct keys in the specified range
+    ///
+    /// Only the keys are read, and the values stored under them are skipped, so this is faster
+    /// than [`ReadableMultimapTable::range()`]
+    fn keys<'a, KR>(&self, range: impl RangeBounds<KR> + 'a) -> Result<MultimapKeys<'_, K, V>>
+    where
+        KR: Borrow<K::SelfType<'a>> + 'a,
+    {
+        Ok(MultimapKeys {
+            inner: self.range(range)?,
+        })
+    }
 }
 
 /// A read-only untyped multimap table
@@ -914,6 +1646,8 @@ impl ReadableTableMetadata for ReadOnlyUntypedMultimapTable {
             stored_leaf_bytes: tree_stats.stored_leaf_bytes,
             metadata_bytes: tree_stats.metadata_bytes,
             fragmented_bytes: tree_stats.fragmented_bytes,
+            leaf_fill_histogram: tree_stats.leaf_fill_histogram,
+            largest_leaf_pages: tree_stats.largest_leaf_pages,
         })
     }
 
@@ -974,6 +1708,61 @@ impl<K: Key + 'static, V: Key + 'static> ReadOnlyMultimapTable<K, V> {
         })
     }
 
+    /// Returns an iterator over the values for the given key that are within `value_range`.
+    /// Values are in ascending order.
+    ///
+    /// Only the part of the key's value collection within the range is read
+    pub fn get_range<'a, 'v, KR>(
+        &self,
+        key: impl Borrow<K::SelfType<'a>>,
+        value_range: impl RangeBounds<KR>,
+    ) -> Result<MultimapValue<'static, V>>
+    where
+        KR: Borrow<V::SelfType<'v>>,
+    {
+        let iter = if let Some(collection) = self.tree.get(key.borrow())? {
+            MultimapValue::from_collection_range(
+                collection,
+                &value_range,
+                self.transaction_guard.clone(),
+                self.mem.clone(),
+            )?
+        } else {
+            MultimapValue::new_subtree(
+                BtreeRangeIter::new::<RangeFull, &V::SelfType<'_>>(
+                    &(..),
+                    None,
+                    self.mem.clone(),
+                    PageHint::None,
+                )?,
+                0,
+                self.transaction_guard.clone(),
+            )
+        };
+
+        Ok(iter)
+    }
+
+    /// Retrieves stats about the keys of the table and how their values are stored
+    ///
+    /// This visits every key, and every page of the value sets which are stored in their own btree
+    pub fn multimap_stats(&self) -> Result<MultimapTableStats> {
+        MultimapTableStats::collect(
+            self.tree.range::<RangeFull, K::SelfType<'_>>(&(..))?,
+            &self.mem,
+        )
+    }
+
+    /// Returns the number of values stored under the given key
+    ///
+    /// The count is read from the key's value collection, so the values are not iterated
+    pub fn value_len<'a>(&self, key: impl Borrow<K::SelfType<'a>>) -> Result<u64> {
+        Ok(self
+            .tree
+            .get(key.borrow())?
+            .map_or(0, |collection| collection.value().get_num_values()))
+    }
+
     /// This method is like [`ReadableMultimapTable::get()`], but the iterator is reference counted and keeps the transaction
     /// alive until it is dropped.
     pub fn get<'a>(&self, key: impl Borrow<K::SelfType<'a>>) -> Result<MultimapValue<'static, V>> {
@@ -1031,6 +1820,8 @@ impl<K: Key + 'static, V: Key + 'static> ReadableTableMetadata for ReadOnlyMulti
             stored_leaf_bytes: tree_stats.stored_leaf_bytes,
             metadata_bytes: tree_stats.metadata_bytes,
             fragmented_bytes: tree_stats.fragmented_bytes,
+            leaf_fill_histogram: tree_stats.leaf_fill_histogram,
+            largest_leaf_pages: tree_stats.largest_leaf_pages,
         })
     }
 
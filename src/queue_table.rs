@@ -0,0 +1,244 @@
+use crate::db::TableHandle;
+use crate::sealed::Sealed;
+use crate::{
+    AccessGuard, QueueError, ReadOnlyTable, ReadableTable, ReadableTableMetadata, Result, Table,
+    TableDefinition, TableStats, Value, Values,
+};
+use std::borrow::Borrow;
+use std::fmt::{Debug, Display, Formatter};
+
+// Position assigned to the first element pushed onto an empty queue. Starting in the middle of the
+// key space lets the queue grow in both directions
+const INITIAL_POSITION: u64 = 1 << 63;
+
+/// Defines the name and value type of a queue table
+///
+/// A [`QueueTableDefinition`] should be opened for use by calling
+/// [`ReadTransaction::open_queue_table`](crate::ReadTransaction::open_queue_table) or
+/// [`WriteTransaction::open_queue_table`](crate::WriteTransaction::open_queue_table)
+///
+/// A queue table is stored as a table keyed by the `u64` position of each element, and can also
+/// be opened with a [`TableDefinition<u64, V>`] of the same name.
+pub struct QueueTableDefinition<'a, V: Value + 'static> {
+    inner: TableDefinition<'a, u64, V>,
+}
+
+impl<'a, V: Value + 'static> QueueTableDefinition<'a, V> {
+    /// Construct a new queue table with given `name`
+    ///
+    /// # Panics
+    ///
+    /// Panics if `name` is empty
+    pub const fn new(name: &'a str) -> Self {
+        Self {
+            inner: TableDefinition::new(name),
+        }
+    }
+
+    pub(crate) fn table_definition(self) -> TableDefinition<'a, u64, V> {
+        self.inner
+    }
+}
+
+impl<V: Value + 'static> TableHandle for QueueTableDefinition<'_, V> {
+    fn name(&self) -> &str {
+        self.inner.name()
+    }
+}
+
+impl<V: Value> Sealed for QueueTableDefinition<'_, V> {}
+
+impl<V: Value + 'static> Clone for QueueTableDefinition<'_, V> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<V: Value + 'static> Copy for QueueTableDefinition<'_, V> {}
+
+impl<V: Value + 'static> Display for QueueTableDefinition<'_, V> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}<{}>", self.name(), V::type_name().name())
+    }
+}
+
+/// A double-ended queue
+///
+/// The positions of the front and back elements are cached when the table is opened, so pushing
+/// and popping never needs to search for the ends of the queue
+pub struct QueueTable<'txn, V: Value + 'static> {
+    table: Table<'txn, u64, V>,
+    // Positions of the front and back elements, or None if the queue is empty
+    ends: Option<(u64, u64)>,
+}
+
+impl<'txn, V: Value + 'static> QueueTable<'txn, V> {
+    pub(crate) fn new(table: Table<'txn, u64, V>) -> Result<Self> {
+        let front = table.first()?.map(|(k, _)| k.value());
+        let back = table.last()?.map(|(k, _)| k.value());
+        Ok(Self {
+            table,
+            ends: front.zip(back),
+        })
+    }
+
+    /// Appends an element to the back of the queue
+    ///
+    /// Returns [`QueueError::QueueFull`] if 2^63 positions have been used at the back of the queue
+    pub fn push_back<'v>(&mut self, value: impl Borrow<V::SelfType<'v>>) -> Result<(), QueueError> {
+        let ends = match self.ends {
+            Some((front, back)) => (front, back.checked_add(1).ok_or(QueueError::QueueFull)?),
+            None => (INITIAL_POSITION, INITIAL_POSITION),
+        };
+        self.table.insert(ends.1, value)?;
+        self.ends = Some(ends);
+        Ok(())
+    }
+
+    /// Prepends an element to the front of the queue
+    ///
+    /// Returns [`QueueError::QueueFull`] if 2^63 positions have been used at the front of the
+    /// queue
+    pub fn push_front<'v>(
+        &mut self,
+        value: impl Borrow<V::SelfType<'v>>,
+    ) -> Result<(), QueueError> {
+        let ends = match self.ends {
+            Some((front, back)) => (front.checked_sub(1).ok_or(QueueError::QueueFull)?, back),
+            None => (INITIAL_POSITION, INITIAL_POSITION),
+        };
+        self.table.insert(ends.0, value)?;
+        self.ends = Some(ends);
+        Ok(())
+    }
+
+    /// Removes and returns the element at the front of the queue
+    pub fn pop_front(&mut self) -> Result<Option<AccessGuard<'_, V>>> {
+        let Some((front, back)) = self.ends else {
+            return Ok(None);
+        };
+        let removed = self.table.remove(front)?;
+        self.ends = (front != back).then(|| (front + 1, back));
+        Ok(removed)
+    }
+
+    /// Removes and returns the element at the back of the queue
+    pub fn pop_back(&mut self) -> Result<Option<AccessGuard<'_, V>>> {
+        let Some((front, back)) = self.ends else {
+            return Ok(None);
+        };
+        let removed = self.table.remove(back)?;
+        self.ends = (front != back).then(|| (front, back - 1));
+        Ok(removed)
+    }
+
+    /// Returns the element at the front of the queue, without removing it
+    pub fn peek(&self) -> Result<Option<AccessGuard<'_, V>>> {
+        self.peek_front()
+    }
+
+    /// Returns the element at the front of the queue, without removing it
+    pub fn peek_front(&self) -> Result<Option<AccessGuard<'_, V>>> {
+        match self.ends {
+            Some((front, _)) => self.table.get(front),
+            None => Ok(None),
+        }
+    }
+
+    /// Returns the element at the back of the queue, without removing it
+    pub fn peek_back(&self) -> Result<Option<AccessGuard<'_, V>>> {
+        match self.ends {
+            Some((_, back)) => self.table.get(back),
+            None => Ok(None),
+        }
+    }
+
+    /// Returns a double-ended iterator over the elements of the queue, from front to back
+    pub fn iter(&self) -> Result<Values<'_, u64, V>> {
+        self.table.values::<u64>(..)
+    }
+}
+
+impl<V: Value + 'static> TableHandle for QueueTable<'_, V> {
+    fn name(&self) -> &str {
+        self.table.name()
+    }
+}
+
+impl<V: Value + 'static> Sealed for QueueTable<'_, V> {}
+
+impl<V: Value + 'static> ReadableTableMetadata for QueueTable<'_, V> {
+    fn stats(&self) -> Result<TableStats> {
+        self.table.stats()
+    }
+
+    fn len(&self) -> Result<u64> {
+        self.table.len()
+    }
+}
+
+impl<V: Value + 'static> Debug for QueueTable<'_, V> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("QueueTable")
+            .field("name", &self.table.name())
+            .field("ends", &self.ends)
+            .finish_non_exhaustive()
+    }
+}
+
+/// A read-only double-ended queue
+pub struct ReadOnlyQueueTable<V: Value + 'static> {
+    table: ReadOnlyTable<u64, V>,
+}
+
+impl<V: Value + 'static> ReadOnlyQueueTable<V> {
+    pub(crate) fn new(table: ReadOnlyTable<u64, V>) -> Self {
+        Self { table }
+    }
+
+    /// Returns the element at the front of the queue
+    pub fn peek(&self) -> Result<Option<AccessGuard<'_, V>>> {
+        self.peek_front()
+    }
+
+    /// Returns the element at the front of the queue
+    pub fn peek_front(&self) -> Result<Option<AccessGuard<'_, V>>> {
+        Ok(self.table.first()?.map(|(_, v)| v))
+    }
+
+    /// Returns the element at the back of the queue
+    pub fn peek_back(&self) -> Result<Option<AccessGuard<'_, V>>> {
+        Ok(self.table.last()?.map(|(_, v)| v))
+    }
+
+    /// Returns a double-ended iterator over the elements of the queue, from front to back
+    pub fn iter(&self) -> Result<Values<'_, u64, V>> {
+        self.table.values::<u64>(..)
+    }
+}
+
+impl<V: Value + 'static> TableHandle for ReadOnlyQueueTable<V> {
+    fn name(&self) -> &str {
+        self.table.name()
+    }
+}
+
+impl<V: Value + 'static> Sealed for ReadOnlyQueueTable<V> {}
+
+impl<V: Value + 'static> ReadableTableMetadata for ReadOnlyQueueTable<V> {
+    fn stats(&self) -> Result<TableStats> {
+        self.table.stats()
+    }
+
+    fn len(&self) -> Result<u64> {
+        self.table.len()
+    }
+}
+
+impl<V: Value + 'static> Debug for ReadOnlyQueueTable<V> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("ReadOnlyQueueTable")
+            .field("name", &self.table.name())
+            .finish_non_exhaustive()
+    }
+}
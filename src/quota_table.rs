@@ -0,0 +1,197 @@
+use crate::{
+    AccessGuard, Key, QuotaError, ReadableTable, ReadableTableMetadata, Result, Table,
+    TableDefinition, TableError, TableHandle, Value, WriteTransaction,
+};
+use std::borrow::Borrow;
+use std::fmt::{Debug, Formatter};
+
+/// Limits on the size of a [`QuotaTable`]
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub struct Quota {
+    max_entries: Option<u64>,
+    max_bytes: Option<u64>,
+}
+
+impl Quota {
+    /// A quota with no limits
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Limit the table to at most this many entries
+    #[must_use]
+    pub fn max_entries(mut self, entries: u64) -> Self {
+        self.max_entries = Some(entries);
+        self
+    }
+
+    /// Limit the table to at most this many bytes of keys and values, as reported by
+    /// [`TableStats::stored_bytes`](crate::TableStats::stored_bytes)
+    #[must_use]
+    pub fn max_bytes(mut self, bytes: u64) -> Self {
+        self.max_bytes = Some(bytes);
+        self
+    }
+
+    /// The maximum number of entries, if limited
+    pub fn entries_limit(&self) -> Option<u64> {
+        self.max_entries
+    }
+
+    /// The maximum number of bytes of keys and values, if limited
+    pub fn bytes_limit(&self) -> Option<u64> {
+        self.max_bytes
+    }
+}
+
+/// A table whose size is limited by a [`Quota`]
+///
+/// This is intended for applications which store data for many tenants in one database, and need
+/// to prevent any one of them from filling the database file. An insert which would take the table
+/// over its quota fails with [`QuotaError::QuotaExceeded`], and leaves the table unchanged.
+///
+/// A table opened with a limit on its bytes is scanned when it is opened, to measure its size. If
+/// the table is already over its quota, entries can still be removed, or replaced with smaller
+/// values.
+///
+/// The quota is not stored in the database, and is only enforced by this handle. Opening the same
+/// table with [`WriteTransaction::open_table`], or as a `QuotaTable` with a larger quota, allows it
+/// to grow past the quota, so every write to a table which must stay within its quota should go
+/// through a `QuotaTable`.
+///
+/// # Examples
+///
+/// ```rust
+/// use redb::*;
+/// # use tempfile::NamedTempFile;
+/// const UPLOADS: TableDefinition<u64, &[u8]> = TableDefinition::new("tenant_uploads");
+///
+/// # fn main() -> Result<(), Error> {
+/// # #[cfg(not(target_os = "wasi"))]
+/// # let tmpfile = NamedTempFile::new().unwrap();
+/// # #[cfg(target_os = "wasi")]
+/// # let tmpfile = NamedTempFile::new_in("/tmp").unwrap();
+/// # let filename = tmpfile.path();
+/// let db = Database::create(filename)?;
+///
+/// let write_txn = db.begin_write()?;
+/// {
+///     let quota = Quota::new().max_entries(1);
+///     let mut uploads = QuotaTable::open(&write_txn, UPLOADS, quota)?;
+///     uploads.insert(0, [1, 2, 3].as_slice())?;
+///     assert!(matches!(
+///         uploads.insert(1, [4, 5, 6].as_slice()),
+///         Err(QuotaError::QuotaExceeded)
+///     ));
+/// }
+/// write_txn.commit()?;
+/// # Ok(())
+/// # }
+/// ```
+pub struct QuotaTable<'txn, K: Key + 'static, V: Value + 'static> {
+    table: Table<'txn, K, V>,
+    quota: Quota,
+    // Bytes of keys and values in the table. Only tracked if the quota limits bytes
+    stored_bytes: u64,
+}
+
+impl<'txn, K: Key + 'static, V: Value + 'static> QuotaTable<'txn, K, V> {
+    /// Open the given table, which is created if it does not exist
+    pub fn open(
+        transaction: &'txn WriteTransaction,
+        definition: TableDefinition<K, V>,
+        quota: Quota,
+    ) -> Result<Self, TableError> {
+        let table = transaction.open_table(definition)?;
+        let stored_bytes = if quota.max_bytes.is_some() {
+            table.stats()?.stored_bytes()
+        } else {
+            0
+        };
+        Ok(Self {
+            table,
+            quota,
+            stored_bytes,
+        })
+    }
+
+    /// Insert mapping from the given key to the given value
+    ///
+    /// Returns the old value, if the key was present in the table. Returns
+    /// [`QuotaError::QuotaExceeded`], and leaves the table unchanged, if the table would be over
+    /// its quota after the insert
+    pub fn insert<'k, 'v>(
+        &mut self,
+        key: impl Borrow<K::SelfType<'k>>,
+        value: impl Borrow<V::SelfType<'v>>,
+    ) -> Result<Option<AccessGuard<'_, V>>, QuotaError> {
+        if self.quota == Quota::new() {
+            return Ok(self.table.insert(key, value)?);
+        }
+        let old_value_len = self
+            .table
+            .get(key.borrow())?
+            .map(|old| V::as_bytes(&old.value()).as_ref().len() as u64);
+        if let Some(max) = self.quota.max_entries
+            && old_value_len.is_none()
+            && self.table.len()? >= max
+        {
+            return Err(QuotaError::QuotaExceeded);
+        }
+        let mut stored_bytes = self.stored_bytes;
+        if let Some(max) = self.quota.max_bytes {
+            let value_len = V::as_bytes(value.borrow()).as_ref().len() as u64;
+            stored_bytes = if let Some(old_len) = old_value_len {
+                self.stored_bytes - old_len + value_len
+            } else {
+                let key_len = K::as_bytes(key.borrow()).as_ref().len() as u64;
+                self.stored_bytes + key_len + value_len
+            };
+            // A table which is already over its quota may still shrink
+            if stored_bytes > max && stored_bytes > self.stored_bytes {
+                return Err(QuotaError::QuotaExceeded);
+            }
+        }
+        let old = self.table.insert(key, value)?;
+        // Only counted once the insert has succeeded
+        self.stored_bytes = stored_bytes;
+        Ok(old)
+    }
+
+    /// Removes the given key
+    ///
+    /// Returns the old value, if the key was present in the table
+    pub fn remove<'a>(
+        &mut self,
+        key: impl Borrow<K::SelfType<'a>>,
+    ) -> Result<Option<AccessGuard<'_, V>>> {
+        let key_len = K::as_bytes(key.borrow()).as_ref().len() as u64;
+        let removed = self.table.remove(key)?;
+        if self.quota.max_bytes.is_some()
+            && let Some(old) = &removed
+        {
+            let value_len = V::as_bytes(&old.value()).as_ref().len() as u64;
+            self.stored_bytes -= key_len + value_len;
+        }
+        Ok(removed)
+    }
+
+    /// The quota of the table
+    pub fn quota(&self) -> Quota {
+        self.quota
+    }
+
+    /// The underlying table
+    pub fn table(&self) -> &Table<'txn, K, V> {
+        &self.table
+    }
+}
+
+impl<K: Key + 'static, V: Value + 'static> Debug for QuotaTable<'_, K, V> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("QuotaTable")
+            .field("table", &self.table.name())
+            .field("quota", &self.quota)
+            .finish_non_exhaustive()
+    }
+}
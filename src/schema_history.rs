@@ -0,0 +1,133 @@
+use crate::StorageError;
+use crate::tree_store::TableType;
+
+// Format:
+// 1 byte: kind (0 = created, 1 = deleted, 2 = renamed)
+// 1 byte: table type (0 = normal, 1 = multimap)
+// 8 bytes: transaction id
+// 4 bytes: table name length
+// n bytes: table name
+// If renamed:
+// 4 bytes: new table name length
+// n bytes: new table name
+const CREATED: u8 = 0;
+const DELETED: u8 = 1;
+const RENAMED: u8 = 2;
+const NORMAL: u8 = 0;
+const MULTIMAP: u8 = 1;
+
+/// The kind of change made to a table
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum SchemaChangeKind {
+    /// The table was created
+    Created,
+    /// The table was deleted
+    Deleted,
+    /// The table was renamed to `new_name`
+    Renamed { new_name: String },
+}
+
+/// A change to the set of tables in a database, as recorded by [`crate::Database::schema_history`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SchemaChange {
+    transaction_id: u64,
+    table_name: String,
+    multimap: bool,
+    kind: SchemaChangeKind,
+}
+
+impl SchemaChange {
+    pub(crate) fn new(
+        transaction_id: u64,
+        table_name: &str,
+        table_type: TableType,
+        kind: SchemaChangeKind,
+    ) -> Self {
+        Self {
+            transaction_id,
+            table_name: table_name.to_string(),
+            multimap: table_type == TableType::Multimap,
+            kind,
+        }
+    }
+
+    /// Id of the transaction which made the change
+    pub fn transaction_id(&self) -> u64 {
+        self.transaction_id
+    }
+
+    /// Name of the table, before the change was made
+    pub fn table_name(&self) -> &str {
+        &self.table_name
+    }
+
+    /// Returns `true` if the table is a multimap table
+    pub fn is_multimap(&self) -> bool {
+        self.multimap
+    }
+
+    /// The kind of change
+    pub fn kind(&self) -> &SchemaChangeKind {
+        &self.kind
+    }
+
+    pub(crate) fn to_bytes(&self) -> Vec<u8> {
+        let mut result = vec![];
+        result.push(match self.kind {
+            SchemaChangeKind::Created => CREATED,
+            SchemaChangeKind::Deleted => DELETED,
+            SchemaChangeKind::Renamed { .. } => RENAMED,
+        });
+        result.push(if self.multimap { MULTIMAP } else { NORMAL });
+        result.extend_from_slice(&self.transaction_id.to_le_bytes());
+        push_string(&mut result, &self.table_name);
+        if let SchemaChangeKind::Renamed { new_name } = &self.kind {
+            push_string(&mut result, new_name);
+        }
+        result
+    }
+
+    pub(crate) fn from_bytes(data: &[u8]) -> Result<Self, StorageError> {
+        let corrupted = || StorageError::Corrupted("Schema history entry is corrupted".to_string());
+        if data.len() < 10 {
+            return Err(corrupted());
+        }
+        let multimap = match data[1] {
+            NORMAL => false,
+            MULTIMAP => true,
+            _ => return Err(corrupted()),
+        };
+        let transaction_id = u64::from_le_bytes(data[2..10].try_into().unwrap());
+        let mut offset = 10;
+        let table_name = read_string(data, &mut offset).ok_or_else(corrupted)?;
+        let kind = match data[0] {
+            CREATED => SchemaChangeKind::Created,
+            DELETED => SchemaChangeKind::Deleted,
+            RENAMED => SchemaChangeKind::Renamed {
+                new_name: read_string(data, &mut offset).ok_or_else(corrupted)?,
+            },
+            _ => return Err(corrupted()),
+        };
+        Ok(Self {
+            transaction_id,
+            table_name,
+            multimap,
+            kind,
+        })
+    }
+}
+
+fn push_string(output: &mut Vec<u8>, value: &str) {
+    output.extend_from_slice(&u32::try_from(value.len()).unwrap().to_le_bytes());
+    output.extend_from_slice(value.as_bytes());
+}
+
+fn read_string(data: &[u8], offset: &mut usize) -> Option<String> {
+    let len_bytes = data.get(*offset..(*offset + size_of::<u32>()))?;
+    let len = u32::from_le_bytes(len_bytes.try_into().unwrap()) as usize;
+    *offset += size_of::<u32>();
+    let value = data.get(*offset..(*offset + len))?;
+    *offset += len;
+    String::from_utf8(value.to_vec()).ok()
+}
@@ -0,0 +1,212 @@
+use crate::db::TableHandle;
+use crate::sealed::Sealed;
+use crate::{
+    AccessGuard, Key, Keys, ReadOnlyTable, ReadableTable, ReadableTableMetadata, Result, Table,
+    TableDefinition, TableStats,
+};
+use std::borrow::Borrow;
+use std::fmt::{Debug, Display, Formatter};
+use std::ops::RangeBounds;
+
+/// Defines the name and key type of a set table
+///
+/// A [`SetTableDefinition`] should be opened for use by calling
+/// [`ReadTransaction::open_set_table`](crate::ReadTransaction::open_set_table) or
+/// [`WriteTransaction::open_set_table`](crate::WriteTransaction::open_set_table)
+///
+/// A set table stores keys without values. It is a table whose values are `()`, which has a fixed
+/// width of zero, so its leaves hold only the keys and their offsets. The same table can also be
+/// opened with a [`TableDefinition<K, ()>`] of the same name.
+pub struct SetTableDefinition<'a, K: Key + 'static> {
+    inner: TableDefinition<'a, K, ()>,
+}
+
+impl<'a, K: Key + 'static> SetTableDefinition<'a, K> {
+    /// Construct a new set table with given `name`
+    ///
+    /// # Panics
+    ///
+    /// Panics if `name` is empty
+    pub const fn new(name: &'a str) -> Self {
+        Self {
+            inner: TableDefinition::new(name),
+        }
+    }
+
+    pub(crate) fn table_definition(self) -> TableDefinition<'a, K, ()> {
+        self.inner
+    }
+}
+
+impl<K: Key + 'static> TableHandle for SetTableDefinition<'_, K> {
+    fn name(&self) -> &str {
+        self.inner.name()
+    }
+}
+
+impl<K: Key> Sealed for SetTableDefinition<'_, K> {}
+
+impl<K: Key + 'static> Clone for SetTableDefinition<'_, K> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<K: Key + 'static> Copy for SetTableDefinition<'_, K> {}
+
+impl<K: Key + 'static> Display for SetTableDefinition<'_, K> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}<{}>", self.name(), K::type_name().name())
+    }
+}
+
+/// A set of keys
+pub struct SetTable<'txn, K: Key + 'static> {
+    table: Table<'txn, K, ()>,
+}
+
+impl<'txn, K: Key + 'static> SetTable<'txn, K> {
+    pub(crate) fn new(table: Table<'txn, K, ()>) -> Self {
+        Self { table }
+    }
+
+    /// Add the given key to the set
+    ///
+    /// Returns `true` if the key was not already present
+    pub fn insert<'k>(&mut self, key: impl Borrow<K::SelfType<'k>>) -> Result<bool> {
+        Ok(self.table.insert_if_absent(key, ())?.is_none())
+    }
+
+    /// Removes the given key from the set
+    ///
+    /// Returns `true` if the key was present
+    pub fn remove<'k>(&mut self, key: impl Borrow<K::SelfType<'k>>) -> Result<bool> {
+        Ok(self.table.remove(key)?.is_some())
+    }
+
+    /// Returns `true` if the set contains the given key
+    pub fn contains<'k>(&self, key: impl Borrow<K::SelfType<'k>>) -> Result<bool> {
+        Ok(self.table.get(key)?.is_some())
+    }
+
+    /// Returns a double-ended iterator over the keys in the specified range, in order
+    pub fn range<'a, KR>(&self, range: impl RangeBounds<KR> + 'a) -> Result<SetRange<'_, K>>
+    where
+        KR: Borrow<K::SelfType<'a>> + 'a,
+    {
+        Ok(SetRange {
+            inner: self.table.keys(range)?,
+        })
+    }
+
+    /// Returns a double-ended iterator over all the keys in the set, in order
+    pub fn iter(&self) -> Result<SetRange<'_, K>> {
+        self.range::<K::SelfType<'_>>(..)
+    }
+}
+
+impl<K: Key + 'static> TableHandle for SetTable<'_, K> {
+    fn name(&self) -> &str {
+        self.table.name()
+    }
+}
+
+impl<K: Key + 'static> Sealed for SetTable<'_, K> {}
+
+impl<K: Key + 'static> ReadableTableMetadata for SetTable<'_, K> {
+    fn stats(&self) -> Result<TableStats> {
+        self.table.stats()
+    }
+
+    fn len(&self) -> Result<u64> {
+        self.table.len()
+    }
+}
+
+impl<K: Key + 'static> Debug for SetTable<'_, K> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("SetTable")
+            .field("name", &self.table.name())
+            .finish_non_exhaustive()
+    }
+}
+
+/// A read-only set of keys
+pub struct ReadOnlySetTable<K: Key + 'static> {
+    table: ReadOnlyTable<K, ()>,
+}
+
+impl<K: Key + 'static> ReadOnlySetTable<K> {
+    pub(crate) fn new(table: ReadOnlyTable<K, ()>) -> Self {
+        Self { table }
+    }
+
+    /// Returns `true` if the set contains the given key
+    pub fn contains<'k>(&self, key: impl Borrow<K::SelfType<'k>>) -> Result<bool> {
+        Ok(self.table.get(key)?.is_some())
+    }
+
+    /// Returns a double-ended iterator over the keys in the specified range, in order
+    pub fn range<'a, KR>(&self, range: impl RangeBounds<KR> + 'a) -> Result<SetRange<'_, K>>
+    where
+        KR: Borrow<K::SelfType<'a>> + 'a,
+    {
+        Ok(SetRange {
+            inner: self.table.keys(range)?,
+        })
+    }
+
+    /// Returns a double-ended iterator over all the keys in the set, in order
+    pub fn iter(&self) -> Result<SetRange<'_, K>> {
+        self.range::<K::SelfType<'_>>(..)
+    }
+}
+
+impl<K: Key + 'static> TableHandle for ReadOnlySetTable<K> {
+    fn name(&self) -> &str {
+        self.table.name()
+    }
+}
+
+impl<K: Key + 'static> Sealed for ReadOnlySetTable<K> {}
+
+impl<K: Key + 'static> ReadableTableMetadata for ReadOnlySetTable<K> {
+    fn stats(&self) -> Result<TableStats> {
+        self.table.stats()
+    }
+
+    fn len(&self) -> Result<u64> {
+        self.table.len()
+    }
+}
+
+impl<K: Key + 'static> Debug for ReadOnlySetTable<K> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("ReadOnlySetTable")
+            .field("name", &self.table.name())
+            .finish_non_exhaustive()
+    }
+}
+
+/// Iterator over the keys of a [`SetTable`] or [`ReadOnlySetTable`]
+pub struct SetRange<'a, K: Key + 'static> {
+    inner: Keys<'a, K, ()>,
+}
+
+impl<'a, K: Key + 'static> Iterator for SetRange<'a, K> {
+    type Item = Result<AccessGuard<'a, K>>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.inner.next()
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.inner.size_hint()
+    }
+}
+
+impl<K: Key + 'static> DoubleEndedIterator for SetRange<'_, K> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        self.inner.next_back()
+    }
+}
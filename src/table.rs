@@ -1,20 +1,74 @@
 use crate::db::TransactionGuard;
 use crate::sealed::Sealed;
+use crate::transaction_tracker::TransactionId;
 use crate::tree_store::{
-    AccessGuardMutInPlace, Btree, BtreeExtractIf, BtreeHeader, BtreeMut, BtreeRangeIter,
-    MAX_PAIR_LENGTH, MAX_VALUE_LENGTH, PageAllocator, PageHint, PageNumber, PageResolver,
-    PageTrackerPolicy, RawBtree,
+    AccessGuardMutInPlace, Btree, BtreeCursor, BtreeExtractIf, BtreeHeader, BtreeMut,
+    BtreeRangeIter, Filter, LEAF_FILL_BUCKETS, MAX_PAIR_LENGTH, MAX_VALUE_LENGTH, PageAllocator,
+    PageHint, PageNumber, PageResolver, PageTrackerPolicy, RawBtree, RawBtreeIter, Stoppable,
+};
+use crate::types::{Key, MutInPlaceValue, PrefixKey, TryValue, TypeName, Value};
+use crate::{
+    AccessGuard, AccessGuardMut, AppendError, DecodeError, MergeError, RangeMut, StorageError,
+    WriteTransaction,
 };
-use crate::types::{Key, MutInPlaceValue, Value};
-use crate::{AccessGuard, AccessGuardMut, StorageError, WriteTransaction};
 use crate::{Result, TableHandle};
 use std::borrow::Borrow;
+use std::cmp::Ordering;
 use std::fmt::{Debug, Formatter};
+use std::io::Read;
 use std::marker::PhantomData;
-use std::ops::RangeBounds;
+use std::ops::{Bound, ControlFlow, RangeBounds};
 use std::sync::{Arc, Mutex};
 use std::thread;
 
+/// Estimated size of a range of a table, returned by [`Table::estimate_range_size()`]
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct RangeEstimate {
+    entries: u64,
+    stored_bytes: u64,
+}
+
+impl RangeEstimate {
+    /// Estimated number of entries in the range
+    pub fn entries(&self) -> u64 {
+        self.entries
+    }
+
+    /// Estimated number of bytes consumed by the keys and values in the range.
+    /// Does not include indexing overhead
+    pub fn stored_bytes(&self) -> u64 {
+        self.stored_bytes
+    }
+}
+
+/// Number of writes to a table in a write transaction, returned by
+/// [`TransactionStats::table_operations()`](crate::TransactionStats::table_operations)
+#[derive(Debug, Copy, Clone, Default, PartialEq, Eq)]
+pub struct TableOperationStats {
+    pub(crate) inserts: u64,
+    pub(crate) removes: u64,
+}
+
+impl TableOperationStats {
+    /// Number of values inserted or replaced, including by [`Table::merge`] and
+    /// [`Table::update_inplace`]. Values modified through [`Table::get_mut`] or
+    /// [`Table::range_mut`] are not counted
+    pub fn inserts(&self) -> u64 {
+        self.inserts
+    }
+
+    /// Number of entries removed, including by [`Table::retain`], [`Table::clear`], and the
+    /// iterator returned by [`Table::extract_if`]
+    pub fn removes(&self) -> u64 {
+        self.removes
+    }
+
+    pub(crate) fn add(&mut self, other: TableOperationStats) {
+        self.inserts += other.inserts;
+        self.removes += other.removes;
+    }
+}
+
 /// Informational storage stats about a table
 #[derive(Debug)]
 pub struct TableStats {
@@ -24,6 +78,8 @@ pub struct TableStats {
     pub(crate) stored_leaf_bytes: u64,
     pub(crate) metadata_bytes: u64,
     pub(crate) fragmented_bytes: u64,
+    pub(crate) leaf_fill_histogram: [u64; LEAF_FILL_BUCKETS],
+    pub(crate) largest_leaf_pages: u64,
 }
 
 impl TableStats {
@@ -57,6 +113,50 @@ impl TableStats {
     pub fn fragmented_bytes(&self) -> u64 {
         self.fragmented_bytes
     }
+
+    /// Number of leaf pages, grouped by how full they are
+    ///
+    /// Element `i` counts the leaves which are at least `i * 10`% and less than `(i + 1) * 10`%
+    /// full, except that completely full leaves are counted in the last element
+    pub fn leaf_fill_histogram(&self) -> [u64; LEAF_FILL_BUCKETS] {
+        self.leaf_fill_histogram
+    }
+
+    /// Number of pages spanned by the largest leaf
+    ///
+    /// A leaf which holds a value that does not fit in a single page spans multiple contiguous
+    /// pages, all of which are read to access any entry in it. A large value here indicates that
+    /// the table holds values which are very large relative to the page size
+    pub fn largest_leaf_pages(&self) -> u64 {
+        self.largest_leaf_pages
+    }
+}
+
+fn byte_range(range: &impl RangeBounds<usize>) -> std::ops::Range<usize> {
+    let start = match range.start_bound() {
+        Bound::Included(&x) => x,
+        Bound::Excluded(&x) => x.saturating_add(1),
+        Bound::Unbounded => 0,
+    };
+    let end = match range.end_bound() {
+        Bound::Included(&x) => x.saturating_add(1),
+        Bound::Excluded(&x) => x,
+        Bound::Unbounded => usize::MAX,
+    };
+    start..end
+}
+
+/// Combines an operand with the existing value of a key, for [`Table::merge`]
+pub trait MergeOperator<K: Key, V: Value> {
+    /// Returns the value to store for `key`
+    fn merge<'a>(
+        &self,
+        key: K::SelfType<'a>,
+        existing: Option<V::SelfType<'a>>,
+        operand: V::SelfType<'a>,
+    ) -> V::SelfType<'a>
+    where
+        Self: 'a;
 }
 
 /// A table containing key-value mappings
@@ -64,6 +164,8 @@ pub struct Table<'txn, K: Key + 'static, V: Value + 'static> {
     name: String,
     transaction: &'txn WriteTransaction,
     tree: BtreeMut<K, V>,
+    merge_operator: Option<Box<dyn MergeOperator<K, V> + Send + Sync + 'txn>>,
+    operations: TableOperationStats,
 }
 
 impl<K: Key + 'static, V: Value + 'static> TableHandle for Table<'_, K, V> {
@@ -117,6 +219,8 @@ impl<'txn, K: Key + 'static, V: Value + 'static> Table<'txn, K, V> {
                 freed_pages,
                 allocated_pages,
             ),
+            merge_operator: None,
+            operations: TableOperationStats::default(),
         }
     }
 
@@ -126,6 +230,12 @@ impl<'txn, K: Key + 'static, V: Value + 'static> Table<'txn, K, V> {
     }
 
     /// Returns an accessor, which allows mutation, to the value corresponding to the given key
+    ///
+    /// The value is modified in the leaf page which holds it, so this avoids the lookups and page
+    /// rewrites of a [`Self::remove`] followed by an [`Self::insert`]. For numbers and arrays of
+    /// numbers, which implement [`MutInPlaceValue`], the stored value can be updated directly with
+    /// [`AsMut::as_mut`]. [`AccessGuardMut::insert`] replaces the value, and is also done in place
+    /// when the leaf has room for the new value.
     pub fn get_mut<'k>(
         &mut self,
         key: impl Borrow<K::SelfType<'k>>,
@@ -133,14 +243,81 @@ impl<'txn, K: Key + 'static, V: Value + 'static> Table<'txn, K, V> {
         self.tree.get_mut(key.borrow())
     }
 
+    /// Returns the given bytes of the value corresponding to the given key, or `None` if the key
+    /// is not present
+    ///
+    /// `range` is truncated to the length of the value. For a value which is larger than a page,
+    /// only the requested bytes are read from the file, and they are not added to the cache, so
+    /// the header of a large value can be read without reading the whole value.
+    pub fn get_range_of_value<'a>(
+        &self,
+        key: impl Borrow<K::SelfType<'a>>,
+        range: impl RangeBounds<usize>,
+    ) -> Result<Option<Vec<u8>>> {
+        self.tree.get_value_range(key.borrow(), byte_range(&range))
+    }
+
+    /// Estimates the number of entries in the specified range, and the bytes of their keys and
+    /// values
+    ///
+    /// Only the pages on the paths from the root of the table to the ends of the range are read,
+    /// so this takes time proportional to the height of the tree, however large the range is. The
+    /// estimate assumes that entries are spread evenly over the leaves of the tree, and that the
+    /// entries in the range are the same size as those in the leaves at its ends. Use
+    /// [`ReadableTable::range_len()`] for an exact count.
+    pub fn estimate_range_size<'a, KR>(
+        &self,
+        range: impl RangeBounds<KR> + 'a,
+    ) -> Result<RangeEstimate>
+    where
+        KR: Borrow<K::SelfType<'a>> + 'a,
+    {
+        let (entries, stored_bytes) = self.tree.estimate_range(&range)?;
+        Ok(RangeEstimate {
+            entries,
+            stored_bytes,
+        })
+    }
+
+    /// Returns a [`Cursor`] over the table, which is not positioned at any entry until it is
+    /// seeked
+    pub fn cursor(&self) -> Result<Cursor<'_, K, V>> {
+        Ok(Cursor::new(
+            self.tree.cursor()?,
+            self.transaction.transaction_guard(),
+        ))
+    }
+
+    /// Returns a cursor over a range of elements in the table, whose values can be replaced
+    ///
+    /// Entries are visited in key order, with [`RangeMut::next_entry`]. Values are modified in the
+    /// leaf which holds them, so unlike collecting keys and then calling [`Self::get_mut`] or
+    /// [`Self::insert`] for each one, the tree is only descended once per leaf. Every leaf in the
+    /// range is made writable, by copying it if it was committed, even if none of its values are
+    /// replaced.
+    pub fn range_mut<'a, KR>(&mut self, range: impl RangeBounds<KR>) -> Result<RangeMut<'_, K, V>>
+    where
+        KR: Borrow<K::SelfType<'a>>,
+    {
+        self.tree.range_mut(&range)
+    }
+
     /// Removes and returns the first key-value pair in the table
     pub fn pop_first(&mut self) -> Result<Option<(AccessGuard<'_, K>, AccessGuard<'_, V>)>> {
-        self.tree.pop_first()
+        let entry = self.tree.pop_first()?;
+        if entry.is_some() {
+            self.operations.removes += 1;
+        }
+        Ok(entry)
     }
 
     /// Removes and returns the last key-value pair in the table
     pub fn pop_last(&mut self) -> Result<Option<(AccessGuard<'_, K>, AccessGuard<'_, V>)>> {
-        self.tree.pop_last()
+        let entry = self.tree.pop_last()?;
+        if entry.is_some() {
+            self.operations.removes += 1;
+        }
+        Ok(entry)
     }
 
     /// Applies `predicate` to all key-value pairs. All entries for which
@@ -174,8 +351,44 @@ impl<'txn, K: Key + 'static, V: Value + 'static> Table<'txn, K, V> {
     where
         KR: Borrow<K::SelfType<'a>> + 'a,
     {
-        let inner = self.tree.extract_from_if(&range, predicate)?;
-        Ok(ExtractIf::new(inner, Some(self.transaction)))
+        let inner = self.tree.extract_from_if(&range, Filter(predicate))?;
+        Ok(ExtractIf::new(
+            inner,
+            Some(self.transaction),
+            Some(&mut self.operations.removes),
+        ))
+    }
+
+    /// Like [`Self::extract_from_if`], but `predicate` can also end the iteration early
+    ///
+    /// `predicate` returns [`ControlFlow::Continue`] with `true` to return and remove the entry,
+    /// or with `false` to keep it. Once it returns [`ControlFlow::Break`], the iterator ends and no
+    /// further entries are visited. This bounds the work done by a single call, for example to
+    /// remove up to N expired entries per transaction, or to examine at most N entries. To bound
+    /// only the number removed, [`Iterator::take`] can be used with [`Self::extract_from_if`],
+    /// since values not read from the iterator are not removed.
+    ///
+    /// The predicate must not panic. If it panics, the write transaction is
+    /// poisoned and [`crate::WriteTransaction::commit`] will return
+    /// [`crate::CommitError::TransactionPoisoned`].
+    pub fn extract_from_while<
+        'a,
+        KR,
+        F: for<'f> FnMut(K::SelfType<'f>, V::SelfType<'f>) -> ControlFlow<(), bool>,
+    >(
+        &mut self,
+        range: impl RangeBounds<KR> + 'a,
+        predicate: F,
+    ) -> Result<ExtractWhile<'_, K, V, F>>
+    where
+        KR: Borrow<K::SelfType<'a>> + 'a,
+    {
+        let inner = self.tree.extract_from_if(&range, Stoppable(predicate))?;
+        Ok(ExtractWhile {
+            inner,
+            poison_target: self.transaction,
+            removes: &mut self.operations.removes,
+        })
     }
 
     /// Applies `predicate` to all key-value pairs. All entries for which
@@ -190,9 +403,12 @@ impl<'txn, K: Key + 'static, V: Value + 'static> Table<'txn, K, V> {
         predicate: F,
     ) -> Result {
         let mut panic_guard = RetainPanicGuard::new(self.transaction);
+        let before = self.len()?;
         let result = self.tree.retain_in::<K::SelfType<'_>, F>(predicate, ..);
         panic_guard.disarm();
-        result
+        result?;
+        self.operations.removes += before - self.len()?;
+        Ok(())
     }
 
     /// Applies `predicate` to all key-value pairs in the range `start..end`. All entries for which
@@ -211,9 +427,12 @@ impl<'txn, K: Key + 'static, V: Value + 'static> Table<'txn, K, V> {
         KR: Borrow<K::SelfType<'a>> + 'a,
     {
         let mut panic_guard = RetainPanicGuard::new(self.transaction);
+        let before = self.len()?;
         let result = self.tree.retain_in(predicate, range);
         panic_guard.disarm();
-        result
+        result?;
+        self.operations.removes += before - self.len()?;
+        Ok(())
     }
 
     /// Insert mapping of the given key to the given value
@@ -226,18 +445,335 @@ impl<'txn, K: Key + 'static, V: Value + 'static> Table<'txn, K, V> {
         key: impl Borrow<K::SelfType<'k>>,
         value: impl Borrow<V::SelfType<'v>>,
     ) -> Result<Option<AccessGuard<'_, V>>> {
+        Self::check_pair_length(key.borrow(), value.borrow())?;
+        let old_value = self.tree.insert(key.borrow(), value.borrow())?;
+        self.operations.inserts += 1;
+        Ok(old_value)
+    }
+
+    /// Insert mapping of the given key to the given value, only if the key is not present
+    ///
+    /// Returns the existing value, and leaves the table unchanged, if the key was present in the
+    /// table, otherwise None is returned. This is a single lookup, unlike a [`ReadableTable::get`]
+    /// followed by an [`Self::insert`]
+    pub fn insert_if_absent<'k, 'v>(
+        &mut self,
+        key: impl Borrow<K::SelfType<'k>>,
+        value: impl Borrow<V::SelfType<'v>>,
+    ) -> Result<Option<AccessGuard<'_, V>>> {
+        Self::check_pair_length(key.borrow(), value.borrow())?;
+        let existing = self.tree.insert_if_absent(key.borrow(), value.borrow())?;
+        if existing.is_none() {
+            self.operations.inserts += 1;
+        }
+        Ok(existing)
+    }
+
+    /// Overwrite the value of the given key, which must be present, with a value of the same
+    /// serialized length
+    ///
+    /// The value is written over the existing one in its leaf page. The first write to a key in a
+    /// transaction copies the pages on the path to it, as any modification does, but later calls
+    /// for the same key in the transaction allocate no pages, which makes this suitable for
+    /// frequently updated counters and other fixed-size values.
+    ///
+    /// Returns `false`, and leaves the table unchanged, if the key is not present or its value has
+    /// a different serialized length
+    pub fn update_inplace<'k, 'v>(
+        &mut self,
+        key: impl Borrow<K::SelfType<'k>>,
+        value: impl Borrow<V::SelfType<'v>>,
+    ) -> Result<bool> {
         let value_len = V::as_bytes(value.borrow()).as_ref().len();
+        let Some(existing) = self.tree.get(key.borrow())? else {
+            return Ok(false);
+        };
+        if V::as_bytes(&existing.value()).as_ref().len() != value_len {
+            return Ok(false);
+        }
+        drop(existing);
+        let mut guard = self.tree.get_mut(key.borrow())?.unwrap();
+        guard.insert(value)?;
+        self.operations.inserts += 1;
+        Ok(true)
+    }
+
+    /// Set the operator used by [`Self::merge`] to combine operands with existing values
+    ///
+    /// The operator is not stored in the database, and only applies to this `Table` handle. It must
+    /// be set again on every `Table` opened later, including in the same transaction, and values
+    /// already in the table are not changed when a different operator is set
+    pub fn set_merge_operator(&mut self, operator: impl MergeOperator<K, V> + Send + Sync + 'txn) {
+        self.merge_operator = Some(Box::new(operator));
+    }
+
+    /// Replace the value of the given key with the result of the table's [`MergeOperator`]
+    ///
+    /// The operator is called with the key, its existing value, or `None` if the key is not
+    /// present, and `operand`, while the key's leaf page is being updated. Counters, sets, and
+    /// other accumulated values can therefore be updated with a single lookup, rather than a
+    /// [`ReadableTable::get`] followed by an [`Self::insert`]
+    ///
+    /// Returns [`MergeError::NoMergeOperator`], and leaves the table unchanged, if no operator has
+    /// been set on this `Table` with [`Self::set_merge_operator`]
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use redb::*;
+    /// # use tempfile::NamedTempFile;
+    /// const COUNTERS: TableDefinition<&str, u64> = TableDefinition::new("counters");
+    ///
+    /// struct Sum;
+    ///
+    /// impl MergeOperator<&str, u64> for Sum {
+    ///     fn merge<'a>(&self, _: &'a str, existing: Option<u64>, operand: u64) -> u64
+    ///     where
+    ///         Self: 'a,
+    ///     {
+    ///         existing.unwrap_or(0) + operand
+    ///     }
+    /// }
+    ///
+    /// # fn main() -> Result<(), Error> {
+    /// # #[cfg(not(target_os = "wasi"))]
+    /// # let tmpfile = NamedTempFile::new().unwrap();
+    /// # #[cfg(target_os = "wasi")]
+    /// # let tmpfile = NamedTempFile::new_in("/tmp").unwrap();
+    /// # let filename = tmpfile.path();
+    /// let db = Database::create(filename)?;
+    ///
+    /// let write_txn = db.begin_write()?;
+    /// {
+    ///     let mut table = write_txn.open_table(COUNTERS)?;
+    ///     table.set_merge_operator(Sum);
+    ///     table.merge("hits", 1)?;
+    ///     table.merge("hits", 2)?;
+    ///     assert_eq!(table.get("hits")?.unwrap().value(), 3);
+    /// }
+    /// write_txn.commit()?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn merge<'k, 'v>(
+        &mut self,
+        key: impl Borrow<K::SelfType<'k>>,
+        operand: impl Borrow<V::SelfType<'v>>,
+    ) -> Result<(), MergeError> {
+        let operator = self
+            .merge_operator
+            .as_deref()
+            .ok_or(MergeError::NoMergeOperator)?;
+        let key = key.borrow();
+        let key_bytes = K::as_bytes(key);
+        let key_bytes = key_bytes.as_ref();
+        let mut merge = |existing: Option<&[u8]>, operand: &[u8]| -> Result<Vec<u8>> {
+            let merged = operator.merge(
+                K::from_bytes(key_bytes),
+                existing.map(V::from_bytes),
+                V::from_bytes(operand),
+            );
+            Self::check_pair_length(&K::from_bytes(key_bytes), &merged)?;
+            Ok(V::as_bytes(&merged).as_ref().to_vec())
+        };
+        self.tree.merge(key, operand.borrow(), &mut merge)?;
+        self.operations.inserts += 1;
+        Ok(())
+    }
+
+    /// Insert mapping of the given key to `new`, only if the key currently maps to `expected`
+    ///
+    /// If `expected` is `None`, the value is only inserted if the key is not present. Values are
+    /// compared by their serialized bytes.
+    ///
+    /// Returns `Ok(())` if the value was inserted. Otherwise, returns `Err` with the current
+    /// value, or `None` if the key is not present, and the table is unchanged
+    pub fn compare_and_swap<'k, 'v>(
+        &mut self,
+        key: impl Borrow<K::SelfType<'k>>,
+        expected: Option<&V::SelfType<'_>>,
+        new: impl Borrow<V::SelfType<'v>>,
+    ) -> Result<Result<(), Option<AccessGuard<'_, V>>>> {
+        let Some(expected) = expected else {
+            return match self.insert_if_absent(key, new)? {
+                None => Ok(Ok(())),
+                existing => Ok(Err(existing)),
+            };
+        };
+        let matches = self.tree.get(key.borrow())?.is_some_and(|current| {
+            V::as_bytes(&current.value()).as_ref() == V::as_bytes(expected).as_ref()
+        });
+        if matches {
+            Self::check_pair_length(key.borrow(), new.borrow())?;
+            self.tree.insert(key.borrow(), new.borrow())?;
+            self.operations.inserts += 1;
+            Ok(Ok(()))
+        } else {
+            Ok(Err(self.tree.get(key.borrow())?))
+        }
+    }
+
+    /// Insert all the given key-value pairs, which should be sorted by key
+    ///
+    /// Each pair whose key is after every key in the table is appended to the last leaf of the
+    /// tree, which is left full rather than split in half, so loading sorted data into a table runs
+    /// at close to sequential write speed. Pairs which are out of order are inserted as if by
+    /// [`Self::insert`]. If a key is already present, its value is replaced.
+    pub fn insert_sorted<'k, 'v, I, KB, VB>(&mut self, entries: I) -> Result
+    where
+        I: IntoIterator<Item = (KB, VB)>,
+        KB: Borrow<K::SelfType<'k>>,
+        VB: Borrow<V::SelfType<'v>>,
+    {
+        let mut last_key = self.last_key_bytes()?;
+        for (key, value) in entries {
+            self.insert_sorted_entry(&mut last_key, key.borrow(), value.borrow())?;
+        }
+        Ok(())
+    }
+
+    /// Copy every entry of `source` into this table, and return the number of entries copied
+    ///
+    /// `source` may be a table in this transaction, or in a different [`Database`](crate::Database).
+    /// Its entries are read in order and inserted as if by [`Self::insert_sorted`], so copying into
+    /// an empty table, or one whose keys are all smaller, builds the table from full leaves. If a
+    /// key is already present, its value is replaced.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use redb::*;
+    /// # use tempfile::NamedTempFile;
+    /// const TABLE: TableDefinition<u64, &str> = TableDefinition::new("my_data");
+    ///
+    /// # fn main() -> Result<(), Error> {
+    /// # #[cfg(not(target_os = "wasi"))]
+    /// # let tmpfile = NamedTempFile::new().unwrap();
+    /// # #[cfg(target_os = "wasi")]
+    /// # let tmpfile = NamedTempFile::new_in("/tmp").unwrap();
+    /// # let source_file = tmpfile.path();
+    /// # #[cfg(not(target_os = "wasi"))]
+    /// # let tmpfile2 = NamedTempFile::new().unwrap();
+    /// # #[cfg(target_os = "wasi")]
+    /// # let tmpfile2 = NamedTempFile::new_in("/tmp").unwrap();
+    /// # let backup_file = tmpfile2.path();
+    /// let source = Database::create(source_file)?;
+    /// let write_txn = source.begin_write()?;
+    /// write_txn.open_table(TABLE)?.insert(0, "hello")?;
+    /// write_txn.commit()?;
+    ///
+    /// let backup = Database::create(backup_file)?;
+    /// let read_txn = source.begin_read()?;
+    /// let write_txn = backup.begin_write()?;
+    /// let copied = write_txn
+    ///     .open_table(TABLE)?
+    ///     .copy_from(&read_txn.open_table(TABLE)?)?;
+    /// assert_eq!(copied, 1);
+    /// write_txn.commit()?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn copy_from(&mut self, source: &impl ReadableTable<K, V>) -> Result<u64> {
+        let mut last_key = self.last_key_bytes()?;
+        let mut copied = 0;
+        for entry in source.iter()? {
+            let (key, value) = entry?;
+            self.insert_sorted_entry(&mut last_key, &key.value(), &value.value())?;
+            copied += 1;
+        }
+        Ok(copied)
+    }
+
+    fn last_key_bytes(&self) -> Result<Option<Vec<u8>>> {
+        Ok(self
+            .last()?
+            .map(|(key, _)| K::as_bytes(&key.value()).as_ref().to_vec()))
+    }
+
+    // Inserts the entry, by appending it if its key is after `last_key`, which is the last key in
+    // the table and is updated
+    fn insert_sorted_entry(
+        &mut self,
+        last_key: &mut Option<Vec<u8>>,
+        key: &K::SelfType<'_>,
+        value: &V::SelfType<'_>,
+    ) -> Result {
+        Self::check_pair_length(key, value)?;
+        let key_bytes = K::as_bytes(key);
+        let key_bytes = key_bytes.as_ref();
+        let appended = last_key
+            .as_ref()
+            .is_none_or(|last| K::compare(key_bytes, last) == Ordering::Greater);
+        if appended {
+            self.tree.insert_append(key, value)?;
+            let last = last_key.get_or_insert_with(Vec::new);
+            last.clear();
+            last.extend_from_slice(key_bytes);
+        } else {
+            self.tree.insert(key, value)?;
+        }
+        self.operations.inserts += 1;
+        Ok(())
+    }
+
+    /// Insert mapping from the given key to the given value, which must be greater than every key
+    /// in the table
+    ///
+    /// This is faster than [`Self::insert`] for keys which only increase, such as timestamps or
+    /// sequence numbers. The rightmost leaf of the table is cached, so that most appends in a
+    /// transaction do not descend from the root, and a full leaf is left full, rather than split in
+    /// half, when a key is appended after it.
+    ///
+    /// Returns [`AppendError::KeyOutOfOrder`], and does not modify the table, if the key is not
+    /// greater than the last key in the table
+    pub fn append<'k, 'v>(
+        &mut self,
+        key: impl Borrow<K::SelfType<'k>>,
+        value: impl Borrow<V::SelfType<'v>>,
+    ) -> Result<(), AppendError> {
+        Self::check_pair_length(key.borrow(), value.borrow())?;
+        if self.tree.append(key.borrow(), value.borrow())? {
+            self.operations.inserts += 1;
+            Ok(())
+        } else {
+            Err(AppendError::KeyOutOfOrder)
+        }
+    }
+
+    // Same as insert(), but optimized for keys which are appended after the last key in the table
+    pub(crate) fn insert_append<'k, 'v>(
+        &mut self,
+        key: impl Borrow<K::SelfType<'k>>,
+        value: impl Borrow<V::SelfType<'v>>,
+    ) -> Result<Option<AccessGuard<'_, V>>> {
+        Self::check_pair_length(key.borrow(), value.borrow())?;
+        let old_value = self.tree.insert_append(key.borrow(), value.borrow())?;
+        self.operations.inserts += 1;
+        Ok(old_value)
+    }
+
+    fn check_pair_length(key: &K::SelfType<'_>, value: &V::SelfType<'_>) -> Result {
+        let value_len = V::as_bytes(value).as_ref().len();
         if value_len > MAX_VALUE_LENGTH {
             return Err(StorageError::ValueTooLarge(value_len));
         }
-        let key_len = K::as_bytes(key.borrow()).as_ref().len();
+        let key_len = K::as_bytes(key).as_ref().len();
         if key_len > MAX_VALUE_LENGTH {
             return Err(StorageError::ValueTooLarge(key_len));
         }
         if value_len + key_len > MAX_PAIR_LENGTH {
             return Err(StorageError::ValueTooLarge(value_len + key_len));
         }
-        self.tree.insert(key.borrow(), value.borrow())
+        Ok(())
+    }
+
+    /// Removes all entries from the table
+    ///
+    /// Only the branch pages of the table are read, so this is much faster than removing each
+    /// entry, and does not require the table to be deleted and recreated
+    pub fn clear(&mut self) -> Result {
+        self.operations.removes += self.len()?;
+        self.tree.clear()
     }
 
     /// Removes the given key
@@ -247,7 +783,11 @@ impl<'txn, K: Key + 'static, V: Value + 'static> Table<'txn, K, V> {
         &mut self,
         key: impl Borrow<K::SelfType<'a>>,
     ) -> Result<Option<AccessGuard<'_, V>>> {
-        self.tree.remove(key.borrow())
+        let value = self.tree.remove(key.borrow())?;
+        if value.is_some() {
+            self.operations.removes += 1;
+        }
+        Ok(value)
     }
 
     /// Gets the given key's corresponding entry in the table for in-place manipulation.
@@ -262,11 +802,13 @@ impl<'txn, K: Key + 'static, V: Value + 'static> Table<'txn, K, V> {
         if self.tree.get(&key)?.is_some() {
             Ok(Entry::Occupied(OccupiedEntry {
                 tree: &mut self.tree,
+                operations: &mut self.operations,
                 key,
             }))
         } else {
             Ok(Entry::Vacant(VacantEntry {
                 tree: &mut self.tree,
+                operations: &mut self.operations,
                 key,
             }))
         }
@@ -278,12 +820,19 @@ impl<K: Key + 'static, V: MutInPlaceValue + 'static> Table<'_, K, V> {
     ///
     /// If key is already present it is replaced
     ///
-    /// The returned reference will have length equal to `value_length`
+    /// The returned reference will have length equal to `value_length`. If `V` is fixed width,
+    /// `value_length` must equal its width
     pub fn insert_reserve<'a>(
         &mut self,
         key: impl Borrow<K::SelfType<'a>>,
         value_length: usize,
     ) -> Result<AccessGuardMutInPlace<'_, V>> {
+        if let Some(width) = V::fixed_width().filter(|width| *width != value_length) {
+            return Err(StorageError::Decode(DecodeError::new(format!(
+                "{} values are {width} bytes, but {value_length} bytes were reserved",
+                V::type_name().name()
+            ))));
+        }
         if value_length > MAX_VALUE_LENGTH {
             return Err(StorageError::ValueTooLarge(value_length));
         }
@@ -294,7 +843,48 @@ impl<K: Key + 'static, V: MutInPlaceValue + 'static> Table<'_, K, V> {
         if value_length + key_len > MAX_PAIR_LENGTH {
             return Err(StorageError::ValueTooLarge(value_length + key_len));
         }
-        self.tree.insert_reserve(key.borrow(), value_length)
+        let guard = self.tree.insert_reserve(key.borrow(), value_length)?;
+        self.operations.inserts += 1;
+        Ok(guard)
+    }
+
+    /// Insert a value of `len` bytes, which is read from `reader`
+    ///
+    /// The value is copied from `reader` into the space reserved for it with
+    /// [`Self::insert_reserve`], so the caller does not need to assemble it in a separate buffer.
+    /// It is stored like any other value, so the whole value is held in the page cache until it is
+    /// written to the file. If key is already present it is replaced
+    ///
+    /// If `reader` returns an error, or ends before `len` bytes have been read, the error is
+    /// returned and the key is left with the value it had before the call, if any
+    pub fn insert_stream<'a>(
+        &mut self,
+        key: impl Borrow<K::SelfType<'a>>,
+        len: usize,
+        mut reader: impl Read,
+    ) -> Result
+    where
+        V: MutInPlaceValue<BaseRefType = [u8]>,
+    {
+        // Copied, since the reserved space may overwrite it in place
+        let previous = self
+            .tree
+            .get(key.borrow())?
+            .map(|guard| V::as_bytes(&guard.value()).as_ref().to_vec());
+        let mut guard = self.insert_reserve(key.borrow(), len)?;
+        let result = reader.read_exact(guard.as_mut());
+        drop(guard);
+        if let Err(err) = result {
+            if let Some(previous) = previous {
+                self.insert_reserve(key.borrow(), previous.len())?
+                    .as_mut()
+                    .copy_from_slice(&previous);
+            } else {
+                self.tree.remove(key.borrow())?;
+            }
+            return Err(StorageError::Io(err));
+        }
+        Ok(())
     }
 }
 
@@ -309,6 +899,8 @@ impl<K: Key + 'static, V: Value + 'static> ReadableTableMetadata for Table<'_, K
             stored_leaf_bytes: tree_stats.stored_leaf_bytes,
             metadata_bytes: tree_stats.metadata_bytes,
             fragmented_bytes: tree_stats.fragmented_bytes,
+            leaf_fill_histogram: tree_stats.leaf_fill_histogram,
+            largest_leaf_pages: tree_stats.largest_leaf_pages,
         })
     }
 
@@ -331,6 +923,13 @@ impl<K: Key + 'static, V: Value + 'static> ReadableTable<K, V> for Table<'_, K,
             .map(|x| Range::new(x, self.transaction.transaction_guard()))
     }
 
+    fn range_len<'a, KR>(&self, range: impl RangeBounds<KR> + 'a) -> Result<u64>
+    where
+        KR: Borrow<K::SelfType<'a>> + 'a,
+    {
+        self.tree.range_len(&range)
+    }
+
     fn first(&self) -> Result<Option<(AccessGuard<'_, K>, AccessGuard<'_, V>)>> {
         self.tree.first()
     }
@@ -348,6 +947,7 @@ impl<K: Key + 'static, V: Value + 'static> Drop for Table<'_, K, V> {
             &self.name,
             &self.tree,
             self.tree.get_root().map(|x| x.length).unwrap_or_default(),
+            self.operations,
         );
     }
 }
@@ -457,6 +1057,24 @@ pub trait ReadableTable<K: Key + 'static, V: Value + 'static>: ReadableTableMeta
     where
         KR: Borrow<K::SelfType<'a>> + 'a;
 
+    /// Returns the number of entries in the specified range
+    ///
+    /// For [`Table`] and [`ReadOnlyTable`], only the leaf pages which contain the ends of the range
+    /// are searched. The number of entries in each of the other leaf pages in the range is read
+    /// from its header, without reading any of its keys or values, so this is much faster than
+    /// counting the entries returned by [`ReadableTable::range()`]
+    fn range_len<'a, KR>(&self, range: impl RangeBounds<KR> + 'a) -> Result<u64>
+    where
+        KR: Borrow<K::SelfType<'a>> + 'a,
+    {
+        let mut len = 0;
+        for entry in self.range(range)? {
+            entry?;
+            len += 1;
+        }
+        Ok(len)
+    }
+
     /// Returns the first key-value pair in the table, if it exists
     fn first(&self) -> Result<Option<(AccessGuard<'_, K>, AccessGuard<'_, V>)>>;
 
@@ -467,11 +1085,123 @@ pub trait ReadableTable<K: Key + 'static, V: Value + 'static>: ReadableTableMeta
     fn iter(&self) -> Result<Range<'_, K, V>> {
         self.range::<K::SelfType<'_>>(..)
     }
+
+    /// Same as [`ReadableTable::get()`], but checks that the value can be decoded, so that
+    /// [`AccessGuard::value()`] will not panic
+    ///
+    /// Returns [`StorageError::Decode`] if it can't be decoded. This should be used instead of
+    /// `get()` when the database may have been written by an untrusted process
+    fn try_get<'a>(&self, key: impl Borrow<K::SelfType<'a>>) -> Result<Option<AccessGuard<'_, V>>>
+    where
+        V: TryValue,
+    {
+        let Some(guard) = self.get(key)? else {
+            return Ok(None);
+        };
+        guard.try_value()?;
+        Ok(Some(guard))
+    }
+
+    /// Same as [`ReadableTable::range()`], but checks that the key and value of each entry can be
+    /// decoded, so that [`AccessGuard::value()`] will not panic
+    ///
+    /// An entry which can't be decoded is returned as [`StorageError::Decode`]. This should be used
+    /// instead of `range()` when the database may have been written by an untrusted process
+    fn try_range<'a, KR>(&self, range: impl RangeBounds<KR> + 'a) -> Result<TryRange<'_, K, V>>
+    where
+        KR: Borrow<K::SelfType<'a>> + 'a,
+        K: TryValue,
+        V: TryValue,
+    {
+        Ok(TryRange {
+            inner: self.range(range)?,
+        })
+    }
+
+    /// Returns a double-ended iterator over all the entries whose key starts with `prefix`
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use redb::*;
+    /// # use tempfile::NamedTempFile;
+    /// const TABLE: TableDefinition<&str, u64> = TableDefinition::new("my_data");
+    ///
+    /// # fn main() -> Result<(), Error> {
+    /// # #[cfg(not(target_os = "wasi"))]
+    /// # let tmpfile = NamedTempFile::new().unwrap();
+    /// # #[cfg(target_os = "wasi")]
+    /// # let tmpfile = NamedTempFile::new_in("/tmp").unwrap();
+    /// # let filename = tmpfile.path();
+    /// let db = Database::create(filename)?;
+    /// let write_txn = db.begin_write()?;
+    /// {
+    ///     let mut table = write_txn.open_table(TABLE)?;
+    ///     table.insert("user/1", &1)?;
+    ///     table.insert("user/2", &2)?;
+    ///     table.insert("users", &3)?;
+    /// }
+    /// write_txn.commit()?;
+    ///
+    /// let read_txn = db.begin_read()?;
+    /// let table = read_txn.open_table(TABLE)?;
+    /// let users: Vec<u64> = table
+    ///     .prefix("user/")?
+    ///     .map(|entry| entry.unwrap().1.value())
+    ///     .collect();
+    /// assert_eq!(users, vec![1, 2]);
+    /// # Ok(())
+    /// # }
+    /// ```
+    fn prefix<'p>(&self, prefix: impl Borrow<K::SelfType<'p>>) -> Result<Range<'_, K, V>>
+    where
+        K: PrefixKey,
+    {
+        let start = K::as_bytes(prefix.borrow()).as_ref().to_vec();
+        match K::prefix_end(&start) {
+            Some(end) => self.range(K::from_bytes(&start)..K::from_bytes(&end)),
+            None => self.range(K::from_bytes(&start)..),
+        }
+    }
+
+    /// Returns a double-ended iterator over the keys in the specified range
+    ///
+    /// Only the keys are read, so this is faster than [`ReadableTable::range()`] when the values
+    /// are large
+    fn keys<'a, KR>(&self, range: impl RangeBounds<KR> + 'a) -> Result<Keys<'_, K, V>>
+    where
+        KR: Borrow<K::SelfType<'a>> + 'a,
+    {
+        Ok(Keys {
+            inner: self.range(range)?,
+        })
+    }
+
+    /// Returns a double-ended iterator over the values in the specified range, in key order
+    ///
+    /// Only the values are read, so this is faster than [`ReadableTable::range()`] when the keys
+    /// are large
+    fn values<'a, KR>(&self, range: impl RangeBounds<KR> + 'a) -> Result<Values<'_, K, V>>
+    where
+        KR: Borrow<K::SelfType<'a>> + 'a,
+    {
+        Ok(Values {
+            inner: self.range(range)?,
+        })
+    }
 }
 
 /// A read-only untyped table
+///
+/// This gives access to the stored bytes of the keys and values of a table, and the names of their
+/// types, for tools which operate on tables whose types are not known at compile time. Since keys
+/// are ordered by their type, which is not available, entries can only be iterated over, in key
+/// order, and not looked up by key.
 pub struct ReadOnlyUntypedTable {
     tree: RawBtree,
+    key_type: TypeName,
+    value_type: TypeName,
+    transaction_guard: Arc<TransactionGuard>,
 }
 
 impl Sealed for ReadOnlyUntypedTable {}
@@ -488,6 +1218,8 @@ impl ReadableTableMetadata for ReadOnlyUntypedTable {
             stored_leaf_bytes: tree_stats.stored_leaf_bytes,
             metadata_bytes: tree_stats.metadata_bytes,
             fragmented_bytes: tree_stats.fragmented_bytes,
+            leaf_fill_histogram: tree_stats.leaf_fill_histogram,
+            largest_leaf_pages: tree_stats.largest_leaf_pages,
         })
     }
 
@@ -497,17 +1229,76 @@ impl ReadableTableMetadata for ReadOnlyUntypedTable {
 }
 
 impl ReadOnlyUntypedTable {
+    #[allow(clippy::too_many_arguments)]
     pub(crate) fn new(
         root_page: Option<BtreeHeader>,
         hint: PageHint,
         fixed_key_size: Option<usize>,
         fixed_value_size: Option<usize>,
+        key_type: TypeName,
+        value_type: TypeName,
+        guard: Arc<TransactionGuard>,
         mem: PageResolver,
     ) -> Self {
         Self {
             tree: RawBtree::new(root_page, fixed_key_size, fixed_value_size, mem, hint),
+            key_type,
+            value_type,
+            transaction_guard: guard,
         }
     }
+
+    /// The name of the type of the keys, as stored when the table was created
+    pub fn key_type(&self) -> &TypeName {
+        &self.key_type
+    }
+
+    /// The name of the type of the values, as stored when the table was created
+    pub fn value_type(&self) -> &TypeName {
+        &self.value_type
+    }
+
+    /// Returns an iterator over the stored bytes of every key and value in the table, in key
+    /// order
+    pub fn iter(&self) -> Result<UntypedRange<'_>> {
+        Ok(UntypedRange {
+            inner: self.tree.iter()?,
+            _transaction_guard: self.transaction_guard.clone(),
+            _lifetime: PhantomData,
+        })
+    }
+}
+
+/// Iterator over the stored bytes of the entries of a [`ReadOnlyUntypedTable`]
+pub struct UntypedRange<'a> {
+    inner: RawBtreeIter,
+    _transaction_guard: Arc<TransactionGuard>,
+    _lifetime: PhantomData<&'a ()>,
+}
+
+impl Iterator for UntypedRange<'_> {
+    type Item = Result<(
+        AccessGuard<'static, &'static [u8]>,
+        AccessGuard<'static, &'static [u8]>,
+    )>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.inner.next().map(|x| {
+            x.map(|(page, key, value)| {
+                (
+                    AccessGuard::with_page(page.clone(), key),
+                    AccessGuard::with_page(page, value),
+                )
+            })
+        })
+    }
+}
+
+// Identifies the contents of a table, see ReadOnlyTable::version()
+#[derive(Copy, Clone, Eq, PartialEq, Debug)]
+pub(crate) struct TableVersion {
+    root: Option<BtreeHeader>,
+    transaction_id: Option<TransactionId>,
 }
 
 /// A read-only table
@@ -538,6 +1329,24 @@ impl<K: Key + 'static, V: Value + 'static> ReadOnlyTable<K, V> {
         })
     }
 
+    // Identifies the contents of the table, or returns None if they can't be identified. The root
+    // checksum covers every page, so it identifies the contents once it has been computed. Until
+    // then, the transaction id of the snapshot is included too, and a table read through a view of
+    // an uncommitted write transaction, whose pages may still change, can't be identified
+    pub(crate) fn version(&self) -> Option<TableVersion> {
+        let root = self.tree.get_root();
+        let transaction_id = match root {
+            Some(header) if header.checksum_deferred() => {
+                Some(self.transaction_guard.tracked_id()?)
+            }
+            _ => None,
+        };
+        Some(TableVersion {
+            root,
+            transaction_id,
+        })
+    }
+
     /// This method is like [`ReadableTable::get()`], but the [`AccessGuard`] is reference counted
     /// and keeps the transaction alive until it is dropped.
     pub fn get<'a>(
@@ -547,6 +1356,39 @@ impl<K: Key + 'static, V: Value + 'static> ReadOnlyTable<K, V> {
         self.tree.get(key.borrow())
     }
 
+    /// Same as [`Table::get_range_of_value()`]
+    pub fn get_range_of_value<'a>(
+        &self,
+        key: impl Borrow<K::SelfType<'a>>,
+        range: impl RangeBounds<usize>,
+    ) -> Result<Option<Vec<u8>>> {
+        self.tree.get_value_range(key.borrow(), byte_range(&range))
+    }
+
+    /// Same as [`Table::estimate_range_size()`]
+    pub fn estimate_range_size<'a, KR>(
+        &self,
+        range: impl RangeBounds<KR> + 'a,
+    ) -> Result<RangeEstimate>
+    where
+        KR: Borrow<K::SelfType<'a>> + 'a,
+    {
+        let (entries, stored_bytes) = self.tree.estimate_range(&range)?;
+        Ok(RangeEstimate {
+            entries,
+            stored_bytes,
+        })
+    }
+
+    /// Returns a [`Cursor`] over the table, which is not positioned at any entry until it is
+    /// seeked. The cursor is reference counted and keeps the transaction alive until it is dropped.
+    pub fn cursor(&self) -> Result<Cursor<'static, K, V>> {
+        Ok(Cursor::new(
+            self.tree.cursor(),
+            self.transaction_guard.clone(),
+        ))
+    }
+
     /// This method is like [`ReadableTable::range()`], but the iterator is reference counted and keeps the transaction
     /// alive until it is dropped.
     pub fn range<'a, KR>(&self, range: impl RangeBounds<KR>) -> Result<Range<'static, K, V>>
@@ -570,6 +1412,8 @@ impl<K: Key + 'static, V: Value + 'static> ReadableTableMetadata for ReadOnlyTab
             stored_leaf_bytes: tree_stats.stored_leaf_bytes,
             metadata_bytes: tree_stats.metadata_bytes,
             fragmented_bytes: tree_stats.fragmented_bytes,
+            leaf_fill_histogram: tree_stats.leaf_fill_histogram,
+            largest_leaf_pages: tree_stats.largest_leaf_pages,
         })
     }
 
@@ -592,6 +1436,13 @@ impl<K: Key + 'static, V: Value + 'static> ReadableTable<K, V> for ReadOnlyTable
             .map(|x| Range::new(x, self.transaction_guard.clone()))
     }
 
+    fn range_len<'a, KR>(&self, range: impl RangeBounds<KR> + 'a) -> Result<u64>
+    where
+        KR: Borrow<K::SelfType<'a>> + 'a,
+    {
+        self.tree.range_len(&range)
+    }
+
     fn first(&self) -> Result<Option<(AccessGuard<'_, K>, AccessGuard<'_, V>)>> {
         self.tree.first()
     }
@@ -615,8 +1466,9 @@ pub struct ExtractIf<
     V: Value + 'static,
     F: for<'f> FnMut(K::SelfType<'f>, V::SelfType<'f>) -> bool,
 > {
-    inner: BtreeExtractIf<'a, K, V, F>,
+    inner: BtreeExtractIf<'a, K, V, Filter<F>>,
     poison_target: Option<&'a WriteTransaction>,
+    removes: Option<&'a mut u64>,
 }
 
 impl<
@@ -627,12 +1479,14 @@ impl<
 > ExtractIf<'a, K, V, F>
 {
     pub(crate) fn new(
-        inner: BtreeExtractIf<'a, K, V, F>,
+        inner: BtreeExtractIf<'a, K, V, Filter<F>>,
         poison_target: Option<&'a WriteTransaction>,
+        removes: Option<&'a mut u64>,
     ) -> Self {
         Self {
             inner,
             poison_target,
+            removes,
         }
     }
 }
@@ -663,6 +1517,11 @@ impl<
 
     fn next(&mut self) -> Option<Self::Item> {
         let entry = self.inner.next()?;
+        if entry.is_ok()
+            && let Some(removes) = self.removes.as_deref_mut()
+        {
+            *removes += 1;
+        }
         Some(entry.map(|entry| {
             let (page, key_range, value_range) = entry.into_raw();
             let key = AccessGuard::with_page(page.clone(), key_range);
@@ -680,6 +1539,79 @@ impl<
 {
     fn next_back(&mut self) -> Option<Self::Item> {
         let entry = self.inner.next_back()?;
+        if entry.is_ok()
+            && let Some(removes) = self.removes.as_deref_mut()
+        {
+            *removes += 1;
+        }
+        Some(entry.map(|entry| {
+            let (page, key_range, value_range) = entry.into_raw();
+            let key = AccessGuard::with_page(page.clone(), key_range);
+            let value = AccessGuard::with_page(page, value_range);
+            (key, value)
+        }))
+    }
+}
+
+/// Iterator returned by [`Table::extract_from_while`]
+pub struct ExtractWhile<
+    'a,
+    K: Key + 'static,
+    V: Value + 'static,
+    F: for<'f> FnMut(K::SelfType<'f>, V::SelfType<'f>) -> ControlFlow<(), bool>,
+> {
+    inner: BtreeExtractIf<'a, K, V, Stoppable<F>>,
+    poison_target: &'a WriteTransaction,
+    removes: &'a mut u64,
+}
+
+impl<
+    K: Key + 'static,
+    V: Value + 'static,
+    F: for<'f> FnMut(K::SelfType<'f>, V::SelfType<'f>) -> ControlFlow<(), bool>,
+> Drop for ExtractWhile<'_, K, V, F>
+{
+    fn drop(&mut self) {
+        if self.inner.predicate_panicked() {
+            self.poison_target.poison();
+        }
+    }
+}
+
+impl<
+    'a,
+    K: Key + 'static,
+    V: Value + 'static,
+    F: for<'f> FnMut(K::SelfType<'f>, V::SelfType<'f>) -> ControlFlow<(), bool>,
+> Iterator for ExtractWhile<'a, K, V, F>
+{
+    type Item = Result<(AccessGuard<'a, K>, AccessGuard<'a, V>)>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let entry = self.inner.next()?;
+        if entry.is_ok() {
+            *self.removes += 1;
+        }
+        Some(entry.map(|entry| {
+            let (page, key_range, value_range) = entry.into_raw();
+            let key = AccessGuard::with_page(page.clone(), key_range);
+            let value = AccessGuard::with_page(page, value_range);
+            (key, value)
+        }))
+    }
+}
+
+impl<
+    K: Key + 'static,
+    V: Value + 'static,
+    F: for<'f> FnMut(K::SelfType<'f>, V::SelfType<'f>) -> ControlFlow<(), bool>,
+> DoubleEndedIterator for ExtractWhile<'_, K, V, F>
+{
+    fn next_back(&mut self) -> Option<Self::Item> {
+        let entry = self.inner.next_back()?;
+        if entry.is_ok() {
+            *self.removes += 1;
+        }
         Some(entry.map(|entry| {
             let (page, key_range, value_range) = entry.into_raw();
             let key = AccessGuard::with_page(page.clone(), key_range);
@@ -720,6 +1652,10 @@ impl<'a, K: Key + 'static, V: Value + 'static> Iterator for Range<'a, K, V> {
             })
         })
     }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.inner.size_hint()
+    }
 }
 
 impl<K: Key + 'static, V: Value + 'static> DoubleEndedIterator for Range<'_, K, V> {
@@ -735,6 +1671,201 @@ impl<K: Key + 'static, V: Value + 'static> DoubleEndedIterator for Range<'_, K,
     }
 }
 
+/// A cursor over the entries of a table, which can be moved in either direction, and repositioned
+/// without creating a new iterator
+///
+/// A cursor is either positioned at an entry, which is returned by [`Cursor::current`], or is
+/// unpositioned. It is unpositioned when it is created, and after it moves past either end of the
+/// table, after which it must be repositioned by one of the `seek` methods.
+///
+/// # Examples
+///
+/// ```rust
+/// use redb::*;
+/// # use tempfile::NamedTempFile;
+/// const TABLE: TableDefinition<u64, u64> = TableDefinition::new("my_data");
+///
+/// # fn main() -> Result<(), Error> {
+/// # #[cfg(not(target_os = "wasi"))]
+/// # let tmpfile = NamedTempFile::new().unwrap();
+/// # #[cfg(target_os = "wasi")]
+/// # let tmpfile = NamedTempFile::new_in("/tmp").unwrap();
+/// # let filename = tmpfile.path();
+/// let db = Database::create(filename)?;
+/// let write_txn = db.begin_write()?;
+/// {
+///     let mut table = write_txn.open_table(TABLE)?;
+///     for i in [10, 20, 30] {
+///         table.insert(i, i)?;
+///     }
+/// }
+/// write_txn.commit()?;
+///
+/// let read_txn = db.begin_read()?;
+/// let table = read_txn.open_table(TABLE)?;
+/// let mut cursor = table.cursor()?;
+/// cursor.seek(15)?;
+/// assert_eq!(cursor.current().unwrap().0.value(), 20);
+/// cursor.prev()?;
+/// assert_eq!(cursor.current().unwrap().0.value(), 10);
+/// cursor.seek_for_prev(29)?;
+/// assert_eq!(cursor.current().unwrap().0.value(), 20);
+/// cursor.next()?;
+/// cursor.next()?;
+/// assert!(cursor.current().is_none());
+/// # Ok(())
+/// # }
+/// ```
+pub struct Cursor<'a, K: Key + 'static, V: Value + 'static> {
+    inner: BtreeCursor<K, V>,
+    _transaction_guard: Arc<TransactionGuard>,
+    // This lifetime is here so that `&` can be held on `Table` preventing concurrent mutation
+    _lifetime: PhantomData<&'a ()>,
+}
+
+impl<'a, K: Key + 'static, V: Value + 'static> Cursor<'a, K, V> {
+    fn new(inner: BtreeCursor<K, V>, guard: Arc<TransactionGuard>) -> Self {
+        Self {
+            inner,
+            _transaction_guard: guard,
+            _lifetime: PhantomData,
+        }
+    }
+
+    /// Returns the entry the cursor is positioned at, or `None` if it is unpositioned
+    pub fn current(&self) -> Option<(AccessGuard<'a, K>, AccessGuard<'a, V>)> {
+        let (page, key_range, value_range) = self.inner.current()?;
+        let key = AccessGuard::with_page(page.clone(), key_range);
+        let value = AccessGuard::with_page(page, value_range);
+        Some((key, value))
+    }
+
+    /// Positions the cursor at the first entry whose key is greater than or equal to `key`
+    pub fn seek<'k>(&mut self, key: impl Borrow<K::SelfType<'k>>) -> Result {
+        self.inner.seek(K::as_bytes(key.borrow()).as_ref())
+    }
+
+    /// Positions the cursor at the last entry whose key is less than or equal to `key`
+    pub fn seek_for_prev<'k>(&mut self, key: impl Borrow<K::SelfType<'k>>) -> Result {
+        self.inner.seek_for_prev(K::as_bytes(key.borrow()).as_ref())
+    }
+
+    /// Positions the cursor at the first entry in the table
+    pub fn seek_to_first(&mut self) -> Result {
+        self.inner.seek_to_first()
+    }
+
+    /// Positions the cursor at the last entry in the table
+    pub fn seek_to_last(&mut self) -> Result {
+        self.inner.seek_to_last()
+    }
+
+    /// Moves the cursor to the next entry. If it is at the last entry, it becomes unpositioned
+    #[allow(clippy::should_implement_trait)]
+    pub fn next(&mut self) -> Result {
+        self.inner.next()
+    }
+
+    /// Moves the cursor to the previous entry. If it is at the first entry, it becomes unpositioned
+    pub fn prev(&mut self) -> Result {
+        self.inner.prev()
+    }
+}
+
+/// Iterator returned by [`ReadableTable::try_range()`], which checks that each entry can be decoded
+pub struct TryRange<'a, K: Key + 'static, V: Value + 'static> {
+    inner: Range<'a, K, V>,
+}
+
+impl<'a, K: Key + TryValue + 'static, V: TryValue + 'static> TryRange<'a, K, V> {
+    fn check(
+        entry: Result<(AccessGuard<'a, K>, AccessGuard<'a, V>)>,
+    ) -> Result<(AccessGuard<'a, K>, AccessGuard<'a, V>)> {
+        let (key, value) = entry?;
+        key.try_value()?;
+        value.try_value()?;
+        Ok((key, value))
+    }
+}
+
+impl<'a, K: Key + TryValue + 'static, V: TryValue + 'static> Iterator for TryRange<'a, K, V> {
+    type Item = Result<(AccessGuard<'a, K>, AccessGuard<'a, V>)>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.inner.next().map(Self::check)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.inner.size_hint()
+    }
+}
+
+impl<K: Key + TryValue + 'static, V: TryValue + 'static> DoubleEndedIterator
+    for TryRange<'_, K, V>
+{
+    fn next_back(&mut self) -> Option<Self::Item> {
+        self.inner.next_back().map(Self::check)
+    }
+}
+
+/// Iterator returned by [`ReadableTable::keys()`]
+pub struct Keys<'a, K: Key + 'static, V: Value + 'static> {
+    inner: Range<'a, K, V>,
+}
+
+impl<'a, K: Key + 'static, V: Value + 'static> Iterator for Keys<'a, K, V> {
+    type Item = Result<AccessGuard<'a, K>>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.inner
+            .inner
+            .next_key()
+            .map(|x| x.map(|(page, key_range)| AccessGuard::with_page(page, key_range)))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.inner.size_hint()
+    }
+}
+
+impl<K: Key + 'static, V: Value + 'static> DoubleEndedIterator for Keys<'_, K, V> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        self.inner
+            .inner
+            .next_back_key()
+            .map(|x| x.map(|(page, key_range)| AccessGuard::with_page(page, key_range)))
+    }
+}
+
+/// Iterator returned by [`ReadableTable::values()`]
+pub struct Values<'a, K: Key + 'static, V: Value + 'static> {
+    inner: Range<'a, K, V>,
+}
+
+impl<'a, K: Key + 'static, V: Value + 'static> Iterator for Values<'a, K, V> {
+    type Item = Result<AccessGuard<'a, V>>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.inner
+            .inner
+            .next_value()
+            .map(|x| x.map(|(page, value_range)| AccessGuard::with_page(page, value_range)))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.inner.size_hint()
+    }
+}
+
+impl<K: Key + 'static, V: Value + 'static> DoubleEndedIterator for Values<'_, K, V> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        self.inner
+            .inner
+            .next_back_value()
+            .map(|x| x.map(|(page, value_range)| AccessGuard::with_page(page, value_range)))
+    }
+}
+
 /// A view into a single entry in a [`Table`], which may either be vacant or occupied.
 ///
 /// This `enum` is constructed from the [`entry`] method on [`Table`], and mirrors
@@ -829,6 +1960,7 @@ impl<'a, K: Key + 'static, V: Value + 'static> Entry<'a, K, V> {
 /// A view into an occupied entry in a [`Table`]. It is part of the [`Entry`] enum.
 pub struct OccupiedEntry<'a, K: Key + 'static, V: Value + 'static> {
     tree: &'a mut BtreeMut<K, V>,
+    operations: &'a mut TableOperationStats,
     key: K::SelfType<'a>,
 }
 
@@ -879,30 +2011,42 @@ impl<'a, K: Key + 'static, V: Value + 'static> OccupiedEntry<'a, K, V> {
         if value_len + key_len > MAX_PAIR_LENGTH {
             return Err(StorageError::ValueTooLarge(value_len + key_len));
         }
-        self.tree.insert(&self.key, value.borrow())?.ok_or_else(|| {
-            StorageError::Corrupted(
-                "entry for key disappeared while OccupiedEntry was live".to_string(),
-            )
-        })
+        let old_value = self
+            .tree
+            .insert(&self.key, value.borrow())?
+            .ok_or_else(|| {
+                StorageError::Corrupted(
+                    "entry for key disappeared while OccupiedEntry was live".to_string(),
+                )
+            })?;
+        self.operations.inserts += 1;
+        Ok(old_value)
     }
 
     /// Takes the value out of the entry, and returns it.
     pub fn remove(self) -> Result<AccessGuard<'a, V>> {
-        self.tree.remove(&self.key)?.ok_or_else(|| {
+        let value = self.tree.remove(&self.key)?.ok_or_else(|| {
             StorageError::Corrupted(
                 "entry for key disappeared while OccupiedEntry was live".to_string(),
             )
-        })
+        })?;
+        self.operations.removes += 1;
+        Ok(value)
     }
 
     /// Takes the entry out of the table, returning the key and the value.
     pub fn remove_entry(self) -> Result<(K::SelfType<'a>, AccessGuard<'a, V>)> {
-        let OccupiedEntry { tree, key } = self;
+        let OccupiedEntry {
+            tree,
+            operations,
+            key,
+        } = self;
         let value = tree.remove(&key)?.ok_or_else(|| {
             StorageError::Corrupted(
                 "entry for key disappeared while OccupiedEntry was live".to_string(),
             )
         })?;
+        operations.removes += 1;
         Ok((key, value))
     }
 }
@@ -910,6 +2054,7 @@ impl<'a, K: Key + 'static, V: Value + 'static> OccupiedEntry<'a, K, V> {
 /// A view into a vacant entry in a [`Table`]. It is part of the [`Entry`] enum.
 pub struct VacantEntry<'a, K: Key + 'static, V: Value + 'static> {
     tree: &'a mut BtreeMut<K, V>,
+    operations: &'a mut TableOperationStats,
     key: K::SelfType<'a>,
 }
 
@@ -935,6 +2080,7 @@ impl<'a, K: Key + 'static, V: Value + 'static> VacantEntry<'a, K, V> {
             return Err(StorageError::ValueTooLarge(value_len + key_len));
         }
         self.tree.insert(&self.key, value.borrow())?;
+        self.operations.inserts += 1;
         self.tree.get_mut(&self.key)?.ok_or_else(|| {
             StorageError::Corrupted(
                 "inserted entry not found after VacantEntry::insert".to_string(),
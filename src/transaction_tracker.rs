@@ -1,5 +1,7 @@
+#[cfg(debug_assertions)]
+use crate::tree_store::PageNumber;
 use crate::tree_store::TransactionalMemory;
-use crate::{Key, Result, Savepoint, TypeName, Value};
+use crate::{Key, Result, Savepoint, StorageError, TypeName, Value, WritePriority};
 #[cfg(feature = "logging")]
 use log::debug;
 use std::cmp::Ordering;
@@ -7,7 +9,8 @@ use std::collections::btree_map::BTreeMap;
 use std::collections::{BTreeSet, HashMap};
 use std::mem;
 use std::mem::size_of;
-use std::sync::{Condvar, Mutex};
+use std::sync::{Arc, Condvar, Mutex, RwLock, RwLockReadGuard};
+use std::time::{Duration, SystemTime};
 
 #[derive(Copy, Clone, Hash, Ord, PartialOrd, Eq, PartialEq, Debug)]
 pub(crate) struct TransactionId(u64);
@@ -74,13 +77,153 @@ impl Key for SavepointId {
     }
 }
 
+/// Whether an [`ActiveTransaction`] is a read or a write transaction
+#[derive(Copy, Clone, Eq, PartialEq, Debug)]
+pub enum TransactionKind {
+    Read,
+    Write,
+}
+
+/// A transaction which has been started and not yet dropped
+///
+/// Returned by [`Database::active_transactions()`](crate::Database::active_transactions)
+#[derive(Clone, Debug)]
+pub struct ActiveTransaction {
+    kind: TransactionKind,
+    transaction_id: u64,
+    started: SystemTime,
+    snapshot_age: u64,
+    label: Option<String>,
+}
+
+impl ActiveTransaction {
+    /// Whether this is a read or a write transaction
+    pub fn kind(&self) -> TransactionKind {
+        self.kind
+    }
+
+    /// The id of the transaction
+    ///
+    /// For a read transaction, this is the id of the committed write transaction whose snapshot it
+    /// reads, so several read transactions may share an id
+    pub fn transaction_id(&self) -> u64 {
+        self.transaction_id
+    }
+
+    /// The label set with [`ReadTransaction::set_label()`](crate::ReadTransaction::set_label) or
+    /// [`WriteTransaction::set_label()`](crate::WriteTransaction::set_label), if any
+    pub fn label(&self) -> Option<&str> {
+        self.label.as_deref()
+    }
+
+    /// The time at which the transaction began
+    pub fn started(&self) -> SystemTime {
+        self.started
+    }
+
+    /// Time elapsed since the transaction began
+    pub fn elapsed(&self) -> Duration {
+        self.started.elapsed().unwrap_or_default()
+    }
+
+    /// How far the snapshot this transaction reads is behind the latest commit, measured in
+    /// transaction ids. Zero if nothing has been committed since it began, and always zero for a
+    /// write transaction
+    ///
+    /// Pages freed by later commits cannot be reused until this transaction is dropped
+    pub fn snapshot_age(&self) -> u64 {
+        self.snapshot_age
+    }
+}
+
+// Shared by a read transaction and the tracker. Pages are only read from the transaction's snapshot
+// while holding the lease, so that once it has expired the snapshot can be released without racing
+// with a read of one of its pages
+#[derive(Default)]
+pub(crate) struct ReadLease {
+    expired: RwLock<bool>,
+    // Reference counts of the pages read through this lease which are outstanding
+    #[cfg(debug_assertions)]
+    open_pages: Arc<Mutex<HashMap<PageNumber, u64>>>,
+}
+
+impl ReadLease {
+    #[cfg(debug_assertions)]
+    pub(crate) fn open_pages(&self) -> Arc<Mutex<HashMap<PageNumber, u64>>> {
+        self.open_pages.clone()
+    }
+
+    // Pages held by an expired transaction may be freed and reused, since it can no longer access
+    // them through its snapshot
+    #[cfg(debug_assertions)]
+    pub(crate) fn holds_page(&self, page: PageNumber) -> bool {
+        match self.expired.try_read() {
+            Ok(expired) if !*expired => self.open_pages.lock().unwrap().contains_key(&page),
+            _ => false,
+        }
+    }
+
+    pub(crate) fn hold(&self) -> Result<RwLockReadGuard<'_, bool>> {
+        let guard = self.expired.read()?;
+        if *guard {
+            return Err(StorageError::ReadTransactionExpired);
+        }
+        Ok(guard)
+    }
+
+    pub(crate) fn expire(&self) {
+        *self.expired.write().unwrap() = true;
+    }
+}
+
+struct ActiveRead {
+    snapshot: TransactionId,
+    started: SystemTime,
+    lease: Arc<ReadLease>,
+    label: Option<String>,
+    // Whether it has already been returned by `take_long_reads`
+    reported: bool,
+}
+
+impl ActiveRead {
+    fn to_active_transaction(&self, last_committed: TransactionId) -> ActiveTransaction {
+        ActiveTransaction {
+            kind: TransactionKind::Read,
+            transaction_id: self.snapshot.raw_id(),
+            started: self.started,
+            snapshot_age: last_committed
+                .raw_id()
+                .saturating_sub(self.snapshot.raw_id()),
+            label: self.label.clone(),
+        }
+    }
+}
+
 struct State {
     next_savepoint_id: SavepointId,
     // reference count of read transactions per transaction id
     live_read_transactions: BTreeMap<TransactionId, u64>,
     next_transaction_id: TransactionId,
     live_write_transaction: Option<TransactionId>,
+    live_write_started: Option<SystemTime>,
+    live_write_label: Option<String>,
+    // User read transactions, keyed by a handle unique to each transaction. Their snapshots are
+    // also counted in live_read_transactions
+    active_reads: BTreeMap<u64, ActiveRead>,
+    next_read_handle: u64,
+    // Number of threads waiting to begin a high priority write transaction. Normal priority writers
+    // wait until this is zero
+    waiting_high_priority_writers: u64,
+    // Number of threads waiting to begin a write transaction of any priority
+    waiting_writers: u64,
     valid_savepoints: BTreeMap<SavepointId, TransactionId>,
+    // Transactions of the savepoints that have been deleted or invalidated since the last call to
+    // take_released_savepoints(). Data pages freed after them may no longer be reachable from any
+    // savepoint
+    released_savepoints: Vec<TransactionId>,
+    // Data pages freed before this transaction have been checked for whether any savepoint can
+    // reach them. See WriteTransaction::free_unpinned_data_pages()
+    unpinned_checked_until: TransactionId,
     // Non-durable commits that are still in-memory, and waiting for a durable commit to get flushed
     // We need to make sure that the freed-table does not get processed for these, since they are not durable yet
     // Therefore, we hold a read transaction on their nearest durable ancestor
@@ -104,7 +247,15 @@ impl TransactionTracker {
                 live_read_transactions: BTreeMap::default(),
                 next_transaction_id,
                 live_write_transaction: None,
+                live_write_started: None,
+                live_write_label: None,
+                active_reads: BTreeMap::default(),
+                next_read_handle: 0,
+                waiting_high_priority_writers: 0,
+                waiting_writers: 0,
                 valid_savepoints: BTreeMap::default(),
+                released_savepoints: vec![],
+                unpinned_checked_until: TransactionId::new(0),
                 pending_non_durable_commits: HashMap::default(),
                 unprocessed_freed_non_durable_commits: BTreeSet::default(),
             }),
@@ -112,16 +263,32 @@ impl TransactionTracker {
         }
     }
 
-    pub(crate) fn start_write_transaction(&self) -> TransactionId {
+    pub(crate) fn start_write_transaction(&self, priority: WritePriority) -> TransactionId {
         let mut state = self.state.lock().unwrap();
-        while state.live_write_transaction.is_some() {
-            state = self.live_write_transaction_available.wait(state).unwrap();
+        state.waiting_writers += 1;
+        match priority {
+            WritePriority::Normal => {
+                while state.live_write_transaction.is_some()
+                    || state.waiting_high_priority_writers > 0
+                {
+                    state = self.live_write_transaction_available.wait(state).unwrap();
+                }
+            }
+            WritePriority::High => {
+                state.waiting_high_priority_writers += 1;
+                while state.live_write_transaction.is_some() {
+                    state = self.live_write_transaction_available.wait(state).unwrap();
+                }
+                state.waiting_high_priority_writers -= 1;
+            }
         }
         assert!(state.live_write_transaction.is_none());
+        state.waiting_writers -= 1;
         let transaction_id = state.next_transaction_id.increment();
         #[cfg(feature = "logging")]
         debug!("Beginning write transaction id={transaction_id:?}");
         state.live_write_transaction = Some(transaction_id);
+        state.live_write_started = Some(SystemTime::now());
 
         transaction_id
     }
@@ -130,7 +297,15 @@ impl TransactionTracker {
         let mut state = self.state.lock().unwrap();
         assert_eq!(state.live_write_transaction.unwrap(), id);
         state.live_write_transaction = None;
-        self.live_write_transaction_available.notify_one();
+        state.live_write_started = None;
+        state.live_write_label = None;
+        // Wake every waiter, since a normal priority writer must not consume the wakeup while a
+        // high priority writer is waiting
+        self.live_write_transaction_available.notify_all();
+    }
+
+    pub(crate) fn any_writer_waiting(&self) -> bool {
+        self.state.lock().unwrap().waiting_writers > 0
     }
 
     pub(crate) fn clear_pending_non_durable_commits(&self) {
@@ -253,6 +428,115 @@ impl TransactionTracker {
         }
     }
 
+    // Records a read transaction, whose snapshot must already be registered with
+    // `register_read_transaction`, and returns the handle to pass to `end_read_transaction`
+    pub(crate) fn begin_read_transaction(&self, snapshot: TransactionId) -> (u64, Arc<ReadLease>) {
+        let mut state = self.state.lock().unwrap();
+        let handle = state.next_read_handle;
+        state.next_read_handle += 1;
+        let lease = Arc::new(ReadLease::default());
+        state.active_reads.insert(
+            handle,
+            ActiveRead {
+                snapshot,
+                started: SystemTime::now(),
+                lease: lease.clone(),
+                label: None,
+                reported: false,
+            },
+        );
+        (handle, lease)
+    }
+
+    pub(crate) fn end_read_transaction(&self, handle: u64) {
+        let read = self.state.lock().unwrap().active_reads.remove(&handle);
+        // Expired transactions have already released their snapshot
+        if let Some(read) = read {
+            self.deallocate_read_transaction(read.snapshot);
+        }
+    }
+
+    // Expires the read transactions which began more than `max_age` ago, and releases their
+    // snapshots. Returns the number expired
+    pub(crate) fn expire_reads(&self, max_age: Duration) -> usize {
+        let expired: Vec<ActiveRead> = {
+            let mut state = self.state.lock().unwrap();
+            let handles: Vec<u64> = state
+                .active_reads
+                .iter()
+                .filter(|(_, read)| read.started.elapsed().unwrap_or_default() >= max_age)
+                .map(|(handle, _)| *handle)
+                .collect();
+            handles
+                .iter()
+                .map(|handle| state.active_reads.remove(handle).unwrap())
+                .collect()
+        };
+        for read in &expired {
+            // Waits for any page read in progress, so no page of the snapshot is read after it
+            // is released
+            read.lease.expire();
+            self.deallocate_read_transaction(read.snapshot);
+        }
+        expired.len()
+    }
+
+    pub(crate) fn set_read_label(&self, handle: u64, label: String) {
+        let mut state = self.state.lock().unwrap();
+        if let Some(read) = state.active_reads.get_mut(&handle) {
+            read.label = Some(label);
+        }
+    }
+
+    pub(crate) fn set_write_label(&self, id: TransactionId, label: String) {
+        let mut state = self.state.lock().unwrap();
+        assert_eq!(state.live_write_transaction, Some(id));
+        state.live_write_label = Some(label);
+    }
+
+    // Returns the read transactions which began more than `threshold` ago, and have not been
+    // returned by a previous call
+    pub(crate) fn take_long_reads(
+        &self,
+        threshold: Duration,
+        last_committed: TransactionId,
+    ) -> Vec<ActiveTransaction> {
+        let mut state = self.state.lock().unwrap();
+        let mut result = vec![];
+        for read in state.active_reads.values_mut() {
+            if !read.reported && read.started.elapsed().unwrap_or_default() >= threshold {
+                read.reported = true;
+                result.push(read.to_active_transaction(last_committed));
+            }
+        }
+        result
+    }
+
+    // Oldest snapshot first
+    pub(crate) fn active_transactions(
+        &self,
+        last_committed: TransactionId,
+    ) -> Vec<ActiveTransaction> {
+        let state = self.state.lock().unwrap();
+        let mut result: Vec<ActiveTransaction> = state
+            .active_reads
+            .values()
+            .map(|read| read.to_active_transaction(last_committed))
+            .collect();
+        if let (Some(id), Some(started)) = (state.live_write_transaction, state.live_write_started)
+        {
+            result.push(ActiveTransaction {
+                kind: TransactionKind::Write,
+                transaction_id: id.raw_id(),
+                started,
+                snapshot_age: 0,
+                label: state.live_write_label.clone(),
+            });
+        }
+        result.sort_by_key(|txn| (txn.transaction_id, txn.started));
+        result
+    }
+
     pub(crate) fn any_savepoint_exists(&self) -> bool {
         !self.state.lock().unwrap().valid_savepoints.is_empty()
     }
@@ -284,11 +568,12 @@ impl TransactionTracker {
 
     // Deallocates the given savepoint and its matching reference count on the transcation
     pub(crate) fn deallocate_savepoint(&self, savepoint: SavepointId, transaction: TransactionId) {
-        self.state
-            .lock()
-            .unwrap()
-            .valid_savepoints
-            .remove(&savepoint);
+        {
+            let mut state = self.state.lock().unwrap();
+            if let Some(transaction_id) = state.valid_savepoints.remove(&savepoint) {
+                state.released_savepoints.push(transaction_id);
+            }
+        }
         self.deallocate_read_transaction(transaction);
     }
 
@@ -323,10 +608,28 @@ impl TransactionTracker {
     pub(crate) fn invalidate_savepoints(&self, savepoints: impl IntoIterator<Item = SavepointId>) {
         let mut state = self.state.lock().unwrap();
         for id in savepoints {
-            state.valid_savepoints.remove(&id);
+            if let Some(transaction_id) = state.valid_savepoints.remove(&id) {
+                state.released_savepoints.push(transaction_id);
+            }
         }
     }
 
+    // Returns the transactions of the valid savepoints, along with those of the savepoints released
+    // since the last call
+    pub(crate) fn take_released_savepoints(&self) -> (BTreeSet<TransactionId>, Vec<TransactionId>) {
+        let mut state = self.state.lock().unwrap();
+        let valid = state.valid_savepoints.values().copied().collect();
+        (valid, mem::take(&mut state.released_savepoints))
+    }
+
+    pub(crate) fn unpinned_checked_until(&self) -> TransactionId {
+        self.state.lock().unwrap().unpinned_checked_until
+    }
+
+    pub(crate) fn set_unpinned_checked_until(&self, transaction_id: TransactionId) {
+        self.state.lock().unwrap().unpinned_checked_until = transaction_id;
+    }
+
     pub(crate) fn oldest_savepoint(&self) -> Option<(SavepointId, TransactionId)> {
         self.state
             .lock()
@@ -346,6 +649,22 @@ impl TransactionTracker {
             .copied()
     }
 
+    // Like oldest_live_read_transaction(), but ignores the references held by valid savepoints
+    pub(crate) fn oldest_live_read_transaction_excluding_savepoints(
+        &self,
+    ) -> Option<TransactionId> {
+        let state = self.state.lock().unwrap();
+        let mut savepoints: HashMap<TransactionId, u64> = HashMap::new();
+        for transaction_id in state.valid_savepoints.values() {
+            *savepoints.entry(*transaction_id).or_default() += 1;
+        }
+        state
+            .live_read_transactions
+            .iter()
+            .find(|(id, count)| **count > savepoints.get(id).copied().unwrap_or_default())
+            .map(|(id, _)| *id)
+    }
+
     // Returns the transaction id of the oldest non-durable transaction which has not been processed
     // for freeing, which has live read transactions
     pub(crate) fn oldest_live_read_nondurable_transaction(&self) -> Option<TransactionId> {
@@ -1,34 +1,47 @@
-use crate::db::TransactionGuard;
-use crate::error::CommitError;
+use crate::db::{Database, TransactionGuard};
+use crate::error::{CommitError, ValidationError};
+use crate::group_commit::GroupCommit;
 use crate::multimap_table::ReadOnlyUntypedMultimapTable;
+use crate::schema_history::{SchemaChange, SchemaChangeKind};
 use crate::sealed::Sealed;
 use crate::table::ReadOnlyUntypedTable;
-use crate::transaction_tracker::{SavepointId, TransactionId, TransactionTracker};
+use crate::transaction_tracker::{ReadLease, SavepointId, TransactionId, TransactionTracker};
 use crate::tree_store::{
-    AllocationPolicy, Btree, BtreeHeader, BtreeMut, InternalTableDefinition, MAX_PAIR_LENGTH,
-    MAX_VALUE_LENGTH, Page, PageAllocator, PageHint, PageListMut, PageNumber, PageResolver,
-    PageTrackerPolicy, SerializedSavepoint, ShrinkPolicy, TableTree, TableTreeMut, TableType,
-    TransactionalMemory,
+    AllocationPolicy, Btree, BtreeHeader, BtreeMut, ContentHasher, Filter, InternalTableDefinition,
+    MAX_PAIR_LENGTH, MAX_VALUE_LENGTH, Page, PageAllocator, PageHint, PageListMut, PageNumber,
+    PageNumberHashSet, PagePath, PageResolver, PageTrackerPolicy, RawBtree, SerializedSavepoint,
+    ShrinkPolicy, TableTree, TableTreeCheckpoint, TableTreeMut, TableType, TransactionalMemory,
+    diff_btrees,
 };
 use crate::types::{Key, Value};
+use crate::watch::{PendingEvents, WatchRegistry};
 use crate::{
-    AccessGuard, AccessGuardMutInPlace, ExtractIf, MultimapTable, MultimapTableDefinition,
-    MultimapTableHandle, MutInPlaceValue, Range, ReadOnlyMultimapTable, ReadOnlyTable, Result,
-    Savepoint, SavepointError, SetDurabilityError, StorageError, Table, TableDefinition,
-    TableError, TableHandle, TransactionError, TypeName, UntypedMultimapTableHandle,
-    UntypedTableHandle,
+    AccessGuard, AccessGuardMutInPlace, ChangeRecord, ExtractIf, MultimapTable,
+    MultimapTableDefinition, MultimapTableHandle, MutInPlaceValue, PrepareError, QueueTable,
+    QueueTableDefinition, Range, ReadOnlyMultimapTable, ReadOnlyQueueTable, ReadOnlySetTable,
+    ReadOnlyTable, Result, Savepoint, SavepointError, SavepointInfo, SequenceError,
+    SetDurabilityError, SetTable, SetTableDefinition, StorageError, Table, TableDefinition,
+    TableError, TableHandle, TableOperationStats, TransactionError, TypeName,
+    UntypedMultimapTableHandle, UntypedTableHandle, WriteBatch,
 };
 #[cfg(feature = "logging")]
-use log::{debug, warn};
+use log::{debug, info, warn};
 use std::borrow::Borrow;
-use std::cmp::min;
+use std::cmp::{max, min};
 use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
 use std::fmt::{Debug, Display, Formatter};
 use std::marker::PhantomData;
-use std::mem::size_of;
-use std::ops::{RangeBounds, RangeFull};
+use std::mem::{self, size_of};
+use std::ops::{Bound, RangeBounds, RangeFrom, RangeFull};
 use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::{Arc, Mutex};
+use std::time::{Duration, SystemTime};
+#[cfg(feature = "async")]
+use std::{
+    future::Future,
+    pin::Pin,
+    task::{Context, Poll, Waker},
+};
 use std::{panic, thread};
 
 const MAX_PAGES_PER_COMPACTION: usize = 1_000_000;
@@ -42,6 +55,10 @@ pub(crate) const DATA_ALLOCATED_TABLE: SystemTableDefinition<
     TransactionIdWithPagination,
     PageList,
 > = SystemTableDefinition::new("data_pages_allocated");
+// The transaction which allocated each of the pages in DATA_ALLOCATED_TABLE, keyed by the
+// serialized page number. Used to find the freed pages that no savepoint can reference
+const DATA_ALLOCATED_BY_PAGE_TABLE: SystemTableDefinition<u64, u64> =
+    SystemTableDefinition::new("data_pages_allocated_by_page");
 // Pages in the data tree that are in the pending free state: i.e., they are unreachable from the
 // root as of the given transaction.
 pub(crate) const DATA_FREED_TABLE: SystemTableDefinition<TransactionIdWithPagination, PageList> =
@@ -50,6 +67,44 @@ pub(crate) const DATA_FREED_TABLE: SystemTableDefinition<TransactionIdWithPagina
 // root as of the given transaction.
 pub(crate) const SYSTEM_FREED_TABLE: SystemTableDefinition<TransactionIdWithPagination, PageList> =
     SystemTableDefinition::new("system_pages_unreachable");
+// Changes to the set of tables, keyed by a sequence number. See SchemaChange for the format
+const SCHEMA_HISTORY_TABLE: SystemTableDefinition<u64, &[u8]> =
+    SystemTableDefinition::new("schema_history");
+// Changes to the entries of normal tables, keyed by transaction id and a sequence number. See
+// ChangeRecord for the format
+const CHANGE_LOG_TABLE: SystemTableDefinition<(u64, u64), &[u8]> =
+    SystemTableDefinition::new("change_log");
+// Transactions which have been prepared, but not yet committed or rolled back, keyed by their id.
+// See PreparedTransaction for the format
+const PREPARED_TABLE: SystemTableDefinition<u64, &[u8]> =
+    SystemTableDefinition::new("prepared_transactions");
+// Pages in the data tree that were allocated by a prepared transaction. They are only reachable
+// from its prepared root
+pub(crate) const PREPARED_ALLOCATED_TABLE: SystemTableDefinition<
+    TransactionIdWithPagination,
+    PageList,
+> = SystemTableDefinition::new("prepared_pages_allocated");
+// Pages in the data tree that a prepared transaction replaced. They become unreachable once it is
+// committed
+const PREPARED_FREED_TABLE: SystemTableDefinition<TransactionIdWithPagination, PageList> =
+    SystemTableDefinition::new("prepared_pages_replaced");
+// Coordinated commits which have been decided by the coordinator database, keyed by their id. See
+// CommitCoordinator
+const COORDINATED_COMMITS_TABLE: SystemTableDefinition<u64, ()> =
+    SystemTableDefinition::new("coordinated_commits");
+// Creation time, in microseconds since the unix epoch, and label of each persistent savepoint.
+// Savepoints created by older versions have no entry
+const SAVEPOINT_INFO_TABLE: SystemTableDefinition<SavepointId, (u64, Option<&str>)> =
+    SystemTableDefinition::new("persistent_savepoint_info");
+// Persistent savepoints which were created with a name, keyed by the name
+const NAMED_SAVEPOINT_TABLE: SystemTableDefinition<&str, SavepointId> =
+    SystemTableDefinition::new("named_persistent_savepoints");
+// Regions quarantined by Database::quarantine_corrupted_regions(), which are quarantined again
+// when the database is opened
+const QUARANTINED_REGIONS_TABLE: SystemTableDefinition<u32, ()> =
+    SystemTableDefinition::new("quarantined_regions");
+// Next id to be returned by each sequence
+const SEQUENCE_TABLE: SystemTableDefinition<&str, u64> = SystemTableDefinition::new("sequences");
 // The allocator state table is stored in the system table tree, but it's accessed using
 // raw btree operations rather than open_system_table(), so there's no SystemTableDefinition
 pub(crate) const ALLOCATOR_STATE_TABLE_NAME: &str = "allocator_state";
@@ -131,6 +186,10 @@ impl MutInPlaceValue for PageList<'_> {
     }
 }
 
+fn page_key(page: PageNumber) -> u64 {
+    u64::from_le_bytes(page.to_le_bytes())
+}
+
 #[derive(Debug)]
 pub(crate) struct TransactionIdWithPagination {
     pub(crate) transaction_id: u64,
@@ -358,6 +417,48 @@ impl DatabaseStats {
     }
 }
 
+/// Work done so far by a write transaction, returned by [`WriteTransaction::transaction_stats()`]
+#[derive(Debug, Clone)]
+pub struct TransactionStats {
+    pub(crate) allocated_pages: u64,
+    pub(crate) allocated_bytes: u64,
+    pub(crate) freed_pages: u64,
+    pub(crate) dirty_bytes: u64,
+    pub(crate) table_operations: Vec<(String, TableOperationStats)>,
+}
+
+impl TransactionStats {
+    /// Number of pages allocated, including those which were later freed in the same transaction
+    pub fn allocated_pages(&self) -> u64 {
+        self.allocated_pages
+    }
+
+    /// Number of bytes in the pages allocated. Every allocated page which is still in use at
+    /// commit is written to storage
+    pub fn written_bytes(&self) -> u64 {
+        self.allocated_bytes
+    }
+
+    /// Number of pages freed, or which will be freed once no read transaction or savepoint
+    /// references them
+    pub fn freed_pages(&self) -> u64 {
+        self.freed_pages
+    }
+
+    /// Number of bytes of modified pages held in the cache, which have not been written to storage
+    pub fn dirty_bytes(&self) -> u64 {
+        self.dirty_bytes
+    }
+
+    /// Writes to each table, ordered by table name. Only tables which have been written to, and
+    /// then dropped, are included
+    pub fn table_operations(&self) -> impl Iterator<Item = (&str, TableOperationStats)> {
+        self.table_operations
+            .iter()
+            .map(|(name, operations)| (name.as_str(), *operations))
+    }
+}
+
 #[derive(Copy, Clone, Debug)]
 #[non_exhaustive]
 pub enum Durability {
@@ -369,6 +470,18 @@ pub enum Durability {
     Immediate,
 }
 
+/// Priority of a write transaction, when waiting to begin
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum WritePriority {
+    /// Begins in no particular order, relative to other writers with this priority
+    Normal,
+    /// Begins before any waiting [`WritePriority::Normal`] writers. Intended for operational
+    /// transactions, such as deleting data to free space, which should not wait behind a queue of
+    /// other writes
+    High,
+}
+
 // These are the actual durability levels used internally. `Durability::Paranoid` is translated
 // to `InternalDurability::Immediate`, and also enables 2-phase commit
 #[derive(Copy, Clone, Debug, PartialEq, Eq)]
@@ -383,6 +496,18 @@ enum PostCommitFree {
     Disabled,
 }
 
+#[derive(Copy, Clone, Eq, PartialEq)]
+enum ChangeCapture {
+    Enabled,
+    Disabled,
+}
+
+#[derive(Copy, Clone, Eq, PartialEq)]
+enum SchemaHistory {
+    Enabled,
+    Disabled,
+}
+
 // Like a Table but only one may be open at a time to avoid possible races
 pub struct SystemTable<'s, K: Key + 'static, V: Value + 'static> {
     name: String,
@@ -443,8 +568,8 @@ impl<'s, K: Key + 'static, V: Value + 'static> SystemTable<'s, K, V> {
         KR: Borrow<K::SelfType<'a>> + 'a,
     {
         self.tree
-            .extract_from_if(&range, predicate)
-            .map(|inner| ExtractIf::new(inner, None))
+            .extract_from_if(&range, Filter(predicate))
+            .map(|inner| ExtractIf::new(inner, None, None))
     }
 
     pub fn insert<'k, 'v>(
@@ -545,7 +670,7 @@ impl SystemNamespace {
         transaction: &WriteTransaction,
         definition: SystemTableDefinition<K, V>,
     ) -> Result<SystemTable<'s, K, V>> {
-        let (root, _) = self
+        let (root, _, _) = self
             .table_tree
             .get_or_create_table::<K, V>(definition.name(), TableType::Normal)
             .map_err(|e| {
@@ -596,6 +721,8 @@ struct TableNamespace {
     allocated_pages: Arc<Mutex<PageTrackerPolicy>>,
     freed_pages: Arc<Mutex<Vec<PageNumber>>>,
     table_tree: TableTreeMut,
+    // Writes to each table, added when it is closed
+    operations: HashMap<String, TableOperationStats>,
 }
 
 impl TableNamespace {
@@ -620,6 +747,7 @@ impl TableNamespace {
             table_tree,
             freed_pages,
             allocated_pages: allocated,
+            operations: HashMap::default(),
         }
     }
 
@@ -632,9 +760,9 @@ impl TableNamespace {
         }
     }
 
-    fn set_root(&mut self, root: Option<BtreeHeader>) {
+    fn set_root(&mut self, root: Option<BtreeHeader>) -> Result {
         assert!(self.open_tables.is_empty());
-        self.table_tree.set_root(root);
+        self.table_tree.set_root(root)
     }
 
     #[track_caller]
@@ -642,7 +770,7 @@ impl TableNamespace {
         &mut self,
         name: &str,
         table_type: TableType,
-    ) -> Result<(Option<BtreeHeader>, u64), TableError> {
+    ) -> Result<(Option<BtreeHeader>, u64, bool), TableError> {
         if let Some(location) = self.open_tables.get(name) {
             return Err(TableError::TableAlreadyOpen(name.to_string(), location));
         }
@@ -661,21 +789,24 @@ impl TableNamespace {
         &mut self,
         transaction: &'txn WriteTransaction,
         definition: MultimapTableDefinition<K, V>,
-    ) -> Result<MultimapTable<'txn, K, V>, TableError> {
+    ) -> Result<(MultimapTable<'txn, K, V>, bool), TableError> {
         #[cfg(feature = "logging")]
         debug!("Opening multimap table: {definition}");
-        let (root, length) = self.inner_open::<K, V>(definition.name(), TableType::Multimap)?;
+        let (root, length, created) =
+            self.inner_open::<K, V>(definition.name(), TableType::Multimap)?;
         self.set_dirty(transaction);
 
-        Ok(MultimapTable::new(
+        let table = MultimapTable::new(
             definition.name(),
             root,
             length,
+            definition.max_values_per_key(),
             self.freed_pages.clone(),
             self.allocated_pages.clone(),
             self.table_tree.page_allocator().clone(),
             transaction,
-        ))
+        );
+        Ok((table, created))
     }
 
     #[track_caller]
@@ -683,20 +814,21 @@ impl TableNamespace {
         &mut self,
         transaction: &'txn WriteTransaction,
         definition: TableDefinition<K, V>,
-    ) -> Result<Table<'txn, K, V>, TableError> {
+    ) -> Result<(Table<'txn, K, V>, bool), TableError> {
         #[cfg(feature = "logging")]
         debug!("Opening table: {definition}");
-        let (root, _) = self.inner_open::<K, V>(definition.name(), TableType::Normal)?;
+        let (root, _, created) = self.inner_open::<K, V>(definition.name(), TableType::Normal)?;
         self.set_dirty(transaction);
 
-        Ok(Table::new(
+        let table = Table::new(
             definition.name(),
             root,
             self.freed_pages.clone(),
             self.allocated_pages.clone(),
             self.table_tree.page_allocator().clone(),
             transaction,
-        ))
+        );
+        Ok((table, created))
     }
 
     #[track_caller]
@@ -748,6 +880,32 @@ impl TableNamespace {
         self.table_tree.delete_table(name, table_type)
     }
 
+    // Returns whether the table existed, and the pages which are only referenced by the restored
+    // version of it
+    #[track_caller]
+    fn restore_table(
+        &mut self,
+        transaction: &WriteTransaction,
+        name: &str,
+        table_type: TableType,
+        definition: Option<InternalTableDefinition>,
+    ) -> Result<(bool, PageNumberHashSet), TableError> {
+        if let Some(location) = self.open_tables.get(name) {
+            return Err(TableError::TableAlreadyOpen(name.to_string(), location));
+        }
+        #[cfg(feature = "logging")]
+        debug!("Restoring table: {name}");
+        self.set_dirty(transaction);
+        let existed = self
+            .table_tree
+            .get_table_untyped(name, table_type)?
+            .is_some();
+        let revived = self
+            .table_tree
+            .restore_table(name, table_type, definition)?;
+        Ok((existed, revived))
+    }
+
     #[track_caller]
     fn delete_table(
         &mut self,
@@ -777,10 +935,17 @@ impl TableNamespace {
         name: &str,
         table: &BtreeMut<K, V>,
         length: u64,
+        operations: TableOperationStats,
     ) {
         self.open_tables.remove(name).unwrap();
         self.table_tree
             .stage_update_table_root(name, table.get_root(), length);
+        if operations != TableOperationStats::default() {
+            self.operations
+                .entry(name.to_string())
+                .or_default()
+                .add(operations);
+        }
     }
 
     pub(crate) fn close_table_without_update(&mut self, name: &str) {
@@ -817,6 +982,15 @@ impl SavepointTransactionState {
         !self.created_persistent.is_empty() || !self.deleted_persistent.is_empty()
     }
 
+    fn deleted_count(&self) -> usize {
+        self.deleted_persistent.len()
+    }
+
+    // Forgets the deletions made by a nested transaction which was rolled back
+    fn truncate_deleted(&mut self, count: usize) {
+        self.deleted_persistent.truncate(count);
+    }
+
     fn apply_on_commit(&mut self, tracker: &TransactionTracker) {
         // Persistent savepoints whose on-disk entry was deleted: release their
         // tracker refcount now that the deletion is durable.
@@ -850,9 +1024,207 @@ impl SavepointTransactionState {
     }
 }
 
+pub(crate) type CommitHook = dyn Fn(&CompletedTransaction) + Send + Sync;
+
+// The data root which a prepared transaction started from, the root which it produced, and the
+// id of the coordinated commit which it is part of, if any. See CommitCoordinator
+//
+// Format:
+// 1 byte: base root present
+// If present, the serialized BtreeHeader of the base root
+// 1 byte: prepared root present
+// If present, the serialized BtreeHeader of the prepared root
+// 1 byte: coordinated commit present
+// If present, 8 bytes: coordinated commit id
+struct PreparedTransaction {
+    base: Option<BtreeHeader>,
+    prepared: Option<BtreeHeader>,
+    coordinated_commit: Option<u64>,
+}
+
+impl PreparedTransaction {
+    fn to_bytes(&self) -> Vec<u8> {
+        let mut result = vec![];
+        for root in [self.base, self.prepared] {
+            if let Some(header) = root {
+                result.push(1);
+                result.extend(header.to_le_bytes());
+            } else {
+                result.push(0);
+            }
+        }
+        if let Some(id) = self.coordinated_commit {
+            result.push(1);
+            result.extend(id.to_le_bytes());
+        } else {
+            result.push(0);
+        }
+        result
+    }
+
+    fn from_bytes(data: &[u8]) -> Result<Self> {
+        let corrupted = || StorageError::Corrupted("Prepared transaction is corrupted".to_string());
+        let mut offset = 0;
+        let mut roots = [None, None];
+        for root in &mut roots {
+            match data.get(offset) {
+                Some(0) => {
+                    offset += 1;
+                }
+                Some(1) => {
+                    offset += 1;
+                    let bytes = data
+                        .get(offset..(offset + BtreeHeader::serialized_size()))
+                        .ok_or_else(corrupted)?;
+                    *root = Some(BtreeHeader::from_le_bytes(bytes.try_into().unwrap()));
+                    offset += BtreeHeader::serialized_size();
+                }
+                _ => return Err(corrupted()),
+            }
+        }
+        let coordinated_commit = match data.get(offset) {
+            Some(0) => {
+                offset += 1;
+                None
+            }
+            Some(1) => {
+                offset += 1;
+                let bytes = data
+                    .get(offset..(offset + size_of::<u64>()))
+                    .ok_or_else(corrupted)?;
+                offset += size_of::<u64>();
+                Some(u64::from_le_bytes(bytes.try_into().unwrap()))
+            }
+            _ => return Err(corrupted()),
+        };
+        if offset != data.len() {
+            return Err(corrupted());
+        }
+        let [base, prepared] = roots;
+        Ok(Self {
+            base,
+            prepared,
+            coordinated_commit,
+        })
+    }
+}
+
+// The state of a write transaction at the start of a nested transaction. If the nested transaction
+// panics, the outer pages are restored and the write transaction is poisoned
+struct NestedScope<'a> {
+    transaction: &'a WriteTransaction,
+    state: Option<NestedState>,
+}
+
+struct NestedState {
+    outer_pages: PageNumberHashSet,
+    tables: TableTreeCheckpoint,
+    system_tables: TableTreeCheckpoint,
+    operations: HashMap<String, TableOperationStats>,
+    deleted_savepoints: usize,
+    poisoned: bool,
+}
+
+impl NestedScope<'_> {
+    fn commit(mut self) {
+        let state = self.state.take().unwrap();
+        let mut tables = self.transaction.tables.lock().unwrap();
+        tables
+            .table_tree
+            .page_allocator()
+            .end_nested(state.outer_pages);
+        tables.table_tree.release_checkpoint(state.tables);
+        self.transaction
+            .system_tables
+            .lock()
+            .unwrap()
+            .table_tree
+            .release_checkpoint(state.system_tables);
+    }
+
+    fn rollback(mut self) {
+        let state = self.state.take().unwrap();
+        let mut tables = self.transaction.tables.lock().unwrap();
+        tables.table_tree.rollback_to(state.tables);
+        self.transaction
+            .system_tables
+            .lock()
+            .unwrap()
+            .table_tree
+            .rollback_to(state.system_tables);
+        tables.operations = state.operations;
+        let allocated_pages = tables.allocated_pages.clone();
+        tables
+            .table_tree
+            .page_allocator()
+            .rollback_nested(state.outer_pages, &mut allocated_pages.lock().unwrap());
+        self.transaction
+            .savepoint_state
+            .lock()
+            .unwrap()
+            .truncate_deleted(state.deleted_savepoints);
+        // Writes which failed within the nested transaction have been rolled back too
+        self.transaction
+            .poisoned
+            .store(state.poisoned, Ordering::Release);
+    }
+}
+
+impl Drop for NestedScope<'_> {
+    fn drop(&mut self) {
+        if let Some(state) = self.state.take() {
+            self.transaction
+                .page_allocator()
+                .end_nested(state.outer_pages);
+            self.transaction.poison();
+        }
+    }
+}
+
+type TransactionHook = Box<dyn FnOnce(&CompletedTransaction) + Send>;
+type Validator = Box<dyn Fn(&WriteTransaction) -> Result<(), ValidationError> + Send>;
+
+/// A write transaction which has been committed or aborted
+///
+/// This is passed to the hooks registered with [`crate::Database::set_commit_hook`] and
+/// [`WriteTransaction::on_commit`]
+#[derive(Clone, Debug)]
+pub struct CompletedTransaction {
+    transaction_id: u64,
+    committed: bool,
+    modified_tables: Vec<String>,
+}
+
+impl CompletedTransaction {
+    /// The id of the transaction
+    ///
+    /// Ids increase with each write transaction
+    pub fn transaction_id(&self) -> u64 {
+        self.transaction_id
+    }
+
+    /// Returns `true` if the transaction was committed, or `false` if it was aborted
+    pub fn committed(&self) -> bool {
+        self.committed
+    }
+
+    /// The names of the tables which the transaction created, deleted, renamed, or wrote to, in
+    /// sorted order
+    ///
+    /// If the transaction was aborted, these are the tables whose changes were rolled back
+    pub fn modified_tables(&self) -> &[String] {
+        &self.modified_tables
+    }
+}
+
 /// A read/write transaction
 ///
 /// Only a single [`WriteTransaction`] may exist at a time
+///
+/// [`WriteTransaction`] is [`Send`] and [`Sync`], so an open transaction can be handed off to
+/// another thread, for example to a worker pool or across an `.await` on a multi-threaded
+/// executor, and committed or aborted there. Tables opened from the transaction borrow it, so they
+/// must be dropped before it is moved.
 pub struct WriteTransaction {
     transaction_tracker: Arc<TransactionTracker>,
     mem: Arc<TransactionalMemory>,
@@ -862,15 +1234,30 @@ pub struct WriteTransaction {
     system_tables: Mutex<SystemNamespace>,
     completed: bool,
     dirty: AtomicBool,
-    poisoned: AtomicBool,
+    poisoned: Arc<AtomicBool>,
     durability: InternalDurability,
     two_phase_commit: bool,
     shrink_policy: ShrinkPolicy,
     quick_repair: bool,
     post_commit_free: PostCommitFree,
+    // If set, a non-durable commit is promoted to a durable one once the pages written by
+    // non-durable commits reach this many bytes
+    auto_checkpoint_size: Option<u64>,
+    // Limits on the persistent savepoints that are kept by a durable commit
+    max_persistent_savepoints: Option<usize>,
+    persistent_savepoint_max_age: Option<Duration>,
     // All transaction-local savepoint lifecycle state. See
     // `SavepointTransactionState` for the commit/abort contract.
     savepoint_state: Mutex<SavepointTransactionState>,
+    // Hooks registered with on_commit(), followed by the database's commit hook
+    hooks: Mutex<Vec<TransactionHook>>,
+    // Validators registered with validate(), which are run before the transaction commits
+    validators: Mutex<Vec<Validator>>,
+    database_hook: Option<Arc<CommitHook>>,
+    watch_registry: Option<Arc<WatchRegistry>>,
+    change_capture: ChangeCapture,
+    schema_history: SchemaHistory,
+    group_commit: Option<Arc<GroupCommit>>,
 }
 
 impl WriteTransaction {
@@ -887,6 +1274,7 @@ impl WriteTransaction {
         let system_page = mem.get_system_root();
 
         let page_allocator = PageAllocator::new(mem.clone(), allocation_policy);
+        let poisoned = page_allocator.poisoned_flag();
         let tables = TableNamespace::new(root_page, guard.clone(), page_allocator.clone());
         let system_tables = SystemNamespace::new(system_page, guard.clone(), page_allocator);
 
@@ -899,13 +1287,23 @@ impl WriteTransaction {
             system_tables: Mutex::new(system_tables),
             completed: false,
             dirty: AtomicBool::new(false),
-            poisoned: AtomicBool::new(false),
+            poisoned,
             durability: InternalDurability::Immediate,
             two_phase_commit: false,
             quick_repair: false,
             post_commit_free: PostCommitFree::Enabled,
             shrink_policy: ShrinkPolicy::Default,
+            auto_checkpoint_size: None,
+            max_persistent_savepoints: None,
+            persistent_savepoint_max_age: None,
             savepoint_state: Mutex::new(SavepointTransactionState::default()),
+            hooks: Mutex::new(vec![]),
+            validators: Mutex::new(vec![]),
+            database_hook: None,
+            watch_registry: None,
+            change_capture: ChangeCapture::Disabled,
+            schema_history: SchemaHistory::Disabled,
+            group_commit: None,
         })
     }
 
@@ -913,6 +1311,303 @@ impl WriteTransaction {
         self.shrink_policy = shrink_policy;
     }
 
+    pub(crate) fn set_auto_checkpoint_size(&mut self, auto_checkpoint_size: Option<u64>) {
+        self.auto_checkpoint_size = auto_checkpoint_size;
+    }
+
+    pub(crate) fn set_savepoint_retention(
+        &mut self,
+        max_persistent_savepoints: Option<usize>,
+        persistent_savepoint_max_age: Option<Duration>,
+    ) {
+        self.max_persistent_savepoints = max_persistent_savepoints;
+        self.persistent_savepoint_max_age = persistent_savepoint_max_age;
+    }
+
+    pub(crate) fn set_database_hook(&mut self, hook: Option<Arc<CommitHook>>) {
+        self.database_hook = hook;
+    }
+
+    pub(crate) fn set_group_commit(&mut self, group_commit: Option<Arc<GroupCommit>>) {
+        self.group_commit = group_commit;
+    }
+
+    pub(crate) fn set_watch_registry(&mut self, registry: Arc<WatchRegistry>) {
+        self.watch_registry = Some(registry);
+    }
+
+    pub(crate) fn set_change_capture(&mut self, enabled: bool) {
+        self.change_capture = if enabled {
+            ChangeCapture::Enabled
+        } else {
+            ChangeCapture::Disabled
+        };
+    }
+
+    pub(crate) fn set_schema_history(&mut self, enabled: bool) {
+        self.schema_history = if enabled {
+            SchemaHistory::Enabled
+        } else {
+            SchemaHistory::Disabled
+        };
+    }
+
+    // Appends the changes made to each modified normal table to the change log
+    fn capture_changes(&self) -> Result {
+        if self.change_capture == ChangeCapture::Disabled {
+            return Ok(());
+        }
+        let resolver = self.page_allocator().resolver();
+        let committed = TableTree::new(
+            self.mem.get_data_root(),
+            PageHint::None,
+            self.transaction_guard.clone(),
+            resolver.clone(),
+        )?;
+        // A table which does not exist, or is a multimap table, has no entries to record
+        let normal_btree = |definition: Result<Option<InternalTableDefinition>, TableError>| {
+            let definition = match definition {
+                Ok(definition) => definition,
+                Err(TableError::Storage(err)) => return Err(err),
+                Err(_) => None,
+            };
+            Ok(definition
+                .and_then(|x| x.normal_btree(resolver.clone(), PageHint::None))
+                .unwrap_or_else(|| {
+                    RawBtree::new(None, None, None, resolver.clone(), PageHint::None)
+                }))
+        };
+
+        let page_allocator = self.page_allocator();
+        let mut records = vec![];
+        {
+            let mut tables = self.tables.lock().unwrap();
+            for name in tables.table_tree.modified_tables() {
+                let old = normal_btree(committed.get_table_untyped(&name, TableType::Normal))?;
+                let new = normal_btree(
+                    tables
+                        .table_tree
+                        .get_table_untyped(&name, TableType::Normal),
+                )?;
+                for diff in diff_btrees(&old, &new, |page| page_allocator.uncommitted(page))? {
+                    records.push(ChangeRecord::new(
+                        self.transaction_id.raw_id(),
+                        &name,
+                        diff.key,
+                        diff.old_value,
+                        diff.new_value,
+                    ));
+                }
+            }
+        }
+        if records.is_empty() {
+            return Ok(());
+        }
+
+        let mut system_tables = self.system_tables.lock().unwrap();
+        let mut log = system_tables.open_system_table(self, CHANGE_LOG_TABLE)?;
+        for (sequence, record) in (0u64..).zip(records) {
+            log.insert(
+                (self.transaction_id.raw_id(), sequence),
+                record.to_bytes().as_slice(),
+            )?;
+        }
+        Ok(())
+    }
+
+    /// Remove the changes recorded by transactions with ids less than `transaction_id` from the
+    /// change log
+    ///
+    /// Returns the number of changes removed. See [`crate::Builder::set_change_capture`]
+    pub fn truncate_change_log(&self, transaction_id: u64) -> Result<u64> {
+        let mut system_tables = self.system_tables.lock().unwrap();
+        let mut log = system_tables.open_system_table(self, CHANGE_LOG_TABLE)?;
+        let mut removed = 0;
+        for entry in log.extract_from_if(..(transaction_id, 0), |_, _| true)? {
+            entry?;
+            removed += 1;
+        }
+        Ok(removed)
+    }
+
+    // Computes the events for each active watch, while the committed table roots are still
+    // readable. They are sent once the commit has succeeded
+    fn watch_events(&self) -> Result<Vec<PendingEvents>> {
+        let Some(registry) = &self.watch_registry else {
+            return Ok(vec![]);
+        };
+        let watchers = registry.watchers();
+        if watchers.is_empty() {
+            return Ok(vec![]);
+        }
+        let resolver = self.page_allocator().resolver();
+        let committed = TableTree::new(
+            self.mem.get_data_root(),
+            PageHint::None,
+            self.transaction_guard.clone(),
+            resolver.clone(),
+        )?;
+        let tables = self.tables.lock().unwrap();
+        let mut result = vec![];
+        for watcher in watchers {
+            let events = watcher.diff(
+                &committed,
+                &tables.table_tree,
+                &self.transaction_guard,
+                &resolver,
+            )?;
+            if !events.is_empty() {
+                result.push(events);
+            }
+        }
+        Ok(result)
+    }
+
+    /// Attach a label to this transaction, which is reported by
+    /// [`crate::Database::active_transactions`] to help identify it
+    pub fn set_label(&self, label: impl Into<String>) {
+        self.transaction_guard.set_label(label.into());
+    }
+
+    /// Register a callback to be invoked once this transaction has completed
+    ///
+    /// The callback is invoked after the transaction has been committed, or after it has been
+    /// aborted, either by [`Self::abort`] or by being dropped. It is not invoked if [`Self::commit`]
+    /// fails with an error other than [`CommitError::TransactionPoisoned`]. Callbacks are invoked in
+    /// the order they were registered, before the hook set with
+    /// [`crate::Database::set_commit_hook`].
+    ///
+    /// Callbacks run on the thread which completes the transaction, before the write lock is
+    /// released, so they must not begin another write transaction on the same database.
+    pub fn on_commit(&self, callback: impl FnOnce(&CompletedTransaction) + Send + 'static) {
+        self.hooks.lock().unwrap().push(Box::new(callback));
+    }
+
+    /// Run `f` as a nested transaction, whose changes can be rolled back without rolling back the
+    /// rest of this transaction
+    ///
+    /// If `f` returns an error, all the changes it made are rolled back and the error is returned.
+    /// Otherwise, its changes become part of this transaction. Nested transactions may themselves
+    /// be nested.
+    ///
+    /// Unlike [`Self::ephemeral_savepoint`], this may be used after tables have been modified, and
+    /// does not snapshot the database. Instead, pages written earlier in this transaction are
+    /// copied, rather than modified in place, by the writes made in `f`.
+    ///
+    /// Returns [`TableError::TableAlreadyOpen`] if a table is open when this is called.
+    pub fn nested<T, E: From<TableError>>(
+        &self,
+        f: impl FnOnce(&WriteTransaction) -> Result<T, E>,
+    ) -> Result<T, E> {
+        let scope = self.begin_nested()?;
+        let result = f(self);
+        if result.is_ok() {
+            scope.commit();
+        } else {
+            scope.rollback();
+        }
+        result
+    }
+
+    fn begin_nested(&self) -> Result<NestedScope<'_>, TableError> {
+        let mut tables = self.tables.lock().unwrap();
+        if let Some((name, location)) = tables.open_tables.iter().next() {
+            return Err(TableError::TableAlreadyOpen(name.clone(), location));
+        }
+        tables.set_dirty(self);
+        let mut system_tables = self.system_tables.lock().unwrap();
+        let state = NestedState {
+            tables: tables.table_tree.checkpoint()?,
+            system_tables: system_tables.table_tree.checkpoint()?,
+            operations: tables.operations.clone(),
+            deleted_savepoints: self.savepoint_state.lock().unwrap().deleted_count(),
+            poisoned: self.is_poisoned(),
+            outer_pages: tables.table_tree.page_allocator().begin_nested(),
+        };
+        Ok(NestedScope {
+            transaction: self,
+            state: Some(state),
+        })
+    }
+
+    /// Create a read-only view of the writes made by this transaction so far
+    ///
+    /// The view can be sent to, or shared with, other threads. This transaction can't be used
+    /// until the view is dropped, so the pages it reads can't change.
+    pub fn read_view(&mut self) -> Result<ReadView<'_>> {
+        let root = self.tables.lock().unwrap().table_tree.flush()?;
+        let lease: Arc<ReadLease> = Arc::default();
+        Ok(ReadView {
+            transaction: ReadTransaction::new_view(self.mem.clone(), root, lease.clone())?,
+            lease,
+            _write_transaction: PhantomData,
+        })
+    }
+
+    /// Register a validator to be run before this transaction commits
+    ///
+    /// Validators are run by [`Self::commit`] and [`Self::prepare`], in the order they were
+    /// registered, and see all the writes made by the transaction. If a validator returns an
+    /// error, the remaining validators are not run, and the transaction is aborted and the commit
+    /// fails with [`CommitError::ValidationFailed`]. This can be used to enforce invariants which
+    /// span multiple tables.
+    ///
+    /// Validators may read and write tables, but must drop any tables they open before returning.
+    pub fn validate(
+        &self,
+        validator: impl Fn(&WriteTransaction) -> Result<(), ValidationError> + Send + 'static,
+    ) {
+        self.validators.lock().unwrap().push(Box::new(validator));
+    }
+
+    /// Run the validators registered with [`Self::validate`] against the current state of the
+    /// transaction, without committing it
+    ///
+    /// The transaction remains open, whether or not validation succeeds.
+    pub fn dry_run(&self) -> Result<(), ValidationError> {
+        self.run_validators()
+    }
+
+    fn run_validators(&self) -> Result<(), ValidationError> {
+        // Release the lock while the validators run, so that they can register more validators
+        let validators = mem::take(&mut *self.validators.lock().unwrap());
+        let result = validators.iter().try_for_each(|validator| validator(self));
+        let mut current = self.validators.lock().unwrap();
+        let added = mem::replace(&mut *current, validators);
+        current.extend(added);
+        result
+    }
+
+    // Returns None if there are no hooks to invoke
+    fn completed_transaction(&self, committed: bool) -> Option<CompletedTransaction> {
+        if self.database_hook.is_none() && self.hooks.lock().unwrap().is_empty() {
+            return None;
+        }
+        Some(CompletedTransaction {
+            transaction_id: self.transaction_id.raw_id(),
+            committed,
+            modified_tables: self
+                .tables
+                .lock()
+                .unwrap()
+                .table_tree
+                .take_modified_tables(),
+        })
+    }
+
+    fn invoke_hooks(&self, completed: Option<CompletedTransaction>) {
+        let Some(completed) = completed else {
+            return;
+        };
+        let hooks = mem::take(&mut *self.hooks.lock().unwrap());
+        for hook in hooks {
+            hook(&completed);
+        }
+        if let Some(hook) = &self.database_hook {
+            hook(&completed);
+        }
+    }
+
     pub(crate) fn poison(&self) {
         self.poisoned.store(true, Ordering::Release);
     }
@@ -961,6 +1656,33 @@ impl WriteTransaction {
                 .is_some())
     }
 
+    // Returns the data tree pages allocated by transactions after `transaction_id`. This is only
+    // complete if a savepoint at or before `transaction_id` exists
+    pub(crate) fn data_pages_allocated_after(
+        &self,
+        transaction_id: TransactionId,
+    ) -> Result<PageNumberHashSet> {
+        let mut result: PageNumberHashSet = self
+            .mem
+            .unpersisted_allocations_after(transaction_id)
+            .into_iter()
+            .collect();
+        let mut system_tables = self.system_tables.lock().unwrap();
+        let data_allocated = system_tables.open_system_table(self, DATA_ALLOCATED_TABLE)?;
+        let lower = TransactionIdWithPagination {
+            transaction_id: transaction_id.next().raw_id(),
+            pagination_id: 0,
+        };
+        for entry in data_allocated.range(lower..)? {
+            let (_, value) = entry?;
+            for i in 0..value.value().len() {
+                result.insert(value.value().get(i));
+            }
+        }
+
+        Ok(result)
+    }
+
     #[cfg(debug_assertions)]
     pub fn print_allocated_page_debug(&self) {
         let mut all_allocated: HashSet<PageNumber> =
@@ -1017,12 +1739,12 @@ impl WriteTransaction {
             }
         }
         {
-            println!("Pending free (in system freed table)");
+            println!("Prepared transactions");
             let mut system_tables = self.system_tables.lock().unwrap();
-            let system_freed = system_tables
-                .open_system_table(self, SYSTEM_FREED_TABLE)
+            let prepared_allocated = system_tables
+                .open_system_table(self, PREPARED_ALLOCATED_TABLE)
                 .unwrap();
-            for entry in system_freed
+            for entry in prepared_allocated
                 .range::<TransactionIdWithPagination>(..)
                 .unwrap()
             {
@@ -1036,11 +1758,30 @@ impl WriteTransaction {
             }
         }
         {
-            let tables = self.tables.lock().unwrap();
-            let pages = tables.freed_pages.lock().unwrap();
-            if !pages.is_empty() {
-                println!("Pages in in-memory data freed_pages");
-                for p in pages.iter() {
+            println!("Pending free (in system freed table)");
+            let mut system_tables = self.system_tables.lock().unwrap();
+            let system_freed = system_tables
+                .open_system_table(self, SYSTEM_FREED_TABLE)
+                .unwrap();
+            for entry in system_freed
+                .range::<TransactionIdWithPagination>(..)
+                .unwrap()
+            {
+                let (_, entry) = entry.unwrap();
+                let value = entry.value();
+                for i in 0..value.len() {
+                    let p = value.get(i);
+                    assert!(all_allocated.remove(&p));
+                    println!("{p:?}");
+                }
+            }
+        }
+        {
+            let tables = self.tables.lock().unwrap();
+            let pages = tables.freed_pages.lock().unwrap();
+            if !pages.is_empty() {
+                println!("Pages in in-memory data freed_pages");
+                for p in pages.iter() {
                     println!("{p:?}");
                     assert!(all_allocated.remove(p));
                 }
@@ -1075,6 +1816,18 @@ impl WriteTransaction {
     /// or `[SavepointError::ImmediateDurabilityRequired]` if the transaction's durability is less than
     /// `[Durability::Immediate]`
     pub fn persistent_savepoint(&self) -> Result<u64, SavepointError> {
+        self.persistent_savepoint_inner(None)
+    }
+
+    /// Creates a persistent savepoint with a label, which is returned by
+    /// [`Self::list_persistent_savepoint_info`] to help identify it
+    ///
+    /// Otherwise the same as [`Self::persistent_savepoint`]
+    pub fn persistent_savepoint_with_label(&self, label: &str) -> Result<u64, SavepointError> {
+        self.persistent_savepoint_inner(Some(label))
+    }
+
+    fn persistent_savepoint_inner(&self, label: Option<&str>) -> Result<u64, SavepointError> {
         if self.durability != InternalDurability::Immediate {
             return Err(SavepointError::ImmediateDurabilityRequired);
         }
@@ -1092,6 +1845,15 @@ impl WriteTransaction {
             savepoint.get_id(),
             SerializedSavepoint::from_savepoint(&savepoint),
         )?;
+        drop(savepoint_table);
+
+        let created = SystemTime::now()
+            .duration_since(SystemTime::UNIX_EPOCH)
+            .unwrap_or_default();
+        let created = u64::try_from(created.as_micros()).unwrap_or(u64::MAX);
+        let mut info_table = system_tables.open_system_table(self, SAVEPOINT_INFO_TABLE)?;
+        info_table.insert(savepoint.get_id(), (created, label))?;
+        drop(info_table);
 
         savepoint.set_persistent();
 
@@ -1103,6 +1865,106 @@ impl WriteTransaction {
         Ok(savepoint.get_id().0)
     }
 
+    /// Creates a persistent savepoint, which can be retrieved by `name` with
+    /// [`Self::get_named_savepoint`]
+    ///
+    /// If a savepoint with this name already exists, it is deleted and replaced by the new one.
+    /// The name is also used as the label of the savepoint. Otherwise the same as
+    /// [`Self::persistent_savepoint`]
+    pub fn named_savepoint(&self, name: &str) -> Result<u64, SavepointError> {
+        let previous = self.named_savepoint_id(name)?;
+        let id = self.persistent_savepoint_inner(Some(name))?;
+        if let Some(previous) = previous {
+            self.delete_persistent_savepoint(previous.0)?;
+        }
+        self.system_tables
+            .lock()
+            .unwrap()
+            .open_system_table(self, NAMED_SAVEPOINT_TABLE)?
+            .insert(name, SavepointId(id))?;
+
+        Ok(id)
+    }
+
+    fn named_savepoint_id(&self, name: &str) -> Result<Option<SavepointId>> {
+        Ok(self
+            .read_existing_system_table(NAMED_SAVEPOINT_TABLE, |table| {
+                Ok(table.get(&name)?.map(|x| x.value()))
+            })?
+            .flatten())
+    }
+
+    /// Get a persistent savepoint given the name it was created with
+    pub fn get_named_savepoint(&self, name: &str) -> Result<Savepoint, SavepointError> {
+        let id = self
+            .named_savepoint_id(name)?
+            .ok_or(SavepointError::InvalidSavepoint)?;
+        self.get_persistent_savepoint(id.0)
+    }
+
+    /// Delete the persistent savepoint with the given name
+    ///
+    /// Returns `true` if the savepoint existed. Otherwise the same as
+    /// [`Self::delete_persistent_savepoint`]
+    pub fn delete_named_savepoint(&self, name: &str) -> Result<bool, SavepointError> {
+        if self.durability != InternalDurability::Immediate {
+            return Err(SavepointError::ImmediateDurabilityRequired);
+        }
+        let Some(id) = self.named_savepoint_id(name)? else {
+            return Ok(false);
+        };
+        self.delete_persistent_savepoint(id.0)
+    }
+
+    /// List the names of all named savepoints, in order, along with their ids
+    pub fn list_named_savepoints(&self) -> Result<impl Iterator<Item = (String, u64)>> {
+        let savepoints = self
+            .read_existing_system_table(NAMED_SAVEPOINT_TABLE, |table| {
+                let mut savepoints = vec![];
+                for entry in table.range::<RangeFull, &str>(&..)? {
+                    let entry = entry?;
+                    savepoints.push((entry.key().to_string(), entry.value().0));
+                }
+                Ok(savepoints)
+            })?
+            .unwrap_or_default();
+        Ok(savepoints.into_iter())
+    }
+
+    /// Returns the next id from the sequence with the given name
+    ///
+    /// Sequences start at 0, and each id is returned at most once: ids increase from one call to
+    /// the next, including across transactions. Ids returned by a transaction which is aborted are
+    /// returned again by a later transaction. Sequences are stored separately from tables, and are
+    /// not affected by [`WriteTransaction::restore_savepoint`].
+    ///
+    /// Returns [`SequenceError::SequenceExhausted`] if the sequence has reached `u64::MAX`
+    pub fn next_sequence(&self, name: &str) -> Result<u64, SequenceError> {
+        Ok(self.reserve_sequence(name, 1)?.start)
+    }
+
+    /// Reserves `count` consecutive ids from the sequence with the given name, and returns them
+    ///
+    /// This is the same as calling [`WriteTransaction::next_sequence`] `count` times, but only
+    /// writes to the database once, so ids can be handed out in batches.
+    ///
+    /// Returns [`SequenceError::SequenceExhausted`], and reserves nothing, if the sequence would
+    /// pass `u64::MAX`
+    pub fn reserve_sequence(
+        &self,
+        name: &str,
+        count: u64,
+    ) -> Result<std::ops::Range<u64>, SequenceError> {
+        let mut system_tables = self.system_tables.lock().unwrap();
+        let mut sequences = system_tables.open_system_table(self, SEQUENCE_TABLE)?;
+        let start = sequences.get(name)?.map_or(0, |next| next.value());
+        let end = start
+            .checked_add(count)
+            .ok_or(SequenceError::SequenceExhausted)?;
+        sequences.insert(name, end)?;
+        Ok(start..end)
+    }
+
     pub(crate) fn transaction_guard(&self) -> Arc<TransactionGuard> {
         self.transaction_guard.clone()
     }
@@ -1149,11 +2011,27 @@ impl WriteTransaction {
             return Ok(false);
         }
         let mut table = system_tables.open_system_table(self, SAVEPOINT_TABLE)?;
-        let savepoint = table.remove(SavepointId(id))?;
-        if let Some(serialized) = savepoint {
-            let savepoint = serialized
+        let savepoint = table.remove(SavepointId(id))?.map(|serialized| {
+            serialized
                 .value()
-                .to_savepoint(self.transaction_tracker.clone());
+                .to_savepoint(self.transaction_tracker.clone())
+        });
+        drop(table);
+        if let Some(savepoint) = savepoint {
+            system_tables
+                .open_system_table(self, SAVEPOINT_INFO_TABLE)?
+                .remove(SavepointId(id))?;
+            if system_tables
+                .get_system_table_root(NAMED_SAVEPOINT_TABLE)?
+                .is_some()
+            {
+                let mut names = system_tables.open_system_table(self, NAMED_SAVEPOINT_TABLE)?;
+                for entry in
+                    names.extract_from_if::<&str, _>(.., |_, value| value == SavepointId(id))?
+                {
+                    entry?;
+                }
+            }
             self.savepoint_state
                 .lock()
                 .unwrap()
@@ -1179,6 +2057,51 @@ impl WriteTransaction {
         Ok(savepoints.into_iter())
     }
 
+    /// List all persistent savepoints, in order of their ids, with their creation time and label
+    pub fn list_persistent_savepoint_info(&self) -> Result<Vec<SavepointInfo>> {
+        let Some(savepoints) = self.read_existing_system_table(SAVEPOINT_TABLE, |table| {
+            let mut savepoints = vec![];
+            for entry in table.range::<RangeFull, SavepointId>(&..)? {
+                savepoints.push(
+                    entry?
+                        .value()
+                        .to_savepoint(self.transaction_tracker.clone()),
+                );
+            }
+            Ok(savepoints)
+        })?
+        else {
+            return Ok(vec![]);
+        };
+        let info = self
+            .read_existing_system_table(SAVEPOINT_INFO_TABLE, |table| {
+                let mut info = HashMap::new();
+                for entry in table.range::<RangeFull, SavepointId>(&..)? {
+                    let entry = entry?;
+                    let (created, label) = entry.value();
+                    info.insert(
+                        entry.key(),
+                        (
+                            SystemTime::UNIX_EPOCH + Duration::from_micros(created),
+                            label.map(str::to_string),
+                        ),
+                    );
+                }
+                Ok(info)
+            })?
+            .unwrap_or_default();
+        Ok(savepoints
+            .iter()
+            .map(|savepoint| {
+                let (created, label) = info
+                    .get(&savepoint.get_id())
+                    .cloned()
+                    .map_or((None, None), |(created, label)| (Some(created), label));
+                SavepointInfo::new(savepoint, created, label)
+            })
+            .collect())
+    }
+
     fn allocate_savepoint(&self) -> Result<(SavepointId, TransactionId)> {
         let transaction_id = self
             .transaction_tracker
@@ -1217,30 +2140,7 @@ impl WriteTransaction {
     ///
     /// Calling this method invalidates all [`Savepoint`]s created after savepoint
     pub fn restore_savepoint(&mut self, savepoint: &Savepoint) -> Result<(), SavepointError> {
-        // Reject a Savepoint that is from a different Database
-        if std::ptr::from_ref(self.transaction_tracker.as_ref()) != savepoint.db_address() {
-            return Err(SavepointError::InvalidSavepoint);
-        }
-
-        if !self
-            .transaction_tracker
-            .is_valid_savepoint(savepoint.get_id())
-            || self
-                .savepoint_state
-                .lock()
-                .unwrap()
-                .is_invalidated(savepoint.get_id())
-        {
-            return Err(SavepointError::InvalidSavepoint);
-        }
-
-        if self.durability != InternalDurability::Immediate
-            && self
-                .list_persistent_savepoints()?
-                .any(|id| id > savepoint.get_id().0)
-        {
-            return Err(SavepointError::ImmediateDurabilityRequired);
-        }
+        self.check_restorable(savepoint)?;
         #[cfg(feature = "logging")]
         debug!(
             "Beginning savepoint restore (id={:?}) in transaction id={:?}",
@@ -1265,7 +2165,7 @@ impl WriteTransaction {
             self.tables
                 .lock()
                 .unwrap()
-                .set_root(savepoint.get_user_root());
+                .set_root(savepoint.get_user_root())?;
         }
 
         // 1a) purge all transactions that happened after the savepoint from the data freed tree
@@ -1318,11 +2218,161 @@ impl WriteTransaction {
             }
         }
 
-        // 3) Mark all savepoints newer than the restored one as invalidated for this
-        // transaction, to prevent the user from later trying to restore a savepoint
-        // "on another timeline". The invalidation is purely per-transaction state -
-        // the shared `valid_savepoints` map is only updated if/when commit_inner()
-        // runs, so an abort implicitly reverts the invalidation by dropping this set.
+        // 3) update the system tree to remove invalid persistent savepoints
+        self.invalidate_savepoints_after(savepoint)?;
+
+        Ok(())
+    }
+
+    /// Restore the given table to its state at the time that `savepoint` was created
+    ///
+    /// Unlike [`Self::restore_savepoint`], all other tables are left unchanged. The table is
+    /// deleted if it did not exist when the savepoint was created.
+    ///
+    /// Calling this invalidates all savepoints created after `savepoint`
+    pub fn restore_table(
+        &mut self,
+        savepoint: &Savepoint,
+        table: impl TableHandle,
+    ) -> Result<(), SavepointError> {
+        let name = table.name().to_string();
+        drop(table);
+        self.restore_table_inner(savepoint, &name, TableType::Normal)
+    }
+
+    /// Restore the given multimap table to its state at the time that `savepoint` was created
+    ///
+    /// Unlike [`Self::restore_savepoint`], all other tables are left unchanged. The table is
+    /// deleted if it did not exist when the savepoint was created.
+    ///
+    /// Calling this invalidates all savepoints created after `savepoint`
+    pub fn restore_multimap_table(
+        &mut self,
+        savepoint: &Savepoint,
+        table: impl MultimapTableHandle,
+    ) -> Result<(), SavepointError> {
+        let name = table.name().to_string();
+        drop(table);
+        self.restore_table_inner(savepoint, &name, TableType::Multimap)
+    }
+
+    fn restore_table_inner(
+        &mut self,
+        savepoint: &Savepoint,
+        name: &str,
+        table_type: TableType,
+    ) -> Result<(), SavepointError> {
+        self.check_restorable(savepoint)?;
+        #[cfg(feature = "logging")]
+        debug!(
+            "Restoring table {name} to savepoint (id={:?}) in transaction id={:?}",
+            savepoint.get_id(),
+            self.transaction_id
+        );
+        assert_eq!(self.mem.get_version(), savepoint.get_version());
+
+        let definition = TableTree::new(
+            savepoint.get_user_root(),
+            PageHint::None,
+            self.transaction_guard.clone(),
+            self.page_allocator().resolver(),
+        )?
+        .get_table_untyped(name, table_type)?;
+        let exists = definition.is_some();
+
+        // Swap in the old version of the table, and free the pages which are only referenced by
+        // the current version
+        let (existed, revived) = self
+            .tables
+            .lock()
+            .unwrap()
+            .restore_table(self, name, table_type, definition)?;
+
+        // The pages which are only referenced by the old version were freed after the savepoint,
+        // so remove them from the data freed tree
+        if !revived.is_empty() {
+            let lower = TransactionIdWithPagination {
+                transaction_id: savepoint.get_transaction_id().next().raw_id(),
+                pagination_id: 0,
+            };
+            let mut system_tables = self.system_tables.lock().unwrap();
+            let mut data_freed = system_tables.open_system_table(self, DATA_FREED_TABLE)?;
+            let mut remaining = vec![];
+            for entry in data_freed.extract_from_if(lower.., |_, pages| {
+                (0..pages.len()).any(|i| revived.contains(&pages.get(i)))
+            })? {
+                let (key, pages) = entry?;
+                let pages: Vec<PageNumber> = (0..pages.value().len())
+                    .map(|i| pages.value().get(i))
+                    .filter(|page| !revived.contains(page))
+                    .collect();
+                remaining.push((key.value(), pages));
+            }
+            for (key, pages) in remaining {
+                if pages.is_empty() {
+                    continue;
+                }
+                let mut access_guard =
+                    data_freed.insert_reserve(&key, PageList::required_bytes(pages.len()))?;
+                access_guard.as_mut().clear();
+                for page in pages {
+                    access_guard.as_mut().push_back(page);
+                }
+            }
+        }
+
+        match (existed, exists) {
+            (true, false) => {
+                self.record_schema_change(name, table_type, SchemaChangeKind::Deleted)?;
+            }
+            (false, true) => {
+                self.record_schema_change(name, table_type, SchemaChangeKind::Created)?;
+            }
+            _ => {}
+        }
+
+        // Savepoints created after this one may reference pages that have now been freed
+        self.invalidate_savepoints_after(savepoint)?;
+
+        Ok(())
+    }
+
+    // Checks that the savepoint can be restored by this transaction
+    fn check_restorable(&self, savepoint: &Savepoint) -> Result<(), SavepointError> {
+        // Reject a Savepoint that is from a different Database
+        if std::ptr::from_ref(self.transaction_tracker.as_ref()) != savepoint.db_address() {
+            return Err(SavepointError::InvalidSavepoint);
+        }
+
+        if !self
+            .transaction_tracker
+            .is_valid_savepoint(savepoint.get_id())
+            || self
+                .savepoint_state
+                .lock()
+                .unwrap()
+                .is_invalidated(savepoint.get_id())
+        {
+            return Err(SavepointError::InvalidSavepoint);
+        }
+
+        if self.durability != InternalDurability::Immediate
+            && self
+                .list_persistent_savepoints()?
+                .any(|id| id > savepoint.get_id().0)
+        {
+            return Err(SavepointError::ImmediateDurabilityRequired);
+        }
+
+        Ok(())
+    }
+
+    // Mark all savepoints newer than the restored one as invalidated for this transaction, to
+    // prevent the user from later trying to restore a savepoint "on another timeline". The
+    // invalidation is purely per-transaction state - the shared `valid_savepoints` map is only
+    // updated if/when commit_inner() runs, so an abort implicitly reverts the invalidation by
+    // dropping this set.
+    fn invalidate_savepoints_after(&self, savepoint: &Savepoint) -> Result<(), SavepointError> {
         let invalidated = self
             .transaction_tracker
             .list_savepoints_after(savepoint.get_id());
@@ -1423,6 +2473,53 @@ impl WriteTransaction {
         self.post_commit_free = PostCommitFree::Disabled;
     }
 
+    /// Apply all the operations in `batch`
+    ///
+    /// Each table is opened once, and its operations are applied in the order that they were added
+    /// to the batch. A table which does not exist is only created if the batch inserts into it.
+    ///
+    /// If an error is returned, some of the operations may already have been applied. Use
+    /// [`Self::abort`] to discard them.
+    pub fn apply(&self, batch: WriteBatch) -> Result<(), TableError> {
+        batch.apply(self)
+    }
+
+    pub(crate) fn table_exists(&self, name: &str) -> Result<bool, TableError> {
+        Ok(self
+            .tables
+            .lock()
+            .unwrap()
+            .table_tree
+            .get_table_untyped(name, TableType::Normal)?
+            .is_some())
+    }
+
+    /// Open the given queue table
+    ///
+    /// The table will be created if it does not exist
+    #[track_caller]
+    pub fn open_queue_table<'txn, V: Value + 'static>(
+        &'txn self,
+        definition: QueueTableDefinition<V>,
+    ) -> Result<QueueTable<'txn, V>, TableError> {
+        Ok(QueueTable::new(
+            self.open_table(definition.table_definition())?,
+        )?)
+    }
+
+    /// Open the given set table
+    ///
+    /// The table will be created if it does not exist
+    #[track_caller]
+    pub fn open_set_table<'txn, K: Key + 'static>(
+        &'txn self,
+        definition: SetTableDefinition<K>,
+    ) -> Result<SetTable<'txn, K>, TableError> {
+        Ok(SetTable::new(
+            self.open_table(definition.table_definition())?,
+        ))
+    }
+
     /// Open the given table
     ///
     /// The table will be created if it does not exist
@@ -1431,7 +2528,15 @@ impl WriteTransaction {
         &'txn self,
         definition: TableDefinition<K, V>,
     ) -> Result<Table<'txn, K, V>, TableError> {
-        self.tables.lock().unwrap().open_table(self, definition)
+        let (table, created) = self.tables.lock().unwrap().open_table(self, definition)?;
+        if created {
+            self.record_schema_change(
+                definition.name(),
+                TableType::Normal,
+                SchemaChangeKind::Created,
+            )?;
+        }
+        Ok(table)
     }
 
     /// Open the given table
@@ -1442,10 +2547,39 @@ impl WriteTransaction {
         &'txn self,
         definition: MultimapTableDefinition<K, V>,
     ) -> Result<MultimapTable<'txn, K, V>, TableError> {
-        self.tables
+        let (table, created) = self
+            .tables
             .lock()
             .unwrap()
-            .open_multimap_table(self, definition)
+            .open_multimap_table(self, definition)?;
+        if created {
+            self.record_schema_change(
+                definition.name(),
+                TableType::Multimap,
+                SchemaChangeKind::Created,
+            )?;
+        }
+        Ok(table)
+    }
+
+    fn record_schema_change(
+        &self,
+        name: &str,
+        table_type: TableType,
+        kind: SchemaChangeKind,
+    ) -> Result {
+        if self.schema_history == SchemaHistory::Disabled {
+            return Ok(());
+        }
+        let change = SchemaChange::new(self.transaction_id.raw_id(), name, table_type, kind);
+        let mut system_tables = self.system_tables.lock().unwrap();
+        let mut history = system_tables.open_system_table(self, SCHEMA_HISTORY_TABLE)?;
+        let sequence = match history.range::<u64>(..)?.next_back() {
+            Some(entry) => entry?.0.value() + 1,
+            None => 0,
+        };
+        history.insert(sequence, change.to_bytes().as_slice())?;
+        Ok(())
     }
 
     pub(crate) fn close_table<K: Key + 'static, V: Value + 'static>(
@@ -1453,12 +2587,13 @@ impl WriteTransaction {
         name: &str,
         table: &BtreeMut<K, V>,
         length: u64,
+        operations: TableOperationStats,
     ) {
         let mut tables = self.tables.lock().unwrap();
         if self.is_poisoned() {
             tables.close_table_without_update(name);
         } else {
-            tables.close_table(name, table, length);
+            tables.close_table(name, table, length, operations);
         }
     }
 
@@ -1474,7 +2609,15 @@ impl WriteTransaction {
         self.tables
             .lock()
             .unwrap()
-            .rename_table(self, &name, new_name.name())
+            .rename_table(self, &name, new_name.name())?;
+        self.record_schema_change(
+            &name,
+            TableType::Normal,
+            SchemaChangeKind::Renamed {
+                new_name: new_name.name().to_string(),
+            },
+        )?;
+        Ok(())
     }
 
     /// Rename the given multimap table
@@ -1489,7 +2632,15 @@ impl WriteTransaction {
         self.tables
             .lock()
             .unwrap()
-            .rename_multimap_table(self, &name, new_name.name())
+            .rename_multimap_table(self, &name, new_name.name())?;
+        self.record_schema_change(
+            &name,
+            TableType::Multimap,
+            SchemaChangeKind::Renamed {
+                new_name: new_name.name().to_string(),
+            },
+        )?;
+        Ok(())
     }
 
     /// Delete the given table
@@ -1499,7 +2650,11 @@ impl WriteTransaction {
         let name = definition.name().to_string();
         // Drop the definition so that callers can pass in a `Table` or `MultimapTable` to delete, without getting a TableAlreadyOpen error
         drop(definition);
-        self.tables.lock().unwrap().delete_table(self, &name)
+        let existed = self.tables.lock().unwrap().delete_table(self, &name)?;
+        if existed {
+            self.record_schema_change(&name, TableType::Normal, SchemaChangeKind::Deleted)?;
+        }
+        Ok(existed)
     }
 
     /// Delete the given table
@@ -1512,10 +2667,15 @@ impl WriteTransaction {
         let name = definition.name().to_string();
         // Drop the definition so that callers can pass in a `Table` or `MultimapTable` to delete, without getting a TableAlreadyOpen error
         drop(definition);
-        self.tables
+        let existed = self
+            .tables
             .lock()
             .unwrap()
-            .delete_multimap_table(self, &name)
+            .delete_multimap_table(self, &name)?;
+        if existed {
+            self.record_schema_change(&name, TableType::Multimap, SchemaChangeKind::Deleted)?;
+        }
+        Ok(existed)
     }
 
     /// List all the tables
@@ -1545,16 +2705,482 @@ impl WriteTransaction {
     /// All writes performed in this transaction will be visible to future transactions, and are
     /// durable as consistent with the [`Durability`] level set by [`Self::set_durability`]
     ///
-    /// Returns [`CommitError::TransactionPoisoned`] if a previous operation panicked and left the
-    /// transaction unable to commit.
-    pub fn commit(mut self) -> Result<(), CommitError> {
+    /// Returns [`CommitError::TransactionPoisoned`] if a previous operation panicked, or failed
+    /// part way through modifying a table, and left the transaction unable to commit.
+    pub fn commit(self) -> Result<(), CommitError> {
+        self.commit_grouped(true)
+    }
+
+    // `allow_group` is false when making a group of deferred commits durable, so that the commit
+    // which does so is never deferred itself
+    fn commit_grouped(mut self, allow_group: bool) -> Result<(), CommitError> {
         // Set completed flag first, so that we don't go through the abort() path on drop, if this fails
         self.completed = true;
         if self.is_poisoned() {
             self.abort_inner()?;
             return Err(CommitError::TransactionPoisoned);
         }
-        self.commit_inner()
+        if let Err(err) = self.run_validators() {
+            self.abort_inner()?;
+            return Err(CommitError::ValidationFailed(err));
+        }
+        let events = match self.capture_changes().and_then(|()| self.watch_events()) {
+            Ok(events) => events,
+            Err(err) => {
+                self.abort_inner()?;
+                return Err(err.into());
+            }
+        };
+        let group_commit = if allow_group {
+            self.defer_durability()
+        } else {
+            None
+        };
+        self.commit_inner()?;
+        for events in events {
+            events.send();
+        }
+        let completed = self.completed_transaction(true);
+        self.invoke_hooks(completed);
+        if let Some(group_commit) = group_commit {
+            let id = self.transaction_id;
+            let tracker = self.transaction_tracker.clone();
+            let mem = self.mem.clone();
+            // Release the write lock, so the waiting writers can commit
+            drop(self);
+            return Self::wait_for_group_commit(&group_commit, tracker, mem, id);
+        }
+        Ok(())
+    }
+
+    // If group commit is enabled, and other writers are waiting to begin, lowers the durability of
+    // a commit with `Durability::Immediate`, so that a later commit in the group syncs it. Returns
+    // the group commit state if the commit was deferred
+    fn defer_durability(&mut self) -> Option<Arc<GroupCommit>> {
+        let group_commit = self.group_commit.clone()?;
+        if self.durability != InternalDurability::Immediate
+            || self.two_phase_commit
+            || self.quick_repair
+            || self
+                .savepoint_state
+                .lock()
+                .unwrap()
+                .has_created_or_deleted()
+            || !self.transaction_tracker.any_writer_waiting()
+        {
+            return None;
+        }
+        self.durability = InternalDurability::None;
+        Some(group_commit)
+    }
+
+    // Waits for a deferred commit to be made durable by a later commit, or makes it durable once
+    // the group commit window has elapsed
+    fn wait_for_group_commit(
+        group_commit: &Arc<GroupCommit>,
+        tracker: Arc<TransactionTracker>,
+        mem: Arc<TransactionalMemory>,
+        id: TransactionId,
+    ) -> Result<(), CommitError> {
+        if group_commit.wait_durable(id) {
+            return Ok(());
+        }
+        let guard = TransactionGuard::new_write(
+            tracker.start_write_transaction(WritePriority::High),
+            tracker.clone(),
+        );
+        if group_commit.is_durable(id) {
+            return Ok(());
+        }
+        let mut txn = WriteTransaction::new(guard, tracker, mem, AllocationPolicy::Default)?;
+        txn.set_group_commit(Some(group_commit.clone()));
+        txn.commit_grouped(false)
+    }
+
+    /// Commit the transaction on a separate thread
+    ///
+    /// Returns a future which resolves to the result of [`Self::commit`] once the commit has
+    /// completed, and its writes are durable as consistent with the [`Durability`] level. This
+    /// allows an async task to wait for a commit with [`Durability::Immediate`] without blocking the
+    /// thread which polls it while the file is synced.
+    ///
+    /// The future does not depend on any particular async runtime. The commit proceeds even if the
+    /// future is dropped before it resolves.
+    #[cfg(feature = "async")]
+    pub fn commit_async(self) -> CommitFuture {
+        let state = Arc::new(Mutex::new(CommitFutureState::default()));
+        let thread_state = state.clone();
+        let spawned = thread::Builder::new()
+            .name("redb-commit".to_string())
+            .spawn(move || {
+                let result = self.commit();
+                let waker = {
+                    let mut state = thread_state.lock().unwrap();
+                    state.result = Some(result);
+                    state.waker.take()
+                };
+                if let Some(waker) = waker {
+                    waker.wake();
+                }
+            });
+        // If the thread could not be spawned, the transaction was dropped along with the closure,
+        // and so it has been aborted
+        if let Err(err) = spawned {
+            state.lock().unwrap().result = Some(Err(StorageError::Io(err).into()));
+        }
+        CommitFuture { state }
+    }
+
+    /// Prepare the transaction for a two-phase commit
+    ///
+    /// All writes performed in this transaction are made durable, but are not visible to future
+    /// transactions until [`Self::commit_prepared`] is called with the returned id, in a later
+    /// transaction. That transaction can also call [`Self::rollback_prepared`] to discard the
+    /// writes instead. Prepared transactions survive the database being closed and reopened, and
+    /// are listed by [`Self::list_prepared_transactions`] until they are committed or rolled back.
+    ///
+    /// A prepared transaction can only be committed if the database has not been modified since it
+    /// was prepared. Hooks registered with [`Self::on_commit`] are not run, and the changes are
+    /// delivered to commit hooks and watches when the transaction which commits them commits.
+    ///
+    /// Returns [`PrepareError::PersistentSavepointExists`] or
+    /// [`PrepareError::EphemeralSavepointExists`] if a savepoint exists, in which case the
+    /// transaction is aborted.
+    pub fn prepare(self) -> Result<u64, PrepareError> {
+        self.prepare_inner(None)
+    }
+
+    // Prepares the transaction as part of the commit `commit_id` of a CommitCoordinator
+    pub(crate) fn prepare_coordinated(self, commit_id: u64) -> Result<u64, PrepareError> {
+        self.prepare_inner(Some(commit_id))
+    }
+
+    fn prepare_inner(mut self, coordinated_commit: Option<u64>) -> Result<u64, PrepareError> {
+        // Set completed flag first, so that we don't go through the abort() path on drop, if this fails
+        self.completed = true;
+        if self.is_poisoned() {
+            self.abort_inner()?;
+            return Err(PrepareError::TransactionPoisoned);
+        }
+        if let Err(err) = self.run_validators() {
+            self.abort_inner()?;
+            return Err(PrepareError::ValidationFailed(err));
+        }
+        if self.list_persistent_savepoints()?.next().is_some() {
+            self.abort_inner()?;
+            return Err(PrepareError::PersistentSavepointExists);
+        }
+        if self.transaction_tracker.any_savepoint_exists() {
+            self.abort_inner()?;
+            return Err(PrepareError::EphemeralSavepointExists);
+        }
+        // Quick-repair requires 2-phase commit
+        if self.quick_repair {
+            self.two_phase_commit = true;
+        }
+
+        let id = self.transaction_id.raw_id();
+        let base = self.mem.get_data_root();
+        let (prepared, _, replaced) = self.tables.lock().unwrap().table_tree.flush_and_close()?;
+
+        // Pages are only written once, so every page written by this transaction is reachable
+        // through other pages which it wrote
+        let page_allocator = self.page_allocator();
+        let mut allocated = vec![];
+        TableTree::new(
+            prepared,
+            PageHint::None,
+            self.transaction_guard.clone(),
+            page_allocator.resolver(),
+        )?
+        .visit_pages_while(|path| {
+            let page = path.page_number();
+            if page_allocator.uncommitted(page) {
+                allocated.push(page);
+                Ok(true)
+            } else {
+                Ok(false)
+            }
+        })?;
+
+        #[cfg(feature = "logging")]
+        debug!(
+            "Preparing transaction id={:?} with {} allocated pages",
+            self.transaction_id,
+            allocated.len()
+        );
+        {
+            let mut system_tables = self.system_tables.lock().unwrap();
+            let mut prepared_table = system_tables.open_system_table(&self, PREPARED_TABLE)?;
+            let record = PreparedTransaction {
+                base,
+                prepared,
+                coordinated_commit,
+            };
+            prepared_table.insert(id, record.to_bytes().as_slice())?;
+            drop(prepared_table);
+            let mut allocated_table =
+                system_tables.open_system_table(&self, PREPARED_ALLOCATED_TABLE)?;
+            Self::write_page_list(&mut allocated_table, self.transaction_id, allocated)?;
+            drop(allocated_table);
+            let mut replaced_table =
+                system_tables.open_system_table(&self, PREPARED_FREED_TABLE)?;
+            Self::write_page_list(&mut replaced_table, self.transaction_id, replaced)?;
+        }
+
+        // The data root is left as it was, so that the writes are not visible
+        self.durability = InternalDurability::Immediate;
+        self.durable_commit(base, vec![])?;
+
+        Ok(id)
+    }
+
+    /// Commit the writes of a transaction which was prepared with [`Self::prepare`]
+    ///
+    /// The writes become visible when this transaction commits, and the prepared transaction is
+    /// removed. This must be called before any other writes are made in this transaction.
+    ///
+    /// Returns [`PrepareError::Conflict`] if the database, or this transaction, has been modified
+    /// since the transaction was prepared. It can then only be rolled back. Returns
+    /// [`PrepareError::PersistentSavepointExists`] or [`PrepareError::EphemeralSavepointExists`] if
+    /// a savepoint exists.
+    pub fn commit_prepared(&mut self, id: u64) -> Result<(), PrepareError> {
+        let roots = self
+            .read_existing_system_table(PREPARED_TABLE, |table| {
+                table
+                    .get(&id)?
+                    .map(|value| PreparedTransaction::from_bytes(value.value()))
+                    .transpose()
+            })?
+            .flatten()
+            .ok_or(PrepareError::NotFound(id))?;
+        if self.list_persistent_savepoints()?.next().is_some() {
+            return Err(PrepareError::PersistentSavepointExists);
+        }
+        if self.transaction_tracker.any_savepoint_exists() {
+            return Err(PrepareError::EphemeralSavepointExists);
+        }
+        let mut tables = self.tables.lock().unwrap();
+        if self.mem.get_data_root() != roots.base || !tables.table_tree.modified_tables().is_empty()
+        {
+            return Err(PrepareError::Conflict(id));
+        }
+        #[cfg(feature = "logging")]
+        debug!(
+            "Committing prepared transaction id={id} in transaction id={:?}",
+            self.transaction_id
+        );
+        self.dirty.store(true, Ordering::Release);
+        tables.table_tree.set_root(roots.prepared)?;
+
+        let replaced = {
+            let mut system_tables = self.system_tables.lock().unwrap();
+            system_tables
+                .open_system_table(self, PREPARED_TABLE)?
+                .remove(&id)?;
+            self.take_page_list(&mut system_tables, PREPARED_ALLOCATED_TABLE, id)?;
+            self.take_page_list(&mut system_tables, PREPARED_FREED_TABLE, id)?
+        };
+        tables.freed_pages.lock().unwrap().extend(replaced);
+
+        Ok(())
+    }
+
+    /// Discard the writes of a transaction which was prepared with [`Self::prepare`]
+    ///
+    /// The prepared transaction is removed when this transaction commits
+    pub fn rollback_prepared(&mut self, id: u64) -> Result<(), PrepareError> {
+        let allocated = {
+            let mut system_tables = self.system_tables.lock().unwrap();
+            if system_tables
+                .open_system_table(self, PREPARED_TABLE)?
+                .remove(&id)?
+                .is_none()
+            {
+                return Err(PrepareError::NotFound(id));
+            }
+            self.take_page_list(&mut system_tables, PREPARED_FREED_TABLE, id)?;
+            self.take_page_list(&mut system_tables, PREPARED_ALLOCATED_TABLE, id)?
+        };
+        #[cfg(feature = "logging")]
+        debug!(
+            "Rolling back prepared transaction id={id} in transaction id={:?}",
+            self.transaction_id
+        );
+        self.dirty.store(true, Ordering::Release);
+        // The pages were never reachable from a committed root, so no reader can be using them.
+        // They're freed when this transaction commits
+        self.tables
+            .lock()
+            .unwrap()
+            .freed_pages
+            .lock()
+            .unwrap()
+            .extend(allocated);
+
+        Ok(())
+    }
+
+    /// List the ids of the transactions which have been prepared, but not yet committed or rolled
+    /// back
+    pub fn list_prepared_transactions(&self) -> Result<impl Iterator<Item = u64>> {
+        let Some(prepared) = self.read_existing_system_table(PREPARED_TABLE, |table| {
+            let mut prepared = vec![];
+            for entry in table.range::<RangeFull, u64>(&..)? {
+                prepared.push(entry?.key());
+            }
+            Ok(prepared)
+        })?
+        else {
+            return Ok(vec![].into_iter());
+        };
+        Ok(prepared.into_iter())
+    }
+
+    // Returns the prepared transactions which are part of a coordinated commit, as pairs of the
+    // prepared transaction id and the coordinated commit id
+    pub(crate) fn list_coordinated_prepared(&self) -> Result<Vec<(u64, u64)>> {
+        Ok(self
+            .read_existing_system_table(PREPARED_TABLE, |table| {
+                let mut result = vec![];
+                for entry in table.range::<RangeFull, u64>(&..)? {
+                    let entry = entry?;
+                    let record = PreparedTransaction::from_bytes(entry.value())?;
+                    if let Some(commit_id) = record.coordinated_commit {
+                        result.push((entry.key(), commit_id));
+                    }
+                }
+                Ok(result)
+            })?
+            .unwrap_or_default())
+    }
+
+    // Records that the coordinated commit `commit_id` is committed, in the coordinator database
+    pub(crate) fn record_coordinated_commit(&self, commit_id: u64) -> Result {
+        let mut system_tables = self.system_tables.lock().unwrap();
+        let mut table = system_tables.open_system_table(self, COORDINATED_COMMITS_TABLE)?;
+        table.insert(commit_id, ())?;
+        Ok(())
+    }
+
+    pub(crate) fn remove_coordinated_commits(&self, commit_ids: &[u64]) -> Result {
+        let mut system_tables = self.system_tables.lock().unwrap();
+        let mut table = system_tables.open_system_table(self, COORDINATED_COMMITS_TABLE)?;
+        for commit_id in commit_ids {
+            table.remove(commit_id)?;
+        }
+        Ok(())
+    }
+
+    // Returns the coordinated commits which have been recorded in the coordinator database
+    pub(crate) fn list_coordinated_commits(&self) -> Result<Vec<u64>> {
+        Ok(self
+            .read_existing_system_table(COORDINATED_COMMITS_TABLE, |table| {
+                let mut result = vec![];
+                for entry in table.range::<RangeFull, u64>(&..)? {
+                    result.push(entry?.key());
+                }
+                Ok(result)
+            })?
+            .unwrap_or_default())
+    }
+
+    pub(crate) fn record_quarantined_regions(&self, regions: &[u32]) -> Result {
+        let mut system_tables = self.system_tables.lock().unwrap();
+        let mut table = system_tables.open_system_table(self, QUARANTINED_REGIONS_TABLE)?;
+        for region in regions {
+            table.insert(region, ())?;
+        }
+        Ok(())
+    }
+
+    pub(crate) fn clear_quarantined_regions(&self) -> Result {
+        let regions = self.list_quarantined_regions()?;
+        let mut system_tables = self.system_tables.lock().unwrap();
+        let mut table = system_tables.open_system_table(self, QUARANTINED_REGIONS_TABLE)?;
+        for region in regions {
+            table.remove(region)?;
+        }
+        Ok(())
+    }
+
+    pub(crate) fn list_quarantined_regions(&self) -> Result<Vec<u32>> {
+        Ok(self
+            .read_existing_system_table(QUARANTINED_REGIONS_TABLE, |table| {
+                let mut result = vec![];
+                for entry in table.range::<RangeFull, u32>(&..)? {
+                    result.push(entry?.key());
+                }
+                Ok(result)
+            })?
+            .unwrap_or_default())
+    }
+
+    pub(crate) fn id(&self) -> u64 {
+        self.transaction_id.raw_id()
+    }
+
+    pub(crate) fn belongs_to(&self, database: &Database) -> bool {
+        Arc::ptr_eq(&self.mem, &database.get_memory())
+    }
+
+    // Removes the page list stored for the prepared transaction `id`, and returns its pages
+    fn take_page_list(
+        &self,
+        system_tables: &mut SystemNamespace,
+        definition: SystemTableDefinition<TransactionIdWithPagination, PageList>,
+        id: u64,
+    ) -> Result<Vec<PageNumber>> {
+        let mut table = system_tables.open_system_table(self, definition)?;
+        let lower = TransactionIdWithPagination {
+            transaction_id: id,
+            pagination_id: 0,
+        };
+        let upper = TransactionIdWithPagination {
+            transaction_id: id,
+            pagination_id: u64::MAX,
+        };
+        let mut pages = vec![];
+        for entry in table.extract_from_if(lower..=upper, |_, _| true)? {
+            let (_, value) = entry?;
+            for i in 0..value.value().len() {
+                pages.push(value.value().get(i));
+            }
+        }
+        Ok(pages)
+    }
+
+    // Deletes the persistent savepoints that exceed the limits set with
+    // Builder::set_max_persistent_savepoints() and Builder::set_persistent_savepoint_max_age()
+    fn prune_persistent_savepoints(&self) -> Result {
+        if self.max_persistent_savepoints.is_none() && self.persistent_savepoint_max_age.is_none() {
+            return Ok(());
+        }
+        let savepoints = self.list_persistent_savepoint_info()?;
+        // Savepoints are listed from oldest to newest
+        let excess = self
+            .max_persistent_savepoints
+            .map_or(0, |max| savepoints.len().saturating_sub(max));
+        let now = SystemTime::now();
+        for (i, savepoint) in savepoints.iter().enumerate() {
+            let expired = match (self.persistent_savepoint_max_age, savepoint.created()) {
+                (Some(max_age), Some(created)) => {
+                    now.duration_since(created).unwrap_or_default() > max_age
+                }
+                _ => false,
+            };
+            if i < excess || expired {
+                self.delete_persistent_savepoint(savepoint.id())
+                    .map_err(|e| match e {
+                        SavepointError::Storage(storage) => storage,
+                        SavepointError::InvalidSavepoint
+                        | SavepointError::ImmediateDurabilityRequired
+                        | SavepointError::Table(_) => unreachable!(),
+                    })?;
+            }
+        }
+
+        Ok(())
     }
 
     fn commit_inner(&mut self) -> Result<(), CommitError> {
@@ -1563,6 +3189,27 @@ impl WriteTransaction {
             self.two_phase_commit = true;
         }
 
+        // Checkpoint by making this commit durable, if too much data has accumulated in
+        // non-durable commits
+        if self.durability == InternalDurability::None
+            && let Some(limit) = self.auto_checkpoint_size
+            && self.mem.unpersisted_bytes() >= limit
+        {
+            #[cfg(feature = "logging")]
+            info!(
+                target: crate::log_targets::DURABILITY,
+                event = "auto_checkpoint",
+                transaction_id = self.transaction_id.raw_id(),
+                unpersisted_bytes = self.mem.unpersisted_bytes();
+                "Making commit durable, because the auto checkpoint size was reached"
+            );
+            self.durability = InternalDurability::Immediate;
+        }
+
+        if self.durability == InternalDurability::Immediate {
+            self.prune_persistent_savepoints()?;
+        }
+
         let (user_root, allocated_pages, data_freed) =
             self.tables.lock().unwrap().table_tree.flush_and_close()?;
 
@@ -1677,39 +3324,77 @@ impl WriteTransaction {
             }
         }
 
+        let oldest = self
+            .transaction_tracker
+            .oldest_savepoint()
+            .map_or(u64::MAX, |(_, x)| x.raw_id());
+        let mut allocated_by_page = vec![];
+        let mut unallocated_by_page = vec![];
+
         let unpersisted = self.mem.take_unpersisted_allocations();
         let mut system_tables = self.system_tables.lock().unwrap();
         let mut allocated_table = system_tables.open_system_table(self, DATA_ALLOCATED_TABLE)?;
         for (txn_id, pages) in unpersisted {
-            Self::write_allocated_pages_entry(
-                &mut allocated_table,
-                txn_id,
-                pages.into_iter().collect(),
-            )?;
+            if txn_id.raw_id() >= oldest {
+                allocated_by_page.extend(pages.iter().map(|page| (*page, txn_id)));
+            }
+            Self::write_page_list(&mut allocated_table, txn_id, pages.into_iter().collect())?;
         }
-        Self::write_allocated_pages_entry(
+        if self.transaction_id.raw_id() >= oldest {
+            allocated_by_page.extend(
+                data_allocated_pages
+                    .iter()
+                    .map(|page| (*page, self.transaction_id)),
+            );
+        }
+        Self::write_page_list(
             &mut allocated_table,
             self.transaction_id,
             data_allocated_pages,
         )?;
 
         // Purge any transactions that are no longer referenced
-        let oldest = self
-            .transaction_tracker
-            .oldest_savepoint()
-            .map_or(u64::MAX, |(_, x)| x.raw_id());
         let key = TransactionIdWithPagination {
             transaction_id: oldest,
             pagination_id: 0,
         };
         for entry in allocated_table.extract_from_if(..key, |_, _| true)? {
-            entry?;
+            let (key, pages) = entry?;
+            let transaction_id = TransactionId::new(key.value().transaction_id);
+            let pages = pages.value();
+            for i in 0..pages.len() {
+                unallocated_by_page.push((pages.get(i), transaction_id));
+            }
+        }
+        drop(allocated_table);
+
+        if allocated_by_page.is_empty()
+            && system_tables
+                .get_system_table_root(DATA_ALLOCATED_BY_PAGE_TABLE)?
+                .is_none()
+        {
+            return Ok(());
+        }
+        let mut by_page_table =
+            system_tables.open_system_table(self, DATA_ALLOCATED_BY_PAGE_TABLE)?;
+        for (page, transaction_id) in unallocated_by_page {
+            // The page may have been freed and allocated again by a later transaction
+            let key = page_key(page);
+            if by_page_table
+                .get(&key)?
+                .is_some_and(|x| x.value() == transaction_id.raw_id())
+            {
+                by_page_table.remove(&key)?;
+            }
+        }
+        for (page, transaction_id) in allocated_by_page {
+            by_page_table.insert(&page_key(page), &transaction_id.raw_id())?;
         }
 
         Ok(())
     }
 
-    fn write_allocated_pages_entry(
+    fn write_page_list(
         allocated_table: &mut SystemTable<'_, TransactionIdWithPagination, PageList<'static>>,
         transaction_id: TransactionId,
         mut pages: Vec<PageNumber>,
@@ -1747,6 +3432,7 @@ impl WriteTransaction {
     fn abort_inner(&mut self) -> Result {
         #[cfg(feature = "logging")]
         debug!("Aborting transaction id={:?}", self.transaction_id);
+        let completed = self.completed_transaction(false);
         self.tables
             .lock()
             .unwrap()
@@ -1764,6 +3450,7 @@ impl WriteTransaction {
         self.page_allocator().rollback_all();
         #[cfg(feature = "logging")]
         debug!("Finished abort of transaction id={:?}", self.transaction_id);
+        self.invoke_hooks(completed);
         Ok(())
     }
 
@@ -1772,11 +3459,24 @@ impl WriteTransaction {
         user_root: Option<BtreeHeader>,
         allocated_pages: Vec<PageNumber>,
     ) -> Result {
+        let external_reader = self.mem.oldest_external_reader()?;
         let free_until_transaction = self
             .transaction_tracker
             .oldest_live_read_transaction()
+            .into_iter()
+            .chain(external_reader)
+            .min()
+            .map_or(self.transaction_id, |x| x.next());
+        // Savepoints only reference pages in the data tree, and only some of those
+        let unpinned_until_transaction = self
+            .transaction_tracker
+            .oldest_live_read_transaction_excluding_savepoints()
+            .into_iter()
+            .chain(external_reader)
+            .min()
             .map_or(self.transaction_id, |x| x.next());
-        self.process_freed_pages(free_until_transaction)?;
+        self.process_freed_pages(free_until_transaction, unpinned_until_transaction)?;
+        self.free_unpinned_data_pages(unpinned_until_transaction)?;
         // Flush allocated pages (including previously unpersisted allocations that are now
         // becoming durable) AFTER process_freed_pages, so that any pages reclaimed here have
         // already been dropped from the in-memory `unpersisted_allocations` map.
@@ -1843,6 +3543,9 @@ impl WriteTransaction {
 
         // Mark any pending non-durable commits as fully committed.
         self.transaction_tracker.clear_pending_non_durable_commits();
+        if let Some(group_commit) = &self.group_commit {
+            group_commit.notify_durable(self.transaction_id);
+        }
 
         // Immediately free the pages that were freed from the system-tree. These are only
         // accessed by write transactions, so it's safe to free them as soon as the commit is done.
@@ -1870,6 +3573,9 @@ impl WriteTransaction {
         let free_until = self
             .transaction_tracker
             .oldest_live_read_transaction()
+            .into_iter()
+            .chain(self.mem.oldest_external_reader()?)
+            .min()
             .map_or(epilogue_transaction, |x| x.next());
 
         let mut freed_any = false;
@@ -2062,13 +3768,106 @@ impl WriteTransaction {
 
         table_tree.relocate_tables(&relocation_map)?;
         system_table_tree.relocate_tables(&relocation_map)?;
+        self.mem.transfer_access_recency(&relocation_map);
 
         Ok(progress)
     }
 
+    // Returns the path of every page in the database, in tree order
+    pub(crate) fn page_paths(&self) -> Result<Vec<PagePath>> {
+        let mut paths = vec![];
+        self.tables
+            .lock()
+            .unwrap()
+            .table_tree
+            .pages_in_tree_order(&mut paths)?;
+        self.system_tables
+            .lock()
+            .unwrap()
+            .table_tree
+            .pages_in_tree_order(&mut paths)?;
+        Ok(paths)
+    }
+
+    // Copies every table reachable from the given table tree root of another database, whose pages
+    // are read through source, into this one
+    pub(crate) fn import_tables(&self, root: Option<BtreeHeader>, source: &PageResolver) -> Result {
+        let tree = TableTree::new(
+            root,
+            PageHint::None,
+            Arc::new(TransactionGuard::untracked()),
+            source.clone(),
+        )?;
+        let mut tables = self.tables.lock().unwrap();
+        tables.set_dirty(self);
+        for table_type in [TableType::Normal, TableType::Multimap] {
+            for name in tree.list_tables(table_type)? {
+                let definition = tree
+                    .get_table_untyped(&name, table_type)
+                    .map_err(|e| e.into_storage_error_or_corrupted("Internal corruption"))?
+                    .unwrap();
+                tables
+                    .table_tree
+                    .import_table(&name, &definition, source)
+                    .map_err(|e| e.into_storage_error_or_corrupted("Internal corruption"))?;
+            }
+        }
+        Ok(())
+    }
+
+    // Relocate the given pages, in order, to the lowest free pages. The parents of each page are
+    // relocated along with it, so that they can be rewritten to point to the new location.
+    // Returns the mapping from old to new page numbers
+    pub(crate) fn relocate_pages(
+        &mut self,
+        pages: &[PageNumber],
+    ) -> Result<HashMap<PageNumber, PageNumber>> {
+        let paths: HashMap<PageNumber, PagePath> = self
+            .page_paths()?
+            .into_iter()
+            .map(|path| (path.page_number(), path))
+            .collect();
+
+        let mut tables = self.tables.lock().unwrap();
+        let table_tree = &mut tables.table_tree;
+        let mut system_tables = self.system_tables.lock().unwrap();
+        let system_table_tree = &mut system_tables.table_tree;
+        let page_allocator = table_tree.page_allocator().clone();
+
+        let mut relocation_map = HashMap::new();
+        for page in pages {
+            // Pages that have been rewritten since they were collected are no longer in the tree
+            let Some(path) = paths.get(page) else {
+                continue;
+            };
+            for page_number in path.parents().iter().chain([page]) {
+                if relocation_map.contains_key(page_number) {
+                    continue;
+                }
+                let old_page = page_allocator.get_page(*page_number, PageHint::None)?;
+                let mut new_page = page_allocator
+                    .allocate_lowest(old_page.memory().len(), &mut PageTrackerPolicy::Ignore)?;
+                // We have to copy at least the page type into the new page.
+                // Otherwise its cache priority will be calculated incorrectly
+                new_page.memory_mut()[0] = old_page.memory()[0];
+                relocation_map.insert(*page_number, new_page.get_page_number());
+            }
+        }
+
+        table_tree.relocate_tables(&relocation_map)?;
+        system_table_tree.relocate_tables(&relocation_map)?;
+        self.mem.transfer_access_recency(&relocation_map);
+
+        Ok(relocation_map)
+    }
+
     // NOTE: must be called before store_system_freed_pages() during commit, since this can create
     // more pages freed by the current transaction
-    fn process_freed_pages(&mut self, free_until: TransactionId) -> Result {
+    fn process_freed_pages(
+        &mut self,
+        free_until: TransactionId,
+        system_free_until: TransactionId,
+    ) -> Result {
         // We assume below that PageNumber is length 8
         assert_eq!(PageNumber::serialized_size(), 8);
 
@@ -2093,7 +3892,7 @@ impl WriteTransaction {
             extracted_transactions.extend(self.extract_freed_pages(
                 &mut system_tables,
                 SYSTEM_FREED_TABLE,
-                free_until,
+                system_free_until,
                 &mut free_page,
             )?);
             extracted_transactions
@@ -2104,6 +3903,178 @@ impl WriteTransaction {
         Ok(())
     }
 
+    // Frees the pages in DATA_FREED_TABLE which were freed before `unpinned_until`, and which are
+    // not reachable from any savepoint: a page is reachable from a savepoint if it was allocated
+    // at or before the savepoint's transaction, and freed after it. Other pages are left in the
+    // table, and checked again if one of the savepoints which could reach them is released.
+    //
+    // Pages whose allocating transaction is not recorded in DATA_ALLOCATED_BY_PAGE_TABLE are
+    // assumed to be reachable.
+    fn free_unpinned_data_pages(&self, unpinned_until: TransactionId) -> Result {
+        let checked_until = self.transaction_tracker.unpinned_checked_until();
+        let (savepoints, released) = self.transaction_tracker.take_released_savepoints();
+        self.transaction_tracker
+            .set_unpinned_checked_until(max(checked_until, unpinned_until));
+        if savepoints.is_empty() {
+            // Everything will be freed by process_freed_pages()
+            return Ok(());
+        }
+
+        // Pages freed after a released savepoint, and before the next valid one, may no longer be
+        // reachable. Pages freed after the next valid savepoint are still reachable from it, if
+        // they were reachable from the released one
+        let mut ranges = vec![checked_until..unpinned_until];
+        for transaction_id in released {
+            let end = savepoints
+                .range(transaction_id..)
+                .next()
+                .map_or(unpinned_until, |x| min(x.next(), unpinned_until));
+            ranges.push(transaction_id.next()..end);
+        }
+        ranges.retain(|x| x.start < x.end);
+        ranges.sort_by_key(|x| x.start);
+        let mut merged: Vec<std::ops::Range<TransactionId>> = vec![];
+        for range in ranges {
+            match merged.last_mut() {
+                Some(last) if range.start <= last.end => last.end = max(last.end, range.end),
+                _ => merged.push(range),
+            }
+        }
+
+        let mut system_tables = self.system_tables.lock().unwrap();
+        if system_tables
+            .get_system_table_root(DATA_FREED_TABLE)?
+            .is_none()
+            || system_tables
+                .get_system_table_root(DATA_ALLOCATED_BY_PAGE_TABLE)?
+                .is_none()
+        {
+            return Ok(());
+        }
+
+        let mut candidates: BTreeMap<TransactionId, Vec<PageNumber>> = BTreeMap::new();
+        let freed_table = system_tables.open_system_table(self, DATA_FREED_TABLE)?;
+        for range in merged {
+            let start = TransactionIdWithPagination {
+                transaction_id: range.start.raw_id(),
+                pagination_id: 0,
+            };
+            let end = TransactionIdWithPagination {
+                transaction_id: range.end.raw_id(),
+                pagination_id: 0,
+            };
+            for entry in freed_table.range(start..end)? {
+                let (key, pages) = entry?;
+                let transaction_id = TransactionId::new(key.value().transaction_id);
+                let pages = pages.value();
+                let list = candidates.entry(transaction_id).or_default();
+                for i in 0..pages.len() {
+                    list.push(pages.get(i));
+                }
+            }
+        }
+        drop(freed_table);
+
+        // Split the candidates into the pages that are still reachable, and those that can be
+        // freed, grouped by the transaction that allocated them
+        let mut unpinned: BTreeMap<TransactionId, Vec<PageNumber>> = BTreeMap::new();
+        let mut pinned: BTreeMap<TransactionId, Vec<PageNumber>> = BTreeMap::new();
+        let by_page_table = system_tables.open_system_table(self, DATA_ALLOCATED_BY_PAGE_TABLE)?;
+        for (freed_transaction, pages) in candidates {
+            let mut still_pinned = vec![];
+            let candidate_count = pages.len();
+            for page in pages {
+                let allocated = by_page_table
+                    .get(&page_key(page))?
+                    .map(|x| TransactionId::new(x.value()));
+                match allocated {
+                    Some(allocated)
+                        if savepoints
+                            .range(allocated..freed_transaction)
+                            .next()
+                            .is_none() =>
+                    {
+                        unpinned.entry(allocated).or_default().push(page);
+                    }
+                    _ => still_pinned.push(page),
+                }
+            }
+            if still_pinned.len() != candidate_count {
+                pinned.insert(freed_transaction, still_pinned);
+            }
+        }
+        drop(by_page_table);
+        if unpinned.is_empty() {
+            return Ok(());
+        }
+
+        let mut freed_table = system_tables.open_system_table(self, DATA_FREED_TABLE)?;
+        for (transaction_id, pages) in pinned {
+            Self::replace_page_list(&mut freed_table, transaction_id, pages)?;
+        }
+        drop(freed_table);
+
+        // Remove the pages from the allocation records, so that a later restore_savepoint() doesn't
+        // free them again
+        let mut allocated_table = system_tables.open_system_table(self, DATA_ALLOCATED_TABLE)?;
+        for (transaction_id, pages) in &unpinned {
+            let pages: HashSet<PageNumber> = pages.iter().copied().collect();
+            let lower = TransactionIdWithPagination {
+                transaction_id: transaction_id.raw_id(),
+                pagination_id: 0,
+            };
+            let upper = TransactionIdWithPagination {
+                transaction_id: transaction_id.raw_id(),
+                pagination_id: u64::MAX,
+            };
+            let mut remaining = vec![];
+            for entry in allocated_table.range(lower..=upper)? {
+                let (_, list) = entry?;
+                let list = list.value();
+                for i in 0..list.len() {
+                    if !pages.contains(&list.get(i)) {
+                        remaining.push(list.get(i));
+                    }
+                }
+            }
+            Self::replace_page_list(&mut allocated_table, *transaction_id, remaining)?;
+        }
+        drop(allocated_table);
+
+        let mut by_page_table =
+            system_tables.open_system_table(self, DATA_ALLOCATED_BY_PAGE_TABLE)?;
+        let page_allocator = self.page_allocator();
+        for page in unpinned.into_values().flatten() {
+            by_page_table.remove(&page_key(page))?;
+            // See process_freed_pages(): unpinned_until excludes pages that are still needed by
+            // a pending non-durable commit
+            debug_assert!(!self.mem.unpersisted(page));
+            page_allocator.free(page, &mut PageTrackerPolicy::Ignore);
+        }
+
+        Ok(())
+    }
+
+    // Replaces all the entries of `transaction_id` in `table` with `pages`
+    fn replace_page_list(
+        table: &mut SystemTable<'_, TransactionIdWithPagination, PageList<'static>>,
+        transaction_id: TransactionId,
+        pages: Vec<PageNumber>,
+    ) -> Result {
+        let lower = TransactionIdWithPagination {
+            transaction_id: transaction_id.raw_id(),
+            pagination_id: 0,
+        };
+        let upper = TransactionIdWithPagination {
+            transaction_id: transaction_id.raw_id(),
+            pagination_id: u64::MAX,
+        };
+        for entry in table.extract_from_if(lower..=upper, |_, _| true)? {
+            entry?;
+        }
+        Self::write_page_list(table, transaction_id, pages)
+    }
+
     fn extract_freed_pages(
         &self,
         system_tables: &mut SystemNamespace,
@@ -2307,6 +4278,35 @@ impl WriteTransaction {
             .map_or(0, |entry| entry.key().pagination_id + 1))
     }
 
+    /// Retrieves the pages allocated and freed, and the tables written to, by this transaction so
+    /// far
+    ///
+    /// This is cheap to call, and can be used to log transactions which do a lot of work, or to
+    /// decide when to commit a batch of writes. Unlike [`Self::stats`], it does not read the
+    /// database
+    pub fn transaction_stats(&self) -> TransactionStats {
+        let tables = self.tables.lock().unwrap();
+        let system_tables = self.system_tables.lock().unwrap();
+        let page_allocator = tables.table_tree.page_allocator();
+        let (allocated_pages, allocated_bytes) = page_allocator.allocated();
+        let queued = tables.freed_pages.lock().unwrap().len()
+            + system_tables.freed_pages.lock().unwrap().len();
+        let mut table_operations: Vec<(String, TableOperationStats)> = tables
+            .operations
+            .iter()
+            .map(|(name, operations)| (name.clone(), *operations))
+            .collect();
+        table_operations.sort_by(|a, b| a.0.cmp(&b.0));
+
+        TransactionStats {
+            allocated_pages,
+            allocated_bytes,
+            freed_pages: page_allocator.freed() + queued as u64,
+            dirty_bytes: self.mem.write_buffer_bytes() as u64,
+            table_operations,
+        }
+    }
+
     /// Retrieves information about storage usage in the database
     pub fn stats(&self) -> Result<DatabaseStats> {
         let tables = self.tables.lock().unwrap();
@@ -2380,6 +4380,42 @@ impl WriteTransaction {
     }
 }
 
+/// Future returned by [`WriteTransaction::commit_async`], which resolves once the commit has
+/// completed
+#[cfg(feature = "async")]
+pub struct CommitFuture {
+    state: Arc<Mutex<CommitFutureState>>,
+}
+
+#[cfg(feature = "async")]
+#[derive(Default)]
+struct CommitFutureState {
+    result: Option<Result<(), CommitError>>,
+    waker: Option<Waker>,
+}
+
+#[cfg(feature = "async")]
+impl Future for CommitFuture {
+    type Output = Result<(), CommitError>;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let mut state = self.state.lock().unwrap();
+        if let Some(result) = state.result.take() {
+            Poll::Ready(result)
+        } else {
+            state.waker = Some(cx.waker().clone());
+            Poll::Pending
+        }
+    }
+}
+
+#[cfg(feature = "async")]
+impl Debug for CommitFuture {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("CommitFuture").finish_non_exhaustive()
+    }
+}
+
 impl Drop for WriteTransaction {
     fn drop(&mut self) {
         if !self.completed && !thread::panicking() && !self.mem.storage_failure() {
@@ -2402,8 +4438,13 @@ impl Drop for WriteTransaction {
 ///
 /// Read-only transactions may exist concurrently with writes
 pub struct ReadTransaction {
-    mem: Arc<TransactionalMemory>,
+    resolver: PageResolver,
     tree: TableTree,
+    // System root of the snapshot, used to read the schema history and change log
+    system_root: Option<BtreeHeader>,
+    // Clean for a snapshot of a commit. Views of a write transaction must also read its pending
+    // pages from the write buffer
+    hint: PageHint,
 }
 
 impl ReadTransaction {
@@ -2411,16 +4452,126 @@ impl ReadTransaction {
         mem: Arc<TransactionalMemory>,
         guard: TransactionGuard,
     ) -> Result<Self, TransactionError> {
-        let root_page = mem.get_data_root();
+        let (root_page, system_root) = mem.get_roots();
+        let resolver = PageResolver::with_lease(mem.clone(), guard.lease());
         let guard = Arc::new(guard);
-        let resolver = PageResolver::new(mem.clone());
         Ok(Self {
-            mem,
+            resolver: resolver.clone(),
             tree: TableTree::new(root_page, PageHint::Clean, guard, resolver)
                 .map_err(TransactionError::Storage)?,
+            system_root,
+            hint: PageHint::Clean,
+        })
+    }
+
+    // A view of the uncommitted state of a write transaction, whose data root is `root`. It can no
+    // longer read once `lease` expires
+    fn new_view(
+        mem: Arc<TransactionalMemory>,
+        root: Option<BtreeHeader>,
+        lease: Arc<ReadLease>,
+    ) -> Result<Self> {
+        let resolver = PageResolver::with_lease(mem.clone(), Some(lease));
+        let guard = Arc::new(TransactionGuard::untracked());
+        // The write transaction can't commit while the view exists, so the committed system root
+        // does not change
+        let system_root = mem.get_system_root();
+        Ok(Self {
+            resolver: resolver.clone(),
+            tree: TableTree::new(root, PageHint::None, guard, resolver)?,
+            system_root,
+            hint: PageHint::None,
         })
     }
 
+    pub(crate) fn schema_history(&self) -> Result<Vec<SchemaChange>> {
+        let guard = self.tree.transaction_guard().clone();
+        let system_tree = TableTree::new(
+            self.system_root,
+            PageHint::None,
+            guard.clone(),
+            self.resolver.clone(),
+        )?;
+        let definition = system_tree
+            .get_table::<u64, &[u8]>(SCHEMA_HISTORY_TABLE.name(), TableType::Normal)
+            .map_err(|e| {
+                e.into_storage_error_or_corrupted("Internal error. System table is corrupted")
+            })?;
+        let Some(InternalTableDefinition::Normal {
+            table_root: Some(root),
+            ..
+        }) = definition
+        else {
+            return Ok(vec![]);
+        };
+        let table: Btree<u64, &[u8]> =
+            Btree::new(Some(root), PageHint::None, guard, self.resolver.clone())?;
+        let mut changes = vec![];
+        for entry in table.range::<RangeFull, u64>(&..)? {
+            changes.push(SchemaChange::from_bytes(entry?.value())?);
+        }
+        Ok(changes)
+    }
+
+    pub(crate) fn change_log(
+        &self,
+        transactions: impl RangeBounds<u64>,
+    ) -> Result<Vec<ChangeRecord>> {
+        let guard = self.tree.transaction_guard().clone();
+        let system_tree = TableTree::new(
+            self.system_root,
+            PageHint::None,
+            guard.clone(),
+            self.resolver.clone(),
+        )?;
+        let definition = system_tree
+            .get_table::<(u64, u64), &[u8]>(CHANGE_LOG_TABLE.name(), TableType::Normal)
+            .map_err(|e| {
+                e.into_storage_error_or_corrupted("Internal error. System table is corrupted")
+            })?;
+        let Some(InternalTableDefinition::Normal {
+            table_root: Some(root),
+            ..
+        }) = definition
+        else {
+            return Ok(vec![]);
+        };
+        let table: Btree<(u64, u64), &[u8]> =
+            Btree::new(Some(root), PageHint::None, guard, self.resolver.clone())?;
+        let start = match transactions.start_bound() {
+            Bound::Included(id) => (*id, 0),
+            Bound::Excluded(id) => match id.checked_add(1) {
+                Some(id) => (id, 0),
+                None => return Ok(vec![]),
+            },
+            Bound::Unbounded => (0, 0),
+        };
+        let mut changes = vec![];
+        for entry in table.range::<RangeFrom<(u64, u64)>, (u64, u64)>(&(start..))? {
+            let entry = entry?;
+            let (transaction_id, _) = entry.key();
+            if !transactions.contains(&transaction_id) {
+                break;
+            }
+            changes.push(ChangeRecord::from_bytes(transaction_id, entry.value())?);
+        }
+        Ok(changes)
+    }
+
+    /// Attach a label to this transaction, which is reported by
+    /// [`crate::Database::active_transactions`] and [`crate::Database::set_long_read_hook`] to help
+    /// find the code which holds it open
+    pub fn set_label(&self, label: impl Into<String>) {
+        self.tree.transaction_guard().set_label(label.into());
+    }
+
+    // Pages in the user tables visible to this transaction whose checksums do not match
+    pub(crate) fn find_corrupted_pages(&self) -> Result<Vec<PageNumber>> {
+        let mut corrupted = vec![];
+        self.tree.find_corrupted_pages(&mut corrupted)?;
+        Ok(corrupted)
+    }
+
     /// Open the given table
     pub fn open_table<K: Key + 'static, V: Value + 'static>(
         &self,
@@ -2435,9 +4586,9 @@ impl ReadTransaction {
             InternalTableDefinition::Normal { table_root, .. } => Ok(ReadOnlyTable::new(
                 definition.name().to_string(),
                 table_root,
-                PageHint::Clean,
+                self.hint,
                 self.tree.transaction_guard().clone(),
-                PageResolver::new(self.mem.clone()),
+                self.resolver.clone(),
             )?),
             InternalTableDefinition::Multimap { .. } => unreachable!(),
         }
@@ -2458,18 +4609,43 @@ impl ReadTransaction {
                 table_root,
                 fixed_key_size,
                 fixed_value_size,
+                key_type,
+                value_type,
                 ..
             } => Ok(ReadOnlyUntypedTable::new(
                 table_root,
-                PageHint::Clean,
+                self.hint,
                 fixed_key_size,
                 fixed_value_size,
-                PageResolver::new(self.mem.clone()),
+                key_type,
+                value_type,
+                self.tree.transaction_guard().clone(),
+                self.resolver.clone(),
             )),
             InternalTableDefinition::Multimap { .. } => unreachable!(),
         }
     }
 
+    /// Open the given queue table
+    pub fn open_queue_table<V: Value + 'static>(
+        &self,
+        definition: QueueTableDefinition<V>,
+    ) -> Result<ReadOnlyQueueTable<V>, TableError> {
+        Ok(ReadOnlyQueueTable::new(
+            self.open_table(definition.table_definition())?,
+        ))
+    }
+
+    /// Open the given set table
+    pub fn open_set_table<K: Key + 'static>(
+        &self,
+        definition: SetTableDefinition<K>,
+    ) -> Result<ReadOnlySetTable<K>, TableError> {
+        Ok(ReadOnlySetTable::new(
+            self.open_table(definition.table_definition())?,
+        ))
+    }
+
     /// Open the given table
     pub fn open_multimap_table<K: Key + 'static, V: Key + 'static>(
         &self,
@@ -2489,9 +4665,9 @@ impl ReadTransaction {
             } => Ok(ReadOnlyMultimapTable::new(
                 table_root,
                 table_length,
-                PageHint::Clean,
+                self.hint,
                 self.tree.transaction_guard().clone(),
-                PageResolver::new(self.mem.clone()),
+                self.resolver.clone(),
             )?),
         }
     }
@@ -2517,14 +4693,106 @@ impl ReadTransaction {
             } => Ok(ReadOnlyUntypedMultimapTable::new(
                 table_root,
                 table_length,
-                PageHint::Clean,
+                self.hint,
                 fixed_key_size,
                 fixed_value_size,
-                PageResolver::new(self.mem.clone()),
+                self.resolver.clone(),
             )),
         }
     }
 
+    /// Returns the number of entries in the given table
+    ///
+    /// The length is read from the table's catalog entry, so the table does not need to be opened
+    /// or traversed, and its key and value types do not need to be known
+    pub fn table_len(&self, handle: impl TableHandle) -> Result<u64, TableError> {
+        Ok(self
+            .tree
+            .get_table_untyped(handle.name(), TableType::Normal)?
+            .ok_or_else(|| TableError::TableDoesNotExist(handle.name().to_string()))?
+            .get_length())
+    }
+
+    /// Returns `true` if the given table contains no entries
+    ///
+    /// See [`ReadTransaction::table_len`]
+    pub fn table_is_empty(&self, handle: impl TableHandle) -> Result<bool, TableError> {
+        Ok(self.table_len(handle)? == 0)
+    }
+
+    /// Returns the number of (key, value) pairs in the given multimap table
+    ///
+    /// The length is read from the table's catalog entry, so the table does not need to be opened
+    /// or traversed, and its key and value types do not need to be known
+    pub fn multimap_table_len(&self, handle: impl MultimapTableHandle) -> Result<u64, TableError> {
+        Ok(self
+            .tree
+            .get_table_untyped(handle.name(), TableType::Multimap)?
+            .ok_or_else(|| TableError::TableDoesNotExist(handle.name().to_string()))?
+            .get_length())
+    }
+
+    /// Returns `true` if the given multimap table contains no entries
+    ///
+    /// See [`ReadTransaction::multimap_table_len`]
+    pub fn multimap_table_is_empty(
+        &self,
+        handle: impl MultimapTableHandle,
+    ) -> Result<bool, TableError> {
+        Ok(self.multimap_table_len(handle)? == 0)
+    }
+
+    /// Returns a hash of the contents of the given tables
+    ///
+    /// The hash depends only on the names of the tables, and the keys and values that they contain.
+    /// It does not depend on the order in which the tables are given, or on how their contents are
+    /// laid out in the file, so it can be used to check that two databases, such as a replica or
+    /// a backup, contain the same data. Entries are hashed as they are read, so tables of any size
+    /// can be hashed. Every table can be hashed by passing the result of
+    /// [`ReadTransaction::list_tables`].
+    ///
+    /// The hash is not cryptographic, and must not be relied on to detect deliberate tampering
+    pub fn content_hash<T: TableHandle>(
+        &self,
+        tables: impl IntoIterator<Item = T>,
+    ) -> Result<u128, TableError> {
+        let names: BTreeSet<String> = tables
+            .into_iter()
+            .map(|table| table.name().to_string())
+            .collect();
+        let mut hasher = ContentHasher::new();
+        for name in names {
+            let definition = self
+                .tree
+                .get_table_untyped(&name, TableType::Normal)?
+                .ok_or_else(|| TableError::TableDoesNotExist(name.clone()))?;
+            let InternalTableDefinition::Normal {
+                table_root,
+                table_length,
+                fixed_key_size,
+                fixed_value_size,
+                ..
+            } = definition
+            else {
+                unreachable!();
+            };
+            hasher.write(name.as_bytes());
+            hasher.write(&table_length.to_le_bytes());
+            let tree = RawBtree::new(
+                table_root,
+                fixed_key_size,
+                fixed_value_size,
+                self.resolver.clone(),
+                PageHint::None,
+            );
+            tree.visit_entries(&mut |key, value| {
+                hasher.write(key);
+                hasher.write(value);
+            })?;
+        }
+        Ok(hasher.finish())
+    }
+
     /// List all the tables
     pub fn list_tables(&self) -> Result<impl Iterator<Item = UntypedTableHandle>> {
         self.tree
@@ -2561,6 +4829,41 @@ impl Debug for ReadTransaction {
     }
 }
 
+/// A read-only view of the uncommitted state of a [`WriteTransaction`]
+///
+/// Created by [`WriteTransaction::read_view`]. The view dereferences to a [`ReadTransaction`], and
+/// can be shared with other threads, for example with [`std::thread::scope`], to read the pending
+/// writes in parallel before the transaction commits.
+///
+/// Tables opened from the view may be kept after it is dropped, but reads from them then fail with
+/// [`StorageError::ReadTransactionExpired`]
+pub struct ReadView<'txn> {
+    transaction: ReadTransaction,
+    lease: Arc<ReadLease>,
+    _write_transaction: PhantomData<&'txn mut WriteTransaction>,
+}
+
+impl std::ops::Deref for ReadView<'_> {
+    type Target = ReadTransaction;
+
+    fn deref(&self) -> &ReadTransaction {
+        &self.transaction
+    }
+}
+
+impl Drop for ReadView<'_> {
+    fn drop(&mut self) {
+        // The write transaction may modify or free the pages of the view once it's dropped
+        self.lease.expire();
+    }
+}
+
+impl Debug for ReadView<'_> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.write_str("ReadView")
+    }
+}
+
 #[cfg(test)]
 mod test {
     use crate::{Database, TableDefinition};
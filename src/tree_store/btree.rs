@@ -1,25 +1,29 @@
 use crate::db::TransactionGuard;
 use crate::tree_store::btree_base::{
     AccessGuardMut, BRANCH, BranchAccessor, BranchMutator, BtreeHeader, Checksum, DEFERRED, LEAF,
-    LeafAccessor, LeafPageMut, branch_checksum, leaf_checksum,
+    LeafAccessor, LeafMutator, LeafPageMut, branch_checksum, leaf_checksum,
 };
-use crate::tree_store::btree_mutator::MutateHelper;
+use crate::tree_store::btree_mutator::{MergeFn, MutateHelper};
 use crate::tree_store::page_store::{Page, PageImpl, PageMut};
+use crate::tree_store::range_mut::RangeMut;
 use crate::tree_store::{
-    AccessGuardMutInPlace, AllPageNumbersBtreeIter, BtreeExtractIf, BtreeRangeIter, PageAllocator,
-    PageHint, PageNumber, PageResolver, PageTrackerPolicy,
+    AccessGuardMutInPlace, AllPageNumbersBtreeIter, BtreeCursor, BtreeExtractIf, BtreeRangeIter,
+    ExtractPredicate, PageAllocator, PageHint, PageNumber, PageResolver, PageTrackerPolicy,
+    estimate_range, range_len,
 };
 use crate::types::{Key, MutInPlaceValue, Value};
-use crate::{AccessGuard, Result};
+use crate::{AccessGuard, Result, StorageError};
 #[cfg(feature = "logging")]
 use log::trace;
 use std::borrow::Borrow;
-use std::cmp::max;
-use std::collections::HashMap;
+use std::cmp::{Ordering, max, min};
+use std::collections::{HashMap, HashSet};
 use std::marker::PhantomData;
-use std::ops::RangeBounds;
+use std::ops::{Bound, Range, RangeBounds};
 use std::sync::{Arc, Mutex};
 
+pub(crate) const LEAF_FILL_BUCKETS: usize = 10;
+
 pub(crate) struct BtreeStats {
     pub(crate) tree_height: u32,
     pub(crate) leaf_pages: u64,
@@ -27,6 +31,24 @@ pub(crate) struct BtreeStats {
     pub(crate) stored_leaf_bytes: u64,
     pub(crate) metadata_bytes: u64,
     pub(crate) fragmented_bytes: u64,
+    pub(crate) leaf_fill_histogram: [u64; LEAF_FILL_BUCKETS],
+    pub(crate) largest_leaf_pages: u64,
+}
+
+// Histogram containing a single leaf page, which uses `used` bytes of its `page_len` bytes
+pub(crate) fn leaf_fill_histogram(used: usize, page_len: usize) -> [u64; LEAF_FILL_BUCKETS] {
+    let mut histogram = [0; LEAF_FILL_BUCKETS];
+    histogram[min(used * LEAF_FILL_BUCKETS / page_len, LEAF_FILL_BUCKETS - 1)] = 1;
+    histogram
+}
+
+pub(crate) fn merge_leaf_fill_histogram(
+    histogram: &mut [u64; LEAF_FILL_BUCKETS],
+    other: &[u64; LEAF_FILL_BUCKETS],
+) {
+    for (count, other_count) in histogram.iter_mut().zip(other) {
+        *count += other_count;
+    }
 }
 
 #[derive(Clone)]
@@ -91,6 +113,18 @@ impl UntypedBtree {
     pub(super) fn visit_all_pages<F>(&self, mut visitor: F) -> Result
     where
         F: FnMut(&PagePath) -> Result,
+    {
+        self.visit_pages_while(|path| {
+            visitor(path)?;
+            Ok(true)
+        })
+    }
+
+    // Applies visitor to pages in the tree, skipping the children of any page for which it
+    // returns false
+    pub(super) fn visit_pages_while<F>(&self, mut visitor: F) -> Result
+    where
+        F: FnMut(&PagePath) -> Result<bool>,
     {
         if let Some(page_number) = self.root.map(|x| x.root) {
             self.visit_pages_helper(PagePath::new_root(page_number), &mut visitor)?;
@@ -101,9 +135,11 @@ impl UntypedBtree {
 
     fn visit_pages_helper<F>(&self, path: PagePath, visitor: &mut F) -> Result
     where
-        F: FnMut(&PagePath) -> Result,
+        F: FnMut(&PagePath) -> Result<bool>,
     {
-        visitor(&path)?;
+        if !visitor(&path)? {
+            return Ok(());
+        }
         let page = self.mem.get_page(path.page_number(), self.hint)?;
 
         match page.memory()[0] {
@@ -118,7 +154,7 @@ impl UntypedBtree {
                     self.visit_pages_helper(child_path, visitor)?;
                 }
             }
-            _ => unreachable!(),
+            _ => return Err(self.mem.corrupted_page(&page)),
         }
 
         Ok(())
@@ -203,7 +239,7 @@ impl UntypedBtreeMut {
 
                 branch_checksum(&page, self.key_width)
             }
-            _ => unreachable!(),
+            _ => Err(self.page_allocator.corrupted_page(&page)),
         }
     }
 
@@ -227,7 +263,7 @@ impl UntypedBtreeMut {
                     drop(page);
                     self.dirty_leaf_visitor_helper(page_number, &visitor)?;
                 }
-                _ => unreachable!(),
+                _ => return Err(self.page_allocator.corrupted_page(&page)),
             }
         }
 
@@ -254,7 +290,7 @@ impl UntypedBtreeMut {
                     }
                 }
             }
-            _ => unreachable!(),
+            _ => return Err(self.page_allocator.corrupted_page(&page)),
         }
 
         Ok(())
@@ -305,7 +341,7 @@ impl UntypedBtreeMut {
                     }
                 }
             }
-            _ => unreachable!(),
+            _ => return Err(self.page_allocator.corrupted_page(&old_page)),
         }
 
         let mut freed_pages = self.freed_pages.lock().unwrap();
@@ -323,12 +359,56 @@ impl UntypedBtreeMut {
     }
 }
 
+// Copies the tree rooted at the given page, which is read through source, into pages allocated from
+// page_allocator. Returns the root of the copy, whose checksums must be finalized by the caller
+pub(super) fn copy_btree(
+    page_number: PageNumber,
+    key_width: Option<usize>,
+    source: &PageResolver,
+    page_allocator: &PageAllocator,
+    allocated: &mut PageTrackerPolicy,
+) -> Result<PageNumber> {
+    let old_page = source.get_page(page_number, PageHint::None)?;
+    let mut new_page = page_allocator.allocate(old_page.memory().len(), allocated)?;
+    let new_page_number = new_page.get_page_number();
+    new_page.memory_mut().copy_from_slice(old_page.memory());
+
+    match old_page.memory()[0] {
+        LEAF => {
+            // No-op
+        }
+        BRANCH => {
+            let accessor = BranchAccessor::new(&old_page, key_width);
+            let mut mutator = BranchMutator::new(new_page.memory_mut());
+            for i in 0..accessor.count_children() {
+                let child = accessor.child_page(i).unwrap();
+                let new_child = copy_btree(child, key_width, source, page_allocator, allocated)?;
+                mutator.write_child_page(i, new_child, DEFERRED);
+            }
+        }
+        _ => return Err(source.corrupted_page(&old_page)),
+    }
+
+    Ok(new_page_number)
+}
+
+// Rightmost leaf of a tree, cached by insert_append() so that consecutive appends can go directly to
+// it, without descending from the root
+struct RightEdge {
+    // Header of the tree when the leaf was cached
+    root: BtreeHeader,
+    leaf: PageNumber,
+}
+
 pub(crate) struct BtreeMut<K: Key + 'static, V: Value + 'static> {
     page_allocator: PageAllocator,
     transaction_guard: Arc<TransactionGuard>,
     root: Option<BtreeHeader>,
     freed_pages: Arc<Mutex<Vec<PageNumber>>>,
     allocated_pages: Arc<Mutex<PageTrackerPolicy>>,
+    // Only valid until the tree is modified by anything other than insert_append(), so every other
+    // method which modifies the tree must clear it
+    right_edge: Option<RightEdge>,
     _key_type: PhantomData<K>,
     _value_type: PhantomData<V>,
 }
@@ -347,12 +427,14 @@ impl<K: Key + 'static, V: Value + 'static> BtreeMut<K, V> {
             root,
             freed_pages,
             allocated_pages,
+            right_edge: None,
             _key_type: PhantomData,
             _value_type: PhantomData,
         }
     }
 
     pub(crate) fn finalize_dirty_checksums(&mut self) -> Result<Option<BtreeHeader>> {
+        self.right_edge = None;
         let mut tree = UntypedBtreeMut::new(
             self.get_root(),
             self.page_allocator.clone(),
@@ -391,6 +473,7 @@ impl<K: Key + 'static, V: Value + 'static> BtreeMut<K, V> {
     }
 
     pub(crate) fn set_root(&mut self, root: Option<BtreeHeader>) {
+        self.right_edge = None;
         self.root = root;
     }
 
@@ -398,6 +481,7 @@ impl<K: Key + 'static, V: Value + 'static> BtreeMut<K, V> {
         &mut self,
         relocation_map: &HashMap<PageNumber, PageNumber>,
     ) -> Result<bool> {
+        self.right_edge = None;
         let mut tree = UntypedBtreeMut::new(
             self.get_root(),
             self.page_allocator.clone(),
@@ -425,6 +509,73 @@ impl<K: Key + 'static, V: Value + 'static> BtreeMut<K, V> {
             key,
             V::as_bytes(value).as_ref().len()
         );
+        self.right_edge = None;
+        let mut freed_pages = self.freed_pages.lock().unwrap();
+        let mut operation: MutateHelper<'_, '_, K, V> = MutateHelper::new(
+            &mut self.root,
+            self.page_allocator.clone(),
+            freed_pages.as_mut(),
+            self.allocated_pages.clone(),
+        );
+        let (old_value, _) = operation.insert(key, value)?;
+        Ok(old_value)
+    }
+
+    // Same as insert(), except that if the key is already present, the tree is left unchanged and
+    // the existing value is returned
+    pub(crate) fn insert_if_absent(
+        &mut self,
+        key: &K::SelfType<'_>,
+        value: &V::SelfType<'_>,
+    ) -> Result<Option<AccessGuard<'_, V>>> {
+        self.right_edge = None;
+        let mut freed_pages = self.freed_pages.lock().unwrap();
+        let mut operation: MutateHelper<'_, '_, K, V> = MutateHelper::new(
+            &mut self.root,
+            self.page_allocator.clone(),
+            freed_pages.as_mut(),
+            self.allocated_pages.clone(),
+        );
+        operation.set_only_if_absent(true);
+        let (existing, _) = operation.insert(key, value)?;
+        Ok(existing)
+    }
+
+    // Same as insert(), except that the value stored is the one returned by `merge`, which is
+    // called with the existing value of the key, if any, and `operand`
+    pub(crate) fn merge(
+        &mut self,
+        key: &K::SelfType<'_>,
+        operand: &V::SelfType<'_>,
+        merge: &mut MergeFn<'_>,
+    ) -> Result {
+        self.right_edge = None;
+        let mut freed_pages = self.freed_pages.lock().unwrap();
+        let mut operation: MutateHelper<'_, '_, K, V> = MutateHelper::new(
+            &mut self.root,
+            self.page_allocator.clone(),
+            freed_pages.as_mut(),
+            self.allocated_pages.clone(),
+        );
+        operation.set_merge(merge);
+        operation.insert(key, operand)?;
+        Ok(())
+    }
+
+    // Same as insert(), except that a full leaf is left as it is, rather than split in half, when
+    // the key is appended after its last key. The rightmost leaf is also cached, so that a key after
+    // every key in the tree can usually be inserted without descending from the root
+    pub(crate) fn insert_append(
+        &mut self,
+        key: &K::SelfType<'_>,
+        value: &V::SelfType<'_>,
+    ) -> Result<Option<AccessGuard<'_, V>>> {
+        let right_edge =
+            self.try_insert_at_right_edge(K::as_bytes(key).as_ref(), V::as_bytes(value).as_ref());
+        if self.page_allocator.poison_on_error(right_edge)? {
+            return Ok(None);
+        }
+        self.right_edge = None;
         let mut freed_pages = self.freed_pages.lock().unwrap();
         let mut operation: MutateHelper<'_, '_, K, V> = MutateHelper::new(
             &mut self.root,
@@ -432,10 +583,127 @@ impl<K: Key + 'static, V: Value + 'static> BtreeMut<K, V> {
             freed_pages.as_mut(),
             self.allocated_pages.clone(),
         );
+        operation.set_append_split(true);
         let (old_value, _) = operation.insert(key, value)?;
         Ok(old_value)
     }
 
+    // Same as insert_append(), but only inserts the key if it is greater than every key in the tree.
+    // Returns false if it is not
+    pub(crate) fn append(
+        &mut self,
+        key: &K::SelfType<'_>,
+        value: &V::SelfType<'_>,
+    ) -> Result<bool> {
+        if let Some(root) = self.root {
+            let key_bytes = K::as_bytes(key);
+            let leaf = match &self.right_edge {
+                Some(edge) if edge.root == root => Some(edge.leaf),
+                _ => {
+                    self.right_edge = None;
+                    let leaf = self.find_right_edge(root)?;
+                    if let Some(leaf) = leaf {
+                        self.right_edge = Some(RightEdge { root, leaf });
+                    }
+                    leaf
+                }
+            };
+            let greater = if let Some(leaf) = leaf {
+                let page = self.page_allocator.get_page(leaf, PageHint::None)?;
+                let accessor = LeafAccessor::new(page.memory(), K::fixed_width(), V::fixed_width());
+                K::compare(key_bytes.as_ref(), accessor.last_entry().key()) == Ordering::Greater
+            } else {
+                let tree = self.read_tree()?;
+                let (last, _) = tree.last()?.unwrap();
+                K::compare(key_bytes.as_ref(), K::as_bytes(&last.value()).as_ref())
+                    == Ordering::Greater
+            };
+            if !greater {
+                return Ok(false);
+            }
+        }
+        self.insert_append(key, value)?;
+        Ok(true)
+    }
+
+    // Inserts the key directly into the cached rightmost leaf, if it comes after every key in the
+    // leaf, and the leaf can be modified in place. Returns false if the key was not inserted
+    fn try_insert_at_right_edge(&mut self, key: &[u8], value: &[u8]) -> Result<bool> {
+        let Some(root) = self.root else {
+            return Ok(false);
+        };
+        let leaf = match &self.right_edge {
+            Some(edge) if edge.root == root => edge.leaf,
+            _ => {
+                self.right_edge = None;
+                let Some(leaf) = self.find_right_edge(root)? else {
+                    return Ok(false);
+                };
+                self.right_edge = Some(RightEdge { root, leaf });
+                leaf
+            }
+        };
+
+        let page = self.page_allocator.get_page(leaf, PageHint::None)?;
+        let accessor = LeafAccessor::new(page.memory(), K::fixed_width(), V::fixed_width());
+        let position = accessor.num_pairs();
+        if K::compare(key, accessor.last_entry().key()) != Ordering::Greater
+            || !LeafMutator::sufficient_insert_inplace_space(
+                &page,
+                position,
+                K::fixed_width(),
+                V::fixed_width(),
+                key,
+                value,
+            )
+        {
+            return Ok(false);
+        }
+        drop(page);
+
+        let mut page = self.page_allocator.get_page_mut(leaf)?;
+        LeafMutator::new(page.memory_mut(), K::fixed_width(), V::fixed_width())
+            .insert(position, key, value);
+        let new_root = BtreeHeader::new(root.root, root.checksum, root.length + 1);
+        self.root = Some(new_root);
+        self.right_edge = Some(RightEdge {
+            root: new_root,
+            leaf,
+        });
+        Ok(true)
+    }
+
+    // Returns the rightmost leaf, if it and every branch above it can be modified in place. That
+    // requires that they are uncommitted, and that their checksums have not been computed yet
+    fn find_right_edge(&self, root: BtreeHeader) -> Result<Option<PageNumber>> {
+        if root.checksum != DEFERRED {
+            return Ok(None);
+        }
+        let mut page_number = root.root;
+        loop {
+            if !self.page_allocator.uncommitted(page_number) {
+                return Ok(None);
+            }
+            let page = self.page_allocator.get_page(page_number, PageHint::None)?;
+            match page.memory()[0] {
+                LEAF => {
+                    return Ok(Some(page_number));
+                }
+                BRANCH => {
+                    let accessor = BranchAccessor::new(&page, K::fixed_width());
+                    let last_child = accessor.count_children() - 1;
+                    if accessor.child_checksum(last_child).unwrap() != DEFERRED {
+                        return Ok(None);
+                    }
+                    page_number = accessor.child_page(last_child).unwrap();
+                }
+                _ => {
+                    return Err(self.page_allocator.corrupted_page(&page));
+                }
+            }
+        }
+    }
+
     // Insert without allocating or freeing any pages. This requires that you've previously
     // inserted the same key, with a value of at least the same serialized length, earlier
     // in the same transaction. If those preconditions aren't satisfied, insert_inplace()
@@ -445,6 +713,7 @@ impl<K: Key + 'static, V: Value + 'static> BtreeMut<K, V> {
         key: &K::SelfType<'_>,
         value: &V::SelfType<'_>,
     ) -> Result<()> {
+        self.right_edge = None;
         let mut fake_freed_pages = vec![];
         let fake_allocated_pages = Arc::new(Mutex::new(PageTrackerPolicy::Closed));
         let mut operation = MutateHelper::<K, V>::new(
@@ -478,9 +747,72 @@ impl<K: Key + 'static, V: Value + 'static> BtreeMut<K, V> {
         Ok(bytes)
     }
 
+    // Removes every entry. Only the branch pages are read, to find the pages of the tree, and the
+    // leftmost leaf, to find the depth of the leaves. Every page is then freed, or queued to be
+    // freed if it was committed
+    pub(crate) fn clear(&mut self) -> Result {
+        let result = self.clear_inner();
+        self.page_allocator.poison_on_error(result)
+    }
+
+    fn clear_inner(&mut self) -> Result {
+        self.right_edge = None;
+        let Some(header) = self.root else {
+            return Ok(());
+        };
+        let mem = self.page_allocator.resolver();
+        let mut leaf_depth = 0;
+        let mut page = mem.get_page(header.root, PageHint::None)?;
+        loop {
+            match page.memory()[0] {
+                LEAF => break,
+                BRANCH => {
+                    let child = BranchAccessor::new(&page, K::fixed_width())
+                        .child_page(0)
+                        .unwrap();
+                    page = mem.get_page(child, PageHint::None)?;
+                    leaf_depth += 1;
+                }
+                _ => return Err(mem.corrupted_page(&page)),
+            }
+        }
+        drop(page);
+
+        let mut pages = vec![];
+        let mut pending = vec![(header.root, 0)];
+        while let Some((page_number, depth)) = pending.pop() {
+            pages.push(page_number);
+            if depth == leaf_depth {
+                continue;
+            }
+            let page = mem.get_page(page_number, PageHint::None)?;
+            if page.memory()[0] != BRANCH {
+                return Err(mem.corrupted_page(&page));
+            }
+            let accessor = BranchAccessor::new(&page, K::fixed_width());
+            for i in 0..accessor.count_children() {
+                pending.push((accessor.child_page(i).unwrap(), depth + 1));
+            }
+        }
+
+        let mut freed_pages = self.freed_pages.lock().unwrap();
+        let mut allocated_pages = self.allocated_pages.lock().unwrap();
+        for page in pages {
+            if !self
+                .page_allocator
+                .free_if_uncommitted(page, &mut allocated_pages)
+            {
+                freed_pages.push(page);
+            }
+        }
+        self.root = None;
+        Ok(())
+    }
+
     pub(crate) fn remove(&mut self, key: &K::SelfType<'_>) -> Result<Option<AccessGuard<'_, V>>> {
         #[cfg(feature = "logging")]
         trace!("Btree(root={:?}): Deleting {:?}", &self.root, key);
+        self.right_edge = None;
         let mut freed_pages = self.freed_pages.lock().unwrap();
         let mut operation: MutateHelper<'_, '_, K, V> = MutateHelper::new(
             &mut self.root,
@@ -494,6 +826,7 @@ impl<K: Key + 'static, V: Value + 'static> BtreeMut<K, V> {
 
     // Removes and returns the leftmost entry in the tree, if any, in a single tree descent.
     pub(crate) fn pop_first(&mut self) -> Result<Option<(AccessGuard<'_, K>, AccessGuard<'_, V>)>> {
+        self.right_edge = None;
         let mut freed_pages = self.freed_pages.lock().unwrap();
         let mut operation: MutateHelper<'_, '_, K, V> = MutateHelper::new(
             &mut self.root,
@@ -506,6 +839,7 @@ impl<K: Key + 'static, V: Value + 'static> BtreeMut<K, V> {
 
     // Removes and returns the rightmost entry in the tree, if any, in a single tree descent.
     pub(crate) fn pop_last(&mut self) -> Result<Option<(AccessGuard<'_, K>, AccessGuard<'_, V>)>> {
+        self.right_edge = None;
         let mut freed_pages = self.freed_pages.lock().unwrap();
         let mut operation: MutateHelper<'_, '_, K, V> = MutateHelper::new(
             &mut self.root,
@@ -544,10 +878,36 @@ impl<K: Key + 'static, V: Value + 'static> BtreeMut<K, V> {
         self.read_tree()?.get(key)
     }
 
+    pub(crate) fn range_mut<'a, KR>(
+        &mut self,
+        range: &impl RangeBounds<KR>,
+    ) -> Result<RangeMut<'_, K, V>>
+    where
+        KR: Borrow<K::SelfType<'a>>,
+    {
+        self.right_edge = None;
+        let to_bytes =
+            |bound: Bound<&KR>| bound.map(|key| K::as_bytes(key.borrow()).as_ref().to_vec());
+        RangeMut::new(
+            &mut self.root,
+            self.page_allocator.clone(),
+            self.freed_pages.clone(),
+            self.allocated_pages.clone(),
+            to_bytes(range.start_bound()),
+            to_bytes(range.end_bound()),
+        )
+    }
+
     pub(crate) fn get_mut(
         &mut self,
         key: &K::SelfType<'_>,
     ) -> Result<Option<AccessGuardMut<'_, V>>> {
+        let page_allocator = self.page_allocator.clone();
+        page_allocator.poison_on_error(self.get_mut_inner(key))
+    }
+
+    fn get_mut_inner(&mut self, key: &K::SelfType<'_>) -> Result<Option<AccessGuardMut<'_, V>>> {
+        self.right_edge = None;
         if let Some(ref mut root) = self.root {
             let key_bytes = K::as_bytes(key);
             let query = key_bytes.as_ref();
@@ -634,7 +994,7 @@ impl<K: Key + 'static, V: Value + 'static> BtreeMut<K, V> {
                 };
                 self.get_mut_helper(Some((page, child_index)), child_page_mut, query)
             }
-            _ => unreachable!(),
+            _ => Err(self.page_allocator.corrupted_page(&page)),
         }
     }
 
@@ -660,12 +1020,54 @@ impl<K: Key + 'static, V: Value + 'static> BtreeMut<K, V> {
         self.read_tree()?.range(range)
     }
 
+    pub(crate) fn cursor(&self) -> Result<BtreeCursor<K, V>> {
+        Ok(self.read_tree()?.cursor())
+    }
+
+    pub(crate) fn estimate_range<
+        'a0,
+        T: RangeBounds<KR> + 'a0,
+        KR: Borrow<K::SelfType<'a0>> + 'a0,
+    >(
+        &self,
+        range: &'_ T,
+    ) -> Result<(u64, u64)>
+    where
+        K: 'a0,
+    {
+        self.read_tree()?.estimate_range(range)
+    }
+
+    pub(crate) fn get_value_range(
+        &self,
+        key: &K::SelfType<'_>,
+        range: Range<usize>,
+    ) -> Result<Option<Vec<u8>>> {
+        get_value_range::<K, V>(
+            self.get_root().map(|x| x.root),
+            K::as_bytes(key).as_ref(),
+            range,
+            &self.page_allocator.resolver(),
+            PageHint::None,
+        )
+    }
+
+    pub(crate) fn range_len<'a0, T: RangeBounds<KR> + 'a0, KR: Borrow<K::SelfType<'a0>> + 'a0>(
+        &self,
+        range: &'_ T,
+    ) -> Result<u64>
+    where
+        K: 'a0,
+    {
+        self.read_tree()?.range_len(range)
+    }
+
     pub(crate) fn extract_from_if<
         'a,
         'a0,
         T: RangeBounds<KR> + 'a0,
         KR: Borrow<K::SelfType<'a0>> + 'a0,
-        F: for<'f> FnMut(K::SelfType<'f>, V::SelfType<'f>) -> bool,
+        F: ExtractPredicate<K, V>,
     >(
         &'a mut self,
         range: &'_ T,
@@ -674,6 +1076,7 @@ impl<K: Key + 'static, V: Value + 'static> BtreeMut<K, V> {
     where
         K: 'a0,
     {
+        self.right_edge = None;
         let iter = self.range(range)?;
 
         let result = BtreeExtractIf::new(
@@ -696,6 +1099,7 @@ impl<K: Key + 'static, V: Value + 'static> BtreeMut<K, V> {
     where
         KR: Borrow<K::SelfType<'a>> + 'a,
     {
+        self.right_edge = None;
         let mut freed = vec![];
         let mut operation: MutateHelper<'_, '_, K, V> = MutateHelper::new(
             &mut self.root,
@@ -729,6 +1133,7 @@ impl<K: Key + 'static, V: MutInPlaceValue + 'static> BtreeMut<K, V> {
             "Btree(root={:?}): Inserting {:?} with {} reserved bytes for the value",
             &self.root, key, value_length
         );
+        self.right_edge = None;
         let mut freed_pages = self.freed_pages.lock().unwrap();
         let mut value = vec![0u8; value_length];
         V::initialize(&mut value);
@@ -739,10 +1144,154 @@ impl<K: Key + 'static, V: MutInPlaceValue + 'static> BtreeMut<K, V> {
             self.allocated_pages.clone(),
         );
         let (_, guard) = operation.insert(key, &V::from_bytes(&value))?;
-        Ok(guard)
+        Ok(guard.unwrap())
     }
 }
 
+// Iterates over the entries of a RawBtree, in key order. Yields the leaf page holding each entry,
+// and the ranges of its key and value in the page
+pub(crate) struct RawBtreeIter {
+    mem: PageResolver,
+    hint: PageHint,
+    fixed_key_size: Option<usize>,
+    fixed_value_size: Option<usize>,
+    // Branch pages from the root to the current leaf, and the index of the next child to visit in
+    // each
+    path: Vec<(PageImpl, usize)>,
+    // The current leaf, and the index of the next entry in it
+    leaf: Option<(PageImpl, usize)>,
+}
+
+impl RawBtreeIter {
+    fn enter(&mut self, page_number: PageNumber) -> Result {
+        let page = self.mem.get_page(page_number, self.hint)?;
+        match page.memory()[0] {
+            LEAF => self.leaf = Some((page, 0)),
+            BRANCH => self.path.push((page, 0)),
+            _ => return Err(self.mem.corrupted_page(&page)),
+        }
+        Ok(())
+    }
+}
+
+impl Iterator for RawBtreeIter {
+    type Item = Result<(PageImpl, Range<usize>, Range<usize>)>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            if let Some((page, entry)) = self.leaf.as_mut() {
+                let accessor =
+                    LeafAccessor::new(page.memory(), self.fixed_key_size, self.fixed_value_size);
+                if let Some((key, value)) = accessor.entry_ranges(*entry) {
+                    *entry += 1;
+                    return Some(Ok((page.clone(), key, value)));
+                }
+                self.leaf = None;
+            }
+            let (branch, child) = self.path.last_mut()?;
+            let accessor = BranchAccessor::new(branch, self.fixed_key_size);
+            if *child >= accessor.count_children() {
+                self.path.pop();
+                continue;
+            }
+            let child_page = accessor.child_page(*child).unwrap();
+            *child += 1;
+            if let Err(err) = self.enter(child_page) {
+                self.path.clear();
+                return Some(Err(err));
+            }
+        }
+    }
+}
+
+// An entry which differs between two versions of a tree
+pub(crate) struct EntryDiff {
+    pub(crate) key: Vec<u8>,
+    pub(crate) old_value: Option<Vec<u8>>,
+    pub(crate) new_value: Option<Vec<u8>>,
+}
+
+// Returns the entries which differ between two versions of a tree. Changed entries come first, in
+// the key order of `new`, followed by removed entries, in the key order of `old`.
+//
+// Committed pages are never modified, so a committed page which is reachable from both roots has
+// the same contents in both. Only the pages written since the last commit, as reported by
+// `uncommitted`, and the pages of `old` which they replaced, need to be read. A committed page
+// reachable from `new` but not from `old`, such as one restored from a savepoint, is read in full
+pub(crate) fn diff_btrees(
+    old: &RawBtree,
+    new: &RawBtree,
+    uncommitted: impl Fn(PageNumber) -> bool,
+) -> Result<Vec<EntryDiff>> {
+    let mut new_entries = vec![];
+    let mut shared = HashSet::new();
+    if let Some(root) = new.root {
+        new.collect_entries(
+            root.root,
+            &mut |page| {
+                if uncommitted(page) {
+                    true
+                } else {
+                    shared.insert(page);
+                    false
+                }
+            },
+            &mut new_entries,
+        )?;
+    }
+
+    let mut old_entries = vec![];
+    let mut found = HashSet::new();
+    if let Some(root) = old.root {
+        old.collect_entries(
+            root.root,
+            &mut |page| {
+                if shared.contains(&page) {
+                    found.insert(page);
+                    false
+                } else {
+                    true
+                }
+            },
+            &mut old_entries,
+        )?;
+    }
+    for page in shared {
+        if !found.contains(&page) {
+            new.collect_entries(page, &mut |_| true, &mut new_entries)?;
+        }
+    }
+
+    let mut old_values: HashMap<Vec<u8>, Vec<u8>> = HashMap::new();
+    let mut old_order = vec![];
+    for (key, value) in old_entries {
+        old_order.push(key.clone());
+        old_values.insert(key, value);
+    }
+    let mut result = vec![];
+    for (key, value) in new_entries {
+        let old_value = old_values.remove(&key);
+        if old_value.as_ref() != Some(&value) {
+            result.push(EntryDiff {
+                key,
+                old_value,
+                new_value: Some(value),
+            });
+        }
+    }
+    for key in old_order {
+        if let Some(value) = old_values.remove(&key) {
+            result.push(EntryDiff {
+                key,
+                old_value: Some(value),
+                new_value: None,
+            });
+        }
+    }
+
+    Ok(result)
+}
+
 pub(crate) struct RawBtree {
     mem: PageResolver,
     root: Option<BtreeHeader>,
@@ -772,6 +1321,39 @@ impl RawBtree {
         self.root
     }
 
+    // Appends the entries of the leaves reachable from `page_number` to `output`, in key order,
+    // descending only into the pages for which `descend` returns true
+    fn collect_entries(
+        &self,
+        page_number: PageNumber,
+        descend: &mut impl FnMut(PageNumber) -> bool,
+        output: &mut Vec<(Vec<u8>, Vec<u8>)>,
+    ) -> Result {
+        if !descend(page_number) {
+            return Ok(());
+        }
+        let page = self.mem.get_page(page_number, self.hint)?;
+        match page.memory()[0] {
+            LEAF => {
+                let accessor =
+                    LeafAccessor::new(page.memory(), self.fixed_key_size, self.fixed_value_size);
+                for i in 0..accessor.num_pairs() {
+                    let entry = accessor.entry(i).unwrap();
+                    output.push((entry.key().to_vec(), entry.value().to_vec()));
+                }
+            }
+            BRANCH => {
+                let accessor = BranchAccessor::new(&page, self.fixed_key_size);
+                for i in 0..accessor.count_children() {
+                    let child = accessor.child_page(i).unwrap();
+                    self.collect_entries(child, descend, output)?;
+                }
+            }
+            _ => return Err(self.mem.corrupted_page(&page)),
+        }
+        Ok(())
+    }
+
     pub(crate) fn stats(&self) -> Result<BtreeStats> {
         btree_stats(
             self.root.map(|x| x.root),
@@ -833,6 +1415,184 @@ impl RawBtree {
             _ => false,
         })
     }
+
+    pub(crate) fn iter(&self) -> Result<RawBtreeIter> {
+        let mut iter = RawBtreeIter {
+            mem: self.mem.clone(),
+            hint: self.hint,
+            fixed_key_size: self.fixed_key_size,
+            fixed_value_size: self.fixed_value_size,
+            path: vec![],
+            leaf: None,
+        };
+        if let Some(header) = self.root {
+            iter.enter(header.root)?;
+        }
+        Ok(iter)
+    }
+
+    // Calls the visitor with the key and value of every entry, in key order
+    pub(crate) fn visit_entries(&self, visitor: &mut impl FnMut(&[u8], &[u8])) -> Result {
+        if let Some(header) = self.root {
+            self.visit_entries_helper(header.root, visitor)?;
+        }
+        Ok(())
+    }
+
+    fn visit_entries_helper(
+        &self,
+        page_number: PageNumber,
+        visitor: &mut impl FnMut(&[u8], &[u8]),
+    ) -> Result {
+        let page = self.mem.get_page(page_number, self.hint)?;
+        match page.memory()[0] {
+            LEAF => {
+                let accessor =
+                    LeafAccessor::new(page.memory(), self.fixed_key_size, self.fixed_value_size);
+                for i in 0..accessor.num_pairs() {
+                    let entry = accessor.entry(i).unwrap();
+                    visitor(entry.key(), entry.value());
+                }
+            }
+            BRANCH => {
+                let accessor = BranchAccessor::new(&page, self.fixed_key_size);
+                for i in 0..accessor.count_children() {
+                    self.visit_entries_helper(accessor.child_page(i).unwrap(), visitor)?;
+                }
+            }
+            _ => {
+                return Err(self.mem.corrupted_page(&page));
+            }
+        }
+        Ok(())
+    }
+
+    // Appends every page whose checksum does not match the checksum recorded for it by its parent.
+    // The children of a corrupted branch page are not visited
+    pub(crate) fn find_corrupted_pages(&self, corrupted: &mut Vec<PageNumber>) -> Result {
+        if let Some(header) = self.root {
+            self.find_corrupted_pages_helper(header.root, header.checksum, corrupted)?;
+        }
+        Ok(())
+    }
+
+    fn find_corrupted_pages_helper(
+        &self,
+        page_number: PageNumber,
+        expected_checksum: Checksum,
+        corrupted: &mut Vec<PageNumber>,
+    ) -> Result {
+        let page = match self.mem.get_page(page_number, self.hint) {
+            Ok(page) => page,
+            // The page is in a region that has already been quarantined
+            Err(StorageError::Corrupted(_)) => {
+                corrupted.push(page_number);
+                return Ok(());
+            }
+            Err(err) => {
+                return Err(err);
+            }
+        };
+        match page.memory()[0] {
+            LEAF => {
+                if leaf_checksum(&page, self.fixed_key_size, self.fixed_value_size).ok()
+                    != Some(expected_checksum)
+                {
+                    corrupted.push(page_number);
+                }
+            }
+            BRANCH => {
+                if branch_checksum(&page, self.fixed_key_size).ok() != Some(expected_checksum) {
+                    corrupted.push(page_number);
+                    return Ok(());
+                }
+                let accessor = BranchAccessor::new(&page, self.fixed_key_size);
+                for i in 0..accessor.count_children() {
+                    self.find_corrupted_pages_helper(
+                        accessor.child_page(i).unwrap(),
+                        accessor.child_checksum(i).unwrap(),
+                        corrupted,
+                    )?;
+                }
+            }
+            _ => {
+                corrupted.push(page_number);
+            }
+        }
+        Ok(())
+    }
+}
+
+fn clamp_range(range: &Range<usize>, len: usize) -> Range<usize> {
+    let end = range.end.min(len);
+    range.start.min(end)..end
+}
+
+// Returns `range` of the value of `query`, truncated to the length of the value. Pages larger than
+// a single page, which are leaves holding large values, are read partially: first the keys, and
+// then only the requested bytes of the value
+fn get_value_range<K: Key, V: Value>(
+    root: Option<PageNumber>,
+    query: &[u8],
+    range: Range<usize>,
+    mem: &PageResolver,
+    hint: PageHint,
+) -> Result<Option<Vec<u8>>> {
+    let Some(mut page_number) = root else {
+        return Ok(None);
+    };
+    let page_size = mem.get_page_size();
+    loop {
+        if page_number.page_order > 0 {
+            let mut header = mem.read_page_range(page_number, 0..page_size, hint)?;
+            if header[0] == LEAF {
+                let num_pairs = LeafAccessor::new(&header, None, None).num_pairs();
+                if num_pairs == 0 {
+                    return Ok(None);
+                }
+                // The header is followed by the end offsets of the keys and values, and then the keys
+                let offsets_end = 4 + 2 * size_of::<u32>() * num_pairs;
+                if offsets_end > header.len() {
+                    header = mem.read_page_range(page_number, 0..offsets_end, hint)?;
+                }
+                let keys_end = LeafAccessor::new(&header, K::fixed_width(), V::fixed_width())
+                    .offset_of_first_value();
+                if keys_end > header.len() {
+                    header = mem.read_page_range(page_number, 0..keys_end, hint)?;
+                }
+                let accessor = LeafAccessor::new(&header, K::fixed_width(), V::fixed_width());
+                let Some(position) = accessor.find_key::<K>(query) else {
+                    return Ok(None);
+                };
+                let (start, end) = accessor.value_range(position).unwrap();
+                let within = clamp_range(&range, end - start);
+                return mem
+                    .read_page_range(
+                        page_number,
+                        (start + within.start)..(start + within.end),
+                        hint,
+                    )
+                    .map(Some);
+            }
+        }
+        let page = mem.get_page(page_number, hint)?;
+        match page.memory()[0] {
+            LEAF => {
+                let accessor = LeafAccessor::new(page.memory(), K::fixed_width(), V::fixed_width());
+                let Some(position) = accessor.find_key::<K>(query) else {
+                    return Ok(None);
+                };
+                let (start, end) = accessor.value_range(position).unwrap();
+                let value = &page.memory()[start..end];
+                return Ok(Some(value[clamp_range(&range, value.len())].to_vec()));
+            }
+            BRANCH => {
+                let accessor = BranchAccessor::new(&page, K::fixed_width());
+                page_number = accessor.child_for_key::<K>(query).1;
+            }
+            _ => return Err(mem.corrupted_page(&page)),
+        }
+    }
 }
 
 pub(crate) struct Btree<K: Key + 'static, V: Value + 'static> {
@@ -892,6 +1652,17 @@ impl<K: Key, V: Value> Btree<K, V> {
         .verify_checksum()
     }
 
+    pub(crate) fn find_corrupted_pages(&self, corrupted: &mut Vec<PageNumber>) -> Result {
+        RawBtree::new(
+            self.get_root(),
+            K::fixed_width(),
+            V::fixed_width(),
+            self.mem.clone(),
+            self.hint,
+        )
+        .find_corrupted_pages(corrupted)
+    }
+
     pub(crate) fn visit_all_pages<F>(&self, visitor: F) -> Result
     where
         F: FnMut(&PagePath) -> Result,
@@ -906,6 +1677,20 @@ impl<K: Key, V: Value> Btree<K, V> {
         tree.visit_all_pages(visitor)
     }
 
+    pub(crate) fn visit_pages_while<F>(&self, visitor: F) -> Result
+    where
+        F: FnMut(&PagePath) -> Result<bool>,
+    {
+        let tree = UntypedBtree::new(
+            self.root,
+            self.mem.clone(),
+            self.hint,
+            K::fixed_width(),
+            V::fixed_width(),
+        );
+        tree.visit_pages_while(visitor)
+    }
+
     pub(crate) fn get(&self, key: &K::SelfType<'_>) -> Result<Option<AccessGuard<'static, V>>> {
         if let Some(ref root_page) = self.cached_root {
             self.get_helper(root_page, K::as_bytes(key).as_ref())
@@ -934,7 +1719,7 @@ impl<K: Key, V: Value> Btree<K, V> {
                 let child_page = self.mem.get_page(child_page, self.hint)?;
                 self.get_helper(&child_page, query)
             }
-            _ => unreachable!(),
+            _ => Err(self.mem.corrupted_page(page)),
         }
     }
 
@@ -966,7 +1751,7 @@ impl<K: Key, V: Value> Btree<K, V> {
                 let child_page = accessor.child_page(0).unwrap();
                 self.first_helper(self.mem.get_page(child_page, self.hint)?)
             }
-            _ => unreachable!(),
+            _ => Err(self.mem.corrupted_page(&page)),
         }
     }
 
@@ -999,7 +1784,7 @@ impl<K: Key, V: Value> Btree<K, V> {
                 let child_page = accessor.child_page(accessor.count_children() - 1).unwrap();
                 self.last_helper(self.mem.get_page(child_page, self.hint)?)
             }
-            _ => unreachable!(),
+            _ => Err(self.mem.corrupted_page(&page)),
         }
     }
 
@@ -1007,11 +1792,44 @@ impl<K: Key, V: Value> Btree<K, V> {
         &self,
         range: &'_ T,
     ) -> Result<BtreeRangeIter<K, V>> {
-        BtreeRangeIter::new(
+        let iter = BtreeRangeIter::new(
             range,
             self.root.map(|x| x.root),
             self.mem.clone(),
             self.hint,
+        )?;
+        Ok(iter.with_table_length(self.root.map_or(0, |x| x.length)))
+    }
+
+    pub(crate) fn cursor(&self) -> BtreeCursor<K, V> {
+        BtreeCursor::new(self.root.map(|x| x.root), self.mem.clone(), self.hint)
+    }
+
+    pub(crate) fn estimate_range<'a0, T: RangeBounds<KR>, KR: Borrow<K::SelfType<'a0>>>(
+        &self,
+        range: &'_ T,
+    ) -> Result<(u64, u64)> {
+        estimate_range::<K, V, KR, T>(range, self.root, &self.mem, self.hint)
+    }
+
+    pub(crate) fn range_len<'a0, T: RangeBounds<KR>, KR: Borrow<K::SelfType<'a0>>>(
+        &self,
+        range: &'_ T,
+    ) -> Result<u64> {
+        range_len::<K, V, KR, T>(range, self.root, &self.mem, self.hint)
+    }
+
+    pub(crate) fn get_value_range(
+        &self,
+        key: &K::SelfType<'_>,
+        range: Range<usize>,
+    ) -> Result<Option<Vec<u8>>> {
+        get_value_range::<K, V>(
+            self.root.map(|x| x.root),
+            K::as_bytes(key).as_ref(),
+            range,
+            &self.mem,
+            self.hint,
         )
     }
 
@@ -1073,7 +1891,7 @@ impl<K: Key, V: Value> Drop for Btree<K, V> {
     }
 }
 
-pub(super) fn btree_stats(
+pub(crate) fn btree_stats(
     root: Option<PageNumber>,
     mem: &PageResolver,
     fixed_key_size: Option<usize>,
@@ -1090,6 +1908,8 @@ pub(super) fn btree_stats(
             stored_leaf_bytes: 0,
             metadata_bytes: 0,
             fragmented_bytes: 0,
+            leaf_fill_histogram: [0; LEAF_FILL_BUCKETS],
+            largest_leaf_pages: 0,
         })
     }
 }
@@ -1116,6 +1936,11 @@ fn stats_helper(
                 stored_leaf_bytes: leaf_bytes.try_into().unwrap(),
                 metadata_bytes: overhead_bytes.try_into().unwrap(),
                 fragmented_bytes,
+                leaf_fill_histogram: leaf_fill_histogram(
+                    accessor.total_length(),
+                    page.memory().len(),
+                ),
+                largest_leaf_pages: 1 << page_number.page_order,
             })
         }
         BRANCH => {
@@ -1126,6 +1951,8 @@ fn stats_helper(
             let mut stored_leaf_bytes = 0;
             let mut metadata_bytes = accessor.total_length() as u64;
             let mut fragmented_bytes = (page.memory().len() - accessor.total_length()) as u64;
+            let mut histogram = [0; LEAF_FILL_BUCKETS];
+            let mut largest_leaf_pages = 0;
             for i in 0..accessor.count_children() {
                 if let Some(child) = accessor.child_page(i) {
                     let stats = stats_helper(child, mem, fixed_key_size, fixed_value_size, hint)?;
@@ -1135,6 +1962,8 @@ fn stats_helper(
                     stored_leaf_bytes += stats.stored_leaf_bytes;
                     metadata_bytes += stats.metadata_bytes;
                     fragmented_bytes += stats.fragmented_bytes;
+                    merge_leaf_fill_histogram(&mut histogram, &stats.leaf_fill_histogram);
+                    largest_leaf_pages = max(largest_leaf_pages, stats.largest_leaf_pages);
                 }
             }
 
@@ -1145,8 +1974,10 @@ fn stats_helper(
                 stored_leaf_bytes,
                 metadata_bytes,
                 fragmented_bytes,
+                leaf_fill_histogram: histogram,
+                largest_leaf_pages,
             })
         }
-        _ => unreachable!(),
+        _ => Err(mem.corrupted_page(&page)),
     }
 }
@@ -1,9 +1,10 @@
 use crate::tree_store::page_store::{Page, PageImpl, PageMut, xxh3_checksum};
 use crate::tree_store::{PageAllocator, PageNumber, PageTrackerPolicy};
-use crate::types::{Key, MutInPlaceValue, Value};
-use crate::{Result, StorageError};
+use crate::types::{Key, MutInPlaceValue, TryValue, Value};
+use crate::{DecodeError, Result, StorageError};
 use std::borrow::Borrow;
 use std::cmp::Ordering;
+use std::io;
 use std::marker::PhantomData;
 use std::mem::size_of;
 use std::ops::Range;
@@ -98,6 +99,12 @@ impl BtreeHeader {
         PageNumber::serialized_size() + size_of::<Checksum>() + size_of::<u64>()
     }
 
+    // The checksum has not been computed yet, because the tree was modified by a transaction which
+    // is uncommitted, or was committed without durability
+    pub(crate) fn checksum_deferred(&self) -> bool {
+        self.checksum == DEFERRED
+    }
+
     pub(crate) fn from_le_bytes(bytes: [u8; Self::serialized_size()]) -> Self {
         let root =
             PageNumber::from_le_bytes(bytes[..PageNumber::serialized_size()].try_into().unwrap());
@@ -227,6 +234,33 @@ impl<'a, V: Value + 'static> AccessGuard<'a, V> {
         V::from_bytes(&self.page.memory()[self.offset..(self.offset + self.len)])
     }
 
+    /// Access the stored value, or return an error if it can't be decoded
+    pub fn try_value(&self) -> Result<V::SelfType<'_>, DecodeError>
+    where
+        V: TryValue,
+    {
+        V::try_from_bytes(&self.page.memory()[self.offset..(self.offset + self.len)])
+    }
+
+    /// Returns a reader over the stored bytes
+    ///
+    /// The value has already been read into memory. This allows it to be passed to APIs which take
+    /// an [`io::Read`], such as [`std::io::copy`], without copying it first
+    pub fn as_reader(&self) -> io::Cursor<&[u8]> {
+        io::Cursor::new(&self.page.memory()[self.offset..(self.offset + self.len)])
+    }
+
+    /// Returns the stored bytes as a [`bytes::Bytes`]
+    ///
+    /// For values read from the database file, the result shares the page that holds them, so no
+    /// data is copied. The page stays in memory until the result is dropped, even after the
+    /// transaction is closed
+    #[cfg(feature = "bytes")]
+    pub fn to_bytes(&self) -> bytes::Bytes {
+        let (page, range) = self.arc_view();
+        bytes::Bytes::from_owner(page).slice(range)
+    }
+
     pub(crate) fn arc_view(&self) -> (Arc<[u8]>, Range<usize>) {
         match &self.page {
             EitherPage::Immutable(page) => (page.to_arc(), self.offset..(self.offset + self.len)),
@@ -345,7 +379,7 @@ impl<'a, V: Value + 'static> AccessGuardMut<'a, V> {
                 }
             }
 
-            let new_page = builder.build()?;
+            let new_page = self.page_allocator.poison_on_error(builder.build())?;
 
             // Update parent branch page if it exists, otherwise update root
             if let Some((ref mut parent_page, parent_entry_index)) = self.parent {
@@ -376,6 +410,12 @@ impl<'a, V: Value + 'static> AccessGuardMut<'a, V> {
     }
 }
 
+impl<V: MutInPlaceValue + 'static> AsMut<V::BaseRefType> for AccessGuardMut<'_, V> {
+    fn as_mut(&mut self) -> &mut V::BaseRefType {
+        V::from_bytes_mut(&mut self.page.memory_mut()[self.offset..(self.offset + self.len)])
+    }
+}
+
 impl<V: Value + 'static> Drop for AccessGuardMut<'_, V> {
     fn drop(&mut self) {
         // no-op. This Drop impl is only here to ensure that self is dropped before the transaction
@@ -583,6 +623,10 @@ impl<'a> LeafAccessor<'a> {
         Some((self.value_start(n)?, self.value_end(n)?))
     }
 
+    pub(crate) fn key_range(&self, n: usize) -> Option<Range<usize>> {
+        Some(self.key_start(n)?..self.key_end(n)?)
+    }
+
     // Returns the length of all keys and values between [start, end)
     pub(crate) fn length_of_pairs(&self, start: usize, end: usize) -> usize {
         self.length_of_values(start, end) + self.length_of_keys(start, end)
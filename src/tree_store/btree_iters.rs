@@ -291,7 +291,7 @@ impl RangeIterState {
                         parent,
                     }))
                 }
-                _ => unreachable!(),
+                _ => Err(manager.corrupted_page(&page)),
             },
             Leaf {
                 page,
@@ -481,6 +481,43 @@ impl RangeIterState {
             Enter { .. } | BranchChild { .. } | Exit { .. } => None,
         }
     }
+
+    // Returns the page and range of the current entry's key, without locating its value
+    fn get_key(&self) -> Option<(PageImpl, Range<usize>)> {
+        match self {
+            Leaf {
+                page,
+                fixed_key_size,
+                fixed_value_size,
+                entry,
+                ..
+            } => {
+                let key = LeafAccessor::new(page.memory(), *fixed_key_size, *fixed_value_size)
+                    .key_range(*entry)?;
+                Some((page.clone(), key))
+            }
+            Enter { .. } | BranchChild { .. } | Exit { .. } => None,
+        }
+    }
+
+    // Returns the page and range of the current entry's value, without locating its key
+    fn get_value(&self) -> Option<(PageImpl, Range<usize>)> {
+        match self {
+            Leaf {
+                page,
+                fixed_key_size,
+                fixed_value_size,
+                entry,
+                ..
+            } => {
+                let (start, end) =
+                    LeafAccessor::new(page.memory(), *fixed_key_size, *fixed_value_size)
+                        .value_range(*entry)?;
+                Some((page.clone(), start..end))
+            }
+            Enter { .. } | BranchChild { .. } | Exit { .. } => None,
+        }
+    }
 }
 
 pub(crate) struct EntryGuard<K: Key, V: Value> {
@@ -506,6 +543,10 @@ impl<K: Key, V: Value> EntryGuard<K, V> {
         self.page.memory()[self.key_range.clone()].to_vec()
     }
 
+    pub(crate) fn value_data(&self) -> Vec<u8> {
+        self.page.memory()[self.value_range.clone()].to_vec()
+    }
+
     pub(crate) fn key(&self) -> K::SelfType<'_> {
         K::from_bytes(&self.page.memory()[self.key_range.clone()])
     }
@@ -570,7 +611,7 @@ impl Iterator for AllPageNumbersBtreeIter {
                             .num_pairs()
                             == 0
                     }
-                    _ => unreachable!(),
+                    _ => return Some(Err(self.manager.corrupted_page(page))),
                 },
                 Leaf { entry, .. } => *entry == 0,
                 BranchChild { .. } | Exit { .. } => false,
@@ -608,6 +649,10 @@ pub(crate) struct BtreeRangeIter<K: Key + 'static, V: Value + 'static> {
     // extra state-machine step past the last yielded entry.
     include_left: bool,  // left is inclusive, instead of exclusive
     include_right: bool, // right is inclusive, instead of exclusive
+    // Upper bound on the number of entries not yet returned, if the length of the table is known
+    remaining: Option<u64>,
+    // Whether `remaining` is exact, because the range covers the whole table
+    exact: bool,
     manager: PageResolver,
     hint: PageHint,
     _key_type: PhantomData<K>,
@@ -636,6 +681,184 @@ fn range_is_empty<'a, K: Key + 'static, KR: Borrow<K::SelfType<'a>>, T: RangeBou
     }
 }
 
+// Returns the number of entries in the range. Only the leaves which contain the ends of the range are
+// searched. The other leaves in it are counted from their headers
+pub(crate) fn range_len<
+    'a,
+    K: Key + 'static,
+    V: Value + 'static,
+    KR: Borrow<K::SelfType<'a>>,
+    T: RangeBounds<KR>,
+>(
+    query_range: &T,
+    table_root: Option<BtreeHeader>,
+    manager: &PageResolver,
+    hint: PageHint,
+) -> Result<u64> {
+    let Some(header) = table_root else {
+        return Ok(0);
+    };
+    if range_is_empty::<K, KR, T>(query_range) {
+        return Ok(0);
+    }
+    let left_bound = query_range
+        .start_bound()
+        .map(|k| K::as_bytes(k.borrow()).as_ref().to_vec());
+    let right_bound = query_range
+        .end_bound()
+        .map(|k| K::as_bytes(k.borrow()).as_ref().to_vec());
+    if matches!((&left_bound, &right_bound), (Unbounded, Unbounded)) {
+        return Ok(header.length);
+    }
+    subtree_range_len::<K, V>(
+        header.root,
+        left_bound.as_ref().map(Vec::as_slice),
+        right_bound.as_ref().map(Vec::as_slice),
+        manager,
+        hint,
+    )
+}
+
+// Returns an estimate of the number of entries in the range, and of the bytes of their keys and
+// values. Only the pages on the paths from the root to the ends of the range are read. The position
+// of each end is estimated by assuming that every subtree of a branch holds the same number of
+// entries, and the size of an entry by averaging the entries in the leaves at the ends
+pub(crate) fn estimate_range<
+    'a,
+    K: Key + 'static,
+    V: Value + 'static,
+    KR: Borrow<K::SelfType<'a>>,
+    T: RangeBounds<KR>,
+>(
+    query_range: &T,
+    table_root: Option<BtreeHeader>,
+    manager: &PageResolver,
+    hint: PageHint,
+) -> Result<(u64, u64)> {
+    let Some(header) = table_root else {
+        return Ok((0, 0));
+    };
+    if header.length == 0 || range_is_empty::<K, KR, T>(query_range) {
+        return Ok((0, 0));
+    }
+    let left_bound = query_range
+        .start_bound()
+        .map(|k| K::as_bytes(k.borrow()).as_ref().to_vec());
+    let right_bound = query_range
+        .end_bound()
+        .map(|k| K::as_bytes(k.borrow()).as_ref().to_vec());
+    let (start, start_leaf) = estimate_position::<K, V>(
+        header.root,
+        left_bound.as_ref().map(Vec::as_slice),
+        false,
+        manager,
+        hint,
+    )?;
+    let (end, end_leaf) = estimate_position::<K, V>(
+        header.root,
+        right_bound.as_ref().map(Vec::as_slice),
+        true,
+        manager,
+        hint,
+    )?;
+    let entries = scale_estimate(end - start, header.length);
+    let (leaf_bytes, leaf_entries) = (start_leaf.0 + end_leaf.0, start_leaf.1 + end_leaf.1);
+    let bytes = if leaf_entries == 0 {
+        0
+    } else {
+        entries * leaf_bytes / leaf_entries
+    };
+    Ok((entries, bytes))
+}
+
+#[allow(
+    clippy::cast_precision_loss,
+    clippy::cast_possible_truncation,
+    clippy::cast_sign_loss
+)]
+fn scale_estimate(fraction: f64, length: u64) -> u64 {
+    ((fraction.clamp(0.0, 1.0) * length as f64).round() as u64).min(length)
+}
+
+// Returns the fraction of the entries in the tree which are before the bound, and the bytes of the
+// keys and values in the leaf it is in, and the number of entries in that leaf
+#[allow(clippy::cast_precision_loss)]
+fn estimate_position<K: Key, V: Value>(
+    root: PageNumber,
+    bound: Bound<&[u8]>,
+    end: bool,
+    manager: &PageResolver,
+    hint: PageHint,
+) -> Result<(f64, (u64, u64))> {
+    let mut position = 0.0;
+    let mut scale = 1.0;
+    let mut page = manager.get_page(root, hint)?;
+    loop {
+        match page.memory()[0] {
+            LEAF => {
+                let accessor = LeafAccessor::new(page.memory(), K::fixed_width(), V::fixed_width());
+                let num_pairs = accessor.num_pairs();
+                if num_pairs > 0 {
+                    let entry = if end {
+                        upper_bound_entry::<K>(&accessor, bound)
+                    } else {
+                        lower_bound_entry::<K>(&accessor, bound)
+                    };
+                    position += scale * entry as f64 / num_pairs as f64;
+                }
+                let bytes = accessor.length_of_pairs(0, num_pairs);
+                return Ok((position, (bytes as u64, num_pairs as u64)));
+            }
+            BRANCH => {
+                let accessor = BranchAccessor::new(&page, K::fixed_width());
+                let children = accessor.count_children() as f64;
+                let child = child_to_visit::<K>(&accessor, bound, end);
+                position += scale * child as f64 / children;
+                scale /= children;
+                let child_page = accessor.child_page(child).unwrap();
+                page = manager.get_page(child_page, hint)?;
+            }
+            _ => return Err(manager.corrupted_page(&page)),
+        }
+    }
+}
+
+fn subtree_range_len<K: Key, V: Value>(
+    page_number: PageNumber,
+    left_bound: Bound<&[u8]>,
+    right_bound: Bound<&[u8]>,
+    manager: &PageResolver,
+    hint: PageHint,
+) -> Result<u64> {
+    let page = manager.get_page(page_number, hint)?;
+    match page.memory()[0] {
+        LEAF => {
+            let accessor = LeafAccessor::new(page.memory(), K::fixed_width(), V::fixed_width());
+            let entries = leaf_entries::<K>(&accessor, left_bound, right_bound);
+            Ok(entries.len() as u64)
+        }
+        BRANCH => {
+            let accessor = BranchAccessor::new(&page, K::fixed_width());
+            let first = child_to_visit::<K>(&accessor, left_bound, false);
+            let last = child_to_visit::<K>(&accessor, right_bound, true);
+            let mut len = 0;
+            for i in first..=last {
+                let left = if i == first { left_bound } else { Unbounded };
+                let right = if i == last { right_bound } else { Unbounded };
+                len += subtree_range_len::<K, V>(
+                    accessor.child_page(i).unwrap(),
+                    left,
+                    right,
+                    manager,
+                    hint,
+                )?;
+            }
+            Ok(len)
+        }
+        _ => Err(manager.corrupted_page(&page)),
+    }
+}
+
 impl<K: Key + 'static, V: Value + 'static> BtreeRangeIter<K, V> {
     pub(crate) fn new<'a, T: RangeBounds<KR>, KR: Borrow<K::SelfType<'a>>>(
         query_range: &'_ T,
@@ -679,6 +902,8 @@ impl<K: Key + 'static, V: Value + 'static> BtreeRangeIter<K, V> {
                 right_bound: Unbounded,
                 include_left: false,
                 include_right: false,
+                remaining: None,
+                exact: true,
                 manager,
                 hint,
                 _key_type: PhantomData,
@@ -693,6 +918,7 @@ impl<K: Key + 'static, V: Value + 'static> BtreeRangeIter<K, V> {
             let right_bound = query_range
                 .end_bound()
                 .map(|k| K::as_bytes(k.borrow()).as_ref().to_vec());
+            let exact = matches!((&left_bound, &right_bound), (Unbounded, Unbounded));
             let left = Some(Enter {
                 page: root_page.clone(),
                 fixed_key_size: K::fixed_width(),
@@ -714,6 +940,8 @@ impl<K: Key + 'static, V: Value + 'static> BtreeRangeIter<K, V> {
                 right_bound,
                 include_left: true,
                 include_right: true,
+                remaining: None,
+                exact,
                 manager,
                 hint,
                 _key_type: PhantomData,
@@ -727,6 +955,8 @@ impl<K: Key + 'static, V: Value + 'static> BtreeRangeIter<K, V> {
                 right_bound: Unbounded,
                 include_left: false,
                 include_right: false,
+                remaining: None,
+                exact: true,
                 manager,
                 hint,
                 _key_type: PhantomData,
@@ -910,20 +1140,75 @@ impl<K: Key + 'static, V: Value + 'static> BtreeRangeIter<K, V> {
     }
 }
 
+impl<K: Key, V: Value> BtreeRangeIter<K, V> {
+    // Provides the number of entries in the table, so that size_hint() can bound the number of
+    // entries remaining
+    pub(crate) fn with_table_length(mut self, length: u64) -> Self {
+        self.remaining = Some(if self.left.is_none() { 0 } else { length });
+        self
+    }
+
+    fn step(&mut self) -> Option<Result> {
+        let mut ignore_events = ignore_range_event;
+        let result = self.next_state(&mut ignore_events)?;
+        self.remaining = self.remaining.map(|x| x.saturating_sub(1));
+        Some(result)
+    }
+
+    fn step_back(&mut self) -> Option<Result> {
+        let mut ignore_events = ignore_range_event;
+        let result = self.next_back_state(&mut ignore_events)?;
+        self.remaining = self.remaining.map(|x| x.saturating_sub(1));
+        Some(result)
+    }
+
+    // Like next(), but only locates the key of the entry
+    pub(crate) fn next_key(&mut self) -> Option<Result<(PageImpl, Range<usize>)>> {
+        self.step()
+            .map(|result| result.map(|()| self.left.as_ref().unwrap().get_key().unwrap()))
+    }
+
+    pub(crate) fn next_back_key(&mut self) -> Option<Result<(PageImpl, Range<usize>)>> {
+        self.step_back()
+            .map(|result| result.map(|()| self.right.as_ref().unwrap().get_key().unwrap()))
+    }
+
+    // Like next(), but only locates the value of the entry
+    pub(crate) fn next_value(&mut self) -> Option<Result<(PageImpl, Range<usize>)>> {
+        self.step()
+            .map(|result| result.map(|()| self.left.as_ref().unwrap().get_value().unwrap()))
+    }
+
+    pub(crate) fn next_back_value(&mut self) -> Option<Result<(PageImpl, Range<usize>)>> {
+        self.step_back()
+            .map(|result| result.map(|()| self.right.as_ref().unwrap().get_value().unwrap()))
+    }
+}
+
 impl<K: Key, V: Value> Iterator for BtreeRangeIter<K, V> {
     type Item = Result<EntryGuard<K, V>>;
 
     fn next(&mut self) -> Option<Self::Item> {
-        let mut ignore_events = ignore_range_event;
-        self.next_state(&mut ignore_events)
+        self.step()
             .map(|result| result.map(|()| self.left.as_ref().unwrap().get_entry().unwrap()))
     }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let Some(remaining) = self.remaining else {
+            return (0, None);
+        };
+        let upper = usize::try_from(remaining).ok();
+        if self.exact {
+            (upper.unwrap_or(usize::MAX), upper)
+        } else {
+            (0, upper)
+        }
+    }
 }
 
 impl<K: Key, V: Value> DoubleEndedIterator for BtreeRangeIter<K, V> {
     fn next_back(&mut self) -> Option<Self::Item> {
-        let mut ignore_events = ignore_range_event;
-        self.next_back_state(&mut ignore_events)
+        self.step_back()
             .map(|result| result.map(|()| self.right.as_ref().unwrap().get_entry().unwrap()))
     }
 }
@@ -62,15 +62,25 @@ struct InsertionResult<'a, V: Value + 'static> {
     root_checksum: Checksum,
     // Following sibling, if the root had to be split
     additional_sibling: Option<(Vec<u8>, PageNumber, Checksum)>,
-    // The inserted value for .insert_reserve() to use
-    inserted_value: AccessGuardMutInPlace<'a, V>,
+    // The inserted value for .insert_reserve() to use. None if nothing was inserted
+    inserted_value: Option<AccessGuardMutInPlace<'a, V>>,
     // The previous value, if any
     old_value: Option<AccessGuard<'a, V>>,
 }
 
+pub(crate) type MergeFn<'a> = dyn FnMut(Option<&[u8]>, &[u8]) -> Result<Vec<u8>> + 'a;
+
 pub(crate) struct MutateHelper<'a, 'b, K: Key, V: Value> {
     root: &'b mut Option<BtreeHeader>,
     modify_uncommitted: bool,
+    // When a key is appended after the last key of a full leaf, leave the leaf as it is and start a
+    // new one, rather than splitting it in half. Sequential appends then fill every leaf
+    append_split: bool,
+    // Leave the tree unchanged, and return the existing value, if the key is already present
+    only_if_absent: bool,
+    // Called with the existing value of the key, if any, and the value being inserted. The value it
+    // returns is stored instead
+    merge: Option<&'b mut MergeFn<'b>>,
     page_allocator: PageAllocator,
     freed: &'b mut Vec<PageNumber>,
     allocated: Arc<Mutex<PageTrackerPolicy>>,
@@ -89,6 +99,9 @@ impl<'a, 'b, K: Key + 'static, V: Value + 'static> MutateHelper<'a, 'b, K, V> {
         Self {
             root,
             modify_uncommitted: true,
+            append_split: false,
+            only_if_absent: false,
+            merge: None,
             page_allocator,
             freed,
             allocated,
@@ -109,6 +122,9 @@ impl<'a, 'b, K: Key + 'static, V: Value + 'static> MutateHelper<'a, 'b, K, V> {
         Self {
             root,
             modify_uncommitted: false,
+            append_split: false,
+            only_if_absent: false,
+            merge: None,
             page_allocator,
             freed,
             allocated,
@@ -118,6 +134,18 @@ impl<'a, 'b, K: Key + 'static, V: Value + 'static> MutateHelper<'a, 'b, K, V> {
         }
     }
 
+    pub(crate) fn set_append_split(&mut self, append_split: bool) {
+        self.append_split = append_split;
+    }
+
+    pub(crate) fn set_only_if_absent(&mut self, only_if_absent: bool) {
+        self.only_if_absent = only_if_absent;
+    }
+
+    pub(crate) fn set_merge(&mut self, merge: &'b mut MergeFn<'b>) {
+        self.merge = Some(merge);
+    }
+
     fn conditional_free(&mut self, page_number: PageNumber) {
         if self.modify_uncommitted {
             let mut allocated = self.allocated.lock().unwrap();
@@ -152,6 +180,19 @@ impl<'a, 'b, K: Key + 'static, V: Value + 'static> MutateHelper<'a, 'b, K, V> {
     }
 
     pub(crate) fn retain_in_range<'r, KR, F>(
+        &mut self,
+        range: &'_ impl RangeBounds<KR>,
+        predicate: F,
+    ) -> Result
+    where
+        KR: Borrow<K::SelfType<'r>> + 'r,
+        F: for<'f> FnMut(K::SelfType<'f>, V::SelfType<'f>) -> bool,
+    {
+        let result = self.retain_in_range_inner(range, predicate);
+        self.page_allocator.poison_on_error(result)
+    }
+
+    fn retain_in_range_inner<'r, KR, F>(
         &mut self,
         range: &'_ impl RangeBounds<KR>,
         mut predicate: F,
@@ -189,6 +230,15 @@ impl<'a, 'b, K: Key + 'static, V: Value + 'static> MutateHelper<'a, 'b, K, V> {
         &mut self,
         target: DeleteTarget<'_>,
         found_key: &mut Option<AccessGuard<'a, K>>,
+    ) -> Result<Option<AccessGuard<'a, V>>> {
+        let result = self.delete_target_inner(target, found_key);
+        self.page_allocator.poison_on_error(result)
+    }
+
+    fn delete_target_inner(
+        &mut self,
+        target: DeleteTarget<'_>,
+        found_key: &mut Option<AccessGuard<'a, K>>,
     ) -> Result<Option<AccessGuard<'a, V>>> {
         if let Some(BtreeHeader {
             root: p, length, ..
@@ -262,7 +312,23 @@ impl<'a, 'b, K: Key + 'static, V: Value + 'static> MutateHelper<'a, 'b, K, V> {
         &mut self,
         key: &K::SelfType<'_>,
         value: &V::SelfType<'_>,
-    ) -> Result<(Option<AccessGuard<'a, V>>, AccessGuardMutInPlace<'a, V>)> {
+    ) -> Result<(
+        Option<AccessGuard<'a, V>>,
+        Option<AccessGuardMutInPlace<'a, V>>,
+    )> {
+        let result = self.insert_inner(key, value);
+        self.page_allocator.poison_on_error(result)
+    }
+
+    #[allow(clippy::type_complexity)]
+    fn insert_inner(
+        &mut self,
+        key: &K::SelfType<'_>,
+        value: &V::SelfType<'_>,
+    ) -> Result<(
+        Option<AccessGuard<'a, V>>,
+        Option<AccessGuardMutInPlace<'a, V>>,
+    )> {
         let (new_root, old_value, guard) = if let Some(BtreeHeader {
             root: p,
             checksum,
@@ -298,7 +364,13 @@ impl<'a, 'b, K: Key + 'static, V: Value + 'static> MutateHelper<'a, 'b, K, V> {
             let key_bytes = K::as_bytes(key);
             let value_bytes = V::as_bytes(value);
             let key_bytes = key_bytes.as_ref();
-            let value_bytes = value_bytes.as_ref();
+            let merged;
+            let value_bytes = if let Some(merge) = self.merge.as_mut() {
+                merged = merge(None, value_bytes.as_ref())?;
+                merged.as_slice()
+            } else {
+                value_bytes.as_ref()
+            };
             let mut builder = LeafBuilder::new(
                 &self.page_allocator,
                 &self.allocated,
@@ -314,7 +386,7 @@ impl<'a, 'b, K: Key + 'static, V: Value + 'static> MutateHelper<'a, 'b, K, V> {
             let page_num = page.get_page_number();
             let guard = AccessGuardMutInPlace::new(page, offset, value_bytes.len());
 
-            (BtreeHeader::new(page_num, DEFERRED, 1), None, guard)
+            (BtreeHeader::new(page_num, DEFERRED, 1), None, Some(guard))
         };
         *self.root = Some(new_root);
         Ok((old_value, guard))
@@ -333,6 +405,30 @@ impl<'a, 'b, K: Key + 'static, V: Value + 'static> MutateHelper<'a, 'b, K, V> {
                 let accessor = LeafAccessor::new(page.memory(), K::fixed_width(), V::fixed_width());
                 let (position, found) = accessor.position::<K>(key);
 
+                let merged;
+                let value = if let Some(merge) = self.merge.as_mut() {
+                    let existing = if found {
+                        Some(accessor.entry(position).unwrap().value())
+                    } else {
+                        None
+                    };
+                    merged = merge(existing, value)?;
+                    merged.as_slice()
+                } else {
+                    value
+                };
+
+                if found && self.only_if_absent {
+                    let (start, end) = accessor.value_range(position).unwrap();
+                    return Ok(InsertionResult {
+                        new_root: page.get_page_number(),
+                        root_checksum: page_checksum,
+                        additional_sibling: None,
+                        inserted_value: None,
+                        old_value: Some(AccessGuard::with_page(page, start..end)),
+                    });
+                }
+
                 // Fast-path to avoid re-building and splitting pages with a single large value
                 let single_large_value = accessor.num_pairs() == 1
                     && accessor.total_length() >= self.page_allocator.get_page_size();
@@ -360,7 +456,7 @@ impl<'a, 'b, K: Key + 'static, V: Value + 'static> MutateHelper<'a, 'b, K, V> {
                                 page.get_page_number(),
                                 page_checksum,
                             )),
-                            inserted_value: guard,
+                            inserted_value: Some(guard),
                             old_value: None,
                         })
                     } else {
@@ -369,7 +465,7 @@ impl<'a, 'b, K: Key + 'static, V: Value + 'static> MutateHelper<'a, 'b, K, V> {
                             new_root: page.get_page_number(),
                             root_checksum: page_checksum,
                             additional_sibling: Some((split_key, new_page_number, DEFERRED)),
-                            inserted_value: guard,
+                            inserted_value: Some(guard),
                             old_value: None,
                         })
                     };
@@ -424,7 +520,7 @@ impl<'a, 'b, K: Key + 'static, V: Value + 'static> MutateHelper<'a, 'b, K, V> {
                         new_root: page_number,
                         root_checksum: DEFERRED,
                         additional_sibling: None,
-                        inserted_value: guard,
+                        inserted_value: Some(guard),
                         old_value: existing_value,
                     });
                 }
@@ -448,6 +544,35 @@ impl<'a, 'b, K: Key + 'static, V: Value + 'static> MutateHelper<'a, 'b, K, V> {
                 if accessor.num_pairs() == position {
                     builder.push(key, value);
                 }
+                if self.append_split
+                    && !found
+                    && position == accessor.num_pairs()
+                    && builder.should_split()
+                {
+                    drop(builder);
+                    let mut builder = LeafBuilder::new(
+                        &self.page_allocator,
+                        &self.allocated,
+                        1,
+                        K::fixed_width(),
+                        V::fixed_width(),
+                    );
+                    builder.push(key, value);
+                    let new_page = builder.build()?;
+                    let new_page_number = new_page.get_page_number();
+                    let new_page_accessor =
+                        LeafAccessor::new(new_page.memory(), K::fixed_width(), V::fixed_width());
+                    let offset = new_page_accessor.offset_of_first_value();
+                    let guard = AccessGuardMutInPlace::new(new_page, offset, value.len());
+                    let split_key = accessor.last_entry().key().to_vec();
+                    return Ok(InsertionResult {
+                        new_root: page.get_page_number(),
+                        root_checksum: page_checksum,
+                        additional_sibling: Some((split_key, new_page_number, DEFERRED)),
+                        inserted_value: Some(guard),
+                        old_value: None,
+                    });
+                }
                 if !builder.should_split() {
                     let new_page = builder.build()?;
 
@@ -480,7 +605,7 @@ impl<'a, 'b, K: Key + 'static, V: Value + 'static> MutateHelper<'a, 'b, K, V> {
                         new_root: new_page_number,
                         root_checksum: DEFERRED,
                         additional_sibling: None,
-                        inserted_value: guard,
+                        inserted_value: Some(guard),
                         old_value: existing_value,
                     }
                 } else {
@@ -532,7 +657,7 @@ impl<'a, 'b, K: Key + 'static, V: Value + 'static> MutateHelper<'a, 'b, K, V> {
                         new_root: new_page_number,
                         root_checksum: DEFERRED,
                         additional_sibling: Some((split_key, new_page_number2, DEFERRED)),
-                        inserted_value: guard,
+                        inserted_value: Some(guard),
                         old_value: existing_value,
                     }
                 }
@@ -660,7 +785,7 @@ impl<'a, 'b, K: Key + 'static, V: Value + 'static> MutateHelper<'a, 'b, K, V> {
 
                 result
             }
-            _ => unreachable!(),
+            _ => return Err(self.page_allocator.corrupted_page(&page)),
         })
     }
 
@@ -668,6 +793,15 @@ impl<'a, 'b, K: Key + 'static, V: Value + 'static> MutateHelper<'a, 'b, K, V> {
         &mut self,
         key: &K::SelfType<'_>,
         value: &V::SelfType<'_>,
+    ) -> Result<()> {
+        let result = self.insert_inplace_inner(key, value);
+        self.page_allocator.poison_on_error(result)
+    }
+
+    fn insert_inplace_inner(
+        &mut self,
+        key: &K::SelfType<'_>,
+        value: &V::SelfType<'_>,
     ) -> Result<()> {
         assert!(self.modify_uncommitted);
         let header = self.root.expect("Key not found (tree is empty)");
@@ -706,7 +840,7 @@ impl<'a, 'b, K: Key + 'static, V: Value + 'static> MutateHelper<'a, 'b, K, V> {
                 let mut mutator = BranchMutator::new(page.memory_mut());
                 mutator.write_child_page(child_index, child_page, DEFERRED);
             }
-            _ => unreachable!(),
+            _ => return Err(self.page_allocator.corrupted_page(&page)),
         }
 
         Ok(())
@@ -1192,7 +1326,7 @@ impl<'a, 'b, K: Key + 'static, V: Value + 'static> MutateHelper<'a, 'b, K, V> {
         match node_mem[0] {
             LEAF => self.delete_leaf_helper(page, target, found_key),
             BRANCH => self.delete_branch_helper(page, target, found_key),
-            _ => unreachable!(),
+            _ => Err(self.page_allocator.corrupted_page(&page)),
         }
     }
 }
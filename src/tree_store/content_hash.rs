@@ -0,0 +1,36 @@
+use crate::tree_store::page_store::xxh3_checksum;
+
+// Data is hashed in chunks of at least this size. Each chunk starts with the hash of the previous
+// chunk, so the result depends on all the data, without it all being held in memory
+const CHUNK_SIZE: usize = 64 * 1024;
+
+// Hashes a stream of byte strings. The result depends only on the sequence of byte strings, and not
+// on how they are stored, so it can be used to compare the contents of two databases
+pub(crate) struct ContentHasher {
+    buffer: Vec<u8>,
+}
+
+impl ContentHasher {
+    pub(crate) fn new() -> Self {
+        Self {
+            buffer: Vec::with_capacity(CHUNK_SIZE),
+        }
+    }
+
+    // Each byte string is prefixed with its length, so that the boundaries between them are part
+    // of the hash
+    pub(crate) fn write(&mut self, data: &[u8]) {
+        let len: u64 = data.len().try_into().unwrap();
+        self.buffer.extend_from_slice(&len.to_le_bytes());
+        self.buffer.extend_from_slice(data);
+        if self.buffer.len() >= CHUNK_SIZE {
+            let hash = xxh3_checksum(&self.buffer);
+            self.buffer.clear();
+            self.buffer.extend_from_slice(&hash.to_le_bytes());
+        }
+    }
+
+    pub(crate) fn finish(self) -> u128 {
+        xxh3_checksum(&self.buffer)
+    }
+}
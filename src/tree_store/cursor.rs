@@ -0,0 +1,214 @@
+use crate::Result;
+use crate::tree_store::btree_base::{BRANCH, BranchAccessor, LEAF, LeafAccessor};
+use crate::tree_store::page_store::{Page, PageImpl};
+use crate::tree_store::{PageHint, PageNumber, PageResolver};
+use crate::types::{Key, Value};
+use std::marker::PhantomData;
+use std::ops::Range;
+
+// Which entry of a leaf to position at, after descending to it
+#[derive(Copy, Clone)]
+enum Edge {
+    First,
+    Last,
+}
+
+pub(crate) struct BtreeCursor<K: Key + 'static, V: Value + 'static> {
+    root: Option<PageNumber>,
+    manager: PageResolver,
+    hint: PageHint,
+    // Branch pages from the root to the current leaf, and the index of the child taken in each
+    path: Vec<(PageImpl, usize)>,
+    // The current leaf, and the index of the current entry in it. None if the cursor is not
+    // positioned at an entry
+    leaf: Option<(PageImpl, usize)>,
+    _key_type: PhantomData<K>,
+    _value_type: PhantomData<V>,
+}
+
+impl<K: Key + 'static, V: Value + 'static> BtreeCursor<K, V> {
+    pub(crate) fn new(root: Option<PageNumber>, manager: PageResolver, hint: PageHint) -> Self {
+        Self {
+            root,
+            manager,
+            hint,
+            path: vec![],
+            leaf: None,
+            _key_type: PhantomData,
+            _value_type: PhantomData,
+        }
+    }
+
+    // Returns the page containing the current entry, and the ranges of its key and value
+    pub(crate) fn current(&self) -> Option<(PageImpl, Range<usize>, Range<usize>)> {
+        let (page, entry) = self.leaf.as_ref()?;
+        let (key, value) = Self::leaf_accessor(page).entry_ranges(*entry)?;
+        Some((page.clone(), key, value))
+    }
+
+    pub(crate) fn seek_to_first(&mut self) -> Result {
+        self.reset();
+        if let Some(root) = self.root {
+            self.descend(root, Edge::First)?;
+        }
+        Ok(())
+    }
+
+    pub(crate) fn seek_to_last(&mut self) -> Result {
+        self.reset();
+        if let Some(root) = self.root {
+            self.descend(root, Edge::Last)?;
+        }
+        Ok(())
+    }
+
+    // Positions the cursor at the first entry whose key is greater than or equal to `query`
+    pub(crate) fn seek(&mut self, query: &[u8]) -> Result {
+        let Some(page) = self.seek_leaf(query)? else {
+            return Ok(());
+        };
+        let accessor = Self::leaf_accessor(&page);
+        let (position, _) = accessor.position::<K>(query);
+        if position == accessor.num_pairs() {
+            return self.next_leaf();
+        }
+        self.leaf = Some((page, position));
+        Ok(())
+    }
+
+    // Positions the cursor at the last entry whose key is less than or equal to `query`
+    pub(crate) fn seek_for_prev(&mut self, query: &[u8]) -> Result {
+        let Some(page) = self.seek_leaf(query)? else {
+            return Ok(());
+        };
+        let (position, found) = Self::leaf_accessor(&page).position::<K>(query);
+        if found {
+            self.leaf = Some((page, position));
+            Ok(())
+        } else if position > 0 {
+            self.leaf = Some((page, position - 1));
+            Ok(())
+        } else {
+            self.prev_leaf()
+        }
+    }
+
+    // Moves to the next entry. If the cursor is at the last entry, it is left unpositioned
+    pub(crate) fn next(&mut self) -> Result {
+        let Some((page, entry)) = self.leaf.as_mut() else {
+            return Ok(());
+        };
+        *entry += 1;
+        let num_pairs =
+            LeafAccessor::new(page.memory(), K::fixed_width(), V::fixed_width()).num_pairs();
+        if *entry < num_pairs {
+            return Ok(());
+        }
+        self.next_leaf()
+    }
+
+    // Moves to the previous entry. If the cursor is at the first entry, it is left unpositioned
+    pub(crate) fn prev(&mut self) -> Result {
+        let Some((_, entry)) = self.leaf.as_mut() else {
+            return Ok(());
+        };
+        if *entry > 0 {
+            *entry -= 1;
+            return Ok(());
+        }
+        self.prev_leaf()
+    }
+
+    fn reset(&mut self) {
+        self.path.clear();
+        self.leaf = None;
+    }
+
+    fn leaf_accessor(page: &PageImpl) -> LeafAccessor<'_> {
+        LeafAccessor::new(page.memory(), K::fixed_width(), V::fixed_width())
+    }
+
+    // Descends from the root to the leaf which may contain `query`, and records the path to it.
+    // Returns None if the tree is empty
+    fn seek_leaf(&mut self, query: &[u8]) -> Result<Option<PageImpl>> {
+        self.reset();
+        let Some(root) = self.root else {
+            return Ok(None);
+        };
+        let mut page = self.manager.get_page(root, self.hint)?;
+        loop {
+            match page.memory()[0] {
+                LEAF => return Ok(Some(page)),
+                BRANCH => {
+                    let (child_index, child_page) =
+                        BranchAccessor::new(&page, K::fixed_width()).child_for_key::<K>(query);
+                    self.path.push((page, child_index));
+                    page = self.manager.get_page(child_page, self.hint)?;
+                }
+                _ => return Err(self.manager.corrupted_page(&page)),
+            }
+        }
+    }
+
+    // Descends from the given page to its first or last entry, and records the path to it
+    fn descend(&mut self, page_number: PageNumber, edge: Edge) -> Result {
+        let mut page = self.manager.get_page(page_number, self.hint)?;
+        loop {
+            match page.memory()[0] {
+                LEAF => {
+                    let num_pairs = Self::leaf_accessor(&page).num_pairs();
+                    if num_pairs == 0 {
+                        self.leaf = None;
+                        return Ok(());
+                    }
+                    let entry = match edge {
+                        Edge::First => 0,
+                        Edge::Last => num_pairs - 1,
+                    };
+                    self.leaf = Some((page, entry));
+                    return Ok(());
+                }
+                BRANCH => {
+                    let accessor = BranchAccessor::new(&page, K::fixed_width());
+                    let child_index = match edge {
+                        Edge::First => 0,
+                        Edge::Last => accessor.count_children() - 1,
+                    };
+                    let child_page = accessor.child_page(child_index).unwrap();
+                    self.path.push((page, child_index));
+                    page = self.manager.get_page(child_page, self.hint)?;
+                }
+                _ => return Err(self.manager.corrupted_page(&page)),
+            }
+        }
+    }
+
+    // Moves to the first entry of the leaf after the current one
+    fn next_leaf(&mut self) -> Result {
+        self.leaf = None;
+        while let Some((branch, child_index)) = self.path.pop() {
+            let accessor = BranchAccessor::new(&branch, K::fixed_width());
+            if child_index + 1 < accessor.count_children() {
+                let child_page = accessor.child_page(child_index + 1).unwrap();
+                self.path.push((branch, child_index + 1));
+                return self.descend(child_page, Edge::First);
+            }
+        }
+        Ok(())
+    }
+
+    // Moves to the last entry of the leaf before the current one
+    fn prev_leaf(&mut self) -> Result {
+        self.leaf = None;
+        while let Some((branch, child_index)) = self.path.pop() {
+            if child_index > 0 {
+                let child_page = BranchAccessor::new(&branch, K::fixed_width())
+                    .child_page(child_index - 1)
+                    .unwrap();
+                self.path.push((branch, child_index - 1));
+                return self.descend(child_page, Edge::Last);
+            }
+        }
+        Ok(())
+    }
+}
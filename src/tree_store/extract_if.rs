@@ -3,27 +3,52 @@ use crate::tree_store::btree_iters::{BtreeRangeIter, EntryGuard};
 use crate::tree_store::btree_mutator::MutateHelper;
 use crate::tree_store::{BtreeHeader, PageAllocator, PageNumber, PageTrackerPolicy};
 use crate::types::{Key, Value};
+use std::ops::ControlFlow;
 use std::sync::{Arc, Mutex};
 
+pub(crate) trait ExtractPredicate<K: Key, V: Value> {
+    // Returns whether to remove the entry, or Break to end the iteration
+    fn test<'f>(&mut self, key: K::SelfType<'f>, value: V::SelfType<'f>) -> ControlFlow<(), bool>;
+}
+
+pub(crate) struct Filter<F>(pub(crate) F);
+
+impl<K: Key, V: Value, F: for<'f> FnMut(K::SelfType<'f>, V::SelfType<'f>) -> bool>
+    ExtractPredicate<K, V> for Filter<F>
+{
+    fn test<'f>(&mut self, key: K::SelfType<'f>, value: V::SelfType<'f>) -> ControlFlow<(), bool> {
+        ControlFlow::Continue((self.0)(key, value))
+    }
+}
+
+pub(crate) struct Stoppable<F>(pub(crate) F);
+
+impl<K: Key, V: Value, F: for<'f> FnMut(K::SelfType<'f>, V::SelfType<'f>) -> ControlFlow<(), bool>>
+    ExtractPredicate<K, V> for Stoppable<F>
+{
+    fn test<'f>(&mut self, key: K::SelfType<'f>, value: V::SelfType<'f>) -> ControlFlow<(), bool> {
+        (self.0)(key, value)
+    }
+}
+
 pub(crate) struct BtreeExtractIf<
     'a,
     K: Key + 'static,
     V: Value + 'static,
-    F: for<'f> FnMut(K::SelfType<'f>, V::SelfType<'f>) -> bool,
+    F: ExtractPredicate<K, V>,
 > {
     root: &'a mut Option<BtreeHeader>,
     inner: BtreeRangeIter<K, V>,
     predicate: F,
     predicate_running: bool,
+    stopped: bool,
     free_on_drop: Vec<PageNumber>,
     master_free_list: Arc<Mutex<Vec<PageNumber>>>,
     allocated: Arc<Mutex<PageTrackerPolicy>>,
     page_allocator: PageAllocator,
 }
 
-impl<'a, K: Key, V: Value, F: for<'f> FnMut(K::SelfType<'f>, V::SelfType<'f>) -> bool>
-    BtreeExtractIf<'a, K, V, F>
-{
+impl<'a, K: Key, V: Value, F: ExtractPredicate<K, V>> BtreeExtractIf<'a, K, V, F> {
     pub(crate) fn new(
         root: &'a mut Option<BtreeHeader>,
         inner: BtreeRangeIter<K, V>,
@@ -37,6 +62,7 @@ impl<'a, K: Key, V: Value, F: for<'f> FnMut(K::SelfType<'f>, V::SelfType<'f>) ->
             inner,
             predicate,
             predicate_running: false,
+            stopped: false,
             free_on_drop: vec![],
             master_free_list,
             allocated,
@@ -48,24 +74,32 @@ impl<'a, K: Key, V: Value, F: for<'f> FnMut(K::SelfType<'f>, V::SelfType<'f>) ->
         self.predicate_running
     }
 
-    fn predicate_matches(&mut self, entry: &EntryGuard<K, V>) -> bool {
+    // Returns None, and stops the iteration, if the predicate returned Break
+    fn predicate_matches(&mut self, entry: &EntryGuard<K, V>) -> Option<bool> {
         assert!(!self.predicate_running);
         self.predicate_running = true;
-        let result = (self.predicate)(entry.key(), entry.value());
+        let result = self.predicate.test(entry.key(), entry.value());
         self.predicate_running = false;
-        result
+        match result {
+            ControlFlow::Continue(matches) => Some(matches),
+            ControlFlow::Break(()) => {
+                self.stopped = true;
+                None
+            }
+        }
     }
 }
 
-impl<K: Key, V: Value, F: for<'f> FnMut(K::SelfType<'f>, V::SelfType<'f>) -> bool> Iterator
-    for BtreeExtractIf<'_, K, V, F>
-{
+impl<K: Key, V: Value, F: ExtractPredicate<K, V>> Iterator for BtreeExtractIf<'_, K, V, F> {
     type Item = Result<EntryGuard<K, V>>;
 
     fn next(&mut self) -> Option<Self::Item> {
+        if self.stopped {
+            return None;
+        }
         let mut item = self.inner.next();
         while let Some(Ok(ref entry)) = item {
-            if self.predicate_matches(entry) {
+            if self.predicate_matches(entry)? {
                 let mut operation: MutateHelper<'_, '_, K, V> = MutateHelper::new_do_not_modify(
                     self.root,
                     self.page_allocator.clone(),
@@ -88,13 +122,16 @@ impl<K: Key, V: Value, F: for<'f> FnMut(K::SelfType<'f>, V::SelfType<'f>) -> boo
     }
 }
 
-impl<K: Key, V: Value, F: for<'f> FnMut(K::SelfType<'f>, V::SelfType<'f>) -> bool>
-    DoubleEndedIterator for BtreeExtractIf<'_, K, V, F>
+impl<K: Key, V: Value, F: ExtractPredicate<K, V>> DoubleEndedIterator
+    for BtreeExtractIf<'_, K, V, F>
 {
     fn next_back(&mut self) -> Option<Self::Item> {
+        if self.stopped {
+            return None;
+        }
         let mut item = self.inner.next_back();
         while let Some(Ok(ref entry)) = item {
-            if self.predicate_matches(entry) {
+            if self.predicate_matches(entry)? {
                 let mut operation: MutateHelper<'_, '_, K, V> = MutateHelper::new_do_not_modify(
                     self.root,
                     self.page_allocator.clone(),
@@ -117,9 +154,7 @@ impl<K: Key, V: Value, F: for<'f> FnMut(K::SelfType<'f>, V::SelfType<'f>) -> boo
     }
 }
 
-impl<K: Key, V: Value, F: for<'f> FnMut(K::SelfType<'f>, V::SelfType<'f>) -> bool> Drop
-    for BtreeExtractIf<'_, K, V, F>
-{
+impl<K: Key, V: Value, F: ExtractPredicate<K, V>> Drop for BtreeExtractIf<'_, K, V, F> {
     fn drop(&mut self) {
         self.inner.close();
         let mut master_free_list = self.master_free_list.lock().unwrap();
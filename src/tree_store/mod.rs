@@ -2,27 +2,37 @@ mod btree;
 mod btree_base;
 mod btree_iters;
 mod btree_mutator;
+mod content_hash;
+mod cursor;
 mod extract_if;
 mod multimap_btree;
 mod page_store;
+mod range_mut;
 mod retain;
 mod subtree_rebuild;
 mod table_tree;
 mod table_tree_base;
 
-pub(crate) use btree::{Btree, BtreeMut, BtreeStats, RawBtree};
+pub(crate) use btree::{
+    Btree, BtreeMut, BtreeStats, LEAF_FILL_BUCKETS, PagePath, RawBtree, RawBtreeIter, btree_stats,
+    diff_btrees,
+};
 pub(crate) use btree_base::BtreeHeader;
 pub use btree_base::{AccessGuard, AccessGuardMut, AccessGuardMutInPlace};
 pub(crate) use btree_base::{BRANCH, LEAF, LeafAccessor, RawLeafBuilder};
-pub(crate) use btree_iters::{AllPageNumbersBtreeIter, BtreeRangeIter};
-pub(crate) use extract_if::BtreeExtractIf;
+pub(crate) use btree_iters::{AllPageNumbersBtreeIter, BtreeRangeIter, estimate_range, range_len};
+pub(crate) use content_hash::ContentHasher;
+pub(crate) use cursor::BtreeCursor;
+pub(crate) use extract_if::{BtreeExtractIf, ExtractPredicate, Filter, Stoppable};
 pub(crate) use multimap_btree::{DynamicCollection, DynamicCollectionType, multimap_btree_stats};
 pub(crate) use page_store::ReadOnlyBackend;
 pub(crate) use page_store::{
     AllocationPolicy, FILE_FORMAT_VERSION3, MAX_PAIR_LENGTH, MAX_VALUE_LENGTH, PAGE_SIZE, Page,
     PageAllocator, PageHint, PageNumber, PageNumberHashSet, PageResolver, PageTrackerPolicy,
-    SerializedSavepoint, ShrinkPolicy, TransactionalMemory,
+    ReaderTable, SerializedSavepoint, ShrinkPolicy, TransactionalMemory, apply_backup,
+    write_backup,
 };
-pub use page_store::{InMemoryBackend, Savepoint, file_backend};
-pub(crate) use table_tree::{PageListMut, TableTree, TableTreeMut};
+pub use page_store::{InMemoryBackend, Savepoint, SavepointInfo, file_backend};
+pub use range_mut::{RangeMut, RangeMutEntry};
+pub(crate) use table_tree::{PageListMut, TableTree, TableTreeCheckpoint, TableTreeMut};
 pub(crate) use table_tree_base::{InternalTableDefinition, TableType};
@@ -1,5 +1,8 @@
 use crate::Result;
-use crate::tree_store::btree::{PagePath, UntypedBtree, UntypedBtreeMut, btree_stats};
+use crate::tree_store::btree::{
+    LEAF_FILL_BUCKETS, PagePath, UntypedBtree, UntypedBtreeMut, btree_stats, copy_btree,
+    leaf_fill_histogram, merge_leaf_fill_histogram,
+};
 use crate::tree_store::btree_base::{
     BRANCH, BranchAccessor, BranchMutator, Checksum, DEFERRED, LEAF, LeafAccessor, LeafPageMut,
 };
@@ -33,6 +36,8 @@ pub(crate) fn multimap_btree_stats(
             stored_leaf_bytes: 0,
             metadata_bytes: 0,
             fragmented_bytes: 0,
+            leaf_fill_histogram: [0; LEAF_FILL_BUCKETS],
+            largest_leaf_pages: 0,
         })
     }
 }
@@ -78,6 +83,16 @@ fn multimap_stats_helper(
             let mut fragmented_bytes = (page.memory().len() - accessor.total_length()) as u64;
             let mut max_child_height = 0;
             let (mut leaf_pages, mut branch_pages) = if is_branch { (0, 1) } else { (1, 0) };
+            let mut histogram = if is_branch {
+                [0; LEAF_FILL_BUCKETS]
+            } else {
+                leaf_fill_histogram(accessor.total_length(), page.memory().len())
+            };
+            let mut largest_leaf_pages = if is_branch {
+                0
+            } else {
+                1 << page_number.page_order
+            };
 
             for i in 0..accessor.num_pairs() {
                 let entry = accessor.entry(i).unwrap();
@@ -102,6 +117,8 @@ fn multimap_stats_helper(
                         fragmented_bytes += stats.fragmented_bytes;
                         overhead_bytes += stats.metadata_bytes;
                         leaf_bytes += stats.stored_leaf_bytes;
+                        merge_leaf_fill_histogram(&mut histogram, &stats.leaf_fill_histogram);
+                        largest_leaf_pages = max(largest_leaf_pages, stats.largest_leaf_pages);
                     }
                 }
             }
@@ -113,6 +130,8 @@ fn multimap_stats_helper(
                 stored_leaf_bytes: leaf_bytes,
                 metadata_bytes: overhead_bytes,
                 fragmented_bytes,
+                leaf_fill_histogram: histogram,
+                largest_leaf_pages,
             })
         }
         BRANCH => {
@@ -123,6 +142,8 @@ fn multimap_stats_helper(
             let mut stored_leaf_bytes = 0;
             let mut metadata_bytes = accessor.total_length() as u64;
             let mut fragmented_bytes = (page.memory().len() - accessor.total_length()) as u64;
+            let mut histogram = [0; LEAF_FILL_BUCKETS];
+            let mut largest_leaf_pages = 0;
             for i in 0..accessor.count_children() {
                 if let Some(child) = accessor.child_page(i) {
                     let stats =
@@ -133,6 +154,8 @@ fn multimap_stats_helper(
                     stored_leaf_bytes += stats.stored_leaf_bytes;
                     metadata_bytes += stats.metadata_bytes;
                     fragmented_bytes += stats.fragmented_bytes;
+                    merge_leaf_fill_histogram(&mut histogram, &stats.leaf_fill_histogram);
+                    largest_leaf_pages = max(largest_leaf_pages, stats.largest_leaf_pages);
                 }
             }
 
@@ -143,9 +166,11 @@ fn multimap_stats_helper(
                 stored_leaf_bytes,
                 metadata_bytes,
                 fragmented_bytes,
+                leaf_fill_histogram: histogram,
+                largest_leaf_pages,
             })
         }
-        _ => unreachable!(),
+        _ => Err(mem.corrupted_page(&page)),
     }
 }
 
@@ -199,6 +224,56 @@ pub(super) fn verify_tree_and_subtree_checksums(
     Ok(true)
 }
 
+// Appends every corrupted page in the tree and in its subtrees. Subtrees are only searched if the
+// tree itself is intact
+pub(super) fn find_corrupted_tree_and_subtree_pages(
+    root: Option<BtreeHeader>,
+    key_size: Option<usize>,
+    value_size: Option<usize>,
+    mem: PageResolver,
+    hint: PageHint,
+    corrupted: &mut Vec<PageNumber>,
+) -> Result {
+    if let Some(header) = root {
+        let initial_len = corrupted.len();
+        RawBtree::new(
+            Some(header),
+            key_size,
+            DynamicCollection::<()>::fixed_width_with(value_size),
+            mem.clone(),
+            hint,
+        )
+        .find_corrupted_pages(corrupted)?;
+        if corrupted.len() > initial_len {
+            return Ok(());
+        }
+
+        let table_pages_iter = AllPageNumbersBtreeIter::new(
+            header.root,
+            key_size,
+            DynamicCollection::<()>::fixed_width_with(value_size),
+            mem.clone(),
+            hint,
+        )?;
+        for table_page in table_pages_iter {
+            let page = mem.get_page(table_page?, hint)?;
+            let subtree_roots = parse_subtree_roots(&page, key_size, value_size);
+            for header in subtree_roots {
+                RawBtree::new(
+                    Some(header),
+                    value_size,
+                    <()>::fixed_width(),
+                    mem.clone(),
+                    hint,
+                )
+                .find_corrupted_pages(corrupted)?;
+            }
+        }
+    }
+
+    Ok(())
+}
+
 // Relocate all subtrees to lower index pages, if possible
 pub(super) fn relocate_subtrees(
     root: (PageNumber, Checksum),
@@ -268,7 +343,7 @@ pub(super) fn relocate_subtrees(
                 }
             }
         }
-        _ => unreachable!(),
+        _ => return Err(page_allocator.corrupted_page(&old_page)),
     }
 
     let old_page_number = old_page.get_page_number();
@@ -282,6 +357,70 @@ pub(super) fn relocate_subtrees(
     Ok((new_page_number, DEFERRED))
 }
 
+// Copies the tree rooted at the given page, including all subtrees, which is read through source,
+// into pages allocated from page_allocator. Returns the root of the copy, whose checksums must be
+// finalized by the caller
+pub(super) fn copy_subtrees(
+    page_number: PageNumber,
+    key_size: Option<usize>,
+    value_size: Option<usize>,
+    source: &PageResolver,
+    page_allocator: &PageAllocator,
+    allocated: &mut PageTrackerPolicy,
+) -> Result<PageNumber> {
+    let old_page = source.get_page(page_number, PageHint::None)?;
+    let mut new_page = page_allocator.allocate(old_page.memory().len(), allocated)?;
+    let new_page_number = new_page.get_page_number();
+    new_page.memory_mut().copy_from_slice(old_page.memory());
+
+    match old_page.memory()[0] {
+        LEAF => {
+            let mut leaf_page = LeafPageMut::new(
+                new_page,
+                key_size,
+                UntypedDynamicCollection::fixed_width_with(value_size),
+            );
+            let accessor = LeafAccessor::new(
+                old_page.memory(),
+                key_size,
+                UntypedDynamicCollection::fixed_width_with(value_size),
+            );
+            for i in 0..accessor.num_pairs() {
+                let entry = accessor.entry(i).unwrap();
+                let collection = UntypedDynamicCollection::from_bytes(entry.value());
+                if matches!(collection.collection_type(), SubtreeV2) {
+                    let sub_root = collection.as_subtree();
+                    let new_sub_root =
+                        copy_btree(sub_root.root, value_size, source, page_allocator, allocated)?;
+                    let new_collection = UntypedDynamicCollection::make_subtree_data(
+                        BtreeHeader::new(new_sub_root, DEFERRED, sub_root.length),
+                    );
+                    leaf_page.replace_value(i, &new_collection);
+                }
+            }
+        }
+        BRANCH => {
+            let accessor = BranchAccessor::new(&old_page, key_size);
+            let mut mutator = BranchMutator::new(new_page.memory_mut());
+            for i in 0..accessor.count_children() {
+                let child = accessor.child_page(i).unwrap();
+                let new_child = copy_subtrees(
+                    child,
+                    key_size,
+                    value_size,
+                    source,
+                    page_allocator,
+                    allocated,
+                )?;
+                mutator.write_child_page(i, new_child, DEFERRED);
+            }
+        }
+        _ => return Err(source.corrupted_page(&old_page)),
+    }
+
+    Ok(new_page_number)
+}
+
 // Finalize all the checksums in the tree, including any Dynamic collection subtrees
 // Returns the root checksum
 pub(super) fn finalize_tree_and_subtree_checksums(
@@ -388,10 +527,11 @@ impl UntypedMultiBtree {
         }
     }
 
-    // Applies visitor to pages in the tree
-    pub(super) fn visit_all_pages<F>(&self, mut visitor: F) -> Result
+    // Applies visitor to pages in the tree, skipping the children and subtrees of any page for
+    // which it returns false
+    pub(super) fn visit_pages_while<F>(&self, mut visitor: F) -> Result
     where
-        F: FnMut(&PagePath) -> Result,
+        F: FnMut(&PagePath) -> Result<bool>,
     {
         let tree = UntypedBtree::new(
             self.root,
@@ -400,8 +540,10 @@ impl UntypedMultiBtree {
             self.key_width,
             UntypedDynamicCollection::fixed_width_with(self.value_width),
         );
-        tree.visit_all_pages(|path| {
-            visitor(path)?;
+        tree.visit_pages_while(|path| {
+            if !visitor(path)? {
+                return Ok(false);
+            }
             let page = self.mem.get_page(path.page_number(), self.hint)?;
             match page.memory()[0] {
                 LEAF => {
@@ -413,7 +555,7 @@ impl UntypedMultiBtree {
                             self.value_width,
                             <() as Value>::fixed_width(),
                         );
-                        subtree.visit_all_pages(|subpath| {
+                        subtree.visit_pages_while(|subpath| {
                             let full_path = path.with_subpath(subpath);
                             visitor(&full_path)
                         })?;
@@ -422,9 +564,9 @@ impl UntypedMultiBtree {
                 BRANCH => {
                     // No-op. The tree.visit_pages() call will process this sub-tree
                 }
-                _ => unreachable!(),
+                _ => return Err(self.mem.corrupted_page(&page)),
             }
-            Ok(())
+            Ok(true)
         })?;
 
         Ok(())
@@ -0,0 +1,78 @@
+use crate::tree_store::PageNumber;
+use crate::tree_store::page_store::fast_hash::FastHasher64;
+use std::collections::HashMap;
+use std::hash::{BuildHasher, BuildHasherDefault};
+use std::sync::atomic::{AtomicBool, AtomicU32, Ordering};
+
+// Number of recency slots. Pages are hashed into slots, so recency is approximate: a cold page
+// may appear hot if it shares a slot with a hot page
+const SLOTS: usize = 1 << 14;
+
+// Tracks approximately when each page was last accessed, measured in commits since the tracker
+// was last reset. Used by compaction to place frequently read pages near the start of the file
+pub(crate) struct PageAccessTracker {
+    slots: Vec<AtomicU32>,
+    // Starts at 1, so that a recency of 0 means "not accessed since the last reset"
+    clock: AtomicU32,
+    paused: AtomicBool,
+}
+
+impl PageAccessTracker {
+    pub(crate) fn new() -> Self {
+        Self {
+            slots: (0..SLOTS).map(|_| AtomicU32::new(0)).collect(),
+            clock: AtomicU32::new(1),
+            paused: AtomicBool::new(false),
+        }
+    }
+
+    fn slot(page: PageNumber) -> usize {
+        let hash = BuildHasherDefault::<FastHasher64>::default().hash_one(page);
+        (hash % SLOTS as u64).try_into().unwrap()
+    }
+
+    pub(crate) fn record(&self, page: PageNumber) {
+        if self.paused.load(Ordering::Relaxed) {
+            return;
+        }
+        let now = self.clock.load(Ordering::Relaxed);
+        self.slots[Self::slot(page)].store(now, Ordering::Relaxed);
+    }
+
+    pub(crate) fn recency(&self, page: PageNumber) -> u32 {
+        self.slots[Self::slot(page)].load(Ordering::Relaxed)
+    }
+
+    pub(crate) fn tick(&self) {
+        // Saturate rather than wrap, so that older accesses never appear newer
+        let _ = self
+            .clock
+            .fetch_update(Ordering::Relaxed, Ordering::Relaxed, |x| x.checked_add(1));
+    }
+
+    // Stop recording accesses. Used while compaction walks every page, so that the walk itself
+    // does not make every page look hot
+    pub(crate) fn pause(&self) {
+        self.paused.store(true, Ordering::Relaxed);
+    }
+
+    // Carry the recency of relocated pages over to their new location
+    pub(crate) fn transfer(&self, relocations: &HashMap<PageNumber, PageNumber>) {
+        let recencies: Vec<(PageNumber, u32)> = relocations
+            .iter()
+            .map(|(old, new)| (*new, self.recency(*old)))
+            .collect();
+        for (new, recency) in recencies {
+            self.slots[Self::slot(new)].fetch_max(recency, Ordering::Relaxed);
+        }
+    }
+
+    // Forget all recorded accesses, and resume recording
+    pub(crate) fn reset(&self) {
+        for slot in &self.slots {
+            slot.store(0, Ordering::Relaxed);
+        }
+        self.clock.store(1, Ordering::Relaxed);
+        self.paused.store(false, Ordering::Relaxed);
+    }
+}
@@ -0,0 +1,161 @@
+use crate::error::BackupError;
+use crate::transaction_tracker::TransactionId;
+use crate::tree_store::btree_base::BtreeHeader;
+use crate::tree_store::page_store::base::PageHint;
+use crate::tree_store::page_store::header::{
+    DB_HEADER_SIZE, DatabaseHeader, UnrepairedDatabaseHeader,
+};
+use crate::tree_store::page_store::layout::DatabaseLayout;
+use crate::tree_store::page_store::page_manager::TransactionalMemory;
+use crate::tree_store::{Page, PageNumber};
+use crate::{DatabaseError, Result, StorageBackend, StorageError};
+use std::io::{Read, Write};
+use std::ops::Range;
+
+// Backup layout (all integers are little endian):
+//
+// 8 bytes: magic number
+// 1 byte: version
+// 1 byte: != 0 if the backup is incremental
+// 8 bytes: transaction id that an incremental backup must be applied on top of
+// 8 bytes: length of the database file
+// DB_HEADER_SIZE bytes: database header, with the backed up transaction in both commit slots
+// 8 bytes: number of pages
+//
+// Then for each page:
+// 8 bytes: page number
+// n bytes: page contents
+
+const BACKUP_MAGICNUMBER: [u8; 8] = *b"redbbkup";
+const BACKUP_VERSION: u8 = 1;
+
+fn read_array<const N: usize>(reader: &mut impl Read) -> Result<[u8; N]> {
+    let mut buffer = [0; N];
+    reader.read_exact(&mut buffer)?;
+    Ok(buffer)
+}
+
+fn page_range(page: PageNumber, layout: &DatabaseLayout) -> Range<u64> {
+    let region_layout = layout.full_region_layout();
+    page.address_range(
+        region_layout.page_size().into(),
+        region_layout.len(),
+        region_layout.data_section().start,
+        region_layout.page_size(),
+    )
+}
+
+// Writes `pages` of the tree rooted at `user_root`, along with a header that describes the state
+// as of `transaction_id`. The system tree is not backed up, and is rebuilt when the restored
+// database is repaired on open
+pub(crate) fn write_backup(
+    mem: &TransactionalMemory,
+    since: Option<TransactionId>,
+    transaction_id: TransactionId,
+    user_root: Option<BtreeHeader>,
+    pages: &[PageNumber],
+    writer: &mut impl Write,
+) -> Result {
+    let layout = mem.get_layout();
+    let mut header = DatabaseHeader::new(layout, transaction_id);
+    header.two_phase_commit = true;
+    header.secondary_slot_mut().user_root = user_root;
+    header.swap_primary_slot();
+    header.secondary_slot_mut().user_root = user_root;
+
+    writer.write_all(&BACKUP_MAGICNUMBER)?;
+    writer.write_all(&[BACKUP_VERSION, u8::from(since.is_some())])?;
+    writer.write_all(&since.map_or(0, |x| x.raw_id()).to_le_bytes())?;
+    writer.write_all(&layout.len().to_le_bytes())?;
+    writer.write_all(&header.to_bytes(true))?;
+    writer.write_all(&(pages.len() as u64).to_le_bytes())?;
+    for page_number in pages {
+        let page = mem.get_page(*page_number, PageHint::None)?;
+        writer.write_all(&page_number.to_le_bytes())?;
+        writer.write_all(page.memory())?;
+    }
+    writer.flush()?;
+
+    Ok(())
+}
+
+// Applies a backup written by `write_backup()` to `file`. The pages are written before the header,
+// and an incremental backup never overwrites a page that is reachable from the previous state, so
+// if this is interrupted the file is left in its previous state
+pub(crate) fn apply_backup(
+    file: &dyn StorageBackend,
+    reader: &mut impl Read,
+) -> Result<(), BackupError> {
+    let magic: [u8; BACKUP_MAGICNUMBER.len()] = read_array(reader)?;
+    let flags: [u8; 2] = read_array(reader)?;
+    if magic != BACKUP_MAGICNUMBER || flags[0] != BACKUP_VERSION {
+        return Err(StorageError::Corrupted("Invalid backup header".to_string()).into());
+    }
+    let incremental = flags[1] != 0;
+    let since = TransactionId::new(u64::from_le_bytes(read_array(reader)?));
+    let len = u64::from_le_bytes(read_array(reader)?);
+    let header_bytes: [u8; DB_HEADER_SIZE] = read_array(reader)?;
+    let (header, clean) = UnrepairedDatabaseHeader::from_bytes(&header_bytes)?.finalize(len)?;
+    if !clean {
+        return Err(StorageError::Corrupted("Invalid backup header".to_string()).into());
+    }
+    let layout = header.layout();
+
+    let file_len = file.len().map_err(StorageError::from)?;
+    if incremental {
+        if file_len < DB_HEADER_SIZE as u64 {
+            return Err(BackupError::IncompatibleBackup);
+        }
+        let mut target_bytes = [0; DB_HEADER_SIZE];
+        file.read(0, &mut target_bytes)
+            .map_err(StorageError::from)?;
+        let unrepaired =
+            UnrepairedDatabaseHeader::from_bytes(&target_bytes).map_err(|err| match err {
+                DatabaseError::Storage(StorageError::Corrupted(_))
+                | DatabaseError::UpgradeRequired(_) => BackupError::IncompatibleBackup,
+                err => err.into(),
+            })?;
+        // A file that has been opened as a database since the last backup was applied no longer
+        // needs recovery, and may have overwritten pages that this backup depends on
+        if !unrepaired.recovery_required(file_len) {
+            return Err(BackupError::IncompatibleBackup);
+        }
+        let (target, _) = unrepaired.finalize(file_len)?;
+        if target.primary_slot().transaction_id != since
+            || target.layout().full_region_layout() != layout.full_region_layout()
+        {
+            return Err(BackupError::IncompatibleBackup);
+        }
+    } else if file_len != 0 {
+        return Err(BackupError::IncompatibleBackup);
+    }
+
+    if file_len < len {
+        file.set_len(len).map_err(StorageError::from)?;
+    }
+    let mut buffer = vec![];
+    for _ in 0..u64::from_le_bytes(read_array(reader)?) {
+        let page_number = PageNumber::from_le_bytes(read_array(reader)?);
+        let range = page_range(page_number, &layout);
+        if range.end > len {
+            return Err(StorageError::Corrupted(format!(
+                "Backup contains page {page_number:?} outside of the database"
+            ))
+            .into());
+        }
+        buffer.resize((range.end - range.start).try_into().unwrap(), 0);
+        reader.read_exact(&mut buffer).map_err(StorageError::from)?;
+        file.write(range.start, &buffer)
+            .map_err(StorageError::from)?;
+    }
+    file.sync_data().map_err(StorageError::from)?;
+
+    file.write(0, &header_bytes).map_err(StorageError::from)?;
+    file.sync_data().map_err(StorageError::from)?;
+    if file_len > len {
+        file.set_len(len).map_err(StorageError::from)?;
+        file.sync_data().map_err(StorageError::from)?;
+    }
+
+    Ok(())
+}
@@ -189,6 +189,29 @@ impl PageImpl {
     pub(crate) fn to_arc(&self) -> Arc<[u8]> {
         self.mem.clone()
     }
+
+    // Moves the reference to this page from its current set of open pages to `open_pages`
+    #[cfg(debug_assertions)]
+    pub(super) fn move_reference(&mut self, open_pages: Arc<Mutex<HashMap<PageNumber, u64>>>) {
+        *open_pages
+            .lock()
+            .unwrap()
+            .entry(self.page_number)
+            .or_default() += 1;
+        self.release_reference();
+        self.open_pages = open_pages;
+    }
+
+    #[cfg(debug_assertions)]
+    fn release_reference(&self) {
+        let mut open_pages = self.open_pages.lock().unwrap();
+        let value = open_pages.get_mut(&self.page_number).unwrap();
+        assert!(*value > 0);
+        *value -= 1;
+        if *value == 0 {
+            open_pages.remove(&self.page_number);
+        }
+    }
 }
 
 impl Debug for PageImpl {
@@ -200,13 +223,7 @@ impl Debug for PageImpl {
 #[cfg(debug_assertions)]
 impl Drop for PageImpl {
     fn drop(&mut self) {
-        let mut open_pages = self.open_pages.lock().unwrap();
-        let value = open_pages.get_mut(&self.page_number).unwrap();
-        assert!(*value > 0);
-        *value -= 1;
-        if *value == 0 {
-            open_pages.remove(&self.page_number);
-        }
+        self.release_reference();
     }
 }
 
@@ -176,6 +176,13 @@ impl BuddyAllocator {
         None
     }
 
+    // Returns true if every order-0 page covered by the given page is allocated
+    pub(crate) fn is_allocated(&self, page_number: u32, order: u8) -> bool {
+        let start = page_number << order;
+        let end = start + (1 << order);
+        end <= self.len() && (start..end).all(|page| self.find_free_order(page).is_none())
+    }
+
     pub(crate) fn trailing_free_pages(&self) -> u32 {
         let mut free_pages = 0;
         let mut next_page = self.len() - 1;
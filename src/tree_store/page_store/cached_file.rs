@@ -1,7 +1,10 @@
 use crate::tree_store::page_store::base::PageHint;
+use crate::tree_store::page_store::compression::{
+    DISCARD_ALIGNMENT, compress, decompress, is_compressed,
+};
 use crate::tree_store::page_store::lru_cache::LRUCache;
-use crate::{CacheStats, DatabaseError, Result, StorageBackend, StorageError};
-use std::ops::{Index, IndexMut};
+use crate::{CacheStats, DatabaseError, PageCompression, Result, StorageBackend, StorageError};
+use std::ops::{Index, IndexMut, Range};
 use std::slice::SliceIndex;
 #[cfg(feature = "cache_metrics")]
 use std::sync::atomic::AtomicU64;
@@ -195,11 +198,25 @@ impl CheckedBackend {
         }
         result.map_err(StorageError::from)
     }
+
+    fn discard(&self, offset: u64, len: u64) -> Result<()> {
+        self.check_failure()?;
+        let result = self.file.discard(offset, len);
+        if result.is_err() {
+            self.io_failed.store(true, Ordering::Release);
+        }
+        result.map_err(StorageError::from)
+    }
 }
 
 pub(super) struct PagedCachedFile {
     file: CheckedBackend,
     page_size: u64,
+    // Applied to pages when they are written to the file
+    compression: PageCompression,
+    // Whether the file may contain compressed pages. If not, part of a page can be read without
+    // first checking whether the page is compressed
+    compressed_pages: AtomicBool,
     // Dynamic cache partitioning.  Three invariants:
     //
     // 1. The write buffer NEVER exceeds 50% of max_cache_size.
@@ -251,6 +268,8 @@ impl PagedCachedFile {
         Ok(Self {
             file: CheckedBackend::new(file),
             page_size,
+            compression: PageCompression::None,
+            compressed_pages: AtomicBool::new(false),
             read_cache_bytes: AtomicUsize::new(0),
             write_buffer_bytes: AtomicUsize::new(0),
             max_cache_size,
@@ -303,6 +322,11 @@ impl PagedCachedFile {
         }
     }
 
+    // Bytes of pages modified since they were last written to storage
+    pub(crate) fn write_buffer_bytes(&self) -> usize {
+        self.write_buffer_bytes.load(Ordering::Acquire)
+    }
+
     pub(crate) fn close(&self) -> Result {
         self.file.close()
     }
@@ -315,6 +339,32 @@ impl PagedCachedFile {
         self.file.len()
     }
 
+    pub(super) fn set_compression(&mut self, compression: PageCompression) {
+        if compression != PageCompression::None {
+            self.mark_compressed_pages();
+        }
+        self.compression = compression;
+    }
+
+    pub(super) fn mark_compressed_pages(&self) {
+        self.compressed_pages.store(true, Ordering::Release);
+    }
+
+    // Writes a page from the write buffer to the file, compressing it if that saves space
+    fn write_page(&self, offset: u64, page: &[u8]) -> Result {
+        let Some(compressed) = compress(page, self.compression) else {
+            return self.file.write(offset, page);
+        };
+        self.file.write(offset, &compressed)?;
+        let end = offset + page.len() as u64;
+        let unused_start = (offset + compressed.len() as u64).next_multiple_of(DISCARD_ALIGNMENT);
+        let unused_end = end - end % DISCARD_ALIGNMENT;
+        if unused_start < unused_end {
+            self.file.discard(unused_start, unused_end - unused_start)?;
+        }
+        Ok(())
+    }
+
     const fn lock_stripes() -> u64 {
         131
     }
@@ -357,7 +407,7 @@ impl PagedCachedFile {
         let mut write_buffer = self.write_buffer.lock().unwrap();
 
         for (offset, buffer) in write_buffer.cache.iter() {
-            self.file.write(*offset, buffer.as_ref().unwrap())?;
+            self.write_page(*offset, buffer.as_ref().unwrap())?;
         }
         // Transfer flushed pages into the read cache so they are available
         // for subsequent reads without a file I/O.  The write buffer is being
@@ -439,13 +489,65 @@ impl PagedCachedFile {
 
     // Like `read_direct`, but writes directly into an `Arc<[u8]>` instead of a
     // `Vec<u8>` that is then copied into an `Arc`. The buffer is zero-filled
-    // because `StorageBackend::read` takes `&mut [u8]`.
+    // because `StorageBackend::read` takes `&mut [u8]`. Compressed pages are decompressed
     fn read_direct_into_arc(&self, offset: u64, len: usize) -> Result<Arc<[u8]>> {
         let mut arc = zero_filled_arc(len);
         self.file.read(offset, Arc::get_mut(&mut arc).unwrap())?;
+        if is_compressed(&arc) {
+            return decompress(&arc, len);
+        }
         Ok(arc)
     }
 
+    // Read `range` of the `len` bytes at `offset`. Cached data is used if present, but the data
+    // read is not added to the cache
+    pub(super) fn read_range(
+        &self,
+        offset: u64,
+        len: usize,
+        range: Range<usize>,
+        hint: PageHint,
+    ) -> Result<Vec<u8>> {
+        debug_assert_eq!(0, offset % self.page_size);
+        debug_assert!(range.end <= len);
+
+        if !matches!(hint, PageHint::Clean) {
+            let lock = self.write_buffer.lock().unwrap();
+            if let Some(cached) = lock.get(offset) {
+                debug_assert_eq!(cached.len(), len);
+                return Ok(cached[range].to_vec());
+            }
+        }
+
+        let cache_slot: usize = (offset % Self::lock_stripes()).try_into().unwrap();
+        {
+            let read_lock = self.read_cache[cache_slot].read().unwrap();
+            if let Some(cached) = read_lock.get(offset) {
+                debug_assert_eq!(cached.len(), len);
+                return Ok(cached[range].to_vec());
+            }
+        }
+
+        if !self.compressed_pages.load(Ordering::Acquire) {
+            return self.read_direct(offset + range.start as u64, range.len());
+        }
+        // A compressed page can only be read in full
+        let compressed = if range.start == 0 {
+            let data = self.read_direct(offset, range.len())?;
+            if !is_compressed(&data) {
+                return Ok(data);
+            }
+            true
+        } else {
+            is_compressed(&self.read_direct(offset, 1)?)
+        };
+        if compressed {
+            return Ok(self.read_direct_into_arc(offset, len)?[range].to_vec());
+        }
+
+        self.read_direct(offset + range.start as u64, range.len())
+    }
+
     // Read with caching. Caller must not read overlapping ranges without first calling invalidate_cache().
     // Doing so will not cause UB, but is a logic error.
     pub(super) fn read(&self, offset: u64, len: usize, hint: PageHint) -> Result<Arc<[u8]>> {
@@ -583,7 +685,7 @@ impl PagedCachedFile {
                 while flushed < excess {
                     if let Some((offset, buffer)) = lock.pop_lowest_priority() {
                         let removed_len = buffer.len();
-                        let result = self.file.write(offset, &buffer);
+                        let result = self.write_page(offset, &buffer);
                         if result.is_err() {
                             lock.insert(offset, buffer);
                         }
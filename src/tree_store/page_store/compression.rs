@@ -0,0 +1,82 @@
+use crate::tree_store::btree_base::{BRANCH, LEAF};
+use crate::{PageCompression, Result, StorageError};
+use std::sync::Arc;
+
+// Compressed page layout (all integers are little endian):
+//
+// 1 byte: COMPRESSED_PAGE
+// 1 byte: algorithm
+// 2 bytes: padding
+// 4 bytes: length of the compressed data
+// n bytes: compressed data
+//
+// The rest of the page is unused, and is released with StorageBackend::discard(). Only btree pages
+// are compressed, so the first byte distinguishes compressed pages from uncompressed ones
+const COMPRESSED_PAGE: u8 = 3;
+#[cfg(feature = "lz4")]
+const LZ4: u8 = 1;
+const HEADER_SIZE: usize = 8;
+
+// Storage is released in blocks of this size, so compressing a page only saves space if it shrinks
+// by at least one block
+pub(super) const DISCARD_ALIGNMENT: u64 = 4096;
+
+pub(super) fn is_compressed(page: &[u8]) -> bool {
+    page.first() == Some(&COMPRESSED_PAGE)
+}
+
+#[cfg_attr(not(feature = "lz4"), allow(unused_variables))]
+fn compress_data(page: &[u8], compression: PageCompression) -> Option<(u8, Vec<u8>)> {
+    match compression {
+        PageCompression::None => None,
+        #[cfg(feature = "lz4")]
+        PageCompression::Lz4 => Some((LZ4, lz4_flex::block::compress(page))),
+    }
+}
+
+// Returns the compressed form of `page`, or None if it should be stored uncompressed
+pub(super) fn compress(page: &[u8], compression: PageCompression) -> Option<Vec<u8>> {
+    if page.len() as u64 <= DISCARD_ALIGNMENT || !matches!(page[0], LEAF | BRANCH) {
+        return None;
+    }
+    let (algorithm, data) = compress_data(page, compression)?;
+    let compressed_len = (HEADER_SIZE + data.len()) as u64;
+    if compressed_len.next_multiple_of(DISCARD_ALIGNMENT) >= page.len() as u64 {
+        return None;
+    }
+
+    let mut result = Vec::with_capacity(HEADER_SIZE + data.len());
+    result.extend_from_slice(&[COMPRESSED_PAGE, algorithm, 0, 0]);
+    result.extend_from_slice(&u32::try_from(data.len()).unwrap().to_le_bytes());
+    result.extend_from_slice(&data);
+    Some(result)
+}
+
+// Decompresses a page written by compress(), whose uncompressed length is `len`
+#[cfg_attr(not(feature = "lz4"), allow(unused_variables))]
+pub(super) fn decompress(page: &[u8], len: usize) -> Result<Arc<[u8]>> {
+    debug_assert!(is_compressed(page));
+    let corrupted = || StorageError::Corrupted("Compressed page is corrupted".to_string());
+    let data_len: usize = u32::from_le_bytes(page[4..HEADER_SIZE].try_into().unwrap())
+        .try_into()
+        .unwrap();
+    let data = page
+        .get(HEADER_SIZE..(HEADER_SIZE + data_len))
+        .ok_or_else(corrupted)?;
+    match page[1] {
+        #[cfg(feature = "lz4")]
+        LZ4 => {
+            let mut result: Arc<[u8]> = std::iter::repeat_n(0u8, len).collect();
+            let decompressed =
+                lz4_flex::block::decompress_into(data, Arc::get_mut(&mut result).unwrap())
+                    .map_err(|_| corrupted())?;
+            if decompressed != len {
+                return Err(corrupted());
+            }
+            Ok(result)
+        }
+        algorithm => Err(StorageError::Corrupted(format!(
+            "Page is compressed with unsupported algorithm {algorithm}"
+        ))),
+    }
+}
@@ -16,6 +16,12 @@ impl FileBackend {
         Self::new_internal(file, false)
     }
 
+    pub(crate) fn new_unlocked(file: File) -> Self {
+        Self {
+            file: Mutex::new(file),
+        }
+    }
+
     pub(crate) fn new_internal(file: File, _: bool) -> Result<Self, DatabaseError> {
         Ok(Self {
             file: Mutex::new(file),
@@ -24,8 +24,17 @@ impl FileBackend {
         Self::new_internal(file, false)
     }
 
-    pub(crate) fn new_internal(file: File, read_only: bool) -> Result<Self, DatabaseError> {
-        let result = if read_only {
+    // Does not lock the file, so that it can be read even while another process has it open
+    pub(crate) fn new_unlocked(file: File) -> Self {
+        Self {
+            file,
+            lock_supported: false,
+        }
+    }
+
+    // If `shared` is true, other processes may also open the file with a shared lock
+    pub(crate) fn new_internal(file: File, shared: bool) -> Result<Self, DatabaseError> {
+        let result = if shared {
             file.try_lock_shared()
         } else {
             file.try_lock()
@@ -110,6 +119,29 @@ impl StorageBackend for FileBackend {
         Ok(())
     }
 
+    #[cfg(any(target_os = "linux", target_os = "android"))]
+    fn discard(&self, offset: u64, len: u64) -> Result<(), io::Error> {
+        use std::os::fd::AsRawFd;
+
+        // SAFETY: the file descriptor is valid for the lifetime of `self.file`
+        let result = unsafe {
+            libc::fallocate(
+                self.file.as_raw_fd(),
+                libc::FALLOC_FL_PUNCH_HOLE | libc::FALLOC_FL_KEEP_SIZE,
+                libc::off_t::try_from(offset).unwrap(),
+                libc::off_t::try_from(len).unwrap(),
+            )
+        };
+        if result == -1 {
+            let err = io::Error::last_os_error();
+            // Not all file systems support releasing space. The data is still correct
+            if !matches!(err.raw_os_error(), Some(libc::EOPNOTSUPP | libc::ENOSYS)) {
+                return Err(err);
+            }
+        }
+        Ok(())
+    }
+
     fn close(&self) -> Result<(), io::Error> {
         if self.lock_supported {
             self.file.unlock()?;
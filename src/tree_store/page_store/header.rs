@@ -58,6 +58,11 @@ pub(super) const DB_HEADER_SIZE: usize = TRANSACTION_1_OFFSET + TRANSACTION_SIZE
 const PRIMARY_BIT: u8 = 1;
 const RECOVERY_REQUIRED: u8 = 2;
 const TWO_PHASE_COMMIT: u8 = 4;
+// The file has been finalized by Database::seal() and must not be opened for writing
+const SEALED: u8 = 8;
+// Page compression has been enabled, so the file may contain compressed pages. Builds without
+// support for compression must not open it
+const COMPRESSED_PAGES: u8 = 16;
 
 // Structure of each commit slot
 const VERSION_OFFSET: usize = 0;
@@ -91,11 +96,15 @@ pub(super) struct UnrepairedDatabaseHeader {
     secondary_corrupted: bool,
 }
 
+// The bools are the flags of the god byte
 #[derive(Clone)]
+#[allow(clippy::struct_excessive_bools)]
 pub(super) struct DatabaseHeader {
     primary_slot: usize,
     pub(super) recovery_required: bool,
     pub(super) two_phase_commit: bool,
+    pub(super) sealed: bool,
+    pub(super) compressed_pages: bool,
     page_size: u32,
     region_header_pages: u32,
     region_max_data_pages: u32,
@@ -113,6 +122,8 @@ impl UnrepairedDatabaseHeader {
         let primary_slot = usize::from(data[GOD_BYTE_OFFSET] & PRIMARY_BIT != 0);
         let recovery_required = (data[GOD_BYTE_OFFSET] & RECOVERY_REQUIRED) != 0;
         let two_phase_commit = (data[GOD_BYTE_OFFSET] & TWO_PHASE_COMMIT) != 0;
+        let sealed = (data[GOD_BYTE_OFFSET] & SEALED) != 0;
+        let compressed_pages = (data[GOD_BYTE_OFFSET] & COMPRESSED_PAGES) != 0;
         let page_size = get_u32(&data[PAGE_SIZE_OFFSET..]);
         let region_header_pages = get_u32(&data[REGION_HEADER_PAGES_OFFSET..]);
         let region_max_data_pages = get_u32(&data[REGION_MAX_DATA_PAGES_OFFSET..]);
@@ -135,6 +146,8 @@ impl UnrepairedDatabaseHeader {
                 primary_slot,
                 recovery_required,
                 two_phase_commit,
+                sealed,
+                compressed_pages,
                 page_size,
                 region_header_pages,
                 region_max_data_pages,
@@ -151,6 +164,22 @@ impl UnrepairedDatabaseHeader {
         self.inner.page_size
     }
 
+    pub(super) fn sealed(&self) -> bool {
+        self.inner.sealed
+    }
+
+    pub(super) fn compressed_pages(&self) -> bool {
+        self.inner.compressed_pages
+    }
+
+    pub(super) fn primary_corrupted(&self) -> bool {
+        self.primary_corrupted
+    }
+
+    pub(super) fn secondary_corrupted(&self) -> bool {
+        self.secondary_corrupted
+    }
+
     // Returns true if the header needs to be repaired before use: either the recovery_required
     // flag is set on disk, or the stored layout no longer matches the current file length (e.g.
     // the file was truncated or extended externally). Callers must pass the actual file length
@@ -159,6 +188,16 @@ impl UnrepairedDatabaseHeader {
         self.inner.recovery_required || self.inner.layout().len() != file_len
     }
 
+    // Returns the header with the primary slot selected by the god byte, without checking it against
+    // the file. Returns None if the primary slot is corrupted
+    pub(super) fn into_primary(self) -> Option<DatabaseHeader> {
+        if self.primary_corrupted {
+            None
+        } else {
+            Some(self.inner)
+        }
+    }
+
     // Consume self, reconcile the layout against the actual file length, and select a primary slot
     // (repairing if necessary). Returns the usable DatabaseHeader along with a `clean` flag that is
     // true only when nothing had to be reconciled: the primary was kept and the stored layout
@@ -242,6 +281,8 @@ impl DatabaseHeader {
             primary_slot: 0,
             recovery_required: true,
             two_phase_commit: false,
+            sealed: false,
+            compressed_pages: false,
             page_size: layout.full_region_layout().page_size(),
             region_header_pages: layout.full_region_layout().get_header_pages(),
             region_max_data_pages: layout.full_region_layout().num_pages(),
@@ -319,6 +360,12 @@ impl DatabaseHeader {
         if self.two_phase_commit {
             result[GOD_BYTE_OFFSET] |= TWO_PHASE_COMMIT;
         }
+        if self.sealed {
+            result[GOD_BYTE_OFFSET] |= SEALED;
+        }
+        if self.compressed_pages {
+            result[GOD_BYTE_OFFSET] |= COMPRESSED_PAGES;
+        }
         result[PAGE_SIZE_OFFSET..(PAGE_SIZE_OFFSET + size_of::<u32>())]
             .copy_from_slice(&self.page_size.to_le_bytes());
         result[REGION_HEADER_PAGES_OFFSET..(REGION_HEADER_PAGES_OFFSET + size_of::<u32>())]
@@ -771,6 +818,34 @@ mod test {
         assert!(matches!(err, DatabaseError::RepairAborted));
     }
 
+    #[test]
+    fn read_only_requires_recovery() {
+        let tmpfile = crate::create_tempfile();
+        let db = Database::builder().create(tmpfile.path()).unwrap();
+        drop(db);
+
+        let mut file = tmpfile.as_file();
+
+        file.seek(SeekFrom::Start(GOD_BYTE_OFFSET as u64)).unwrap();
+        let mut buffer = [0u8; 1];
+        file.read_exact(&mut buffer).unwrap();
+        file.seek(SeekFrom::Start(GOD_BYTE_OFFSET as u64)).unwrap();
+        buffer[0] |= RECOVERY_REQUIRED;
+        file.write_all(&buffer).unwrap();
+        let before = std::fs::read(tmpfile.path()).unwrap();
+
+        assert!(matches!(
+            Database::open_read_only(tmpfile.path()),
+            Err(DatabaseError::RecoveryRequired)
+        ));
+        assert_eq!(before, std::fs::read(tmpfile.path()).unwrap());
+
+        // Once repaired, it can be opened read-only
+        drop(Database::open(tmpfile.path()).unwrap());
+        let db = Database::open_read_only(tmpfile.path()).unwrap();
+        assert_eq!(db.begin_read().unwrap().list_tables().unwrap().count(), 0);
+    }
+
     #[test]
     fn repair_insert_reserve_regression() {
         let tmpfile = crate::create_tempfile();
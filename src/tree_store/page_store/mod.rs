@@ -1,14 +1,18 @@
+mod access_tracker;
 mod backends;
+mod backup;
 mod base;
 mod bitmap;
 mod buddy_allocator;
 mod cached_file;
+mod compression;
 mod fast_hash;
 pub mod file_backend;
 mod header;
 mod layout;
 mod lru_cache;
 mod page_manager;
+mod reader_table;
 mod region;
 mod savepoint;
 #[allow(clippy::pedantic, dead_code)]
@@ -16,6 +20,7 @@ mod xxh3;
 
 pub use backends::InMemoryBackend;
 pub(crate) use backends::ReadOnlyBackend;
+pub(crate) use backup::{apply_backup, write_backup};
 pub(crate) use base::{
     MAX_PAIR_LENGTH, MAX_VALUE_LENGTH, Page, PageHint, PageNumber, PageTrackerPolicy,
 };
@@ -25,8 +30,9 @@ pub(crate) use page_manager::{
     AllocationPolicy, FILE_FORMAT_VERSION3, PageAllocator, PageResolver, ShrinkPolicy,
     TransactionalMemory, xxh3_checksum,
 };
-pub use savepoint::Savepoint;
+pub(crate) use reader_table::ReaderTable;
 pub(crate) use savepoint::SerializedSavepoint;
+pub use savepoint::{Savepoint, SavepointInfo};
 
 pub(super) use base::{PageImpl, PageMut};
 pub(super) use xxh3::hash128_with_seed;
@@ -1,6 +1,7 @@
-use crate::transaction_tracker::TransactionId;
+use crate::transaction_tracker::{ReadLease, TransactionId};
 use crate::transactions::{AllocatorStateKey, AllocatorStateTree, AllocatorStateTreeMut};
 use crate::tree_store::btree_base::{BtreeHeader, Checksum};
+use crate::tree_store::page_store::access_tracker::PageAccessTracker;
 use crate::tree_store::page_store::base::{MAX_PAGE_INDEX, PageHint};
 use crate::tree_store::page_store::buddy_allocator::BuddyAllocator;
 use crate::tree_store::page_store::cached_file::PagedCachedFile;
@@ -9,22 +10,25 @@ use crate::tree_store::page_store::header::{
     DB_HEADER_SIZE, DatabaseHeader, MAGICNUMBER, TransactionHeader, UnrepairedDatabaseHeader,
 };
 use crate::tree_store::page_store::layout::DatabaseLayout;
+use crate::tree_store::page_store::reader_table::ReaderTable;
 use crate::tree_store::page_store::region::{Allocators, RegionTracker};
 use crate::tree_store::page_store::{PageImpl, PageMut, hash128_with_seed};
 use crate::tree_store::{Page, PageNumber, PageTrackerPolicy};
-use crate::{CacheStats, StorageBackend};
+use crate::{CacheStats, CorruptionPolicy, PageCompression, StorageBackend};
 use crate::{DatabaseError, Result, StorageError};
 use std::cmp::{max, min};
 use std::collections::BTreeMap;
-#[cfg(debug_assertions)]
 use std::collections::HashMap;
-#[cfg(debug_assertions)]
 use std::collections::HashSet;
 use std::convert::TryInto;
 use std::io::ErrorKind;
 use std::marker::PhantomData;
+use std::ops::Range;
 use std::sync::Arc;
 use std::sync::Mutex;
+#[cfg(debug_assertions)]
+use std::sync::Weak;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
 use std::thread;
 
 // The region header is optional in the v3 file format
@@ -89,23 +93,61 @@ pub(crate) enum AllocationPolicy {
 /// or `PageResolver::new(mem)` (read-transaction context). Read-only btree
 /// types accept `PageResolver` rather than `Arc<TransactionalMemory>` so that
 /// they cannot be used to bypass `PageAllocator`'s allocation tracking.
+///
+/// A read transaction's resolver holds its `ReadLease` while reading each page, and fails once the
+/// transaction has expired.
 #[derive(Clone)]
 pub(crate) struct PageResolver {
     mem: Arc<TransactionalMemory>,
+    lease: Option<Arc<ReadLease>>,
 }
 
 impl PageResolver {
     pub(crate) fn new(mem: Arc<TransactionalMemory>) -> Self {
-        Self { mem }
+        Self { mem, lease: None }
+    }
+
+    pub(crate) fn with_lease(mem: Arc<TransactionalMemory>, lease: Option<Arc<ReadLease>>) -> Self {
+        #[cfg(debug_assertions)]
+        if let Some(lease) = &lease {
+            mem.register_read_lease(lease);
+        }
+        Self { mem, lease }
     }
 
     pub(crate) fn get_page(&self, page_number: PageNumber, hint: PageHint) -> Result<PageImpl> {
-        self.mem.get_page(page_number, hint)
+        let Some(lease) = &self.lease else {
+            return self.mem.get_page(page_number, hint);
+        };
+        let _hold = lease.hold()?;
+        #[allow(unused_mut)]
+        let mut page = self.mem.get_page(page_number, hint)?;
+        #[cfg(debug_assertions)]
+        page.move_reference(lease.open_pages());
+        Ok(page)
     }
 
     pub(crate) fn count_allocated_pages(&self) -> Result<u64> {
         self.mem.count_allocated_pages()
     }
+
+    pub(crate) fn read_page_range(
+        &self,
+        page_number: PageNumber,
+        range: Range<usize>,
+        hint: PageHint,
+    ) -> Result<Vec<u8>> {
+        let _lease = self.lease.as_ref().map(|x| x.hold()).transpose()?;
+        self.mem.read_page_range(page_number, range, hint)
+    }
+
+    pub(crate) fn get_page_size(&self) -> usize {
+        self.mem.get_page_size()
+    }
+
+    pub(crate) fn corrupted_page(&self, page: &impl Page) -> StorageError {
+        self.mem.corrupted_page(page)
+    }
 }
 
 /// Per-write-transaction handle through which btree mutation code allocates
@@ -116,6 +158,18 @@ pub(crate) struct PageAllocator {
     mem: Arc<TransactionalMemory>,
     policy: AllocationPolicy,
     allocated_since_commit: Arc<Mutex<PageTrackerPolicy>>,
+    counters: Arc<AllocationCounters>,
+    // Set when a mutation fails part way through, and may have left a tree partially modified.
+    // The write transaction shares it, and can't be committed once it is set
+    poisoned: Arc<AtomicBool>,
+}
+
+// Running totals of the allocations made through a `PageAllocator`, for `TransactionStats`
+#[derive(Default)]
+struct AllocationCounters {
+    allocated_pages: AtomicU64,
+    allocated_bytes: AtomicU64,
+    freed_pages: AtomicU64,
 }
 
 impl PageAllocator {
@@ -124,7 +178,44 @@ impl PageAllocator {
             mem,
             policy,
             allocated_since_commit: Arc::new(Mutex::new(PageTrackerPolicy::new_tracking())),
+            counters: Arc::new(AllocationCounters::default()),
+            poisoned: Arc::new(AtomicBool::new(false)),
+        }
+    }
+
+    pub(crate) fn poisoned_flag(&self) -> Arc<AtomicBool> {
+        self.poisoned.clone()
+    }
+
+    // Poisons the write transaction if `result` is an error, since the mutation which returned it
+    // may have been left half done
+    pub(crate) fn poison_on_error<T>(&self, result: Result<T>) -> Result<T> {
+        if result.is_err() {
+            self.poisoned.store(true, Ordering::Release);
         }
+        result
+    }
+
+    /// Number of pages allocated, and their total size in bytes
+    pub(crate) fn allocated(&self) -> (u64, u64) {
+        (
+            self.counters.allocated_pages.load(Ordering::Relaxed),
+            self.counters.allocated_bytes.load(Ordering::Relaxed),
+        )
+    }
+
+    /// Number of pages freed immediately, because they were allocated since the last commit
+    pub(crate) fn freed(&self) -> u64 {
+        self.counters.freed_pages.load(Ordering::Relaxed)
+    }
+
+    fn record_allocation(&self, page: &PageMut) {
+        self.counters
+            .allocated_pages
+            .fetch_add(1, Ordering::Relaxed);
+        self.counters
+            .allocated_bytes
+            .fetch_add(page.memory().len() as u64, Ordering::Relaxed);
     }
 
     /// Returns a `PageResolver` for constructing read-only views of this transaction's pages.
@@ -132,6 +223,10 @@ impl PageAllocator {
         PageResolver::new(self.mem.clone())
     }
 
+    pub(crate) fn corrupted_page(&self, page: &impl Page) -> StorageError {
+        self.mem.corrupted_page(page)
+    }
+
     /// Drains the set of pages allocated since the last commit, returning
     /// them. Used by commit and non-durable commit paths to hand the set over
     /// to `TransactionalMemory`.
@@ -149,6 +244,35 @@ impl PageAllocator {
         }
     }
 
+    /// Starts a nested transaction. Until it ends, the pages allocated before it are treated as
+    /// committed, so that they are copied rather than modified in place. Returns those pages,
+    /// which must be passed to `end_nested()` or `rollback_nested()`
+    pub(crate) fn begin_nested(&self) -> PageNumberHashSet {
+        self.take_allocated_since_commit()
+    }
+
+    /// Ends a nested transaction, keeping the pages allocated within it
+    pub(crate) fn end_nested(&self, outer: PageNumberHashSet) {
+        let mut allocated = self.allocated_since_commit.lock().unwrap();
+        for page in outer {
+            allocated.insert(page);
+        }
+    }
+
+    /// Ends a nested transaction, freeing every page allocated within it
+    pub(crate) fn rollback_nested(
+        &self,
+        outer: PageNumberHashSet,
+        allocated: &mut PageTrackerPolicy,
+    ) {
+        for page in self.take_allocated_since_commit() {
+            allocated.remove_if_present(page);
+            self.counters.freed_pages.fetch_add(1, Ordering::Relaxed);
+            self.mem.free(page, &mut PageTrackerPolicy::Ignore);
+        }
+        self.end_nested(outer);
+    }
+
     pub(crate) fn allocate<'a>(
         &self,
         size: usize,
@@ -158,6 +282,7 @@ impl PageAllocator {
             AllocationPolicy::Default => self.mem.allocate(size, allocated)?,
             AllocationPolicy::Lowest => self.mem.allocate_lowest(size, allocated)?,
         };
+        self.record_allocation(&page);
         self.allocated_since_commit
             .lock()
             .unwrap()
@@ -174,6 +299,7 @@ impl PageAllocator {
         allocated: &mut PageTrackerPolicy,
     ) -> Result<PageMut<'a>> {
         let page = self.mem.allocate_lowest(size, allocated)?;
+        self.record_allocation(&page);
         self.allocated_since_commit
             .lock()
             .unwrap()
@@ -186,6 +312,7 @@ impl PageAllocator {
             .lock()
             .unwrap()
             .remove_if_present(page);
+        self.counters.freed_pages.fetch_add(1, Ordering::Relaxed);
         self.mem.free(page, allocated);
     }
 
@@ -200,6 +327,7 @@ impl PageAllocator {
             .unwrap()
             .remove_if_present(page)
         {
+            self.counters.freed_pages.fetch_add(1, Ordering::Relaxed);
             self.mem.free(page, allocated);
             true
         } else {
@@ -309,12 +437,17 @@ pub(crate) struct TransactionalMemory {
     unpersisted_allocation_txn: Mutex<PageNumberHashMap<TransactionId>>,
     storage: PagedCachedFile,
     state: Mutex<InMemoryState>,
+    // Only present if access tracking was enabled with Builder::set_access_tracking()
+    access_tracker: Option<PageAccessTracker>,
     // The number of PageMut which are outstanding
     #[cfg(debug_assertions)]
     open_dirty_pages: Arc<Mutex<HashSet<PageNumber>>>,
     // Reference counts of PageImpls that are outstanding
     #[cfg(debug_assertions)]
     read_page_ref_counts: Arc<Mutex<HashMap<PageNumber, u64>>>,
+    // Leases of read transactions, which track the PageImpls read through them separately
+    #[cfg(debug_assertions)]
+    read_leases: Mutex<Vec<Weak<ReadLease>>>,
     // Set of all allocated pages for debugging assertions
     #[cfg(debug_assertions)]
     allocated_pages: Arc<Mutex<PageNumberHashSet>>,
@@ -323,6 +456,21 @@ pub(crate) struct TransactionalMemory {
     // code path where there is no locking
     region_size: u64,
     region_header_with_padding_size: u64,
+    corruption_policy: CorruptionPolicy,
+    // Regions containing a corrupted page. Reads of any page in them fail, and no new pages are
+    // allocated in them
+    quarantined_regions: Mutex<HashSet<u32>>,
+    // Fast path for get_page(), which is true if quarantined_regions is non-empty
+    has_quarantined_regions: AtomicBool,
+    // Set if the file is shared with other processes. See Builder::set_multi_process()
+    reader_table: Option<ReaderTable>,
+}
+
+// State of the header of a database file, as it was found on disk before any repair
+pub(crate) struct HeaderStatus {
+    pub(crate) recovery_required: bool,
+    pub(crate) primary_corrupted: bool,
+    pub(crate) secondary_corrupted: bool,
 }
 
 impl TransactionalMemory {
@@ -335,6 +483,57 @@ impl TransactionalMemory {
         cache_size: usize,
         read_only: bool,
     ) -> Result<Self, DatabaseError> {
+        Self::new_inner(
+            file,
+            allow_initialize,
+            page_size,
+            requested_region_size,
+            cache_size,
+            read_only,
+            false,
+        )
+    }
+
+    // Opens the file read-only, even if it requires recovery, so that it can be verified. Nothing
+    // is written to the file
+    pub(crate) fn new_for_verification(
+        file: Box<dyn StorageBackend>,
+        cache_size: usize,
+    ) -> Result<(Self, HeaderStatus), DatabaseError> {
+        let file_len = file.len().map_err(StorageError::from)?;
+        if file_len < DB_HEADER_SIZE as u64 {
+            return Err(StorageError::Corrupted("File is too short".to_string()).into());
+        }
+        let mut header_bytes = vec![0; DB_HEADER_SIZE];
+        file.read(0, &mut header_bytes)
+            .map_err(StorageError::from)?;
+        let unrepaired = UnrepairedDatabaseHeader::from_bytes(&header_bytes)?;
+        let page_size = unrepaired.page_size() as usize;
+        if !page_size.is_power_of_two() || page_size < DB_HEADER_SIZE {
+            return Err(StorageError::Corrupted(format!("Invalid page size: {page_size}")).into());
+        }
+        let status = HeaderStatus {
+            recovery_required: unrepaired.recovery_required(file_len),
+            primary_corrupted: unrepaired.primary_corrupted(),
+            secondary_corrupted: unrepaired.secondary_corrupted(),
+        };
+
+        let mem = Self::new_inner(file, false, page_size, None, cache_size, true, true)?;
+
+        Ok((mem, status))
+    }
+
+    fn new_inner(
+        file: Box<dyn StorageBackend>,
+        allow_initialize: bool,
+        page_size: usize,
+        requested_region_size: Option<u64>,
+        cache_size: usize,
+        read_only: bool,
+        // Open the file, even if it requires recovery. Must only be used with read_only
+        allow_recovery_required: bool,
+    ) -> Result<Self, DatabaseError> {
+        assert!(read_only || !allow_recovery_required);
         assert!(page_size.is_power_of_two() && page_size >= DB_HEADER_SIZE);
 
         let region_size = requested_region_size.unwrap_or(MAX_USABLE_REGION_SPACE);
@@ -425,13 +624,22 @@ impl TransactionalMemory {
         let unrepaired = UnrepairedDatabaseHeader::from_bytes(&header_bytes)?;
 
         assert_eq!(unrepaired.page_size() as usize, page_size);
+        if unrepaired.sealed() && !read_only {
+            return Err(DatabaseError::DatabaseSealed);
+        }
+        if unrepaired.compressed_pages() {
+            if !cfg!(feature = "lz4") {
+                return Err(DatabaseError::PageCompressionUnsupported);
+            }
+            storage.mark_compressed_pages();
+        }
         let file_len = storage.raw_file_len()?;
         let needs_recovery = unrepaired.recovery_required(file_len);
-        if needs_recovery && read_only {
-            return Err(DatabaseError::RepairAborted);
+        if needs_recovery && read_only && !allow_recovery_required {
+            return Err(DatabaseError::RecoveryRequired);
         }
         let (header, _) = unrepaired.finalize(file_len)?;
-        if needs_recovery {
+        if needs_recovery && !read_only {
             storage
                 .write(0, DB_HEADER_SIZE, true)?
                 .mem_mut()
@@ -440,7 +648,8 @@ impl TransactionalMemory {
         }
 
         let layout = header.layout();
-        assert_eq!(layout.len(), storage.raw_file_len()?);
+        // If recovery is allowed, another process may be resizing the file
+        assert!(allow_recovery_required || layout.len() == storage.raw_file_len()?);
         let region_size = layout.full_region_layout().len();
         let region_header_size = layout.full_region_layout().data_section().start;
         let state = InMemoryState::new(header);
@@ -453,22 +662,225 @@ impl TransactionalMemory {
             unpersisted_allocation_txn: Mutex::new(PageNumberHashMap::default()),
             storage,
             state: Mutex::new(state),
+            access_tracker: None,
             #[cfg(debug_assertions)]
             open_dirty_pages: Arc::new(Mutex::new(HashSet::new())),
             #[cfg(debug_assertions)]
             read_page_ref_counts: Arc::new(Mutex::new(HashMap::new())),
             #[cfg(debug_assertions)]
+            read_leases: Mutex::new(vec![]),
+            #[cfg(debug_assertions)]
             allocated_pages: Arc::new(Mutex::new(PageNumberHashSet::default())),
             page_size: page_size.try_into().unwrap(),
             region_size,
             region_header_with_padding_size: region_header_size,
+            corruption_policy: CorruptionPolicy::Panic,
+            quarantined_regions: Mutex::new(HashSet::new()),
+            has_quarantined_regions: AtomicBool::new(false),
+            reader_table: None,
         })
     }
 
+    // Opens a file which may be open for writing by another process. The header is reloaded from
+    // the file by reload_header(), and nothing is written to the file, except to the reader table
+    pub(crate) fn new_multi_process_reader(
+        file: Box<dyn StorageBackend>,
+        page_size: usize,
+        cache_size: usize,
+        reader_table: ReaderTable,
+    ) -> Result<Self, DatabaseError> {
+        // The writer keeps the recovery flag set while it has the file open
+        let writer_active = reader_table.writer_active()?;
+        let mut mem = Self::new_inner(
+            file,
+            false,
+            page_size,
+            None,
+            cache_size,
+            true,
+            writer_active,
+        )?;
+        mem.reader_table = Some(reader_table);
+        mem.reload_header()?;
+
+        Ok(mem)
+    }
+
+    pub(crate) fn set_reader_table(&mut self, reader_table: ReaderTable) {
+        self.reader_table = Some(reader_table);
+    }
+
+    pub(crate) fn is_multi_process(&self) -> bool {
+        self.reader_table.is_some()
+    }
+
+    // Returns the oldest transaction which another process may read
+    pub(crate) fn oldest_external_reader(&self) -> Result<Option<TransactionId>> {
+        self.reader_table
+            .as_ref()
+            .map_or(Ok(None), ReaderTable::oldest_reader)
+    }
+
+    // Records that this process may read the transaction returned by `oldest`, or any later
+    // transaction. None means that it has no read transactions
+    pub(crate) fn publish_oldest_reader(
+        &self,
+        oldest: impl FnOnce() -> Option<TransactionId>,
+    ) -> Result {
+        self.reader_table
+            .as_ref()
+            .expect("Only used in multi-process mode")
+            .publish(oldest)
+    }
+
+    fn read_primary_header(&self) -> Result<DatabaseHeader> {
+        // The header may be read while another process is writing it. Retry until the primary
+        // slot has a valid checksum
+        let mut attempts = 0;
+        loop {
+            let header_bytes = self.storage.read_direct(0, DB_HEADER_SIZE)?;
+            let unrepaired =
+                UnrepairedDatabaseHeader::from_bytes(&header_bytes).map_err(|err| match err {
+                    DatabaseError::Storage(storage) => storage,
+                    err => StorageError::Corrupted(err.to_string()),
+                })?;
+            if let Some(header) = unrepaired.into_primary() {
+                return Ok(header);
+            }
+            attempts += 1;
+            if attempts == 100 {
+                return Err(StorageError::Corrupted(
+                    "Primary commit slot is corrupted".to_string(),
+                ));
+            }
+            thread::yield_now();
+        }
+    }
+
+    // Returns the latest durable transaction in the file, which may have been committed by another
+    // process
+    pub(crate) fn durable_transaction_id_in_file(&self) -> Result<TransactionId> {
+        Ok(self.read_primary_header()?.primary_slot().transaction_id)
+    }
+
+    // Loads the latest durable commit from the file, which may have been made by another process,
+    // and returns its transaction id
+    pub(crate) fn reload_header(&self) -> Result<TransactionId> {
+        let header = self.read_primary_header()?;
+        let transaction_id = header.primary_slot().transaction_id;
+        let mut state = self.state.lock().unwrap();
+        if state.header.primary_slot().transaction_id != transaction_id {
+            // Pages which are not reachable from a transaction that this process may read can be
+            // reused by the writer, so cached copies of them may be stale
+            self.storage.invalidate_cache_all();
+        }
+        if header.compressed_pages {
+            self.storage.mark_compressed_pages();
+        }
+        state.header = header;
+        state.read_from_secondary = false;
+
+        Ok(transaction_id)
+    }
+
+    pub(crate) fn set_corruption_policy(&mut self, policy: CorruptionPolicy) {
+        self.corruption_policy = policy;
+    }
+
+    // Once compression is enabled, the file is marked as possibly containing compressed pages.
+    // Takes effect on the next commit
+    pub(crate) fn set_page_compression(&mut self, compression: PageCompression) {
+        if compression != PageCompression::None {
+            self.state.get_mut().unwrap().header.compressed_pages = true;
+        }
+        self.storage.set_compression(compression);
+    }
+
+    pub(crate) fn set_access_tracking(&mut self, enabled: bool) {
+        self.access_tracker = enabled.then(PageAccessTracker::new);
+    }
+
+    pub(crate) fn access_tracking_enabled(&self) -> bool {
+        self.access_tracker.is_some()
+    }
+
+    // Called when a page is found to be corrupted in the middle of an operation. Depending on the
+    // corruption policy, either panics or returns the error to fail the operation with
+    pub(crate) fn corrupted_page(&self, page: &impl Page) -> StorageError {
+        let message = format!(
+            "Page {:?} has invalid type {}",
+            page.get_page_number(),
+            page.memory()[0]
+        );
+        match self.corruption_policy {
+            CorruptionPolicy::Panic => panic!("{message}"),
+            CorruptionPolicy::ReturnError => StorageError::Corrupted(message),
+        }
+    }
+
+    // Returns true if the region was not already quarantined
+    pub(crate) fn quarantine_region(&self, region: u32) -> bool {
+        let mut quarantined = self.quarantined_regions.lock().unwrap();
+        let inserted = quarantined.insert(region);
+        self.has_quarantined_regions.store(true, Ordering::Release);
+        inserted
+    }
+
+    pub(crate) fn quarantined_regions(&self) -> Vec<u32> {
+        let mut result: Vec<u32> = self
+            .quarantined_regions
+            .lock()
+            .unwrap()
+            .iter()
+            .copied()
+            .collect();
+        result.sort_unstable();
+        result
+    }
+
+    pub(crate) fn clear_quarantine(&self) {
+        let regions = {
+            let mut quarantined = self.quarantined_regions.lock().unwrap();
+            self.has_quarantined_regions.store(false, Ordering::Release);
+            std::mem::take(&mut *quarantined)
+        };
+        // Allocation marks quarantined regions as full. The region tracker is only a hint, so it's
+        // safe to optimistically mark them as free again
+        let mut state = self.state.lock().unwrap();
+        if state.allocators.is_some() {
+            for region in regions {
+                if (region as usize) < state.allocators().region_allocators.len() {
+                    let order = state.get_region(region).get_max_order();
+                    state.get_region_tracker_mut().mark_free(order, region);
+                }
+            }
+        }
+    }
+
+    fn check_quarantine(&self, page_number: PageNumber) -> Result {
+        if self.has_quarantined_regions.load(Ordering::Acquire)
+            && self
+                .quarantined_regions
+                .lock()
+                .unwrap()
+                .contains(&page_number.region)
+        {
+            return Err(StorageError::Corrupted(format!(
+                "Page {page_number:?} is in quarantined region {}",
+                page_number.region
+            )));
+        }
+        Ok(())
+    }
+
     pub(crate) fn cache_stats(&self) -> CacheStats {
         self.storage.cache_stats()
     }
 
+    pub(crate) fn write_buffer_bytes(&self) -> usize {
+        self.storage.write_buffer_bytes()
+    }
+
     pub(crate) fn check_io_errors(&self) -> Result {
         self.storage.check_io_errors()
     }
@@ -564,6 +976,11 @@ impl TransactionalMemory {
         self.state.lock().unwrap().header.two_phase_commit
     }
 
+    // Marks the file as sealed. Takes effect on the next commit
+    pub(crate) fn seal(&self) {
+        self.state.lock().unwrap().header.sealed = true;
+    }
+
     pub(crate) fn allocator_hash(&self) -> u128 {
         self.state.lock().unwrap().allocators().xxh3_hash()
     }
@@ -751,6 +1168,15 @@ impl TransactionalMemory {
         Ok(())
     }
 
+    // Checks the loaded allocator state, rather than the debug tracking used by is_allocated()
+    pub(crate) fn is_page_allocated(&self, page: PageNumber) -> bool {
+        let state = self.state.lock().unwrap();
+        page.region < state.header.layout().num_regions()
+            && state
+                .get_region(page.region)
+                .is_allocated(page.page_index, page.page_order)
+    }
+
     #[cfg_attr(not(debug_assertions), expect(unused_variables))]
     pub(crate) fn is_allocated(&self, page: PageNumber) -> bool {
         #[cfg(debug_assertions)]
@@ -778,6 +1204,9 @@ impl TransactionalMemory {
         // to future read transactions
         self.debug_assert_no_dirty_pages();
         self.storage.check_io_errors()?;
+        // Other processes read the file as soon as the primary slot changes, so all of the pages
+        // must be written first
+        let two_phase = two_phase || self.reader_table.is_some();
 
         let mut state = self.state.lock().unwrap();
         // Trim surplus file space, before finalizing the commit
@@ -831,6 +1260,9 @@ impl TransactionalMemory {
         state.header = header;
         state.read_from_secondary = false;
         drop(state);
+        if let Some(tracker) = &self.access_tracker {
+            tracker.tick();
+        }
 
         Ok(())
     }
@@ -860,11 +1292,43 @@ impl TransactionalMemory {
         secondary.user_root = data_root;
         secondary.system_root = system_root;
         state.read_from_secondary = true;
+        drop(state);
+        if let Some(tracker) = &self.access_tracker {
+            tracker.tick();
+        }
 
         Ok(())
     }
 
+    #[cfg(debug_assertions)]
+    fn register_read_lease(&self, lease: &Arc<ReadLease>) {
+        let mut leases = self.read_leases.lock().unwrap();
+        leases.retain(|x| x.strong_count() > 0);
+        leases.push(Arc::downgrade(lease));
+    }
+
+    #[cfg(debug_assertions)]
+    fn is_page_referenced(&self, page: PageNumber) -> bool {
+        if self
+            .read_page_ref_counts
+            .lock()
+            .unwrap()
+            .contains_key(&page)
+        {
+            return true;
+        }
+        let leases: Vec<Arc<ReadLease>> = self
+            .read_leases
+            .lock()
+            .unwrap()
+            .iter()
+            .filter_map(Weak::upgrade)
+            .collect();
+        leases.iter().any(|lease| lease.holds_page(page))
+    }
+
     pub(crate) fn get_page(&self, page_number: PageNumber, hint: PageHint) -> Result<PageImpl> {
+        self.check_quarantine(page_number)?;
         let range = page_number.address_range(
             self.page_size.into(),
             self.region_size,
@@ -873,6 +1337,9 @@ impl TransactionalMemory {
         );
         let len: usize = (range.end - range.start).try_into().unwrap();
         let mem = self.storage.read(range.start, len, hint)?;
+        if let Some(tracker) = &self.access_tracker {
+            tracker.record(page_number);
+        }
 
         // We must not retrieve an immutable reference to a page which already has a mutable ref to it
         #[cfg(debug_assertions)]
@@ -896,17 +1363,38 @@ impl TransactionalMemory {
         })
     }
 
+    // Reads part of a page, without reading the rest of it from the file. `range` is truncated to
+    // the length of the page
+    pub(crate) fn read_page_range(
+        &self,
+        page_number: PageNumber,
+        range: Range<usize>,
+        hint: PageHint,
+    ) -> Result<Vec<u8>> {
+        self.check_quarantine(page_number)?;
+        let address = page_number.address_range(
+            self.page_size.into(),
+            self.region_size,
+            self.region_header_with_padding_size,
+            self.page_size,
+        );
+        let len: usize = (address.end - address.start).try_into().unwrap();
+        let end = range.end.min(len);
+        let start = range.start.min(end);
+        let data = self
+            .storage
+            .read_range(address.start, len, start..end, hint)?;
+        if let Some(tracker) = &self.access_tracker {
+            tracker.record(page_number);
+        }
+        Ok(data)
+    }
+
     // NOTE: the caller must ensure that the read cache has been invalidated or stale reads my occur
     pub(crate) fn get_page_mut<'txn>(&self, page_number: PageNumber) -> Result<PageMut<'txn>> {
         #[cfg(debug_assertions)]
         {
-            assert!(
-                !self
-                    .read_page_ref_counts
-                    .lock()
-                    .unwrap()
-                    .contains_key(&page_number)
-            );
+            assert!(!self.is_page_referenced(page_number));
             assert!(!self.open_dirty_pages.lock().unwrap().contains(&page_number));
         }
 
@@ -935,6 +1423,32 @@ impl TransactionalMemory {
         })
     }
 
+    // Approximate number of commits between the last reset of the access tracker and the most
+    // recent access to `page`. Zero if the page has not been accessed since the reset
+    pub(crate) fn page_recency(&self, page: PageNumber) -> u32 {
+        self.access_tracker
+            .as_ref()
+            .map_or(0, |tracker| tracker.recency(page))
+    }
+
+    pub(crate) fn pause_access_tracking(&self) {
+        if let Some(tracker) = &self.access_tracker {
+            tracker.pause();
+        }
+    }
+
+    pub(crate) fn transfer_access_recency(&self, relocations: &HashMap<PageNumber, PageNumber>) {
+        if let Some(tracker) = &self.access_tracker {
+            tracker.transfer(relocations);
+        }
+    }
+
+    pub(crate) fn reset_access_tracking(&self) {
+        if let Some(tracker) = &self.access_tracker {
+            tracker.reset();
+        }
+    }
+
     pub(crate) fn get_version(&self) -> u8 {
         let state = self.state.lock().unwrap();
         state.latest_slot().version
@@ -950,6 +1464,13 @@ impl TransactionalMemory {
         state.latest_slot().system_root
     }
 
+    // The data and system roots of the latest commit, read together so that they are consistent
+    pub(crate) fn get_roots(&self) -> (Option<BtreeHeader>, Option<BtreeHeader>) {
+        let state = self.state.lock().unwrap();
+        let slot = state.latest_slot();
+        (slot.user_root, slot.system_root)
+    }
+
     pub(crate) fn get_last_committed_transaction_id(&self) -> Result<TransactionId> {
         let state = self.state.lock()?;
         Ok(state.latest_slot().transaction_id)
@@ -990,13 +1511,7 @@ impl TransactionalMemory {
     fn free_helper(&self, page: PageNumber, allocated: &mut PageTrackerPolicy) {
         #[cfg(debug_assertions)]
         {
-            assert!(
-                !self
-                    .read_page_ref_counts
-                    .lock()
-                    .unwrap()
-                    .contains_key(&page)
-            );
+            assert!(!self.is_page_referenced(page));
             assert!(self.allocated_pages.lock().unwrap().remove(&page));
             assert!(!self.open_dirty_pages.lock().unwrap().contains(&page));
         }
@@ -1025,6 +1540,16 @@ impl TransactionalMemory {
         self.storage.cancel_pending_write(address_range.start, len);
     }
 
+    // Total size of the pages written by non-durable commits since the last durable commit
+    pub(crate) fn unpersisted_bytes(&self) -> u64 {
+        self.unpersisted
+            .lock()
+            .unwrap()
+            .iter()
+            .map(|page| page.page_size_bytes(self.page_size))
+            .sum()
+    }
+
     // Frees the page if no durable commit has occurred, since it was allocated. Returns true, if the page was freed
     pub(crate) fn free_if_unpersisted(
         &self,
@@ -1096,26 +1621,34 @@ impl TransactionalMemory {
         let required_pages = allocation_size.div_ceil(self.get_page_size());
         let required_order = ceil_log2(required_pages);
 
+        // Copied, so that the quarantine lock is never held at the same time as the state lock
+        let quarantined = if self.has_quarantined_regions.load(Ordering::Acquire) {
+            self.quarantined_regions.lock().unwrap().clone()
+        } else {
+            HashSet::new()
+        };
         let mut state = self.state.lock().unwrap();
 
         let page_number = if let Some(page_number) =
-            Self::allocate_helper_retry(&mut state, required_order, lowest)?
+            Self::allocate_helper_retry(&mut state, required_order, lowest, &quarantined)?
         {
             page_number
         } else {
             self.grow(&mut state, required_order)?;
-            Self::allocate_helper_retry(&mut state, required_order, lowest)?.unwrap()
+            // grow() may have extended the last region, which can be quarantined
+            Self::allocate_helper_retry(&mut state, required_order, lowest, &quarantined)?
+                .ok_or_else(|| {
+                    StorageError::Corrupted(format!(
+                        "Unable to allocate a page of order {required_order}, because the last region is quarantined"
+                    ))
+                })?
         };
 
         #[cfg(debug_assertions)]
         {
             assert!(self.allocated_pages.lock().unwrap().insert(page_number));
             assert!(
-                !self
-                    .read_page_ref_counts
-                    .lock()
-                    .unwrap()
-                    .contains_key(&page_number),
+                !self.is_page_referenced(page_number),
                 "Allocated a page that is still referenced! {page_number:?}"
             );
             assert!(!self.open_dirty_pages.lock().unwrap().contains(&page_number));
@@ -1156,12 +1689,19 @@ impl TransactionalMemory {
         state: &mut InMemoryState,
         required_order: u8,
         lowest: bool,
+        quarantined: &HashSet<u32>,
     ) -> Result<Option<PageNumber>> {
         loop {
             let Some(candidate_region) = state.get_region_tracker_mut().find_free(required_order)
             else {
                 return Ok(None);
             };
+            if quarantined.contains(&candidate_region) {
+                state
+                    .get_region_tracker_mut()
+                    .mark_full(required_order, candidate_region);
+                continue;
+            }
             let region = state.get_region_mut(candidate_region);
             let r = if lowest {
                 region.alloc_lowest(required_order)
@@ -1304,6 +1844,10 @@ impl TransactionalMemory {
         self.page_size.try_into().unwrap()
     }
 
+    pub(super) fn get_layout(&self) -> DatabaseLayout {
+        self.state.lock().unwrap().header.layout()
+    }
+
     pub(crate) fn close(&self) -> Result {
         if self.storage.check_io_errors().is_ok() && !thread::panicking() {
             let mut state = self.state.lock()?;
@@ -0,0 +1,222 @@
+use crate::transaction_tracker::TransactionId;
+use crate::tree_store::page_store::header::DB_HEADER_SIZE;
+use crate::{DatabaseError, Result};
+use std::fs::File;
+use std::io;
+use std::sync::Mutex;
+
+// The reader table is stored in the unused space of the super-header page, after the database
+// header. It allows one process to write to the database, while others read from it.
+//
+// 8 bytes: reserved. The first byte is locked by the process that has the database open for
+// writing
+//
+// Then for each reader slot:
+// 8 bytes: one plus the oldest transaction that the process which owns the slot may read, or zero
+//
+// A slot is owned by the process which holds an exclusive lock on its first byte. Slots whose lock
+// is not held were owned by a process that exited without releasing them, and are ignored.
+const WRITER_LOCK_OFFSET: u64 = DB_HEADER_SIZE as u64;
+const SLOTS_OFFSET: u64 = WRITER_LOCK_OFFSET + 8;
+const SLOT_SIZE: u64 = 8;
+
+pub(crate) struct ReaderTable {
+    // A handle to the database file, which shares its locks with the handle used by the backend
+    file: File,
+    slots: u64,
+    // The offset of the slot owned by this process, if it has the database open for reading.
+    // Otherwise this process holds the writer lock
+    slot: Option<u64>,
+    // Held while the oldest transaction is computed and published, so that a stale value can't
+    // overwrite a newer one
+    publish_lock: Mutex<()>,
+}
+
+impl ReaderTable {
+    // Registers the calling process as the only writer. Returns `DatabaseAlreadyOpen` if another
+    // process has the database open for writing
+    pub(crate) fn new_writer(file: File, page_size: usize) -> Result<Self, DatabaseError> {
+        if !lock::try_lock(&file, WRITER_LOCK_OFFSET)? {
+            return Err(DatabaseError::DatabaseAlreadyOpen);
+        }
+
+        Ok(Self {
+            file,
+            slots: Self::slot_count(page_size),
+            slot: None,
+            publish_lock: Mutex::new(()),
+        })
+    }
+
+    // Claims a reader slot for the calling process
+    pub(crate) fn new_reader(file: File, page_size: usize) -> Result<Self, DatabaseError> {
+        let slots = Self::slot_count(page_size);
+        for i in 0..slots {
+            let offset = SLOTS_OFFSET + i * SLOT_SIZE;
+            if lock::try_lock(&file, offset)? {
+                let table = Self {
+                    file,
+                    slots,
+                    slot: Some(offset),
+                    publish_lock: Mutex::new(()),
+                };
+                table.write_slot(offset, 0)?;
+                return Ok(table);
+            }
+        }
+
+        Err(io::Error::other("All reader slots of the database are in use").into())
+    }
+
+    fn slot_count(page_size: usize) -> u64 {
+        (page_size as u64 - SLOTS_OFFSET) / SLOT_SIZE
+    }
+
+    fn write_slot(&self, offset: u64, value: u64) -> Result {
+        write_all_at(&self.file, &value.to_le_bytes(), offset)?;
+        Ok(())
+    }
+
+    // Returns true if another process has the database open for writing
+    pub(crate) fn writer_active(&self) -> Result<bool> {
+        Ok(lock::is_locked(&self.file, WRITER_LOCK_OFFSET)?)
+    }
+
+    // Records that this process may read the transaction returned by `oldest`, or any later
+    // transaction, or clears the slot if it returns None. The writer does not free pages which are
+    // reachable from it
+    pub(crate) fn publish(&self, oldest: impl FnOnce() -> Option<TransactionId>) -> Result {
+        let offset = self
+            .slot
+            .expect("Only readers publish their oldest transaction");
+        let _guard = self.publish_lock.lock()?;
+        self.write_slot(offset, oldest().map_or(0, |id| id.raw_id() + 1))
+    }
+
+    // Returns the oldest transaction which another process may read
+    pub(crate) fn oldest_reader(&self) -> Result<Option<TransactionId>> {
+        let mut buffer = vec![0; (self.slots * SLOT_SIZE).try_into().unwrap()];
+        read_exact_at(&self.file, &mut buffer, SLOTS_OFFSET)?;
+        let mut oldest: Option<TransactionId> = None;
+        for (i, slot) in buffer
+            .chunks_exact(SLOT_SIZE.try_into().unwrap())
+            .enumerate()
+        {
+            let value = u64::from_le_bytes(slot.try_into().unwrap());
+            if value == 0 {
+                continue;
+            }
+            let offset = SLOTS_OFFSET + i as u64 * SLOT_SIZE;
+            if Some(offset) != self.slot && lock::is_locked(&self.file, offset)? {
+                let transaction_id = TransactionId::new(value - 1);
+                oldest = Some(oldest.map_or(transaction_id, |x| x.min(transaction_id)));
+            }
+        }
+
+        Ok(oldest)
+    }
+}
+
+impl Drop for ReaderTable {
+    fn drop(&mut self) {
+        let offset = self.slot.unwrap_or(WRITER_LOCK_OFFSET);
+        if self.slot.is_some() {
+            let _ = self.write_slot(offset, 0);
+        }
+        let _ = lock::unlock(&self.file, offset);
+    }
+}
+
+#[cfg(unix)]
+fn read_exact_at(file: &File, buffer: &mut [u8], offset: u64) -> io::Result<()> {
+    std::os::unix::fs::FileExt::read_exact_at(file, buffer, offset)
+}
+
+#[cfg(unix)]
+fn write_all_at(file: &File, data: &[u8], offset: u64) -> io::Result<()> {
+    std::os::unix::fs::FileExt::write_all_at(file, data, offset)
+}
+
+#[cfg(not(unix))]
+fn read_exact_at(_: &File, _: &mut [u8], _: u64) -> io::Result<()> {
+    Err(io::ErrorKind::Unsupported.into())
+}
+
+#[cfg(not(unix))]
+fn write_all_at(_: &File, _: &[u8], _: u64) -> io::Result<()> {
+    Err(io::ErrorKind::Unsupported.into())
+}
+
+// Locks on single bytes of the file. These are open file description locks, so unlike POSIX record
+// locks they conflict with the locks of other handles in the same process, and aren't released
+// when an unrelated handle to the file is closed
+#[cfg(any(target_os = "linux", target_os = "android"))]
+mod lock {
+    use std::fs::File;
+    use std::io;
+    use std::os::fd::AsRawFd;
+
+    fn request(lock_type: libc::c_int, offset: u64) -> libc::flock {
+        // SAFETY: flock is a plain C struct, for which all zeros is a valid value
+        let mut request: libc::flock = unsafe { std::mem::zeroed() };
+        request.l_type = libc::c_short::try_from(lock_type).unwrap();
+        request.l_whence = libc::c_short::try_from(libc::SEEK_SET).unwrap();
+        request.l_start = libc::off_t::try_from(offset).unwrap();
+        request.l_len = 1;
+        request
+    }
+
+    fn fcntl(file: &File, command: libc::c_int, request: &mut libc::flock) -> io::Result<()> {
+        // SAFETY: the file descriptor is valid for the lifetime of `file`, and `request` is a valid
+        // flock struct
+        if unsafe { libc::fcntl(file.as_raw_fd(), command, std::ptr::from_mut(request)) } == -1 {
+            return Err(io::Error::last_os_error());
+        }
+        Ok(())
+    }
+
+    // Returns false if the byte is locked by another handle
+    pub(super) fn try_lock(file: &File, offset: u64) -> io::Result<bool> {
+        let mut request = request(libc::F_WRLCK, offset);
+        match fcntl(file, libc::F_OFD_SETLK, &mut request) {
+            Ok(()) => Ok(true),
+            Err(err) if matches!(err.raw_os_error(), Some(libc::EACCES | libc::EAGAIN)) => {
+                Ok(false)
+            }
+            Err(err) => Err(err),
+        }
+    }
+
+    pub(super) fn unlock(file: &File, offset: u64) -> io::Result<()> {
+        let mut request = request(libc::F_UNLCK, offset);
+        fcntl(file, libc::F_OFD_SETLK, &mut request)
+    }
+
+    // Returns true if the byte is locked by another handle
+    pub(super) fn is_locked(file: &File, offset: u64) -> io::Result<bool> {
+        let mut request = request(libc::F_WRLCK, offset);
+        fcntl(file, libc::F_OFD_GETLK, &mut request)?;
+        Ok(request.l_type != libc::c_short::try_from(libc::F_UNLCK).unwrap())
+    }
+}
+
+#[cfg(not(any(target_os = "linux", target_os = "android")))]
+mod lock {
+    use std::fs::File;
+    use std::io;
+
+    pub(super) fn try_lock(_: &File, _: u64) -> io::Result<bool> {
+        Err(io::Error::new(
+            io::ErrorKind::Unsupported,
+            "Multi-process mode is not supported on this platform",
+        ))
+    }
+
+    pub(super) fn unlock(_: &File, _: u64) -> io::Result<()> {
+        Err(io::ErrorKind::Unsupported.into())
+    }
+
+    pub(super) fn is_locked(_: &File, _: u64) -> io::Result<bool> {
+        Err(io::ErrorKind::Unsupported.into())
+    }
+}
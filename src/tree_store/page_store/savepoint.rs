@@ -5,6 +5,7 @@ use crate::{TypeName, Value};
 use std::fmt::Debug;
 use std::mem::size_of;
 use std::sync::Arc;
+use std::time::SystemTime;
 
 // on-disk format:
 // * 1 byte: version
@@ -83,6 +84,62 @@ impl Drop for Savepoint {
     }
 }
 
+/// Information about a persistent savepoint
+///
+/// Returned by [`WriteTransaction::list_persistent_savepoint_info`]
+///
+/// [`WriteTransaction::list_persistent_savepoint_info`]: crate::WriteTransaction::list_persistent_savepoint_info
+#[derive(Clone, Debug)]
+pub struct SavepointInfo {
+    id: u64,
+    transaction_id: u64,
+    created: Option<SystemTime>,
+    label: Option<String>,
+}
+
+impl SavepointInfo {
+    pub(crate) fn new(
+        savepoint: &Savepoint,
+        created: Option<SystemTime>,
+        label: Option<String>,
+    ) -> Self {
+        Self {
+            id: savepoint.id.0,
+            transaction_id: savepoint.transaction_id.raw_id(),
+            created,
+            label,
+        }
+    }
+
+    /// The id of the savepoint, which can be passed to
+    /// [`WriteTransaction::get_persistent_savepoint`]
+    ///
+    /// [`WriteTransaction::get_persistent_savepoint`]: crate::WriteTransaction::get_persistent_savepoint
+    pub fn id(&self) -> u64 {
+        self.id
+    }
+
+    /// The id of the latest transaction included in the savepoint
+    pub fn transaction_id(&self) -> u64 {
+        self.transaction_id
+    }
+
+    /// When the savepoint was created
+    ///
+    /// Returns `None` for savepoints created by versions of redb which did not record it
+    pub fn created(&self) -> Option<SystemTime> {
+        self.created
+    }
+
+    /// The label the savepoint was created with, by
+    /// [`WriteTransaction::persistent_savepoint_with_label`]
+    ///
+    /// [`WriteTransaction::persistent_savepoint_with_label`]: crate::WriteTransaction::persistent_savepoint_with_label
+    pub fn label(&self) -> Option<&str> {
+        self.label.as_deref()
+    }
+}
+
 #[derive(Debug)]
 pub(crate) enum SerializedSavepoint<'a> {
     Ref(&'a [u8]),
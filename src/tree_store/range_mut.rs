@@ -0,0 +1,336 @@
+use crate::Result;
+use crate::tree_store::btree_base::{
+    BRANCH, BranchAccessor, BranchMutator, DEFERRED, LEAF, LeafAccessor, LeafBuilder, LeafMutator,
+};
+use crate::tree_store::page_store::PageMut;
+use crate::tree_store::{
+    BtreeHeader, Page, PageAllocator, PageHint, PageNumber, PageTrackerPolicy,
+};
+use crate::types::{Key, MutInPlaceValue, Value};
+use std::borrow::Borrow;
+use std::cmp::Ordering;
+use std::marker::PhantomData;
+use std::ops::Bound;
+use std::sync::{Arc, Mutex};
+
+/// A cursor over a range of a [`Table`](crate::Table), whose values can be modified in place
+///
+/// Returned by [`Table::range_mut`](crate::Table::range_mut). Entries are visited in key order, by
+/// calling [`Self::next_entry`].
+pub struct RangeMut<'a, K: Key + 'static, V: Value + 'static> {
+    root: &'a mut Option<BtreeHeader>,
+    page_allocator: PageAllocator,
+    freed: Arc<Mutex<Vec<PageNumber>>>,
+    allocated: Arc<Mutex<PageTrackerPolicy>>,
+    // Branch pages from the root to the current leaf, and the index of the child taken in each.
+    // Every page on the path is uncommitted, so it can be modified in place
+    path: Vec<(PageMut<'a>, usize)>,
+    leaf: Option<PageMut<'a>>,
+    // Index in the current leaf of the next entry to visit
+    position: usize,
+    end: Bound<Vec<u8>>,
+    _key_type: PhantomData<K>,
+    _value_type: PhantomData<V>,
+}
+
+impl<'a, K: Key + 'static, V: Value + 'static> RangeMut<'a, K, V> {
+    pub(crate) fn new(
+        root: &'a mut Option<BtreeHeader>,
+        page_allocator: PageAllocator,
+        freed: Arc<Mutex<Vec<PageNumber>>>,
+        allocated: Arc<Mutex<PageTrackerPolicy>>,
+        start: Bound<Vec<u8>>,
+        end: Bound<Vec<u8>>,
+    ) -> Result<Self> {
+        let mut result = Self {
+            root,
+            page_allocator,
+            freed,
+            allocated,
+            path: vec![],
+            leaf: None,
+            position: 0,
+            end,
+            _key_type: PhantomData,
+            _value_type: PhantomData,
+        };
+        let empty_range = match (&start, &result.end) {
+            (Bound::Included(start), Bound::Included(end)) => {
+                K::compare(start, end) == Ordering::Greater
+            }
+            (Bound::Included(start) | Bound::Excluded(start), Bound::Excluded(end))
+            | (Bound::Excluded(start), Bound::Included(end)) => {
+                K::compare(start, end) != Ordering::Less
+            }
+            _ => false,
+        };
+        if !empty_range {
+            let seek = result.seek(&start);
+            result.page_allocator.poison_on_error(seek)?;
+        }
+        Ok(result)
+    }
+
+    /// Returns the next entry in the range, or `None` if there are no more
+    pub fn next_entry(&mut self) -> Option<Result<RangeMutEntry<'_, 'a, K, V>>> {
+        match self.advance() {
+            Ok(true) => {}
+            Ok(false) => return None,
+            Err(err) => {
+                self.finish();
+                return Some(self.page_allocator.poison_on_error(Err(err)));
+            }
+        }
+        let index = self.position;
+        self.position += 1;
+        Some(Ok(RangeMutEntry { range: self, index }))
+    }
+
+    // Positions the cursor at the next entry in the range. Returns false if there are no more
+    fn advance(&mut self) -> Result<bool> {
+        loop {
+            let Some(leaf) = &self.leaf else {
+                return Ok(false);
+            };
+            let accessor = LeafAccessor::new(leaf.memory(), K::fixed_width(), V::fixed_width());
+            if self.position < accessor.num_pairs() {
+                let key = accessor.entry(self.position).unwrap().key();
+                let in_range = match &self.end {
+                    Bound::Included(end) => K::compare(key, end) != Ordering::Greater,
+                    Bound::Excluded(end) => K::compare(key, end) == Ordering::Less,
+                    Bound::Unbounded => true,
+                };
+                if !in_range {
+                    self.finish();
+                }
+                return Ok(in_range);
+            }
+            self.next_leaf()?;
+        }
+    }
+
+    fn finish(&mut self) {
+        self.leaf = None;
+        self.path.clear();
+    }
+
+    // Descends from the root to the leaf which contains the first key in the range
+    fn seek(&mut self, start: &Bound<Vec<u8>>) -> Result {
+        let Some(header) = self.root.as_mut() else {
+            return Ok(());
+        };
+        let (mut page, moved) = make_mut(
+            &self.page_allocator,
+            &self.freed,
+            &self.allocated,
+            header.root,
+        )?;
+        if moved {
+            header.root = page.get_page_number();
+            header.checksum = DEFERRED;
+        }
+        loop {
+            match page.memory()[0] {
+                LEAF => {
+                    let accessor =
+                        LeafAccessor::new(page.memory(), K::fixed_width(), V::fixed_width());
+                    self.position = match start {
+                        Bound::Included(key) => accessor.position::<K>(key).0,
+                        Bound::Excluded(key) => {
+                            let (position, found) = accessor.position::<K>(key);
+                            if found { position + 1 } else { position }
+                        }
+                        Bound::Unbounded => 0,
+                    };
+                    self.leaf = Some(page);
+                    return Ok(());
+                }
+                BRANCH => {
+                    let child_index = match start {
+                        Bound::Included(key) | Bound::Excluded(key) => {
+                            BranchAccessor::new(&page, K::fixed_width())
+                                .child_for_key::<K>(key)
+                                .0
+                        }
+                        Bound::Unbounded => 0,
+                    };
+                    let child = self.make_child_mut(&mut page, child_index)?;
+                    self.path.push((page, child_index));
+                    page = child;
+                }
+                _ => return Err(self.page_allocator.corrupted_page(&page)),
+            }
+        }
+    }
+
+    // Moves the cursor to the first entry of the leaf after the current one
+    fn next_leaf(&mut self) -> Result {
+        self.leaf = None;
+        self.position = 0;
+        let mut page = loop {
+            let Some((mut branch, child_index)) = self.path.pop() else {
+                return Ok(());
+            };
+            let children = BranchAccessor::new(&branch, K::fixed_width()).count_children();
+            if child_index + 1 < children {
+                let child = self.make_child_mut(&mut branch, child_index + 1)?;
+                self.path.push((branch, child_index + 1));
+                break child;
+            }
+        };
+        loop {
+            match page.memory()[0] {
+                LEAF => {
+                    self.leaf = Some(page);
+                    return Ok(());
+                }
+                BRANCH => {
+                    let child = self.make_child_mut(&mut page, 0)?;
+                    self.path.push((page, 0));
+                    page = child;
+                }
+                _ => return Err(self.page_allocator.corrupted_page(&page)),
+            }
+        }
+    }
+
+    fn make_child_mut(&self, branch: &mut PageMut<'a>, child_index: usize) -> Result<PageMut<'a>> {
+        let child_page = BranchAccessor::new(branch, K::fixed_width())
+            .child_page(child_index)
+            .unwrap();
+        let (child, moved) = make_mut(
+            &self.page_allocator,
+            &self.freed,
+            &self.allocated,
+            child_page,
+        )?;
+        if moved {
+            let mut mutator = BranchMutator::new(branch.memory_mut());
+            mutator.write_child_page(child_index, child.get_page_number(), DEFERRED);
+        }
+        Ok(child)
+    }
+}
+
+// Returns a writable version of the given page, copying it first if it is already committed.
+// Returns true if the page was copied
+fn make_mut<'a>(
+    page_allocator: &PageAllocator,
+    freed: &Mutex<Vec<PageNumber>>,
+    allocated: &Mutex<PageTrackerPolicy>,
+    page_number: PageNumber,
+) -> Result<(PageMut<'a>, bool)> {
+    if page_allocator.uncommitted(page_number) {
+        return Ok((page_allocator.get_page_mut(page_number)?, false));
+    }
+    let required: usize = page_number
+        .page_size_bytes(page_allocator.get_page_size().try_into().unwrap())
+        .try_into()
+        .unwrap();
+    let mut new_page = page_allocator.allocate(required, &mut allocated.lock().unwrap())?;
+    let old_page = page_allocator.get_page(page_number, PageHint::None)?;
+    new_page.memory_mut().copy_from_slice(old_page.memory());
+    drop(old_page);
+    freed.lock().unwrap().push(page_number);
+    Ok((new_page, true))
+}
+
+/// An entry of a [`RangeMut`], whose value can be replaced
+pub struct RangeMutEntry<'r, 'a, K: Key + 'static, V: Value + 'static> {
+    range: &'r mut RangeMut<'a, K, V>,
+    index: usize,
+}
+
+impl<K: Key + 'static, V: Value + 'static> RangeMutEntry<'_, '_, K, V> {
+    /// Access the key
+    pub fn key(&self) -> K::SelfType<'_> {
+        K::from_bytes(self.accessor().entry(self.index).unwrap().key())
+    }
+
+    /// Access the stored value
+    pub fn value(&self) -> V::SelfType<'_> {
+        V::from_bytes(self.accessor().entry(self.index).unwrap().value())
+    }
+
+    /// Replace the stored value
+    ///
+    /// The value is written into the leaf which holds it, if there is room. Otherwise the leaf is
+    /// rewritten, without affecting the rest of the range
+    pub fn insert<'v>(&mut self, value: impl Borrow<V::SelfType<'v>>) -> Result<()> {
+        let value_bytes = V::as_bytes(value.borrow());
+        let value_bytes = value_bytes.as_ref();
+        let leaf = self.range.leaf.as_mut().unwrap();
+        if LeafMutator::sufficient_replace_inplace_space(
+            &*leaf,
+            self.index,
+            K::fixed_width(),
+            V::fixed_width(),
+            value_bytes,
+        ) {
+            let mut mutator =
+                LeafMutator::new(leaf.memory_mut(), K::fixed_width(), V::fixed_width());
+            mutator.replace(self.index, value_bytes);
+            return Ok(());
+        }
+
+        let new_leaf = {
+            let accessor = LeafAccessor::new(leaf.memory(), K::fixed_width(), V::fixed_width());
+            let mut builder = LeafBuilder::new(
+                &self.range.page_allocator,
+                &self.range.allocated,
+                accessor.num_pairs(),
+                K::fixed_width(),
+                V::fixed_width(),
+            );
+            for i in 0..accessor.num_pairs() {
+                let entry = accessor.entry(i).unwrap();
+                if i == self.index {
+                    builder.push(entry.key(), value_bytes);
+                } else {
+                    builder.push(entry.key(), entry.value());
+                }
+            }
+            self.range.page_allocator.poison_on_error(builder.build())?
+        };
+
+        if let Some((parent, child_index)) = self.range.path.last_mut() {
+            let mut mutator = BranchMutator::new(parent.memory_mut());
+            mutator.write_child_page(*child_index, new_leaf.get_page_number(), DEFERRED);
+        } else {
+            let header = self.range.root.as_mut().unwrap();
+            header.root = new_leaf.get_page_number();
+            header.checksum = DEFERRED;
+        }
+        let old_leaf = self.range.leaf.replace(new_leaf).unwrap();
+        let old_page_number = old_leaf.get_page_number();
+        drop(old_leaf);
+        let mut allocated = self.range.allocated.lock().unwrap();
+        assert!(
+            self.range
+                .page_allocator
+                .free_if_uncommitted(old_page_number, &mut allocated)
+        );
+
+        Ok(())
+    }
+
+    fn accessor(&self) -> LeafAccessor<'_> {
+        LeafAccessor::new(
+            self.range.leaf.as_ref().unwrap().memory(),
+            K::fixed_width(),
+            V::fixed_width(),
+        )
+    }
+}
+
+impl<K: Key + 'static, V: MutInPlaceValue + 'static> AsMut<V::BaseRefType>
+    for RangeMutEntry<'_, '_, K, V>
+{
+    fn as_mut(&mut self) -> &mut V::BaseRefType {
+        let leaf = self.range.leaf.as_mut().unwrap();
+        let (start, end) = LeafAccessor::new(leaf.memory(), K::fixed_width(), V::fixed_width())
+            .value_range(self.index)
+            .unwrap();
+        V::from_bytes_mut(&mut leaf.memory_mut()[start..end])
+    }
+}
@@ -3,7 +3,8 @@ use crate::error::TableError;
 use crate::tree_store::btree::{PagePath, UntypedBtreeMut, btree_stats};
 use crate::tree_store::btree_base::BtreeHeader;
 use crate::tree_store::multimap_btree::{
-    finalize_tree_and_subtree_checksums, verify_tree_and_subtree_checksums,
+    finalize_tree_and_subtree_checksums, find_corrupted_tree_and_subtree_pages,
+    verify_tree_and_subtree_checksums,
 };
 use crate::tree_store::{
     Btree, BtreeMut, BtreeRangeIter, InternalTableDefinition, PageAllocator, PageHint, PageNumber,
@@ -12,7 +13,7 @@ use crate::tree_store::{
 use crate::types::{Key, Value};
 use crate::{DatabaseStats, Result};
 use std::cmp::max;
-use std::collections::{BTreeMap, HashMap};
+use std::collections::{BTreeMap, HashMap, HashSet};
 use std::mem::size_of;
 use std::ops::RangeFull;
 use std::sync::{Arc, Mutex};
@@ -86,6 +87,19 @@ impl TableTree {
         self.tree.transaction_guard()
     }
 
+    // The serialized definition of every table, by name
+    fn serialized_definitions(&self) -> Result<HashMap<String, Vec<u8>>> {
+        let mut result = HashMap::new();
+        for entry in self.tree.range::<RangeFull, &str>(&(..))? {
+            let entry = entry?;
+            result.insert(
+                entry.key().to_string(),
+                InternalTableDefinition::as_bytes(&entry.value()),
+            );
+        }
+        Ok(result)
+    }
+
     pub(crate) fn verify_checksums(&self) -> Result<bool> {
         if !self.tree.verify_checksum()? {
             return Ok(false);
@@ -136,6 +150,55 @@ impl TableTree {
         Ok(true)
     }
 
+    // Appends every corrupted page in the table tree and in the tables it contains. Tables are only
+    // searched if the table tree itself is intact
+    pub(crate) fn find_corrupted_pages(&self, corrupted: &mut Vec<PageNumber>) -> Result {
+        let initial_len = corrupted.len();
+        self.tree.find_corrupted_pages(corrupted)?;
+        if corrupted.len() > initial_len {
+            return Ok(());
+        }
+
+        for entry in self.tree.range::<RangeFull, &str>(&(..))? {
+            let entry = entry?;
+            let definition = entry.value();
+            match definition {
+                InternalTableDefinition::Normal {
+                    table_root,
+                    fixed_key_size,
+                    fixed_value_size,
+                    ..
+                } => {
+                    RawBtree::new(
+                        table_root,
+                        fixed_key_size,
+                        fixed_value_size,
+                        self.mem.clone(),
+                        self.tree.hint(),
+                    )
+                    .find_corrupted_pages(corrupted)?;
+                }
+                InternalTableDefinition::Multimap {
+                    table_root,
+                    fixed_key_size,
+                    fixed_value_size,
+                    ..
+                } => {
+                    find_corrupted_tree_and_subtree_pages(
+                        table_root,
+                        fixed_key_size,
+                        fixed_value_size,
+                        self.mem.clone(),
+                        self.tree.hint(),
+                        corrupted,
+                    )?;
+                }
+            }
+        }
+
+        Ok(())
+    }
+
     // root_page: the root of the master table
     pub(crate) fn list_tables(&self, table_type: TableType) -> Result<Vec<String>> {
         let iter = self.tree.range::<RangeFull, &str>(&(..))?;
@@ -184,9 +247,21 @@ impl TableTree {
     pub(crate) fn visit_all_pages<F>(&self, mut visitor: F) -> Result
     where
         F: FnMut(&PagePath) -> Result,
+    {
+        self.visit_pages_while(|path| {
+            visitor(path)?;
+            Ok(true)
+        })
+    }
+
+    // Applies visitor to all the pages reachable from the table tree, skipping the children of any
+    // page for which it returns false
+    pub(crate) fn visit_pages_while<F>(&self, mut visitor: F) -> Result
+    where
+        F: FnMut(&PagePath) -> Result<bool>,
     {
         // All the pages in the table tree itself
-        self.tree.visit_all_pages(&mut visitor)?;
+        self.tree.visit_pages_while(&mut visitor)?;
 
         // All the normal tables
         for entry in self.list_tables(TableType::Normal)? {
@@ -194,7 +269,8 @@ impl TableTree {
                 .get_table_untyped(&entry, TableType::Normal)
                 .map_err(|e| e.into_storage_error_or_corrupted("Internal corruption"))?
                 .unwrap();
-            definition.visit_all_pages(self.mem.clone(), self.tree.hint(), |path| visitor(path))?;
+            definition
+                .visit_pages_while(self.mem.clone(), self.tree.hint(), |path| visitor(path))?;
         }
 
         for entry in self.list_tables(TableType::Multimap)? {
@@ -202,13 +278,21 @@ impl TableTree {
                 .get_table_untyped(&entry, TableType::Multimap)
                 .map_err(|e| e.into_storage_error_or_corrupted("Internal corruption"))?
                 .unwrap();
-            definition.visit_all_pages(self.mem.clone(), self.tree.hint(), |path| visitor(path))?;
+            definition
+                .visit_pages_while(self.mem.clone(), self.tree.hint(), |path| visitor(path))?;
         }
 
         Ok(())
     }
 }
 
+// The state of a `TableTreeMut` at the start of a nested transaction
+pub(crate) struct TableTreeCheckpoint {
+    root: Option<BtreeHeader>,
+    modified_tables: HashSet<String>,
+    freed_pages: usize,
+}
+
 pub(crate) struct TableTreeMut {
     tree: BtreeMut<&'static str, InternalTableDefinition>,
     guard: Arc<TransactionGuard>,
@@ -216,6 +300,8 @@ pub(crate) struct TableTreeMut {
     // Cached updates from tables that have been closed. These must be flushed to the btree.
     // The bool indicates whether the root has dirty (DEFERRED) checksums that need finalization.
     pending_table_updates: HashMap<String, (Option<BtreeHeader>, u64, bool)>,
+    // Names of the tables which have been created, deleted, renamed, or had their root updated
+    modified_tables: HashSet<String>,
     freed_pages: Arc<Mutex<Vec<PageNumber>>>,
     allocated_pages: Arc<Mutex<PageTrackerPolicy>>,
 }
@@ -239,6 +325,7 @@ impl TableTreeMut {
             guard,
             page_allocator,
             pending_table_updates: HashMap::default(),
+            modified_tables: HashSet::default(),
             freed_pages,
             allocated_pages,
         }
@@ -248,10 +335,63 @@ impl TableTreeMut {
         &self.page_allocator
     }
 
-    pub(crate) fn set_root(&mut self, root: Option<BtreeHeader>) {
+    pub(crate) fn set_root(&mut self, root: Option<BtreeHeader>) -> Result {
+        self.record_pending_modifications();
+        let before = self.read_only_tree()?.serialized_definitions()?;
         self.tree.set_root(root);
         // Pending updates were staged for the old root and are invalid for the new one
         self.pending_table_updates.clear();
+        let after = self.read_only_tree()?.serialized_definitions()?;
+        for (name, definition) in &before {
+            if after.get(name) != Some(definition) {
+                self.modified_tables.insert(name.clone());
+            }
+        }
+        for name in after.keys() {
+            if !before.contains_key(name) {
+                self.modified_tables.insert(name.clone());
+            }
+        }
+        Ok(())
+    }
+
+    fn read_only_tree(&self) -> Result<TableTree> {
+        TableTree::new(
+            self.tree.get_root(),
+            PageHint::None,
+            self.guard.clone(),
+            self.page_allocator.resolver(),
+        )
+    }
+
+    // Records the tables whose staged root differs from the one in the table tree
+    fn record_pending_modifications(&mut self) {
+        for (name, (new_root, _, dirty)) in &self.pending_table_updates {
+            let unchanged = !dirty
+                && matches!(
+                    self.tree.get(&name.as_str()),
+                    Ok(Some(definition)) if definition.value().private_get_root() == *new_root
+                );
+            if !unchanged {
+                self.modified_tables.insert(name.clone());
+            }
+        }
+    }
+
+    // Returns the names of the tables which have been modified, in sorted order, including those
+    // with staged updates that have not yet been flushed
+    pub(crate) fn take_modified_tables(&mut self) -> Vec<String> {
+        let result = self.modified_tables();
+        self.modified_tables.clear();
+        result
+    }
+
+    // Returns the names of the tables which have been modified, in sorted order
+    pub(crate) fn modified_tables(&mut self) -> Vec<String> {
+        self.record_pending_modifications();
+        let mut result: Vec<String> = self.modified_tables.iter().cloned().collect();
+        result.sort();
+        result
     }
 
     #[cfg_attr(not(debug_assertions), expect(dead_code))]
@@ -325,6 +465,49 @@ impl TableTreeMut {
         }
     }
 
+    // Flushes the staged table updates, so that all the pages of the tree have valid checksums,
+    // and returns a checkpoint which rollback_to() can restore
+    pub(crate) fn checkpoint(&mut self) -> Result<TableTreeCheckpoint> {
+        let root = self.flush_inner()?;
+        Ok(TableTreeCheckpoint {
+            root,
+            modified_tables: self.modified_tables.clone(),
+            freed_pages: self.freed_pages.lock()?.len(),
+        })
+    }
+
+    // Restores the tree to the checkpoint. The caller must free the pages allocated since then
+    pub(crate) fn rollback_to(&mut self, checkpoint: TableTreeCheckpoint) {
+        self.pending_table_updates.clear();
+        self.tree.set_root(checkpoint.root);
+        self.modified_tables = checkpoint.modified_tables;
+        self.freed_pages
+            .lock()
+            .unwrap()
+            .truncate(checkpoint.freed_pages);
+    }
+
+    // Keeps the changes made since the checkpoint. Pages which were queued to be freed since then,
+    // but are uncommitted, are freed immediately
+    pub(crate) fn release_checkpoint(&mut self, checkpoint: TableTreeCheckpoint) {
+        let mut freed_pages = self.freed_pages.lock().unwrap();
+        let queued = freed_pages.split_off(checkpoint.freed_pages);
+        for page in queued {
+            if self.page_allocator.uncommitted(page) {
+                self.allocated_pages.lock().unwrap().remove_if_present(page);
+                self.page_allocator
+                    .free(page, &mut PageTrackerPolicy::Ignore);
+            } else {
+                freed_pages.push(page);
+            }
+        }
+    }
+
+    // Flushes the staged table updates, and returns the root of the tree
+    pub(crate) fn flush(&mut self) -> Result<Option<BtreeHeader>> {
+        self.flush_inner()
+    }
+
     fn flush_inner(&mut self) -> Result<Option<BtreeHeader>> {
         self.flush_table_root_updates()?.finalize_dirty_checksums()
     }
@@ -344,6 +527,7 @@ impl TableTreeMut {
                     }
                 }
             }
+            self.modified_tables.insert(name.clone());
             // Finalize any dirty checksums
             match definition {
                 InternalTableDefinition::Normal {
@@ -548,6 +732,8 @@ impl TableTreeMut {
             }
             assert!(self.tree.remove(&name)?.is_some());
             assert!(self.tree.insert(&new_name, &definition)?.is_none());
+            self.modified_tables.insert(name.to_string());
+            self.modified_tables.insert(new_name.to_string());
         } else {
             return Err(TableError::TableDoesNotExist(name.to_string()));
         }
@@ -584,23 +770,96 @@ impl TableTreeMut {
             self.pending_table_updates.remove(name);
 
             let found = self.tree.remove(&name)?.is_some();
+            self.modified_tables.insert(name.to_string());
             return Ok(found);
         }
 
         Ok(false)
     }
 
+    // Replaces the table with the given definition from an older version of the table tree, or
+    // deletes it if None. Pages which are only referenced by the current version are freed.
+    // Returns the pages which are only referenced by the restored version, which the caller must
+    // remove from the freed tree
+    pub(crate) fn restore_table(
+        &mut self,
+        name: &str,
+        table_type: TableType,
+        definition: Option<InternalTableDefinition>,
+    ) -> Result<PageNumberHashSet, TableError> {
+        let mut current_pages = PageNumberHashSet::default();
+        if let Some(current) = self.get_table_untyped(name, table_type)? {
+            current.visit_all_pages(self.page_allocator.resolver(), PageHint::None, |path| {
+                current_pages.insert(path.page_number());
+                Ok(())
+            })?;
+        }
+        let mut restored_pages = PageNumberHashSet::default();
+        if let Some(restored) = definition.as_ref() {
+            restored.visit_all_pages(self.page_allocator.resolver(), PageHint::None, |path| {
+                restored_pages.insert(path.page_number());
+                Ok(())
+            })?;
+        }
+
+        let mut freed_pages = self.freed_pages.lock().unwrap();
+        let mut allocated_pages = self.allocated_pages.lock().unwrap();
+        for page in current_pages.difference(&restored_pages) {
+            if !self
+                .page_allocator
+                .free_if_uncommitted(*page, &mut allocated_pages)
+            {
+                freed_pages.push(*page);
+            }
+        }
+        let revived: PageNumberHashSet =
+            restored_pages.difference(&current_pages).copied().collect();
+        freed_pages.retain(|page| !revived.contains(page));
+        drop(allocated_pages);
+        drop(freed_pages);
+
+        self.pending_table_updates.remove(name);
+        if let Some(restored) = definition {
+            self.tree.insert(&name, &restored)?;
+        } else {
+            self.tree.remove(&name)?;
+        }
+        self.modified_tables.insert(name.to_string());
+
+        Ok(revived)
+    }
+
+    // Copies the table from another database, whose pages are read through source
+    pub(crate) fn import_table(
+        &mut self,
+        name: &str,
+        definition: &InternalTableDefinition,
+        source: &PageResolver,
+    ) -> Result<(), TableError> {
+        if self.tree.get(&name)?.is_some() {
+            return Err(TableError::TableExists(name.to_string()));
+        }
+        let mut allocated_pages = self.allocated_pages.lock().unwrap();
+        let copy = definition.copy_from(source, &self.page_allocator, &mut allocated_pages)?;
+        drop(allocated_pages);
+        self.tree.insert(&name, &copy)?;
+        self.modified_tables.insert(name.to_string());
+        Ok(())
+    }
+
+    // Returns the root and length of the table, and whether it was created
     pub(crate) fn get_or_create_table<K: Key, V: Value>(
         &mut self,
         name: &str,
         table_type: TableType,
-    ) -> Result<(Option<BtreeHeader>, u64), TableError> {
-        let table = if let Some(found) = self.get_table::<K, V>(name, table_type)? {
-            found
+    ) -> Result<(Option<BtreeHeader>, u64, bool), TableError> {
+        let (table, created) = if let Some(found) = self.get_table::<K, V>(name, table_type)? {
+            (found, false)
         } else {
             let table = InternalTableDefinition::new::<K, V>(table_type, None, 0);
             self.tree.insert(&name, &table)?;
-            table
+            self.modified_tables.insert(name.to_string());
+            (table, true)
         };
 
         match table {
@@ -613,7 +872,7 @@ impl TableTreeMut {
                 table_root,
                 table_length,
                 ..
-            } => Ok((table_root, table_length)),
+            } => Ok((table_root, table_length, created)),
         }
     }
 
@@ -653,6 +912,32 @@ impl TableTreeMut {
         Ok(())
     }
 
+    // Appends the path of every page, in tree order: each table in name order, followed by the
+    // table tree itself
+    pub(crate) fn pages_in_tree_order(&self, output: &mut Vec<PagePath>) -> Result {
+        for entry in self.tree.range::<RangeFull, &str>(&(..))? {
+            let entry = entry?;
+            let mut definition = entry.value();
+            if let Some((updated_root, updated_length, _)) =
+                self.pending_table_updates.get(entry.key())
+            {
+                definition.set_header(*updated_root, *updated_length);
+            }
+
+            definition.visit_all_pages(self.page_allocator.resolver(), PageHint::None, |path| {
+                output.push(path.clone());
+                Ok(())
+            })?;
+        }
+
+        self.tree.visit_all_pages(|path| {
+            output.push(path.clone());
+            Ok(())
+        })?;
+
+        Ok(())
+    }
+
     pub(crate) fn relocate_tables(
         &mut self,
         relocation_map: &HashMap<PageNumber, PageNumber>,
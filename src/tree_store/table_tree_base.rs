@@ -1,6 +1,11 @@
-use crate::tree_store::btree::{PagePath, UntypedBtree, UntypedBtreeMut};
-use crate::tree_store::multimap_btree::{UntypedMultiBtree, relocate_subtrees};
-use crate::tree_store::{BtreeHeader, PageAllocator, PageHint, PageNumber, PageResolver};
+use crate::tree_store::btree::{PagePath, RawBtree, UntypedBtree, UntypedBtreeMut, copy_btree};
+use crate::tree_store::btree_base::DEFERRED;
+use crate::tree_store::multimap_btree::{
+    UntypedMultiBtree, copy_subtrees, finalize_tree_and_subtree_checksums, relocate_subtrees,
+};
+use crate::tree_store::{
+    BtreeHeader, PageAllocator, PageHint, PageNumber, PageResolver, PageTrackerPolicy,
+};
 use crate::{Key, Result, TableError, TypeName, Value};
 use std::collections::HashMap;
 use std::mem::size_of;
@@ -180,14 +185,50 @@ impl InternalTableDefinition {
         Ok(())
     }
 
+    // The tree of a normal table, or None if this is a multimap table
+    pub(crate) fn normal_btree(&self, mem: PageResolver, hint: PageHint) -> Option<RawBtree> {
+        match self {
+            InternalTableDefinition::Normal {
+                table_root,
+                fixed_key_size,
+                fixed_value_size,
+                ..
+            } => Some(RawBtree::new(
+                *table_root,
+                *fixed_key_size,
+                *fixed_value_size,
+                mem,
+                hint,
+            )),
+            InternalTableDefinition::Multimap { .. } => None,
+        }
+    }
+
     pub(crate) fn visit_all_pages<'a, F>(
         &self,
         mem: PageResolver,
         hint: PageHint,
-        visitor: F,
+        mut visitor: F,
     ) -> Result
     where
         F: FnMut(&PagePath) -> Result + 'a,
+    {
+        self.visit_pages_while(mem, hint, |path| {
+            visitor(path)?;
+            Ok(true)
+        })
+    }
+
+    // Applies visitor to the pages of the table, skipping the children of any page for which it
+    // returns false
+    pub(crate) fn visit_pages_while<'a, F>(
+        &self,
+        mem: PageResolver,
+        hint: PageHint,
+        visitor: F,
+    ) -> Result
+    where
+        F: FnMut(&PagePath) -> Result<bool> + 'a,
     {
         match self {
             InternalTableDefinition::Normal {
@@ -198,7 +239,7 @@ impl InternalTableDefinition {
             } => {
                 let tree =
                     UntypedBtree::new(*table_root, mem, hint, *fixed_key_size, *fixed_value_size);
-                tree.visit_all_pages(visitor)?;
+                tree.visit_pages_while(visitor)?;
             }
             InternalTableDefinition::Multimap {
                 table_root,
@@ -213,7 +254,7 @@ impl InternalTableDefinition {
                     *fixed_key_size,
                     *fixed_value_size,
                 );
-                tree.visit_all_pages(visitor)?;
+                tree.visit_pages_while(visitor)?;
             }
         }
 
@@ -266,7 +307,66 @@ impl InternalTableDefinition {
         }
     }
 
-    fn private_get_root(&self) -> Option<BtreeHeader> {
+    // Copies the table from another database, whose pages are read through source, into pages
+    // allocated from page_allocator. Returns the definition of the copy
+    pub(crate) fn copy_from(
+        &self,
+        source: &PageResolver,
+        page_allocator: &PageAllocator,
+        allocated: &mut PageTrackerPolicy,
+    ) -> Result<InternalTableDefinition> {
+        let mut result = self.clone();
+        let Some(header) = self.private_get_root() else {
+            return Ok(result);
+        };
+        let copied_root = match self {
+            InternalTableDefinition::Normal {
+                fixed_key_size,
+                fixed_value_size,
+                ..
+            } => {
+                let root = copy_btree(
+                    header.root,
+                    *fixed_key_size,
+                    source,
+                    page_allocator,
+                    allocated,
+                )?;
+                let mut tree = UntypedBtreeMut::new(
+                    Some(BtreeHeader::new(root, DEFERRED, header.length)),
+                    page_allocator.clone(),
+                    Arc::new(Mutex::new(vec![])),
+                    *fixed_key_size,
+                    *fixed_value_size,
+                );
+                tree.finalize_dirty_checksums()?
+            }
+            InternalTableDefinition::Multimap {
+                fixed_key_size,
+                fixed_value_size,
+                ..
+            } => {
+                let root = copy_subtrees(
+                    header.root,
+                    *fixed_key_size,
+                    *fixed_value_size,
+                    source,
+                    page_allocator,
+                    allocated,
+                )?;
+                finalize_tree_and_subtree_checksums(
+                    Some(BtreeHeader::new(root, DEFERRED, header.length)),
+                    *fixed_key_size,
+                    *fixed_value_size,
+                    page_allocator.clone(),
+                )?
+            }
+        };
+        result.set_header(copied_root, self.get_length());
+        Ok(result)
+    }
+
+    pub(crate) fn private_get_root(&self) -> Option<BtreeHeader> {
         match self {
             InternalTableDefinition::Normal { table_root, .. }
             | InternalTableDefinition::Multimap { table_root, .. } => *table_root,
@@ -1,5 +1,6 @@
-use crate::complex_types::{decode_varint_len, encode_varint_len};
-use crate::types::{Key, TypeName, Value};
+use crate::DecodeError;
+use crate::complex_types::{decode_varint_len, encode_varint_len, try_decode_varint_len};
+use crate::types::{Key, TryValue, TypeName, Value};
 use std::borrow::Borrow;
 use std::cmp::Ordering;
 
@@ -53,6 +54,35 @@ fn parse_lens<const N: usize>(fixed_width: [Option<usize>; N], data: &[u8]) -> (
     (offset, result)
 }
 
+// Splits data into the serialized elements of a tuple. The lengths of the variable width elements,
+// other than the last, are stored first, and the last element is the rest of the data
+fn try_split_tuple<const N: usize>(
+    fixed_width: [Option<usize>; N],
+    data: &[u8],
+) -> Result<[&[u8]; N], DecodeError> {
+    let mut lens = [0; N];
+    let mut offset = 0;
+    for (i, &fixed) in fixed_width[..(N - 1)].iter().enumerate() {
+        if let Some(len) = fixed {
+            lens[i] = len;
+        } else {
+            let (len, bytes_read) = try_decode_varint_len(&data[offset..])?;
+            lens[i] = len;
+            offset += bytes_read;
+        }
+    }
+    let mut result = [&data[..0]; N];
+    for i in 0..(N - 1) {
+        let Some(element) = data.get(offset..offset.saturating_add(lens[i])) else {
+            return Err(DecodeError::new("tuple element is out of bounds"));
+        };
+        result[i] = element;
+        offset += lens[i];
+    }
+    result[N - 1] = &data[offset..];
+    Ok(result)
+}
+
 fn not_equal<T: Key>(data1: &[u8], data2: &[u8]) -> Option<Ordering> {
     match T::compare(data1, data2) {
         Ordering::Less => Some(Ordering::Less),
@@ -260,6 +290,19 @@ macro_rules! tuple_impl {
             }
         }
 
+        impl<$($t: TryValue,)+ $t_last: TryValue> TryValue for ($($t,)+ $t_last) {
+            fn try_from_bytes<'a>(data: &'a [u8]) -> Result<Self::SelfType<'a>, DecodeError>
+            where
+                Self: 'a,
+            {
+                let [$($v,)+ $v_last] = try_split_tuple(
+                    [$(<$t>::fixed_width(),)+ <$t_last>::fixed_width()],
+                    data,
+                )?;
+                Ok(($(<$t>::try_from_bytes($v)?,)+ <$t_last>::try_from_bytes($v_last)?))
+            }
+        }
+
         impl<$($t: Key,)+ $t_last: Key> Key for ($($t,)+ $t_last) {
             fn compare(data1: &[u8], data2: &[u8]) -> Ordering {
                 if Self::fixed_width().is_some() {
@@ -306,12 +349,23 @@ impl<T: Value> Value for (T,) {
     }
 }
 
+impl<T: TryValue> TryValue for (T,) {
+    fn try_from_bytes<'a>(data: &'a [u8]) -> Result<Self::SelfType<'a>, DecodeError>
+    where
+        Self: 'a,
+    {
+        Ok((T::try_from_bytes(data)?,))
+    }
+}
+
 impl<T: Key> Key for (T,) {
     fn compare(data1: &[u8], data2: &[u8]) -> Ordering {
         T::compare(data1, data2)
     }
 }
 
+// Tuples are implemented up to 12 elements. Value requires Debug on both Self and SelfType, and
+// the standard library only implements Debug for tuples of up to 12 elements
 tuple_impl! {
     T0, t0, 0
     | T1, t1, 1
@@ -424,7 +478,7 @@ tuple_impl! {
 
 #[cfg(test)]
 mod test {
-    use crate::types::Value;
+    use crate::types::{TryValue, Value};
 
     #[test]
     fn width() {
@@ -445,4 +499,23 @@ mod test {
             "hello".len() + size_of::<u8>() + size_of::<u8>()
         );
     }
+
+    #[test]
+    fn try_from_bytes() {
+        let data = <(&str, u8, Vec<u16>)>::as_bytes(&("hello", 1, vec![2, 3]));
+        assert_eq!(
+            <(&str, u8, Vec<u16>)>::try_from_bytes(&data).unwrap(),
+            ("hello", 1, vec![2, 3])
+        );
+        for len in 0..data.len() {
+            assert!(<(&str, u8, Vec<u16>)>::try_from_bytes(&data[..len]).is_err());
+        }
+
+        let data = <(u16, u8)>::as_bytes(&(1, 2));
+        assert!(<(u16, u8)>::try_from_bytes(&data[..2]).is_err());
+        assert!(<(u16, Option<u8>)>::try_from_bytes(&[0, 0, 2, 0]).is_err());
+        assert!(<(u8, &str)>::try_from_bytes(&[0, 0xFF]).is_err());
+        assert!(<(char, bool)>::try_from_bytes(&[0, 0xD8, 0, 1]).is_err());
+        assert!(<[&str; 2]>::try_from_bytes(&[9, 0, 0, 0, 9, 0, 0, 0]).is_err());
+    }
 }
@@ -1,12 +1,43 @@
+use crate::DecodeError;
+use crate::sealed::Sealed;
+use std::borrow::Cow;
 use std::cmp::Ordering;
 use std::convert::TryInto;
+use std::ffi::CString;
 use std::fmt::Debug;
 use std::mem::size_of;
+use std::num::{
+    NonZeroI8, NonZeroI16, NonZeroI32, NonZeroI64, NonZeroI128, NonZeroU8, NonZeroU16, NonZeroU32,
+    NonZeroU64, NonZeroU128,
+};
+use std::sync::Arc;
+#[cfg(feature = "arrayvec")]
+mod arrayvec;
+mod big_endian;
+#[cfg(feature = "bytes")]
+mod bytes;
 #[cfg(feature = "chrono_v0_4")]
 mod chrono_v0_4;
+#[cfg(feature = "decimal")]
+mod decimal;
+mod float;
+mod int_repr;
+mod net;
+mod numeric_mut;
+#[cfg(any(unix, windows, target_os = "wasi"))]
+mod os_string;
+#[cfg(feature = "smallvec")]
+mod smallvec;
+#[cfg(feature = "time_v0_3")]
+mod time_v0_3;
 #[cfg(feature = "uuid")]
 mod uuid;
 
+pub use big_endian::{I16BE, I32BE, I64BE, I128BE, U16BE, U32BE, U64BE, U128BE};
+pub use float::{F32, F64};
+pub use int_repr::{EnumRepr, Flags};
+pub use numeric_mut::{NumericMut, NumericSliceMut};
+
 #[derive(Eq, PartialEq, Clone, Debug)]
 enum TypeClassification {
     Internal,
@@ -122,6 +153,30 @@ pub trait Value: Debug {
     fn type_name() -> TypeName;
 }
 
+/// Implementing this trait indicates that the type can be deserialized from untrusted data.
+/// This enables the `try_get()` and `try_range()` methods on tables
+pub trait TryValue: Value {
+    /// Deserializes data, or returns an error if it is not the serialization of any value
+    ///
+    /// Must not panic for any `data`. When it returns `Ok`, the result must be the same as that of
+    /// `from_bytes()`, which must not panic for the same `data`
+    fn try_from_bytes<'a>(data: &'a [u8]) -> Result<Self::SelfType<'a>, DecodeError>
+    where
+        Self: 'a;
+}
+
+// Returns an error unless data has the width of a fixed width type
+fn check_fixed_width(data: &[u8], width: usize, type_name: &str) -> Result<(), DecodeError> {
+    if data.len() == width {
+        Ok(())
+    } else {
+        Err(DecodeError::new(format!(
+            "{type_name} must be {width} bytes, but found {} bytes",
+            data.len()
+        )))
+    }
+}
+
 /// Implementing this trait indicates that the type can be mutated in-place as a &mut [u8].
 /// This enables the `.insert_reserve()` method on Table
 pub trait MutInPlaceValue: Value {
@@ -157,6 +212,76 @@ pub trait Key: Value {
     fn compare(data1: &[u8], data2: &[u8]) -> Ordering;
 }
 
+/// A [`Key`] which is ordered by its encoding, byte by byte, so that all the keys which start with a
+/// given prefix are adjacent. This enables the `.prefix()` method on tables
+pub trait PrefixKey: Key + Sealed {
+    /// Returns the encoding of the smallest key greater than every key starting with `prefix`, or
+    /// `None` if there is no such key
+    fn prefix_end(prefix: &[u8]) -> Option<Vec<u8>>;
+}
+
+fn bytes_prefix_end(prefix: &[u8]) -> Option<Vec<u8>> {
+    let mut end = prefix.to_vec();
+    // Increment the last byte, carrying into the byte before it if the last byte is 0xFF
+    while let Some(last) = end.pop() {
+        if last < u8::MAX {
+            end.push(last + 1);
+            return Some(end);
+        }
+    }
+    None
+}
+
+fn str_prefix_end(prefix: &[u8]) -> Option<Vec<u8>> {
+    let mut end: Vec<char> = std::str::from_utf8(prefix).unwrap().chars().collect();
+    // The UTF-8 encoding orders strings by code point, so increment the last code point, skipping
+    // the surrogates, which are not valid chars
+    while let Some(last) = end.pop() {
+        let next = match last {
+            '\u{D7FF}' => Some('\u{E000}'),
+            char::MAX => None,
+            c => char::from_u32(c as u32 + 1),
+        };
+        if let Some(next) = next {
+            end.push(next);
+            return Some(end.into_iter().collect::<String>().into_bytes());
+        }
+    }
+    None
+}
+
+impl Sealed for &[u8] {}
+
+impl PrefixKey for &[u8] {
+    fn prefix_end(prefix: &[u8]) -> Option<Vec<u8>> {
+        bytes_prefix_end(prefix)
+    }
+}
+
+impl Sealed for Arc<[u8]> {}
+
+impl PrefixKey for Arc<[u8]> {
+    fn prefix_end(prefix: &[u8]) -> Option<Vec<u8>> {
+        bytes_prefix_end(prefix)
+    }
+}
+
+impl Sealed for &str {}
+
+impl PrefixKey for &str {
+    fn prefix_end(prefix: &[u8]) -> Option<Vec<u8>> {
+        str_prefix_end(prefix)
+    }
+}
+
+impl Sealed for String {}
+
+impl PrefixKey for String {
+    fn prefix_end(prefix: &[u8]) -> Option<Vec<u8>> {
+        str_prefix_end(prefix)
+    }
+}
+
 impl Value for () {
     type SelfType<'a>
         = ()
@@ -192,6 +317,16 @@ impl Value for () {
     }
 }
 
+impl TryValue for () {
+    #[allow(clippy::unused_unit)]
+    fn try_from_bytes<'a>(_data: &'a [u8]) -> Result<(), DecodeError>
+    where
+        Self: 'a,
+    {
+        Ok(())
+    }
+}
+
 impl Key for () {
     fn compare(_data1: &[u8], _data2: &[u8]) -> Ordering {
         Ordering::Equal
@@ -238,6 +373,20 @@ impl Value for bool {
     }
 }
 
+impl TryValue for bool {
+    fn try_from_bytes<'a>(data: &'a [u8]) -> Result<bool, DecodeError>
+    where
+        Self: 'a,
+    {
+        check_fixed_width(data, 1, "bool")?;
+        match data[0] {
+            0 => Ok(false),
+            1 => Ok(true),
+            x => Err(DecodeError::new(format!("{x} is not a valid bool"))),
+        }
+    }
+}
+
 impl Key for bool {
     fn compare(data1: &[u8], data2: &[u8]) -> Ordering {
         let value1 = Self::from_bytes(data1);
@@ -290,6 +439,20 @@ impl<T: Value> Value for Option<T> {
     }
 }
 
+impl<T: TryValue> TryValue for Option<T> {
+    fn try_from_bytes<'a>(data: &'a [u8]) -> Result<Option<T::SelfType<'a>>, DecodeError>
+    where
+        Self: 'a,
+    {
+        match data.first() {
+            Some(0) => Ok(None),
+            Some(1) => Ok(Some(T::try_from_bytes(&data[1..])?)),
+            Some(x) => Err(DecodeError::new(format!("{x} is not a valid Option tag"))),
+            None => Err(DecodeError::new("Option is empty")),
+        }
+    }
+}
+
 impl<T: Key> Key for Option<T> {
     #[allow(clippy::collapsible_else_if)]
     fn compare(data1: &[u8], data2: &[u8]) -> Ordering {
@@ -342,12 +505,70 @@ impl Value for &[u8] {
     }
 }
 
+impl TryValue for &[u8] {
+    fn try_from_bytes<'a>(data: &'a [u8]) -> Result<&'a [u8], DecodeError>
+    where
+        Self: 'a,
+    {
+        Ok(data)
+    }
+}
+
 impl Key for &[u8] {
     fn compare(data1: &[u8], data2: &[u8]) -> Ordering {
         data1.cmp(data2)
     }
 }
 
+// Stored in the same format, and with the same type name, as &[u8], so the two are interchangeable
+impl Value for Arc<[u8]> {
+    type SelfType<'a>
+        = Arc<[u8]>
+    where
+        Self: 'a;
+    type AsBytes<'a>
+        = &'a [u8]
+    where
+        Self: 'a;
+
+    fn fixed_width() -> Option<usize> {
+        None
+    }
+
+    fn from_bytes<'a>(data: &'a [u8]) -> Arc<[u8]>
+    where
+        Self: 'a,
+    {
+        Arc::from(data)
+    }
+
+    fn as_bytes<'a, 'b: 'a>(value: &'a Arc<[u8]>) -> &'a [u8]
+    where
+        Self: 'b,
+    {
+        value
+    }
+
+    fn type_name() -> TypeName {
+        <&[u8]>::type_name()
+    }
+}
+
+impl TryValue for Arc<[u8]> {
+    fn try_from_bytes<'a>(data: &'a [u8]) -> Result<Arc<[u8]>, DecodeError>
+    where
+        Self: 'a,
+    {
+        Ok(Arc::from(data))
+    }
+}
+
+impl Key for Arc<[u8]> {
+    fn compare(data1: &[u8], data2: &[u8]) -> Ordering {
+        data1.cmp(data2)
+    }
+}
+
 impl<const N: usize> Value for &[u8; N] {
     type SelfType<'a>
         = &'a [u8; N]
@@ -381,6 +602,16 @@ impl<const N: usize> Value for &[u8; N] {
     }
 }
 
+impl<const N: usize> TryValue for &[u8; N] {
+    fn try_from_bytes<'a>(data: &'a [u8]) -> Result<&'a [u8; N], DecodeError>
+    where
+        Self: 'a,
+    {
+        check_fixed_width(data, N, &format!("[u8;{N}]"))?;
+        Ok(data.try_into().unwrap())
+    }
+}
+
 impl<const N: usize> Key for &[u8; N] {
     fn compare(data1: &[u8], data2: &[u8]) -> Ordering {
         data1.cmp(data2)
@@ -453,6 +684,36 @@ impl<const N: usize, T: Value> Value for [T; N] {
     }
 }
 
+impl<const N: usize, T: TryValue> TryValue for [T; N] {
+    fn try_from_bytes<'a>(data: &'a [u8]) -> Result<[T::SelfType<'a>; N], DecodeError>
+    where
+        Self: 'a,
+    {
+        let mut result = Vec::with_capacity(N);
+        if let Some(fixed) = T::fixed_width() {
+            check_fixed_width(data, fixed * N, "array")?;
+            for i in 0..N {
+                result.push(T::try_from_bytes(&data[fixed * i..fixed * (i + 1)])?);
+            }
+        } else {
+            let Some(ends) = data.get(..(size_of::<u32>() * N)) else {
+                return Err(DecodeError::new("array is missing its end offsets"));
+            };
+            // Set offset to the first data item
+            let mut start = size_of::<u32>() * N;
+            for end in ends.chunks_exact(size_of::<u32>()) {
+                let end = u32::from_le_bytes(end.try_into().unwrap()) as usize;
+                let Some(item) = data.get(start..end) else {
+                    return Err(DecodeError::new("array element is out of bounds"));
+                };
+                result.push(T::try_from_bytes(item)?);
+                start = end;
+            }
+        }
+        Ok(result.try_into().unwrap())
+    }
+}
+
 impl<const N: usize, T: Key> Key for [T; N] {
     fn compare(data1: &[u8], data2: &[u8]) -> Ordering {
         if let Some(fixed) = T::fixed_width() {
@@ -516,6 +777,15 @@ impl Value for &str {
     }
 }
 
+impl TryValue for &str {
+    fn try_from_bytes<'a>(data: &'a [u8]) -> Result<&'a str, DecodeError>
+    where
+        Self: 'a,
+    {
+        std::str::from_utf8(data).map_err(|err| DecodeError::new(err.to_string()))
+    }
+}
+
 impl Key for &str {
     fn compare(data1: &[u8], data2: &[u8]) -> Ordering {
         let str1 = Self::from_bytes(data1);
@@ -557,6 +827,15 @@ impl Value for String {
     }
 }
 
+impl TryValue for String {
+    fn try_from_bytes<'a>(data: &'a [u8]) -> Result<String, DecodeError>
+    where
+        Self: 'a,
+    {
+        Ok(<&str>::try_from_bytes(data)?.to_string())
+    }
+}
+
 impl Key for String {
     fn compare(data1: &[u8], data2: &[u8]) -> Ordering {
         let str1 = std::str::from_utf8(data1).unwrap();
@@ -565,6 +844,88 @@ impl Key for String {
     }
 }
 
+impl Value for Cow<'_, str> {
+    type SelfType<'a>
+        = Cow<'a, str>
+    where
+        Self: 'a;
+    type AsBytes<'a>
+        = &'a str
+    where
+        Self: 'a;
+
+    fn fixed_width() -> Option<usize> {
+        None
+    }
+
+    fn from_bytes<'a>(data: &'a [u8]) -> Cow<'a, str>
+    where
+        Self: 'a,
+    {
+        Cow::Borrowed(<&str>::from_bytes(data))
+    }
+
+    fn as_bytes<'a, 'b: 'a>(value: &'a Self::SelfType<'b>) -> &'a str
+    where
+        Self: 'b,
+    {
+        value
+    }
+
+    fn type_name() -> TypeName {
+        // Uses the same type name as &str, so that tables are compatible with &str types. This
+        // requires that the binary encoding be the same
+        <&str>::type_name()
+    }
+}
+
+impl Key for Cow<'_, str> {
+    fn compare(data1: &[u8], data2: &[u8]) -> Ordering {
+        <&str>::compare(data1, data2)
+    }
+}
+
+impl Value for Cow<'_, [u8]> {
+    type SelfType<'a>
+        = Cow<'a, [u8]>
+    where
+        Self: 'a;
+    type AsBytes<'a>
+        = &'a [u8]
+    where
+        Self: 'a;
+
+    fn fixed_width() -> Option<usize> {
+        None
+    }
+
+    fn from_bytes<'a>(data: &'a [u8]) -> Cow<'a, [u8]>
+    where
+        Self: 'a,
+    {
+        Cow::Borrowed(data)
+    }
+
+    fn as_bytes<'a, 'b: 'a>(value: &'a Self::SelfType<'b>) -> &'a [u8]
+    where
+        Self: 'b,
+    {
+        value
+    }
+
+    fn type_name() -> TypeName {
+        // Uses the same type name as &[u8], so that tables are compatible with &[u8] types. This
+        // requires that the binary encoding be the same
+        <&[u8]>::type_name()
+    }
+}
+
+impl Key for Cow<'_, [u8]> {
+    fn compare(data1: &[u8], data2: &[u8]) -> Ordering {
+        <&[u8]>::compare(data1, data2)
+    }
+}
+
 impl Value for char {
     type SelfType<'a> = char;
     type AsBytes<'a>
@@ -596,12 +957,64 @@ impl Value for char {
     }
 }
 
+impl TryValue for char {
+    fn try_from_bytes<'a>(data: &'a [u8]) -> Result<char, DecodeError>
+    where
+        Self: 'a,
+    {
+        check_fixed_width(data, 3, "char")?;
+        let value = u32::from_le_bytes([data[0], data[1], data[2], 0]);
+        char::from_u32(value)
+            .ok_or_else(|| DecodeError::new(format!("{value:#x} is not a valid char")))
+    }
+}
+
 impl Key for char {
     fn compare(data1: &[u8], data2: &[u8]) -> Ordering {
         Self::from_bytes(data1).cmp(&Self::from_bytes(data2))
     }
 }
 
+// Stored without the nul terminator
+impl Value for CString {
+    type SelfType<'a>
+        = CString
+    where
+        Self: 'a;
+    type AsBytes<'a>
+        = &'a [u8]
+    where
+        Self: 'a;
+
+    fn fixed_width() -> Option<usize> {
+        None
+    }
+
+    fn from_bytes<'a>(data: &'a [u8]) -> CString
+    where
+        Self: 'a,
+    {
+        CString::new(data).unwrap()
+    }
+
+    fn as_bytes<'a, 'b: 'a>(value: &'a Self::SelfType<'b>) -> &'a [u8]
+    where
+        Self: 'b,
+    {
+        value.as_bytes()
+    }
+
+    fn type_name() -> TypeName {
+        TypeName::internal("CString")
+    }
+}
+
+impl Key for CString {
+    fn compare(data1: &[u8], data2: &[u8]) -> Ordering {
+        data1.cmp(data2)
+    }
+}
+
 macro_rules! le_value {
     ($t:ty) => {
         impl Value for $t {
@@ -636,6 +1049,16 @@ macro_rules! le_value {
                 TypeName::internal(stringify!($t))
             }
         }
+
+        impl TryValue for $t {
+            fn try_from_bytes<'a>(data: &'a [u8]) -> Result<$t, DecodeError>
+            where
+                Self: 'a,
+            {
+                check_fixed_width(data, std::mem::size_of::<$t>(), stringify!($t))?;
+                Ok(Self::from_bytes(data))
+            }
+        }
     };
 }
 
@@ -663,3 +1086,59 @@ le_impl!(i64);
 le_impl!(i128);
 le_value!(f32);
 le_value!(f64);
+
+// Stored in the same little-endian encoding as the underlying primitive
+macro_rules! nonzero_impl {
+    ($t:ty, $inner:ty) => {
+        impl Value for $t {
+            type SelfType<'a> = $t;
+            type AsBytes<'a>
+                = [u8; std::mem::size_of::<$inner>()]
+            where
+                Self: 'a;
+
+            fn fixed_width() -> Option<usize> {
+                Some(std::mem::size_of::<$inner>())
+            }
+
+            fn from_bytes<'a>(data: &'a [u8]) -> $t
+            where
+                Self: 'a,
+            {
+                <$t>::new(<$inner>::from_le_bytes(data.try_into().unwrap())).unwrap()
+            }
+
+            fn as_bytes<'a, 'b: 'a>(
+                value: &'a Self::SelfType<'b>,
+            ) -> [u8; std::mem::size_of::<$inner>()]
+            where
+                Self: 'a,
+                Self: 'b,
+            {
+                value.get().to_le_bytes()
+            }
+
+            fn type_name() -> TypeName {
+                TypeName::internal(stringify!($t))
+            }
+        }
+
+        impl Key for $t {
+            fn compare(data1: &[u8], data2: &[u8]) -> Ordering {
+                <$inner>::from_le_bytes(data1.try_into().unwrap())
+                    .cmp(&<$inner>::from_le_bytes(data2.try_into().unwrap()))
+            }
+        }
+    };
+}
+
+nonzero_impl!(NonZeroU8, u8);
+nonzero_impl!(NonZeroU16, u16);
+nonzero_impl!(NonZeroU32, u32);
+nonzero_impl!(NonZeroU64, u64);
+nonzero_impl!(NonZeroU128, u128);
+nonzero_impl!(NonZeroI8, i8);
+nonzero_impl!(NonZeroI16, i16);
+nonzero_impl!(NonZeroI32, i32);
+nonzero_impl!(NonZeroI64, i64);
+nonzero_impl!(NonZeroI128, i128);
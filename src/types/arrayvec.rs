@@ -0,0 +1,104 @@
+use crate::complex_types::{decode_sequence, encode_sequence, try_decode_sequence};
+use crate::{DecodeError, TryValue, TypeName, Value};
+use arrayvec::ArrayVec;
+
+// Stored in the same format as Vec. The capacity is part of the type name, so a value always has
+// at most N elements, unless it is corrupted
+impl<T: Value, const N: usize> Value for ArrayVec<T, N> {
+    type SelfType<'a>
+        = ArrayVec<T::SelfType<'a>, N>
+    where
+        Self: 'a;
+    type AsBytes<'a>
+        = Vec<u8>
+    where
+        Self: 'a;
+
+    fn fixed_width() -> Option<usize> {
+        None
+    }
+
+    // Panics if data holds more than N elements. try_from_bytes() returns an error instead
+    fn from_bytes<'a>(data: &'a [u8]) -> ArrayVec<T::SelfType<'a>, N>
+    where
+        Self: 'a,
+    {
+        let elements: Vec<T::SelfType<'a>> = decode_sequence::<T, _>(data);
+        assert!(
+            elements.len() <= N,
+            "ArrayVec with capacity {N} has {} elements",
+            elements.len()
+        );
+        elements.into_iter().collect()
+    }
+
+    fn as_bytes<'a, 'b: 'a>(value: &'a ArrayVec<T::SelfType<'b>, N>) -> Vec<u8>
+    where
+        Self: 'b,
+    {
+        encode_sequence::<T>(value)
+    }
+
+    fn type_name() -> TypeName {
+        TypeName::internal(&format!("ArrayVec<{}, {N}>", T::type_name().name()))
+    }
+}
+
+impl<T: TryValue, const N: usize> TryValue for ArrayVec<T, N> {
+    fn try_from_bytes<'a>(data: &'a [u8]) -> Result<ArrayVec<T::SelfType<'a>, N>, DecodeError>
+    where
+        Self: 'a,
+    {
+        let elements: Vec<T::SelfType<'a>> = try_decode_sequence::<T, _>(data)?;
+        if elements.len() > N {
+            return Err(DecodeError::new(format!(
+                "ArrayVec with capacity {N} has {} elements",
+                elements.len()
+            )));
+        }
+        Ok(elements.into_iter().collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{Database, ReadableDatabase, TableDefinition, TableError, Value};
+    use arrayvec::ArrayVec;
+    use tempfile::NamedTempFile;
+
+    #[test]
+    fn test_arrayvec_table() {
+        let definition: TableDefinition<u64, ArrayVec<u32, 4>> = TableDefinition::new("table");
+        let vec_definition: TableDefinition<u64, Vec<u32>> = TableDefinition::new("table");
+        let db = Database::create(NamedTempFile::new().unwrap()).unwrap();
+        let write_txn = db.begin_write().unwrap();
+        {
+            let mut table = write_txn.open_table(definition).unwrap();
+            table.insert(0, ArrayVec::new()).unwrap();
+            table.insert(1, ArrayVec::from([1, 2, 3, 4])).unwrap();
+        }
+        write_txn.commit().unwrap();
+
+        let read_txn = db.begin_read().unwrap();
+        let table = read_txn.open_table(definition).unwrap();
+        assert!(table.get(0).unwrap().unwrap().value().is_empty());
+        assert_eq!(
+            table.get(1).unwrap().unwrap().value().as_slice(),
+            [1, 2, 3, 4]
+        );
+        // The capacity is part of the type
+        assert!(matches!(
+            read_txn.open_table(vec_definition),
+            Err(TableError::TableTypeMismatch { .. })
+        ));
+        let larger: TableDefinition<u64, ArrayVec<u32, 8>> = TableDefinition::new("table");
+        assert!(matches!(
+            read_txn.open_table(larger),
+            Err(TableError::TableTypeMismatch { .. })
+        ));
+
+        // Too many elements for the capacity
+        let bytes = <Vec<u32>>::as_bytes(&vec![1, 2, 3, 4, 5]);
+        assert!(<ArrayVec<u32, 4> as crate::TryValue>::try_from_bytes(&bytes).is_err());
+    }
+}
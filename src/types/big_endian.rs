@@ -0,0 +1,183 @@
+use crate::{Key, TypeName, Value};
+use std::cmp::Ordering;
+use std::fmt::{Display, Formatter};
+
+// Integers are stored big-endian, with the sign bit flipped for signed types. Byte-wise order of
+// the encoding then matches numeric order, so encoded keys can be concatenated into a composite
+// byte key, or compared with memcmp, without changing their order
+
+macro_rules! big_endian_impl {
+    ($name:ident, $int:ty, $sign_flip:expr, $doc:literal) => {
+        #[doc = $doc]
+        ///
+        /// The serialized form sorts byte-wise in numeric order, so it can be used as a component of
+        /// a composite key built by concatenating bytes.
+        #[derive(Copy, Clone, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+        pub struct $name(pub $int);
+
+        impl $name {
+            // Xor-ed into the most significant byte
+            const SIGN_FLIP: u8 = $sign_flip;
+
+            // Big-endian for the reason given in float.rs
+            #[allow(clippy::big_endian_bytes)]
+            fn encode(self) -> [u8; size_of::<$int>()] {
+                let mut bytes = self.0.to_be_bytes();
+                bytes[0] ^= Self::SIGN_FLIP;
+                bytes
+            }
+
+            #[allow(clippy::big_endian_bytes)]
+            fn decode(data: &[u8]) -> Self {
+                let mut bytes: [u8; size_of::<$int>()] = data.try_into().unwrap();
+                bytes[0] ^= Self::SIGN_FLIP;
+                Self(<$int>::from_be_bytes(bytes))
+            }
+        }
+
+        impl From<$int> for $name {
+            fn from(value: $int) -> Self {
+                Self(value)
+            }
+        }
+
+        impl From<$name> for $int {
+            fn from(value: $name) -> Self {
+                value.0
+            }
+        }
+
+        impl Display for $name {
+            fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+                Display::fmt(&self.0, f)
+            }
+        }
+
+        impl Value for $name {
+            type SelfType<'a> = $name;
+            type AsBytes<'a>
+                = [u8; size_of::<$int>()]
+            where
+                Self: 'a;
+
+            fn fixed_width() -> Option<usize> {
+                Some(size_of::<$int>())
+            }
+
+            fn from_bytes<'a>(data: &'a [u8]) -> $name
+            where
+                Self: 'a,
+            {
+                $name::decode(data)
+            }
+
+            fn as_bytes<'a, 'b: 'a>(value: &'a Self::SelfType<'b>) -> [u8; size_of::<$int>()]
+            where
+                Self: 'b,
+            {
+                value.encode()
+            }
+
+            fn type_name() -> TypeName {
+                TypeName::internal(concat!("redb::", stringify!($name)))
+            }
+        }
+
+        impl Key for $name {
+            fn compare(data1: &[u8], data2: &[u8]) -> Ordering {
+                data1.cmp(data2)
+            }
+        }
+    };
+}
+
+big_endian_impl!(U16BE, u16, 0, "A `u16` stored big-endian");
+big_endian_impl!(U32BE, u32, 0, "A `u32` stored big-endian");
+big_endian_impl!(U64BE, u64, 0, "A `u64` stored big-endian");
+big_endian_impl!(U128BE, u128, 0, "A `u128` stored big-endian");
+big_endian_impl!(
+    I16BE,
+    i16,
+    0x80,
+    "An `i16` stored big-endian, with its sign bit flipped"
+);
+big_endian_impl!(
+    I32BE,
+    i32,
+    0x80,
+    "An `i32` stored big-endian, with its sign bit flipped"
+);
+big_endian_impl!(
+    I64BE,
+    i64,
+    0x80,
+    "An `i64` stored big-endian, with its sign bit flipped"
+);
+big_endian_impl!(
+    I128BE,
+    i128,
+    0x80,
+    "An `i128` stored big-endian, with its sign bit flipped"
+);
+
+#[cfg(test)]
+mod tests {
+    use crate::types::big_endian::{I64BE, U16BE, U64BE};
+    use crate::{Database, Key, ReadableDatabase, ReadableTable, TableDefinition, Value};
+    use tempfile::NamedTempFile;
+
+    #[test]
+    fn test_big_endian_ordering() {
+        let values: Vec<I64BE> = [i64::MIN, -256, -1, 0, 1, 255, 256, i64::MAX]
+            .into_iter()
+            .map(I64BE)
+            .collect();
+        for (i, a) in values.iter().enumerate() {
+            assert_eq!(&I64BE::from_bytes(&I64BE::as_bytes(a)), a);
+            for (j, b) in values.iter().enumerate() {
+                assert_eq!(I64BE::as_bytes(a).cmp(&I64BE::as_bytes(b)), i.cmp(&j));
+            }
+        }
+
+        let values: Vec<U16BE> = [0, 1, 255, 256, u16::MAX].into_iter().map(U16BE).collect();
+        for (i, a) in values.iter().enumerate() {
+            assert_eq!(&U16BE::from_bytes(&U16BE::as_bytes(a)), a);
+            for (j, b) in values.iter().enumerate() {
+                assert_eq!(
+                    U16BE::compare(&U16BE::as_bytes(a), &U16BE::as_bytes(b)),
+                    i.cmp(&j)
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn test_composite_byte_key() {
+        let definition: TableDefinition<&[u8], u64> = TableDefinition::new("composite");
+        let key = |a: i64, b: u64| {
+            let mut key = I64BE::as_bytes(&I64BE(a)).to_vec();
+            key.extend_from_slice(&U64BE::as_bytes(&U64BE(b)));
+            key
+        };
+        let entries = [(-2, 5), (-1, 0), (-1, 256), (0, 1), (1, 0), (256, 0)];
+
+        let db = Database::create(NamedTempFile::new().unwrap()).unwrap();
+        let write_txn = db.begin_write().unwrap();
+        {
+            let mut table = write_txn.open_table(definition).unwrap();
+            for (i, (a, b)) in entries.iter().enumerate().rev() {
+                table.insert(key(*a, *b).as_slice(), i as u64).unwrap();
+            }
+        }
+        write_txn.commit().unwrap();
+
+        let read_txn = db.begin_read().unwrap();
+        let table = read_txn.open_table(definition).unwrap();
+        let order: Vec<u64> = table
+            .iter()
+            .unwrap()
+            .map(|item| item.unwrap().1.value())
+            .collect();
+        assert_eq!(order, (0..entries.len() as u64).collect::<Vec<_>>());
+    }
+}
@@ -0,0 +1,104 @@
+use crate::{DecodeError, Key, TryValue, TypeName, Value};
+use bytes::Bytes;
+use std::cmp::Ordering;
+
+// Stored in the same format, and with the same type name, as &[u8], so the two are interchangeable.
+// Reading through Value copies the data, because only a borrowed slice is available. Use
+// AccessGuard::to_bytes() to get a Bytes which shares the page instead
+impl Value for Bytes {
+    type SelfType<'a>
+        = Bytes
+    where
+        Self: 'a;
+    type AsBytes<'a>
+        = &'a [u8]
+    where
+        Self: 'a;
+
+    fn fixed_width() -> Option<usize> {
+        None
+    }
+
+    fn from_bytes<'a>(data: &'a [u8]) -> Bytes
+    where
+        Self: 'a,
+    {
+        Bytes::copy_from_slice(data)
+    }
+
+    fn as_bytes<'a, 'b: 'a>(value: &'a Bytes) -> &'a [u8]
+    where
+        Self: 'b,
+    {
+        value
+    }
+
+    fn type_name() -> TypeName {
+        <&[u8]>::type_name()
+    }
+}
+
+impl TryValue for Bytes {
+    fn try_from_bytes<'a>(data: &'a [u8]) -> Result<Bytes, DecodeError>
+    where
+        Self: 'a,
+    {
+        Ok(Bytes::copy_from_slice(data))
+    }
+}
+
+impl Key for Bytes {
+    fn compare(data1: &[u8], data2: &[u8]) -> Ordering {
+        <&[u8]>::compare(data1, data2)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{Database, ReadableDatabase, ReadableTable, TableDefinition};
+    use bytes::Bytes;
+    use tempfile::NamedTempFile;
+
+    #[test]
+    fn test_bytes_table() {
+        let definition: TableDefinition<Bytes, Bytes> = TableDefinition::new("table");
+        let slice_definition: TableDefinition<&[u8], &[u8]> = TableDefinition::new("table");
+        let db = Database::create(NamedTempFile::new().unwrap()).unwrap();
+        let write_txn = db.begin_write().unwrap();
+        {
+            let mut table = write_txn.open_table(definition).unwrap();
+            table
+                .insert(Bytes::from_static(b"a"), Bytes::from_static(b"hello"))
+                .unwrap();
+            table
+                .insert(Bytes::from_static(b"b"), Bytes::from(vec![7; 100]))
+                .unwrap();
+        }
+        write_txn.commit().unwrap();
+
+        let read_txn = db.begin_read().unwrap();
+        let table = read_txn.open_table(definition).unwrap();
+        let key = Bytes::from_static(b"a");
+        assert_eq!(table.get(&key).unwrap().unwrap().value(), "hello");
+        let guard = table.get(Bytes::from_static(b"b")).unwrap().unwrap();
+        let shared = guard.to_bytes();
+        drop(guard);
+        drop(table);
+        drop(read_txn);
+        assert_eq!(shared, vec![7; 100]);
+
+        // Compatible with &[u8]
+        let read_txn = db.begin_read().unwrap();
+        let table = read_txn.open_table(slice_definition).unwrap();
+        let keys: Vec<Vec<u8>> = table
+            .iter()
+            .unwrap()
+            .map(|entry| entry.unwrap().0.value().to_vec())
+            .collect();
+        assert_eq!(keys, vec![b"a".to_vec(), b"b".to_vec()]);
+        assert_eq!(
+            table.get(b"a".as_slice()).unwrap().unwrap().to_bytes(),
+            "hello"
+        );
+    }
+}
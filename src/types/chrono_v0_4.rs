@@ -8,6 +8,7 @@
 //!| NaiveTime             | `{seconds_from_midnight:u32:first 3 bytes}\|{nanoseconds:u32}`                                                           | 7    |                                                  |
 //!| NaiveDateTime         | `{year:i32}\|{month:u8}\|{day:u8}\|{seconds_from_midnight:u32:first 3 bytes}\|{nanoseconds:u32}`                         | 13   |                                                  |
 //!| FixedOffset           | `{seconds_from_utc:i32}`                                                                                                 | 4    |                                                  |
+//!| DateTime<Utc>         | `{year:i32}\|{month:u8}\|{day:u8}\|{seconds_from_midnight:u32:first 3 bytes}\|{nanoseconds:u32}`                         | 13   | Same layout as `NaiveDateTime`, in UTC           |
 //!| DateTime<FixedOffset> | `{year:i32}\|{month:u8}\|{day:u8}\|{seconds_from_midnight:u32:first 3 bytes}\|{nanoseconds:u32}\|{seconds_from_utc:i32}` | 17   | Time is stored in UTC with the offset in seconds |
 use crate::{Key, TypeName, Value};
 
@@ -193,6 +194,54 @@ impl Key for NaiveDateTime {
     }
 }
 
+/// A 13-byte representation of a UTC date and time, using the same layout as `NaiveDateTime`.
+impl Value for DateTime<Utc> {
+    type SelfType<'a>
+        = DateTime<Utc>
+    where
+        Self: 'a;
+
+    type AsBytes<'a>
+        = [u8; 13]
+    where
+        Self: 'a;
+
+    fn fixed_width() -> Option<usize> {
+        Some(13)
+    }
+
+    fn from_bytes<'a>(data: &'a [u8]) -> Self::SelfType<'a>
+    where
+        Self: 'a,
+    {
+        assert_eq!(
+            data.len(),
+            13,
+            "DateTime<Utc> must be 13 bytes long, got {}",
+            data.len()
+        );
+        NaiveDateTime::from_bytes(data).and_utc()
+    }
+
+    fn as_bytes<'a, 'b: 'a>(value: &'a Self::SelfType<'b>) -> Self::AsBytes<'a>
+    where
+        Self: 'b,
+    {
+        NaiveDateTime::as_bytes(&value.naive_utc())
+    }
+
+    fn type_name() -> TypeName {
+        TypeName::new("chrono::DateTime<chrono::Utc>")
+    }
+}
+impl Key for DateTime<Utc> {
+    fn compare(data1: &[u8], data2: &[u8]) -> std::cmp::Ordering {
+        let datetime1 = DateTime::<Utc>::from_bytes(data1);
+        let datetime2 = DateTime::<Utc>::from_bytes(data2);
+        datetime1.cmp(&datetime2)
+    }
+}
+
 impl Value for DateTime<FixedOffset> {
     type SelfType<'a>
         = DateTime<FixedOffset>
@@ -339,7 +388,7 @@ fn time_from_bytes(data: &[u8]) -> NaiveTime {
 mod tests {
     use crate::{Database, Key, ReadableDatabase, TableDefinition, Value};
     use chrono_v0_4::{
-        DateTime, FixedOffset, Local, NaiveDate, NaiveDateTime, NaiveTime, TimeZone,
+        DateTime, FixedOffset, Local, NaiveDate, NaiveDateTime, NaiveTime, TimeDelta, TimeZone, Utc,
     };
     use tempfile::NamedTempFile;
     const NAIVE_DATE_TABLE: TableDefinition<NaiveDate, i32> =
@@ -352,6 +401,8 @@ mod tests {
         TableDefinition::new("fixed_offset_table");
     const DATETIME_FIXED_OFFSET_TABLE: TableDefinition<DateTime<FixedOffset>, i32> =
         TableDefinition::new("datetime_fixed_offset_table");
+    const DATETIME_UTC_TABLE: TableDefinition<DateTime<Utc>, i32> =
+        TableDefinition::new("datetime_utc_table");
     #[test]
     fn test_naive_date() {
         let date = NaiveDate::from_ymd_opt(2023, 10, 5).unwrap();
@@ -544,4 +595,49 @@ mod tests {
             assert_eq!(value.value(), 1);
         }
     }
+    #[test]
+    fn test_date_time_utc() {
+        let datetime =
+            Utc.with_ymd_and_hms(2023, 10, 5, 12, 30, 45).unwrap() + TimeDelta::nanoseconds(123);
+        let bytes = DateTime::<Utc>::as_bytes(&datetime);
+        assert_eq!(
+            DateTime::<Utc>::fixed_width(),
+            Some(bytes.len()),
+            "DateTime<Utc> should have fixed width"
+        );
+        assert_eq!(datetime, DateTime::<Utc>::from_bytes(&bytes));
+
+        let earlier = datetime - TimeDelta::nanoseconds(1);
+        assert_eq!(
+            DateTime::<Utc>::compare(&DateTime::<Utc>::as_bytes(&earlier), &bytes),
+            std::cmp::Ordering::Less
+        );
+    }
+    #[test]
+    fn test_datetime_utc_table() {
+        let start = Utc.with_ymd_and_hms(1969, 12, 31, 23, 59, 0).unwrap();
+        let db = Database::create(NamedTempFile::new().unwrap()).unwrap();
+        let write_txn = db.begin_write().unwrap();
+        {
+            let mut table = write_txn.open_table(DATETIME_UTC_TABLE).unwrap();
+            for i in (0..100).rev() {
+                table.insert(start + TimeDelta::seconds(i), 1).unwrap();
+            }
+        }
+        write_txn.commit().unwrap();
+
+        let read_txn = db.begin_read().unwrap();
+        {
+            let table = read_txn.open_table(DATETIME_UTC_TABLE).unwrap();
+            let end = start + TimeDelta::seconds(10);
+            let keys: Vec<DateTime<Utc>> = table
+                .range(start..end)
+                .unwrap()
+                .map(|item| item.unwrap().0.value())
+                .collect();
+            let expected: Vec<DateTime<Utc>> =
+                (0..10).map(|i| start + TimeDelta::seconds(i)).collect();
+            assert_eq!(keys, expected);
+        }
+    }
 }
@@ -0,0 +1,125 @@
+use crate::{Key, TypeName, Value};
+use rust_decimal::Decimal;
+use std::cmp::Ordering;
+
+impl Value for Decimal {
+    type SelfType<'a>
+        = Decimal
+    where
+        Self: 'a;
+    type AsBytes<'a>
+        = [u8; 16]
+    where
+        Self: 'a;
+
+    fn fixed_width() -> Option<usize> {
+        Some(16)
+    }
+
+    fn from_bytes<'a>(data: &'a [u8]) -> Self::SelfType<'a>
+    where
+        Self: 'a,
+    {
+        Decimal::deserialize(data.try_into().unwrap())
+    }
+
+    fn as_bytes<'a, 'b: 'a>(value: &'a Self::SelfType<'b>) -> Self::AsBytes<'a>
+    where
+        Self: 'b,
+    {
+        value.serialize()
+    }
+
+    fn type_name() -> TypeName {
+        TypeName::new("rust_decimal::Decimal")
+    }
+}
+
+// The serialized form stores the scale separately from the mantissa, so it does not sort
+// byte-wise. Keys are compared by value, so 1.0 and 1.00 are the same key, as they are equal
+impl Key for Decimal {
+    fn compare(data1: &[u8], data2: &[u8]) -> Ordering {
+        Decimal::from_bytes(data1).cmp(&Decimal::from_bytes(data2))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{Database, Key, ReadableDatabase, ReadableTable, TableDefinition, Value};
+    use rust_decimal::Decimal;
+    use tempfile::NamedTempFile;
+
+    const DECIMAL_TABLE: TableDefinition<Decimal, Decimal> = TableDefinition::new("table");
+
+    fn decimal_values() -> Vec<Decimal> {
+        vec![
+            Decimal::MIN,
+            Decimal::new(-1_000_001, 2),
+            Decimal::new(-1, 0),
+            Decimal::new(-1, 28),
+            Decimal::ZERO,
+            Decimal::new(1, 28),
+            Decimal::new(5, 1),
+            Decimal::new(1, 0),
+            Decimal::new(1_000_001, 2),
+            Decimal::MAX,
+        ]
+    }
+
+    #[test]
+    fn test_decimal_ordering() {
+        let values = decimal_values();
+        for (i, a) in values.iter().enumerate() {
+            assert_eq!(&Decimal::from_bytes(&Decimal::as_bytes(a)), a);
+            for (j, b) in values.iter().enumerate() {
+                assert_eq!(
+                    Decimal::compare(&Decimal::as_bytes(a), &Decimal::as_bytes(b)),
+                    i.cmp(&j),
+                    "{a} {b}"
+                );
+            }
+        }
+
+        // Scale is preserved, but does not affect ordering
+        let one = Decimal::new(100, 2);
+        assert_eq!(Decimal::from_bytes(&Decimal::as_bytes(&one)).scale(), 2);
+        assert!(
+            Decimal::compare(&Decimal::as_bytes(&one), &Decimal::as_bytes(&Decimal::ONE)).is_eq()
+        );
+    }
+
+    #[test]
+    fn test_decimal_table() {
+        let db = Database::create(NamedTempFile::new().unwrap()).unwrap();
+        let write_txn = db.begin_write().unwrap();
+        {
+            let mut table = write_txn.open_table(DECIMAL_TABLE).unwrap();
+            for value in decimal_values().iter().rev() {
+                table.insert(value, value * Decimal::new(1, 1)).unwrap();
+            }
+        }
+        write_txn.commit().unwrap();
+
+        let read_txn = db.begin_read().unwrap();
+        let table = read_txn.open_table(DECIMAL_TABLE).unwrap();
+        let keys: Vec<Decimal> = table
+            .iter()
+            .unwrap()
+            .map(|item| item.unwrap().0.value())
+            .collect();
+        assert_eq!(keys, decimal_values());
+        assert_eq!(
+            table.get(Decimal::new(1000, 3)).unwrap().unwrap().value(),
+            Decimal::new(1, 1)
+        );
+        let positive: Vec<Decimal> = table
+            .range(Decimal::ZERO..Decimal::ONE)
+            .unwrap()
+            .map(|item| item.unwrap().0.value())
+            .collect();
+        assert_eq!(
+            positive,
+            vec![Decimal::ZERO, Decimal::new(1, 28), Decimal::new(5, 1)]
+        );
+    }
+}
@@ -0,0 +1,220 @@
+use crate::{Key, TypeName, Value};
+use std::cmp::Ordering;
+use std::fmt::{Display, Formatter};
+
+// Floats are stored big-endian, with the sign bit flipped for positive values and every bit
+// flipped for negative values. Byte-wise order of the encoding then matches the IEEE 754 total
+// order, as implemented by total_cmp(): -NaN < -inf < ... < -0.0 < 0.0 < ... < inf < NaN
+
+macro_rules! float_impl {
+    ($name:ident, $float:ty, $bits:ty, $doc:literal) => {
+        #[doc = $doc]
+        ///
+        /// Values are ordered, and compared for equality, with the IEEE 754 total order, so
+        /// `-0.0` is less than `0.0`, and NaNs are ordered by their sign and payload. Keys are
+        /// encoded so that their bytes sort in the same order, so range scans visit keys in
+        /// numeric order.
+        #[derive(Copy, Clone, Debug, Default)]
+        pub struct $name(pub $float);
+
+        impl $name {
+            const SIGN_BIT: $bits = 1 << (<$bits>::BITS - 1);
+
+            // clippy::big_endian_bytes is denied because redb stores numbers little-endian. The
+            // order-preserving key encodings are the exception: their bytes are compared one at a
+            // time, most significant first, so the byte-wise order of the encoding only matches the
+            // numeric order if it is big-endian. The other encodings which rely on this refer here
+            #[allow(clippy::big_endian_bytes)]
+            fn encode(self) -> [u8; size_of::<$bits>()] {
+                let bits = self.0.to_bits();
+                let ordered = if bits & Self::SIGN_BIT == 0 {
+                    bits ^ Self::SIGN_BIT
+                } else {
+                    !bits
+                };
+                ordered.to_be_bytes()
+            }
+
+            #[allow(clippy::big_endian_bytes)]
+            fn decode(data: &[u8]) -> Self {
+                let ordered = <$bits>::from_be_bytes(data.try_into().unwrap());
+                let bits = if ordered & Self::SIGN_BIT == 0 {
+                    !ordered
+                } else {
+                    ordered ^ Self::SIGN_BIT
+                };
+                Self(<$float>::from_bits(bits))
+            }
+        }
+
+        impl From<$float> for $name {
+            fn from(value: $float) -> Self {
+                Self(value)
+            }
+        }
+
+        impl From<$name> for $float {
+            fn from(value: $name) -> Self {
+                value.0
+            }
+        }
+
+        impl Display for $name {
+            fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+                Display::fmt(&self.0, f)
+            }
+        }
+
+        impl PartialEq for $name {
+            fn eq(&self, other: &Self) -> bool {
+                self.cmp(other) == Ordering::Equal
+            }
+        }
+
+        impl Eq for $name {}
+
+        impl PartialOrd for $name {
+            fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+                Some(self.cmp(other))
+            }
+        }
+
+        impl Ord for $name {
+            fn cmp(&self, other: &Self) -> Ordering {
+                self.0.total_cmp(&other.0)
+            }
+        }
+
+        impl Value for $name {
+            type SelfType<'a> = $name;
+            type AsBytes<'a>
+                = [u8; size_of::<$bits>()]
+            where
+                Self: 'a;
+
+            fn fixed_width() -> Option<usize> {
+                Some(size_of::<$bits>())
+            }
+
+            fn from_bytes<'a>(data: &'a [u8]) -> $name
+            where
+                Self: 'a,
+            {
+                $name::decode(data)
+            }
+
+            fn as_bytes<'a, 'b: 'a>(value: &'a Self::SelfType<'b>) -> [u8; size_of::<$bits>()]
+            where
+                Self: 'b,
+            {
+                value.encode()
+            }
+
+            fn type_name() -> TypeName {
+                TypeName::internal(concat!("redb::", stringify!($name)))
+            }
+        }
+
+        impl Key for $name {
+            fn compare(data1: &[u8], data2: &[u8]) -> Ordering {
+                data1.cmp(data2)
+            }
+        }
+    };
+}
+
+float_impl!(F32, f32, u32, "An `f32` which can be used as a key");
+float_impl!(F64, f64, u64, "An `f64` which can be used as a key");
+
+#[cfg(test)]
+mod tests {
+    use crate::types::float::{F32, F64};
+    use crate::{Database, Key, ReadableDatabase, ReadableTable, TableDefinition, Value};
+    use tempfile::NamedTempFile;
+
+    fn f64_values() -> Vec<F64> {
+        [
+            -f64::NAN,
+            f64::NEG_INFINITY,
+            f64::MIN,
+            -1.5,
+            -f64::MIN_POSITIVE,
+            -0.0,
+            0.0,
+            f64::MIN_POSITIVE,
+            1.0,
+            1.5,
+            f64::MAX,
+            f64::INFINITY,
+            f64::NAN,
+        ]
+        .into_iter()
+        .map(F64)
+        .collect()
+    }
+
+    #[test]
+    fn test_float_ordering() {
+        let values = f64_values();
+        for (i, a) in values.iter().enumerate() {
+            assert_eq!(
+                F64::from_bytes(&F64::as_bytes(a)).0.to_bits(),
+                a.0.to_bits()
+            );
+            for (j, b) in values.iter().enumerate() {
+                assert_eq!(a.cmp(b), i.cmp(&j), "{a:?} {b:?}");
+                assert_eq!(
+                    F64::compare(&F64::as_bytes(a), &F64::as_bytes(b)),
+                    i.cmp(&j)
+                );
+            }
+        }
+
+        let values: Vec<F32> = [-f32::NAN, f32::NEG_INFINITY, -1.0, -0.0, 0.0, 1.0, f32::NAN]
+            .into_iter()
+            .map(F32)
+            .collect();
+        for (i, a) in values.iter().enumerate() {
+            assert_eq!(
+                F32::from_bytes(&F32::as_bytes(a)).0.to_bits(),
+                a.0.to_bits()
+            );
+            for (j, b) in values.iter().enumerate() {
+                assert_eq!(
+                    F32::compare(&F32::as_bytes(a), &F32::as_bytes(b)),
+                    i.cmp(&j)
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn test_float_table() {
+        let definition: TableDefinition<F64, u64> = TableDefinition::new("floats");
+        let db = Database::create(NamedTempFile::new().unwrap()).unwrap();
+        let write_txn = db.begin_write().unwrap();
+        {
+            let mut table = write_txn.open_table(definition).unwrap();
+            for (i, value) in f64_values().iter().enumerate().rev() {
+                table.insert(value, i as u64).unwrap();
+            }
+        }
+        write_txn.commit().unwrap();
+
+        let read_txn = db.begin_read().unwrap();
+        let table = read_txn.open_table(definition).unwrap();
+        let order: Vec<u64> = table
+            .iter()
+            .unwrap()
+            .map(|item| item.unwrap().1.value())
+            .collect();
+        assert_eq!(order, (0..f64_values().len() as u64).collect::<Vec<_>>());
+
+        let positive: Vec<f64> = table
+            .range(F64(0.0)..F64(f64::INFINITY))
+            .unwrap()
+            .map(|item| item.unwrap().0.value().0)
+            .collect();
+        assert_eq!(positive, vec![0.0, f64::MIN_POSITIVE, 1.0, 1.5, f64::MAX]);
+    }
+}
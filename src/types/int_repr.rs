@@ -0,0 +1,177 @@
+use crate::{Key, TypeName, Value};
+use std::cmp::Ordering;
+use std::fmt::Debug;
+
+// Values are stored as a u64, in the same format and with the same type name as u64 itself, so a
+// table can be opened either with the wrapper or with u64
+
+macro_rules! int_repr_impl {
+    ($name:ident, $doc:literal) => {
+        #[doc = $doc]
+        ///
+        /// The wrapped type is stored as the `u64` that it converts into, and keys are ordered by
+        /// that integer. Tables using it are interchangeable with tables using `u64`.
+        ///
+        /// # Panics
+        ///
+        /// Reading a stored integer which the wrapped type can't be converted from panics
+        #[derive(Copy, Clone, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+        pub struct $name<T>(pub T);
+
+        impl<T> From<T> for $name<T> {
+            fn from(value: T) -> Self {
+                Self(value)
+            }
+        }
+
+        impl<T> Value for $name<T>
+        where
+            T: Copy + Debug + Into<u64> + TryFrom<u64>,
+        {
+            type SelfType<'a>
+                = $name<T>
+            where
+                Self: 'a;
+            type AsBytes<'a>
+                = [u8; size_of::<u64>()]
+            where
+                Self: 'a;
+
+            fn fixed_width() -> Option<usize> {
+                Some(size_of::<u64>())
+            }
+
+            fn from_bytes<'a>(data: &'a [u8]) -> $name<T>
+            where
+                Self: 'a,
+            {
+                let value = u64::from_le_bytes(data.try_into().unwrap());
+                let Ok(inner) = T::try_from(value) else {
+                    panic!("{value} is not a valid {}", std::any::type_name::<T>());
+                };
+                $name(inner)
+            }
+
+            fn as_bytes<'a, 'b: 'a>(value: &'a $name<T>) -> [u8; size_of::<u64>()]
+            where
+                Self: 'b,
+            {
+                let value: u64 = value.0.into();
+                value.to_le_bytes()
+            }
+
+            fn type_name() -> TypeName {
+                <u64>::type_name()
+            }
+        }
+
+        impl<T> Key for $name<T>
+        where
+            T: Copy + Debug + Into<u64> + TryFrom<u64>,
+        {
+            fn compare(data1: &[u8], data2: &[u8]) -> Ordering {
+                <u64>::compare(data1, data2)
+            }
+        }
+    };
+}
+
+int_repr_impl!(
+    EnumRepr,
+    "Stores a fieldless enum, or any other type which converts to and from `u64`"
+);
+int_repr_impl!(
+    Flags,
+    "Stores a set of bit flags, or any other type which converts to and from `u64`"
+);
+
+#[cfg(test)]
+mod tests {
+    use crate::types::int_repr::{EnumRepr, Flags};
+    use crate::{Database, ReadableDatabase, ReadableTable, TableDefinition};
+    use tempfile::NamedTempFile;
+
+    #[derive(Copy, Clone, Debug, PartialEq)]
+    #[repr(u8)]
+    enum Color {
+        Red = 1,
+        Green = 2,
+        Blue = 3,
+    }
+
+    impl From<Color> for u64 {
+        fn from(value: Color) -> Self {
+            value as u64
+        }
+    }
+
+    impl TryFrom<u64> for Color {
+        type Error = ();
+
+        fn try_from(value: u64) -> Result<Self, ()> {
+            match value {
+                1 => Ok(Color::Red),
+                2 => Ok(Color::Green),
+                3 => Ok(Color::Blue),
+                _ => Err(()),
+            }
+        }
+    }
+
+    #[derive(Copy, Clone, Debug, PartialEq)]
+    struct Permissions(u8);
+
+    impl From<Permissions> for u64 {
+        fn from(value: Permissions) -> Self {
+            value.0.into()
+        }
+    }
+
+    impl TryFrom<u64> for Permissions {
+        type Error = std::num::TryFromIntError;
+
+        fn try_from(value: u64) -> Result<Self, Self::Error> {
+            Ok(Permissions(value.try_into()?))
+        }
+    }
+
+    #[test]
+    fn test_int_repr_table() {
+        let definition: TableDefinition<EnumRepr<Color>, Flags<Permissions>> =
+            TableDefinition::new("colors");
+        let u64_definition: TableDefinition<u64, u64> = TableDefinition::new("colors");
+        let db = Database::create(NamedTempFile::new().unwrap()).unwrap();
+        let write_txn = db.begin_write().unwrap();
+        {
+            let mut table = write_txn.open_table(definition).unwrap();
+            for (color, permissions) in [(Color::Blue, 0b101), (Color::Red, 0b1)] {
+                table
+                    .insert(EnumRepr(color), Flags(Permissions(permissions)))
+                    .unwrap();
+            }
+        }
+        write_txn.commit().unwrap();
+
+        let read_txn = db.begin_read().unwrap();
+        let table = read_txn.open_table(definition).unwrap();
+        let entries: Vec<(Color, Permissions)> = table
+            .iter()
+            .unwrap()
+            .map(|entry| {
+                let (key, value) = entry.unwrap();
+                (key.value().0, value.value().0)
+            })
+            .collect();
+        assert_eq!(
+            entries,
+            vec![
+                (Color::Red, Permissions(0b1)),
+                (Color::Blue, Permissions(0b101))
+            ]
+        );
+        assert!(table.get(EnumRepr(Color::Green)).unwrap().is_none());
+
+        let table = read_txn.open_table(u64_definition).unwrap();
+        assert_eq!(table.get(3).unwrap().unwrap().value(), 0b101);
+    }
+}
@@ -0,0 +1,305 @@
+use crate::{Key, TypeName, Value};
+use std::cmp::Ordering;
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr, SocketAddrV4, SocketAddrV6};
+
+// Addresses are stored as their octets, and ports, flow info, and scope ids as little-endian
+// integers. IpAddr and SocketAddr are prefixed with a tag byte (0 for V4, 1 for V6) and zero padded
+// to the width of the V6 variant. Keys are compared by decoding them, so they sort in the same
+// order as the std types, with every V4 address before every V6 address
+
+const IPV4_WIDTH: usize = 4;
+const IPV6_WIDTH: usize = 16;
+const IP_WIDTH: usize = 1 + IPV6_WIDTH;
+const SOCKET_V4_WIDTH: usize = IPV4_WIDTH + 2;
+// Address, port, flow info, and scope id
+const SOCKET_V6_WIDTH: usize = IPV6_WIDTH + 2 + 4 + 4;
+const SOCKET_WIDTH: usize = 1 + SOCKET_V6_WIDTH;
+
+const V4_TAG: u8 = 0;
+const V6_TAG: u8 = 1;
+
+fn ipv4_to_bytes(value: Ipv4Addr) -> [u8; IPV4_WIDTH] {
+    value.octets()
+}
+
+fn ipv4_from_bytes(data: &[u8]) -> Ipv4Addr {
+    Ipv4Addr::from(<[u8; IPV4_WIDTH]>::try_from(data).unwrap())
+}
+
+fn ipv6_to_bytes(value: Ipv6Addr) -> [u8; IPV6_WIDTH] {
+    value.octets()
+}
+
+fn ipv6_from_bytes(data: &[u8]) -> Ipv6Addr {
+    Ipv6Addr::from(<[u8; IPV6_WIDTH]>::try_from(data).unwrap())
+}
+
+fn ip_to_bytes(value: IpAddr) -> [u8; IP_WIDTH] {
+    let mut result = [0; IP_WIDTH];
+    match value {
+        IpAddr::V4(addr) => {
+            result[0] = V4_TAG;
+            result[1..=IPV4_WIDTH].copy_from_slice(&ipv4_to_bytes(addr));
+        }
+        IpAddr::V6(addr) => {
+            result[0] = V6_TAG;
+            result[1..].copy_from_slice(&ipv6_to_bytes(addr));
+        }
+    }
+    result
+}
+
+fn ip_from_bytes(data: &[u8]) -> IpAddr {
+    match data[0] {
+        V4_TAG => IpAddr::V4(ipv4_from_bytes(&data[1..=IPV4_WIDTH])),
+        V6_TAG => IpAddr::V6(ipv6_from_bytes(&data[1..IP_WIDTH])),
+        _ => unreachable!(),
+    }
+}
+
+fn socket_v4_to_bytes(value: SocketAddrV4) -> [u8; SOCKET_V4_WIDTH] {
+    let mut result = [0; SOCKET_V4_WIDTH];
+    result[..IPV4_WIDTH].copy_from_slice(&ipv4_to_bytes(*value.ip()));
+    result[IPV4_WIDTH..].copy_from_slice(&value.port().to_le_bytes());
+    result
+}
+
+fn socket_v4_from_bytes(data: &[u8]) -> SocketAddrV4 {
+    let ip = ipv4_from_bytes(&data[..IPV4_WIDTH]);
+    let port = u16::from_le_bytes(data[IPV4_WIDTH..SOCKET_V4_WIDTH].try_into().unwrap());
+    SocketAddrV4::new(ip, port)
+}
+
+fn socket_v6_to_bytes(value: SocketAddrV6) -> [u8; SOCKET_V6_WIDTH] {
+    let mut result = [0; SOCKET_V6_WIDTH];
+    result[..IPV6_WIDTH].copy_from_slice(&ipv6_to_bytes(*value.ip()));
+    result[IPV6_WIDTH..(IPV6_WIDTH + 2)].copy_from_slice(&value.port().to_le_bytes());
+    result[(IPV6_WIDTH + 2)..(IPV6_WIDTH + 6)].copy_from_slice(&value.flowinfo().to_le_bytes());
+    result[(IPV6_WIDTH + 6)..].copy_from_slice(&value.scope_id().to_le_bytes());
+    result
+}
+
+fn socket_v6_from_bytes(data: &[u8]) -> SocketAddrV6 {
+    let ip = ipv6_from_bytes(&data[..IPV6_WIDTH]);
+    let port = u16::from_le_bytes(data[IPV6_WIDTH..(IPV6_WIDTH + 2)].try_into().unwrap());
+    let flowinfo = u32::from_le_bytes(data[(IPV6_WIDTH + 2)..(IPV6_WIDTH + 6)].try_into().unwrap());
+    let scope_id = u32::from_le_bytes(data[(IPV6_WIDTH + 6)..SOCKET_V6_WIDTH].try_into().unwrap());
+    SocketAddrV6::new(ip, port, flowinfo, scope_id)
+}
+
+fn socket_to_bytes(value: SocketAddr) -> [u8; SOCKET_WIDTH] {
+    let mut result = [0; SOCKET_WIDTH];
+    match value {
+        SocketAddr::V4(addr) => {
+            result[0] = V4_TAG;
+            result[1..=SOCKET_V4_WIDTH].copy_from_slice(&socket_v4_to_bytes(addr));
+        }
+        SocketAddr::V6(addr) => {
+            result[0] = V6_TAG;
+            result[1..].copy_from_slice(&socket_v6_to_bytes(addr));
+        }
+    }
+    result
+}
+
+fn socket_from_bytes(data: &[u8]) -> SocketAddr {
+    match data[0] {
+        V4_TAG => SocketAddr::V4(socket_v4_from_bytes(&data[1..=SOCKET_V4_WIDTH])),
+        V6_TAG => SocketAddr::V6(socket_v6_from_bytes(&data[1..SOCKET_WIDTH])),
+        _ => unreachable!(),
+    }
+}
+
+macro_rules! net_impl {
+    ($t:ty, $width:expr, $to_bytes:ident, $from_bytes:ident) => {
+        impl Value for $t {
+            type SelfType<'a> = $t;
+            type AsBytes<'a>
+                = [u8; $width]
+            where
+                Self: 'a;
+
+            fn fixed_width() -> Option<usize> {
+                Some($width)
+            }
+
+            fn from_bytes<'a>(data: &'a [u8]) -> $t
+            where
+                Self: 'a,
+            {
+                $from_bytes(data)
+            }
+
+            fn as_bytes<'a, 'b: 'a>(value: &'a Self::SelfType<'b>) -> [u8; $width]
+            where
+                Self: 'b,
+            {
+                $to_bytes(*value)
+            }
+
+            fn type_name() -> TypeName {
+                TypeName::internal(stringify!($t))
+            }
+        }
+
+        impl Key for $t {
+            fn compare(data1: &[u8], data2: &[u8]) -> Ordering {
+                $from_bytes(data1).cmp(&$from_bytes(data2))
+            }
+        }
+    };
+}
+
+net_impl!(Ipv4Addr, IPV4_WIDTH, ipv4_to_bytes, ipv4_from_bytes);
+net_impl!(Ipv6Addr, IPV6_WIDTH, ipv6_to_bytes, ipv6_from_bytes);
+net_impl!(IpAddr, IP_WIDTH, ip_to_bytes, ip_from_bytes);
+net_impl!(
+    SocketAddrV4,
+    SOCKET_V4_WIDTH,
+    socket_v4_to_bytes,
+    socket_v4_from_bytes
+);
+net_impl!(
+    SocketAddrV6,
+    SOCKET_V6_WIDTH,
+    socket_v6_to_bytes,
+    socket_v6_from_bytes
+);
+net_impl!(SocketAddr, SOCKET_WIDTH, socket_to_bytes, socket_from_bytes);
+
+#[cfg(test)]
+mod tests {
+    use crate::{Database, Key, ReadableDatabase, ReadableTable, TableDefinition, Value};
+    use std::fmt::Debug;
+    use std::net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr, SocketAddrV4, SocketAddrV6};
+    use tempfile::NamedTempFile;
+
+    fn check_ordering<T>(values: &[T])
+    where
+        T: for<'a> Value<SelfType<'a> = T> + Key + Ord + Debug,
+    {
+        for a in values {
+            assert_eq!(T::from_bytes(T::as_bytes(a).as_ref()), *a);
+            for b in values {
+                assert_eq!(
+                    T::compare(T::as_bytes(a).as_ref(), T::as_bytes(b).as_ref()),
+                    a.cmp(b),
+                    "{a:?} {b:?}"
+                );
+            }
+        }
+    }
+
+    fn ip_values() -> Vec<IpAddr> {
+        vec![
+            IpAddr::V4(Ipv4Addr::UNSPECIFIED),
+            IpAddr::V4(Ipv4Addr::new(10, 0, 0, 1)),
+            IpAddr::V4(Ipv4Addr::new(10, 0, 1, 0)),
+            IpAddr::V4(Ipv4Addr::BROADCAST),
+            IpAddr::V6(Ipv6Addr::UNSPECIFIED),
+            IpAddr::V6(Ipv6Addr::LOCALHOST),
+            IpAddr::V6(Ipv6Addr::new(0xfe80, 0, 0, 0, 0, 0, 0, 1)),
+            IpAddr::V6(Ipv6Addr::new(0xffff, 0xffff, 0, 0, 0, 0, 0, 0)),
+        ]
+    }
+
+    fn socket_values() -> Vec<SocketAddr> {
+        let mut result = vec![];
+        for ip in ip_values() {
+            for port in [0, 80, 443, u16::MAX] {
+                result.push(SocketAddr::new(ip, port));
+            }
+        }
+        result.push(SocketAddr::V6(SocketAddrV6::new(
+            Ipv6Addr::LOCALHOST,
+            80,
+            1,
+            2,
+        )));
+        result.push(SocketAddr::V6(SocketAddrV6::new(
+            Ipv6Addr::LOCALHOST,
+            80,
+            2,
+            1,
+        )));
+        result
+    }
+
+    #[test]
+    fn test_ip_ordering() {
+        let ips = ip_values();
+        check_ordering(&ips);
+        let v4: Vec<Ipv4Addr> = ips
+            .iter()
+            .filter_map(|ip| match ip {
+                IpAddr::V4(ip) => Some(*ip),
+                IpAddr::V6(_) => None,
+            })
+            .collect();
+        check_ordering(&v4);
+        let v6: Vec<Ipv6Addr> = ips
+            .iter()
+            .filter_map(|ip| match ip {
+                IpAddr::V4(_) => None,
+                IpAddr::V6(ip) => Some(*ip),
+            })
+            .collect();
+        check_ordering(&v6);
+    }
+
+    #[test]
+    fn test_socket_ordering() {
+        let sockets = socket_values();
+        check_ordering(&sockets);
+        let v4: Vec<SocketAddrV4> = sockets
+            .iter()
+            .filter_map(|socket| match socket {
+                SocketAddr::V4(socket) => Some(*socket),
+                SocketAddr::V6(_) => None,
+            })
+            .collect();
+        check_ordering(&v4);
+        let v6: Vec<SocketAddrV6> = sockets
+            .iter()
+            .filter_map(|socket| match socket {
+                SocketAddr::V4(_) => None,
+                SocketAddr::V6(socket) => Some(*socket),
+            })
+            .collect();
+        check_ordering(&v6);
+    }
+
+    #[test]
+    fn test_socket_table() {
+        let definition: TableDefinition<SocketAddr, IpAddr> = TableDefinition::new("peers");
+        let db = Database::create(NamedTempFile::new().unwrap()).unwrap();
+        let write_txn = db.begin_write().unwrap();
+        {
+            let mut table = write_txn.open_table(definition).unwrap();
+            for socket in socket_values().iter().rev() {
+                table.insert(socket, socket.ip()).unwrap();
+            }
+        }
+        write_txn.commit().unwrap();
+
+        let mut expected = socket_values();
+        expected.sort();
+        let read_txn = db.begin_read().unwrap();
+        let table = read_txn.open_table(definition).unwrap();
+        let keys: Vec<SocketAddr> = table
+            .iter()
+            .unwrap()
+            .map(|item| item.unwrap().0.value())
+            .collect();
+        assert_eq!(keys, expected);
+
+        // Range scans cover exactly the V4 addresses
+        let start = SocketAddr::new(IpAddr::V4(Ipv4Addr::UNSPECIFIED), 0);
+        let end = SocketAddr::new(IpAddr::V6(Ipv6Addr::UNSPECIFIED), 0);
+        let v4_count = table.range(start..end).unwrap().count();
+        assert_eq!(
+            v4_count,
+            expected.iter().filter(|socket| socket.is_ipv4()).count()
+        );
+    }
+}
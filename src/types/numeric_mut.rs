@@ -0,0 +1,277 @@
+use crate::{DecodeError, MutInPlaceValue, Value};
+use std::fmt::{Debug, Formatter};
+use std::marker::PhantomData;
+
+// Values are not aligned within pages, so numbers can't be borrowed directly. Instead these types
+// wrap the stored little-endian bytes, and convert on each access
+//
+// Table::insert_reserve() rejects lengths which don't match the value's fixed width, and stored
+// values of a fixed width type always have that width, so the length checks below only fail if the
+// page is corrupted
+
+/// A stored number which can be modified in place
+///
+/// Returned by [`AccessGuardMut`](crate::AccessGuardMut) and
+/// [`AccessGuardMutInPlace`](crate::AccessGuardMutInPlace) for tables whose value type is a
+/// primitive number
+#[repr(transparent)]
+pub struct NumericMut<T> {
+    _type: PhantomData<T>,
+    data: [u8],
+}
+
+impl<T> NumericMut<T>
+where
+    T: for<'a> Value<SelfType<'a> = T> + 'static,
+{
+    fn from_bytes_mut(data: &mut [u8]) -> Result<&mut Self, DecodeError> {
+        if data.len() != size_of::<T>() {
+            return Err(DecodeError::new(format!(
+                "{} must be {} bytes, got {}",
+                T::type_name().name(),
+                size_of::<T>(),
+                data.len()
+            )));
+        }
+        // SAFETY: Self is a repr(transparent) wrapper around [u8] (PhantomData is zero sized), so
+        // it has the same layout as the slice and the fat pointer keeps its length. The returned
+        // reference borrows `data` mutably, so it can't outlive or alias it
+        Ok(unsafe { &mut *(std::ptr::from_mut::<[u8]>(data) as *mut Self) })
+    }
+
+    /// Returns the stored number
+    pub fn get(&self) -> T {
+        T::from_bytes(&self.data)
+    }
+
+    /// Replace the stored number
+    pub fn set(&mut self, value: T) {
+        self.data.copy_from_slice(T::as_bytes(&value).as_ref());
+    }
+}
+
+impl<T> Debug for NumericMut<T>
+where
+    T: for<'a> Value<SelfType<'a> = T> + 'static + Debug,
+{
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        Debug::fmt(&self.get(), f)
+    }
+}
+
+/// A stored array of numbers whose elements can be modified in place
+///
+/// Returned by [`AccessGuardMut`](crate::AccessGuardMut) and
+/// [`AccessGuardMutInPlace`](crate::AccessGuardMutInPlace) for tables whose value type is an
+/// array of a primitive number type, other than `u8`. Arrays of `u8` are accessed as `[u8; N]`
+#[repr(transparent)]
+pub struct NumericSliceMut<T> {
+    _type: PhantomData<T>,
+    data: [u8],
+}
+
+impl<T> NumericSliceMut<T>
+where
+    T: for<'a> Value<SelfType<'a> = T> + 'static,
+{
+    fn from_bytes_mut(data: &mut [u8], len: usize) -> Result<&mut Self, DecodeError> {
+        if data.len() != len * size_of::<T>() {
+            return Err(DecodeError::new(format!(
+                "[{}; {len}] must be {} bytes, got {}",
+                T::type_name().name(),
+                len * size_of::<T>(),
+                data.len()
+            )));
+        }
+        // SAFETY: same as NumericMut::from_bytes_mut(). Elements are read and written through
+        // from_bytes() and as_bytes(), so no alignment is required
+        Ok(unsafe { &mut *(std::ptr::from_mut::<[u8]>(data) as *mut Self) })
+    }
+
+    /// Returns the number of elements
+    pub fn len(&self) -> usize {
+        self.data.len() / size_of::<T>()
+    }
+
+    /// Returns `true` if there are no elements
+    pub fn is_empty(&self) -> bool {
+        self.data.is_empty()
+    }
+
+    /// Returns the element at the given index
+    ///
+    /// # Panics
+    ///
+    /// Panics if `index` is out of bounds
+    pub fn get(&self, index: usize) -> T {
+        T::from_bytes(&self.data[Self::range(index)])
+    }
+
+    /// Replace the element at the given index
+    ///
+    /// # Panics
+    ///
+    /// Panics if `index` is out of bounds
+    pub fn set(&mut self, index: usize, value: T) {
+        self.data[Self::range(index)].copy_from_slice(T::as_bytes(&value).as_ref());
+    }
+
+    /// Iterate over the elements
+    pub fn iter(&self) -> impl ExactSizeIterator<Item = T> + '_ {
+        (0..self.len()).map(|i| self.get(i))
+    }
+
+    fn range(index: usize) -> std::ops::Range<usize> {
+        (index * size_of::<T>())..((index + 1) * size_of::<T>())
+    }
+}
+
+impl<T> Debug for NumericSliceMut<T>
+where
+    T: for<'a> Value<SelfType<'a> = T> + 'static + Debug,
+{
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.debug_list().entries(self.iter()).finish()
+    }
+}
+
+impl<const N: usize> MutInPlaceValue for [u8; N] {
+    type BaseRefType = [u8; N];
+
+    fn initialize(_data: &mut [u8]) {
+        // no-op. All values are valid.
+    }
+
+    fn from_bytes_mut(data: &mut [u8]) -> &mut Self::BaseRefType {
+        data.try_into().unwrap()
+    }
+}
+
+macro_rules! numeric_mut_impl {
+    ($t:ty) => {
+        impl MutInPlaceValue for $t {
+            type BaseRefType = NumericMut<$t>;
+
+            fn initialize(_data: &mut [u8]) {
+                // no-op. All values are valid.
+            }
+
+            fn from_bytes_mut(data: &mut [u8]) -> &mut Self::BaseRefType {
+                NumericMut::from_bytes_mut(data).unwrap()
+            }
+        }
+    };
+}
+
+macro_rules! numeric_slice_mut_impl {
+    ($t:ty) => {
+        impl<const N: usize> MutInPlaceValue for [$t; N] {
+            type BaseRefType = NumericSliceMut<$t>;
+
+            fn initialize(_data: &mut [u8]) {
+                // no-op. All values are valid.
+            }
+
+            fn from_bytes_mut(data: &mut [u8]) -> &mut Self::BaseRefType {
+                NumericSliceMut::from_bytes_mut(data, N).unwrap()
+            }
+        }
+    };
+}
+
+numeric_mut_impl!(u8);
+numeric_mut_impl!(u16);
+numeric_mut_impl!(u32);
+numeric_mut_impl!(u64);
+numeric_mut_impl!(u128);
+numeric_mut_impl!(i8);
+numeric_mut_impl!(i16);
+numeric_mut_impl!(i32);
+numeric_mut_impl!(i64);
+numeric_mut_impl!(i128);
+numeric_mut_impl!(f32);
+numeric_mut_impl!(f64);
+
+numeric_slice_mut_impl!(u16);
+numeric_slice_mut_impl!(u32);
+numeric_slice_mut_impl!(u64);
+numeric_slice_mut_impl!(u128);
+numeric_slice_mut_impl!(i8);
+numeric_slice_mut_impl!(i16);
+numeric_slice_mut_impl!(i32);
+numeric_slice_mut_impl!(i64);
+numeric_slice_mut_impl!(i128);
+numeric_slice_mut_impl!(f32);
+numeric_slice_mut_impl!(f64);
+
+#[cfg(test)]
+mod tests {
+    use super::{NumericMut, NumericSliceMut};
+    use crate::{Database, ReadableDatabase, StorageError, TableDefinition};
+    use tempfile::NamedTempFile;
+
+    #[test]
+    fn test_mutate_in_place() {
+        let counters: TableDefinition<&str, u64> = TableDefinition::new("counters");
+        let buffers: TableDefinition<u8, [i32; 3]> = TableDefinition::new("buffers");
+        let hashes: TableDefinition<u8, [u8; 4]> = TableDefinition::new("hashes");
+
+        let db = Database::create(NamedTempFile::new().unwrap()).unwrap();
+        let write_txn = db.begin_write().unwrap();
+        {
+            let mut table = write_txn.open_table(counters).unwrap();
+            table.insert("a", 5).unwrap();
+            let mut guard = table.get_mut("a").unwrap().unwrap();
+            let counter = guard.as_mut();
+            counter.set(counter.get() + 1);
+            drop(guard);
+            table.insert_reserve("b", 8).unwrap().as_mut().set(7);
+
+            let mut table = write_txn.open_table(buffers).unwrap();
+            let mut guard = table.insert_reserve(0, 12).unwrap();
+            let buffer = guard.as_mut();
+            assert_eq!(buffer.len(), 3);
+            for (i, x) in [10, -20, 30].into_iter().enumerate() {
+                buffer.set(i, x);
+            }
+            drop(guard);
+            table.get_mut(0).unwrap().unwrap().as_mut().set(2, 31);
+
+            let mut table = write_txn.open_table(hashes).unwrap();
+            table.insert(0, [1, 2, 3, 4]).unwrap();
+            table.get_mut(0).unwrap().unwrap().as_mut()[3] = 9;
+        }
+        write_txn.commit().unwrap();
+
+        let read_txn = db.begin_read().unwrap();
+        let table = read_txn.open_table(counters).unwrap();
+        assert_eq!(table.get("a").unwrap().unwrap().value(), 6);
+        assert_eq!(table.get("b").unwrap().unwrap().value(), 7);
+        let table = read_txn.open_table(buffers).unwrap();
+        assert_eq!(table.get(0).unwrap().unwrap().value(), [10, -20, 31]);
+        let table = read_txn.open_table(hashes).unwrap();
+        assert_eq!(table.get(0).unwrap().unwrap().value(), [1, 2, 3, 9]);
+    }
+
+    #[test]
+    fn test_wrong_length() {
+        let mut data = [0u8; 12];
+        assert!(NumericMut::<u64>::from_bytes_mut(&mut data).is_err());
+        assert!(NumericMut::<u64>::from_bytes_mut(&mut data[..8]).is_ok());
+        assert!(NumericSliceMut::<i32>::from_bytes_mut(&mut data, 2).is_err());
+        assert!(NumericSliceMut::<i32>::from_bytes_mut(&mut data, 3).is_ok());
+
+        let counters: TableDefinition<&str, u64> = TableDefinition::new("counters");
+        let db = Database::create(NamedTempFile::new().unwrap()).unwrap();
+        let write_txn = db.begin_write().unwrap();
+        {
+            let mut table = write_txn.open_table(counters).unwrap();
+            assert!(matches!(
+                table.insert_reserve("a", 3),
+                Err(StorageError::Decode(_))
+            ));
+            table.insert_reserve("a", 8).unwrap().as_mut().set(1);
+        }
+        write_txn.commit().unwrap();
+    }
+}
@@ -0,0 +1,198 @@
+use crate::{Key, TypeName, Value};
+use std::borrow::Cow;
+use std::cmp::Ordering;
+use std::ffi::{OsStr, OsString};
+#[cfg(unix)]
+use std::os::unix::ffi::OsStrExt;
+#[cfg(target_os = "wasi")]
+use std::os::wasi::ffi::OsStrExt;
+#[cfg(windows)]
+use std::os::windows::ffi::{OsStrExt, OsStringExt};
+use std::path::{Path, PathBuf};
+
+// On Unix and WASI, OsStrings are stored as their raw bytes. On Windows, they are stored as their
+// UTF-16 code units, which may include unpaired surrogates, as little-endian u16s. The type name
+// includes the platform, so that a table can't be opened on a platform that would decode it
+// differently
+
+#[cfg(any(unix, target_os = "wasi"))]
+const PLATFORM: &str = "unix";
+#[cfg(windows)]
+const PLATFORM: &str = "windows";
+
+#[cfg(any(unix, target_os = "wasi"))]
+fn encode(value: &OsStr) -> Cow<'_, [u8]> {
+    Cow::Borrowed(value.as_bytes())
+}
+
+#[cfg(any(unix, target_os = "wasi"))]
+fn decode(data: &[u8]) -> Cow<'_, OsStr> {
+    Cow::Borrowed(OsStr::from_bytes(data))
+}
+
+#[cfg(windows)]
+fn encode(value: &OsStr) -> Cow<'_, [u8]> {
+    Cow::Owned(value.encode_wide().flat_map(u16::to_le_bytes).collect())
+}
+
+#[cfg(windows)]
+fn decode(data: &[u8]) -> Cow<'_, OsStr> {
+    let wide: Vec<u16> = data
+        .chunks_exact(2)
+        .map(|pair| u16::from_le_bytes([pair[0], pair[1]]))
+        .collect();
+    Cow::Owned(OsString::from_wide(&wide))
+}
+
+impl Value for OsString {
+    type SelfType<'a>
+        = OsString
+    where
+        Self: 'a;
+    type AsBytes<'a>
+        = Cow<'a, [u8]>
+    where
+        Self: 'a;
+
+    fn fixed_width() -> Option<usize> {
+        None
+    }
+
+    fn from_bytes<'a>(data: &'a [u8]) -> OsString
+    where
+        Self: 'a,
+    {
+        decode(data).into_owned()
+    }
+
+    fn as_bytes<'a, 'b: 'a>(value: &'a Self::SelfType<'b>) -> Cow<'a, [u8]>
+    where
+        Self: 'b,
+    {
+        encode(value)
+    }
+
+    fn type_name() -> TypeName {
+        TypeName::internal(&format!("OsString({PLATFORM})"))
+    }
+}
+
+impl Key for OsString {
+    fn compare(data1: &[u8], data2: &[u8]) -> Ordering {
+        decode(data1).cmp(&decode(data2))
+    }
+}
+
+impl Value for PathBuf {
+    type SelfType<'a>
+        = PathBuf
+    where
+        Self: 'a;
+    type AsBytes<'a>
+        = Cow<'a, [u8]>
+    where
+        Self: 'a;
+
+    fn fixed_width() -> Option<usize> {
+        None
+    }
+
+    fn from_bytes<'a>(data: &'a [u8]) -> PathBuf
+    where
+        Self: 'a,
+    {
+        PathBuf::from(decode(data).into_owned())
+    }
+
+    fn as_bytes<'a, 'b: 'a>(value: &'a Self::SelfType<'b>) -> Cow<'a, [u8]>
+    where
+        Self: 'b,
+    {
+        encode(value.as_os_str())
+    }
+
+    fn type_name() -> TypeName {
+        TypeName::internal(&format!("PathBuf({PLATFORM})"))
+    }
+}
+
+// Paths are compared component-wise, as Path does, so "a/b" sorts before "a-b"
+impl Key for PathBuf {
+    fn compare(data1: &[u8], data2: &[u8]) -> Ordering {
+        Path::new(&*decode(data1)).cmp(Path::new(&*decode(data2)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{Database, Key, ReadableDatabase, ReadableTable, TableDefinition, Value};
+    use std::ffi::OsString;
+    use std::path::PathBuf;
+    use tempfile::NamedTempFile;
+
+    fn path_values() -> Vec<PathBuf> {
+        let mut result: Vec<PathBuf> = ["", "a", "a/b", "a/b/c", "a-b", "b", "\u{e9}t\u{e9}"]
+            .into_iter()
+            .map(PathBuf::from)
+            .collect();
+        #[cfg(unix)]
+        {
+            use std::os::unix::ffi::OsStringExt;
+            result.push(PathBuf::from(OsString::from_vec(vec![b'a', 0xff, 0xfe])));
+        }
+        result
+    }
+
+    #[test]
+    fn test_path_ordering() {
+        let paths = path_values();
+        for a in &paths {
+            assert_eq!(&PathBuf::from_bytes(&PathBuf::as_bytes(a)), a);
+            for b in &paths {
+                assert_eq!(
+                    PathBuf::compare(&PathBuf::as_bytes(a), &PathBuf::as_bytes(b)),
+                    a.cmp(b),
+                    "{a:?} {b:?}"
+                );
+            }
+        }
+
+        let strings: Vec<OsString> = paths.into_iter().map(PathBuf::into_os_string).collect();
+        for a in &strings {
+            assert_eq!(&OsString::from_bytes(&OsString::as_bytes(a)), a);
+            for b in &strings {
+                assert_eq!(
+                    OsString::compare(&OsString::as_bytes(a), &OsString::as_bytes(b)),
+                    a.cmp(b),
+                    "{a:?} {b:?}"
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn test_path_table() {
+        let definition: TableDefinition<PathBuf, OsString> = TableDefinition::new("files");
+        let db = Database::create(NamedTempFile::new().unwrap()).unwrap();
+        let write_txn = db.begin_write().unwrap();
+        {
+            let mut table = write_txn.open_table(definition).unwrap();
+            for path in path_values() {
+                table.insert(&path, path.clone().into_os_string()).unwrap();
+            }
+        }
+        write_txn.commit().unwrap();
+
+        let mut expected = path_values();
+        expected.sort();
+        let read_txn = db.begin_read().unwrap();
+        let table = read_txn.open_table(definition).unwrap();
+        let mut keys = vec![];
+        for entry in table.iter().unwrap() {
+            let (key, value) = entry.unwrap();
+            assert_eq!(key.value().into_os_string(), value.value());
+            keys.push(key.value());
+        }
+        assert_eq!(keys, expected);
+    }
+}
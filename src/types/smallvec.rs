@@ -0,0 +1,74 @@
+use crate::complex_types::{decode_sequence, encode_sequence};
+use crate::{TypeName, Value};
+use smallvec::SmallVec;
+
+// Stored in the same format, and with the same type name, as Vec, so the two are interchangeable
+impl<T: Value, const N: usize> Value for SmallVec<[T; N]> {
+    type SelfType<'a>
+        = SmallVec<[T::SelfType<'a>; N]>
+    where
+        Self: 'a;
+    type AsBytes<'a>
+        = Vec<u8>
+    where
+        Self: 'a;
+
+    fn fixed_width() -> Option<usize> {
+        None
+    }
+
+    fn from_bytes<'a>(data: &'a [u8]) -> SmallVec<[T::SelfType<'a>; N]>
+    where
+        Self: 'a,
+    {
+        decode_sequence::<T, _>(data)
+    }
+
+    fn as_bytes<'a, 'b: 'a>(value: &'a SmallVec<[T::SelfType<'b>; N]>) -> Vec<u8>
+    where
+        Self: 'b,
+    {
+        encode_sequence::<T>(value)
+    }
+
+    fn type_name() -> TypeName {
+        <Vec<T>>::type_name()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{Database, ReadableDatabase, TableDefinition};
+    use smallvec::{SmallVec, smallvec};
+    use tempfile::NamedTempFile;
+
+    #[test]
+    fn test_smallvec_table() {
+        let definition: TableDefinition<u64, SmallVec<[&str; 2]>> = TableDefinition::new("table");
+        let vec_definition: TableDefinition<u64, Vec<&str>> = TableDefinition::new("table");
+        let db = Database::create(NamedTempFile::new().unwrap()).unwrap();
+        let write_txn = db.begin_write().unwrap();
+        {
+            let mut table = write_txn.open_table(definition).unwrap();
+            let inline: SmallVec<[&str; 2]> = smallvec!["a", "b"];
+            let spilled: SmallVec<[&str; 2]> = smallvec!["a", "b", "c"];
+            table.insert(0, &inline).unwrap();
+            table.insert(1, &spilled).unwrap();
+        }
+        write_txn.commit().unwrap();
+
+        let read_txn = db.begin_read().unwrap();
+        let table = read_txn.open_table(definition).unwrap();
+        assert_eq!(
+            table.get(0).unwrap().unwrap().value().as_slice(),
+            ["a", "b"]
+        );
+        assert_eq!(
+            table.get(1).unwrap().unwrap().value().as_slice(),
+            ["a", "b", "c"]
+        );
+        // Compatible with Vec
+        let table = read_txn.open_table(vec_definition).unwrap();
+        assert_eq!(table.get(1).unwrap().unwrap().value(), vec!["a", "b", "c"]);
+    }
+}
@@ -0,0 +1,169 @@
+//! `OffsetDateTime` is serialized to a fixed-width byte array, using the same layout as
+//! `chrono::DateTime<FixedOffset>`. All integers are little endian:
+//!
+//!| Structure      | Layout                                                                                                                   | Size |                                                  |
+//!|----------------|--------------------------------------------------------------------------------------------------------------------------|------|--------------------------------------------------|
+//!| OffsetDateTime | `{year:i32}\|{month:u8}\|{day:u8}\|{seconds_from_midnight:u32:first 3 bytes}\|{nanoseconds:u32}\|{seconds_from_utc:i32}` | 17   | Time is stored in UTC with the offset in seconds |
+use crate::{Key, TypeName, Value};
+
+use time_v0_3::{Date, Month, OffsetDateTime, PrimitiveDateTime, Time, UtcOffset};
+
+/// A 17-byte representation of a date and time, stored in UTC followed by the offset from UTC in
+/// seconds. Values are ordered by the instant they represent, regardless of their offset
+impl Value for OffsetDateTime {
+    type SelfType<'a>
+        = OffsetDateTime
+    where
+        Self: 'a;
+
+    type AsBytes<'a>
+        = [u8; 17]
+    where
+        Self: 'a;
+
+    fn fixed_width() -> Option<usize> {
+        Some(17)
+    }
+
+    fn from_bytes<'a>(data: &'a [u8]) -> Self::SelfType<'a>
+    where
+        Self: 'a,
+    {
+        assert_eq!(
+            data.len(),
+            17,
+            "OffsetDateTime must be 17 bytes long, got {}",
+            data.len()
+        );
+        let date = date_from_bytes(&data[0..6]);
+        let time = time_from_bytes(&data[6..13]);
+        let offset_seconds = i32::from_le_bytes(data[13..17].try_into().unwrap());
+        let offset = UtcOffset::from_whole_seconds(offset_seconds)
+            .expect("Invalid offset seconds, must be between -93599 and 93599");
+        PrimitiveDateTime::new(date, time)
+            .assume_utc()
+            .to_offset(offset)
+    }
+
+    fn as_bytes<'a, 'b: 'a>(value: &'a Self::SelfType<'b>) -> Self::AsBytes<'a>
+    where
+        Self: 'b,
+    {
+        let value_utc = value.to_offset(UtcOffset::UTC);
+        let year = value_utc.year().to_le_bytes();
+        let month = u8::from(value_utc.month());
+        let day = value_utc.day();
+        let (hour, minute, second) = value_utc.to_hms();
+        let time_since_midnight =
+            (u32::from(hour) * 3600 + u32::from(minute) * 60 + u32::from(second)).to_le_bytes();
+        let nanoseconds = value_utc.nanosecond().to_le_bytes();
+        let offset_seconds = value.offset().whole_seconds().to_le_bytes();
+        [
+            year[0],
+            year[1],
+            year[2],
+            year[3],
+            month,
+            day,
+            time_since_midnight[0],
+            time_since_midnight[1],
+            time_since_midnight[2],
+            nanoseconds[0],
+            nanoseconds[1],
+            nanoseconds[2],
+            nanoseconds[3],
+            offset_seconds[0],
+            offset_seconds[1],
+            offset_seconds[2],
+            offset_seconds[3],
+        ]
+    }
+
+    fn type_name() -> TypeName {
+        TypeName::new("time::OffsetDateTime")
+    }
+}
+impl Key for OffsetDateTime {
+    fn compare(data1: &[u8], data2: &[u8]) -> std::cmp::Ordering {
+        let datetime1 = OffsetDateTime::from_bytes(data1);
+        let datetime2 = OffsetDateTime::from_bytes(data2);
+        datetime1.cmp(&datetime2)
+    }
+}
+fn date_from_bytes(data: &[u8]) -> Date {
+    let year = i32::from_le_bytes(data[0..4].try_into().unwrap());
+    let month = Month::try_from(data[4]).expect("Invalid month");
+    Date::from_calendar_date(year, month, data[5]).expect("Invalid date")
+}
+fn time_from_bytes(data: &[u8]) -> Time {
+    let num_seconds = u32::from_le_bytes([data[0], data[1], data[2], 0]);
+    let num_nanoseconds = u32::from_le_bytes(data[3..7].try_into().unwrap());
+    let hour = u8::try_from(num_seconds / 3600).unwrap();
+    let minute = u8::try_from(num_seconds / 60 % 60).unwrap();
+    let second = u8::try_from(num_seconds % 60).unwrap();
+    Time::from_hms_nano(hour, minute, second, num_nanoseconds).expect("Invalid time")
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{Database, Key, ReadableDatabase, TableDefinition, Value};
+    use tempfile::NamedTempFile;
+    use time_v0_3::{Duration, OffsetDateTime, UtcOffset};
+
+    const OFFSET_DATETIME_TABLE: TableDefinition<OffsetDateTime, i32> =
+        TableDefinition::new("offset_datetime_table");
+
+    #[test]
+    fn test_offset_date_time() {
+        let utc = OffsetDateTime::from_unix_timestamp_nanos(1_696_509_045_000_000_123).unwrap();
+        for i in -12..=12 {
+            let offset = UtcOffset::from_whole_seconds(i * 3600 + 30).unwrap();
+            let datetime = utc.to_offset(offset);
+            let bytes = OffsetDateTime::as_bytes(&datetime);
+            assert_eq!(
+                OffsetDateTime::fixed_width(),
+                Some(bytes.len()),
+                "OffsetDateTime should have fixed width"
+            );
+            let datetime_from_bytes = OffsetDateTime::from_bytes(&bytes);
+            assert_eq!(datetime, datetime_from_bytes);
+            assert_eq!(datetime.offset(), datetime_from_bytes.offset());
+
+            let earlier = OffsetDateTime::as_bytes(&(utc - Duration::nanoseconds(1)));
+            assert_eq!(
+                OffsetDateTime::compare(&earlier, &bytes),
+                std::cmp::Ordering::Less
+            );
+        }
+    }
+
+    #[test]
+    fn test_offset_datetime_table() {
+        let start = OffsetDateTime::from_unix_timestamp(-60)
+            .unwrap()
+            .to_offset(UtcOffset::from_hms(-5, 0, 0).unwrap());
+        let db = Database::create(NamedTempFile::new().unwrap()).unwrap();
+        let write_txn = db.begin_write().unwrap();
+        {
+            let mut table = write_txn.open_table(OFFSET_DATETIME_TABLE).unwrap();
+            for i in (0..100).rev() {
+                table.insert(start + Duration::seconds(i), 1).unwrap();
+            }
+        }
+        write_txn.commit().unwrap();
+
+        let read_txn = db.begin_read().unwrap();
+        {
+            let table = read_txn.open_table(OFFSET_DATETIME_TABLE).unwrap();
+            let end = start + Duration::seconds(10);
+            let keys: Vec<OffsetDateTime> = table
+                .range(start..end)
+                .unwrap()
+                .map(|item| item.unwrap().0.value())
+                .collect();
+            let expected: Vec<OffsetDateTime> =
+                (0..10).map(|i| start + Duration::seconds(i)).collect();
+            assert_eq!(keys, expected);
+        }
+    }
+}
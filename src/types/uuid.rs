@@ -35,6 +35,8 @@ impl Value for Uuid {
     }
 }
 
+// The bytes of a Uuid are big-endian, so byte-wise order matches the order of the Uuid, and
+// version 7 UUIDs sort by their timestamp
 impl Key for Uuid {
     fn compare(data1: &[u8], data2: &[u8]) -> Ordering {
         data1.cmp(data2)
@@ -43,9 +45,9 @@ impl Key for Uuid {
 
 #[cfg(test)]
 mod tests {
-    use crate::{Database, Key, ReadableDatabase, TableDefinition, Value};
+    use crate::{Database, Key, ReadableDatabase, ReadableTable, TableDefinition, Value};
     use tempfile::NamedTempFile;
-    use uuid::Uuid;
+    use uuid::{Builder, Uuid};
 
     const UUID_TABLE: TableDefinition<Uuid, Uuid> = TableDefinition::new("table");
 
@@ -61,6 +63,40 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_uuid_v7_ordering() {
+        let db = Database::create(NamedTempFile::new().unwrap()).unwrap();
+        let write_txn = db.begin_write().unwrap();
+        {
+            let mut table = write_txn.open_table(UUID_TABLE).unwrap();
+            // Insert in reverse time order, with random bytes that decrease as time increases
+            for millis in (0..100u8).rev() {
+                let uuid = Builder::from_unix_timestamp_millis(
+                    1_700_000_000_000 + u64::from(millis) * 1000,
+                    &[u8::MAX - millis; 10],
+                )
+                .into_uuid();
+                table.insert(uuid, uuid).unwrap();
+            }
+        }
+        write_txn.commit().unwrap();
+
+        let read_txn = db.begin_read().unwrap();
+        let table = read_txn.open_table(UUID_TABLE).unwrap();
+        let timestamps: Vec<u64> = table
+            .iter()
+            .unwrap()
+            .map(|item| {
+                let (seconds, _) = item.unwrap().0.value().get_timestamp().unwrap().to_unix();
+                seconds
+            })
+            .collect();
+        let mut expected = timestamps.clone();
+        expected.sort_unstable();
+        assert_eq!(timestamps.len(), 100);
+        assert_eq!(timestamps, expected);
+    }
+
     #[test]
     fn test_uuid_table() {
         let uuid1 = Uuid::new_v4();
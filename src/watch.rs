@@ -0,0 +1,302 @@
+use crate::db::TransactionGuard;
+use crate::tree_store::{
+    AccessGuard, Btree, BtreeHeader, InternalTableDefinition, PageHint, PageResolver, TableTree,
+    TableTreeMut, TableType,
+};
+use crate::types::{Key, Value};
+use crate::{Result, TableDefinition, TableError, TableHandle};
+use std::borrow::Borrow;
+use std::cmp::Ordering;
+use std::fmt::{Debug, Formatter};
+use std::marker::PhantomData;
+use std::ops::{Bound, RangeBounds};
+use std::sync::atomic::{self, AtomicBool};
+use std::sync::mpsc::{Receiver, RecvTimeoutError, Sender, TryRecvError};
+use std::sync::{Arc, Mutex, mpsc};
+use std::time::Duration;
+
+enum RawWatchEvent {
+    Insert(Vec<u8>, Vec<u8>),
+    Remove(Vec<u8>, Vec<u8>),
+}
+
+/// A change to a watched key, delivered by a [`Watch`]
+#[non_exhaustive]
+pub enum WatchEvent<K: Key + 'static, V: Value + 'static> {
+    /// The key was inserted, or its value was replaced
+    Insert {
+        key: AccessGuard<'static, K>,
+        value: AccessGuard<'static, V>,
+    },
+    /// The key was removed. `value` is the value it had before it was removed
+    Remove {
+        key: AccessGuard<'static, K>,
+        value: AccessGuard<'static, V>,
+    },
+}
+
+impl<K: Key + 'static, V: Value + 'static> WatchEvent<K, V> {
+    fn from_raw(event: RawWatchEvent) -> Self {
+        match event {
+            RawWatchEvent::Insert(key, value) => WatchEvent::Insert {
+                key: AccessGuard::with_owned_value(key),
+                value: AccessGuard::with_owned_value(value),
+            },
+            RawWatchEvent::Remove(key, value) => WatchEvent::Remove {
+                key: AccessGuard::with_owned_value(key),
+                value: AccessGuard::with_owned_value(value),
+            },
+        }
+    }
+
+    /// The key which changed
+    pub fn key(&self) -> &AccessGuard<'static, K> {
+        match self {
+            WatchEvent::Insert { key, .. } | WatchEvent::Remove { key, .. } => key,
+        }
+    }
+}
+
+impl<K: Key + 'static, V: Value + 'static> Debug for WatchEvent<K, V> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            WatchEvent::Insert { key, value } => f
+                .debug_struct("Insert")
+                .field("key", &key.value())
+                .field("value", &value.value())
+                .finish(),
+            WatchEvent::Remove { key, value } => f
+                .debug_struct("Remove")
+                .field("key", &key.value())
+                .field("value", &value.value())
+                .finish(),
+        }
+    }
+}
+
+/// Receives the changes made to a range of keys in a table, created by
+/// [`crate::Database::watch`]
+///
+/// Events are delivered after the transaction which made them has committed, in key order within
+/// each transaction, and in commit order across transactions. Changes made by aborted transactions
+/// are never delivered.
+///
+/// Iterating over a [`Watch`] blocks until the next event is available, and ends once the database
+/// has been closed.
+pub struct Watch<K: Key + 'static, V: Value + 'static> {
+    receiver: Receiver<RawWatchEvent>,
+    closed: Arc<AtomicBool>,
+    _types: PhantomData<fn() -> (K, V)>,
+}
+
+impl<K: Key + 'static, V: Value + 'static> Watch<K, V> {
+    /// Wait for the next event
+    ///
+    /// Returns `None` once the database has been closed, and every event has been received
+    pub fn recv(&self) -> Option<WatchEvent<K, V>> {
+        self.receiver.recv().ok().map(WatchEvent::from_raw)
+    }
+
+    /// Wait up to `timeout` for the next event
+    ///
+    /// Returns `None` if no event arrived in time, or if the database has been closed
+    pub fn recv_timeout(&self, timeout: Duration) -> Option<WatchEvent<K, V>> {
+        match self.receiver.recv_timeout(timeout) {
+            Ok(event) => Some(WatchEvent::from_raw(event)),
+            Err(RecvTimeoutError::Timeout | RecvTimeoutError::Disconnected) => None,
+        }
+    }
+
+    /// Returns the next event, if one is available without waiting
+    pub fn try_recv(&self) -> Option<WatchEvent<K, V>> {
+        match self.receiver.try_recv() {
+            Ok(event) => Some(WatchEvent::from_raw(event)),
+            Err(TryRecvError::Empty | TryRecvError::Disconnected) => None,
+        }
+    }
+}
+
+impl<K: Key + 'static, V: Value + 'static> Iterator for Watch<K, V> {
+    type Item = WatchEvent<K, V>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.recv()
+    }
+}
+
+impl<K: Key + 'static, V: Value + 'static> Drop for Watch<K, V> {
+    fn drop(&mut self) {
+        self.closed.store(true, atomic::Ordering::Release);
+    }
+}
+
+impl<K: Key + 'static, V: Value + 'static> Debug for Watch<K, V> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Watch").finish_non_exhaustive()
+    }
+}
+
+pub(crate) trait Watcher: Send + Sync {
+    fn is_closed(&self) -> bool;
+
+    // Computes the events for the changes to the watched range, between the last committed table
+    // tree and the one about to be committed
+    fn diff(
+        &self,
+        committed: &TableTree,
+        pending: &TableTreeMut,
+        guard: &Arc<TransactionGuard>,
+        mem: &PageResolver,
+    ) -> Result<PendingEvents>;
+}
+
+// Events computed before a commit, which are sent once it has succeeded
+pub(crate) struct PendingEvents {
+    sender: Sender<RawWatchEvent>,
+    events: Vec<RawWatchEvent>,
+}
+
+impl PendingEvents {
+    pub(crate) fn is_empty(&self) -> bool {
+        self.events.is_empty()
+    }
+
+    pub(crate) fn send(self) {
+        for event in self.events {
+            // The Watch has been dropped
+            if self.sender.send(event).is_err() {
+                break;
+            }
+        }
+    }
+}
+
+struct RangeWatcher<K: Key + 'static, V: Value + 'static> {
+    name: String,
+    start: Bound<Vec<u8>>,
+    end: Bound<Vec<u8>>,
+    sender: Sender<RawWatchEvent>,
+    // Set when the Watch is dropped
+    closed: Arc<AtomicBool>,
+    _types: PhantomData<fn() -> (K, V)>,
+}
+
+impl<K: Key + 'static, V: Value + 'static> Watcher for RangeWatcher<K, V> {
+    fn is_closed(&self) -> bool {
+        self.closed.load(atomic::Ordering::Acquire)
+    }
+
+    fn diff(
+        &self,
+        committed: &TableTree,
+        pending: &TableTreeMut,
+        guard: &Arc<TransactionGuard>,
+        mem: &PageResolver,
+    ) -> Result<PendingEvents> {
+        let mut events = vec![];
+        let old_root = table_root(committed.get_table::<K, V>(&self.name, TableType::Normal))?;
+        let new_root = table_root(pending.get_table::<K, V>(&self.name, TableType::Normal))?;
+        if old_root == new_root {
+            return Ok(PendingEvents {
+                sender: self.sender.clone(),
+                events,
+            });
+        }
+        let start = self.start.as_ref().map(|x| K::from_bytes(x));
+        let end = self.end.as_ref().map(|x| K::from_bytes(x));
+        let range = (start, end);
+        let old = Btree::<K, V>::new(old_root, PageHint::None, guard.clone(), mem.clone())?;
+        let new = Btree::<K, V>::new(new_root, PageHint::None, guard.clone(), mem.clone())?;
+        let mut old_iter = old.range(&range)?.peekable();
+        let mut new_iter = new.range(&range)?.peekable();
+        loop {
+            let order = match (old_iter.peek(), new_iter.peek()) {
+                (None, None) => break,
+                (Some(_), None) => Ordering::Less,
+                (None, Some(_)) => Ordering::Greater,
+                (Some(Err(_)), _) => return Err(old_iter.next().unwrap().err().unwrap()),
+                (_, Some(Err(_))) => return Err(new_iter.next().unwrap().err().unwrap()),
+                (Some(Ok(old_entry)), Some(Ok(new_entry))) => {
+                    K::compare(&old_entry.key_data(), &new_entry.key_data())
+                }
+            };
+            match order {
+                Ordering::Less => {
+                    let entry = old_iter.next().unwrap()?;
+                    events.push(RawWatchEvent::Remove(entry.key_data(), entry.value_data()));
+                }
+                Ordering::Greater => {
+                    let entry = new_iter.next().unwrap()?;
+                    events.push(RawWatchEvent::Insert(entry.key_data(), entry.value_data()));
+                }
+                Ordering::Equal => {
+                    let old_entry = old_iter.next().unwrap()?;
+                    let new_entry = new_iter.next().unwrap()?;
+                    let value = new_entry.value_data();
+                    if old_entry.value_data() != value {
+                        events.push(RawWatchEvent::Insert(new_entry.key_data(), value));
+                    }
+                }
+            }
+        }
+
+        Ok(PendingEvents {
+            sender: self.sender.clone(),
+            events,
+        })
+    }
+}
+
+// A table which does not exist, or has a different type than the watcher, has no entries in the
+// watched range
+fn table_root(
+    definition: Result<Option<InternalTableDefinition>, TableError>,
+) -> Result<Option<BtreeHeader>> {
+    match definition {
+        Ok(definition) => Ok(definition.and_then(|x| x.private_get_root())),
+        Err(TableError::Storage(err)) => Err(err),
+        Err(_) => Ok(None),
+    }
+}
+
+// The watchers registered with a database
+#[derive(Default)]
+pub(crate) struct WatchRegistry {
+    watchers: Mutex<Vec<Arc<dyn Watcher>>>,
+}
+
+impl WatchRegistry {
+    pub(crate) fn watch<'a, K: Key + 'static, V: Value + 'static, KR>(
+        &self,
+        definition: TableDefinition<K, V>,
+        range: impl RangeBounds<KR> + 'a,
+    ) -> Watch<K, V>
+    where
+        KR: Borrow<K::SelfType<'a>> + 'a,
+    {
+        let to_bytes = |bound: Bound<&KR>| bound.map(|x| K::as_bytes(x.borrow()).as_ref().to_vec());
+        let (sender, receiver) = mpsc::channel();
+        let closed = Arc::new(AtomicBool::new(false));
+        let watcher = RangeWatcher::<K, V> {
+            name: definition.name().to_string(),
+            start: to_bytes(range.start_bound()),
+            end: to_bytes(range.end_bound()),
+            sender,
+            closed: closed.clone(),
+            _types: PhantomData,
+        };
+        self.watchers.lock().unwrap().push(Arc::new(watcher));
+        Watch {
+            receiver,
+            closed,
+            _types: PhantomData,
+        }
+    }
+
+    // Returns the active watchers, and discards those whose Watch has been dropped
+    pub(crate) fn watchers(&self) -> Vec<Arc<dyn Watcher>> {
+        let mut watchers = self.watchers.lock().unwrap();
+        watchers.retain(|watcher| !watcher.is_closed());
+        watchers.clone()
+    }
+}
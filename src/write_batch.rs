@@ -0,0 +1,161 @@
+use crate::{Key, TableDefinition, TableError, TableHandle, Value, WriteTransaction};
+use std::any::TypeId;
+use std::borrow::Borrow;
+use std::fmt::{Debug, Formatter};
+
+enum Operation {
+    Insert { key: Vec<u8>, value: Vec<u8> },
+    Remove { key: Vec<u8> },
+}
+
+// The operations on one table, in the order they were added to the batch. `apply` is
+// apply_table() for the key and value types of the table
+struct TableOperations {
+    name: String,
+    types: TypeId,
+    apply: fn(&WriteTransaction, &str, &[Operation]) -> Result<(), TableError>,
+    operations: Vec<Operation>,
+}
+
+fn apply_table<K: Key + 'static, V: Value + 'static>(
+    txn: &WriteTransaction,
+    name: &str,
+    operations: &[Operation],
+) -> Result<(), TableError> {
+    let inserts = operations
+        .iter()
+        .any(|operation| matches!(operation, Operation::Insert { .. }));
+    if !inserts && !txn.table_exists(name)? {
+        return Ok(());
+    }
+    let mut table = txn.open_table(TableDefinition::<K, V>::new(name))?;
+    for operation in operations {
+        match operation {
+            Operation::Insert { key, value } => {
+                table.insert(K::from_bytes(key), V::from_bytes(value))?;
+            }
+            Operation::Remove { key } => {
+                table.remove(K::from_bytes(key))?;
+            }
+        }
+    }
+    Ok(())
+}
+
+/// A list of writes, which can be prepared without a [`WriteTransaction`] and applied later
+/// with [`WriteTransaction::apply`]
+///
+/// Keys and values are serialized when they are added to the batch, so a batch can be built on
+/// any thread, without holding the write lock, and then sent to the thread which applies it.
+/// Operations are grouped by table, so that each table is opened once when the batch is applied.
+///
+/// # Examples
+///
+/// ```rust
+/// use redb::*;
+/// # use tempfile::NamedTempFile;
+/// const TABLE: TableDefinition<u64, &str> = TableDefinition::new("my_data");
+///
+/// # fn main() -> Result<(), Error> {
+/// # #[cfg(not(target_os = "wasi"))]
+/// # let tmpfile = NamedTempFile::new().unwrap();
+/// # #[cfg(target_os = "wasi")]
+/// # let tmpfile = NamedTempFile::new_in("/tmp").unwrap();
+/// # let filename = tmpfile.path();
+/// let db = Database::create(filename)?;
+/// let mut batch = WriteBatch::new();
+/// batch.insert(TABLE, 1, "hello").insert(TABLE, 2, "world").remove(TABLE, 1);
+///
+/// let write_txn = db.begin_write()?;
+/// write_txn.apply(batch)?;
+/// write_txn.commit()?;
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Default)]
+pub struct WriteBatch {
+    tables: Vec<TableOperations>,
+}
+
+impl WriteBatch {
+    /// Create an empty batch
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Record an insertion of `key` and `value` into the table
+    ///
+    /// The table will be created, if it does not exist, when the batch is applied
+    pub fn insert<'k, 'v, K: Key + 'static, V: Value + 'static>(
+        &mut self,
+        definition: TableDefinition<K, V>,
+        key: impl Borrow<K::SelfType<'k>>,
+        value: impl Borrow<V::SelfType<'v>>,
+    ) -> &mut Self {
+        let key = K::as_bytes(key.borrow()).as_ref().to_vec();
+        let value = V::as_bytes(value.borrow()).as_ref().to_vec();
+        self.push(definition, Operation::Insert { key, value });
+        self
+    }
+
+    /// Record a removal of `key` from the table
+    ///
+    /// Nothing is removed, and the table is not created, if it does not exist when the batch is
+    /// applied
+    pub fn remove<'k, K: Key + 'static, V: Value + 'static>(
+        &mut self,
+        definition: TableDefinition<K, V>,
+        key: impl Borrow<K::SelfType<'k>>,
+    ) -> &mut Self {
+        let key = K::as_bytes(key.borrow()).as_ref().to_vec();
+        self.push(definition, Operation::Remove { key });
+        self
+    }
+
+    fn push<K: Key + 'static, V: Value + 'static>(
+        &mut self,
+        definition: TableDefinition<K, V>,
+        operation: Operation,
+    ) {
+        let types = TypeId::of::<(K, V)>();
+        if let Some(table) = self
+            .tables
+            .iter_mut()
+            .find(|table| table.name == definition.name() && table.types == types)
+        {
+            table.operations.push(operation);
+        } else {
+            self.tables.push(TableOperations {
+                name: definition.name().to_string(),
+                types,
+                apply: apply_table::<K, V>,
+                operations: vec![operation],
+            });
+        }
+    }
+
+    /// Number of operations in the batch
+    pub fn len(&self) -> usize {
+        self.tables.iter().map(|table| table.operations.len()).sum()
+    }
+
+    /// Returns `true` if the batch contains no operations
+    pub fn is_empty(&self) -> bool {
+        self.tables.is_empty()
+    }
+
+    pub(crate) fn apply(self, txn: &WriteTransaction) -> Result<(), TableError> {
+        for table in self.tables {
+            (table.apply)(txn, &table.name, &table.operations)?;
+        }
+        Ok(())
+    }
+}
+
+impl Debug for WriteBatch {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("WriteBatch")
+            .field("len", &self.len())
+            .finish()
+    }
+}
@@ -3,13 +3,24 @@ use rand::random;
 use redb::CommitError;
 #[cfg(any(target_os = "linux", target_os = "macos", target_os = "windows"))]
 use redb::DatabaseError;
+#[cfg(feature = "lz4")]
+use redb::PageCompression;
 use redb::backends::InMemoryBackend;
 use redb::{
-    Database, Key, MultimapTableDefinition, MultimapTableHandle, Range, ReadOnlyDatabase,
-    ReadableDatabase, ReadableTable, ReadableTableMetadata, TableDefinition, TableError,
-    TableHandle, TypeName, Value,
+    ActiveTransaction, AppendError, AppendOnlyError, AppendOnlyTable, CachedTable, Cap,
+    CappedTable, Database, Durability, IdempotentTable, Key, MergeError, MergeOperator,
+    MultimapTableDefinition, MultimapTableHandle, QueueError, QueueTableDefinition, Quota,
+    QuotaError, QuotaTable, Range, ReadOnlyDatabase, ReadableDatabase, ReadableTable,
+    ReadableTableMetadata, SchemaChangeKind, SequenceError, SetTableDefinition, StorageError,
+    TableDefinition, TableError, TableHandle, TransactionKind, TryValue, TypeName, Value,
+    WriteBatch,
 };
+use std::borrow::Cow;
 use std::cmp::Ordering;
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
+use std::ffi::{CStr, CString};
+use std::num::{NonZeroI8, NonZeroI64, NonZeroU32, NonZeroU128};
+use std::ops::ControlFlow;
 #[cfg(not(target_os = "wasi"))]
 use std::sync;
 
@@ -45,6 +56,41 @@ fn len() {
     assert_eq!(untyped_table.len().unwrap(), 3);
 }
 
+#[test]
+fn untyped_iter() {
+    let tmpfile = create_tempfile();
+    let db = Database::create(tmpfile.path()).unwrap();
+    let definition: TableDefinition<u64, &str> = TableDefinition::new("x");
+    let empty: TableDefinition<&[u8], u32> = TableDefinition::new("empty");
+
+    let write_txn = db.begin_write().unwrap();
+    {
+        let mut table = write_txn.open_table(definition).unwrap();
+        for i in 0..10_000u64 {
+            table.insert(i, format!("value{i}").as_str()).unwrap();
+        }
+        write_txn.open_table(empty).unwrap();
+    }
+    write_txn.commit().unwrap();
+
+    let read_txn = db.begin_read().unwrap();
+    let untyped = read_txn.open_untyped_table(definition).unwrap();
+    assert_eq!(untyped.key_type(), &<u64 as Value>::type_name());
+    assert_eq!(untyped.value_type(), &<&str as Value>::type_name());
+    let mut expected = 0u64;
+    for entry in untyped.iter().unwrap() {
+        let (key, value) = entry.unwrap();
+        assert_eq!(key.value(), expected.to_le_bytes());
+        assert_eq!(value.value(), format!("value{expected}").as_bytes());
+        expected += 1;
+    }
+    assert_eq!(expected, 10_000);
+
+    let untyped = read_txn.open_untyped_table(empty).unwrap();
+    assert_eq!(untyped.key_type().name(), "&[u8]");
+    assert!(untyped.iter().unwrap().next().is_none());
+}
+
 #[test]
 fn read_only() {
     let tmpfile = create_tempfile();
@@ -86,6 +132,190 @@ fn read_only() {
     drop(db2);
 }
 
+#[cfg(any(target_os = "linux", target_os = "android"))]
+#[test]
+fn multi_process() {
+    let tmpfile = create_tempfile();
+    let mut builder = Database::builder();
+    builder.set_multi_process(true);
+    let db = builder.create(tmpfile.path()).unwrap();
+    let write_txn = db.begin_write().unwrap();
+    {
+        let mut table = write_txn.open_table(SLICE_TABLE).unwrap();
+        table
+            .insert(b"a".as_slice(), [0u8; 10_000].as_slice())
+            .unwrap();
+    }
+    write_txn.commit().unwrap();
+
+    // Only one process may write, and all of them must use multi-process mode
+    assert!(matches!(
+        builder.open(tmpfile.path()),
+        Err(DatabaseError::DatabaseAlreadyOpen)
+    ));
+    assert!(matches!(
+        Database::open(tmpfile.path()),
+        Err(DatabaseError::DatabaseAlreadyOpen)
+    ));
+
+    let reader = builder.open_read_only(tmpfile.path()).unwrap();
+    let read_txn = reader.begin_read().unwrap();
+    for i in 1..=20u8 {
+        let write_txn = db.begin_write().unwrap();
+        {
+            let mut table = write_txn.open_table(SLICE_TABLE).unwrap();
+            table
+                .insert(b"a".as_slice(), [i; 10_000].as_slice())
+                .unwrap();
+        }
+        write_txn.commit().unwrap();
+    }
+
+    // The pages of the old value must not have been reused
+    let table = read_txn.open_table(SLICE_TABLE).unwrap();
+    assert_eq!(
+        table.get(b"a".as_slice()).unwrap().unwrap().value(),
+        [0u8; 10_000]
+    );
+    let read_txn2 = reader.begin_read().unwrap();
+    let table2 = read_txn2.open_table(SLICE_TABLE).unwrap();
+    assert_eq!(
+        table2.get(b"a".as_slice()).unwrap().unwrap().value(),
+        [20u8; 10_000]
+    );
+    drop(table);
+    drop(read_txn);
+    drop(table2);
+    drop(read_txn2);
+
+    drop(db);
+    let read_txn = reader.begin_read().unwrap();
+    let table = read_txn.open_table(SLICE_TABLE).unwrap();
+    assert_eq!(
+        table.get(b"a".as_slice()).unwrap().unwrap().value(),
+        [20u8; 10_000]
+    );
+}
+
+#[cfg(any(target_os = "linux", target_os = "android"))]
+#[test]
+fn multi_process_idle_reader() {
+    let tmpfile = create_tempfile();
+    let mut builder = Database::builder();
+    builder.set_multi_process(true);
+    let db = builder.create(tmpfile.path()).unwrap();
+    let write_txn = db.begin_write().unwrap();
+    write_txn.open_table(SLICE_TABLE).unwrap();
+    write_txn.commit().unwrap();
+
+    // A reader which has finished its reads must not keep the writer from reusing pages
+    let reader = builder.open_read_only(tmpfile.path()).unwrap();
+    let read_txn = reader.begin_read().unwrap();
+    read_txn.open_table(SLICE_TABLE).unwrap();
+    drop(read_txn);
+    for i in 0..200u8 {
+        let write_txn = db.begin_write().unwrap();
+        {
+            let mut table = write_txn.open_table(SLICE_TABLE).unwrap();
+            table
+                .insert(b"a".as_slice(), [i; 10_000].as_slice())
+                .unwrap();
+        }
+        write_txn.commit().unwrap();
+    }
+    // Each commit writes about 10kB, so the file would grow past 2MB if no pages were reused
+    assert!(std::fs::metadata(tmpfile.path()).unwrap().len() < 1024 * 1024);
+
+    let read_txn = reader.begin_read().unwrap();
+    let table = read_txn.open_table(SLICE_TABLE).unwrap();
+    assert_eq!(
+        table.get(b"a".as_slice()).unwrap().unwrap().value(),
+        [199u8; 10_000]
+    );
+}
+
+#[cfg(feature = "lz4")]
+#[test]
+fn page_compression() {
+    let value: Vec<u8> = (0..100_000u32)
+        .flat_map(|i| format!("line {} of some text\n", i % 1000).into_bytes())
+        .take(100_000)
+        .collect();
+
+    let tmpfile = create_tempfile();
+    let uncompressed_tmpfile = create_tempfile();
+    for (file, compression) in [
+        (&tmpfile, PageCompression::Lz4),
+        (&uncompressed_tmpfile, PageCompression::None),
+    ] {
+        let db = Database::builder()
+            .set_page_compression(compression)
+            .create(file.path())
+            .unwrap();
+        let write_txn = db.begin_write().unwrap();
+        {
+            let mut table = write_txn.open_table(SLICE_TABLE).unwrap();
+            for i in 0..10u8 {
+                table.insert([i].as_slice(), value.as_slice()).unwrap();
+            }
+        }
+        write_txn.commit().unwrap();
+        drop(db);
+
+        // The god byte marks files which may contain compressed pages
+        let god_byte = std::fs::read(file.path()).unwrap()[9];
+        assert_eq!(god_byte & 16 != 0, compression == PageCompression::Lz4);
+    }
+
+    // Compressed pages are read regardless of the setting
+    let db = Database::open(tmpfile.path()).unwrap();
+    let read_txn = db.begin_read().unwrap();
+    let table = read_txn.open_table(SLICE_TABLE).unwrap();
+    for i in 0..10u8 {
+        let guard = table.get([i].as_slice()).unwrap().unwrap();
+        assert_eq!(guard.value(), value.as_slice());
+    }
+    drop(table);
+    drop(read_txn);
+    drop(db);
+
+    #[cfg(target_os = "linux")]
+    {
+        use std::os::unix::fs::MetadataExt;
+        let compressed = tmpfile.as_file().metadata().unwrap().blocks();
+        let uncompressed = uncompressed_tmpfile.as_file().metadata().unwrap().blocks();
+        assert!(compressed < uncompressed, "{compressed} >= {uncompressed}");
+    }
+}
+
+#[test]
+fn compressed_pages_require_support() {
+    use std::io::{Read, Seek, SeekFrom, Write};
+
+    let tmpfile = create_tempfile();
+    drop(Database::create(tmpfile.path()).unwrap());
+
+    // Set the flag in the god byte, which follows the 9 byte magic number, that marks the file as
+    // possibly containing compressed pages
+    let mut file = tmpfile.reopen().unwrap();
+    let mut god_byte = [0u8];
+    file.seek(SeekFrom::Start(9)).unwrap();
+    file.read_exact(&mut god_byte).unwrap();
+    file.seek(SeekFrom::Start(9)).unwrap();
+    file.write_all(&[god_byte[0] | 16]).unwrap();
+    file.sync_all().unwrap();
+    drop(file);
+
+    let result = Database::open(tmpfile.path());
+    #[cfg(feature = "lz4")]
+    result.unwrap();
+    #[cfg(not(feature = "lz4"))]
+    assert!(matches!(
+        result,
+        Err(redb::DatabaseError::PageCompressionUnsupported)
+    ));
+}
+
 #[test]
 fn table_stats() {
     let tmpfile = create_tempfile();
@@ -254,6 +484,78 @@ fn extract_if() {
 }
 
 #[cfg(not(target_os = "wasi"))]
+#[test]
+fn extract_from_while() {
+    let tmpfile = create_tempfile();
+    let db = Database::create(tmpfile.path()).unwrap();
+    let write_txn = db.begin_write().unwrap();
+    {
+        let mut table = write_txn.open_table(U64_TABLE).unwrap();
+        for i in 0..10 {
+            table.insert(&i, &i).unwrap();
+        }
+
+        // Remove up to 3 even keys, starting from 1
+        let mut removed = 0;
+        let extracted: Vec<u64> = table
+            .extract_from_while(1.., |k, _| {
+                if removed == 3 {
+                    return ControlFlow::Break(());
+                }
+                if k % 2 == 0 {
+                    removed += 1;
+                    ControlFlow::Continue(true)
+                } else {
+                    ControlFlow::Continue(false)
+                }
+            })
+            .unwrap()
+            .map(|x| x.unwrap().0.value())
+            .collect();
+        assert_eq!(extracted, vec![2, 4, 6]);
+        assert_eq!(table.len().unwrap(), 7);
+
+        // Examine at most 2 entries
+        let mut budget = 2;
+        let extracted = table
+            .extract_from_while(0.., |_, _| {
+                if budget == 0 {
+                    return ControlFlow::Break(());
+                }
+                budget -= 1;
+                ControlFlow::Continue(false)
+            })
+            .unwrap()
+            .count();
+        assert_eq!(extracted, 0);
+        assert_eq!(budget, 0);
+
+        let mut extracted = table
+            .extract_from_while(0.., |k, _| {
+                if k < 8 {
+                    ControlFlow::Continue(true)
+                } else {
+                    ControlFlow::Break(())
+                }
+            })
+            .unwrap();
+        assert!(extracted.next_back().is_none());
+        assert!(extracted.next().is_none());
+        drop(extracted);
+        assert_eq!(table.len().unwrap(), 7);
+    }
+    write_txn.commit().unwrap();
+
+    let read_txn = db.begin_read().unwrap();
+    let table = read_txn.open_table(U64_TABLE).unwrap();
+    let keys: Vec<u64> = table
+        .iter()
+        .unwrap()
+        .map(|x| x.unwrap().0.value())
+        .collect();
+    assert_eq!(keys, vec![0, 1, 3, 5, 7, 8, 9]);
+}
+
 #[test]
 fn extract_if_predicate_panic_poisons_transaction() {
     let tmpfile = create_tempfile();
@@ -637,6 +939,59 @@ fn list_tables() {
     assert_eq!(multimap_tables, &["mx", "my"]);
 }
 
+#[test]
+fn table_len_without_opening() {
+    let tmpfile = create_tempfile();
+    let db = Database::create(tmpfile.path()).unwrap();
+
+    let definition_x: TableDefinition<u64, u64> = TableDefinition::new("x");
+    let definition_y: TableDefinition<&str, &str> = TableDefinition::new("y");
+    let definition_mx: MultimapTableDefinition<u64, u64> = MultimapTableDefinition::new("mx");
+
+    let write_txn = db.begin_write().unwrap();
+    {
+        let mut table = write_txn.open_table(definition_x).unwrap();
+        for i in 0..10 {
+            table.insert(i, i).unwrap();
+        }
+        write_txn.open_table(definition_y).unwrap();
+        let mut table = write_txn.open_multimap_table(definition_mx).unwrap();
+        table.insert(0, 0).unwrap();
+        table.insert(0, 1).unwrap();
+    }
+    write_txn.commit().unwrap();
+
+    let read_txn = db.begin_read().unwrap();
+    let lens: Vec<(String, u64, bool)> = read_txn
+        .list_tables()
+        .unwrap()
+        .map(|h| {
+            (
+                h.name().to_string(),
+                read_txn.table_len(h.clone()).unwrap(),
+                read_txn.table_is_empty(h).unwrap(),
+            )
+        })
+        .collect();
+    assert_eq!(
+        lens,
+        vec![("x".to_string(), 10, false), ("y".to_string(), 0, true)]
+    );
+    assert_eq!(read_txn.multimap_table_len(definition_mx).unwrap(), 2);
+    assert!(!read_txn.multimap_table_is_empty(definition_mx).unwrap());
+
+    let missing: TableDefinition<u64, u64> = TableDefinition::new("missing");
+    assert!(matches!(
+        read_txn.table_len(missing),
+        Err(TableError::TableDoesNotExist(_))
+    ));
+    let wrong_type: TableDefinition<u64, u64> = TableDefinition::new("mx");
+    assert!(matches!(
+        read_txn.table_is_empty(wrong_type),
+        Err(TableError::TableIsMultimap(_))
+    ));
+}
+
 #[test]
 // Test that these signatures compile
 fn tuple_type_function_lifetime() {
@@ -1611,1047 +1966,3963 @@ fn rename_table() {
 }
 
 #[test]
-fn rename_open_table() {
+fn cached_table() {
     let tmpfile = create_tempfile();
     let db = Database::create(tmpfile.path()).unwrap();
+    let cache = CachedTable::new(U64_TABLE, 2);
+
     let write_txn = db.begin_write().unwrap();
     {
-        let table = write_txn.open_table(STR_TABLE).unwrap();
-        assert!(matches!(
-            write_txn.rename_table(STR_TABLE, STR_TABLE).unwrap_err(),
-            TableError::TableAlreadyOpen(_, _)
-        ));
-        drop(table);
+        let mut table = write_txn.open_table(U64_TABLE).unwrap();
+        for i in 0..3 {
+            cache.insert(&mut table, i, i).unwrap();
+        }
     }
     write_txn.commit().unwrap();
-}
 
-#[test]
-fn no_dirty_reads() {
-    let tmpfile = create_tempfile();
-    let db = Database::create(tmpfile.path()).unwrap();
+    let read_txn = db.begin_read().unwrap();
+    let table = read_txn.open_table(U64_TABLE).unwrap();
+    assert_eq!(cache.get(&table, 0).unwrap().unwrap().value(), 0);
+    assert!(cache.get(&table, 10).unwrap().is_none());
+    assert_eq!(cache.len(), 2);
+    // The capacity is never exceeded
+    assert_eq!(cache.get(&table, 1).unwrap().unwrap().value(), 1);
+    assert_eq!(cache.len(), 2);
+
+    // Aborted writes don't invalidate the cache
     let write_txn = db.begin_write().unwrap();
     {
-        let mut table = write_txn.open_table(STR_TABLE).unwrap();
-        table.insert("hello", "world").unwrap();
+        let mut table = write_txn.open_table(U64_TABLE).unwrap();
+        table.insert(1, 100).unwrap();
     }
-
-    let read_txn = db.begin_read().unwrap();
-    let table = read_txn.open_table(STR_TABLE);
-    assert!(table.is_err());
-    write_txn.commit().unwrap();
-
+    write_txn.abort().unwrap();
     let read_txn = db.begin_read().unwrap();
-    let table = read_txn.open_table(STR_TABLE).unwrap();
-    assert_eq!("world", table.get("hello").unwrap().unwrap().value());
-}
+    let table = read_txn.open_table(U64_TABLE).unwrap();
+    assert_eq!(cache.get(&table, 1).unwrap().unwrap().value(), 1);
+    assert_eq!(cache.len(), 2);
 
-#[test]
-fn read_isolation() {
-    let tmpfile = create_tempfile();
-    let db = Database::create(tmpfile.path()).unwrap();
+    // Committed writes do, even if they are not made through the cache
     let write_txn = db.begin_write().unwrap();
     {
-        let mut table = write_txn.open_table(STR_TABLE).unwrap();
-        table.insert("hello", "world").unwrap();
+        let mut table = write_txn.open_table(U64_TABLE).unwrap();
+        table.insert(1, 100).unwrap();
+        table.insert(10, 10).unwrap();
     }
     write_txn.commit().unwrap();
-
     let read_txn = db.begin_read().unwrap();
-    let table = read_txn.open_table(STR_TABLE).unwrap();
-    assert_eq!("world", table.get("hello").unwrap().unwrap().value());
+    let table = read_txn.open_table(U64_TABLE).unwrap();
+    assert_eq!(cache.get(&table, 1).unwrap().unwrap().value(), 100);
+    assert_eq!(cache.get(&table, 10).unwrap().unwrap().value(), 10);
+    assert_eq!(cache.len(), 2);
+
+    // Cached values outlive the transaction they were read in
+    let value = cache.get(&table, 1).unwrap().unwrap();
+    drop(table);
+    drop(read_txn);
+    assert_eq!(value.value(), 100);
 
     let write_txn = db.begin_write().unwrap();
     {
-        let mut write_table = write_txn.open_table(STR_TABLE).unwrap();
-        write_table.remove("hello").unwrap();
-        write_table.insert("hello2", "world2").unwrap();
-        write_table.insert("hello3", "world3").unwrap();
+        let mut table = write_txn.open_table(U64_TABLE).unwrap();
+        assert_eq!(cache.remove(&mut table, 1).unwrap().unwrap().value(), 100);
     }
     write_txn.commit().unwrap();
+    let read_txn = db.begin_read().unwrap();
+    let table = read_txn.open_table(U64_TABLE).unwrap();
+    assert!(cache.get(&table, 1).unwrap().is_none());
 
-    let read_txn2 = db.begin_read().unwrap();
-    let table2 = read_txn2.open_table(STR_TABLE).unwrap();
-    assert!(table2.get("hello").unwrap().is_none());
-    assert_eq!("world2", table2.get("hello2").unwrap().unwrap().value());
-    assert_eq!("world3", table2.get("hello3").unwrap().unwrap().value());
-    assert_eq!(table2.len().unwrap(), 2);
+    // Views of a write transaction see its latest writes, even though uncommitted pages are
+    // modified in place
+    let mut write_txn = db.begin_write().unwrap();
+    for i in 200..202 {
+        {
+            let mut table = write_txn.open_table(U64_TABLE).unwrap();
+            table.insert(0, i).unwrap();
+        }
+        let view = write_txn.read_view().unwrap();
+        let table = view.open_table(U64_TABLE).unwrap();
+        assert_eq!(cache.get(&table, 0).unwrap().unwrap().value(), i);
+    }
+    write_txn.abort().unwrap();
 
-    assert_eq!("world", table.get("hello").unwrap().unwrap().value());
-    assert!(table.get("hello2").unwrap().is_none());
-    assert!(table.get("hello3").unwrap().is_none());
-    assert_eq!(table.len().unwrap(), 1);
+    // Non-durable commits invalidate the cache too
+    for i in 300..302 {
+        let mut write_txn = db.begin_write().unwrap();
+        write_txn.set_durability(Durability::None).unwrap();
+        {
+            let mut table = write_txn.open_table(U64_TABLE).unwrap();
+            table.insert(0, i).unwrap();
+        }
+        write_txn.commit().unwrap();
+        let read_txn = db.begin_read().unwrap();
+        let table = read_txn.open_table(U64_TABLE).unwrap();
+        assert_eq!(cache.get(&table, 0).unwrap().unwrap().value(), i);
+    }
 }
 
 #[test]
-fn read_isolation2() {
+fn idempotent_table() {
     let tmpfile = create_tempfile();
     let db = Database::create(tmpfile.path()).unwrap();
+    let tokens: TableDefinition<&[u8], ()> = TableDefinition::new("tokens");
+
     let write_txn = db.begin_write().unwrap();
     {
-        let mut table = write_txn.open_table(STR_TABLE).unwrap();
-        table.insert("hello", "world").unwrap();
+        let mut table = IdempotentTable::open(&write_txn, U64_TABLE, tokens).unwrap();
+        assert!(!table.was_applied(b"a").unwrap());
+        assert!(table.insert(b"a", 1, 1).unwrap());
+        assert!(table.was_applied(b"a").unwrap());
+        // Retried within the same transaction
+        assert!(!table.insert(b"a", 1, 2).unwrap());
+        assert!(table.insert(b"b", 2, 2).unwrap());
     }
     write_txn.commit().unwrap();
 
+    // A retry in an aborted transaction is not recorded
     let write_txn = db.begin_write().unwrap();
-    let read_txn = db.begin_read().unwrap();
-    let table = read_txn.open_table(STR_TABLE).unwrap();
-    assert_eq!("world", table.get("hello").unwrap().unwrap().value());
     {
-        let mut write_table = write_txn.open_table(STR_TABLE).unwrap();
-        write_table.remove("hello").unwrap();
-        write_table.insert("hello2", "world2").unwrap();
-        write_table.insert("hello3", "world3").unwrap();
+        let mut table = IdempotentTable::open(&write_txn, U64_TABLE, tokens).unwrap();
+        assert!(table.remove(b"c", 2).unwrap());
+    }
+    write_txn.abort().unwrap();
+
+    let write_txn = db.begin_write().unwrap();
+    {
+        let mut table = IdempotentTable::open(&write_txn, U64_TABLE, tokens).unwrap();
+        assert!(!table.insert(b"a", 1, 3).unwrap());
+        assert!(!table.insert(b"b", 2, 3).unwrap());
+        assert!(!table.was_applied(b"c").unwrap());
+        assert!(table.remove(b"c", 2).unwrap());
+        assert!(!table.remove(b"c", 1).unwrap());
+        table.table().insert(3, 3).unwrap();
     }
     write_txn.commit().unwrap();
 
-    let read_txn2 = db.begin_read().unwrap();
-    let table2 = read_txn2.open_table(STR_TABLE).unwrap();
-    assert!(table2.get("hello").unwrap().is_none());
-    assert_eq!("world2", table2.get("hello2").unwrap().unwrap().value());
-    assert_eq!("world3", table2.get("hello3").unwrap().unwrap().value());
-    assert_eq!(table2.len().unwrap(), 2);
-
-    assert_eq!("world", table.get("hello").unwrap().unwrap().value());
-    assert!(table.get("hello2").unwrap().is_none());
-    assert!(table.get("hello3").unwrap().is_none());
-    assert_eq!(table.len().unwrap(), 1);
+    let read_txn = db.begin_read().unwrap();
+    let table = read_txn.open_table(U64_TABLE).unwrap();
+    assert_eq!(table.get(1).unwrap().unwrap().value(), 1);
+    assert!(table.get(2).unwrap().is_none());
+    assert_eq!(table.get(3).unwrap().unwrap().value(), 3);
+    let tokens = read_txn.open_table(tokens).unwrap();
+    assert_eq!(tokens.len().unwrap(), 3);
 }
 
 #[test]
-fn reopen_table() {
+fn append_only_table() {
     let tmpfile = create_tempfile();
     let db = Database::create(tmpfile.path()).unwrap();
+    let log: TableDefinition<u64, &[u8]> = TableDefinition::new("log");
+    let plain: TableDefinition<u64, &[u8]> = TableDefinition::new("plain");
+
     let write_txn = db.begin_write().unwrap();
     {
-        let mut table = write_txn.open_table(U64_TABLE).unwrap();
-        table.insert(&0, &0).unwrap();
-    }
-    {
-        let mut table = write_txn.open_table(U64_TABLE).unwrap();
-        table.insert(&1, &1).unwrap();
+        let mut table = AppendOnlyTable::open(&write_txn, log).unwrap();
+        let mut plain = write_txn.open_table(plain).unwrap();
+        for i in 10..1000 {
+            table.insert(i, [0; 100].as_slice()).unwrap();
+            plain.insert(i, [0; 100].as_slice()).unwrap();
+        }
+        assert!(matches!(
+            table.insert(999, [1; 100].as_slice()),
+            Err(AppendOnlyError::KeyExists)
+        ));
+        assert!(matches!(
+            table.insert(10, [1; 100].as_slice()),
+            Err(AppendOnlyError::KeyExists)
+        ));
+        // New keys before the last key are allowed
+        table.insert(0, [0; 100].as_slice()).unwrap();
+        assert_eq!(table.table().len().unwrap(), 991);
+
+        // Appended entries are densely packed
+        let leaf_pages = table.table().stats().unwrap().leaf_pages();
+        assert!(leaf_pages < plain.stats().unwrap().leaf_pages());
     }
     write_txn.commit().unwrap();
-}
 
-#[test]
-fn u64_type() {
-    let tmpfile = create_tempfile();
-    let db = Database::create(tmpfile.path()).unwrap();
     let write_txn = db.begin_write().unwrap();
     {
-        let mut table = write_txn.open_table(U64_TABLE).unwrap();
-        table.insert(&0, &1).unwrap();
-        table.insert(&1, &1).unwrap();
+        let mut table = AppendOnlyTable::open(&write_txn, log).unwrap();
+        assert!(matches!(
+            table.insert(999, [1; 100].as_slice()),
+            Err(AppendOnlyError::KeyExists)
+        ));
+        table.insert(1000, [0; 100].as_slice()).unwrap();
     }
     write_txn.commit().unwrap();
 
     let read_txn = db.begin_read().unwrap();
-    let table = read_txn.open_table(U64_TABLE).unwrap();
+    let table = read_txn.open_table(log).unwrap();
+    assert_eq!(table.len().unwrap(), 992);
     assert_eq!(
-        2u64,
-        table
-            .range(0..2)
-            .unwrap()
-            .map(|item| item.unwrap().1.value())
-            .sum::<u64>()
+        table.get(999).unwrap().unwrap().value(),
+        [0; 100].as_slice()
     );
-    assert_eq!(1, table.get(&0).unwrap().unwrap().value());
+    let keys: Vec<u64> = table
+        .iter()
+        .unwrap()
+        .map(|entry| entry.unwrap().0.value())
+        .collect();
+    let expected: Vec<u64> = [0].into_iter().chain(10..=1000).collect();
+    assert_eq!(keys, expected);
 }
 
 #[test]
-fn i128_type() {
+fn append_only_table_interleaved() {
     let tmpfile = create_tempfile();
     let db = Database::create(tmpfile.path()).unwrap();
-    let write_txn = db.begin_write().unwrap();
+    let log: TableDefinition<u64, u64> = TableDefinition::new("log");
 
-    let definition: TableDefinition<i128, i128> = TableDefinition::new("x");
-
-    {
-        let mut table = write_txn.open_table(definition).unwrap();
-        for i in -10..=10 {
-            table.insert(&i, &(i - 1)).unwrap();
+    let mut expected = BTreeMap::new();
+    for round in 0..3u64 {
+        let write_txn = db.begin_write().unwrap();
+        {
+            let mut table = AppendOnlyTable::open(&write_txn, log).unwrap();
+            for i in 0..2000u64 {
+                let key = round * 100_000 + i * 10 + 5;
+                table.insert(key, key * 2).unwrap();
+                expected.insert(key, key * 2);
+                // Inserting before the last key moves the tree away from the cached right edge
+                if i % 500 == 0 {
+                    let key = round * 100_000 + i * 10;
+                    table.insert(key, key * 2).unwrap();
+                    expected.insert(key, key * 2);
+                }
+            }
         }
+        write_txn.commit().unwrap();
     }
-    write_txn.commit().unwrap();
 
     let read_txn = db.begin_read().unwrap();
-    let table = read_txn.open_table(definition).unwrap();
-    assert_eq!(-2, table.get(&-1).unwrap().unwrap().value());
-    let mut iter: Range<i128, i128> = table.range::<i128>(..).unwrap();
-    for i in -11..10 {
-        assert_eq!(iter.next().unwrap().unwrap().1.value(), i);
-    }
-    assert!(iter.next().is_none());
+    let table = read_txn.open_table(log).unwrap();
+    assert_eq!(table.len().unwrap(), expected.len() as u64);
+    let entries: Vec<(u64, u64)> = table
+        .iter()
+        .unwrap()
+        .map(|entry| {
+            let (key, value) = entry.unwrap();
+            (key.value(), value.value())
+        })
+        .collect();
+    assert_eq!(entries, expected.into_iter().collect::<Vec<_>>());
 }
 
 #[test]
-fn f32_type() {
+fn capped_table() {
     let tmpfile = create_tempfile();
     let db = Database::create(tmpfile.path()).unwrap();
-
-    let definition: TableDefinition<u8, f32> = TableDefinition::new("x");
+    let entries: TableDefinition<u64, u64> = TableDefinition::new("entries");
+    let bytes: TableDefinition<u64, &str> = TableDefinition::new("bytes");
 
     let write_txn = db.begin_write().unwrap();
     {
-        let mut table = write_txn.open_table(definition).unwrap();
-        table.insert(&0, &0.3).unwrap();
+        let mut table = CappedTable::open(&write_txn, entries, Cap::Entries(3)).unwrap();
+        for i in 0..3 {
+            assert_eq!(table.insert(i, i).unwrap(), 0);
+        }
+        assert_eq!(table.insert(10, 10).unwrap(), 1);
+        // Overwriting an existing key does not grow the table
+        assert_eq!(table.insert(10, 11).unwrap(), 0);
+        // A key smaller than all others is evicted immediately
+        assert_eq!(table.insert(0, 0).unwrap(), 1);
+        assert_eq!(table.table().len().unwrap(), 3);
+        assert_eq!(table.table().first().unwrap().unwrap().0.value(), 1);
+
+        // Each entry is 8 bytes of key and 4 bytes of value
+        let mut table = CappedTable::open(&write_txn, bytes, Cap::Bytes(30)).unwrap();
+        assert_eq!(table.insert(0, "aaaa").unwrap(), 0);
+        assert_eq!(table.insert(1, "bbbb").unwrap(), 0);
+        assert_eq!(table.insert(2, "cccc").unwrap(), 1);
+        // Growing a value can evict other entries
+        assert_eq!(table.insert(2, "ccccccccccc").unwrap(), 1);
+        assert_eq!(table.table().len().unwrap(), 1);
+        assert_eq!(table.insert(3, "dddd").unwrap(), 1);
+        assert_eq!(table.table().len().unwrap(), 1);
     }
     write_txn.commit().unwrap();
 
-    let read_txn = db.begin_read().unwrap();
-    let table = read_txn.open_table(definition).unwrap();
-    assert_eq!(0.3, table.get(&0).unwrap().unwrap().value());
+    // Reopening with a smaller cap evicts the oldest entries
+    let write_txn = db.begin_write().unwrap();
+    {
+        let table = CappedTable::open(&write_txn, entries, Cap::Entries(1)).unwrap();
+        assert_eq!(table.table().len().unwrap(), 1);
+        assert_eq!(table.table().first().unwrap().unwrap().1.value(), 11);
+        let table = CappedTable::open(&write_txn, bytes, Cap::Bytes(8)).unwrap();
+        assert!(table.table().is_empty().unwrap());
+    }
+    write_txn.commit().unwrap();
 }
 
 #[test]
-fn str_type() {
+fn quota_table() {
     let tmpfile = create_tempfile();
     let db = Database::create(tmpfile.path()).unwrap();
-
-    let definition: TableDefinition<&str, &str> = TableDefinition::new("x");
+    let entries: TableDefinition<u64, u64> = TableDefinition::new("entries");
+    let bytes: TableDefinition<u64, &str> = TableDefinition::new("bytes");
 
     let write_txn = db.begin_write().unwrap();
     {
-        let mut table = write_txn.open_table(definition).unwrap();
-        table.insert("hello", "world").unwrap();
+        let mut table = QuotaTable::open(&write_txn, entries, Quota::new().max_entries(2)).unwrap();
+        table.insert(0, 0).unwrap();
+        table.insert(1, 1).unwrap();
+        assert!(matches!(table.insert(2, 2), Err(QuotaError::QuotaExceeded)));
+        // Existing keys can be overwritten
+        assert_eq!(table.insert(1, 10).unwrap().unwrap().value(), 1);
+        table.remove(0).unwrap();
+        table.insert(2, 2).unwrap();
+        assert_eq!(table.table().len().unwrap(), 2);
+
+        // Each entry is 8 bytes of key and 4 bytes of value
+        let mut table = QuotaTable::open(&write_txn, bytes, Quota::new().max_bytes(30)).unwrap();
+        table.insert(0, "aaaa").unwrap();
+        table.insert(1, "bbbb").unwrap();
+        assert!(matches!(
+            table.insert(2, "cccc"),
+            Err(QuotaError::QuotaExceeded)
+        ));
+        assert!(matches!(
+            table.insert(1, "bbbbbbbbbbb"),
+            Err(QuotaError::QuotaExceeded)
+        ));
+        table.insert(1, "bbbbbb").unwrap();
+        assert_eq!(table.table().get(1).unwrap().unwrap().value(), "bbbbbb");
+        assert!(table.table().get(2).unwrap().is_none());
     }
     write_txn.commit().unwrap();
 
-    let read_txn = db.begin_read().unwrap();
-    let table = read_txn.open_table(definition).unwrap();
-    assert_eq!("world", table.get("hello").unwrap().unwrap().value());
-
-    let mut iter = table.iter().unwrap();
-    assert_eq!(iter.next().unwrap().unwrap().1.value(), "world");
-    assert!(iter.next().is_none());
-
-    let mut iter: Range<&str, &str> = table.range("a".."z").unwrap();
-    assert_eq!(iter.next().unwrap().unwrap().1.value(), "world");
-    assert!(iter.next().is_none());
+    // The size of the table is measured when it is opened. A table over its quota can shrink
+    let write_txn = db.begin_write().unwrap();
+    {
+        let mut table = QuotaTable::open(&write_txn, bytes, Quota::new().max_bytes(10)).unwrap();
+        assert!(matches!(
+            table.insert(2, ""),
+            Err(QuotaError::QuotaExceeded)
+        ));
+        table.insert(1, "b").unwrap();
+        table.remove(0).unwrap();
+        table.remove(1).unwrap();
+        table.insert(2, "").unwrap();
+    }
+    write_txn.commit().unwrap();
 }
 
 #[test]
-fn string_type() {
+fn range_mut() {
     let tmpfile = create_tempfile();
     let db = Database::create(tmpfile.path()).unwrap();
+    let strings: TableDefinition<u64, &str> = TableDefinition::new("strings");
+    let counters: TableDefinition<u64, u64> = TableDefinition::new("counters");
 
-    let definition: TableDefinition<String, String> = TableDefinition::new("x");
+    let write_txn = db.begin_write().unwrap();
+    {
+        let mut table = write_txn.open_table(strings).unwrap();
+        let mut counter_table = write_txn.open_table(counters).unwrap();
+        for i in 0..10_000 {
+            table.insert(i, "medium").unwrap();
+            counter_table.insert(i, i).unwrap();
+        }
+    }
+    write_txn.commit().unwrap();
 
     let write_txn = db.begin_write().unwrap();
     {
-        let mut table = write_txn.open_table(definition).unwrap();
-        table
-            .insert("hello".to_string(), "world".to_string())
-            .unwrap();
+        let mut table = write_txn.open_table(strings).unwrap();
+        let mut range = table.range_mut(100..9_000).unwrap();
+        let mut visited = 0;
+        while let Some(entry) = range.next_entry() {
+            let mut entry = entry.unwrap();
+            let key = entry.key();
+            assert_eq!(key, 100 + visited);
+            match key % 3 {
+                0 => entry.insert("short").unwrap(),
+                1 => entry.insert("a much longer value than before").unwrap(),
+                _ => {}
+            }
+            visited += 1;
+        }
+        assert_eq!(visited, 8_900);
+        drop(range);
+
+        let mut range = table.range_mut(9_998..).unwrap();
+        range.next_entry().unwrap().unwrap().insert("end").unwrap();
+        range.next_entry().unwrap().unwrap().insert("end").unwrap();
+        assert!(range.next_entry().is_none());
+        drop(range);
+        assert!(table.range_mut(5..5).unwrap().next_entry().is_none());
+        assert_eq!(table.len().unwrap(), 10_000);
+
+        let mut table = write_txn.open_table(counters).unwrap();
+        let mut range = table.range_mut::<u64>(..).unwrap();
+        while let Some(entry) = range.next_entry() {
+            let mut entry = entry.unwrap();
+            let counter = entry.as_mut();
+            counter.set(counter.get() * 2);
+        }
     }
     write_txn.commit().unwrap();
+    drop(db);
+    assert!(redb::verify_file(tmpfile.path()).unwrap().is_valid());
+    let db = Database::open(tmpfile.path()).unwrap();
 
     let read_txn = db.begin_read().unwrap();
-    let table = read_txn.open_table(definition).unwrap();
-    assert_eq!(
-        "world",
-        table.get("hello".to_string()).unwrap().unwrap().value()
-    );
-
-    let mut iter = table.iter().unwrap();
-    assert_eq!(iter.next().unwrap().unwrap().1.value(), "world");
-    assert!(iter.next().is_none());
-
-    let mut iter: Range<String, String> = table.range("a".to_string().."z".to_string()).unwrap();
-    assert_eq!(iter.next().unwrap().unwrap().1.value(), "world");
-    assert!(iter.next().is_none());
+    let table = read_txn.open_table(strings).unwrap();
+    for i in 0..10_000 {
+        let expected = if i >= 9_998 {
+            "end"
+        } else if !(100..9_000).contains(&i) {
+            "medium"
+        } else {
+            match i % 3 {
+                0 => "short",
+                1 => "a much longer value than before",
+                _ => "medium",
+            }
+        };
+        assert_eq!(table.get(i).unwrap().unwrap().value(), expected);
+    }
+    let table = read_txn.open_table(counters).unwrap();
+    for i in 0..10_000 {
+        assert_eq!(table.get(i).unwrap().unwrap().value(), i * 2);
+    }
 }
 
 #[test]
-fn empty_type() {
+fn append() {
     let tmpfile = create_tempfile();
     let db = Database::create(tmpfile.path()).unwrap();
-
-    let definition: TableDefinition<u8, ()> = TableDefinition::new("x");
+    let write_txn = db.begin_write().unwrap();
+    {
+        let mut table = write_txn.open_table(U64_TABLE).unwrap();
+        for i in 0..10_000 {
+            table.append(i * 2, i).unwrap();
+        }
+        assert!(matches!(
+            table.append(19_998, 0),
+            Err(AppendError::KeyOutOfOrder)
+        ));
+        assert!(matches!(
+            table.append(5, 0),
+            Err(AppendError::KeyOutOfOrder)
+        ));
+        assert_eq!(table.len().unwrap(), 10_000);
+        assert_eq!(table.get(19_998).unwrap().unwrap().value(), 9_999);
+    }
+    write_txn.commit().unwrap();
 
     let write_txn = db.begin_write().unwrap();
     {
-        let mut table = write_txn.open_table(definition).unwrap();
-        table.insert(&0, &()).unwrap();
+        let mut table = write_txn.open_table(U64_TABLE).unwrap();
+        assert!(matches!(
+            table.append(0, 0),
+            Err(AppendError::KeyOutOfOrder)
+        ));
+        table.append(20_000, 10_000).unwrap();
+        // Other writes in between appends
+        table.insert(1, 1).unwrap();
+        table.remove(20_000).unwrap();
+        table.append(20_000, 10_000).unwrap();
+        table.append(20_001, 10_001).unwrap();
     }
     write_txn.commit().unwrap();
 
     let read_txn = db.begin_read().unwrap();
-    let table = read_txn.open_table(definition).unwrap();
-    assert!(!table.is_empty().unwrap());
+    let table = read_txn.open_table(U64_TABLE).unwrap();
+    assert_eq!(table.len().unwrap(), 10_003);
+    let mut expected: Vec<u64> = (0..=10_000).map(|i| i * 2).collect();
+    expected.insert(1, 1);
+    expected.push(20_001);
+    let keys: Vec<u64> = table
+        .iter()
+        .unwrap()
+        .map(|x| x.unwrap().0.value())
+        .collect();
+    assert_eq!(keys, expected);
+    drop(table);
+    drop(read_txn);
+    drop(db);
+    assert!(redb::verify_file(tmpfile.path()).unwrap().is_valid());
 }
 
 #[test]
-#[allow(clippy::bool_assert_comparison)]
-fn bool_type() {
+fn insert_sorted() {
     let tmpfile = create_tempfile();
     let db = Database::create(tmpfile.path()).unwrap();
-
-    let definition: TableDefinition<bool, bool> = TableDefinition::new("x");
+    let definition: TableDefinition<u64, &str> = TableDefinition::new("x");
 
     let write_txn = db.begin_write().unwrap();
     {
         let mut table = write_txn.open_table(definition).unwrap();
-        table.insert(true, &false).unwrap();
-        table.insert(&false, false).unwrap();
+        table.insert(100, "existing").unwrap();
+        table
+            .insert_sorted((0..50_000u64).map(|i| (i * 2, "sorted")))
+            .unwrap();
     }
     write_txn.commit().unwrap();
 
-    let read_txn = db.begin_read().unwrap();
-    let table = read_txn.open_table(definition).unwrap();
-    assert_eq!(false, table.get(&true).unwrap().unwrap().value());
-    assert_eq!(false, table.get(&false).unwrap().unwrap().value());
-
-    let mut iter = table.iter().unwrap();
-    assert_eq!(iter.next().unwrap().unwrap().0.value(), false);
-    assert_eq!(iter.next().unwrap().unwrap().0.value(), true);
-    assert!(iter.next().is_none());
-}
-
-#[test]
-fn option_type() {
-    let tmpfile = create_tempfile();
-    let db = Database::create(tmpfile.path()).unwrap();
-
-    let definition: TableDefinition<Option<u8>, Option<u32>> = TableDefinition::new("x");
-
     let write_txn = db.begin_write().unwrap();
     {
         let mut table = write_txn.open_table(definition).unwrap();
-        table.insert(None, None).unwrap();
-        table.insert(None, Some(0)).unwrap();
-        table.insert(Some(1), Some(1)).unwrap();
+        // Out of order keys, and keys which are already present, are inserted normally
+        table
+            .insert_sorted([(5, "unsorted"), (1_000_000, "appended"), (4, "replaced")])
+            .unwrap();
+        assert_eq!(table.len().unwrap(), 50_002);
     }
     write_txn.commit().unwrap();
 
     let read_txn = db.begin_read().unwrap();
     let table = read_txn.open_table(definition).unwrap();
-    assert_eq!(table.get(None).unwrap().unwrap().value(), Some(0));
-    assert_eq!(table.get(Some(1)).unwrap().unwrap().value(), Some(1));
-    let mut iter = table.iter().unwrap();
-    assert_eq!(iter.next().unwrap().unwrap().0.value(), None);
-    assert_eq!(iter.next().unwrap().unwrap().0.value(), Some(1));
+    let mut expected: BTreeMap<u64, &str> = (0..50_000u64).map(|i| (i * 2, "sorted")).collect();
+    expected.insert(5, "unsorted");
+    expected.insert(1_000_000, "appended");
+    expected.insert(4, "replaced");
+    let entries: Vec<(u64, String)> = table
+        .iter()
+        .unwrap()
+        .map(|entry| {
+            let (key, value) = entry.unwrap();
+            (key.value(), value.value().to_string())
+        })
+        .collect();
+    let expected: Vec<(u64, String)> = expected
+        .into_iter()
+        .map(|(key, value)| (key, value.to_string()))
+        .collect();
+    assert_eq!(entries, expected);
 }
 
 #[test]
-fn array_type() {
+fn insert_if_absent() {
     let tmpfile = create_tempfile();
     let db = Database::create(tmpfile.path()).unwrap();
-
-    let definition: TableDefinition<&[u8; 5], &[u8; 9]> = TableDefinition::new("x");
+    let definition: TableDefinition<u64, u64> = TableDefinition::new("x");
 
     let write_txn = db.begin_write().unwrap();
     {
         let mut table = write_txn.open_table(definition).unwrap();
-        table.insert(b"hello", b"world_123").unwrap();
+        for i in 0..10_000 {
+            assert!(table.insert_if_absent(i, i).unwrap().is_none());
+        }
     }
     write_txn.commit().unwrap();
 
-    let read_txn = db.begin_read().unwrap();
-    let table = read_txn.open_table(definition).unwrap();
-    let hello = b"hello";
-    assert_eq!(b"world_123", table.get(hello).unwrap().unwrap().value());
-
-    let mut iter: Range<&[u8; 5], &[u8; 9]> = table.range::<&[u8; 5]>(..).unwrap();
-    assert_eq!(iter.next().unwrap().unwrap().1.value(), b"world_123");
-    assert!(iter.next().is_none());
-}
-
-#[test]
-fn vec_fixed_width_value_type() {
-    let tmpfile = create_tempfile();
-    let db = Database::create(tmpfile.path()).unwrap();
-
-    let definition: TableDefinition<u8, Vec<u64>> = TableDefinition::new("x");
-
-    let value = vec![0, 1, 2, 3];
     let write_txn = db.begin_write().unwrap();
     {
         let mut table = write_txn.open_table(definition).unwrap();
-        table.insert(0, &value).unwrap();
+        for i in (0..20_000).step_by(7) {
+            let existing = table.insert_if_absent(i, 0).unwrap();
+            if i < 10_000 {
+                assert_eq!(existing.unwrap().value(), i);
+            } else {
+                assert!(existing.is_none());
+            }
+        }
+        assert_eq!(table.len().unwrap(), 10_000 + (10_000 / 7) + 1);
     }
     write_txn.commit().unwrap();
 
     let read_txn = db.begin_read().unwrap();
     let table = read_txn.open_table(definition).unwrap();
-    assert_eq!(value, table.get(0).unwrap().unwrap().value());
+    for i in (0..20_000).step_by(7) {
+        let expected = if i < 10_000 { i } else { 0 };
+        assert_eq!(table.get(i).unwrap().unwrap().value(), expected);
+    }
 }
 
 #[test]
-fn vec_var_width_value_type() {
+fn compare_and_swap() {
     let tmpfile = create_tempfile();
     let db = Database::create(tmpfile.path()).unwrap();
+    let definition: TableDefinition<&str, &str> = TableDefinition::new("x");
 
-    let definition: TableDefinition<u8, Vec<&str>> = TableDefinition::new("x");
-
-    let value = vec!["hello", "world"];
     let write_txn = db.begin_write().unwrap();
     {
         let mut table = write_txn.open_table(definition).unwrap();
-        table.insert(0, &value).unwrap();
+        // Only inserted if absent
+        assert!(table.compare_and_swap("a", None, "1").unwrap().is_ok());
+        let current = table.compare_and_swap("a", None, "2").unwrap().unwrap_err();
+        assert_eq!(current.unwrap().value(), "1");
+
+        let current = table
+            .compare_and_swap("a", Some(&"2"), "3")
+            .unwrap()
+            .unwrap_err();
+        assert_eq!(current.unwrap().value(), "1");
+        assert!(
+            table
+                .compare_and_swap("a", Some(&"1"), "3")
+                .unwrap()
+                .is_ok()
+        );
+        assert_eq!(table.get("a").unwrap().unwrap().value(), "3");
+
+        let current = table
+            .compare_and_swap("b", Some(&"3"), "4")
+            .unwrap()
+            .unwrap_err();
+        assert!(current.is_none());
+        drop(current);
+        assert!(table.get("b").unwrap().is_none());
     }
     write_txn.commit().unwrap();
-
-    let read_txn = db.begin_read().unwrap();
-    let table = read_txn.open_table(definition).unwrap();
-    assert_eq!(value, table.get(0).unwrap().unwrap().value());
 }
 
 #[test]
-fn vec_vec_type() {
+fn sequences() {
     let tmpfile = create_tempfile();
     let db = Database::create(tmpfile.path()).unwrap();
+    let write_txn = db.begin_write().unwrap();
+    let savepoint = write_txn.ephemeral_savepoint().unwrap();
+    assert_eq!(write_txn.next_sequence("a").unwrap(), 0);
+    assert_eq!(write_txn.next_sequence("a").unwrap(), 1);
+    assert_eq!(write_txn.next_sequence("b").unwrap(), 0);
+    assert_eq!(write_txn.reserve_sequence("a", 10).unwrap(), 2..12);
+    assert_eq!(write_txn.reserve_sequence("a", 0).unwrap(), 12..12);
+    write_txn.commit().unwrap();
 
-    let definition: TableDefinition<u8, Vec<Vec<&str>>> = TableDefinition::new("x");
-
-    let value = vec![vec!["hello", "world"], vec!["this", "is", "a", "test"]];
+    // Aborted ids are handed out again
     let write_txn = db.begin_write().unwrap();
-    {
-        let mut table = write_txn.open_table(definition).unwrap();
-        table.insert(0, &value).unwrap();
-    }
+    assert_eq!(write_txn.next_sequence("a").unwrap(), 12);
+    write_txn.abort().unwrap();
+
+    // Restoring a savepoint does not rewind sequences
+    let mut write_txn = db.begin_write().unwrap();
+    write_txn.restore_savepoint(&savepoint).unwrap();
+    assert_eq!(write_txn.next_sequence("a").unwrap(), 12);
     write_txn.commit().unwrap();
+    drop(savepoint);
+    drop(db);
 
-    let read_txn = db.begin_read().unwrap();
-    let table = read_txn.open_table(definition).unwrap();
-    assert_eq!(value, table.get(0).unwrap().unwrap().value());
+    let db = Database::open(tmpfile.path()).unwrap();
+    let write_txn = db.begin_write().unwrap();
+    assert_eq!(write_txn.next_sequence("a").unwrap(), 13);
+    assert_eq!(write_txn.next_sequence("b").unwrap(), 1);
+    // Sequences are not tables
+    assert!(write_txn.list_tables().unwrap().next().is_none());
+
+    // Reserving past u64::MAX fails, and leaves the sequence unchanged
+    assert_eq!(
+        write_txn.reserve_sequence("c", u64::MAX).unwrap(),
+        0..u64::MAX
+    );
+    assert!(matches!(
+        write_txn.next_sequence("c"),
+        Err(SequenceError::SequenceExhausted)
+    ));
+    assert!(matches!(
+        write_txn.reserve_sequence("a", u64::MAX),
+        Err(SequenceError::SequenceExhausted)
+    ));
+    assert_eq!(write_txn.next_sequence("a").unwrap(), 14);
+    write_txn.commit().unwrap();
 }
 
 #[test]
-fn vec_long_string_element() {
-    // Vec elements with serialized length >= 254 bytes use the multi-byte varint path
-    // in complex_types.rs to encode and decode the element length.
+fn schema_history() {
+    let table_def: TableDefinition<&str, &str> = TableDefinition::new("x");
+    let table_def2: TableDefinition<&str, &str> = TableDefinition::new("x2");
+    let multitable_def: MultimapTableDefinition<&str, &str> = MultimapTableDefinition::new("m");
+
+    // Nothing is recorded unless enabled
     let tmpfile = create_tempfile();
     let db = Database::create(tmpfile.path()).unwrap();
+    let write_txn = db.begin_write().unwrap();
+    write_txn.open_table(table_def).unwrap();
+    write_txn.commit().unwrap();
+    assert!(db.schema_history().unwrap().is_empty());
+    drop(db);
 
-    let definition: TableDefinition<u8, Vec<&str>> = TableDefinition::new("x");
-    let long_str = "a".repeat(254);
-    let value = vec![long_str.as_str(), "short"];
+    let tmpfile = create_tempfile();
+    let db = Database::builder()
+        .set_schema_history(true)
+        .create(tmpfile.path())
+        .unwrap();
+    assert!(db.schema_history().unwrap().is_empty());
 
     let write_txn = db.begin_write().unwrap();
-    {
-        let mut table = write_txn.open_table(definition).unwrap();
-        table.insert(0, &value).unwrap();
-    }
+    write_txn.open_table(table_def).unwrap();
+    write_txn.open_multimap_table(multitable_def).unwrap();
     write_txn.commit().unwrap();
 
-    let read_txn = db.begin_read().unwrap();
-    let table = read_txn.open_table(definition).unwrap();
-    assert_eq!(value, table.get(0).unwrap().unwrap().value());
-}
-
-#[test]
-fn range_lifetime() {
-    let tmpfile = create_tempfile();
-    let db = Database::create(tmpfile.path()).unwrap();
+    let write_txn = db.begin_write().unwrap();
+    // Opening an existing table, and deleting a missing one, are not changes
+    write_txn.open_table(table_def).unwrap();
+    assert!(!write_txn.delete_table(table_def2).unwrap());
+    write_txn.rename_table(table_def, table_def2).unwrap();
+    write_txn.commit().unwrap();
 
-    let definition: TableDefinition<&str, &str> = TableDefinition::new("x");
+    // Aborted changes are not recorded
+    let write_txn = db.begin_write().unwrap();
+    write_txn.delete_multimap_table(multitable_def).unwrap();
+    write_txn.abort().unwrap();
 
     let write_txn = db.begin_write().unwrap();
-    {
-        let mut table = write_txn.open_table(definition).unwrap();
-        table.insert("hello", "world").unwrap();
-    }
+    assert!(write_txn.delete_table(table_def2).unwrap());
     write_txn.commit().unwrap();
 
-    let read_txn = db.begin_read().unwrap();
-    let table = read_txn.open_table(definition).unwrap();
+    let history = db.schema_history().unwrap();
+    let summary: Vec<(&str, bool, SchemaChangeKind)> = history
+        .iter()
+        .map(|change| {
+            (
+                change.table_name(),
+                change.is_multimap(),
+                change.kind().clone(),
+            )
+        })
+        .collect();
+    assert_eq!(
+        summary,
+        vec![
+            ("x", false, SchemaChangeKind::Created),
+            ("m", true, SchemaChangeKind::Created),
+            (
+                "x",
+                false,
+                SchemaChangeKind::Renamed {
+                    new_name: "x2".to_string()
+                }
+            ),
+            ("x2", false, SchemaChangeKind::Deleted),
+        ]
+    );
+    assert_eq!(history[0].transaction_id(), history[1].transaction_id());
+    assert!(history[1].transaction_id() < history[2].transaction_id());
+    assert!(history[2].transaction_id() < history[3].transaction_id());
 
-    let mut iter = {
-        let start = "hello".to_string();
-        table.range::<&str>(start.as_str()..).unwrap()
-    };
-    assert_eq!(iter.next().unwrap().unwrap().1.value(), "world");
-    assert!(iter.next().is_none());
+    // History survives reopening the database
+    drop(db);
+    let db = Database::open(tmpfile.path()).unwrap();
+    assert_eq!(db.schema_history().unwrap(), history);
 }
 
 #[test]
-fn range_empty() {
+fn rename_open_table() {
     let tmpfile = create_tempfile();
     let db = Database::create(tmpfile.path()).unwrap();
-
-    let definition: TableDefinition<u128, u128> = TableDefinition::new("x");
-
     let write_txn = db.begin_write().unwrap();
     {
-        let mut table = write_txn.open_table(definition).unwrap();
-        for i in 0..1000 {
-            table.insert(i, i).unwrap();
-        }
-        #[expect(clippy::reversed_empty_ranges)]
-        let mut iter = table.range(500..0).unwrap();
-        assert!(iter.next().is_none());
+        let table = write_txn.open_table(STR_TABLE).unwrap();
+        assert!(matches!(
+            write_txn.rename_table(STR_TABLE, STR_TABLE).unwrap_err(),
+            TableError::TableAlreadyOpen(_, _)
+        ));
+        drop(table);
     }
     write_txn.commit().unwrap();
 }
 
 #[test]
-fn extract_from_if_empty() {
+fn no_dirty_reads() {
     let tmpfile = create_tempfile();
     let db = Database::create(tmpfile.path()).unwrap();
-
-    let definition: TableDefinition<u128, u128> = TableDefinition::new("x");
-
     let write_txn = db.begin_write().unwrap();
     {
-        let mut table = write_txn.open_table(definition).unwrap();
-        for i in 0..1000 {
-            table.insert(i, i).unwrap();
-        }
-        #[expect(clippy::reversed_empty_ranges)]
-        let mut iter = table.extract_from_if(500..0, |_, _| true).unwrap();
-        assert!(iter.next().is_none());
+        let mut table = write_txn.open_table(STR_TABLE).unwrap();
+        table.insert("hello", "world").unwrap();
     }
+
+    let read_txn = db.begin_read().unwrap();
+    let table = read_txn.open_table(STR_TABLE);
+    assert!(table.is_err());
     write_txn.commit().unwrap();
+
+    let read_txn = db.begin_read().unwrap();
+    let table = read_txn.open_table(STR_TABLE).unwrap();
+    assert_eq!("world", table.get("hello").unwrap().unwrap().value());
 }
 
 #[test]
-fn retain_in_empty() {
+fn read_isolation() {
     let tmpfile = create_tempfile();
     let db = Database::create(tmpfile.path()).unwrap();
+    let write_txn = db.begin_write().unwrap();
+    {
+        let mut table = write_txn.open_table(STR_TABLE).unwrap();
+        table.insert("hello", "world").unwrap();
+    }
+    write_txn.commit().unwrap();
 
-    let definition: TableDefinition<u128, u128> = TableDefinition::new("x");
+    let read_txn = db.begin_read().unwrap();
+    let table = read_txn.open_table(STR_TABLE).unwrap();
+    assert_eq!("world", table.get("hello").unwrap().unwrap().value());
 
     let write_txn = db.begin_write().unwrap();
     {
-        let mut table = write_txn.open_table(definition).unwrap();
-        for i in 0..1000 {
-            table.insert(i, i).unwrap();
-        }
-        #[expect(clippy::reversed_empty_ranges)]
-        table.retain_in(500..0, |_, _| false).unwrap();
-        assert_eq!(table.len().unwrap(), 1000);
-
-        let mut called = false;
-        table
-            .retain_in(2000..3000, |_, _| {
-                called = true;
-                false
-            })
-            .unwrap();
-        assert!(!called);
-        assert_eq!(table.len().unwrap(), 1000);
+        let mut write_table = write_txn.open_table(STR_TABLE).unwrap();
+        write_table.remove("hello").unwrap();
+        write_table.insert("hello2", "world2").unwrap();
+        write_table.insert("hello3", "world3").unwrap();
     }
     write_txn.commit().unwrap();
+
+    let read_txn2 = db.begin_read().unwrap();
+    let table2 = read_txn2.open_table(STR_TABLE).unwrap();
+    assert!(table2.get("hello").unwrap().is_none());
+    assert_eq!("world2", table2.get("hello2").unwrap().unwrap().value());
+    assert_eq!("world3", table2.get("hello3").unwrap().unwrap().value());
+    assert_eq!(table2.len().unwrap(), 2);
+
+    assert_eq!("world", table.get("hello").unwrap().unwrap().value());
+    assert!(table.get("hello2").unwrap().is_none());
+    assert!(table.get("hello3").unwrap().is_none());
+    assert_eq!(table.len().unwrap(), 1);
 }
 
 #[test]
-fn retain_collapses_to_single_entry() {
+fn read_isolation2() {
     let tmpfile = create_tempfile();
     let db = Database::create(tmpfile.path()).unwrap();
-
-    let definition: TableDefinition<u64, [u8; 200]> = TableDefinition::new("x");
-
     let write_txn = db.begin_write().unwrap();
     {
-        let mut table = write_txn.open_table(definition).unwrap();
-        for i in 0u64..10_000 {
-            table.insert(i, &[0u8; 200]).unwrap();
-        }
-
-        table.retain(|key, _| key == 7777).unwrap();
-        assert_eq!(table.len().unwrap(), 1);
-        assert!(table.get(&7776).unwrap().is_none());
-        assert!(table.get(&7777).unwrap().is_some());
-        assert!(table.get(&7778).unwrap().is_none());
-
-        let mut iter = table.iter().unwrap();
-        let (key, _) = iter.next().unwrap().unwrap();
-        assert_eq!(key.value(), 7777);
-        assert!(iter.next().is_none());
+        let mut table = write_txn.open_table(STR_TABLE).unwrap();
+        table.insert("hello", "world").unwrap();
     }
     write_txn.commit().unwrap();
 
     let write_txn = db.begin_write().unwrap();
+    let read_txn = db.begin_read().unwrap();
+    let table = read_txn.open_table(STR_TABLE).unwrap();
+    assert_eq!("world", table.get("hello").unwrap().unwrap().value());
     {
-        let mut table = write_txn.open_table(definition).unwrap();
-        assert_eq!(table.len().unwrap(), 1);
-        for i in 0u64..1000 {
-            table.insert(i, &[1u8; 200]).unwrap();
-        }
-        assert_eq!(table.len().unwrap(), 1001);
-        for i in 0u64..1000 {
-            assert!(table.remove(i).unwrap().is_some());
-        }
-        assert_eq!(table.len().unwrap(), 1);
+        let mut write_table = write_txn.open_table(STR_TABLE).unwrap();
+        write_table.remove("hello").unwrap();
+        write_table.insert("hello2", "world2").unwrap();
+        write_table.insert("hello3", "world3").unwrap();
     }
     write_txn.commit().unwrap();
+
+    let read_txn2 = db.begin_read().unwrap();
+    let table2 = read_txn2.open_table(STR_TABLE).unwrap();
+    assert!(table2.get("hello").unwrap().is_none());
+    assert_eq!("world2", table2.get("hello2").unwrap().unwrap().value());
+    assert_eq!("world3", table2.get("hello3").unwrap().unwrap().value());
+    assert_eq!(table2.len().unwrap(), 2);
+
+    assert_eq!("world", table.get("hello").unwrap().unwrap().value());
+    assert!(table.get("hello2").unwrap().is_none());
+    assert!(table.get("hello3").unwrap().is_none());
+    assert_eq!(table.len().unwrap(), 1);
 }
 
 #[test]
-fn retain_collapses_to_sparse_entries() {
+fn reopen_table() {
     let tmpfile = create_tempfile();
     let db = Database::create(tmpfile.path()).unwrap();
-
-    let definition: TableDefinition<u64, [u8; 200]> = TableDefinition::new("x");
-
     let write_txn = db.begin_write().unwrap();
     {
-        let mut table = write_txn.open_table(definition).unwrap();
-        for i in 0u64..20_000 {
-            table.insert(i, &[0u8; 200]).unwrap();
-        }
-
-        table.retain(|key, _| key == 1234 || key == 17_777).unwrap();
-        assert_eq!(table.len().unwrap(), 2);
-        assert!(table.get(&1233).unwrap().is_none());
-        assert!(table.get(&1234).unwrap().is_some());
-        assert!(table.get(&17_777).unwrap().is_some());
-        assert!(table.get(&17_778).unwrap().is_none());
-
-        let keys: Vec<_> = table
-            .iter()
-            .unwrap()
-            .map(|entry| entry.unwrap().0.value())
-            .collect();
-        assert_eq!(keys, [1234, 17_777]);
+        let mut table = write_txn.open_table(U64_TABLE).unwrap();
+        table.insert(&0, &0).unwrap();
+    }
+    {
+        let mut table = write_txn.open_table(U64_TABLE).unwrap();
+        table.insert(&1, &1).unwrap();
     }
     write_txn.commit().unwrap();
 }
 
 #[test]
-fn retain_rebuilds_changed_separators() {
+fn u64_type() {
     let tmpfile = create_tempfile();
     let db = Database::create(tmpfile.path()).unwrap();
-
-    let definition: TableDefinition<u64, [u8; 200]> = TableDefinition::new("x");
-
     let write_txn = db.begin_write().unwrap();
     {
-        let mut table = write_txn.open_table(definition).unwrap();
-        for i in 0u64..30_000 {
-            table.insert(i, &[0u8; 200]).unwrap();
-        }
+        let mut table = write_txn.open_table(U64_TABLE).unwrap();
+        table.insert(&0, &1).unwrap();
+        table.insert(&1, &1).unwrap();
+    }
+    write_txn.commit().unwrap();
 
+    let read_txn = db.begin_read().unwrap();
+    let table = read_txn.open_table(U64_TABLE).unwrap();
+    assert_eq!(
+        2u64,
         table
-            .retain(|key, _| key < 10_000 || key == 15_000 || key >= 20_000)
-            .unwrap();
-        assert_eq!(table.len().unwrap(), 20_001);
-        assert!(table.get(&9999).unwrap().is_some());
-        assert!(table.get(&10_000).unwrap().is_none());
-        assert!(table.get(&14_999).unwrap().is_none());
-        assert!(table.get(&15_000).unwrap().is_some());
-        assert!(table.get(&15_001).unwrap().is_none());
-        assert!(table.get(&20_000).unwrap().is_some());
-    }
-    write_txn.commit().unwrap();
+            .range(0..2)
+            .unwrap()
+            .map(|item| item.unwrap().1.value())
+            .sum::<u64>()
+    );
+    assert_eq!(1, table.get(&0).unwrap().unwrap().value());
 }
 
 #[test]
-fn retain_in_rebuilds_range_boundaries() {
+fn i128_type() {
     let tmpfile = create_tempfile();
     let db = Database::create(tmpfile.path()).unwrap();
+    let write_txn = db.begin_write().unwrap();
 
-    let definition: TableDefinition<u64, [u8; 200]> = TableDefinition::new("x");
+    let definition: TableDefinition<i128, i128> = TableDefinition::new("x");
 
-    let write_txn = db.begin_write().unwrap();
     {
         let mut table = write_txn.open_table(definition).unwrap();
-        for i in 0u64..30_000 {
-            table.insert(i, &[0u8; 200]).unwrap();
+        for i in -10..=10 {
+            table.insert(&i, &(i - 1)).unwrap();
         }
-        let height_before = table.stats().unwrap().tree_height();
-
-        table
-            .retain_in(10_000..20_000, |key, _| key == 15_000)
-            .unwrap();
-        assert_eq!(table.len().unwrap(), 20_001);
-        let stats = table.stats().unwrap();
-        assert!(stats.tree_height() <= height_before);
-        assert!(stats.leaf_pages() < 2_100);
-        assert!(table.get(&9999).unwrap().is_some());
-        assert!(table.get(&10_000).unwrap().is_none());
-        assert!(table.get(&14_999).unwrap().is_none());
-        assert!(table.get(&15_000).unwrap().is_some());
-        assert!(table.get(&15_001).unwrap().is_none());
-        assert!(table.get(&19_999).unwrap().is_none());
-        assert!(table.get(&20_000).unwrap().is_some());
     }
     write_txn.commit().unwrap();
+
+    let read_txn = db.begin_read().unwrap();
+    let table = read_txn.open_table(definition).unwrap();
+    assert_eq!(-2, table.get(&-1).unwrap().unwrap().value());
+    let mut iter: Range<i128, i128> = table.range::<i128>(..).unwrap();
+    for i in -11..10 {
+        assert_eq!(iter.next().unwrap().unwrap().1.value(), i);
+    }
+    assert!(iter.next().is_none());
 }
 
 #[test]
-fn retain_in_upper_boundary_does_not_duplicate_subtrees() {
+fn nonzero_type() {
     let tmpfile = create_tempfile();
     let db = Database::create(tmpfile.path()).unwrap();
+    let write_txn = db.begin_write().unwrap();
 
-    let definition: TableDefinition<u64, [u8; 200]> = TableDefinition::new("x");
+    let definition: TableDefinition<NonZeroI64, NonZeroU32> = TableDefinition::new("x");
 
-    let write_txn = db.begin_write().unwrap();
     {
         let mut table = write_txn.open_table(definition).unwrap();
-        for i in 0u64..30_000 {
-            table.insert(i, &[0u8; 200]).unwrap();
-        }
-
-        table.retain_in(0..1295, |_, _| false).unwrap();
-        assert_eq!(table.len().unwrap(), 30_000 - 1295);
-        assert!(table.get(&1294).unwrap().is_none());
-        assert!(table.get(&1295).unwrap().is_some());
-
-        let mut count = 0;
-        let mut previous = None;
-        for entry in table.iter().unwrap() {
-            let (key, _) = entry.unwrap();
-            let key = key.value();
-            assert!(key >= 1295);
-            if let Some(previous) = previous {
-                assert!(previous < key);
-            }
-            previous = Some(key);
-            count += 1;
+        for i in [-10i64, -1, 1, 10] {
+            let key = NonZeroI64::new(i).unwrap();
+            let value = NonZeroU32::new(i.unsigned_abs().try_into().unwrap()).unwrap();
+            table.insert(key, value).unwrap();
         }
-        assert_eq!(count, table.len().unwrap());
     }
     write_txn.commit().unwrap();
+
+    let read_txn = db.begin_read().unwrap();
+    let table = read_txn.open_table(definition).unwrap();
+    assert_eq!(
+        10,
+        table
+            .get(NonZeroI64::new(-10).unwrap())
+            .unwrap()
+            .unwrap()
+            .value()
+            .get()
+    );
+    let keys: Vec<i64> = table
+        .iter()
+        .unwrap()
+        .map(|item| item.unwrap().0.value().get())
+        .collect();
+    assert_eq!(keys, vec![-10, -1, 1, 10]);
+
+    let max = NonZeroU128::MAX;
+    assert_eq!(NonZeroU128::from_bytes(&NonZeroU128::as_bytes(&max)), max);
+    assert_eq!(
+        NonZeroI8::compare(
+            &NonZeroI8::as_bytes(&NonZeroI8::MIN),
+            &NonZeroI8::as_bytes(&NonZeroI8::MAX)
+        ),
+        Ordering::Less
+    );
 }
 
 #[test]
-fn retain_coalesces_sparse_survivors() {
+fn f32_type() {
     let tmpfile = create_tempfile();
     let db = Database::create(tmpfile.path()).unwrap();
 
-    let definition: TableDefinition<u64, [u8; 200]> = TableDefinition::new("x");
+    let definition: TableDefinition<u8, f32> = TableDefinition::new("x");
 
     let write_txn = db.begin_write().unwrap();
     {
         let mut table = write_txn.open_table(definition).unwrap();
-        for i in 0u64..20_000 {
-            table.insert(i, &[0u8; 200]).unwrap();
-        }
-
-        table.retain(|key, _| key % 100 == 0).unwrap();
-        assert_eq!(table.len().unwrap(), 200);
-        assert!(table.stats().unwrap().leaf_pages() < 50);
-        for i in 0u64..20_000 {
-            let value = table.get(&i).unwrap();
-            assert_eq!(value.is_some(), i % 100 == 0);
-        }
+        table.insert(&0, &0.3).unwrap();
     }
     write_txn.commit().unwrap();
+
+    let read_txn = db.begin_read().unwrap();
+    let table = read_txn.open_table(definition).unwrap();
+    assert_eq!(0.3, table.get(&0).unwrap().unwrap().value());
 }
 
 #[test]
-fn retain_variable_sized_sparse_survivors_terminates() {
+fn str_type() {
     let tmpfile = create_tempfile();
     let db = Database::create(tmpfile.path()).unwrap();
 
-    let definition: TableDefinition<u64, &[u8]> = TableDefinition::new("x");
-    let large = vec![0u8; 3_600];
-    let small = vec![1u8; 600];
+    let definition: TableDefinition<&str, &str> = TableDefinition::new("x");
 
     let write_txn = db.begin_write().unwrap();
     {
         let mut table = write_txn.open_table(definition).unwrap();
-        for i in 0u64..3_000 {
-            let value = if i % 3 == 0 { &large } else { &small };
-            table.insert(i, value.as_slice()).unwrap();
-        }
-
-        table.retain(|key, _| key % 3 != 1).unwrap();
-        assert_eq!(table.len().unwrap(), 2_000);
-        for i in 0u64..3_000 {
-            assert_eq!(table.get(i).unwrap().is_some(), i % 3 != 1);
-        }
+        table.insert("hello", "world").unwrap();
     }
     write_txn.commit().unwrap();
+
+    let read_txn = db.begin_read().unwrap();
+    let table = read_txn.open_table(definition).unwrap();
+    assert_eq!("world", table.get("hello").unwrap().unwrap().value());
+
+    let mut iter = table.iter().unwrap();
+    assert_eq!(iter.next().unwrap().unwrap().1.value(), "world");
+    assert!(iter.next().is_none());
+
+    let mut iter: Range<&str, &str> = table.range("a".."z").unwrap();
+    assert_eq!(iter.next().unwrap().unwrap().1.value(), "world");
+    assert!(iter.next().is_none());
 }
 
-#[cfg(not(target_os = "wasi"))]
 #[test]
-fn retain_predicate_panic_poisons_transaction() {
+fn string_type() {
     let tmpfile = create_tempfile();
     let db = Database::create(tmpfile.path()).unwrap();
 
-    let definition: TableDefinition<u64, u64> = TableDefinition::new("x");
+    let definition: TableDefinition<String, String> = TableDefinition::new("x");
 
     let write_txn = db.begin_write().unwrap();
     {
         let mut table = write_txn.open_table(definition).unwrap();
-        for i in 0u64..4 {
-            table.insert(i, i).unwrap();
-        }
+        table
+            .insert("hello".to_string(), "world".to_string())
+            .unwrap();
     }
     write_txn.commit().unwrap();
 
+    let read_txn = db.begin_read().unwrap();
+    let table = read_txn.open_table(definition).unwrap();
+    assert_eq!(
+        "world",
+        table.get("hello".to_string()).unwrap().unwrap().value()
+    );
+
+    let mut iter = table.iter().unwrap();
+    assert_eq!(iter.next().unwrap().unwrap().1.value(), "world");
+    assert!(iter.next().is_none());
+
+    let mut iter: Range<String, String> = table.range("a".to_string().."z".to_string()).unwrap();
+    assert_eq!(iter.next().unwrap().unwrap().1.value(), "world");
+    assert!(iter.next().is_none());
+}
+
+#[test]
+fn empty_type() {
+    let tmpfile = create_tempfile();
+    let db = Database::create(tmpfile.path()).unwrap();
+
+    let definition: TableDefinition<u8, ()> = TableDefinition::new("x");
+
     let write_txn = db.begin_write().unwrap();
     {
         let mut table = write_txn.open_table(definition).unwrap();
-        table.insert(4, 4).unwrap();
-
-        let result = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
-            table
-                .retain(|key, _| {
-                    assert_ne!(key, 2, "retain predicate panic");
-                    key != 0
-                })
-                .unwrap();
-        }));
-        assert!(result.is_err());
+        table.insert(&0, &()).unwrap();
     }
-
-    assert!(matches!(
-        write_txn.commit(),
-        Err(CommitError::TransactionPoisoned)
-    ));
+    write_txn.commit().unwrap();
 
     let read_txn = db.begin_read().unwrap();
     let table = read_txn.open_table(definition).unwrap();
-    assert_eq!(table.len().unwrap(), 4);
-    for i in 0u64..4 {
-        assert_eq!(table.get(i).unwrap().unwrap().value(), i);
-    }
-    assert!(table.get(4).unwrap().is_none());
+    assert!(!table.is_empty().unwrap());
 }
 
 #[test]
-fn range_arc() {
+#[allow(clippy::bool_assert_comparison)]
+fn bool_type() {
     let tmpfile = create_tempfile();
     let db = Database::create(tmpfile.path()).unwrap();
 
-    let definition: TableDefinition<&str, &str> = TableDefinition::new("x");
+    let definition: TableDefinition<bool, bool> = TableDefinition::new("x");
 
     let write_txn = db.begin_write().unwrap();
     {
         let mut table = write_txn.open_table(definition).unwrap();
-        table.insert("hello", "world").unwrap();
+        table.insert(true, &false).unwrap();
+        table.insert(&false, false).unwrap();
     }
     write_txn.commit().unwrap();
 
-    let mut iter = {
-        let read_txn = db.begin_read().unwrap();
-        let table = read_txn.open_table(definition).unwrap();
-        let start = "hello".to_string();
-        table.range::<&str>(start.as_str()..).unwrap()
-    };
-    assert_eq!(iter.next().unwrap().unwrap().1.value(), "world");
+    let read_txn = db.begin_read().unwrap();
+    let table = read_txn.open_table(definition).unwrap();
+    assert_eq!(false, table.get(&true).unwrap().unwrap().value());
+    assert_eq!(false, table.get(&false).unwrap().unwrap().value());
+
+    let mut iter = table.iter().unwrap();
+    assert_eq!(iter.next().unwrap().unwrap().0.value(), false);
+    assert_eq!(iter.next().unwrap().unwrap().0.value(), true);
     assert!(iter.next().is_none());
 }
 
 #[test]
-fn range_clone() {
+fn option_type() {
     let tmpfile = create_tempfile();
     let db = Database::create(tmpfile.path()).unwrap();
 
-    let definition: TableDefinition<&str, &str> = TableDefinition::new("x");
+    let definition: TableDefinition<Option<u8>, Option<u32>> = TableDefinition::new("x");
 
     let write_txn = db.begin_write().unwrap();
     {
         let mut table = write_txn.open_table(definition).unwrap();
-        table.insert("hello", "world").unwrap();
-        let mut iter1 = table.iter().unwrap();
-        let mut iter2 = iter1.clone();
-        let (k1, v1) = iter1.next().unwrap().unwrap();
-        let (k2, v2) = iter2.next().unwrap().unwrap();
-        assert_eq!(k1.value(), k2.value());
-        assert_eq!(v1.value(), v2.value());
+        table.insert(None, None).unwrap();
+        table.insert(None, Some(0)).unwrap();
+        table.insert(Some(1), Some(1)).unwrap();
     }
     write_txn.commit().unwrap();
+
+    let read_txn = db.begin_read().unwrap();
+    let table = read_txn.open_table(definition).unwrap();
+    assert_eq!(table.get(None).unwrap().unwrap().value(), Some(0));
+    assert_eq!(table.get(Some(1)).unwrap().unwrap().value(), Some(1));
+    let mut iter = table.iter().unwrap();
+    assert_eq!(iter.next().unwrap().unwrap().0.value(), None);
+    assert_eq!(iter.next().unwrap().unwrap().0.value(), Some(1));
 }
 
 #[test]
-fn custom_ordering() {
-    #[derive(Debug)]
-    struct ReverseKey(Vec<u8>);
+fn array_type() {
+    let tmpfile = create_tempfile();
+    let db = Database::create(tmpfile.path()).unwrap();
 
-    impl Value for ReverseKey {
-        type SelfType<'a>
-            = ReverseKey
-        where
-            Self: 'a;
-        type AsBytes<'a>
-            = &'a [u8]
-        where
+    let definition: TableDefinition<&[u8; 5], &[u8; 9]> = TableDefinition::new("x");
+
+    let write_txn = db.begin_write().unwrap();
+    {
+        let mut table = write_txn.open_table(definition).unwrap();
+        table.insert(b"hello", b"world_123").unwrap();
+    }
+    write_txn.commit().unwrap();
+
+    let read_txn = db.begin_read().unwrap();
+    let table = read_txn.open_table(definition).unwrap();
+    let hello = b"hello";
+    assert_eq!(b"world_123", table.get(hello).unwrap().unwrap().value());
+
+    let mut iter: Range<&[u8; 5], &[u8; 9]> = table.range::<&[u8; 5]>(..).unwrap();
+    assert_eq!(iter.next().unwrap().unwrap().1.value(), b"world_123");
+    assert!(iter.next().is_none());
+}
+
+#[test]
+fn vec_fixed_width_value_type() {
+    let tmpfile = create_tempfile();
+    let db = Database::create(tmpfile.path()).unwrap();
+
+    let definition: TableDefinition<u8, Vec<u64>> = TableDefinition::new("x");
+
+    let value = vec![0, 1, 2, 3];
+    let write_txn = db.begin_write().unwrap();
+    {
+        let mut table = write_txn.open_table(definition).unwrap();
+        table.insert(0, &value).unwrap();
+    }
+    write_txn.commit().unwrap();
+
+    let read_txn = db.begin_read().unwrap();
+    let table = read_txn.open_table(definition).unwrap();
+    assert_eq!(value, table.get(0).unwrap().unwrap().value());
+}
+
+#[test]
+fn vec_var_width_value_type() {
+    let tmpfile = create_tempfile();
+    let db = Database::create(tmpfile.path()).unwrap();
+
+    let definition: TableDefinition<u8, Vec<&str>> = TableDefinition::new("x");
+
+    let value = vec!["hello", "world"];
+    let write_txn = db.begin_write().unwrap();
+    {
+        let mut table = write_txn.open_table(definition).unwrap();
+        table.insert(0, &value).unwrap();
+    }
+    write_txn.commit().unwrap();
+
+    let read_txn = db.begin_read().unwrap();
+    let table = read_txn.open_table(definition).unwrap();
+    assert_eq!(value, table.get(0).unwrap().unwrap().value());
+}
+
+#[test]
+fn vec_vec_type() {
+    let tmpfile = create_tempfile();
+    let db = Database::create(tmpfile.path()).unwrap();
+
+    let definition: TableDefinition<u8, Vec<Vec<&str>>> = TableDefinition::new("x");
+
+    let value = vec![vec!["hello", "world"], vec!["this", "is", "a", "test"]];
+    let write_txn = db.begin_write().unwrap();
+    {
+        let mut table = write_txn.open_table(definition).unwrap();
+        table.insert(0, &value).unwrap();
+    }
+    write_txn.commit().unwrap();
+
+    let read_txn = db.begin_read().unwrap();
+    let table = read_txn.open_table(definition).unwrap();
+    assert_eq!(value, table.get(0).unwrap().unwrap().value());
+}
+
+#[test]
+fn vec_long_string_element() {
+    // Vec elements with serialized length >= 254 bytes use the multi-byte varint path
+    // in complex_types.rs to encode and decode the element length.
+    let tmpfile = create_tempfile();
+    let db = Database::create(tmpfile.path()).unwrap();
+
+    let definition: TableDefinition<u8, Vec<&str>> = TableDefinition::new("x");
+    let long_str = "a".repeat(254);
+    let value = vec![long_str.as_str(), "short"];
+
+    let write_txn = db.begin_write().unwrap();
+    {
+        let mut table = write_txn.open_table(definition).unwrap();
+        table.insert(0, &value).unwrap();
+    }
+    write_txn.commit().unwrap();
+
+    let read_txn = db.begin_read().unwrap();
+    let table = read_txn.open_table(definition).unwrap();
+    assert_eq!(value, table.get(0).unwrap().unwrap().value());
+}
+
+#[test]
+fn cow_type() {
+    let tmpfile = create_tempfile();
+    let db = Database::create(tmpfile.path()).unwrap();
+
+    let definition: TableDefinition<Cow<str>, Cow<[u8]>> = TableDefinition::new("x");
+
+    let write_txn = db.begin_write().unwrap();
+    {
+        let mut table = write_txn.open_table(definition).unwrap();
+        table
+            .insert(Cow::Borrowed("borrowed"), Cow::Borrowed(&[1u8, 2][..]))
+            .unwrap();
+        table
+            .insert(
+                Cow::<str>::Owned("owned".to_string()),
+                Cow::<[u8]>::Owned(vec![3]),
+            )
+            .unwrap();
+    }
+    write_txn.commit().unwrap();
+
+    let read_txn = db.begin_read().unwrap();
+    let table = read_txn.open_table(definition).unwrap();
+    let value = table.get(Cow::Borrowed("owned")).unwrap().unwrap();
+    assert!(matches!(value.value(), Cow::Borrowed(&[3])));
+    let keys: Vec<String> = table
+        .iter()
+        .unwrap()
+        .map(|item| item.unwrap().0.value().into_owned())
+        .collect();
+    assert_eq!(keys, vec!["borrowed", "owned"]);
+
+    // Tables are compatible with the borrowed types
+    let borrowed_definition: TableDefinition<&str, &[u8]> = TableDefinition::new("x");
+    let table = read_txn.open_table(borrowed_definition).unwrap();
+    assert_eq!(table.get("borrowed").unwrap().unwrap().value(), &[1, 2]);
+}
+
+#[test]
+fn cstring_type() {
+    let tmpfile = create_tempfile();
+    let db = Database::create(tmpfile.path()).unwrap();
+
+    let definition: TableDefinition<CString, CString> = TableDefinition::new("x");
+
+    let keys = [c"", c"a", c"ab", c"b"];
+    let write_txn = db.begin_write().unwrap();
+    {
+        let mut table = write_txn.open_table(definition).unwrap();
+        for key in keys.into_iter().rev() {
+            table.insert(key.to_owned(), c"value".to_owned()).unwrap();
+        }
+    }
+    write_txn.commit().unwrap();
+
+    let read_txn = db.begin_read().unwrap();
+    let table = read_txn.open_table(definition).unwrap();
+    let stored: Vec<CString> = table
+        .iter()
+        .unwrap()
+        .map(|item| item.unwrap().0.value())
+        .collect();
+    assert_eq!(stored, keys.map(CStr::to_owned));
+    assert_eq!(
+        table.get(c"a".to_owned()).unwrap().unwrap().value(),
+        c"value".to_owned()
+    );
+}
+
+#[test]
+fn map_value_type() {
+    let tmpfile = create_tempfile();
+    let db = Database::create(tmpfile.path()).unwrap();
+
+    let btree_definition: TableDefinition<u64, BTreeMap<String, u32>> =
+        TableDefinition::new("btree");
+    let hash_definition: TableDefinition<u64, HashMap<&str, Vec<u64>>> =
+        TableDefinition::new("hash");
+
+    let btree_value: BTreeMap<String, u32> = [("hello".to_string(), 1), ("a".repeat(300), 2)]
+        .into_iter()
+        .collect();
+    let hash_value: HashMap<&str, Vec<u64>> = [("empty", vec![]), ("full", vec![1, 2, 3])]
+        .into_iter()
+        .collect();
+    let write_txn = db.begin_write().unwrap();
+    {
+        let mut table = write_txn.open_table(btree_definition).unwrap();
+        table.insert(0, &btree_value).unwrap();
+        table.insert(1, BTreeMap::new()).unwrap();
+        let mut table = write_txn.open_table(hash_definition).unwrap();
+        table.insert(0, &hash_value).unwrap();
+    }
+    write_txn.commit().unwrap();
+
+    let read_txn = db.begin_read().unwrap();
+    let table = read_txn.open_table(btree_definition).unwrap();
+    assert_eq!(btree_value, table.get(0).unwrap().unwrap().value());
+    assert!(table.get(1).unwrap().unwrap().value().is_empty());
+    let table = read_txn.open_table(hash_definition).unwrap();
+    assert_eq!(hash_value, table.get(0).unwrap().unwrap().value());
+}
+
+#[test]
+fn set_value_type() {
+    let tmpfile = create_tempfile();
+    let db = Database::create(tmpfile.path()).unwrap();
+
+    let btree_definition: TableDefinition<u64, BTreeSet<&str>> = TableDefinition::new("btree");
+    let hash_definition: TableDefinition<u64, HashSet<u32>> = TableDefinition::new("hash");
+
+    let btree_value: BTreeSet<&str> = ["hello", "world"].into_iter().collect();
+    let hash_value: HashSet<u32> = [1, 2, 3].into_iter().collect();
+    let write_txn = db.begin_write().unwrap();
+    {
+        let mut table = write_txn.open_table(btree_definition).unwrap();
+        table.insert(0, &btree_value).unwrap();
+        let mut table = write_txn.open_table(hash_definition).unwrap();
+        table.insert(0, &hash_value).unwrap();
+    }
+    write_txn.commit().unwrap();
+
+    let read_txn = db.begin_read().unwrap();
+    let table = read_txn.open_table(btree_definition).unwrap();
+    assert_eq!(btree_value, table.get(0).unwrap().unwrap().value());
+    let table = read_txn.open_table(hash_definition).unwrap();
+    assert_eq!(hash_value, table.get(0).unwrap().unwrap().value());
+
+    // Equal hash sets and maps are stored the same way, whatever their iteration order
+    let set1: HashSet<u32> = (0..100).collect();
+    let set2: HashSet<u32> = (0..100).rev().collect();
+    assert_eq!(
+        <HashSet<u32>>::as_bytes(&set1),
+        <HashSet<u32>>::as_bytes(&set2)
+    );
+    let map1: HashMap<u32, &str> = (0..100).map(|i| (i, "x")).collect();
+    let map2: HashMap<u32, &str> = (0..100).rev().map(|i| (i, "x")).collect();
+    assert_eq!(
+        <HashMap<u32, &str>>::as_bytes(&map1),
+        <HashMap<u32, &str>>::as_bytes(&map2)
+    );
+
+    // The element type is part of the table type
+    let wrong_type: TableDefinition<u64, HashSet<u64>> = TableDefinition::new("hash");
+    assert!(matches!(
+        read_txn.open_table(wrong_type),
+        Err(TableError::TableTypeMismatch { .. })
+    ));
+}
+
+#[test]
+fn range_lifetime() {
+    let tmpfile = create_tempfile();
+    let db = Database::create(tmpfile.path()).unwrap();
+
+    let definition: TableDefinition<&str, &str> = TableDefinition::new("x");
+
+    let write_txn = db.begin_write().unwrap();
+    {
+        let mut table = write_txn.open_table(definition).unwrap();
+        table.insert("hello", "world").unwrap();
+    }
+    write_txn.commit().unwrap();
+
+    let read_txn = db.begin_read().unwrap();
+    let table = read_txn.open_table(definition).unwrap();
+
+    let mut iter = {
+        let start = "hello".to_string();
+        table.range::<&str>(start.as_str()..).unwrap()
+    };
+    assert_eq!(iter.next().unwrap().unwrap().1.value(), "world");
+    assert!(iter.next().is_none());
+}
+
+#[test]
+fn range_empty() {
+    let tmpfile = create_tempfile();
+    let db = Database::create(tmpfile.path()).unwrap();
+
+    let definition: TableDefinition<u128, u128> = TableDefinition::new("x");
+
+    let write_txn = db.begin_write().unwrap();
+    {
+        let mut table = write_txn.open_table(definition).unwrap();
+        for i in 0..1000 {
+            table.insert(i, i).unwrap();
+        }
+        #[expect(clippy::reversed_empty_ranges)]
+        let mut iter = table.range(500..0).unwrap();
+        assert!(iter.next().is_none());
+    }
+    write_txn.commit().unwrap();
+}
+
+#[test]
+fn extract_from_if_empty() {
+    let tmpfile = create_tempfile();
+    let db = Database::create(tmpfile.path()).unwrap();
+
+    let definition: TableDefinition<u128, u128> = TableDefinition::new("x");
+
+    let write_txn = db.begin_write().unwrap();
+    {
+        let mut table = write_txn.open_table(definition).unwrap();
+        for i in 0..1000 {
+            table.insert(i, i).unwrap();
+        }
+        #[expect(clippy::reversed_empty_ranges)]
+        let mut iter = table.extract_from_if(500..0, |_, _| true).unwrap();
+        assert!(iter.next().is_none());
+    }
+    write_txn.commit().unwrap();
+}
+
+#[test]
+fn retain_in_empty() {
+    let tmpfile = create_tempfile();
+    let db = Database::create(tmpfile.path()).unwrap();
+
+    let definition: TableDefinition<u128, u128> = TableDefinition::new("x");
+
+    let write_txn = db.begin_write().unwrap();
+    {
+        let mut table = write_txn.open_table(definition).unwrap();
+        for i in 0..1000 {
+            table.insert(i, i).unwrap();
+        }
+        #[expect(clippy::reversed_empty_ranges)]
+        table.retain_in(500..0, |_, _| false).unwrap();
+        assert_eq!(table.len().unwrap(), 1000);
+
+        let mut called = false;
+        table
+            .retain_in(2000..3000, |_, _| {
+                called = true;
+                false
+            })
+            .unwrap();
+        assert!(!called);
+        assert_eq!(table.len().unwrap(), 1000);
+    }
+    write_txn.commit().unwrap();
+}
+
+#[test]
+fn retain_collapses_to_single_entry() {
+    let tmpfile = create_tempfile();
+    let db = Database::create(tmpfile.path()).unwrap();
+
+    let definition: TableDefinition<u64, [u8; 200]> = TableDefinition::new("x");
+
+    let write_txn = db.begin_write().unwrap();
+    {
+        let mut table = write_txn.open_table(definition).unwrap();
+        for i in 0u64..10_000 {
+            table.insert(i, &[0u8; 200]).unwrap();
+        }
+
+        table.retain(|key, _| key == 7777).unwrap();
+        assert_eq!(table.len().unwrap(), 1);
+        assert!(table.get(&7776).unwrap().is_none());
+        assert!(table.get(&7777).unwrap().is_some());
+        assert!(table.get(&7778).unwrap().is_none());
+
+        let mut iter = table.iter().unwrap();
+        let (key, _) = iter.next().unwrap().unwrap();
+        assert_eq!(key.value(), 7777);
+        assert!(iter.next().is_none());
+    }
+    write_txn.commit().unwrap();
+
+    let write_txn = db.begin_write().unwrap();
+    {
+        let mut table = write_txn.open_table(definition).unwrap();
+        assert_eq!(table.len().unwrap(), 1);
+        for i in 0u64..1000 {
+            table.insert(i, &[1u8; 200]).unwrap();
+        }
+        assert_eq!(table.len().unwrap(), 1001);
+        for i in 0u64..1000 {
+            assert!(table.remove(i).unwrap().is_some());
+        }
+        assert_eq!(table.len().unwrap(), 1);
+    }
+    write_txn.commit().unwrap();
+}
+
+#[test]
+fn retain_collapses_to_sparse_entries() {
+    let tmpfile = create_tempfile();
+    let db = Database::create(tmpfile.path()).unwrap();
+
+    let definition: TableDefinition<u64, [u8; 200]> = TableDefinition::new("x");
+
+    let write_txn = db.begin_write().unwrap();
+    {
+        let mut table = write_txn.open_table(definition).unwrap();
+        for i in 0u64..20_000 {
+            table.insert(i, &[0u8; 200]).unwrap();
+        }
+
+        table.retain(|key, _| key == 1234 || key == 17_777).unwrap();
+        assert_eq!(table.len().unwrap(), 2);
+        assert!(table.get(&1233).unwrap().is_none());
+        assert!(table.get(&1234).unwrap().is_some());
+        assert!(table.get(&17_777).unwrap().is_some());
+        assert!(table.get(&17_778).unwrap().is_none());
+
+        let keys: Vec<_> = table
+            .iter()
+            .unwrap()
+            .map(|entry| entry.unwrap().0.value())
+            .collect();
+        assert_eq!(keys, [1234, 17_777]);
+    }
+    write_txn.commit().unwrap();
+}
+
+#[test]
+fn retain_rebuilds_changed_separators() {
+    let tmpfile = create_tempfile();
+    let db = Database::create(tmpfile.path()).unwrap();
+
+    let definition: TableDefinition<u64, [u8; 200]> = TableDefinition::new("x");
+
+    let write_txn = db.begin_write().unwrap();
+    {
+        let mut table = write_txn.open_table(definition).unwrap();
+        for i in 0u64..30_000 {
+            table.insert(i, &[0u8; 200]).unwrap();
+        }
+
+        table
+            .retain(|key, _| key < 10_000 || key == 15_000 || key >= 20_000)
+            .unwrap();
+        assert_eq!(table.len().unwrap(), 20_001);
+        assert!(table.get(&9999).unwrap().is_some());
+        assert!(table.get(&10_000).unwrap().is_none());
+        assert!(table.get(&14_999).unwrap().is_none());
+        assert!(table.get(&15_000).unwrap().is_some());
+        assert!(table.get(&15_001).unwrap().is_none());
+        assert!(table.get(&20_000).unwrap().is_some());
+    }
+    write_txn.commit().unwrap();
+}
+
+#[test]
+fn leaf_fill_histogram() {
+    let tmpfile = create_tempfile();
+    let db = Database::create(tmpfile.path()).unwrap();
+
+    let definition: TableDefinition<u64, [u8; 100]> = TableDefinition::new("x");
+    let multimap_definition: MultimapTableDefinition<u64, u64> = MultimapTableDefinition::new("y");
+
+    let write_txn = db.begin_write().unwrap();
+    {
+        let mut table = write_txn.open_table(definition).unwrap();
+        assert_eq!(table.stats().unwrap().leaf_fill_histogram(), [0; 10]);
+        for i in 0u64..10_000 {
+            table.insert(i, &[0u8; 100]).unwrap();
+        }
+        let stats = table.stats().unwrap();
+        let histogram = stats.leaf_fill_histogram();
+        assert_eq!(histogram.iter().sum::<u64>(), stats.leaf_pages());
+        let half_full_before: u64 = histogram[..5].iter().sum();
+
+        // Removing most of the entries in the first half of the table leaves those leaves sparse
+        for i in 0u64..5_000 {
+            if i % 4 != 0 {
+                table.remove(i).unwrap();
+            }
+        }
+        let stats = table.stats().unwrap();
+        let histogram = stats.leaf_fill_histogram();
+        assert_eq!(histogram.iter().sum::<u64>(), stats.leaf_pages());
+        assert!(histogram[..5].iter().sum::<u64>() > half_full_before);
+
+        let mut table = write_txn.open_multimap_table(multimap_definition).unwrap();
+        for i in 0u64..10_000 {
+            table.insert(i % 3, i).unwrap();
+        }
+        let stats = table.stats().unwrap();
+        assert_eq!(
+            stats.leaf_fill_histogram().iter().sum::<u64>(),
+            stats.leaf_pages()
+        );
+    }
+    write_txn.commit().unwrap();
+}
+
+#[test]
+fn largest_leaf_pages() {
+    let tmpfile = create_tempfile();
+    let db = Database::create(tmpfile.path()).unwrap();
+
+    let definition: TableDefinition<u64, &[u8]> = TableDefinition::new("x");
+    let multimap_definition: MultimapTableDefinition<u64, &[u8]> =
+        MultimapTableDefinition::new("y");
+    let large_value = vec![0u8; 20_000];
+
+    let write_txn = db.begin_write().unwrap();
+    {
+        let mut table = write_txn.open_table(definition).unwrap();
+        assert_eq!(table.stats().unwrap().largest_leaf_pages(), 0);
+        for i in 0u64..100 {
+            table.insert(i, [0u8; 10].as_slice()).unwrap();
+        }
+        assert_eq!(table.stats().unwrap().largest_leaf_pages(), 1);
+        // A 20KB value does not fit in a 4KB page, so its leaf spans 8 pages
+        table.insert(1_000, large_value.as_slice()).unwrap();
+        assert_eq!(table.stats().unwrap().largest_leaf_pages(), 8);
+        table.remove(1_000).unwrap();
+        assert_eq!(table.stats().unwrap().largest_leaf_pages(), 1);
+
+        let mut table = write_txn.open_multimap_table(multimap_definition).unwrap();
+        for i in 0u64..1_000 {
+            table.insert(0, i.to_le_bytes().as_slice()).unwrap();
+        }
+        table.insert(1, large_value.as_slice()).unwrap();
+        assert_eq!(table.stats().unwrap().largest_leaf_pages(), 8);
+    }
+    write_txn.commit().unwrap();
+}
+
+#[test]
+fn retain_in_rebuilds_range_boundaries() {
+    let tmpfile = create_tempfile();
+    let db = Database::create(tmpfile.path()).unwrap();
+
+    let definition: TableDefinition<u64, [u8; 200]> = TableDefinition::new("x");
+
+    let write_txn = db.begin_write().unwrap();
+    {
+        let mut table = write_txn.open_table(definition).unwrap();
+        for i in 0u64..30_000 {
+            table.insert(i, &[0u8; 200]).unwrap();
+        }
+        let height_before = table.stats().unwrap().tree_height();
+
+        table
+            .retain_in(10_000..20_000, |key, _| key == 15_000)
+            .unwrap();
+        assert_eq!(table.len().unwrap(), 20_001);
+        let stats = table.stats().unwrap();
+        assert!(stats.tree_height() <= height_before);
+        assert!(stats.leaf_pages() < 2_100);
+        assert!(table.get(&9999).unwrap().is_some());
+        assert!(table.get(&10_000).unwrap().is_none());
+        assert!(table.get(&14_999).unwrap().is_none());
+        assert!(table.get(&15_000).unwrap().is_some());
+        assert!(table.get(&15_001).unwrap().is_none());
+        assert!(table.get(&19_999).unwrap().is_none());
+        assert!(table.get(&20_000).unwrap().is_some());
+    }
+    write_txn.commit().unwrap();
+}
+
+#[test]
+fn retain_in_upper_boundary_does_not_duplicate_subtrees() {
+    let tmpfile = create_tempfile();
+    let db = Database::create(tmpfile.path()).unwrap();
+
+    let definition: TableDefinition<u64, [u8; 200]> = TableDefinition::new("x");
+
+    let write_txn = db.begin_write().unwrap();
+    {
+        let mut table = write_txn.open_table(definition).unwrap();
+        for i in 0u64..30_000 {
+            table.insert(i, &[0u8; 200]).unwrap();
+        }
+
+        table.retain_in(0..1295, |_, _| false).unwrap();
+        assert_eq!(table.len().unwrap(), 30_000 - 1295);
+        assert!(table.get(&1294).unwrap().is_none());
+        assert!(table.get(&1295).unwrap().is_some());
+
+        let mut count = 0;
+        let mut previous = None;
+        for entry in table.iter().unwrap() {
+            let (key, _) = entry.unwrap();
+            let key = key.value();
+            assert!(key >= 1295);
+            if let Some(previous) = previous {
+                assert!(previous < key);
+            }
+            previous = Some(key);
+            count += 1;
+        }
+        assert_eq!(count, table.len().unwrap());
+    }
+    write_txn.commit().unwrap();
+}
+
+#[test]
+fn retain_coalesces_sparse_survivors() {
+    let tmpfile = create_tempfile();
+    let db = Database::create(tmpfile.path()).unwrap();
+
+    let definition: TableDefinition<u64, [u8; 200]> = TableDefinition::new("x");
+
+    let write_txn = db.begin_write().unwrap();
+    {
+        let mut table = write_txn.open_table(definition).unwrap();
+        for i in 0u64..20_000 {
+            table.insert(i, &[0u8; 200]).unwrap();
+        }
+
+        table.retain(|key, _| key % 100 == 0).unwrap();
+        assert_eq!(table.len().unwrap(), 200);
+        assert!(table.stats().unwrap().leaf_pages() < 50);
+        for i in 0u64..20_000 {
+            let value = table.get(&i).unwrap();
+            assert_eq!(value.is_some(), i % 100 == 0);
+        }
+    }
+    write_txn.commit().unwrap();
+}
+
+#[test]
+fn retain_variable_sized_sparse_survivors_terminates() {
+    let tmpfile = create_tempfile();
+    let db = Database::create(tmpfile.path()).unwrap();
+
+    let definition: TableDefinition<u64, &[u8]> = TableDefinition::new("x");
+    let large = vec![0u8; 3_600];
+    let small = vec![1u8; 600];
+
+    let write_txn = db.begin_write().unwrap();
+    {
+        let mut table = write_txn.open_table(definition).unwrap();
+        for i in 0u64..3_000 {
+            let value = if i % 3 == 0 { &large } else { &small };
+            table.insert(i, value.as_slice()).unwrap();
+        }
+
+        table.retain(|key, _| key % 3 != 1).unwrap();
+        assert_eq!(table.len().unwrap(), 2_000);
+        for i in 0u64..3_000 {
+            assert_eq!(table.get(i).unwrap().is_some(), i % 3 != 1);
+        }
+    }
+    write_txn.commit().unwrap();
+}
+
+#[cfg(not(target_os = "wasi"))]
+#[test]
+fn retain_predicate_panic_poisons_transaction() {
+    let tmpfile = create_tempfile();
+    let db = Database::create(tmpfile.path()).unwrap();
+
+    let definition: TableDefinition<u64, u64> = TableDefinition::new("x");
+
+    let write_txn = db.begin_write().unwrap();
+    {
+        let mut table = write_txn.open_table(definition).unwrap();
+        for i in 0u64..4 {
+            table.insert(i, i).unwrap();
+        }
+    }
+    write_txn.commit().unwrap();
+
+    let write_txn = db.begin_write().unwrap();
+    {
+        let mut table = write_txn.open_table(definition).unwrap();
+        table.insert(4, 4).unwrap();
+
+        let result = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+            table
+                .retain(|key, _| {
+                    assert_ne!(key, 2, "retain predicate panic");
+                    key != 0
+                })
+                .unwrap();
+        }));
+        assert!(result.is_err());
+    }
+
+    assert!(matches!(
+        write_txn.commit(),
+        Err(CommitError::TransactionPoisoned)
+    ));
+
+    let read_txn = db.begin_read().unwrap();
+    let table = read_txn.open_table(definition).unwrap();
+    assert_eq!(table.len().unwrap(), 4);
+    for i in 0u64..4 {
+        assert_eq!(table.get(i).unwrap().unwrap().value(), i);
+    }
+    assert!(table.get(4).unwrap().is_none());
+}
+
+#[test]
+fn range_arc() {
+    let tmpfile = create_tempfile();
+    let db = Database::create(tmpfile.path()).unwrap();
+
+    let definition: TableDefinition<&str, &str> = TableDefinition::new("x");
+
+    let write_txn = db.begin_write().unwrap();
+    {
+        let mut table = write_txn.open_table(definition).unwrap();
+        table.insert("hello", "world").unwrap();
+    }
+    write_txn.commit().unwrap();
+
+    let mut iter = {
+        let read_txn = db.begin_read().unwrap();
+        let table = read_txn.open_table(definition).unwrap();
+        let start = "hello".to_string();
+        table.range::<&str>(start.as_str()..).unwrap()
+    };
+    assert_eq!(iter.next().unwrap().unwrap().1.value(), "world");
+    assert!(iter.next().is_none());
+}
+
+#[test]
+fn range_clone() {
+    let tmpfile = create_tempfile();
+    let db = Database::create(tmpfile.path()).unwrap();
+
+    let definition: TableDefinition<&str, &str> = TableDefinition::new("x");
+
+    let write_txn = db.begin_write().unwrap();
+    {
+        let mut table = write_txn.open_table(definition).unwrap();
+        table.insert("hello", "world").unwrap();
+        let mut iter1 = table.iter().unwrap();
+        let mut iter2 = iter1.clone();
+        let (k1, v1) = iter1.next().unwrap().unwrap();
+        let (k2, v2) = iter2.next().unwrap().unwrap();
+        assert_eq!(k1.value(), k2.value());
+        assert_eq!(v1.value(), v2.value());
+    }
+    write_txn.commit().unwrap();
+}
+
+#[test]
+fn custom_ordering() {
+    #[derive(Debug)]
+    struct ReverseKey(Vec<u8>);
+
+    impl Value for ReverseKey {
+        type SelfType<'a>
+            = ReverseKey
+        where
+            Self: 'a;
+        type AsBytes<'a>
+            = &'a [u8]
+        where
+            Self: 'a;
+
+        fn fixed_width() -> Option<usize> {
+            None
+        }
+
+        fn from_bytes<'a>(data: &'a [u8]) -> ReverseKey
+        where
+            Self: 'a,
+        {
+            ReverseKey(data.to_vec())
+        }
+
+        fn as_bytes<'a, 'b: 'a>(value: &'a Self::SelfType<'b>) -> &'a [u8]
+        where
+            Self: 'a,
+            Self: 'b,
+        {
+            &value.0
+        }
+
+        fn type_name() -> TypeName {
+            TypeName::new("test::ReverseKey")
+        }
+    }
+
+    impl Key for ReverseKey {
+        fn compare(data1: &[u8], data2: &[u8]) -> Ordering {
+            data2.cmp(data1)
+        }
+    }
+
+    let definition: TableDefinition<ReverseKey, &str> = TableDefinition::new("x");
+
+    let tmpfile = create_tempfile();
+    let db = Database::create(tmpfile.path()).unwrap();
+    let write_txn = db.begin_write().unwrap();
+    {
+        let mut table = write_txn.open_table(definition).unwrap();
+        for i in 0..10u8 {
+            let key = vec![i];
+            table.insert(&ReverseKey(key), "value").unwrap();
+        }
+    }
+    write_txn.commit().unwrap();
+
+    let read_txn = db.begin_read().unwrap();
+    let table = read_txn.open_table(definition).unwrap();
+    let start = ReverseKey(vec![7u8]); // ReverseKey is used, so 7 < 3
+    let end = ReverseKey(vec![3u8]);
+    let mut iter = table.range(start..=end).unwrap();
+    for i in (3..=7u8).rev() {
+        let (key, value) = iter.next().unwrap().unwrap();
+        assert_eq!(&[i], key.value().0.as_slice());
+        assert_eq!("value", value.value());
+    }
+    assert!(iter.next().is_none());
+}
+
+#[test]
+fn try_get_untrusted() {
+    // Stored as UTF-8, but written without validation
+    #[derive(Debug)]
+    struct RawName;
+
+    impl Value for RawName {
+        type SelfType<'a>
+            = &'a [u8]
+        where
+            Self: 'a;
+        type AsBytes<'a>
+            = &'a [u8]
+        where
+            Self: 'a;
+
+        fn fixed_width() -> Option<usize> {
+            None
+        }
+
+        fn from_bytes<'a>(data: &'a [u8]) -> &'a [u8]
+        where
+            Self: 'a,
+        {
+            data
+        }
+
+        fn as_bytes<'a, 'b: 'a>(value: &'a Self::SelfType<'b>) -> &'a [u8]
+        where
+            Self: 'b,
+        {
+            value
+        }
+
+        fn type_name() -> TypeName {
+            TypeName::new("test::Name")
+        }
+    }
+
+    #[derive(Debug)]
+    struct Name;
+
+    impl Value for Name {
+        type SelfType<'a>
+            = &'a str
+        where
+            Self: 'a;
+        type AsBytes<'a>
+            = &'a str
+        where
             Self: 'a;
 
-        fn fixed_width() -> Option<usize> {
-            None
+        fn fixed_width() -> Option<usize> {
+            None
+        }
+
+        fn from_bytes<'a>(data: &'a [u8]) -> &'a str
+        where
+            Self: 'a,
+        {
+            std::str::from_utf8(data).unwrap()
+        }
+
+        fn as_bytes<'a, 'b: 'a>(value: &'a Self::SelfType<'b>) -> &'a str
+        where
+            Self: 'b,
+        {
+            value
+        }
+
+        fn type_name() -> TypeName {
+            RawName::type_name()
+        }
+    }
+
+    impl TryValue for Name {
+        fn try_from_bytes<'a>(data: &'a [u8]) -> Result<&'a str, redb::DecodeError>
+        where
+            Self: 'a,
+        {
+            <&str>::try_from_bytes(data)
+        }
+    }
+
+    let raw_definition: TableDefinition<u64, RawName> = TableDefinition::new("x");
+    let definition: TableDefinition<u64, Name> = TableDefinition::new("x");
+
+    let tmpfile = create_tempfile();
+    let db = Database::create(tmpfile.path()).unwrap();
+    let write_txn = db.begin_write().unwrap();
+    {
+        let mut table = write_txn.open_table(raw_definition).unwrap();
+        table.insert(0, b"alice".as_slice()).unwrap();
+        table.insert(1, b"\xff\xfe".as_slice()).unwrap();
+        table.insert(2, b"bob".as_slice()).unwrap();
+    }
+    write_txn.commit().unwrap();
+
+    let read_txn = db.begin_read().unwrap();
+    let table = read_txn.open_table(definition).unwrap();
+    assert_eq!(table.try_get(0).unwrap().unwrap().value(), "alice");
+    assert!(table.try_get(3).unwrap().is_none());
+    let Err(StorageError::Decode(err)) = table.try_get(1) else {
+        panic!();
+    };
+    assert!(err.message().contains("utf-8"), "{err}");
+
+    let results: Vec<Option<String>> = table
+        .try_range(0..)
+        .unwrap()
+        .map(|entry| entry.ok().map(|(_, value)| value.value().to_string()))
+        .collect();
+    assert_eq!(
+        results,
+        vec![Some("alice".to_string()), None, Some("bob".to_string())]
+    );
+}
+
+#[test]
+fn content_hash() {
+    let numbers: TableDefinition<u64, &str> = TableDefinition::new("numbers");
+    let names: TableDefinition<&str, u64> = TableDefinition::new("names");
+
+    let tmpfile1 = create_tempfile();
+    let db1 = Database::create(tmpfile1.path()).unwrap();
+    let write_txn = db1.begin_write().unwrap();
+    {
+        let mut table = write_txn.open_table(numbers).unwrap();
+        for i in 0..10_000u64 {
+            table.insert(i, "value").unwrap();
+        }
+        let mut table = write_txn.open_table(names).unwrap();
+        table.insert("a", 1).unwrap();
+    }
+    write_txn.commit().unwrap();
+
+    // Same contents, but written in a different order and with a different layout
+    let tmpfile2 = create_tempfile();
+    let db2 = Database::create(tmpfile2.path()).unwrap();
+    let write_txn = db2.begin_write().unwrap();
+    {
+        let mut table = write_txn.open_table(names).unwrap();
+        table.insert("b", 2).unwrap();
+        table.insert("a", 1).unwrap();
+        table.remove("b").unwrap();
+        let mut table = write_txn.open_table(numbers).unwrap();
+        for i in (0..20_000u64).rev() {
+            table.insert(i, "value").unwrap();
+        }
+        table.retain(|key, _| key < 10_000).unwrap();
+    }
+    write_txn.commit().unwrap();
+
+    let read_txn = db1.begin_read().unwrap();
+    let hash1 = read_txn
+        .content_hash(read_txn.list_tables().unwrap())
+        .unwrap();
+    let read_txn = db2.begin_read().unwrap();
+    let hash2 = read_txn
+        .content_hash(read_txn.list_tables().unwrap())
+        .unwrap();
+    assert_eq!(hash1, hash2);
+    assert_ne!(hash1, read_txn.content_hash([names]).unwrap());
+    assert!(matches!(
+        read_txn.content_hash([TableDefinition::<(), ()>::new("missing")]),
+        Err(TableError::TableDoesNotExist(_))
+    ));
+    drop(read_txn);
+
+    let write_txn = db2.begin_write().unwrap();
+    {
+        let mut table = write_txn.open_table(numbers).unwrap();
+        table.insert(5000, "other").unwrap();
+    }
+    write_txn.commit().unwrap();
+    let read_txn = db2.begin_read().unwrap();
+    let hash3 = read_txn
+        .content_hash(read_txn.list_tables().unwrap())
+        .unwrap();
+    assert_ne!(hash1, hash3);
+}
+
+#[test]
+fn owned_get_signatures() {
+    let tmpfile = create_tempfile();
+    let db = Database::create(tmpfile.path()).unwrap();
+
+    let definition: TableDefinition<u32, u32> = TableDefinition::new("x");
+
+    let write_txn = db.begin_write().unwrap();
+    {
+        let mut table = write_txn.open_table(definition).unwrap();
+        for i in 0..10 {
+            table.insert(&i, &(i + 1)).unwrap();
+        }
+    }
+    write_txn.commit().unwrap();
+
+    let read_txn = db.begin_read().unwrap();
+    let table = read_txn.open_table(definition).unwrap();
+
+    assert_eq!(2, table.get(&1).unwrap().unwrap().value());
+
+    let mut iter: Range<u32, u32> = table.range::<u32>(..).unwrap();
+    for i in 0..10 {
+        assert_eq!(iter.next().unwrap().unwrap().1.value(), i + 1);
+    }
+    assert!(iter.next().is_none());
+    let mut iter: Range<u32, u32> = table.range(0..10).unwrap();
+    for i in 0..10 {
+        assert_eq!(iter.next().unwrap().unwrap().1.value(), i + 1);
+    }
+    assert!(iter.next().is_none());
+    let mut iter = table.range::<&u32>(&0..&10).unwrap();
+    for i in 0..10 {
+        assert_eq!(iter.next().unwrap().unwrap().1.value(), i + 1);
+    }
+    assert!(iter.next().is_none());
+}
+
+#[test]
+fn ref_get_signatures() {
+    let tmpfile = create_tempfile();
+    let db = Database::create(tmpfile.path()).unwrap();
+    let write_txn = db.begin_write().unwrap();
+    {
+        let mut table = write_txn.open_table(SLICE_TABLE).unwrap();
+        for i in 0..10u8 {
+            table.insert([i].as_slice(), [i + 1].as_slice()).unwrap();
+        }
+    }
+    write_txn.commit().unwrap();
+
+    let read_txn = db.begin_read().unwrap();
+    let table = read_txn.open_table(SLICE_TABLE).unwrap();
+
+    let zero = vec![0u8];
+    assert_eq!(&[1], table.get([0].as_slice()).unwrap().unwrap().value());
+    assert_eq!(&[1], table.get(b"\0".as_slice()).unwrap().unwrap().value());
+    assert_eq!(&[1], table.get(zero.as_slice()).unwrap().unwrap().value());
+
+    let start = vec![0u8];
+    let end = vec![10u8];
+    let mut iter = table.range::<&[u8]>(..).unwrap();
+    for i in 0..10 {
+        assert_eq!(iter.next().unwrap().unwrap().1.value(), &[i + 1]);
+    }
+    assert!(iter.next().is_none());
+
+    let mut iter = table.range(start.as_slice()..&end).unwrap();
+    for i in 0..10 {
+        assert_eq!(iter.next().unwrap().unwrap().1.value(), &[i + 1]);
+    }
+    assert!(iter.next().is_none());
+    drop(iter);
+
+    let mut iter = table.range(start.as_slice()..end.as_slice()).unwrap();
+    for i in 0..10 {
+        assert_eq!(iter.next().unwrap().unwrap().1.value(), &[i + 1]);
+    }
+    assert!(iter.next().is_none());
+
+    let mut iter = table.range([0u8].as_slice()..[10u8].as_slice()).unwrap();
+    for i in 0..10u8 {
+        assert_eq!(iter.next().unwrap().unwrap().1.value(), [i + 1].as_slice());
+    }
+    assert!(iter.next().is_none());
+}
+
+#[cfg(not(target_os = "wasi"))]
+#[test]
+fn concurrent_write_transactions_block() {
+    let tmpfile = create_tempfile();
+    let db = sync::Arc::new(Database::create(tmpfile.path()).unwrap());
+    let wtx = db.begin_write().unwrap();
+    let (sender, receiver) = sync::mpsc::channel();
+
+    let t = {
+        std::thread::spawn(move || {
+            sender.send(()).unwrap();
+            db.begin_write().unwrap().commit().unwrap();
+        })
+    };
+
+    receiver.recv().unwrap();
+    std::thread::sleep(std::time::Duration::from_secs(1));
+    wtx.commit().unwrap();
+    t.join().unwrap();
+}
+
+#[cfg(not(target_os = "wasi"))]
+#[test]
+fn begin_write_all_in_any_order() {
+    let tmpfile1 = create_tempfile();
+    let tmpfile2 = create_tempfile();
+    let db1 = sync::Arc::new(Database::create(tmpfile1.path()).unwrap());
+    let db2 = sync::Arc::new(Database::create(tmpfile2.path()).unwrap());
+
+    // Each thread requests the databases in the opposite order. Without a canonical acquisition
+    // order this would eventually deadlock
+    let threads: Vec<_> = [false, true]
+        .into_iter()
+        .map(|reverse| {
+            let db1 = db1.clone();
+            let db2 = db2.clone();
+            std::thread::spawn(move || {
+                for i in 0..100 {
+                    let databases = if reverse {
+                        [db2.as_ref(), db1.as_ref()]
+                    } else {
+                        [db1.as_ref(), db2.as_ref()]
+                    };
+                    let txns = Database::begin_write_all(&databases).unwrap();
+                    assert_eq!(txns.len(), 2);
+                    for txn in txns {
+                        {
+                            let mut table = txn.open_table(U64_TABLE).unwrap();
+                            table.insert(&(i * 2 + u64::from(reverse)), &i).unwrap();
+                        }
+                        txn.commit().unwrap();
+                    }
+                }
+            })
+        })
+        .collect();
+    for t in threads {
+        t.join().unwrap();
+    }
+
+    for db in [db1, db2] {
+        let read_txn = db.begin_read().unwrap();
+        let table = read_txn.open_table(U64_TABLE).unwrap();
+        assert_eq!(table.len().unwrap(), 200);
+    }
+}
+
+#[test]
+#[should_panic(expected = "The same database was given more than once")]
+fn begin_write_all_duplicate() {
+    let tmpfile = create_tempfile();
+    let db = Database::create(tmpfile.path()).unwrap();
+    let _ = Database::begin_write_all(&[&db, &db]);
+}
+
+#[cfg(not(target_os = "wasi"))]
+#[test]
+fn write_batch() {
+    let tmpfile = create_tempfile();
+    let db = Database::create(tmpfile.path()).unwrap();
+    let str_table: TableDefinition<&str, u64> = TableDefinition::new("str");
+
+    let (sender, receiver) = sync::mpsc::channel();
+    let producers: Vec<_> = (0..4u64)
+        .map(|thread| {
+            let sender = sender.clone();
+            std::thread::spawn(move || {
+                let mut batch = WriteBatch::new();
+                for i in 0..10 {
+                    batch.insert(U64_TABLE, thread * 10 + i, i);
+                }
+                batch.insert(str_table, format!("thread{thread}").as_str(), thread);
+                batch.remove(U64_TABLE, thread * 10);
+                assert_eq!(batch.len(), 12);
+                sender.send(batch).unwrap();
+            })
+        })
+        .collect();
+    drop(sender);
+    for t in producers {
+        t.join().unwrap();
+    }
+
+    let write_txn = db.begin_write().unwrap();
+    for batch in receiver {
+        write_txn.apply(batch).unwrap();
+    }
+    write_txn.commit().unwrap();
+
+    let read_txn = db.begin_read().unwrap();
+    let table = read_txn.open_table(U64_TABLE).unwrap();
+    assert_eq!(table.len().unwrap(), 36);
+    assert!(table.get(&10).unwrap().is_none());
+    assert_eq!(table.get(&15).unwrap().unwrap().value(), 5);
+    let table = read_txn.open_table(str_table).unwrap();
+    assert_eq!(table.get("thread3").unwrap().unwrap().value(), 3);
+
+    // Removing from a table which doesn't exist does nothing, and operations on the same table
+    // are applied in order, even when other tables are written in between
+    let missing: TableDefinition<u64, u64> = TableDefinition::new("missing");
+    let mut batch = WriteBatch::new();
+    batch
+        .remove(missing, 0)
+        .insert(U64_TABLE, 100, 1)
+        .insert(str_table, "x", 1)
+        .remove(U64_TABLE, 100)
+        .insert(U64_TABLE, 101, 1);
+    assert_eq!(batch.len(), 5);
+    let write_txn = db.begin_write().unwrap();
+    write_txn.apply(batch).unwrap();
+    assert!(
+        write_txn
+            .list_tables()
+            .unwrap()
+            .all(|t| t.name() != "missing")
+    );
+    write_txn.commit().unwrap();
+    let read_txn = db.begin_read().unwrap();
+    let table = read_txn.open_table(U64_TABLE).unwrap();
+    assert!(table.get(&100).unwrap().is_none());
+    assert_eq!(table.get(&101).unwrap().unwrap().value(), 1);
+
+    // Type mismatches are detected when the batch is applied
+    let wrong_type: TableDefinition<u64, &str> = TableDefinition::new("u64");
+    let mut batch = WriteBatch::new();
+    batch.insert(wrong_type, 0, "zero");
+    let write_txn = db.begin_write().unwrap();
+    assert!(matches!(
+        write_txn.apply(batch),
+        Err(TableError::TableTypeMismatch { .. })
+    ));
+}
+
+#[test]
+fn iter() {
+    let tmpfile = create_tempfile();
+    let db = Database::create(tmpfile.path()).unwrap();
+    let write_txn = db.begin_write().unwrap();
+    {
+        let mut table = write_txn.open_table(U64_TABLE).unwrap();
+        for i in 0..10 {
+            table.insert(&i, &i).unwrap();
+        }
+    }
+    write_txn.commit().unwrap();
+
+    let read_txn = db.begin_read().unwrap();
+    let table = read_txn.open_table(U64_TABLE).unwrap();
+    let mut iter = table.iter().unwrap();
+    for i in 0..10 {
+        let (k, v) = iter.next().unwrap().unwrap();
+        assert_eq!(i, k.value());
+        assert_eq!(i, v.value());
+    }
+}
+
+#[test]
+fn keys_and_values() {
+    let tmpfile = create_tempfile();
+    let db = Database::create(tmpfile.path()).unwrap();
+    let write_txn = db.begin_write().unwrap();
+    {
+        let mut table = write_txn.open_table(STR_TABLE).unwrap();
+        for i in 0..1000u32 {
+            table
+                .insert(format!("{i:04}").as_str(), "x".repeat(i as usize).as_str())
+                .unwrap();
+        }
+        // Uncommitted data
+        let keys: Vec<String> = table
+            .keys("0998"..)
+            .unwrap()
+            .map(|x| x.unwrap().value().to_string())
+            .collect();
+        assert_eq!(keys, vec!["0998", "0999"]);
+    }
+    write_txn.commit().unwrap();
+
+    let read_txn = db.begin_read().unwrap();
+    let table = read_txn.open_table(STR_TABLE).unwrap();
+    let keys: Vec<String> = table
+        .keys::<&str>(..)
+        .unwrap()
+        .map(|x| x.unwrap().value().to_string())
+        .collect();
+    let expected: Vec<String> = (0..1000).map(|i| format!("{i:04}")).collect();
+    assert_eq!(keys, expected);
+
+    let lengths: Vec<usize> = table
+        .values("0100".."0105")
+        .unwrap()
+        .map(|x| x.unwrap().value().len())
+        .collect();
+    assert_eq!(lengths, vec![100, 101, 102, 103, 104]);
+
+    let mut values = table.values::<&str>(..).unwrap();
+    assert_eq!(values.next_back().unwrap().unwrap().value().len(), 999);
+    assert_eq!(values.next().unwrap().unwrap().value().len(), 0);
+    let mut keys = table.keys("0500"..="0501").unwrap();
+    assert_eq!(keys.next_back().unwrap().unwrap().value(), "0501");
+    assert_eq!(keys.next().unwrap().unwrap().value(), "0500");
+    assert!(keys.next().is_none());
+}
+
+#[test]
+fn prefix() {
+    let tmpfile = create_tempfile();
+    let db = Database::create(tmpfile.path()).unwrap();
+    let write_txn = db.begin_write().unwrap();
+    {
+        let mut table = write_txn.open_table(SLICE_TABLE).unwrap();
+        for key in [
+            [0x01].as_slice(),
+            &[0x01, 0xFF],
+            &[0x01, 0xFF, 0xFF],
+            &[0x02],
+            &[0xFF],
+            &[0xFF, 0x00],
+            &[0xFF, 0xFF],
+        ] {
+            table.insert(key, b"".as_slice()).unwrap();
+        }
+        let mut table = write_txn.open_table(STR_TABLE).unwrap();
+        for key in [
+            "a",
+            "a\u{7F}",
+            "a\u{80}",
+            "a\u{D7FF}",
+            "a\u{E000}",
+            "b",
+            "\u{10FFFF}",
+        ] {
+            table.insert(key, "").unwrap();
+        }
+    }
+    write_txn.commit().unwrap();
+
+    let read_txn = db.begin_read().unwrap();
+    let table = read_txn.open_table(SLICE_TABLE).unwrap();
+    let keys = |prefix: &[u8]| -> Vec<Vec<u8>> {
+        table
+            .prefix(prefix)
+            .unwrap()
+            .map(|x| x.unwrap().0.value().to_vec())
+            .collect()
+    };
+    assert_eq!(
+        keys(&[0x01, 0xFF]),
+        vec![vec![0x01, 0xFF], vec![0x01, 0xFF, 0xFF]]
+    );
+    assert_eq!(keys(&[0x01]).len(), 3);
+    assert_eq!(keys(&[0xFF]).len(), 3);
+    assert_eq!(keys(&[0xFF, 0xFF]), vec![vec![0xFF, 0xFF]]);
+    assert_eq!(keys(&[]).len(), 7);
+    assert!(keys(&[0x03]).is_empty());
+    let mut iter = table.prefix([0x01].as_slice()).unwrap();
+    assert_eq!(
+        iter.next_back().unwrap().unwrap().0.value(),
+        &[0x01, 0xFF, 0xFF]
+    );
+
+    let table = read_txn.open_table(STR_TABLE).unwrap();
+    let keys = |prefix: &str| -> Vec<String> {
+        table
+            .prefix(prefix)
+            .unwrap()
+            .map(|x| x.unwrap().0.value().to_string())
+            .collect()
+    };
+    assert_eq!(keys("a").len(), 5);
+    assert_eq!(keys("a\u{7F}"), vec!["a\u{7F}"]);
+    assert_eq!(keys("a\u{D7FF}"), vec!["a\u{D7FF}"]);
+    assert_eq!(keys("\u{10FFFF}"), vec!["\u{10FFFF}"]);
+    assert_eq!(keys("").len(), 7);
+}
+
+#[test]
+fn range_len() {
+    let tmpfile = create_tempfile();
+    let db = Database::create(tmpfile.path()).unwrap();
+    let write_txn = db.begin_write().unwrap();
+    {
+        let mut table = write_txn.open_table(U64_TABLE).unwrap();
+        assert_eq!(table.range_len(0..10).unwrap(), 0);
+        for i in 0..10_000 {
+            table.insert(i * 2, i).unwrap();
+        }
+        assert_eq!(table.range_len(10..20).unwrap(), 5);
+    }
+    write_txn.commit().unwrap();
+
+    let read_txn = db.begin_read().unwrap();
+    let table = read_txn.open_table(U64_TABLE).unwrap();
+    assert!(table.stats().unwrap().tree_height() > 1);
+    assert_eq!(table.range_len::<u64>(..).unwrap(), 10_000);
+    assert_eq!(table.range_len(..=0).unwrap(), 1);
+    assert_eq!(table.range_len(19_998..).unwrap(), 1);
+    assert_eq!(table.range_len(19_999..).unwrap(), 0);
+    assert_eq!(table.range_len(5..5).unwrap(), 0);
+    #[allow(clippy::reversed_empty_ranges)]
+    let empty = 10..5;
+    assert_eq!(table.range_len(empty).unwrap(), 0);
+    for _ in 0..100 {
+        let start = random::<u64>() % 21_000;
+        let end = start + random::<u64>() % 5_000;
+        assert_eq!(
+            table.range_len(start..end).unwrap(),
+            table.range(start..end).unwrap().count() as u64
+        );
+        assert_eq!(
+            table.range_len(start..=end).unwrap(),
+            table.range(start..=end).unwrap().count() as u64
+        );
+        assert_eq!(
+            table.range_len(start..).unwrap(),
+            table.range(start..).unwrap().count() as u64
+        );
+        assert_eq!(
+            table.range_len(..end).unwrap(),
+            table.range(..end).unwrap().count() as u64
+        );
+    }
+}
+
+#[test]
+fn cursor() {
+    let tmpfile = create_tempfile();
+    let db = Database::create(tmpfile.path()).unwrap();
+    let write_txn = db.begin_write().unwrap();
+    {
+        let mut table = write_txn.open_table(U64_TABLE).unwrap();
+        let mut cursor = table.cursor().unwrap();
+        cursor.seek(0).unwrap();
+        assert!(cursor.current().is_none());
+        cursor.seek_to_last().unwrap();
+        assert!(cursor.current().is_none());
+        drop(cursor);
+
+        for i in 0..10_000 {
+            table.insert(i * 2, i).unwrap();
+        }
+        // Uncommitted data
+        let mut cursor = table.cursor().unwrap();
+        cursor.seek(3).unwrap();
+        assert_eq!(cursor.current().unwrap().1.value(), 2);
+    }
+    write_txn.commit().unwrap();
+
+    let read_txn = db.begin_read().unwrap();
+    let table = read_txn.open_table(U64_TABLE).unwrap();
+    assert!(table.stats().unwrap().tree_height() > 1);
+    let mut cursor = table.cursor().unwrap();
+    assert!(cursor.current().is_none());
+    cursor.next().unwrap();
+    assert!(cursor.current().is_none());
+
+    // Walk the whole table in both directions, crossing every leaf boundary
+    cursor.seek_to_first().unwrap();
+    for i in 0..10_000 {
+        assert_eq!(cursor.current().unwrap().0.value(), i * 2);
+        cursor.next().unwrap();
+    }
+    assert!(cursor.current().is_none());
+    cursor.seek_to_last().unwrap();
+    for i in (0..10_000).rev() {
+        assert_eq!(cursor.current().unwrap().0.value(), i * 2);
+        cursor.prev().unwrap();
+    }
+    assert!(cursor.current().is_none());
+
+    for _ in 0..1000 {
+        let key = random::<u64>() % 20_002;
+        cursor.seek(key).unwrap();
+        let expected = table
+            .range(key..)
+            .unwrap()
+            .next()
+            .map(|x| x.unwrap().0.value());
+        assert_eq!(cursor.current().map(|x| x.0.value()), expected);
+        if expected.is_some() {
+            cursor.prev().unwrap();
+            let expected = table
+                .range(..key)
+                .unwrap()
+                .next_back()
+                .map(|x| x.unwrap().0.value());
+            assert_eq!(cursor.current().map(|x| x.0.value()), expected);
+        }
+
+        cursor.seek_for_prev(key).unwrap();
+        let expected = table
+            .range(..=key)
+            .unwrap()
+            .next_back()
+            .map(|x| x.unwrap().0.value());
+        assert_eq!(cursor.current().map(|x| x.0.value()), expected);
+        if expected.is_some() {
+            cursor.next().unwrap();
+            let expected = table
+                .range(key + 1..)
+                .unwrap()
+                .next()
+                .map(|x| x.unwrap().0.value());
+            assert_eq!(cursor.current().map(|x| x.0.value()), expected);
+        }
+    }
+}
+
+#[test]
+fn range_size_hint() {
+    let tmpfile = create_tempfile();
+    let db = Database::create(tmpfile.path()).unwrap();
+    let write_txn = db.begin_write().unwrap();
+    {
+        let mut table = write_txn.open_table(U64_TABLE).unwrap();
+        assert_eq!(table.iter().unwrap().size_hint(), (0, Some(0)));
+        for i in 0..1000 {
+            table.insert(i, i).unwrap();
+        }
+        // Uncommitted data
+        assert_eq!(table.iter().unwrap().size_hint(), (1000, Some(1000)));
+    }
+    write_txn.commit().unwrap();
+
+    let read_txn = db.begin_read().unwrap();
+    let table = read_txn.open_table(U64_TABLE).unwrap();
+    let mut iter = table.iter().unwrap();
+    assert_eq!(iter.size_hint(), (1000, Some(1000)));
+    iter.next().unwrap().unwrap();
+    iter.next_back().unwrap().unwrap();
+    assert_eq!(iter.size_hint(), (998, Some(998)));
+    assert_eq!(iter.count(), 998);
+
+    let mut iter = table.iter().unwrap();
+    for _ in 0..1000 {
+        iter.next().unwrap().unwrap();
+    }
+    assert!(iter.next().is_none());
+    assert_eq!(iter.size_hint(), (0, Some(0)));
+
+    // Bounded ranges only have an upper bound
+    assert_eq!(table.range(10..20).unwrap().size_hint(), (0, Some(1000)));
+    #[allow(clippy::reversed_empty_ranges)]
+    let empty = 20..10;
+    assert_eq!(table.range(empty).unwrap().size_hint(), (0, Some(0)));
+    assert_eq!(
+        table.keys::<u64>(..).unwrap().size_hint(),
+        (1000, Some(1000))
+    );
+    assert_eq!(
+        table.values::<u64>(..).unwrap().size_hint(),
+        (1000, Some(1000))
+    );
+    let entries: Vec<_> = table.iter().unwrap().collect();
+    assert!(entries.capacity() >= 1000);
+}
+
+#[test]
+fn estimate_range_size() {
+    let tmpfile = create_tempfile();
+    let db = Database::create(tmpfile.path()).unwrap();
+    let write_txn = db.begin_write().unwrap();
+    {
+        let mut table = write_txn.open_table(U64_TABLE).unwrap();
+        let estimate = table.estimate_range_size::<u64>(..).unwrap();
+        assert_eq!((estimate.entries(), estimate.stored_bytes()), (0, 0));
+        for i in 0..20_000 {
+            table.insert(i, i).unwrap();
+        }
+    }
+    write_txn.commit().unwrap();
+
+    let read_txn = db.begin_read().unwrap();
+    let table = read_txn.open_table(U64_TABLE).unwrap();
+    assert!(table.stats().unwrap().tree_height() > 1);
+    let estimate = table.estimate_range_size::<u64>(..).unwrap();
+    assert_eq!(estimate.entries(), 20_000);
+    assert_eq!(estimate.stored_bytes(), 20_000 * 16);
+    #[allow(clippy::reversed_empty_ranges)]
+    let empty = 20..10;
+    assert_eq!(table.estimate_range_size(empty).unwrap().entries(), 0);
+
+    for (start, end) in [
+        (0, 10_000),
+        (5_000, 15_000),
+        (1_000, 3_000),
+        (19_000, 30_000),
+    ] {
+        let estimate = table.estimate_range_size(start..end).unwrap();
+        let actual = table.range_len(start..end).unwrap();
+        assert!(
+            estimate.entries().abs_diff(actual) <= actual / 4,
+            "{start}..{end}: estimated {} entries, but there are {actual}",
+            estimate.entries()
+        );
+        assert_eq!(estimate.stored_bytes(), estimate.entries() * 16);
+    }
+}
+
+struct Sum;
+
+impl MergeOperator<u64, u64> for Sum {
+    fn merge<'a>(&self, _: u64, existing: Option<u64>, operand: u64) -> u64
+    where
+        Self: 'a,
+    {
+        existing.unwrap_or(0) + operand
+    }
+}
+
+struct Concat;
+
+impl MergeOperator<&str, String> for Concat {
+    fn merge<'a>(&self, _: &'a str, existing: Option<String>, operand: String) -> String
+    where
+        Self: 'a,
+    {
+        existing.unwrap_or_default() + &operand
+    }
+}
+
+#[test]
+fn merge() {
+    let tmpfile = create_tempfile();
+    let db = Database::create(tmpfile.path()).unwrap();
+    let counters: TableDefinition<u64, u64> = TableDefinition::new("counters");
+    let logs: TableDefinition<&str, String> = TableDefinition::new("logs");
+
+    let write_txn = db.begin_write().unwrap();
+    {
+        let mut table = write_txn.open_table(counters).unwrap();
+        table.set_merge_operator(Sum);
+        for i in 0..10_000u64 {
+            table.merge(i % 1000, i).unwrap();
+        }
+        assert_eq!(table.len().unwrap(), 1000);
+        assert_eq!(
+            table.get(7).unwrap().unwrap().value(),
+            (0..10).map(|j| 7 + j * 1000).sum()
+        );
+
+        let mut table = write_txn.open_table(logs).unwrap();
+        table.set_merge_operator(Concat);
+        table.merge("a", "x".to_string()).unwrap();
+        table.merge("b", "y".to_string()).unwrap();
+        table.merge("a", "z".repeat(10_000)).unwrap();
+        assert_eq!(table.get("a").unwrap().unwrap().value().len(), 10_001);
+    }
+    write_txn.commit().unwrap();
+
+    let write_txn = db.begin_write().unwrap();
+    {
+        // The operator isn't persisted, so it must be set on every handle
+        let mut table = write_txn.open_table(counters).unwrap();
+        assert!(matches!(
+            table.merge(7, 1),
+            Err(MergeError::NoMergeOperator)
+        ));
+        assert_eq!(
+            table.get(7).unwrap().unwrap().value(),
+            (0..10).map(|j| 7 + j * 1000).sum()
+        );
+    }
+    {
+        let mut table = write_txn.open_table(logs).unwrap();
+        table.set_merge_operator(Concat);
+        table.merge("b", "w".to_string()).unwrap();
+        assert_eq!(table.get("b").unwrap().unwrap().value(), "yw");
+        assert_eq!(table.get("a").unwrap().unwrap().value().len(), 10_001);
+    }
+    write_txn.commit().unwrap();
+
+    let read_txn = db.begin_read().unwrap();
+    let table = read_txn.open_table(counters).unwrap();
+    assert_eq!(
+        table.get(999).unwrap().unwrap().value(),
+        (0..10).map(|j| 999 + j * 1000).sum()
+    );
+    drop(table);
+    drop(read_txn);
+    drop(db);
+    assert!(redb::verify_file(tmpfile.path()).unwrap().is_valid());
+}
+
+#[test]
+fn merge_without_operator() {
+    let tmpfile = create_tempfile();
+    let db = Database::create(tmpfile.path()).unwrap();
+    let definition: TableDefinition<u64, u64> = TableDefinition::new("x");
+    let write_txn = db.begin_write().unwrap();
+    let mut table = write_txn.open_table(definition).unwrap();
+    assert!(matches!(
+        table.merge(0, 1),
+        Err(MergeError::NoMergeOperator)
+    ));
+    assert!(table.is_empty().unwrap());
+}
+
+#[test]
+fn insert_stream() {
+    let tmpfile = create_tempfile();
+    let db = Database::create(tmpfile.path()).unwrap();
+    let definition: TableDefinition<u64, &[u8]> = TableDefinition::new("blobs");
+    let blob: Vec<u8> = (0..5_000_000u32).map(|i| (i % 251) as u8).collect();
+
+    let write_txn = db.begin_write().unwrap();
+    {
+        let mut table = write_txn.open_table(definition).unwrap();
+        table.insert(1, [1u8].as_slice()).unwrap();
+        table
+            .insert_stream(0, blob.len(), std::io::Cursor::new(&blob))
+            .unwrap();
+        // A reader which ends early leaves the previous value, or no value
+        let err = table.insert_stream(1, 10, [0u8; 5].as_slice()).unwrap_err();
+        assert!(matches!(err, StorageError::Io(_)));
+        assert_eq!(table.get(1).unwrap().unwrap().value(), [1u8].as_slice());
+        let err = table.insert_stream(2, 10, [0u8; 5].as_slice()).unwrap_err();
+        assert!(matches!(err, StorageError::Io(_)));
+        assert!(table.get(2).unwrap().is_none());
+    }
+    write_txn.commit().unwrap();
+
+    let read_txn = db.begin_read().unwrap();
+    let table = read_txn.open_table(definition).unwrap();
+    let value = table.get(0).unwrap().unwrap();
+    let mut copied = vec![];
+    std::io::copy(&mut value.as_reader(), &mut copied).unwrap();
+    assert_eq!(copied, blob);
+}
+
+#[test]
+fn get_range_of_value() {
+    let tmpfile = create_tempfile();
+    let db = Database::create(tmpfile.path()).unwrap();
+    let definition: TableDefinition<u64, &[u8]> = TableDefinition::new("blobs");
+    let blob: Vec<u8> = (0..1_000_000u32).map(|i| (i % 251) as u8).collect();
+
+    let write_txn = db.begin_write().unwrap();
+    {
+        let mut table = write_txn.open_table(definition).unwrap();
+        for i in 0..100 {
+            table.insert(i, [i as u8; 100].as_slice()).unwrap();
+        }
+        table.insert(50, blob.as_slice()).unwrap();
+        table.insert(1000, blob.as_slice()).unwrap();
+        assert_eq!(
+            table.get_range_of_value(50, 10..20).unwrap().unwrap(),
+            &blob[10..20]
+        );
+        assert_eq!(
+            table.get_range_of_value(3, ..).unwrap().unwrap(),
+            vec![3; 100]
+        );
+    }
+    write_txn.commit().unwrap();
+
+    let read_txn = db.begin_read().unwrap();
+    let table = read_txn.open_table(definition).unwrap();
+    for key in [50, 1000] {
+        assert_eq!(
+            table.get_range_of_value(key, ..16).unwrap().unwrap(),
+            &blob[..16]
+        );
+        assert_eq!(
+            table
+                .get_range_of_value(key, 999_990..=999_999)
+                .unwrap()
+                .unwrap(),
+            &blob[999_990..]
+        );
+        // The range is truncated to the value
+        assert_eq!(
+            table
+                .get_range_of_value(key, 999_990..2_000_000)
+                .unwrap()
+                .unwrap(),
+            &blob[999_990..]
+        );
+        assert!(
+            table
+                .get_range_of_value(key, 2_000_000..)
+                .unwrap()
+                .unwrap()
+                .is_empty()
+        );
+    }
+    assert_eq!(
+        table.get_range_of_value(7, 90..).unwrap().unwrap(),
+        vec![7; 10]
+    );
+    assert!(table.get_range_of_value(500, ..).unwrap().is_none());
+}
+
+#[test]
+fn update_inplace() {
+    let tmpfile = create_tempfile();
+    let db = Database::create(tmpfile.path()).unwrap();
+    let definition: TableDefinition<u64, &str> = TableDefinition::new("x");
+
+    let write_txn = db.begin_write().unwrap();
+    {
+        let mut table = write_txn.open_table(definition).unwrap();
+        for i in 0..10_000 {
+            table.insert(i, "aaaa").unwrap();
+        }
+    }
+    write_txn.commit().unwrap();
+
+    let write_txn = db.begin_write().unwrap();
+    {
+        let mut table = write_txn.open_table(definition).unwrap();
+        assert!(table.update_inplace(5000, "bbbb").unwrap());
+        // Absent keys and values of a different length are not written
+        assert!(!table.update_inplace(10_000, "bbbb").unwrap());
+        assert!(!table.update_inplace(5000, "ccccc").unwrap());
+        assert_eq!(table.get(5000).unwrap().unwrap().value(), "bbbb");
+        assert!(table.get(10_000).unwrap().is_none());
+    }
+    let allocated = write_txn.stats().unwrap().allocated_pages();
+    {
+        let mut table = write_txn.open_table(definition).unwrap();
+        for i in 0..1000u32 {
+            let value = format!("{:04}", i % 10_000);
+            assert!(table.update_inplace(5000, value.as_str()).unwrap());
+        }
+    }
+    assert_eq!(write_txn.stats().unwrap().allocated_pages(), allocated);
+    write_txn.commit().unwrap();
+
+    let read_txn = db.begin_read().unwrap();
+    let table = read_txn.open_table(definition).unwrap();
+    assert_eq!(table.get(5000).unwrap().unwrap().value(), "0999");
+    assert_eq!(table.get(4999).unwrap().unwrap().value(), "aaaa");
+    assert_eq!(table.len().unwrap(), 10_000);
+}
+
+#[test]
+fn clear() {
+    let tmpfile = create_tempfile();
+    let db = Database::create(tmpfile.path()).unwrap();
+    let definition: TableDefinition<u64, &[u8]> = TableDefinition::new("x");
+
+    let write_txn = db.begin_write().unwrap();
+    write_txn.open_table(definition).unwrap();
+    write_txn.commit().unwrap();
+    let write_txn = db.begin_write().unwrap();
+    let empty_pages = write_txn.stats().unwrap().allocated_pages();
+
+    // Pages allocated in the same transaction are freed immediately
+    {
+        let mut table = write_txn.open_table(definition).unwrap();
+        for i in 0..10_000 {
+            table.insert(i, [0u8; 100].as_slice()).unwrap();
+        }
+        table.insert(10_000, [0u8; 100_000].as_slice()).unwrap();
+        table.clear().unwrap();
+        assert!(table.is_empty().unwrap());
+        assert!(table.first().unwrap().is_none());
+    }
+    assert_eq!(write_txn.stats().unwrap().allocated_pages(), empty_pages);
+    {
+        let mut table = write_txn.open_table(definition).unwrap();
+        for i in 0..10_000 {
+            table.insert(i, [0u8; 100].as_slice()).unwrap();
+        }
+    }
+    write_txn.commit().unwrap();
+
+    // Aborting restores the table
+    let write_txn = db.begin_write().unwrap();
+    write_txn.open_table(definition).unwrap().clear().unwrap();
+    write_txn.abort().unwrap();
+    let read_txn = db.begin_read().unwrap();
+    assert_eq!(
+        read_txn.open_table(definition).unwrap().len().unwrap(),
+        10_000
+    );
+    drop(read_txn);
+
+    let write_txn = db.begin_write().unwrap();
+    {
+        let mut table = write_txn.open_table(definition).unwrap();
+        table.clear().unwrap();
+        assert_eq!(table.len().unwrap(), 0);
+        table.insert(1, [1u8].as_slice()).unwrap();
+    }
+    write_txn.commit().unwrap();
+    for _ in 0..2 {
+        db.begin_write().unwrap().commit().unwrap();
+    }
+
+    let read_txn = db.begin_read().unwrap();
+    let table = read_txn.open_table(definition).unwrap();
+    assert_eq!(table.len().unwrap(), 1);
+    assert_eq!(table.get(1).unwrap().unwrap().value(), [1u8].as_slice());
+    drop(table);
+    drop(read_txn);
+    let write_txn = db.begin_write().unwrap();
+    assert!(write_txn.stats().unwrap().allocated_pages() <= empty_pages + 1);
+    write_txn.abort().unwrap();
+    drop(db);
+    assert!(redb::verify_file(tmpfile.path()).unwrap().is_valid());
+}
+
+#[test]
+fn copy_from() {
+    let tmpfile = create_tempfile();
+    let db = Database::create(tmpfile.path()).unwrap();
+    let source_definition: TableDefinition<u64, &str> = TableDefinition::new("source");
+    let copy_definition: TableDefinition<u64, &str> = TableDefinition::new("copy");
+
+    let write_txn = db.begin_write().unwrap();
+    {
+        let mut source = write_txn.open_table(source_definition).unwrap();
+        // Insert out of order, so that the leaves of the source are partly full
+        for i in 0..10_000u64 {
+            source.insert((i * 7919) % 10_000, "value").unwrap();
+        }
+        let mut copy = write_txn.open_table(copy_definition).unwrap();
+        copy.insert(5, "old").unwrap();
+        copy.insert(20_000, "extra").unwrap();
+        assert_eq!(copy.copy_from(&source).unwrap(), 10_000);
+        assert_eq!(copy.len().unwrap(), 10_001);
+        assert_eq!(copy.get(5).unwrap().unwrap().value(), "value");
+        assert_eq!(copy.get(20_000).unwrap().unwrap().value(), "extra");
+    }
+    write_txn.commit().unwrap();
+
+    let tmpfile2 = create_tempfile();
+    let db2 = Database::create(tmpfile2.path()).unwrap();
+    let read_txn = db.begin_read().unwrap();
+    let source = read_txn.open_table(source_definition).unwrap();
+    let write_txn = db2.begin_write().unwrap();
+    {
+        let mut copy = write_txn.open_table(copy_definition).unwrap();
+        assert_eq!(copy.copy_from(&source).unwrap(), 10_000);
+        assert!(
+            copy.iter()
+                .unwrap()
+                .map(|x| x.unwrap().0.value())
+                .eq(0..10_000)
+        );
+        assert!(copy.stats().unwrap().leaf_pages() < source.stats().unwrap().leaf_pages());
+    }
+    write_txn.commit().unwrap();
+}
+
+#[test]
+fn set_table() {
+    let tmpfile = create_tempfile();
+    let db = Database::create(tmpfile.path()).unwrap();
+    let definition: SetTableDefinition<u64> = SetTableDefinition::new("x");
+
+    let write_txn = db.begin_write().unwrap();
+    {
+        let mut set = write_txn.open_set_table(definition).unwrap();
+        for i in 0..10u64 {
+            assert!(set.insert(i).unwrap());
         }
+        assert!(!set.insert(3).unwrap());
+        assert!(set.remove(5).unwrap());
+        assert!(!set.remove(5).unwrap());
+        assert!(set.contains(3).unwrap());
+        assert!(!set.contains(5).unwrap());
+        assert_eq!(set.len().unwrap(), 9);
+        assert_eq!(set.stats().unwrap().stored_bytes(), 9 * 8);
+        let keys: Vec<u64> = set
+            .range(2..7)
+            .unwrap()
+            .map(|k| k.unwrap().value())
+            .collect();
+        assert_eq!(keys, vec![2, 3, 4, 6]);
+        let last = set.iter().unwrap().next_back().unwrap().unwrap().value();
+        assert_eq!(last, 9);
+    }
+    write_txn.commit().unwrap();
 
-        fn from_bytes<'a>(data: &'a [u8]) -> ReverseKey
-        where
-            Self: 'a,
-        {
-            ReverseKey(data.to_vec())
+    let read_txn = db.begin_read().unwrap();
+    let set = read_txn.open_set_table(definition).unwrap();
+    assert!(set.contains(9).unwrap());
+    assert!(!set.contains(5).unwrap());
+    assert_eq!(set.iter().unwrap().count(), 9);
+    let table: TableDefinition<u64, ()> = TableDefinition::new("x");
+    assert_eq!(read_txn.open_table(table).unwrap().len().unwrap(), 9);
+}
+
+#[test]
+fn queue_table() {
+    let tmpfile = create_tempfile();
+    let db = Database::create(tmpfile.path()).unwrap();
+    let definition: QueueTableDefinition<&str> = QueueTableDefinition::new("x");
+
+    let write_txn = db.begin_write().unwrap();
+    {
+        let mut queue = write_txn.open_queue_table(definition).unwrap();
+        assert!(queue.pop_front().unwrap().is_none());
+        assert!(queue.peek().unwrap().is_none());
+        queue.push_back("b").unwrap();
+        queue.push_back("c").unwrap();
+        queue.push_front("a").unwrap();
+        assert_eq!(queue.len().unwrap(), 3);
+        assert_eq!(queue.peek().unwrap().unwrap().value(), "a");
+        assert_eq!(queue.peek_back().unwrap().unwrap().value(), "c");
+        let values: Vec<String> = queue
+            .iter()
+            .unwrap()
+            .map(|v| v.unwrap().value().to_string())
+            .collect();
+        assert_eq!(values, vec!["a", "b", "c"]);
+        assert_eq!(queue.pop_front().unwrap().unwrap().value(), "a");
+    }
+    write_txn.commit().unwrap();
+
+    let read_txn = db.begin_read().unwrap();
+    let queue = read_txn.open_queue_table(definition).unwrap();
+    assert_eq!(queue.len().unwrap(), 2);
+    assert_eq!(queue.peek().unwrap().unwrap().value(), "b");
+    assert_eq!(queue.peek_back().unwrap().unwrap().value(), "c");
+    drop(queue);
+    drop(read_txn);
+
+    let write_txn = db.begin_write().unwrap();
+    {
+        let mut queue = write_txn.open_queue_table(definition).unwrap();
+        queue.push_back("d").unwrap();
+        assert_eq!(queue.pop_back().unwrap().unwrap().value(), "d");
+        assert_eq!(queue.pop_back().unwrap().unwrap().value(), "c");
+        assert_eq!(queue.pop_front().unwrap().unwrap().value(), "b");
+        assert!(queue.pop_back().unwrap().is_none());
+        assert!(queue.is_empty().unwrap());
+        queue.push_front("e").unwrap();
+        assert_eq!(queue.peek_back().unwrap().unwrap().value(), "e");
+    }
+    write_txn.commit().unwrap();
+
+    // Positions at the ends of the key space, written through a plain table
+    let write_txn = db.begin_write().unwrap();
+    {
+        let mut table = write_txn
+            .open_table(TableDefinition::<u64, &str>::new("x"))
+            .unwrap();
+        table.insert(0, "front").unwrap();
+        table.insert(u64::MAX, "back").unwrap();
+    }
+    {
+        let mut queue = write_txn.open_queue_table(definition).unwrap();
+        assert_eq!(queue.len().unwrap(), 3);
+        assert!(matches!(queue.push_back("f"), Err(QueueError::QueueFull)));
+        assert!(matches!(queue.push_front("f"), Err(QueueError::QueueFull)));
+        assert_eq!(queue.len().unwrap(), 3);
+        assert_eq!(queue.pop_back().unwrap().unwrap().value(), "back");
+        assert_eq!(queue.pop_front().unwrap().unwrap().value(), "front");
+        queue.push_front("f").unwrap();
+    }
+    write_txn.commit().unwrap();
+}
+
+#[cfg(feature = "async")]
+#[test]
+fn commit_async() {
+    use std::future::Future;
+    use std::sync::Arc;
+    use std::task::{Context, Poll, Wake};
+    use std::thread::{self, Thread};
+
+    struct ThreadWaker(Thread);
+
+    impl Wake for ThreadWaker {
+        fn wake(self: Arc<Self>) {
+            self.0.unpark();
         }
+    }
 
-        fn as_bytes<'a, 'b: 'a>(value: &'a Self::SelfType<'b>) -> &'a [u8]
-        where
-            Self: 'a,
-            Self: 'b,
-        {
-            &value.0
+    fn block_on<F: Future>(future: F) -> F::Output {
+        let mut future = std::pin::pin!(future);
+        let waker = Arc::new(ThreadWaker(thread::current())).into();
+        let mut cx = Context::from_waker(&waker);
+        loop {
+            if let Poll::Ready(output) = future.as_mut().poll(&mut cx) {
+                return output;
+            }
+            thread::park();
         }
+    }
 
-        fn type_name() -> TypeName {
-            TypeName::new("test::ReverseKey")
+    let tmpfile = create_tempfile();
+    let db = Database::create(tmpfile.path()).unwrap();
+    let definition: TableDefinition<u64, u64> = TableDefinition::new("x");
+
+    let write_txn = db.begin_write().unwrap();
+    write_txn
+        .open_table(definition)
+        .unwrap()
+        .insert(0, 1)
+        .unwrap();
+    block_on(write_txn.commit_async()).unwrap();
+
+    // The commit completes even if the future is never polled
+    let write_txn = db.begin_write().unwrap();
+    write_txn
+        .open_table(definition)
+        .unwrap()
+        .insert(1, 2)
+        .unwrap();
+    drop(write_txn.commit_async());
+
+    // Waits for the previous commit to release the write lock
+    let write_txn = db.begin_write().unwrap();
+    assert_eq!(write_txn.open_table(definition).unwrap().len().unwrap(), 2);
+    write_txn.abort().unwrap();
+
+    let read_txn = db.begin_read().unwrap();
+    let table = read_txn.open_table(definition).unwrap();
+    assert_eq!(table.get(0).unwrap().unwrap().value(), 1);
+    assert_eq!(table.get(1).unwrap().unwrap().value(), 2);
+}
+
+#[cfg(feature = "async")]
+#[test]
+fn async_database() {
+    use redb::{AsyncDatabase, Error};
+    use std::future::Future;
+    use std::sync::Arc;
+    use std::task::{Context, Poll, Wake};
+    use std::thread::{self, Thread};
+
+    struct ThreadWaker(Thread);
+
+    impl Wake for ThreadWaker {
+        fn wake(self: Arc<Self>) {
+            self.0.unpark();
         }
     }
 
-    impl Key for ReverseKey {
-        fn compare(data1: &[u8], data2: &[u8]) -> Ordering {
-            data2.cmp(data1)
+    fn block_on<F: Future>(future: F) -> F::Output {
+        let mut future = std::pin::pin!(future);
+        let waker = Arc::new(ThreadWaker(thread::current())).into();
+        let mut cx = Context::from_waker(&waker);
+        loop {
+            if let Poll::Ready(output) = future.as_mut().poll(&mut cx) {
+                return output;
+            }
+            thread::park();
         }
     }
 
-    let definition: TableDefinition<ReverseKey, &str> = TableDefinition::new("x");
+    fn assert_send<T: Send>(value: T) -> T {
+        value
+    }
+
+    const TABLE: TableDefinition<u64, u64> = TableDefinition::new("x");
+
+    let tmpfile = create_tempfile();
+    let db = AsyncDatabase::with_workers(Database::create(tmpfile.path()).unwrap(), 2);
+
+    let write_txn = block_on(assert_send(db.begin_write())).unwrap();
+    block_on(write_txn.run(|txn| {
+        let mut table = txn.open_table(TABLE)?;
+        for i in 0..10 {
+            table.insert(i, i * 2)?;
+        }
+        Ok::<_, Error>(())
+    }))
+    .unwrap()
+    .unwrap();
+    block_on(assert_send(write_txn.commit())).unwrap();
+
+    // Aborted transactions are discarded, including when the transaction is dropped
+    let write_txn = block_on(db.begin_write()).unwrap();
+    block_on(write_txn.run(|txn| {
+        txn.open_table(TABLE).unwrap().insert(100, 0).unwrap();
+    }))
+    .unwrap();
+    block_on(write_txn.abort()).unwrap();
+    let write_txn = block_on(db.begin_write()).unwrap();
+    block_on(write_txn.run(|txn| {
+        txn.open_table(TABLE).unwrap().insert(101, 0).unwrap();
+    }))
+    .unwrap();
+    drop(write_txn);
+
+    let read_txn = block_on(db.begin_read()).unwrap();
+    let values = block_on(read_txn.run(|txn| {
+        let table = txn.open_table(TABLE)?;
+        table
+            .iter()?
+            .map(|entry| entry.map(|(_, v)| v.value()))
+            .collect::<Result<Vec<u64>, _>>()
+            .map_err(Error::from)
+    }))
+    .unwrap();
+    assert_eq!(values, (0..10).map(|i| i * 2).collect::<Vec<u64>>());
+
+    // Panics in the closure are resumed in the polling task
+    let result = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+        block_on(read_txn.run(|_| panic!("expected")));
+    }));
+    assert!(result.is_err());
+    let len = block_on(read_txn.run(|txn| txn.open_table(TABLE).unwrap().len().unwrap()));
+    assert_eq!(len, 10);
+}
+
+#[test]
+fn commit_hooks() {
+    use redb::CompletedTransaction;
+    use std::sync::{Arc, Mutex};
+
+    const A: TableDefinition<u64, u64> = TableDefinition::new("a");
+    const B: TableDefinition<u64, u64> = TableDefinition::new("b");
+    const C: TableDefinition<u64, u64> = TableDefinition::new("c");
+
+    let tmpfile = create_tempfile();
+    let db = Database::create(tmpfile.path()).unwrap();
+    let write_txn = db.begin_write().unwrap();
+    write_txn.open_table(C).unwrap().insert(0, 0).unwrap();
+    write_txn.commit().unwrap();
+
+    let events: Arc<Mutex<Vec<CompletedTransaction>>> = Arc::default();
+    let db_events = events.clone();
+    db.set_commit_hook(move |completed| db_events.lock().unwrap().push(completed.clone()));
+    let summary = |events: &Arc<Mutex<Vec<CompletedTransaction>>>| -> Vec<(bool, Vec<String>)> {
+        events
+            .lock()
+            .unwrap()
+            .drain(..)
+            .map(|e| (e.committed(), e.modified_tables().to_vec()))
+            .collect()
+    };
+
+    // Tables which are only read are not reported
+    let write_txn = db.begin_write().unwrap();
+    let txn_events: Arc<Mutex<Vec<CompletedTransaction>>> = Arc::default();
+    let hook_events = txn_events.clone();
+    write_txn.on_commit(move |completed| hook_events.lock().unwrap().push(completed.clone()));
+    write_txn.open_table(A).unwrap().insert(1, 1).unwrap();
+    write_txn.open_table(B).unwrap();
+    assert_eq!(write_txn.open_table(C).unwrap().len().unwrap(), 1);
+    write_txn.commit().unwrap();
+    let expected = vec![(true, vec!["a".to_string(), "b".to_string()])];
+    assert_eq!(summary(&txn_events), expected);
+    assert_eq!(summary(&events), expected);
+
+    let write_txn = db.begin_write().unwrap();
+    write_txn.open_table(C).unwrap().insert(1, 1).unwrap();
+    write_txn.abort().unwrap();
+    assert_eq!(summary(&events), vec![(false, vec!["c".to_string()])]);
+
+    let write_txn = db.begin_write().unwrap();
+    write_txn.delete_table(A).unwrap();
+    write_txn
+        .rename_table(B, TableDefinition::<u64, u64>::new("d"))
+        .unwrap();
+    drop(write_txn);
+    assert_eq!(
+        summary(&events),
+        vec![(
+            false,
+            vec!["a".to_string(), "b".to_string(), "d".to_string()]
+        )]
+    );
+
+    let mut write_txn = db.begin_write().unwrap();
+    let savepoint = write_txn.ephemeral_savepoint().unwrap();
+    write_txn.open_table(A).unwrap().insert(2, 2).unwrap();
+    write_txn.open_table(C).unwrap().insert(2, 2).unwrap();
+    write_txn.restore_savepoint(&savepoint).unwrap();
+    write_txn.commit().unwrap();
+    assert_eq!(
+        summary(&events),
+        vec![(true, vec!["a".to_string(), "c".to_string()])]
+    );
+
+    // Transaction ids increase, and hooks are only attached to transactions begun after them
+    let write_txn = db.begin_write().unwrap();
+    db.clear_commit_hook();
+    write_txn.commit().unwrap();
+    let first = events.lock().unwrap().pop().unwrap().transaction_id();
+    db.set_commit_hook(|_| {});
+    let write_txn = db.begin_write().unwrap();
+    let txn_events = Arc::new(Mutex::new(vec![]));
+    let hook_events = txn_events.clone();
+    write_txn.on_commit(move |completed| hook_events.lock().unwrap().push(completed.clone()));
+    write_txn.commit().unwrap();
+    assert!(events.lock().unwrap().is_empty());
+    assert!(txn_events.lock().unwrap()[0].transaction_id() > first);
+    assert!(txn_events.lock().unwrap()[0].modified_tables().is_empty());
+}
+
+#[test]
+fn watch() {
+    use redb::WatchEvent;
+
+    const TABLE: TableDefinition<u64, u64> = TableDefinition::new("x");
+    fn summary(event: WatchEvent<u64, u64>) -> (bool, u64, u64) {
+        match event {
+            WatchEvent::Insert { key, value } => (true, key.value(), value.value()),
+            WatchEvent::Remove { key, value } => (false, key.value(), value.value()),
+            _ => unreachable!(),
+        }
+    }
 
     let tmpfile = create_tempfile();
     let db = Database::create(tmpfile.path()).unwrap();
+    let watch = db.watch(TABLE, 10..20);
+    let unwatched = db.watch(TABLE, 100..);
+    let mismatched = db.watch(TableDefinition::<&str, &str>::new("x"), ""..);
+
     let write_txn = db.begin_write().unwrap();
     {
-        let mut table = write_txn.open_table(definition).unwrap();
-        for i in 0..10u8 {
-            let key = vec![i];
-            table.insert(&ReverseKey(key), "value").unwrap();
+        let mut table = write_txn.open_table(TABLE).unwrap();
+        table.insert(5, 0).unwrap();
+        table.insert(16, 1).unwrap();
+        table.insert(15, 1).unwrap();
+    }
+    write_txn.commit().unwrap();
+    assert_eq!(summary(watch.try_recv().unwrap()), (true, 15, 1));
+    assert_eq!(summary(watch.try_recv().unwrap()), (true, 16, 1));
+    assert!(watch.try_recv().is_none());
+
+    // Aborted changes are not delivered
+    let write_txn = db.begin_write().unwrap();
+    write_txn.open_table(TABLE).unwrap().insert(17, 0).unwrap();
+    write_txn.abort().unwrap();
+    assert!(watch.try_recv().is_none());
+
+    // Rewriting a key with the same value is not a change
+    let write_txn = db.begin_write().unwrap();
+    {
+        let mut table = write_txn.open_table(TABLE).unwrap();
+        table.insert(15, 2).unwrap();
+        table.insert(16, 1).unwrap();
+        table.remove(16).unwrap();
+        table.insert(18, 3).unwrap();
+        table.remove(18).unwrap();
+        table.insert(19, 4).unwrap();
+    }
+    write_txn.commit().unwrap();
+    let events: Vec<(bool, u64, u64)> = std::iter::from_fn(|| watch.try_recv())
+        .map(summary)
+        .collect();
+    assert_eq!(events, vec![(true, 15, 2), (false, 16, 1), (true, 19, 4)]);
+
+    let write_txn = db.begin_write().unwrap();
+    write_txn.delete_table(TABLE).unwrap();
+    write_txn.commit().unwrap();
+    let events: Vec<(bool, u64, u64)> = std::iter::from_fn(|| watch.try_recv())
+        .map(summary)
+        .collect();
+    assert_eq!(events, vec![(false, 15, 2), (false, 19, 4)]);
+    assert!(unwatched.try_recv().is_none());
+    assert!(mismatched.try_recv().is_none());
+
+    // Watches which have been dropped are discarded, and iteration ends when the database closes
+    drop(unwatched);
+    let write_txn = db.begin_write().unwrap();
+    write_txn.open_table(TABLE).unwrap().insert(100, 0).unwrap();
+    write_txn.commit().unwrap();
+    drop(db);
+    assert_eq!(watch.count(), 0);
+}
+
+#[test]
+fn change_log() {
+    use redb::{ChangeRecord, Value};
+
+    const TABLE: TableDefinition<u64, u64> = TableDefinition::new("x");
+    const OTHER: TableDefinition<u64, u64> = TableDefinition::new("y");
+    fn summary(records: &[ChangeRecord]) -> Vec<(String, u64, Option<u64>, Option<u64>)> {
+        records
+            .iter()
+            .map(|x| {
+                (
+                    x.table_name().to_string(),
+                    u64::from_bytes(x.key()),
+                    x.old_value().map(u64::from_bytes),
+                    x.new_value().map(u64::from_bytes),
+                )
+            })
+            .collect()
+    }
+
+    let tmpfile = create_tempfile();
+    let db = Database::builder()
+        .set_change_capture(true)
+        .create(tmpfile.path())
+        .unwrap();
+
+    let write_txn = db.begin_write().unwrap();
+    {
+        let mut table = write_txn.open_table(TABLE).unwrap();
+        for i in 0..10_000 {
+            table.insert(i, i).unwrap();
         }
     }
     write_txn.commit().unwrap();
+    let records = db.change_log(..).unwrap();
+    assert_eq!(records.len(), 10_000);
+    let first = records[0].transaction_id();
+    assert!(records.iter().all(|x| x.transaction_id() == first));
 
-    let read_txn = db.begin_read().unwrap();
-    let table = read_txn.open_table(definition).unwrap();
-    let start = ReverseKey(vec![7u8]); // ReverseKey is used, so 7 < 3
-    let end = ReverseKey(vec![3u8]);
-    let mut iter = table.range(start..=end).unwrap();
-    for i in (3..=7u8).rev() {
-        let (key, value) = iter.next().unwrap().unwrap();
-        assert_eq!(&[i], key.value().0.as_slice());
-        assert_eq!("value", value.value());
+    let write_txn = db.begin_write().unwrap();
+    {
+        let mut table = write_txn.open_table(TABLE).unwrap();
+        table.insert(5000, 1).unwrap();
+        table.insert(7000, 7000).unwrap();
+        table.remove(3).unwrap();
+        table.insert(20_000, 2).unwrap();
+        write_txn.open_table(OTHER).unwrap().insert(1, 1).unwrap();
     }
-    assert!(iter.next().is_none());
+    write_txn.commit().unwrap();
+    let records = db.change_log(first + 1..).unwrap();
+    let second = records[0].transaction_id();
+    let mut changes = summary(&records);
+    changes.sort();
+    assert_eq!(
+        changes,
+        vec![
+            ("x".to_string(), 3, Some(3), None),
+            ("x".to_string(), 5000, Some(5000), Some(1)),
+            ("x".to_string(), 20_000, None, Some(2)),
+            ("y".to_string(), 1, None, Some(1)),
+        ]
+    );
+
+    // Aborted transactions are not recorded
+    let write_txn = db.begin_write().unwrap();
+    write_txn.open_table(TABLE).unwrap().insert(0, 1).unwrap();
+    write_txn.abort().unwrap();
+
+    // Restoring a savepoint records the difference from the last commit
+    let savepoint_txn = db.begin_write().unwrap();
+    let savepoint = savepoint_txn.persistent_savepoint().unwrap();
+    savepoint_txn.commit().unwrap();
+    let write_txn = db.begin_write().unwrap();
+    write_txn.open_table(TABLE).unwrap().remove(9999).unwrap();
+    write_txn.commit().unwrap();
+    let mut write_txn = db.begin_write().unwrap();
+    let savepoint = write_txn.get_persistent_savepoint(savepoint).unwrap();
+    write_txn.restore_savepoint(&savepoint).unwrap();
+    write_txn.open_table(TABLE).unwrap().insert(1, 0).unwrap();
+    write_txn.delete_table(OTHER).unwrap();
+    write_txn.commit().unwrap();
+    let records = db.change_log(second + 1..).unwrap();
+    assert_eq!(
+        summary(&records),
+        vec![
+            ("x".to_string(), 9999, Some(9999), None),
+            ("x".to_string(), 1, Some(1), Some(0)),
+            ("x".to_string(), 9999, None, Some(9999)),
+            ("y".to_string(), 1, Some(1), None),
+        ]
+    );
+    assert_eq!(db.change_log(..=second).unwrap().len(), 10_004);
+
+    let write_txn = db.begin_write().unwrap();
+    assert_eq!(write_txn.truncate_change_log(second).unwrap(), 10_000);
+    write_txn.commit().unwrap();
+    assert_eq!(db.change_log(..).unwrap().len(), 8);
+    assert!(db.change_log(first..second).unwrap().is_empty());
+}
+
+#[test]
+fn two_phase_commit() {
+    use redb::{CompactionError, PrepareError};
+
+    const TABLE: TableDefinition<u64, u64> = TableDefinition::new("x");
+    const MULTI: MultimapTableDefinition<u64, u64> = MultimapTableDefinition::new("y");
+
+    let tmpfile = create_tempfile();
+    let mut db = Database::create(tmpfile.path()).unwrap();
+    let write_txn = db.begin_write().unwrap();
+    {
+        let mut table = write_txn.open_table(TABLE).unwrap();
+        for i in 0..1000 {
+            table.insert(i, i).unwrap();
+        }
+    }
+    write_txn.commit().unwrap();
+
+    let write_txn = db.begin_write().unwrap();
+    {
+        let mut table = write_txn.open_table(TABLE).unwrap();
+        table.insert(500, 0).unwrap();
+        table.insert(2000, 2000).unwrap();
+        let mut multi = write_txn.open_multimap_table(MULTI).unwrap();
+        for i in 0..1000 {
+            multi.insert(1, i).unwrap();
+        }
+    }
+    let id = write_txn.prepare().unwrap();
+
+    // The prepared writes are not visible, including after the database is reopened
+    let read_txn = db.begin_read().unwrap();
+    let table = read_txn.open_table(TABLE).unwrap();
+    assert_eq!(table.get(500).unwrap().unwrap().value(), 500);
+    assert!(table.get(2000).unwrap().is_none());
+    assert!(read_txn.open_multimap_table(MULTI).is_err());
+    drop(table);
+    drop(read_txn);
+    db.check_integrity().unwrap();
+    drop(db);
+    let mut db = Database::create(tmpfile.path()).unwrap();
+    let write_txn = db.begin_write().unwrap();
+    assert_eq!(
+        write_txn
+            .list_prepared_transactions()
+            .unwrap()
+            .collect::<Vec<_>>(),
+        vec![id]
+    );
+    assert!(
+        write_txn
+            .open_table(TABLE)
+            .unwrap()
+            .get(2000)
+            .unwrap()
+            .is_none()
+    );
+    drop(write_txn);
+
+    let mut write_txn = db.begin_write().unwrap();
+    assert!(matches!(
+        write_txn.commit_prepared(id + 1),
+        Err(PrepareError::NotFound(_))
+    ));
+    write_txn.commit_prepared(id).unwrap();
+    write_txn.commit().unwrap();
+
+    let read_txn = db.begin_read().unwrap();
+    let table = read_txn.open_table(TABLE).unwrap();
+    assert_eq!(table.get(500).unwrap().unwrap().value(), 0);
+    assert_eq!(table.get(2000).unwrap().unwrap().value(), 2000);
+    assert_eq!(table.len().unwrap(), 1001);
+    let multi = read_txn.open_multimap_table(MULTI).unwrap();
+    assert_eq!(multi.get(1).unwrap().count(), 1000);
+    drop(table);
+    drop(multi);
+    drop(read_txn);
+
+    // A prepared transaction can't be committed once the database has been modified
+    let write_txn = db.begin_write().unwrap();
+    assert_eq!(write_txn.list_prepared_transactions().unwrap().count(), 0);
+    write_txn
+        .open_table(TABLE)
+        .unwrap()
+        .insert(3000, 3000)
+        .unwrap();
+    let id = write_txn.prepare().unwrap();
+    assert!(matches!(
+        db.compact(),
+        Err(CompactionError::TransactionInProgress)
+    ));
+    let write_txn = db.begin_write().unwrap();
+    write_txn.open_table(TABLE).unwrap().insert(1, 0).unwrap();
+    write_txn.commit().unwrap();
+    let mut write_txn = db.begin_write().unwrap();
+    assert!(matches!(
+        write_txn.commit_prepared(id),
+        Err(PrepareError::Conflict(_))
+    ));
+    write_txn.rollback_prepared(id).unwrap();
+    write_txn.commit().unwrap();
+
+    let read_txn = db.begin_read().unwrap();
+    let table = read_txn.open_table(TABLE).unwrap();
+    assert!(table.get(3000).unwrap().is_none());
+    assert_eq!(table.get(1).unwrap().unwrap().value(), 0);
+    drop(table);
+    drop(read_txn);
+
+    // Prepare isn't allowed while a savepoint exists
+    let write_txn = db.begin_write().unwrap();
+    let savepoint = write_txn.ephemeral_savepoint().unwrap();
+    write_txn
+        .open_table(TABLE)
+        .unwrap()
+        .insert(4000, 4000)
+        .unwrap();
+    assert!(matches!(
+        write_txn.prepare(),
+        Err(PrepareError::EphemeralSavepointExists)
+    ));
+    drop(savepoint);
+    db.compact().unwrap();
 }
 
 #[test]
-fn owned_get_signatures() {
-    let tmpfile = create_tempfile();
-    let db = Database::create(tmpfile.path()).unwrap();
+fn commit_coordinator() {
+    use redb::{CommitCoordinator, PrepareError};
 
-    let definition: TableDefinition<u32, u32> = TableDefinition::new("x");
+    const TABLE: TableDefinition<u64, u64> = TableDefinition::new("x");
 
-    let write_txn = db.begin_write().unwrap();
-    {
-        let mut table = write_txn.open_table(definition).unwrap();
-        for i in 0..10 {
-            table.insert(&i, &(i + 1)).unwrap();
-        }
-    }
-    write_txn.commit().unwrap();
+    let files = [create_tempfile(), create_tempfile(), create_tempfile()];
+    let databases: Vec<Database> = files
+        .iter()
+        .map(|file| Database::create(file.path()).unwrap())
+        .collect();
+    let refs: Vec<&Database> = databases.iter().collect();
+    let coordinator = CommitCoordinator::new(&refs).unwrap();
 
-    let read_txn = db.begin_read().unwrap();
-    let table = read_txn.open_table(definition).unwrap();
+    let transactions = coordinator.begin_write().unwrap();
+    for (i, txn) in (0u64..).zip(&transactions) {
+        txn.open_table(TABLE).unwrap().insert(i, i).unwrap();
+    }
+    coordinator.commit(transactions).unwrap();
+    for (i, db) in (0u64..).zip(&databases) {
+        let read_txn = db.begin_read().unwrap();
+        let table = read_txn.open_table(TABLE).unwrap();
+        assert_eq!(table.get(i).unwrap().unwrap().value(), i);
+        let write_txn = db.begin_write().unwrap();
+        assert_eq!(write_txn.list_prepared_transactions().unwrap().count(), 0);
+    }
 
-    assert_eq!(2, table.get(&1).unwrap().unwrap().value());
+    // Invalid databases and transactions are rejected
+    assert!(matches!(
+        CommitCoordinator::new(&[]),
+        Err(PrepareError::InvalidParticipants(_))
+    ));
+    assert!(matches!(
+        CommitCoordinator::new(&[&databases[0], &databases[1], &databases[0]]),
+        Err(PrepareError::InvalidParticipants(_))
+    ));
+    let mut transactions = coordinator.begin_write().unwrap();
+    transactions.pop();
+    assert!(matches!(
+        coordinator.commit(transactions),
+        Err(PrepareError::InvalidParticipants(_))
+    ));
+    let mut transactions = coordinator.begin_write().unwrap();
+    transactions.swap(0, 1);
+    assert!(matches!(
+        coordinator.commit(transactions),
+        Err(PrepareError::InvalidParticipants(_))
+    ));
 
-    let mut iter: Range<u32, u32> = table.range::<u32>(..).unwrap();
-    for i in 0..10 {
-        assert_eq!(iter.next().unwrap().unwrap().1.value(), i + 1);
-    }
-    assert!(iter.next().is_none());
-    let mut iter: Range<u32, u32> = table.range(0..10).unwrap();
-    for i in 0..10 {
-        assert_eq!(iter.next().unwrap().unwrap().1.value(), i + 1);
+    // If one of the databases can't prepare, none of them commit
+    let transactions = coordinator.begin_write().unwrap();
+    let savepoint = transactions[2].ephemeral_savepoint().unwrap();
+    for txn in &transactions {
+        txn.open_table(TABLE).unwrap().insert(10, 10).unwrap();
     }
-    assert!(iter.next().is_none());
-    let mut iter = table.range::<&u32>(&0..&10).unwrap();
-    for i in 0..10 {
-        assert_eq!(iter.next().unwrap().unwrap().1.value(), i + 1);
+    assert!(matches!(
+        coordinator.commit(transactions),
+        Err(PrepareError::EphemeralSavepointExists)
+    ));
+    drop(savepoint);
+    for db in &databases {
+        let read_txn = db.begin_read().unwrap();
+        let table = read_txn.open_table(TABLE).unwrap();
+        assert!(table.get(10).unwrap().is_none());
+        let write_txn = db.begin_write().unwrap();
+        assert_eq!(write_txn.list_prepared_transactions().unwrap().count(), 0);
     }
-    assert!(iter.next().is_none());
+
+    // Transactions prepared outside of the coordinator are left alone
+    let write_txn = databases[1].begin_write().unwrap();
+    write_txn.open_table(TABLE).unwrap().insert(20, 20).unwrap();
+    let id = write_txn.prepare().unwrap();
+    coordinator.recover().unwrap();
+    let write_txn = databases[1].begin_write().unwrap();
+    assert_eq!(
+        write_txn
+            .list_prepared_transactions()
+            .unwrap()
+            .collect::<Vec<_>>(),
+        vec![id]
+    );
 }
 
 #[test]
-fn ref_get_signatures() {
+fn transaction_stats() {
     let tmpfile = create_tempfile();
     let db = Database::create(tmpfile.path()).unwrap();
+
     let write_txn = db.begin_write().unwrap();
+    let stats = write_txn.transaction_stats();
+    assert_eq!(stats.allocated_pages(), 0);
+    assert_eq!(stats.table_operations().count(), 0);
     {
-        let mut table = write_txn.open_table(SLICE_TABLE).unwrap();
-        for i in 0..10u8 {
-            table.insert([i].as_slice(), [i + 1].as_slice()).unwrap();
+        let mut table = write_txn.open_table(U64_TABLE).unwrap();
+        for i in 0..1000 {
+            table.insert(i, i).unwrap();
         }
-    }
+        table.remove(0).unwrap();
+        table.remove(5000).unwrap();
+        table.retain(|key, _| key % 2 == 0).unwrap();
+        table.entry(5000).unwrap().or_insert(0).unwrap();
+    }
+    let stats = write_txn.transaction_stats();
+    assert!(stats.allocated_pages() > 0);
+    assert!(stats.written_bytes() >= stats.allocated_pages() * 4096);
+    assert!(stats.dirty_bytes() > 0);
+    let operations: Vec<(&str, u64, u64)> = stats
+        .table_operations()
+        .map(|(name, operations)| (name, operations.inserts(), operations.removes()))
+        .collect();
+    assert_eq!(operations, vec![("u64", 1001, 501)]);
     write_txn.commit().unwrap();
 
-    let read_txn = db.begin_read().unwrap();
-    let table = read_txn.open_table(SLICE_TABLE).unwrap();
-
-    let zero = vec![0u8];
-    assert_eq!(&[1], table.get([0].as_slice()).unwrap().unwrap().value());
-    assert_eq!(&[1], table.get(b"\0".as_slice()).unwrap().unwrap().value());
-    assert_eq!(&[1], table.get(zero.as_slice()).unwrap().unwrap().value());
-
-    let start = vec![0u8];
-    let end = vec![10u8];
-    let mut iter = table.range::<&[u8]>(..).unwrap();
-    for i in 0..10 {
-        assert_eq!(iter.next().unwrap().unwrap().1.value(), &[i + 1]);
-    }
-    assert!(iter.next().is_none());
+    let write_txn = db.begin_write().unwrap();
+    write_txn.open_table(U64_TABLE).unwrap().clear().unwrap();
+    let stats = write_txn.transaction_stats();
+    assert!(stats.freed_pages() > 0);
+    let (name, operations) = stats.table_operations().next().unwrap();
+    assert_eq!(name, "u64");
+    assert_eq!(operations.inserts(), 0);
+    assert_eq!(operations.removes(), 500);
+    write_txn.abort().unwrap();
+}
 
-    let mut iter = table.range(start.as_slice()..&end).unwrap();
-    for i in 0..10 {
-        assert_eq!(iter.next().unwrap().unwrap().1.value(), &[i + 1]);
-    }
-    assert!(iter.next().is_none());
-    drop(iter);
+#[test]
+fn active_transactions() {
+    let tmpfile = create_tempfile();
+    let db = Database::create(tmpfile.path()).unwrap();
+    assert!(db.active_transactions().unwrap().is_empty());
 
-    let mut iter = table.range(start.as_slice()..end.as_slice()).unwrap();
-    for i in 0..10 {
-        assert_eq!(iter.next().unwrap().unwrap().1.value(), &[i + 1]);
-    }
-    assert!(iter.next().is_none());
+    let old_read = db.begin_read().unwrap();
+    let write_txn = db.begin_write().unwrap();
+    write_txn
+        .open_table(U64_TABLE)
+        .unwrap()
+        .insert(0, 0)
+        .unwrap();
+    write_txn.commit().unwrap();
+    let new_read = db.begin_read().unwrap();
+    let write_txn = db.begin_write().unwrap();
 
-    let mut iter = table.range([0u8].as_slice()..[10u8].as_slice()).unwrap();
-    for i in 0..10u8 {
-        assert_eq!(iter.next().unwrap().unwrap().1.value(), [i + 1].as_slice());
-    }
-    assert!(iter.next().is_none());
+    let active = db.active_transactions().unwrap();
+    let kinds: Vec<TransactionKind> = active.iter().map(ActiveTransaction::kind).collect();
+    assert_eq!(
+        kinds,
+        vec![
+            TransactionKind::Read,
+            TransactionKind::Read,
+            TransactionKind::Write
+        ]
+    );
+    assert!(active[0].snapshot_age() > 0);
+    assert_eq!(active[1].snapshot_age(), 0);
+    assert_eq!(active[2].snapshot_age(), 0);
+    assert!(active[0].transaction_id() < active[1].transaction_id());
+    assert!(active[1].transaction_id() < active[2].transaction_id());
+    assert!(active[0].started() <= active[1].started());
+
+    drop(old_read);
+    write_txn.abort().unwrap();
+    let active = db.active_transactions().unwrap();
+    assert_eq!(active.len(), 1);
+    assert_eq!(active[0].kind(), TransactionKind::Read);
+    drop(new_read);
+    assert!(db.active_transactions().unwrap().is_empty());
 }
 
-#[cfg(not(target_os = "wasi"))]
 #[test]
-fn concurrent_write_transactions_block() {
+fn transaction_labels() {
+    use std::sync::{Arc, Mutex};
+    use std::time::Duration;
+
     let tmpfile = create_tempfile();
-    let db = sync::Arc::new(Database::create(tmpfile.path()).unwrap());
-    let wtx = db.begin_write().unwrap();
-    let (sender, receiver) = sync::mpsc::channel();
+    let db = Database::create(tmpfile.path()).unwrap();
 
-    let t = {
-        std::thread::spawn(move || {
-            sender.send(()).unwrap();
-            db.begin_write().unwrap().commit().unwrap();
-        })
-    };
+    let reported = Arc::new(Mutex::new(vec![]));
+    let reported2 = reported.clone();
+    db.set_long_read_hook(Duration::ZERO, move |txn| {
+        reported2
+            .lock()
+            .unwrap()
+            .push(txn.label().map(str::to_string));
+    });
 
-    receiver.recv().unwrap();
-    std::thread::sleep(std::time::Duration::from_secs(1));
-    wtx.commit().unwrap();
-    t.join().unwrap();
+    let leaked = db.begin_read().unwrap();
+    leaked.set_label("leaked");
+    let unlabeled = db.begin_read().unwrap();
+    let write_txn = db.begin_write().unwrap();
+    write_txn.set_label("writer");
+    let active = db.active_transactions().unwrap();
+    let labels: Vec<Option<&str>> = active.iter().map(ActiveTransaction::label).collect();
+    assert_eq!(labels, vec![Some("leaked"), None, Some("writer")]);
+    assert_eq!(
+        *reported.lock().unwrap(),
+        vec![Some("leaked".to_string()), None]
+    );
+    write_txn.commit().unwrap();
+
+    // Each read transaction is only reported once
+    drop(unlabeled);
+    db.begin_write().unwrap().abort().unwrap();
+    assert_eq!(reported.lock().unwrap().len(), 2);
+
+    db.clear_long_read_hook();
+    let _read = db.begin_read().unwrap();
+    db.begin_write().unwrap().abort().unwrap();
+    assert_eq!(reported.lock().unwrap().len(), 2);
+    drop(leaked);
 }
 
 #[test]
-fn iter() {
+fn read_transaction_max_age() {
     let tmpfile = create_tempfile();
-    let db = Database::create(tmpfile.path()).unwrap();
+    let db = Database::builder()
+        .set_read_transaction_max_age(std::time::Duration::ZERO)
+        .create(tmpfile.path())
+        .unwrap();
     let write_txn = db.begin_write().unwrap();
     {
         let mut table = write_txn.open_table(U64_TABLE).unwrap();
-        for i in 0..10 {
-            table.insert(&i, &i).unwrap();
+        for i in 0..1000 {
+            table.insert(i, i).unwrap();
         }
     }
     write_txn.commit().unwrap();
 
     let read_txn = db.begin_read().unwrap();
     let table = read_txn.open_table(U64_TABLE).unwrap();
-    let mut iter = table.iter().unwrap();
-    for i in 0..10 {
-        let (k, v) = iter.next().unwrap().unwrap();
-        assert_eq!(i, k.value());
-        assert_eq!(i, v.value());
+    let value = table.get(0).unwrap().unwrap();
+    let mut iter = table.range(500..).unwrap();
+    iter.next();
+    assert_eq!(db.active_transactions().unwrap().len(), 1);
+
+    // Pages read before the transaction expired stay valid, even once they are reused
+    for _ in 0..10 {
+        let write_txn = db.begin_write().unwrap();
+        {
+            let mut table = write_txn.open_table(U64_TABLE).unwrap();
+            for i in 0..1000 {
+                table.insert(i, i + 1).unwrap();
+            }
+        }
+        write_txn.commit().unwrap();
     }
+    assert_eq!(value.value(), 0);
+    assert!(db.active_transactions().unwrap().is_empty());
+
+    assert!(matches!(
+        table.get(1),
+        Err(StorageError::ReadTransactionExpired)
+    ));
+    assert!(matches!(
+        read_txn.open_table(U64_TABLE),
+        Err(TableError::Storage(StorageError::ReadTransactionExpired))
+    ));
+    drop(iter);
+    drop(value);
+    drop(table);
+    drop(read_txn);
+
+    let read_txn = db.begin_read().unwrap();
+    let table = read_txn.open_table(U64_TABLE).unwrap();
+    assert_eq!(table.get(0).unwrap().unwrap().value(), 1);
 }
 
 #[test]
@@ -2813,3 +6084,214 @@ fn u8_array_serialization() {
         assert_eq!(ref_order, generic_order);
     }
 }
+
+#[test]
+fn commit_validators() {
+    use redb::{CommitError, ValidationError};
+
+    const ACCOUNTS: TableDefinition<&str, u64> = TableDefinition::new("accounts");
+    const TOTAL: TableDefinition<(), u64> = TableDefinition::new("total");
+
+    // The balance in TOTAL must always equal the sum of the balances in ACCOUNTS
+    let check_total = |txn: &redb::WriteTransaction| -> Result<(), ValidationError> {
+        let accounts = txn.open_table(ACCOUNTS)?;
+        let mut sum = 0;
+        for entry in accounts.iter()? {
+            sum += entry?.1.value();
+        }
+        let total = txn.open_table(TOTAL)?.get(())?.map_or(0, |x| x.value());
+        if sum == total {
+            Ok(())
+        } else {
+            Err(ValidationError::Rejected(format!("{sum} != {total}")))
+        }
+    };
+
+    let tmpfile = create_tempfile();
+    let db = Database::create(tmpfile.path()).unwrap();
+
+    let write_txn = db.begin_write().unwrap();
+    write_txn.validate(check_total);
+    write_txn
+        .open_table(ACCOUNTS)
+        .unwrap()
+        .insert("a", 5)
+        .unwrap();
+    assert!(matches!(
+        write_txn.dry_run(),
+        Err(ValidationError::Rejected(_))
+    ));
+    write_txn.open_table(TOTAL).unwrap().insert((), 5).unwrap();
+    write_txn.dry_run().unwrap();
+    write_txn.commit().unwrap();
+
+    let write_txn = db.begin_write().unwrap();
+    write_txn.validate(check_total);
+    write_txn
+        .open_table(ACCOUNTS)
+        .unwrap()
+        .insert("b", 3)
+        .unwrap();
+    let err = write_txn.commit().unwrap_err();
+    assert!(matches!(
+        err,
+        CommitError::ValidationFailed(ValidationError::Rejected(_))
+    ));
+    assert_eq!(err.to_string(), "Transaction failed validation: 8 != 5");
+
+    // The failed transaction was aborted
+    let read_txn = db.begin_read().unwrap();
+    let accounts = read_txn.open_table(ACCOUNTS).unwrap();
+    assert_eq!(accounts.len().unwrap(), 1);
+    assert!(accounts.get("b").unwrap().is_none());
+}
+
+#[test]
+fn nested_transactions() {
+    const OTHER: TableDefinition<u64, u64> = TableDefinition::new("other");
+
+    let tmpfile = create_tempfile();
+    let mut db = Database::create(tmpfile.path()).unwrap();
+    let write_txn = db.begin_write().unwrap();
+    {
+        let mut table = write_txn.open_table(U64_TABLE).unwrap();
+        for i in 0..1000 {
+            table.insert(i, i).unwrap();
+        }
+    }
+
+    // A failed nested transaction is rolled back, including changes to pages written earlier in
+    // the transaction and tables it created
+    let result: Result<(), TableError> = write_txn.nested(|txn| {
+        let mut table = txn.open_table(U64_TABLE).unwrap();
+        for i in 0..1000 {
+            table.insert(i, i + 1).unwrap();
+        }
+        table.insert(5000, 0).unwrap();
+        txn.open_table(OTHER).unwrap().insert(0, 0).unwrap();
+        Err(TableError::TableDoesNotExist("rollback".to_string()))
+    });
+    assert!(matches!(result, Err(TableError::TableDoesNotExist(_))));
+
+    // Nested transactions can themselves be nested
+    write_txn
+        .nested(|txn| {
+            txn.open_table(U64_TABLE).unwrap().remove(0).unwrap();
+            let result: Result<(), TableError> = txn.nested(|txn| {
+                txn.open_table(U64_TABLE).unwrap().remove(1).unwrap();
+                Err(TableError::TableDoesNotExist("rollback".to_string()))
+            });
+            assert!(result.is_err());
+            txn.nested(|txn| {
+                txn.open_table(U64_TABLE).unwrap().insert(2, 20)?;
+                Ok::<(), TableError>(())
+            })
+        })
+        .unwrap();
+
+    // Tables must be closed before a nested transaction begins
+    {
+        let _table = write_txn.open_table(U64_TABLE).unwrap();
+        assert!(matches!(
+            write_txn.nested(|_| Ok::<(), TableError>(())),
+            Err(TableError::TableAlreadyOpen(..))
+        ));
+    }
+
+    {
+        let table = write_txn.open_table(U64_TABLE).unwrap();
+        assert_eq!(table.len().unwrap(), 999);
+        assert!(table.get(0).unwrap().is_none());
+        assert_eq!(table.get(1).unwrap().unwrap().value(), 1);
+        assert_eq!(table.get(2).unwrap().unwrap().value(), 20);
+        assert_eq!(table.get(999).unwrap().unwrap().value(), 999);
+    }
+    assert!(
+        write_txn
+            .list_tables()
+            .unwrap()
+            .all(|x| x.name() != "other")
+    );
+    write_txn.commit().unwrap();
+
+    let read_txn = db.begin_read().unwrap();
+    let table = read_txn.open_table(U64_TABLE).unwrap();
+    assert_eq!(table.len().unwrap(), 999);
+    assert_eq!(table.get(2).unwrap().unwrap().value(), 20);
+    assert!(table.get(5000).unwrap().is_none());
+    drop(table);
+    drop(read_txn);
+    assert!(db.check_integrity().unwrap());
+}
+
+#[test]
+fn write_transaction_read_view() {
+    let tmpfile = create_tempfile();
+    let db = Database::create(tmpfile.path()).unwrap();
+    let write_txn = db.begin_write().unwrap();
+    write_txn
+        .open_table(U64_TABLE)
+        .unwrap()
+        .insert(0, 0)
+        .unwrap();
+    write_txn.commit().unwrap();
+
+    let mut write_txn = db.begin_write().unwrap();
+    {
+        let mut table = write_txn.open_table(U64_TABLE).unwrap();
+        for i in 1..1000 {
+            table.insert(i, i * 2).unwrap();
+        }
+    }
+
+    // Worker threads can read the uncommitted writes
+    let view = write_txn.read_view().unwrap();
+    let sums: Vec<u64> = std::thread::scope(|scope| {
+        let handles: Vec<_> = (0..4u64)
+            .map(|worker| {
+                let view = &view;
+                scope.spawn(move || {
+                    let table = view.open_table(U64_TABLE).unwrap();
+                    let mut sum = 0;
+                    for entry in table.range(worker * 250..(worker + 1) * 250).unwrap() {
+                        sum += entry.unwrap().1.value();
+                    }
+                    sum
+                })
+            })
+            .collect();
+        handles.into_iter().map(|x| x.join().unwrap()).collect()
+    });
+    assert_eq!(sums.iter().sum::<u64>(), 999 * 1000);
+
+    // Tables opened from the view can no longer be read once it's dropped
+    let table = view.open_table(U64_TABLE).unwrap();
+    drop(view);
+    assert!(matches!(
+        table.get(1),
+        Err(StorageError::ReadTransactionExpired)
+    ));
+    drop(table);
+
+    write_txn
+        .open_table(U64_TABLE)
+        .unwrap()
+        .insert(1, 1)
+        .unwrap();
+    let view = write_txn.read_view().unwrap();
+    assert_eq!(
+        view.open_table(U64_TABLE)
+            .unwrap()
+            .get(1)
+            .unwrap()
+            .unwrap()
+            .value(),
+        1
+    );
+    drop(view);
+    write_txn.commit().unwrap();
+
+    // The writes are visible to readers of the database once the transaction commits
+    let read_txn = db.begin_read().unwrap();
+    assert_eq!(read_txn.open_table(U64_TABLE).unwrap().len().unwrap(), 1000);
+}
@@ -2,21 +2,24 @@ use rand::RngExt;
 use rand::prelude::SliceRandom;
 use redb::backends::FileBackend;
 use redb::{
-    AccessGuard, Builder, CompactionError, Database, Durability, Key, MultimapRange,
-    MultimapTableDefinition, MultimapValue, Range, ReadableDatabase, ReadableTable,
-    ReadableTableMetadata, SetDurabilityError, StorageBackend, TableDefinition, TableStats,
-    TransactionError, Value, WriteTransaction,
+    AccessGuard, Builder, CommitCoordinator, CommitError, CompactionError, CorruptionPolicy,
+    Database, Durability, Key, MultimapRange, MultimapTableDefinition, MultimapValue, Range,
+    ReadOnlyDatabase, ReadableDatabase, ReadableTable, ReadableTableMetadata, SetDurabilityError,
+    StorageBackend, TableDefinition, TableStats, TransactionError, Value, WriteTransaction,
+};
+use redb::{
+    BackupError, DatabaseError, ExportError, PrepareError, ReadableMultimapTable, SavepointError,
+    StorageError, TableError,
 };
-use redb::{DatabaseError, ReadableMultimapTable, SavepointError, StorageError, TableError};
 use std::borrow::Borrow;
 use std::fs;
 use std::io::{ErrorKind, Write};
 use std::marker::PhantomData;
 use std::ops::RangeBounds;
-use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
 use std::sync::{Arc, Condvar, Mutex, RwLock, mpsc};
 use std::thread;
-use std::time::Duration;
+use std::time::{Duration, Instant};
 
 const ELEMENTS: usize = 100;
 const PAGE_REUSE_VALUE_LEN: usize = 512 * 1024;
@@ -38,6 +41,7 @@ fn create_tempfile() -> tempfile::NamedTempFile {
 
 #[derive(Debug)]
 struct BlockingSyncState {
+    syncs: AtomicU64,
     block_next: AtomicBool,
     blocked: Mutex<bool>,
     blocked_cvar: Condvar,
@@ -48,6 +52,7 @@ struct BlockingSyncState {
 impl BlockingSyncState {
     fn new() -> Self {
         Self {
+            syncs: AtomicU64::new(0),
             block_next: AtomicBool::new(false),
             blocked: Mutex::new(false),
             blocked_cvar: Condvar::new(),
@@ -80,6 +85,7 @@ impl BlockingSyncState {
     }
 
     fn maybe_block(&self) {
+        self.syncs.fetch_add(1, Ordering::SeqCst);
         if !self.block_next.swap(false, Ordering::SeqCst) {
             return;
         }
@@ -252,6 +258,167 @@ fn previous_io_error() {
     ));
 }
 
+#[derive(Debug)]
+struct FailingSyncBackend {
+    inner: SharedInMemoryBackend,
+    fail_flag: Arc<AtomicBool>,
+    fail_writes: bool,
+}
+
+impl StorageBackend for FailingSyncBackend {
+    fn len(&self) -> Result<u64, std::io::Error> {
+        self.inner.len()
+    }
+
+    fn read(&self, offset: u64, out: &mut [u8]) -> Result<(), std::io::Error> {
+        self.inner.read(offset, out)
+    }
+
+    fn set_len(&self, len: u64) -> Result<(), std::io::Error> {
+        self.inner.set_len(len)
+    }
+
+    fn sync_data(&self) -> Result<(), std::io::Error> {
+        if self.fail_flag.load(Ordering::SeqCst) {
+            Err(std::io::Error::from(ErrorKind::Other))
+        } else {
+            self.inner.sync_data()
+        }
+    }
+
+    fn write(&self, offset: u64, data: &[u8]) -> Result<(), std::io::Error> {
+        if self.fail_writes && self.fail_flag.load(Ordering::SeqCst) {
+            Err(std::io::Error::from(ErrorKind::Other))
+        } else {
+            self.inner.write(offset, data)
+        }
+    }
+}
+
+#[test]
+fn coordinated_commit_after_failed_decision() {
+    let coordinator_backend = SharedInMemoryBackend::default();
+    let participant_backends = [
+        SharedInMemoryBackend::default(),
+        SharedInMemoryBackend::default(),
+    ];
+    let fail_flag = Arc::new(AtomicBool::new(false));
+    let coordinator_db = Database::builder()
+        .create_with_backend(FailingSyncBackend {
+            inner: coordinator_backend.clone(),
+            fail_flag: fail_flag.clone(),
+            fail_writes: false,
+        })
+        .unwrap();
+    let participants: Vec<Database> = participant_backends
+        .iter()
+        .map(|backend| {
+            Database::builder()
+                .create_with_backend(backend.clone())
+                .unwrap()
+        })
+        .collect();
+    {
+        let mut databases = vec![&coordinator_db];
+        databases.extend(&participants);
+        let coordinator = CommitCoordinator::new(&databases).unwrap();
+        let transactions = coordinator.begin_write().unwrap();
+        for txn in &transactions {
+            txn.open_table(U64_TABLE).unwrap().insert(0, 1).unwrap();
+        }
+        // The participants are prepared, but the coordinator can't record the decision
+        fail_flag.store(true, Ordering::SeqCst);
+        assert!(coordinator.commit(transactions).is_err());
+    }
+    std::mem::forget(coordinator_db);
+    drop(participants);
+
+    let coordinator_db = Database::builder()
+        .create_with_backend(coordinator_backend)
+        .unwrap();
+    let participants: Vec<Database> = participant_backends
+        .iter()
+        .map(|backend| {
+            Database::builder()
+                .create_with_backend(backend.clone())
+                .unwrap()
+        })
+        .collect();
+    let mut databases = vec![&coordinator_db];
+    databases.extend(&participants);
+    CommitCoordinator::new(&databases).unwrap();
+
+    // Either every database has the write, or none of them do
+    let mut committed = vec![];
+    for db in &databases {
+        let txn = db.begin_write().unwrap();
+        assert_eq!(txn.list_prepared_transactions().unwrap().count(), 0);
+        let table = txn.open_table(U64_TABLE).unwrap();
+        committed.push(table.get(&0).unwrap().is_some());
+    }
+    assert!(
+        committed.iter().all(|x| *x == committed[0]),
+        "{committed:?}"
+    );
+}
+
+#[test]
+fn coordinated_commit_conflicting_with_later_write() {
+    let coordinator_backend = SharedInMemoryBackend::default();
+    let participant_backend = SharedInMemoryBackend::default();
+    let fail_flag = Arc::new(AtomicBool::new(false));
+    let coordinator_db = Database::builder()
+        .create_with_backend(coordinator_backend.clone())
+        .unwrap();
+    let participant_db = Database::builder()
+        .create_with_backend(FailingSyncBackend {
+            inner: participant_backend.clone(),
+            fail_flag: fail_flag.clone(),
+            fail_writes: true,
+        })
+        .unwrap();
+    {
+        let coordinator = CommitCoordinator::new(&[&coordinator_db, &participant_db]).unwrap();
+        // The decision is recorded, but the participant can't commit
+        let hook_flag = fail_flag.clone();
+        coordinator_db.set_commit_hook(move |_| hook_flag.store(true, Ordering::SeqCst));
+        let transactions = coordinator.begin_write().unwrap();
+        for txn in &transactions {
+            txn.open_table(U64_TABLE).unwrap().insert(0, 1).unwrap();
+        }
+        assert!(coordinator.commit(transactions).is_err());
+    }
+    drop(coordinator_db);
+    std::mem::forget(participant_db);
+
+    // The participant is modified before the coordinator recovers, so its prepared transaction
+    // can no longer be committed
+    let participant_db = Database::builder()
+        .create_with_backend(participant_backend)
+        .unwrap();
+    let txn = participant_db.begin_write().unwrap();
+    assert_eq!(txn.list_prepared_transactions().unwrap().count(), 1);
+    txn.open_table(U64_TABLE).unwrap().insert(1, 1).unwrap();
+    txn.commit().unwrap();
+
+    // Recovery fails, and keeps both the prepared transaction and the decision, so that it fails
+    // again on the next attempt rather than dropping the participant's side of the commit
+    let coordinator_db = Database::builder()
+        .create_with_backend(coordinator_backend)
+        .unwrap();
+    for _ in 0..2 {
+        assert!(matches!(
+            CommitCoordinator::new(&[&coordinator_db, &participant_db]),
+            Err(PrepareError::Conflict(_))
+        ));
+        let txn = participant_db.begin_write().unwrap();
+        assert_eq!(txn.list_prepared_transactions().unwrap().count(), 1);
+        let table = txn.open_table(U64_TABLE).unwrap();
+        assert!(table.get(&0).unwrap().is_none());
+        assert_eq!(table.get(&1).unwrap().unwrap().value(), 1);
+    }
+}
+
 #[test]
 fn mixed_durable_commit() {
     let tmpfile = create_tempfile();
@@ -362,6 +529,12 @@ fn page_reuse_after_persistent_savepoint_delete() {
     test_page_reuse_after_persistent_savepoint_delete(true);
 }
 
+#[test]
+fn page_reuse_with_persistent_savepoint() {
+    test_page_reuse_with_persistent_savepoint(false);
+    test_page_reuse_with_persistent_savepoint(true);
+}
+
 #[test]
 fn page_reuse_with_racing_reader() {
     test_page_reuse_with_racing_reader(false);
@@ -374,6 +547,53 @@ fn page_reuse_after_unclean_reopen() {
     test_page_reuse_after_unclean_reopen(true);
 }
 
+#[test]
+fn prepared_transaction_after_unclean_reopen() {
+    test_prepared_transaction_after_unclean_reopen(false);
+    test_prepared_transaction_after_unclean_reopen(true);
+}
+
+fn test_prepared_transaction_after_unclean_reopen(quick_repair: bool) {
+    let backend = SharedInMemoryBackend::default();
+    let db = Database::builder()
+        .create_with_backend(backend.clone())
+        .unwrap();
+
+    let txn = begin_page_reuse_write(&db, quick_repair);
+    {
+        let mut table = txn.open_table(U64_TABLE).unwrap();
+        for i in 0..1000 {
+            table.insert(i, i).unwrap();
+        }
+    }
+    txn.commit().unwrap();
+
+    let txn = begin_page_reuse_write(&db, quick_repair);
+    {
+        let mut table = txn.open_table(U64_TABLE).unwrap();
+        for i in 0..1000 {
+            table.insert(i, i + 1).unwrap();
+        }
+    }
+    let id = txn.prepare().unwrap();
+
+    std::mem::forget(db);
+
+    // The repair must keep the pages of the prepared transaction
+    let mut db = Database::builder().create_with_backend(backend).unwrap();
+    assert!(db.check_integrity().unwrap(), "quick_repair={quick_repair}");
+    let mut txn = begin_page_reuse_write(&db, quick_repair);
+    txn.commit_prepared(id).unwrap();
+    txn.commit().unwrap();
+    {
+        let txn = db.begin_read().unwrap();
+        let table = txn.open_table(U64_TABLE).unwrap();
+        for i in 0..1000 {
+            assert_eq!(i + 1, table.get(&i).unwrap().unwrap().value());
+        }
+    }
+}
+
 fn begin_page_reuse_write(db: &Database, quick_repair: bool) -> WriteTransaction {
     let mut txn = db.begin_write().unwrap();
     txn.set_quick_repair(quick_repair);
@@ -505,6 +725,65 @@ fn test_page_reuse_after_persistent_savepoint_delete(quick_repair: bool) {
     assert_eq!(PAGE_REUSE_VALUE_LEN, value.value().len());
 }
 
+fn overwrite_page_reuse_value(db: &Database, quick_repair: bool, byte: u8) {
+    let txn = begin_page_reuse_write(db, quick_repair);
+    {
+        let mut table = txn.open_table(SLICE_TABLE).unwrap();
+        let value = make_page_reuse_value(byte);
+        table
+            .insert([0u8; 16].as_slice(), value.as_slice())
+            .unwrap();
+    }
+    txn.commit().unwrap();
+}
+
+// Pages allocated after a savepoint, and freed before the next one, are not reachable from any
+// savepoint and must be reused while the savepoint is still alive
+fn test_page_reuse_with_persistent_savepoint(quick_repair: bool) {
+    let tmpfile = create_tempfile();
+    let key = [0u8; 16];
+
+    let mut db = Database::create(tmpfile.path()).unwrap();
+    overwrite_page_reuse_value(&db, quick_repair, 0);
+
+    let txn = begin_page_reuse_write(&db, quick_repair);
+    let savepoint = txn.persistent_savepoint().unwrap();
+    txn.commit().unwrap();
+
+    overwrite_page_reuse_value(&db, quick_repair, 1);
+    overwrite_page_reuse_value(&db, quick_repair, 2);
+
+    let txn = begin_page_reuse_write(&db, quick_repair);
+    let allocated_pages = txn.stats().unwrap().allocated_pages();
+    txn.abort().unwrap();
+
+    for i in 3..20 {
+        overwrite_page_reuse_value(&db, quick_repair, i);
+    }
+
+    let txn = begin_page_reuse_write(&db, quick_repair);
+    let allocated_after_overwrites = txn.stats().unwrap().allocated_pages();
+    assert!(
+        allocated_after_overwrites <= allocated_pages + MAX_PAGE_REUSE_METADATA_GROWTH,
+        "allocated_pages={allocated_pages}, allocated_after_overwrites={allocated_after_overwrites}, quick_repair={quick_repair}"
+    );
+    txn.abort().unwrap();
+
+    // The state at the savepoint must still be intact
+    let mut txn = begin_page_reuse_write(&db, quick_repair);
+    let savepoint = txn.get_persistent_savepoint(savepoint).unwrap();
+    txn.restore_savepoint(&savepoint).unwrap();
+    txn.commit().unwrap();
+    {
+        let txn = db.begin_read().unwrap();
+        let table = txn.open_table(SLICE_TABLE).unwrap();
+        let value = table.get(key.as_slice()).unwrap().unwrap();
+        assert_eq!(0, value.value()[0]);
+        assert_eq!(PAGE_REUSE_VALUE_LEN, value.value().len());
+    }
+    assert!(db.check_integrity().unwrap(), "quick_repair={quick_repair}");
+}
+
 fn test_page_reuse_after_unclean_reopen(quick_repair: bool) {
     let backend = SharedInMemoryBackend::default();
     let db = Database::builder()
@@ -539,6 +818,88 @@ fn test_page_reuse_after_unclean_reopen(quick_repair: bool) {
     assert!(db.check_integrity().unwrap(), "quick_repair={quick_repair}");
 }
 
+#[test]
+fn group_commit() {
+    let tmpfile = create_tempfile();
+    let sync_state = Arc::new(BlockingSyncState::new());
+    let backend = BlockingSyncBackend {
+        inner: FileBackend::new(tmpfile.into_file()).unwrap(),
+        state: sync_state.clone(),
+    };
+    let db = Arc::new(
+        Database::builder()
+            .set_group_commit_window(Duration::from_secs(60))
+            .create_with_backend(backend)
+            .unwrap(),
+    );
+
+    let syncs_before = sync_state.syncs.load(Ordering::SeqCst);
+    let txn = db.begin_write().unwrap();
+    txn.open_table(U64_TABLE).unwrap().insert(0, 0).unwrap();
+    txn.commit().unwrap();
+    let syncs_per_commit = sync_state.syncs.load(Ordering::SeqCst) - syncs_before;
+    assert!(syncs_per_commit > 0);
+
+    // Queue writers behind a commit which is blocked syncing the file
+    sync_state.block_next_sync();
+    let txn = db.begin_write().unwrap();
+    let blocked = thread::spawn(move || txn.commit().unwrap());
+    sync_state.wait_until_blocked();
+    let syncs_before = sync_state.syncs.load(Ordering::SeqCst);
+    let writers: Vec<_> = (1..=8)
+        .map(|i| {
+            let db = db.clone();
+            thread::spawn(move || {
+                let txn = db.begin_write().unwrap();
+                txn.open_table(U64_TABLE).unwrap().insert(i, i).unwrap();
+                txn.commit().unwrap();
+            })
+        })
+        .collect();
+    thread::sleep(Duration::from_millis(200));
+    sync_state.release();
+    blocked.join().unwrap();
+    for writer in writers {
+        writer.join().unwrap();
+    }
+    let syncs = sync_state.syncs.load(Ordering::SeqCst) - syncs_before;
+    assert!(syncs < 8 * syncs_per_commit, "{syncs} syncs");
+
+    let txn = db.begin_read().unwrap();
+    let table = txn.open_table(U64_TABLE).unwrap();
+    assert_eq!(table.len().unwrap(), 9);
+}
+
+#[test]
+fn group_commit_window_elapsed() {
+    let tmpfile = create_tempfile();
+    let sync_state = Arc::new(BlockingSyncState::new());
+    let backend = BlockingSyncBackend {
+        inner: FileBackend::new(tmpfile.into_file()).unwrap(),
+        state: sync_state.clone(),
+    };
+    let db = Arc::new(
+        Database::builder()
+            .set_group_commit_window(Duration::from_millis(50))
+            .create_with_backend(backend)
+            .unwrap(),
+    );
+
+    // The commit is deferred to the waiting writer, which aborts, so the committing thread must
+    // sync the file itself once the window has elapsed
+    let txn = db.begin_write().unwrap();
+    txn.open_table(U64_TABLE).unwrap().insert(0, 0).unwrap();
+    let db2 = db.clone();
+    let waiting = thread::spawn(move || db2.begin_write().unwrap().abort().unwrap());
+    thread::sleep(Duration::from_millis(200));
+    let syncs_before = sync_state.syncs.load(Ordering::SeqCst);
+    let start = Instant::now();
+    txn.commit().unwrap();
+    assert!(start.elapsed() >= Duration::from_millis(50));
+    assert!(sync_state.syncs.load(Ordering::SeqCst) > syncs_before);
+    waiting.join().unwrap();
+}
+
 fn test_page_reuse_with_racing_reader(quick_repair: bool) {
     let tmpfile = create_tempfile();
     let sync_state = Arc::new(BlockingSyncState::new());
@@ -2352,6 +2713,481 @@ fn persistent_savepoint() {
     assert_eq!(table.get(&0).unwrap().unwrap().value(), "hello");
 }
 
+#[test]
+fn persistent_savepoint_info() {
+    let tmpfile = create_tempfile();
+    let db = Database::create(tmpfile.path()).unwrap();
+
+    let txn = db.begin_write().unwrap();
+    assert!(txn.list_persistent_savepoint_info().unwrap().is_empty());
+    let before = std::time::SystemTime::now();
+    let first = txn.persistent_savepoint().unwrap();
+    txn.commit().unwrap();
+
+    let txn = db.begin_write().unwrap();
+    let second = txn
+        .persistent_savepoint_with_label("nightly backup")
+        .unwrap();
+    txn.commit().unwrap();
+    let txn = db.begin_write().unwrap();
+    let third = txn.persistent_savepoint().unwrap();
+    txn.commit().unwrap();
+
+    let txn = db.begin_write().unwrap();
+    assert!(txn.delete_persistent_savepoint(third).unwrap());
+    txn.commit().unwrap();
+
+    drop(db);
+    let db = Database::create(tmpfile.path()).unwrap();
+    let txn = db.begin_write().unwrap();
+    let info = txn.list_persistent_savepoint_info().unwrap();
+    assert_eq!(
+        info.iter().map(|x| x.id()).collect::<Vec<_>>(),
+        vec![first, second]
+    );
+    assert_eq!(info[0].label(), None);
+    assert_eq!(info[1].label(), Some("nightly backup"));
+    assert!(info[0].transaction_id() < info[1].transaction_id());
+    assert!(info[0].created().unwrap() >= before - Duration::from_secs(1));
+    assert!(info[0].created().unwrap() <= info[1].created().unwrap());
+    txn.abort().unwrap();
+}
+
+#[test]
+fn named_savepoint() {
+    let tmpfile = create_tempfile();
+    let db = Database::create(tmpfile.path()).unwrap();
+
+    let txn = db.begin_write().unwrap();
+    txn.open_table(U64_TABLE).unwrap().insert(0, 0).unwrap();
+    txn.commit().unwrap();
+
+    let txn = db.begin_write().unwrap();
+    let first = txn.named_savepoint("daily").unwrap();
+    txn.commit().unwrap();
+    let txn = db.begin_write().unwrap();
+    txn.named_savepoint("weekly").unwrap();
+    txn.commit().unwrap();
+
+    let txn = db.begin_write().unwrap();
+    txn.open_table(U64_TABLE).unwrap().insert(0, 1).unwrap();
+    txn.commit().unwrap();
+
+    // Overwriting a name replaces the previous savepoint
+    let txn = db.begin_write().unwrap();
+    let second = txn.named_savepoint("daily").unwrap();
+    txn.commit().unwrap();
+    let txn = db.begin_write().unwrap();
+    assert!(
+        !txn.list_persistent_savepoints()
+            .unwrap()
+            .any(|x| x == first)
+    );
+    assert!(matches!(
+        txn.get_persistent_savepoint(first),
+        Err(SavepointError::InvalidSavepoint)
+    ));
+    txn.abort().unwrap();
+
+    drop(db);
+    let db = Database::create(tmpfile.path()).unwrap();
+    let mut txn = db.begin_write().unwrap();
+    assert_eq!(
+        txn.list_named_savepoints()
+            .unwrap()
+            .map(|(name, _)| name)
+            .collect::<Vec<_>>(),
+        vec!["daily".to_string(), "weekly".to_string()]
+    );
+    assert_eq!(
+        txn.list_named_savepoints().unwrap().next(),
+        Some(("daily".to_string(), second))
+    );
+    txn.get_named_savepoint("daily").unwrap();
+    assert!(matches!(
+        txn.get_named_savepoint("monthly"),
+        Err(SavepointError::InvalidSavepoint)
+    ));
+    let savepoint = txn.get_named_savepoint("weekly").unwrap();
+    txn.restore_savepoint(&savepoint).unwrap();
+    txn.commit().unwrap();
+
+    // Restoring invalidates the newer savepoint, along with its name
+    let txn = db.begin_write().unwrap();
+    assert_eq!(
+        txn.list_named_savepoints()
+            .unwrap()
+            .map(|(name, _)| name)
+            .collect::<Vec<_>>(),
+        vec!["weekly".to_string()]
+    );
+    assert_eq!(
+        0,
+        txn.open_table(U64_TABLE)
+            .unwrap()
+            .get(0)
+            .unwrap()
+            .unwrap()
+            .value()
+    );
+    assert!(txn.delete_named_savepoint("weekly").unwrap());
+    assert!(!txn.delete_named_savepoint("weekly").unwrap());
+    assert!(txn.list_persistent_savepoints().unwrap().next().is_none());
+    txn.commit().unwrap();
+}
+
+#[test]
+fn persistent_savepoint_retention() {
+    let tmpfile = create_tempfile();
+    let db = Builder::new()
+        .set_max_persistent_savepoints(2)
+        .create(tmpfile.path())
+        .unwrap();
+
+    let mut ids = vec![];
+    for _ in 0..3 {
+        let txn = db.begin_write().unwrap();
+        ids.push(txn.persistent_savepoint().unwrap());
+        txn.commit().unwrap();
+    }
+    let txn = db.begin_write().unwrap();
+    assert_eq!(
+        txn.list_persistent_savepoints()
+            .unwrap()
+            .collect::<Vec<_>>(),
+        ids[1..]
+    );
+    txn.abort().unwrap();
+    drop(db);
+
+    let db = Builder::new()
+        .set_persistent_savepoint_max_age(Duration::from_millis(100))
+        .create(tmpfile.path())
+        .unwrap();
+    let txn = db.begin_write().unwrap();
+    txn.persistent_savepoint().unwrap();
+    txn.commit().unwrap();
+    thread::sleep(Duration::from_millis(200));
+
+    // Non-durable commits can't delete persistent savepoints
+    let mut txn = db.begin_write().unwrap();
+    txn.set_durability(Durability::None).unwrap();
+    txn.commit().unwrap();
+    let txn = db.begin_write().unwrap();
+    assert_eq!(txn.list_persistent_savepoints().unwrap().count(), 3);
+    txn.commit().unwrap();
+
+    let txn = db.begin_write().unwrap();
+    assert_eq!(txn.list_persistent_savepoints().unwrap().count(), 0);
+    let savepoint = txn.persistent_savepoint().unwrap();
+    txn.commit().unwrap();
+    let txn = db.begin_write().unwrap();
+    assert_eq!(
+        txn.list_persistent_savepoints()
+            .unwrap()
+            .collect::<Vec<_>>(),
+        vec![savepoint]
+    );
+    txn.abort().unwrap();
+}
+
+#[test]
+fn restore_table_from_savepoint() {
+    let tmpfile = create_tempfile();
+    let mut db = Database::create(tmpfile.path()).unwrap();
+    let table_a: TableDefinition<u64, u64> = TableDefinition::new("a");
+    let table_b: TableDefinition<u64, u64> = TableDefinition::new("b");
+    let table_c: TableDefinition<u64, u64> = TableDefinition::new("c");
+
+    let txn = db.begin_write().unwrap();
+    {
+        let mut a = txn.open_table(table_a).unwrap();
+        let mut b = txn.open_table(table_b).unwrap();
+        for i in 0..1000 {
+            a.insert(&i, &i).unwrap();
+            b.insert(&i, &i).unwrap();
+        }
+    }
+    txn.commit().unwrap();
+
+    let txn = db.begin_write().unwrap();
+    let savepoint = txn.ephemeral_savepoint().unwrap();
+    txn.commit().unwrap();
+
+    // Accidentally delete most of table "a", while also making changes which should be kept
+    let txn = db.begin_write().unwrap();
+    {
+        let mut a = txn.open_table(table_a).unwrap();
+        a.retain(|k, _| k % 100 == 0).unwrap();
+        let mut b = txn.open_table(table_b).unwrap();
+        b.insert(&5000, &5000).unwrap();
+        let mut c = txn.open_table(table_c).unwrap();
+        c.insert(&0, &0).unwrap();
+    }
+    txn.commit().unwrap();
+
+    let mut txn = db.begin_write().unwrap();
+    let newer_savepoint = txn.ephemeral_savepoint().unwrap();
+    {
+        let mut a = txn.open_table(table_a).unwrap();
+        a.insert(&0, &1).unwrap();
+    }
+    txn.restore_table(&savepoint, table_a).unwrap();
+    txn.restore_table(&savepoint, table_c).unwrap();
+    assert!(matches!(
+        txn.restore_savepoint(&newer_savepoint).unwrap_err(),
+        SavepointError::InvalidSavepoint
+    ));
+    txn.commit().unwrap();
+
+    let txn = db.begin_read().unwrap();
+    let a = txn.open_table(table_a).unwrap();
+    assert_eq!(a.len().unwrap(), 1000);
+    for i in 0..1000 {
+        assert_eq!(a.get(&i).unwrap().unwrap().value(), i);
+    }
+    let b = txn.open_table(table_b).unwrap();
+    assert_eq!(b.len().unwrap(), 1001);
+    assert_eq!(b.get(&5000).unwrap().unwrap().value(), 5000);
+    assert!(matches!(
+        txn.open_table(table_c).unwrap_err(),
+        TableError::TableDoesNotExist(_)
+    ));
+    drop(a);
+    drop(b);
+    drop(txn);
+
+    drop(savepoint);
+    drop(newer_savepoint);
+    for i in 0..3 {
+        let txn = db.begin_write().unwrap();
+        {
+            let mut a = txn.open_table(table_a).unwrap();
+            a.insert(&i, &(i + 1)).unwrap();
+        }
+        txn.commit().unwrap();
+    }
+    // Empty commits process the remaining freed pages, so that any leaked pages are detected
+    for _ in 0..3 {
+        db.begin_write().unwrap().commit().unwrap();
+    }
+    assert!(db.check_integrity().unwrap());
+}
+
+#[test]
+fn export_savepoint() {
+    let tmpfile = create_tempfile();
+    let db = Database::create(tmpfile.path()).unwrap();
+    let table: TableDefinition<u64, &str> = TableDefinition::new("x");
+    let multimap: MultimapTableDefinition<u64, u64> = MultimapTableDefinition::new("y");
+
+    let txn = db.begin_write().unwrap();
+    {
+        let mut t = txn.open_table(table).unwrap();
+        let mut m = txn.open_multimap_table(multimap).unwrap();
+        for i in 0..1000 {
+            t.insert(&i, "hello").unwrap();
+        }
+        // Enough values for the key to be stored in a subtree
+        for i in 0..1000 {
+            m.insert(&0, &i).unwrap();
+        }
+        m.insert(&1, &1).unwrap();
+    }
+    txn.commit().unwrap();
+
+    let txn = db.begin_write().unwrap();
+    let savepoint = txn.ephemeral_savepoint().unwrap();
+    txn.commit().unwrap();
+
+    let txn = db.begin_write().unwrap();
+    {
+        let mut t = txn.open_table(table).unwrap();
+        t.retain(|k, _| k % 2 == 0).unwrap();
+        let mut m = txn.open_multimap_table(multimap).unwrap();
+        m.remove_all(&0).unwrap();
+    }
+    txn.commit().unwrap();
+
+    // Other transactions may run while the snapshot is exported
+    let txn = db.begin_write().unwrap();
+    let snapshot_file = create_tempfile();
+    let snapshot_path = snapshot_file.path().to_path_buf();
+    drop(snapshot_file);
+    db.export_savepoint(&savepoint, &snapshot_path).unwrap();
+    txn.abort().unwrap();
+    assert!(matches!(
+        db.export_savepoint(&savepoint, &snapshot_path).unwrap_err(),
+        ExportError::Database(_) | ExportError::Storage(_)
+    ));
+
+    assert!(matches!(
+        Database::open(&snapshot_path).unwrap_err(),
+        DatabaseError::DatabaseSealed
+    ));
+    let snapshot = ReadOnlyDatabase::open(&snapshot_path).unwrap();
+    let txn = snapshot.begin_read().unwrap();
+    let t = txn.open_table(table).unwrap();
+    assert_eq!(t.len().unwrap(), 1000);
+    for i in 0..1000 {
+        assert_eq!(t.get(&i).unwrap().unwrap().value(), "hello");
+    }
+    let m = txn.open_multimap_table(multimap).unwrap();
+    assert_eq!(m.len().unwrap(), 1001);
+    let values: Vec<u64> = m.get(&0).unwrap().map(|x| x.unwrap().value()).collect();
+    assert_eq!(values, (0..1000).collect::<Vec<u64>>());
+    drop(t);
+    drop(m);
+    drop(txn);
+    drop(snapshot);
+    assert!(
+        fs::metadata(&snapshot_path).unwrap().len() < fs::metadata(tmpfile.path()).unwrap().len()
+    );
+    assert!(redb::verify_file(&snapshot_path).unwrap().checksums_valid());
+    fs::remove_file(&snapshot_path).unwrap();
+
+    let other_file = create_tempfile();
+    let other = Database::create(other_file.path()).unwrap();
+    assert!(matches!(
+        other
+            .export_savepoint(&savepoint, &snapshot_path)
+            .unwrap_err(),
+        ExportError::InvalidSavepoint
+    ));
+    assert!(!snapshot_path.exists());
+}
+
+#[test]
+fn incremental_backup() {
+    let tmpfile = create_tempfile();
+    let db = Database::create(tmpfile.path()).unwrap();
+    let table: TableDefinition<u64, &str> = TableDefinition::new("x");
+    let table2: TableDefinition<u64, u64> = TableDefinition::new("x2");
+    let multimap: MultimapTableDefinition<u64, u64> = MultimapTableDefinition::new("y");
+
+    let txn = db.begin_write().unwrap();
+    {
+        let mut t = txn.open_table(table).unwrap();
+        let mut t2 = txn.open_table(table2).unwrap();
+        let mut m = txn.open_multimap_table(multimap).unwrap();
+        for i in 0..1000 {
+            t.insert(&i, "hello world").unwrap();
+            t2.insert(&i, &i).unwrap();
+        }
+        // Enough values for the key to be stored in a subtree
+        for i in 0..1000 {
+            m.insert(&0, &i).unwrap();
+        }
+    }
+    txn.commit().unwrap();
+    let txn = db.begin_write().unwrap();
+    let savepoint1 = txn.ephemeral_savepoint().unwrap();
+    txn.commit().unwrap();
+
+    let mut full = vec![];
+    db.backup(&savepoint1, &mut full).unwrap();
+    let mirror = create_tempfile();
+    Database::apply_backup(mirror.path(), full.as_slice()).unwrap();
+    assert!(matches!(
+        Database::apply_backup(mirror.path(), full.as_slice()).unwrap_err(),
+        BackupError::IncompatibleBackup
+    ));
+
+    let txn = db.begin_write().unwrap();
+    {
+        let mut t = txn.open_table(table).unwrap();
+        t.insert(&2000, "world").unwrap();
+        t.remove(&3).unwrap();
+        let mut m = txn.open_multimap_table(multimap).unwrap();
+        m.insert(&0, &5000).unwrap();
+    }
+    txn.commit().unwrap();
+    let mut txn = db.begin_write().unwrap();
+    txn.set_durability(Durability::None).unwrap();
+    {
+        let mut t = txn.open_table(table).unwrap();
+        t.insert(&5000, "non-durable").unwrap();
+    }
+    txn.commit().unwrap();
+    // Pages allocated by the non-durable commit must be included, before they have been persisted
+    let txn = db.begin_write().unwrap();
+    let savepoint2 = txn.ephemeral_savepoint().unwrap();
+    txn.abort().unwrap();
+
+    assert!(matches!(
+        db.backup_incremental(&savepoint2, &savepoint1, vec![])
+            .unwrap_err(),
+        BackupError::InvalidSavepoint
+    ));
+    let mut incremental = vec![];
+    db.backup_incremental(&savepoint1, &savepoint2, &mut incremental)
+        .unwrap();
+    // The unmodified table is not included
+    assert!(incremental.len() < full.len() / 2);
+
+    let empty = create_tempfile();
+    assert!(matches!(
+        Database::apply_backup(empty.path(), incremental.as_slice()).unwrap_err(),
+        BackupError::IncompatibleBackup
+    ));
+    Database::apply_backup(mirror.path(), incremental.as_slice()).unwrap();
+    assert!(matches!(
+        Database::apply_backup(mirror.path(), incremental.as_slice()).unwrap_err(),
+        BackupError::IncompatibleBackup
+    ));
+    drop(savepoint1);
+
+    let txn = db.begin_write().unwrap();
+    txn.delete_table(table2).unwrap();
+    txn.commit().unwrap();
+    let txn = db.begin_write().unwrap();
+    let savepoint3 = txn.ephemeral_savepoint().unwrap();
+    txn.commit().unwrap();
+    let mut incremental = vec![];
+    db.backup_incremental(&savepoint2, &savepoint3, &mut incremental)
+        .unwrap();
+    Database::apply_backup(mirror.path(), incremental.as_slice()).unwrap();
+
+    let restored = Database::open(mirror.path()).unwrap();
+    let txn = restored.begin_read().unwrap();
+    let t = txn.open_table(table).unwrap();
+    assert_eq!(t.len().unwrap(), 1001);
+    assert!(t.get(&3).unwrap().is_none());
+    assert_eq!(t.get(&4).unwrap().unwrap().value(), "hello world");
+    assert_eq!(t.get(&2000).unwrap().unwrap().value(), "world");
+    assert_eq!(t.get(&5000).unwrap().unwrap().value(), "non-durable");
+    let m = txn.open_multimap_table(multimap).unwrap();
+    let values: Vec<u64> = m.get(&0).unwrap().map(|x| x.unwrap().value()).collect();
+    let mut expected: Vec<u64> = (0..1000).collect();
+    expected.push(5000);
+    assert_eq!(values, expected);
+    assert!(matches!(
+        txn.open_table(table2).unwrap_err(),
+        TableError::TableDoesNotExist(_)
+    ));
+    drop(t);
+    drop(m);
+    drop(txn);
+    for _ in 0..3 {
+        restored.begin_write().unwrap().commit().unwrap();
+    }
+    let mut restored = restored;
+    assert!(restored.check_integrity().unwrap());
+    drop(restored);
+
+    // The mirror was modified by opening it, so it can't be updated any further
+    let txn = db.begin_write().unwrap();
+    let savepoint4 = txn.ephemeral_savepoint().unwrap();
+    txn.commit().unwrap();
+    let mut incremental = vec![];
+    db.backup_incremental(&savepoint3, &savepoint4, &mut incremental)
+        .unwrap();
+    assert!(matches!(
+        Database::apply_backup(mirror.path(), incremental.as_slice()).unwrap_err(),
+        BackupError::IncompatibleBackup
+    ));
+}
+
 #[test]
 fn savepoint() {
     let tmpfile = create_tempfile();
@@ -2594,6 +3430,208 @@ fn compact_does_not_grow_file() {
     );
 }
 
+#[test]
+fn seal() {
+    let tmpfile = create_tempfile();
+    let definition: TableDefinition<u64, &[u8]> = TableDefinition::new("x");
+
+    let mut keys: Vec<u64> = (0..2000).collect();
+    keys.shuffle(&mut rand::rng());
+    {
+        let db = Database::create(tmpfile.path()).unwrap();
+        let txn = db.begin_write().unwrap();
+        {
+            let mut table = txn.open_table(definition).unwrap();
+            for i in &keys {
+                table.insert(i, [0u8; 600].as_slice()).unwrap();
+            }
+        }
+        txn.commit().unwrap();
+
+        let txn = db.begin_write().unwrap();
+        txn.persistent_savepoint().unwrap();
+        txn.commit().unwrap();
+
+        let txn = db.begin_write().unwrap();
+        {
+            let mut table = txn.open_table(definition).unwrap();
+            for i in 0..1000 {
+                table.remove(&i).unwrap();
+            }
+        }
+        txn.commit().unwrap();
+    }
+    let file_size = tmpfile.as_file().metadata().unwrap().len();
+
+    Database::seal(tmpfile.path()).unwrap();
+    let sealed_size = tmpfile.as_file().metadata().unwrap().len();
+    assert!(sealed_size < file_size);
+
+    assert!(matches!(
+        Database::open(tmpfile.path()),
+        Err(DatabaseError::DatabaseSealed)
+    ));
+    assert!(matches!(
+        Database::create(tmpfile.path()),
+        Err(DatabaseError::DatabaseSealed)
+    ));
+    assert!(matches!(
+        Database::seal(tmpfile.path()),
+        Err(DatabaseError::DatabaseSealed)
+    ));
+
+    let db = ReadOnlyDatabase::open(tmpfile.path()).unwrap();
+    let txn = db.begin_read().unwrap();
+    let table = txn.open_table(definition).unwrap();
+    assert_eq!(table.len().unwrap(), 1000);
+    for (expected, entry) in (1000..2000).zip(table.iter().unwrap()) {
+        let (key, value) = entry.unwrap();
+        assert_eq!(key.value(), expected);
+        assert_eq!(value.value(), [0u8; 600].as_slice());
+    }
+}
+
+#[test]
+fn seal_with_prepared_transaction() {
+    let tmpfile = create_tempfile();
+    let db = Database::create(tmpfile.path()).unwrap();
+    let txn = db.begin_write().unwrap();
+    txn.open_table(U64_TABLE).unwrap().insert(0, 0).unwrap();
+    let id = txn.prepare().unwrap();
+    drop(db);
+
+    assert!(matches!(
+        Database::seal(tmpfile.path()),
+        Err(DatabaseError::TransactionInProgress)
+    ));
+
+    // The file was not modified, and can be sealed once the transaction is resolved
+    let db = Database::open(tmpfile.path()).unwrap();
+    let mut txn = db.begin_write().unwrap();
+    txn.commit_prepared(id).unwrap();
+    txn.commit().unwrap();
+    drop(db);
+    Database::seal(tmpfile.path()).unwrap();
+    let db = ReadOnlyDatabase::open(tmpfile.path()).unwrap();
+    let txn = db.begin_read().unwrap();
+    let table = txn.open_table(U64_TABLE).unwrap();
+    assert_eq!(table.get(0).unwrap().unwrap().value(), 0);
+}
+
+#[test]
+fn verify_file() {
+    let tmpfile = create_tempfile();
+    let definition: TableDefinition<u64, &[u8]> = TableDefinition::new("x");
+
+    let db = Database::create(tmpfile.path()).unwrap();
+    let txn = db.begin_write().unwrap();
+    {
+        let mut table = txn.open_table(definition).unwrap();
+        for i in 0..1000 {
+            table.insert(&i, [0xAB; 600].as_slice()).unwrap();
+        }
+    }
+    txn.commit().unwrap();
+
+    // The file can be verified while the database is still open
+    let report = redb::verify_file(tmpfile.path()).unwrap();
+    assert!(report.is_valid());
+    assert!(report.recovery_required());
+    assert!(!report.rolled_back());
+    assert_eq!(report.allocator_state_valid(), None);
+
+    drop(db);
+    let report = redb::verify_file(tmpfile.path()).unwrap();
+    assert!(report.is_valid());
+    assert!(!report.recovery_required());
+    assert!(report.primary_slot_valid());
+    assert!(report.checksums_valid());
+    assert_eq!(report.allocator_state_valid(), Some(true));
+
+    // Corrupt one of the values
+    let mut data = fs::read(tmpfile.path()).unwrap();
+    let offset = data
+        .windows(600)
+        .position(|window| window.iter().all(|x| *x == 0xAB))
+        .unwrap();
+    data[offset + 300] = 0;
+    fs::write(tmpfile.path(), &data).unwrap();
+    let report = redb::verify_file(tmpfile.path()).unwrap();
+    assert!(!report.is_valid());
+    assert!(!report.checksums_valid());
+
+    // Verification never modifies the file
+    assert_eq!(fs::read(tmpfile.path()).unwrap(), data);
+}
+
+#[test]
+fn corruption_policy() {
+    let corrupted: TableDefinition<u64, &[u8]> = TableDefinition::new("corrupted");
+    let healthy: TableDefinition<u64, u64> = TableDefinition::new("healthy");
+
+    let create = |policy: CorruptionPolicy| {
+        let backend = SharedInMemoryBackend::default();
+        // Disable the cache, so that pages are read from the backend after they're corrupted
+        let db = Builder::new()
+            .set_cache_size(0)
+            .set_corruption_policy(policy)
+            .create_with_backend(backend.clone())
+            .unwrap();
+        let txn = db.begin_write().unwrap();
+        {
+            let mut table = txn.open_table(corrupted).unwrap();
+            table.insert(&0, [0xAB; 600].as_slice()).unwrap();
+            let mut table = txn.open_table(healthy).unwrap();
+            table.insert(&0, &1).unwrap();
+        }
+        txn.commit().unwrap();
+
+        // Overwrite the type of the leaf page which holds the value
+        let mut data = backend.inner.write().unwrap();
+        let offset = data
+            .windows(600)
+            .position(|window| window.iter().all(|x| *x == 0xAB))
+            .unwrap();
+        data[offset - offset % 4096] = 0xFF;
+        drop(data);
+        db
+    };
+
+    let db = create(CorruptionPolicy::ReturnError);
+    let txn = db.begin_read().unwrap();
+    let table = txn.open_table(corrupted).unwrap();
+    assert!(matches!(table.get(&0), Err(StorageError::Corrupted(_))));
+    assert!(matches!(table.first(), Err(StorageError::Corrupted(_))));
+    let result = table
+        .iter()
+        .and_then(|mut iter| iter.next().unwrap().map(|_| ()));
+    assert!(matches!(result, Err(StorageError::Corrupted(_))));
+    // Other tables can still be read
+    let table = txn.open_table(healthy).unwrap();
+    assert_eq!(table.get(&0).unwrap().unwrap().value(), 1);
+
+    // A write which fails part way through poisons its transaction
+    let txn = db.begin_write().unwrap();
+    {
+        let mut table = txn.open_table(corrupted).unwrap();
+        let result = table.insert(&0, [1].as_slice()).map(|_| ());
+        assert!(matches!(result, Err(StorageError::Corrupted(_))));
+    }
+    assert!(matches!(
+        txn.commit(),
+        Err(CommitError::TransactionPoisoned)
+    ));
+    let txn = db.begin_write().unwrap();
+    txn.open_table(healthy).unwrap().insert(&0, &2).unwrap();
+    txn.commit().unwrap();
+
+    let db = create(CorruptionPolicy::Panic);
+    let txn = db.begin_read().unwrap();
+    let table = txn.open_table(corrupted).unwrap();
+    let result = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| table.get(&0)));
+    assert!(result.is_err());
+}
+
 fn require_send<T: Send>(_: &T) {}
 fn require_sync<T: Sync + Send>(_: &T) {}
 
@@ -1,7 +1,9 @@
 use redb::{
-    Database, MultimapTableDefinition, ReadableDatabase, ReadableMultimapTable,
-    ReadableTableMetadata, TableError,
+    Database, IndexedMultimapTable, MultimapTable, MultimapTableDefinition, ReadableDatabase,
+    ReadableMultimapTable, ReadableTableMetadata, TableError, ValueLimitError, ValueLimitPolicy,
 };
+use std::collections::BTreeSet;
+use std::ops::Bound;
 
 const STR_TABLE: MultimapTableDefinition<&str, &str> = MultimapTableDefinition::new("str_to_str");
 const SLICE_U64_TABLE: MultimapTableDefinition<&[u8], u64> =
@@ -52,6 +54,498 @@ fn len() {
     assert_eq!(untyped_table.len().unwrap(), 3);
 }
 
+#[test]
+fn value_len() {
+    let tmpfile = create_tempfile();
+    let db = Database::create(tmpfile.path()).unwrap();
+    let write_txn = db.begin_write().unwrap();
+    {
+        let mut table = write_txn.open_multimap_table(U64_TABLE).unwrap();
+        // Key 0 is promoted to a subtree, key 1 stays inline
+        for v in 0..1000u64 {
+            table.insert(&0u64, &v).unwrap();
+        }
+        table.insert(&1u64, &100u64).unwrap();
+        table.insert(&1u64, &101u64).unwrap();
+        assert_eq!(table.value_len(&0u64).unwrap(), 1000);
+        assert_eq!(table.value_len(&1u64).unwrap(), 2);
+        assert_eq!(table.value_len(&2u64).unwrap(), 0);
+        table.remove(&0u64, &500u64).unwrap();
+        table.remove(&1u64, &100u64).unwrap();
+        assert_eq!(table.value_len(&0u64).unwrap(), 999);
+        assert_eq!(table.value_len(&1u64).unwrap(), 1);
+    }
+    write_txn.commit().unwrap();
+
+    let read_txn = db.begin_read().unwrap();
+    let table = read_txn.open_multimap_table(U64_TABLE).unwrap();
+    assert_eq!(table.value_len(&0u64).unwrap(), 999);
+    assert_eq!(table.value_len(&1u64).unwrap(), 1);
+    assert_eq!(table.value_len(&2u64).unwrap(), 0);
+}
+
+#[test]
+fn get_range() {
+    let tmpfile = create_tempfile();
+    let db = Database::create(tmpfile.path()).unwrap();
+    let write_txn = db.begin_write().unwrap();
+    {
+        let mut table = write_txn.open_multimap_table(U64_TABLE).unwrap();
+        // Key 0 is promoted to a subtree, key 1 stays inline
+        for v in 0..1000u64 {
+            table.insert(&0u64, &v).unwrap();
+        }
+        for v in [10u64, 20, 30, 40] {
+            table.insert(&1u64, &v).unwrap();
+        }
+        let values: Vec<u64> = table
+            .get_range(&1u64, 20u64..40)
+            .unwrap()
+            .map(|v| v.unwrap().value())
+            .collect();
+        assert_eq!(values, vec![20, 30]);
+    }
+    write_txn.commit().unwrap();
+
+    let read_txn = db.begin_read().unwrap();
+    let table = read_txn.open_multimap_table(U64_TABLE).unwrap();
+    let values: Vec<u64> = table
+        .get_range(&0u64, 995u64..)
+        .unwrap()
+        .map(|v| v.unwrap().value())
+        .collect();
+    assert_eq!(values, vec![995, 996, 997, 998, 999]);
+    let values: Vec<u64> = table
+        .get_range(&0u64, ..=2u64)
+        .unwrap()
+        .rev()
+        .map(|v| v.unwrap().value())
+        .collect();
+    assert_eq!(values, vec![2, 1, 0]);
+
+    let iter = table.get_range(&1u64, 15u64..=30).unwrap();
+    assert_eq!(iter.len(), 2);
+    let values: Vec<u64> = iter.map(|v| v.unwrap().value()).collect();
+    assert_eq!(values, vec![20, 30]);
+    let values: Vec<u64> = table
+        .get_range(&1u64, (Bound::Excluded(10u64), Bound::Excluded(40u64)))
+        .unwrap()
+        .rev()
+        .map(|v| v.unwrap().value())
+        .collect();
+    assert_eq!(values, vec![30, 20]);
+    assert!(table.get_range(&1u64, 50u64..).unwrap().next().is_none());
+    assert!(table.get_range(&1u64, 25u64..25).unwrap().next().is_none());
+    assert!(table.get_range(&2u64, 0u64..).unwrap().next().is_none());
+}
+
+#[test]
+fn remove_range() {
+    let tmpfile = create_tempfile();
+    let db = Database::create(tmpfile.path()).unwrap();
+    let write_txn = db.begin_write().unwrap();
+    {
+        let mut table = write_txn.open_multimap_table(U64_TABLE).unwrap();
+        // Key 0 is promoted to a subtree, key 1 stays inline
+        for v in 0..1000u64 {
+            table.insert(&0u64, &v).unwrap();
+        }
+        for v in [10u64, 20, 30, 40] {
+            table.insert(&1u64, &v).unwrap();
+        }
+        assert_eq!(table.remove_range(&1u64, 15u64..=30).unwrap(), 2);
+        assert_eq!(table.remove_range(&1u64, 50u64..).unwrap(), 0);
+        assert_eq!(table.remove_range(&2u64, 0u64..).unwrap(), 0);
+        let values: Vec<u64> = table
+            .get(&1u64)
+            .unwrap()
+            .map(|v| v.unwrap().value())
+            .collect();
+        assert_eq!(values, vec![10, 40]);
+        assert_eq!(table.len().unwrap(), 1002);
+    }
+    write_txn.commit().unwrap();
+
+    let write_txn = db.begin_write().unwrap();
+    {
+        let mut table = write_txn.open_multimap_table(U64_TABLE).unwrap();
+        assert_eq!(table.remove_range(&0u64, 100u64..900).unwrap(), 800);
+        assert_eq!(table.value_len(&0u64).unwrap(), 200);
+        let values: Vec<u64> = table
+            .get_range(&0u64, 98u64..902)
+            .unwrap()
+            .map(|v| v.unwrap().value())
+            .collect();
+        assert_eq!(values, vec![98, 99, 900, 901]);
+        // Small enough to be moved back inline
+        assert_eq!(table.remove_range(&0u64, 5u64..).unwrap(), 195);
+        assert_eq!(table.value_len(&0u64).unwrap(), 5);
+        assert_eq!(table.remove_range::<u64>(&1u64, ..).unwrap(), 2);
+        assert_eq!(table.value_len(&1u64).unwrap(), 0);
+        assert_eq!(table.len().unwrap(), 5);
+    }
+    write_txn.commit().unwrap();
+
+    let read_txn = db.begin_read().unwrap();
+    let table = read_txn.open_multimap_table(U64_TABLE).unwrap();
+    assert_eq!(table.len().unwrap(), 5);
+    let values: Vec<u64> = table
+        .get(&0u64)
+        .unwrap()
+        .map(|v| v.unwrap().value())
+        .collect();
+    assert_eq!(values, vec![0, 1, 2, 3, 4]);
+    assert!(table.get(&1u64).unwrap().next().is_none());
+}
+
+#[test]
+fn extract_if() {
+    let tmpfile = create_tempfile();
+    let db = Database::create(tmpfile.path()).unwrap();
+    let write_txn = db.begin_write().unwrap();
+    {
+        let mut table = write_txn.open_multimap_table(U64_TABLE).unwrap();
+        // Key 0 is promoted to a subtree, the others stay inline
+        for v in 0..1000u64 {
+            table.insert(&0u64, &v).unwrap();
+        }
+        for k in 1..5u64 {
+            for v in 0..4u64 {
+                table.insert(&k, &v).unwrap();
+            }
+        }
+
+        let extracted: Vec<(u64, u64)> = table
+            .extract_if(|k, v| (k + v) % 2 == 1)
+            .unwrap()
+            .map(|e| {
+                let (k, v) = e.unwrap();
+                (k.value(), v.value())
+            })
+            .filter(|(k, _)| *k != 0)
+            .collect();
+        assert_eq!(
+            extracted,
+            vec![
+                (1, 0),
+                (1, 2),
+                (2, 1),
+                (2, 3),
+                (3, 0),
+                (3, 2),
+                (4, 1),
+                (4, 3)
+            ]
+        );
+        assert_eq!(table.len().unwrap(), 500 + 8);
+        assert_eq!(table.value_len(&0u64).unwrap(), 500);
+
+        // Only the entries read from the iterator are removed
+        let first = table
+            .extract_from_if(2u64.., |_, _| true)
+            .unwrap()
+            .next()
+            .unwrap()
+            .unwrap();
+        assert_eq!((first.0.value(), first.1.value()), (2, 0));
+        assert_eq!(table.len().unwrap(), 500 + 7);
+    }
+    write_txn.commit().unwrap();
+
+    let write_txn = db.begin_write().unwrap();
+    {
+        let mut table = write_txn.open_multimap_table(U64_TABLE).unwrap();
+        table.retain_in(1u64..4, |k, v| k != 3 && v > 0).unwrap();
+        let pairs: Vec<(u64, u64)> = table
+            .range(1u64..)
+            .unwrap()
+            .flat_map(|e| {
+                let (k, values) = e.unwrap();
+                let k = k.value();
+                values.map(move |v| (k, v.unwrap().value()))
+            })
+            .collect();
+        assert_eq!(pairs, vec![(1, 1), (1, 3), (2, 2), (4, 0), (4, 2)]);
+        table.retain(|_, v| v < 10).unwrap();
+        assert_eq!(table.value_len(&0u64).unwrap(), 5);
+        assert_eq!(table.len().unwrap(), 5 + 5);
+    }
+    write_txn.commit().unwrap();
+
+    let read_txn = db.begin_read().unwrap();
+    let table = read_txn.open_multimap_table(U64_TABLE).unwrap();
+    assert_eq!(table.len().unwrap(), 10);
+    let values: Vec<u64> = table
+        .get(&0u64)
+        .unwrap()
+        .map(|v| v.unwrap().value())
+        .collect();
+    assert_eq!(values, vec![0, 2, 4, 6, 8]);
+}
+
+#[test]
+fn multimap_stats() {
+    let tmpfile = create_tempfile();
+    let db = Database::create(tmpfile.path()).unwrap();
+    let write_txn = db.begin_write().unwrap();
+    {
+        let mut table = write_txn.open_multimap_table(U64_TABLE).unwrap();
+        let stats = table.multimap_stats().unwrap();
+        assert_eq!(stats.keys(), 0);
+        assert_eq!(stats.average_values_per_key(), 0.0);
+
+        // Key 0 is promoted to a subtree, the others stay inline
+        for v in 0..1000u64 {
+            table.insert(&0u64, &v).unwrap();
+        }
+        for k in 1..4u64 {
+            for v in 0..k {
+                table.insert(&k, &v).unwrap();
+            }
+        }
+    }
+    write_txn.commit().unwrap();
+
+    let read_txn = db.begin_read().unwrap();
+    let table = read_txn.open_multimap_table(U64_TABLE).unwrap();
+    let stats = table.multimap_stats().unwrap();
+    assert_eq!(stats.keys(), 4);
+    assert_eq!(stats.values(), 1006);
+    assert_eq!(stats.values(), table.len().unwrap());
+    assert_eq!(stats.average_values_per_key(), 1006.0 / 4.0);
+    assert_eq!(stats.max_values_per_key(), 1000);
+    assert_eq!(stats.inline_keys(), 3);
+    assert_eq!(stats.inline_values(), 6);
+    assert_eq!(stats.inline_stored_bytes(), 6 * 8);
+    assert_eq!(stats.subtree_keys(), 1);
+    assert_eq!(stats.subtree_values(), 1000);
+    assert_eq!(stats.subtree_stored_bytes(), 1000 * 8);
+    assert!(stats.subtree_pages() > 1);
+}
+
+#[test]
+fn insert_many() {
+    let tmpfile = create_tempfile();
+    let db = Database::create(tmpfile.path()).unwrap();
+    let mut expected = BTreeSet::new();
+    let write_txn = db.begin_write().unwrap();
+    {
+        let mut table = write_txn.open_multimap_table(U64_TABLE).unwrap();
+        assert_eq!(table.insert_many(&0u64, [5u64, 3, 5, 1]).unwrap(), 3);
+        assert_eq!(table.insert_many(&0u64, Vec::<u64>::new()).unwrap(), 0);
+        // Overlaps the inline values
+        assert_eq!(table.insert_many(&0u64, [0u64, 1, 2, 6]).unwrap(), 3);
+        expected.extend([0u64, 1, 2, 3, 5, 6]);
+        // Too many values to stay inline
+        assert_eq!(
+            table.insert_many(&0u64, (100..1100u64).rev()).unwrap(),
+            1000
+        );
+        expected.extend(100..1100u64);
+        assert_eq!(table.value_len(&0u64).unwrap(), 1006);
+        table.insert(&1u64, &7u64).unwrap();
+        assert_eq!(table.len().unwrap(), 1007);
+    }
+    write_txn.commit().unwrap();
+
+    let write_txn = db.begin_write().unwrap();
+    {
+        let mut table = write_txn.open_multimap_table(U64_TABLE).unwrap();
+        // Values before, within, and after the existing subtree
+        let values: Vec<u64> = (50..60).chain(500..520).chain(1000..1500).collect();
+        assert_eq!(table.insert_many(&0u64, &values).unwrap(), 10 + 400);
+        expected.extend(values);
+        assert_eq!(table.len().unwrap(), 1417);
+    }
+    write_txn.commit().unwrap();
+
+    let read_txn = db.begin_read().unwrap();
+    let table = read_txn.open_multimap_table(U64_TABLE).unwrap();
+    let values: Vec<u64> = table
+        .get(&0u64)
+        .unwrap()
+        .map(|v| v.unwrap().value())
+        .collect();
+    assert_eq!(values, expected.into_iter().collect::<Vec<_>>());
+    assert_eq!(table.value_len(&1u64).unwrap(), 1);
+}
+
+#[test]
+fn indexed_multimap_table() {
+    let tmpfile = create_tempfile();
+    let db = Database::create(tmpfile.path()).unwrap();
+    let index: MultimapTableDefinition<u64, u64> = MultimapTableDefinition::new("u64_index");
+
+    let write_txn = db.begin_write().unwrap();
+    {
+        // Pairs written before the index existed
+        let mut table = write_txn.open_multimap_table(U64_TABLE).unwrap();
+        table.insert(&1u64, &10u64).unwrap();
+        table.insert(&2u64, &10u64).unwrap();
+    }
+    write_txn.commit().unwrap();
+
+    let write_txn = db.begin_write().unwrap();
+    {
+        let mut table = IndexedMultimapTable::open(&write_txn, U64_TABLE, index).unwrap();
+        let keys = |table: &IndexedMultimapTable<u64, u64>, value: u64| -> Vec<u64> {
+            table
+                .get_keys(&value)
+                .unwrap()
+                .map(|k| k.unwrap().value())
+                .collect()
+        };
+        assert_eq!(keys(&table, 10), vec![1, 2]);
+
+        assert!(!table.insert(&1u64, &20u64).unwrap());
+        assert!(table.insert(&1u64, &20u64).unwrap());
+        table.insert(&3u64, &20u64).unwrap();
+        assert_eq!(keys(&table, 20), vec![1, 3]);
+
+        assert!(table.remove(&3u64, &20u64).unwrap());
+        assert!(!table.remove(&3u64, &20u64).unwrap());
+        assert_eq!(keys(&table, 20), vec![1]);
+
+        assert_eq!(table.remove_all(&1u64).unwrap(), 2);
+        assert_eq!(keys(&table, 10), vec![2]);
+        assert!(keys(&table, 20).is_empty());
+        assert_eq!(table.table().len().unwrap(), 1);
+        assert_eq!(table.index().len().unwrap(), 1);
+    }
+    write_txn.commit().unwrap();
+
+    let read_txn = db.begin_read().unwrap();
+    let index = read_txn.open_multimap_table(index).unwrap();
+    let keys: Vec<u64> = index
+        .get(&10u64)
+        .unwrap()
+        .map(|k| k.unwrap().value())
+        .collect();
+    assert_eq!(keys, vec![2]);
+    assert_eq!(index.len().unwrap(), 1);
+}
+
+#[test]
+fn keys() {
+    let tmpfile = create_tempfile();
+    let db = Database::create(tmpfile.path()).unwrap();
+    let write_txn = db.begin_write().unwrap();
+    {
+        let mut table = write_txn.open_multimap_table(U64_TABLE).unwrap();
+        // Key 0 is promoted to a subtree, the others stay inline
+        for v in 0..1000u64 {
+            table.insert(&0u64, &v).unwrap();
+        }
+        for k in 1..10u64 {
+            table.insert(&k, &k).unwrap();
+            table.insert(&k, &(k + 1)).unwrap();
+        }
+        let keys: Vec<u64> = table
+            .keys(5u64..)
+            .unwrap()
+            .map(|k| k.unwrap().value())
+            .collect();
+        assert_eq!(keys, vec![5, 6, 7, 8, 9]);
+    }
+    write_txn.commit().unwrap();
+
+    let read_txn = db.begin_read().unwrap();
+    let table = read_txn.open_multimap_table(U64_TABLE).unwrap();
+    let keys: Vec<u64> = table
+        .keys(..3u64)
+        .unwrap()
+        .map(|k| k.unwrap().value())
+        .collect();
+    assert_eq!(keys, vec![0, 1, 2]);
+    let keys: Vec<u64> = table
+        .keys::<u64>(..)
+        .unwrap()
+        .rev()
+        .map(|k| k.unwrap().value())
+        .collect();
+    assert_eq!(keys, (0..10u64).rev().collect::<Vec<_>>());
+}
+
+#[test]
+fn max_values_per_key() {
+    let tmpfile = create_tempfile();
+    let db = Database::create(tmpfile.path()).unwrap();
+    let reject = MultimapTableDefinition::<u64, u64>::new("reject")
+        .with_max_values_per_key(2, ValueLimitPolicy::Reject);
+    let smallest = MultimapTableDefinition::<u64, u64>::new("smallest")
+        .with_max_values_per_key(2, ValueLimitPolicy::EvictSmallest);
+    let largest = MultimapTableDefinition::<u64, u64>::new("largest")
+        .with_max_values_per_key(2, ValueLimitPolicy::EvictLargest);
+    let values = |table: &MultimapTable<u64, u64>, key: u64| -> Vec<u64> {
+        table
+            .get(&key)
+            .unwrap()
+            .map(|v| v.unwrap().value())
+            .collect()
+    };
+
+    let write_txn = db.begin_write().unwrap();
+    {
+        let mut table = write_txn.open_multimap_table(reject).unwrap();
+        table.insert(&0u64, &1u64).unwrap();
+        table.insert(&0u64, &2u64).unwrap();
+        assert!(table.insert(&0u64, &2u64).unwrap());
+        assert!(matches!(
+            table.insert(&0u64, &3u64).unwrap_err(),
+            ValueLimitError::ValueLimitExceeded(2)
+        ));
+        table.insert(&1u64, &3u64).unwrap();
+        assert_eq!(values(&table, 0), vec![1, 2]);
+        assert_eq!(table.len().unwrap(), 3);
+
+        let mut table = write_txn.open_multimap_table(smallest).unwrap();
+        assert_eq!(table.insert_many(&0u64, [5u64, 3, 4]).unwrap(), 3);
+        assert_eq!(values(&table, 0), vec![4, 5]);
+        assert!(!table.insert(&0u64, &1u64).unwrap());
+        assert_eq!(values(&table, 0), vec![4, 5]);
+        assert_eq!(table.len().unwrap(), 2);
+
+        let mut table = write_txn.open_multimap_table(largest).unwrap();
+        for v in [5u64, 3, 4] {
+            table.insert(&0u64, &v).unwrap();
+        }
+        assert_eq!(values(&table, 0), vec![3, 4]);
+        assert_eq!(table.len().unwrap(), 2);
+    }
+    write_txn.commit().unwrap();
+
+    // The limit is not stored in the database
+    let write_txn = db.begin_write().unwrap();
+    {
+        let mut table = write_txn
+            .open_multimap_table(MultimapTableDefinition::<u64, u64>::new("reject"))
+            .unwrap();
+        table.insert(&0u64, &3u64).unwrap();
+        assert_eq!(values(&table, 0), vec![1, 2, 3]);
+    }
+    {
+        // The inverted index stays in sync when values are evicted
+        let index: MultimapTableDefinition<u64, u64> =
+            MultimapTableDefinition::new("smallest_index");
+        let mut table = IndexedMultimapTable::open(&write_txn, smallest, index).unwrap();
+        table.insert(&0u64, &6u64).unwrap();
+        assert!(table.get_keys(&4u64).unwrap().next().is_none());
+        assert_eq!(table.get_keys(&6u64).unwrap().len(), 1);
+        assert_eq!(table.index().len().unwrap(), 2);
+    }
+    {
+        // A limit on the index is ignored, so that it contains every pair
+        let index: MultimapTableDefinition<u64, u64> =
+            MultimapTableDefinition::new("limited_index")
+                .with_max_values_per_key(1, ValueLimitPolicy::Reject);
+        let mut table = IndexedMultimapTable::open(&write_txn, U64_TABLE, index).unwrap();
+        table.insert(&0u64, &7u64).unwrap();
+        table.insert(&1u64, &7u64).unwrap();
+        assert_eq!(table.get_keys(&7u64).unwrap().len(), 2);
+    }
+    write_txn.commit().unwrap();
+}
+
 #[test]
 fn is_empty() {
     let tmpfile = create_tempfile();
@@ -1,8 +1,12 @@
 #[cfg(not(target_os = "wasi"))]
 mod multithreading_test {
-    use redb::{Database, ReadableDatabase, ReadableTable, ReadableTableMetadata, TableDefinition};
-    use std::sync::Arc;
+    use redb::{
+        Database, ReadableDatabase, ReadableTable, ReadableTableMetadata, TableDefinition,
+        WritePriority, WriteTransaction,
+    };
+    use std::sync::{Arc, Mutex, mpsc};
     use std::thread;
+    use std::time::Duration;
 
     fn create_tempfile() -> tempfile::NamedTempFile {
         if cfg!(target_os = "wasi") {
@@ -112,4 +116,78 @@ mod multithreading_test {
         let table = read_txn.open_table(DEF3).unwrap();
         assert_eq!(table.len().unwrap(), 1);
     }
+
+    #[test]
+    fn high_priority_writer() {
+        let tmpfile = create_tempfile();
+        let db = Database::create(tmpfile.path()).unwrap();
+        let order = Mutex::new(vec![]);
+
+        let write_txn = db.begin_write().unwrap();
+        thread::scope(|s| {
+            for i in 0..4 {
+                let db = &db;
+                let order = &order;
+                s.spawn(move || {
+                    let txn = db.begin_write().unwrap();
+                    order.lock().unwrap().push(format!("normal{i}"));
+                    txn.commit().unwrap();
+                });
+            }
+            // Give the normal priority writers time to start waiting
+            thread::sleep(Duration::from_millis(100));
+            s.spawn(|| {
+                let txn = db.begin_write_with_priority(WritePriority::High).unwrap();
+                order.lock().unwrap().push("high".to_string());
+                txn.commit().unwrap();
+            });
+            thread::sleep(Duration::from_millis(100));
+            write_txn.commit().unwrap();
+        });
+
+        let order = order.into_inner().unwrap();
+        assert_eq!(order.len(), 5);
+        assert_eq!(order[0], "high");
+    }
+
+    #[test]
+    fn write_transaction_handoff() {
+        let tmpfile = create_tempfile();
+        let db = Arc::new(Database::create(tmpfile.path()).unwrap());
+        let (to_worker, from_main) = mpsc::channel();
+        let (to_main, from_worker) = mpsc::channel();
+
+        let worker = thread::spawn(move || {
+            let write_txn: WriteTransaction = from_main.recv().unwrap();
+            {
+                let mut table = write_txn.open_table(TABLE).unwrap();
+                assert_eq!(table.get("main").unwrap().unwrap().value(), "1");
+                table.insert("worker", "2").unwrap();
+            }
+            to_main.send(write_txn).unwrap();
+        });
+
+        let write_txn = db.begin_write().unwrap();
+        {
+            let mut table = write_txn.open_table(TABLE).unwrap();
+            table.insert("main", "1").unwrap();
+        }
+        to_worker.send(write_txn).unwrap();
+        let write_txn = from_worker.recv().unwrap();
+        worker.join().unwrap();
+        {
+            let table = write_txn.open_table(TABLE).unwrap();
+            assert_eq!(table.len().unwrap(), 2);
+        }
+        write_txn.commit().unwrap();
+
+        let db2 = db.clone();
+        thread::spawn(move || {
+            let read_txn = db2.begin_read().unwrap();
+            let table = read_txn.open_table(TABLE).unwrap();
+            assert_eq!(table.get("worker").unwrap().unwrap().value(), "2");
+        })
+        .join()
+        .unwrap();
+    }
 }